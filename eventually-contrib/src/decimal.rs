@@ -0,0 +1,89 @@
+//! Contains [`PositiveDecimal`], a [Decimal] number strictly greater than zero.
+
+use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
+use std::str::FromStr;
+
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+/// All possible errors returned when trying to create a [`PositiveDecimal`].
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum InvalidPositiveDecimalError {
+    /// The number is zero or negative.
+    #[error("decimal must be greater than zero, got {0}")]
+    NotPositive(Decimal),
+    /// The string could not be parsed into a [Decimal] number.
+    #[error("failed to parse decimal: {0}")]
+    Parse(#[from] rust_decimal::Error),
+}
+
+/// A [Decimal] number strictly greater than zero, e.g. the amount
+/// of a money transfer.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(try_from = "Decimal", into = "Decimal")]
+pub struct PositiveDecimal(Decimal);
+
+impl PositiveDecimal {
+    /// Returns the underlying [Decimal] number.
+    #[must_use]
+    pub fn get(self) -> Decimal {
+        self.0
+    }
+}
+
+impl TryFrom<Decimal> for PositiveDecimal {
+    type Error = InvalidPositiveDecimalError;
+
+    fn try_from(value: Decimal) -> Result<Self, Self::Error> {
+        if value <= Decimal::ZERO {
+            return Err(InvalidPositiveDecimalError::NotPositive(value));
+        }
+
+        Ok(Self(value))
+    }
+}
+
+impl FromStr for PositiveDecimal {
+    type Err = InvalidPositiveDecimalError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::try_from(Decimal::from_str(s)?)
+    }
+}
+
+impl From<PositiveDecimal> for Decimal {
+    fn from(value: PositiveDecimal) -> Self {
+        value.0
+    }
+}
+
+impl Debug for PositiveDecimal {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        Debug::fmt(&self.0, f)
+    }
+}
+
+impl Display for PositiveDecimal {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        Display::fmt(&self.0, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn positive_decimal_rejects_zero_and_negative_numbers() {
+        assert_eq!(
+            Err(InvalidPositiveDecimalError::NotPositive(Decimal::ZERO)),
+            PositiveDecimal::try_from(Decimal::ZERO)
+        );
+        assert!("-1.5".parse::<PositiveDecimal>().is_err());
+        assert!("abc".parse::<PositiveDecimal>().is_err());
+
+        let amount: PositiveDecimal = serde_json::from_str(r#""10.50""#).unwrap();
+        assert_eq!("10.50", format!("{amount:?}"));
+        assert!(serde_json::from_str::<PositiveDecimal>(r#""0""#).is_err());
+    }
+}
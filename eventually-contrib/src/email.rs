@@ -0,0 +1,155 @@
+//! Contains [`EmailAddress`], a syntactically valid e-mail address.
+
+use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+/// Error returned when trying to create an [`EmailAddress`] from
+/// a string that is not a valid e-mail address.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("invalid email address '{value}': {reason}")]
+pub struct InvalidEmailAddressError {
+    /// The rejected value.
+    pub value: String,
+    /// Why the value has been rejected.
+    pub reason: &'static str,
+}
+
+/// A syntactically valid e-mail address, in the `local@domain` form.
+///
+/// The domain part is normalized to lowercase, while the local part
+/// is kept as-is, since it could be case-sensitive.
+///
+/// Only the general shape of the address is validated: use a confirmation
+/// e-mail to make sure the address actually exists.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct EmailAddress(String);
+
+impl EmailAddress {
+    /// Returns the e-mail address as a string slice.
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Returns the local part of the e-mail address, before the `@`.
+    #[must_use]
+    pub fn local_part(&self) -> &str {
+        self.split().0
+    }
+
+    /// Returns the domain of the e-mail address, after the `@`.
+    #[must_use]
+    pub fn domain(&self) -> &str {
+        self.split().1
+    }
+
+    fn split(&self) -> (&str, &str) {
+        self.0
+            .rsplit_once('@')
+            .expect("email address should have been validated")
+    }
+}
+
+impl TryFrom<String> for EmailAddress {
+    type Error = InvalidEmailAddressError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let reason = if value.chars().any(char::is_whitespace) {
+            Some("must not contain whitespaces")
+        } else {
+            match value.rsplit_once('@') {
+                None => Some("must contain '@'"),
+                Some(("", _)) => Some("local part must not be empty"),
+                Some((local, _)) if local.contains('@') => Some("must contain only one '@'"),
+                Some((_, domain))
+                    if !domain.contains('.')
+                        || domain.starts_with('.')
+                        || domain.ends_with('.')
+                        || domain.contains("..") =>
+                {
+                    Some("domain must be made of dot-separated labels")
+                },
+                Some(_) => None,
+            }
+        };
+
+        if let Some(reason) = reason {
+            return Err(InvalidEmailAddressError { value, reason });
+        }
+
+        let (local, domain) = value
+            .rsplit_once('@')
+            .expect("email address should contain '@'");
+
+        Ok(Self(format!("{local}@{}", domain.to_lowercase())))
+    }
+}
+
+impl FromStr for EmailAddress {
+    type Err = InvalidEmailAddressError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::try_from(s.to_owned())
+    }
+}
+
+impl From<EmailAddress> for String {
+    fn from(value: EmailAddress) -> Self {
+        value.0
+    }
+}
+
+impl AsRef<str> for EmailAddress {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Debug for EmailAddress {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        Debug::fmt(&self.0, f)
+    }
+}
+
+impl Display for EmailAddress {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        Display::fmt(&self.0, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn email_address_normalizes_the_domain() {
+        let email: EmailAddress = "John.Doe@Example.COM".parse().unwrap();
+
+        assert_eq!("John.Doe@example.com", email.as_str());
+        assert_eq!("John.Doe", email.local_part());
+        assert_eq!("example.com", email.domain());
+        assert_eq!(
+            r#""John.Doe@example.com""#,
+            serde_json::to_string(&email).unwrap()
+        );
+    }
+
+    #[test]
+    fn email_address_rejects_invalid_addresses() {
+        for value in [
+            "john",
+            "@example.com",
+            "john@doe@example.com",
+            "john@localhost",
+            "john@example..com",
+            "john doe@example.com",
+        ] {
+            assert!(value.parse::<EmailAddress>().is_err(), "{value}");
+        }
+
+        assert!(serde_json::from_str::<EmailAddress>(r#""john""#).is_err());
+    }
+}
@@ -0,0 +1,160 @@
+//! Contains [`Iban`], an International Bank Account Number
+//! with valid check digits.
+
+use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+/// All possible errors returned when trying to create an [`Iban`].
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum InvalidIbanError {
+    /// The IBAN is shorter or longer than the allowed lengths.
+    #[error("iban must be between 15 and 34 characters long, got {0}")]
+    Length(usize),
+    /// The IBAN does not start with a country code and the check digits,
+    /// or contains characters other than ASCII letters and digits.
+    #[error("iban must be a country code followed by check digits and alphanumeric characters")]
+    Format,
+    /// The check digits of the IBAN do not match its content.
+    #[error("iban check digits do not match")]
+    Checksum,
+}
+
+/// An International Bank Account Number, as specified by ISO 13616.
+///
+/// IBANs are normalized in their electronic format: spaces are removed
+/// and letters are uppercase, e.g. `GB82WEST12345698765432`.
+/// Use the [Display] implementation to print them in groups of four characters.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Iban(String);
+
+impl Iban {
+    /// Returns the IBAN, in its electronic format, as a string slice.
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Returns the ISO 3166-1 alpha-2 country code of the IBAN.
+    #[must_use]
+    pub fn country_code(&self) -> &str {
+        &self.0[..2]
+    }
+}
+
+fn checksum(iban: &str) -> u32 {
+    let (head, tail) = iban.split_at(4);
+
+    tail.chars()
+        .chain(head.chars())
+        .filter_map(|c| c.to_digit(36))
+        .fold(0, |remainder, digit| {
+            let shift = if digit < 10 { 10 } else { 100 };
+            (remainder * shift + digit) % 97
+        })
+}
+
+impl TryFrom<String> for Iban {
+    type Error = InvalidIbanError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let iban: String = value
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .map(|c| c.to_ascii_uppercase())
+            .collect();
+
+        if !(15..=34).contains(&iban.len()) {
+            return Err(InvalidIbanError::Length(iban.len()));
+        }
+
+        let bytes = iban.as_bytes();
+
+        if !bytes[..2].iter().all(u8::is_ascii_uppercase)
+            || !bytes[2..4].iter().all(u8::is_ascii_digit)
+            || !bytes.iter().all(u8::is_ascii_alphanumeric)
+        {
+            return Err(InvalidIbanError::Format);
+        }
+
+        if checksum(&iban) != 1 {
+            return Err(InvalidIbanError::Checksum);
+        }
+
+        Ok(Self(iban))
+    }
+}
+
+impl FromStr for Iban {
+    type Err = InvalidIbanError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::try_from(s.to_owned())
+    }
+}
+
+impl From<Iban> for String {
+    fn from(value: Iban) -> Self {
+        value.0
+    }
+}
+
+impl AsRef<str> for Iban {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Debug for Iban {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        Debug::fmt(&self.0, f)
+    }
+}
+
+impl Display for Iban {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        for (i, group) in self.0.as_bytes().chunks(4).enumerate() {
+            if i > 0 {
+                f.write_str(" ")?;
+            }
+
+            // NOTE: the IBAN is made of ASCII characters only, so every chunk is valid UTF-8.
+            f.write_str(std::str::from_utf8(group).map_err(|_| std::fmt::Error)?)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn iban_is_normalized_and_printed_in_groups() {
+        let iban: Iban = "gb82 west 1234 5698 7654 32".parse().unwrap();
+
+        assert_eq!("GB82WEST12345698765432", iban.as_str());
+        assert_eq!("GB", iban.country_code());
+        assert_eq!("GB82 WEST 1234 5698 7654 32", iban.to_string());
+        assert_eq!(
+            iban,
+            serde_json::from_str(r#""GB82WEST12345698765432""#).unwrap()
+        );
+    }
+
+    #[test]
+    fn iban_rejects_invalid_check_digits() {
+        assert_eq!(
+            Err(InvalidIbanError::Checksum),
+            "GB83WEST12345698765432".parse::<Iban>()
+        );
+        assert_eq!(Err(InvalidIbanError::Length(4)), "GB82".parse::<Iban>());
+        assert_eq!(
+            Err(InvalidIbanError::Format),
+            "12GBWEST12345698765432".parse::<Iban>()
+        );
+    }
+}
@@ -0,0 +1,19 @@
+//! `eventually-contrib` contains validated value objects that are frequently
+//! needed in Domain Events payloads, such as [`email::EmailAddress`] or [`iban::Iban`].
+//!
+//! All value objects can only be constructed through validation, either through
+//! [`TryFrom`] and [`FromStr`][std::str::FromStr] or when deserialized with [serde],
+//! so an invalid value never makes it into a Domain Event.
+//!
+//! Their [Debug] implementation prints the inner value only, so that Domain Events
+//! using them are as readable as if they were using plain strings and numbers.
+
+#![deny(unsafe_code, unused_qualifications, trivial_casts)]
+#![deny(clippy::all, clippy::pedantic, clippy::cargo)]
+#![allow(clippy::multiple_crate_versions)]
+#![warn(missing_docs)]
+
+pub mod decimal;
+pub mod email;
+pub mod iban;
+pub mod string;
@@ -0,0 +1,88 @@
+//! Contains [`NonEmptyString`], a string that contains at least one
+//! non-whitespace character.
+
+use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+/// Error returned when trying to create a [`NonEmptyString`] from an empty
+/// or whitespace-only string.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("string must contain at least one non-whitespace character")]
+pub struct EmptyStringError;
+
+/// A string that contains at least one non-whitespace character,
+/// e.g. a name or a title.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct NonEmptyString(String);
+
+impl NonEmptyString {
+    /// Returns the string as a slice.
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl TryFrom<String> for NonEmptyString {
+    type Error = EmptyStringError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        if value.trim().is_empty() {
+            return Err(EmptyStringError);
+        }
+
+        Ok(Self(value))
+    }
+}
+
+impl FromStr for NonEmptyString {
+    type Err = EmptyStringError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::try_from(s.to_owned())
+    }
+}
+
+impl From<NonEmptyString> for String {
+    fn from(value: NonEmptyString) -> Self {
+        value.0
+    }
+}
+
+impl AsRef<str> for NonEmptyString {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Debug for NonEmptyString {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        Debug::fmt(&self.0, f)
+    }
+}
+
+impl Display for NonEmptyString {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        Display::fmt(&self.0, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn non_empty_string_rejects_blank_strings() {
+        assert_eq!(Err(EmptyStringError), "".parse::<NonEmptyString>());
+        assert_eq!(Err(EmptyStringError), " \t\n".parse::<NonEmptyString>());
+
+        let value: NonEmptyString = serde_json::from_str(r#""John""#).unwrap();
+        assert_eq!("John", value.as_str());
+        assert_eq!(r#""John""#, format!("{value:?}"));
+
+        assert!(serde_json::from_str::<NonEmptyString>(r#""  ""#).is_err());
+    }
+}
@@ -0,0 +1,129 @@
+//! Binary format of the Domain Events stored by the [`Store`][crate::event::Store].
+//!
+//! Each Domain Event is stored as a single value, made of:
+//! - its Event Stream id,
+//! - its version, as a little-endian `u64`,
+//! - the number of its metadata entries, followed by each key and value,
+//! - its serialized payload,
+//!
+//! where strings and byte arrays are prefixed by their length as a little-endian `u32`.
+//! Checksums and crash-safety are provided by the underlying [`redb`] database.
+
+use eventually::message::Metadata;
+use eventually::version::Version;
+
+/// A single Domain Event, together with its metadata and serialized payload.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct StoredEvent {
+    pub(crate) stream_id: String,
+    pub(crate) version: Version,
+    pub(crate) metadata: Metadata,
+    pub(crate) payload: Vec<u8>,
+}
+
+/// Error returned when a stored value is not a valid [`StoredEvent`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("unexpected end of stored event")]
+pub(crate) struct DecodeError;
+
+impl StoredEvent {
+    /// Encodes the [`StoredEvent`] into a new value.
+    pub(crate) fn encode(&self) -> Vec<u8> {
+        let mut data = Vec::new();
+
+        put_bytes(&mut data, self.stream_id.as_bytes());
+        data.extend_from_slice(&self.version.to_le_bytes());
+        put_len(&mut data, self.metadata.len());
+
+        for (key, value) in &self.metadata {
+            put_bytes(&mut data, key.as_bytes());
+            put_bytes(&mut data, value.as_bytes());
+        }
+
+        put_bytes(&mut data, &self.payload);
+
+        data
+    }
+
+    /// Decodes a [`StoredEvent`] from a value returned by [`StoredEvent::encode`].
+    pub(crate) fn decode(mut data: &[u8]) -> Result<Self, DecodeError> {
+        let data = &mut data;
+        let stream_id = get_string(data)?;
+
+        let version = data.get(..8).ok_or(DecodeError)?;
+        let version = Version::from_le_bytes(version.try_into().expect("slice has 8 bytes"));
+        *data = &data[8..];
+
+        let mut metadata = Metadata::new();
+
+        for _ in 0..get_len(data)? {
+            metadata.insert(get_string(data)?, get_string(data)?);
+        }
+
+        let payload = get_bytes(data)?.to_vec();
+
+        if !data.is_empty() {
+            return Err(DecodeError);
+        }
+
+        Ok(Self {
+            stream_id,
+            version,
+            metadata,
+            payload,
+        })
+    }
+}
+
+fn put_len(data: &mut Vec<u8>, len: usize) {
+    let len = u32::try_from(len).expect("stored event fields should be shorter than 4 GiB");
+    data.extend_from_slice(&len.to_le_bytes());
+}
+
+fn put_bytes(data: &mut Vec<u8>, bytes: &[u8]) {
+    put_len(data, bytes.len());
+    data.extend_from_slice(bytes);
+}
+
+fn get_len(data: &mut &[u8]) -> Result<usize, DecodeError> {
+    let bytes = data.get(..4).ok_or(DecodeError)?;
+    let len = u32::from_le_bytes(bytes.try_into().expect("slice has 4 bytes"));
+    *data = &data[4..];
+
+    Ok(len as usize)
+}
+
+fn get_bytes<'a>(data: &mut &'a [u8]) -> Result<&'a [u8], DecodeError> {
+    let len = get_len(data)?;
+    let bytes = data.get(..len).ok_or(DecodeError)?;
+    *data = &data[len..];
+
+    Ok(bytes)
+}
+
+fn get_string(data: &mut &[u8]) -> Result<String, DecodeError> {
+    String::from_utf8(get_bytes(data)?.to_vec()).map_err(|_| DecodeError)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stored_event_is_decoded_from_its_encoding() {
+        let event = StoredEvent {
+            stream_id: "stream:a".to_owned(),
+            version: 42,
+            metadata: Metadata::from([("Key".to_owned(), "value".to_owned())]),
+            payload: b"payload".to_vec(),
+        };
+
+        let data = event.encode();
+
+        assert_eq!(Ok(event), StoredEvent::decode(&data));
+        assert_eq!(
+            Err(DecodeError),
+            StoredEvent::decode(&data[..data.len() - 1])
+        );
+    }
+}
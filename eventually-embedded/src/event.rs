@@ -0,0 +1,726 @@
+//! This module contains the implementation of the [`eventually::event::Store`] trait,
+//! persisting Event Streams to a local [`redb`] database file.
+//!
+//! Check out the [Store] type for more information.
+
+use std::marker::PhantomData;
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use eventually::aggregate::IdSerde;
+use eventually::clock::{Clock, SystemClock};
+use eventually::message::{Message, Metadata};
+use eventually::version::Version;
+use eventually::{event, serde, version};
+use futures::stream::{self, StreamExt, TryStreamExt};
+use redb::{Database, ReadableTable, TableDefinition};
+
+use crate::codec::StoredEvent;
+
+/// Default number of Domain Events read from the database per page
+/// by [`event::store::Streamer::stream`] and [`event::store::GlobalStreamer::stream_all`].
+pub const DEFAULT_STREAM_PAGE_SIZE: u32 = 1000;
+
+/// All the Domain Events, encoded as [`StoredEvent`]s, by their [Sequence][event::Sequence] number.
+const EVENTS: TableDefinition<event::Sequence, &[u8]> = TableDefinition::new("events");
+
+/// The [Sequence][event::Sequence] number of the Domain Events of each Event Stream,
+/// by Event Stream id and [Version].
+const EVENT_STREAMS: TableDefinition<(&str, Version), event::Sequence> =
+    TableDefinition::new("event_streams");
+
+/// All possible errors returned by [`Store::open`].
+#[derive(Debug, thiserror::Error)]
+pub enum OpenError {
+    /// Error returned when the database file could not be opened or initialized.
+    #[error("failed to open the event store database: {0}")]
+    Database(#[from] redb::Error),
+}
+
+/// All possible errors returned by [`Store`] while streaming Domain Events.
+#[derive(Debug, thiserror::Error)]
+pub enum StreamError {
+    /// Error returned when the database could not be read.
+    #[error("failed to read from the event store database: {0}")]
+    Database(#[from] redb::Error),
+    /// Error returned when a stored Domain Event has not been written by a [Store].
+    #[error("stored event with sequence number {0} is corrupted")]
+    Corrupted(event::Sequence),
+    /// Error returned when the Domain Event payload could not be deserialized.
+    #[error("failed to deserialize event from the event store database: {0}")]
+    DeserializeEvent(#[source] anyhow::Error),
+    /// Error returned when the Event Stream id could not be converted
+    /// into the Event Stream id type used by the Event Store.
+    #[error("failed to parse event stream id '{id}': {error}")]
+    ParseStreamId {
+        /// The Event Stream id, as stored in the database.
+        id: String,
+        /// The underlying conversion error.
+        #[source]
+        error: anyhow::Error,
+    },
+}
+
+/// The Domain Events to append to an Event Stream, serialized
+/// before moving to the blocking thread pool.
+struct SerializedAppend {
+    stream_id: String,
+    version_check: version::Check,
+    events: Vec<(Metadata, Vec<u8>)>,
+}
+
+/// Embedded implementation of the [`eventually::event::Store`] trait,
+/// persisting Event Streams to a local [`redb`] database file:
+/// this makes the [Store] a good fit for CLI and desktop applications,
+/// where running a database server is not an option.
+///
+/// Each append operation is committed in a single database transaction,
+/// durably written to disk before returning, so that it is either fully persisted
+/// or not persisted at all in case of crashes. Since transactions are committed
+/// one at a time, [Sequence][event::Sequence] numbers are assigned in commit order.
+///
+/// Domain Events are read from the database one page at a time, so only
+/// the page being streamed is kept in memory.
+///
+/// The database is accessed on the blocking thread pool of the Tokio runtime,
+/// which is required to use the [Store].
+#[derive(Debug, Clone)]
+pub struct Store<Id, Evt, Serde>
+where
+    Evt: Message,
+    Serde: serde::Serde<Evt>,
+{
+    database: Arc<Database>,
+    serde: Serde,
+    clock: Arc<dyn Clock>,
+    stream_page_size: u32,
+    types: PhantomData<fn() -> (Id, Evt)>,
+}
+
+impl<Id, Evt, Serde> Store<Id, Evt, Serde>
+where
+    Evt: Message,
+    Serde: serde::Serde<Evt>,
+{
+    /// Opens the Event Store persisted in the specified database file,
+    /// creating it if it does not exist.
+    ///
+    /// The database can only be opened by one [Store] at a time:
+    /// the file is locked until all the clones of the [Store] are dropped.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the database file could not be opened or initialized,
+    /// e.g. because it is not a [`redb`] database or it is already open.
+    pub fn open(path: impl AsRef<Path>, serde: Serde) -> Result<Self, OpenError> {
+        let database = Database::create(path).map_err(redb::Error::from)?;
+        create_tables(&database)?;
+
+        Ok(Self {
+            database: Arc::new(database),
+            serde,
+            clock: Arc::new(SystemClock),
+            stream_page_size: DEFAULT_STREAM_PAGE_SIZE,
+            types: PhantomData,
+        })
+    }
+
+    /// Uses the specified [Clock] to stamp the appended Domain Events
+    /// in the [`event::RECORDED_AT_KEY`] metadata entry, instead of the system time.
+    #[must_use]
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    /// Sets the number of Domain Events read from the database per page
+    /// when streaming. Defaults to [`DEFAULT_STREAM_PAGE_SIZE`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the page size is zero.
+    #[must_use]
+    pub fn with_stream_page_size(mut self, page_size: u32) -> Self {
+        assert!(page_size > 0, "stream page size must be greater than zero");
+        self.stream_page_size = page_size;
+        self
+    }
+
+    fn deserialize(
+        &self,
+        sequence: event::Sequence,
+        data: &[u8],
+    ) -> Result<(String, Version, event::Envelope<Evt>), StreamError> {
+        let stored = StoredEvent::decode(data).map_err(|_| StreamError::Corrupted(sequence))?;
+
+        let message = self
+            .serde
+            .deserialize(&stored.payload)
+            .map_err(StreamError::DeserializeEvent)?;
+
+        Ok((
+            stored.stream_id,
+            stored.version,
+            event::Envelope {
+                message,
+                metadata: stored.metadata,
+            },
+        ))
+    }
+}
+
+fn create_tables(database: &Database) -> Result<(), redb::Error> {
+    let transaction = database.begin_write()?;
+    transaction.open_table(EVENTS)?;
+    transaction.open_table(EVENT_STREAMS)?;
+    transaction.commit()?;
+
+    Ok(())
+}
+
+/// Runs the blocking database operation on the blocking thread pool,
+/// so that it does not block the threads of the async runtime.
+async fn blocking<T, F>(database: &Arc<Database>, f: F) -> T
+where
+    T: Send + 'static,
+    F: FnOnce(&Database) -> T + Send + 'static,
+{
+    let database = database.clone();
+
+    tokio::task::spawn_blocking(move || f(&database))
+        .await
+        .unwrap_or_else(|err| std::panic::resume_unwind(err.into_panic()))
+}
+
+/// Reads at most `limit` Domain Events of the Event Stream, starting from the specified version.
+fn read_event_stream_page(
+    database: &Database,
+    stream_id: &str,
+    from: Version,
+    limit: usize,
+) -> Result<Vec<(event::Sequence, Vec<u8>)>, redb::Error> {
+    let transaction = database.begin_read()?;
+    let events = transaction.open_table(EVENTS)?;
+    let event_streams = transaction.open_table(EVENT_STREAMS)?;
+
+    let mut page = Vec::new();
+
+    for entry in event_streams
+        .range((stream_id, from)..=(stream_id, Version::MAX))?
+        .take(limit)
+    {
+        let sequence = entry?.1.value();
+        let data = events.get(sequence)?.map(|data| data.value().to_vec());
+
+        // NOTE: a missing Domain Event is reported as corrupted when decoded.
+        page.push((sequence, data.unwrap_or_default()));
+    }
+
+    Ok(page)
+}
+
+/// Reads at most `limit` Domain Events, starting from the specified [Sequence][event::Sequence] number.
+fn read_events_page(
+    database: &Database,
+    from: event::Sequence,
+    limit: usize,
+) -> Result<Vec<(event::Sequence, Vec<u8>)>, redb::Error> {
+    let transaction = database.begin_read()?;
+    let events = transaction.open_table(EVENTS)?;
+
+    events
+        .range(from..)?
+        .take(limit)
+        .map(|entry| {
+            let (sequence, data) = entry?;
+            Ok((sequence.value(), data.value().to_vec()))
+        })
+        .collect()
+}
+
+/// Writes all the append operations in a single transaction, which is
+/// aborted if any of their version checks fails.
+fn write_appends(
+    database: &Database,
+    appends: Vec<SerializedAppend>,
+) -> Result<Result<Vec<Version>, version::ConflictError>, redb::Error> {
+    let transaction = database.begin_write()?;
+    let mut new_versions = Vec::with_capacity(appends.len());
+
+    {
+        let mut events = transaction.open_table(EVENTS)?;
+        let mut event_streams = transaction.open_table(EVENT_STREAMS)?;
+
+        let mut sequence = events.last()?.map_or(0, |(sequence, _)| sequence.value());
+
+        for append in appends {
+            let stream_id = append.stream_id.as_str();
+
+            let mut version = event_streams
+                .range((stream_id, 0)..=(stream_id, Version::MAX))?
+                .next_back()
+                .transpose()?
+                .map_or(0, |(key, _)| key.value().1);
+
+            if let version::Check::MustBe(expected) = append.version_check {
+                if version != expected {
+                    return Ok(Err(version::ConflictError {
+                        expected,
+                        actual: version,
+                    }));
+                }
+            }
+
+            for (metadata, payload) in append.events {
+                sequence += 1;
+                version += 1;
+
+                let stored = StoredEvent {
+                    stream_id: append.stream_id.clone(),
+                    version,
+                    metadata,
+                    payload,
+                };
+
+                events.insert(sequence, stored.encode().as_slice())?;
+                event_streams.insert((stream_id, version), sequence)?;
+            }
+
+            new_versions.push(version);
+        }
+    }
+
+    transaction.commit()?;
+
+    Ok(Ok(new_versions))
+}
+
+impl<Id, Evt, Serde> event::store::Streamer<Id, Evt> for Store<Id, Evt, Serde>
+where
+    Id: IdSerde + Clone + Send + Sync,
+    Evt: Message + Send + Sync,
+    Serde: serde::Serde<Evt>,
+{
+    type Error = StreamError;
+
+    fn stream(
+        &self,
+        id: &Id,
+        select: event::VersionSelect,
+    ) -> event::Stream<'_, Id, Evt, Self::Error> {
+        let id = id.clone();
+        let stream_id = id.encode_id();
+        let page_size = self.stream_page_size as usize;
+
+        let from = match select {
+            event::VersionSelect::All => 1,
+            event::VersionSelect::From(v) => v,
+        };
+
+        stream::try_unfold(Some(from), move |next_version| {
+            let id = id.clone();
+            let stream_id = stream_id.clone();
+
+            async move {
+                let Some(from) = next_version else {
+                    return Ok::<_, StreamError>(None);
+                };
+
+                let page = blocking(&self.database, move |database| {
+                    read_event_stream_page(database, &stream_id, from, page_size)
+                })
+                .await?;
+
+                let has_next_page = page.len() == page_size;
+
+                let events = page
+                    .into_iter()
+                    .map(|(sequence, data)| {
+                        let (_, version, event) = self.deserialize(sequence, &data)?;
+
+                        Ok(event::Persisted {
+                            stream_id: id.clone(),
+                            version,
+                            event,
+                        })
+                    })
+                    .collect::<Result<Vec<_>, StreamError>>()?;
+
+                let next_version = events
+                    .last()
+                    .filter(|_| has_next_page)
+                    .map(|event| event.version + 1);
+
+                Ok(Some((stream::iter(events).map(Ok), next_version)))
+            }
+        })
+        .try_flatten()
+        .boxed()
+    }
+}
+
+impl<Id, Evt, Serde> event::store::GlobalStreamer<Id, Evt> for Store<Id, Evt, Serde>
+where
+    Id: IdSerde + Clone + Send + Sync,
+    Evt: Message + Send + Sync,
+    Serde: serde::Serde<Evt>,
+{
+    type Error = StreamError;
+
+    fn stream_all(
+        &self,
+        select: event::SequenceSelect,
+    ) -> event::SequencedStream<'_, Id, Evt, Self::Error> {
+        let page_size = self.stream_page_size as usize;
+
+        let from = match select {
+            event::SequenceSelect::All => 1,
+            event::SequenceSelect::From(s) => s,
+        };
+
+        stream::try_unfold(Some(from), move |next_sequence| async move {
+            let Some(from) = next_sequence else {
+                return Ok::<_, StreamError>(None);
+            };
+
+            let page = blocking(&self.database, move |database| {
+                read_events_page(database, from, page_size)
+            })
+            .await?;
+
+            let has_next_page = page.len() == page_size;
+
+            let events = page
+                .into_iter()
+                .map(|(sequence, data)| {
+                    let (stream_id, version, event) = self.deserialize(sequence, &data)?;
+
+                    let stream_id =
+                        Id::decode_id(&stream_id).map_err(|error| StreamError::ParseStreamId {
+                            id: stream_id,
+                            error,
+                        })?;
+
+                    Ok(event::Sequenced {
+                        sequence,
+                        event: event::Persisted {
+                            stream_id,
+                            version,
+                            event,
+                        },
+                    })
+                })
+                .collect::<Result<Vec<_>, StreamError>>()?;
+
+            let next_sequence = events
+                .last()
+                .filter(|_| has_next_page)
+                .map(|event| event.sequence + 1);
+
+            Ok(Some((stream::iter(events).map(Ok), next_sequence)))
+        })
+        .try_flatten()
+        .boxed()
+    }
+}
+
+impl<Id, Evt, Serde> Store<Id, Evt, Serde>
+where
+    Id: IdSerde + Send + Sync,
+    Evt: Message + Send + Sync,
+    Serde: serde::Serde<Evt>,
+{
+    async fn append_all(
+        &self,
+        appends: Vec<event::store::StreamAppend<Id, Evt>>,
+    ) -> Result<Vec<Version>, event::store::AppendError> {
+        let recorded_at = self.clock.now().to_rfc3339();
+
+        let appends = appends
+            .into_iter()
+            .map(|append| {
+                let events = append
+                    .events
+                    .into_iter()
+                    .map(|event| {
+                        let event = event
+                            .with_metadata(event::RECORDED_AT_KEY.to_owned(), recorded_at.clone());
+
+                        Ok((event.metadata, self.serde.serialize(event.message)?))
+                    })
+                    .collect::<anyhow::Result<_>>()?;
+
+                Ok(SerializedAppend {
+                    stream_id: append.id.encode_id(),
+                    version_check: append.version_check,
+                    events,
+                })
+            })
+            .collect::<anyhow::Result<Vec<_>>>()
+            .map_err(|err| anyhow!("failed to serialize event message: {err}"))?;
+
+        let new_versions = blocking(&self.database, move |database| {
+            write_appends(database, appends)
+        })
+        .await
+        .map_err(|err| anyhow!("failed to write to the event store database: {err}"))??;
+
+        Ok(new_versions)
+    }
+}
+
+#[async_trait]
+impl<Id, Evt, Serde> event::store::Appender<Id, Evt> for Store<Id, Evt, Serde>
+where
+    Id: IdSerde + Send + Sync,
+    Evt: Message + Send + Sync,
+    Serde: serde::Serde<Evt>,
+{
+    async fn append(
+        &self,
+        id: Id,
+        version_check: version::Check,
+        events: Vec<event::Envelope<Evt>>,
+    ) -> Result<Version, event::store::AppendError> {
+        let mut new_versions = self
+            .append_all(vec![event::store::StreamAppend {
+                id,
+                version_check,
+                events,
+            }])
+            .await?;
+
+        Ok(new_versions
+            .pop()
+            .expect("one version per append operation"))
+    }
+
+    async fn append_multi(
+        &self,
+        appends: Vec<event::store::StreamAppend<Id, Evt>>,
+    ) -> Result<Vec<Version>, event::store::AppendError> {
+        self.append_all(appends).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use eventually::event::store::{AppendError, Appender, GlobalStreamer, Streamer};
+    use futures::TryStreamExt;
+
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq, Eq, ::serde::Serialize, ::serde::Deserialize)]
+    struct StringMessage(String);
+
+    impl Message for StringMessage {
+        fn name(&self) -> &'static str {
+            "StringMessage"
+        }
+    }
+
+    type TestStore = Store<String, StringMessage, serde::Json<StringMessage>>;
+
+    fn events(messages: &[&str]) -> Vec<event::Envelope<StringMessage>> {
+        messages
+            .iter()
+            .map(|message| StringMessage((*message).to_owned()).into())
+            .collect()
+    }
+
+    async fn stream_messages(store: &TestStore, id: &str) -> Vec<(Version, String)> {
+        store
+            .stream(&id.to_owned(), event::VersionSelect::All)
+            .map_ok(|event| (event.version, event.event.message.0))
+            .try_collect()
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn store_persists_event_streams_across_reopens() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("events.redb");
+
+        let store = TestStore::open(&path, serde::Json::default()).unwrap();
+
+        store
+            .append(
+                "stream:a".to_owned(),
+                version::Check::MustBe(0),
+                events(&["a1", "a2"]),
+            )
+            .await
+            .expect("append should not fail");
+
+        store
+            .append("stream:b".to_owned(), version::Check::Any, events(&["b1"]))
+            .await
+            .expect("append should not fail");
+
+        let error = store
+            .append(
+                "stream:a".to_owned(),
+                version::Check::MustBe(1),
+                events(&["a3"]),
+            )
+            .await
+            .expect_err("the version check should fail");
+
+        assert!(matches!(
+            error,
+            AppendError::Conflict(version::ConflictError {
+                expected: 1,
+                actual: 2
+            })
+        ));
+
+        drop(store);
+
+        let store = TestStore::open(&path, serde::Json::default()).unwrap();
+
+        assert_eq!(
+            vec![(1, "a1".to_owned()), (2, "a2".to_owned())],
+            stream_messages(&store, "stream:a").await
+        );
+
+        let sequences: Vec<_> = store
+            .stream_all(event::SequenceSelect::From(2))
+            .map_ok(|event| (event.sequence, event.event.stream_id))
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert_eq!(
+            vec![(2, "stream:a".to_owned()), (3, "stream:b".to_owned())],
+            sequences
+        );
+
+        assert_eq!(
+            3,
+            store
+                .append(
+                    "stream:a".to_owned(),
+                    version::Check::MustBe(2),
+                    events(&["a3"]),
+                )
+                .await
+                .expect("append should not fail after reopening")
+        );
+    }
+
+    #[tokio::test]
+    async fn store_does_not_persist_multi_stream_appends_failing_a_version_check() {
+        let dir = tempfile::tempdir().unwrap();
+        let store =
+            TestStore::open(dir.path().join("events.redb"), serde::Json::default()).unwrap();
+
+        let error = store
+            .append_multi(vec![
+                event::store::StreamAppend {
+                    id: "stream:a".to_owned(),
+                    version_check: version::Check::MustBe(0),
+                    events: events(&["a1"]),
+                },
+                event::store::StreamAppend {
+                    id: "stream:b".to_owned(),
+                    version_check: version::Check::MustBe(1),
+                    events: events(&["b1"]),
+                },
+            ])
+            .await
+            .expect_err("the version check should fail");
+
+        assert!(matches!(
+            error,
+            AppendError::Conflict(version::ConflictError {
+                expected: 1,
+                actual: 0
+            })
+        ));
+
+        assert!(stream_messages(&store, "stream:a").await.is_empty());
+
+        assert_eq!(
+            vec![1, 3],
+            store
+                .append_multi(vec![
+                    event::store::StreamAppend {
+                        id: "stream:a".to_owned(),
+                        version_check: version::Check::MustBe(0),
+                        events: events(&["a1"]),
+                    },
+                    event::store::StreamAppend {
+                        id: "stream:a".to_owned(),
+                        version_check: version::Check::MustBe(1),
+                        events: events(&["a2", "a3"]),
+                    },
+                ])
+                .await
+                .expect("append should not fail")
+        );
+    }
+
+    #[tokio::test]
+    async fn store_streams_events_one_page_at_a_time() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = TestStore::open(dir.path().join("events.redb"), serde::Json::default())
+            .unwrap()
+            .with_stream_page_size(2);
+
+        for (stream_id, message) in [
+            ("a", "a1"),
+            ("b", "b1"),
+            ("a", "a2"),
+            ("a", "a3"),
+            ("b", "b2"),
+        ] {
+            store
+                .append(
+                    stream_id.to_owned(),
+                    version::Check::Any,
+                    events(&[message]),
+                )
+                .await
+                .expect("append should not fail");
+        }
+
+        assert_eq!(
+            vec![
+                (1, "a1".to_owned()),
+                (2, "a2".to_owned()),
+                (3, "a3".to_owned())
+            ],
+            stream_messages(&store, "a").await
+        );
+
+        let messages: Vec<_> = store
+            .stream(&"a".to_owned(), event::VersionSelect::From(2))
+            .map_ok(|event| event.event.message.0)
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert_eq!(vec!["a2".to_owned(), "a3".to_owned()], messages);
+
+        let sequences: Vec<_> = store
+            .stream_all(event::SequenceSelect::All)
+            .map_ok(|event| (event.sequence, event.event.event.message.0))
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert_eq!(
+            vec![
+                (1, "a1".to_owned()),
+                (2, "b1".to_owned()),
+                (3, "a2".to_owned()),
+                (4, "a3".to_owned()),
+                (5, "b2".to_owned()),
+            ],
+            sequences
+        );
+    }
+}
@@ -0,0 +1,16 @@
+//! `eventually-embedded` contains an embedded implementation of the
+//! [`eventually::event::Store`] trait, persisting Event Streams to a local
+//! [`redb`] database file.
+//!
+//! It is meant for CLI and desktop applications, where running a database
+//! server is not an option. Check out the [`event::Store`] type to know more.
+
+#![deny(unsafe_code, unused_qualifications, trivial_casts)]
+#![deny(clippy::all, clippy::pedantic, clippy::cargo)]
+#![allow(clippy::multiple_crate_versions)]
+// redb::Error is the error type of all the database operations, there is no point in boxing it.
+#![allow(clippy::result_large_err)]
+#![warn(missing_docs)]
+
+mod codec;
+pub mod event;
@@ -0,0 +1,7 @@
+fn main() {
+    tonic_build::configure()
+        .build_server(true)
+        .build_client(true)
+        .compile(&["proto/eventually/v1/event_store.proto"], &["proto"])
+        .unwrap();
+}
@@ -0,0 +1,382 @@
+//! Module containing the [Client] of the `eventually.v1.EventStore` gRPC service,
+//! which implements the [`eventually::event::Store`] traits.
+
+use std::marker::PhantomData;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use eventually::aggregate::IdSerde;
+use eventually::event::store::{self, AppendError, GlobalStreamer};
+use eventually::message::Message;
+use eventually::subscription::{Polled, PolledStream};
+use eventually::{event, serde, version};
+use futures::stream::StreamExt;
+use tonic::transport::Channel;
+use tonic::{Code, Status};
+
+use crate::proto;
+use crate::proto::event_store_client::EventStoreClient;
+
+/// All possible errors returned by the [Client] while streaming Domain Events.
+#[derive(Debug, thiserror::Error)]
+pub enum ClientError {
+    /// Error returned by the gRPC server or transport.
+    #[error("event store server returned an error: {0}")]
+    Status(#[from] Status),
+    /// Error returned when a Domain Event received from the server
+    /// cannot be deserialized.
+    #[error("failed to deserialize event from the server: {0}")]
+    DeserializeEvent(#[source] anyhow::Error),
+    /// Error returned when the Event Stream id received from the server
+    /// cannot be decoded.
+    #[error("failed to decode event stream id '{id}': {error}")]
+    DecodeStreamId {
+        /// The Event Stream id received from the server.
+        id: String,
+        /// The error returned by [`IdSerde::decode_id`].
+        #[source]
+        error: anyhow::Error,
+    },
+    /// Error returned when the server has returned a malformed resume token.
+    #[error("invalid resume token from the server: {0}")]
+    ResumeToken(#[source] event::ResumeTokenError),
+    /// Error returned when the server has returned a message
+    /// with a required field unset.
+    #[error("missing required field in server response: {0}")]
+    MissingField(&'static str),
+}
+
+/// Client of the `eventually.v1.EventStore` gRPC service, exposing a remote
+/// Event Store through the [`event::Store`] and [`GlobalStreamer`] traits.
+#[derive(Debug)]
+pub struct Client<Id, Evt, Serde> {
+    inner: EventStoreClient<Channel>,
+    serde: Arc<Serde>,
+    id: PhantomData<fn() -> Id>,
+    evt: PhantomData<fn() -> Evt>,
+}
+
+impl<Id, Evt, Serde> Clone for Client<Id, Evt, Serde> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            serde: self.serde.clone(),
+            id: PhantomData,
+            evt: PhantomData,
+        }
+    }
+}
+
+impl<Id, Evt, Serde> Client<Id, Evt, Serde>
+where
+    Id: IdSerde + Send + Sync,
+    Evt: Message + Send + Sync,
+    Serde: serde::Serde<Evt>,
+{
+    /// Creates a new [Client] using the specified gRPC [Channel],
+    /// and the [`serde::Serde`] implementation used by the server.
+    ///
+    /// The [Channel] can be created with [`tonic::transport::Endpoint::connect`].
+    pub fn new(channel: Channel, serde: Serde) -> Self {
+        Self {
+            inner: EventStoreClient::new(channel),
+            serde: Arc::new(serde),
+            id: PhantomData,
+            evt: PhantomData,
+        }
+    }
+
+    /// Subscribes to all the Domain Events appended to the Event Store, including
+    /// the ones appended after the subscription has started, resuming right after
+    /// the specified [`event::ResumeToken`] or from the start if there is none.
+    ///
+    /// When a heartbeat interval is specified, the server delivers
+    /// a [heartbeat][Polled::Heartbeat] when no Domain Event has been delivered for that long,
+    /// whose token can be persisted to resume the subscription later.
+    ///
+    /// The returned stream never ends, unless an error occurs.
+    pub fn subscribe(
+        &self,
+        resume_token: Option<event::ResumeToken>,
+        names: event::NameSelect,
+        heartbeat_interval: Option<Duration>,
+    ) -> PolledStream<'static, Id, Evt, ClientError>
+    where
+        Id: 'static,
+        Evt: 'static,
+        Serde: 'static,
+    {
+        let mut client = self.inner.clone();
+        let serde = self.serde.clone();
+        let request = proto::SubscribeRequest {
+            resume_token: resume_token
+                .map(|token| token.to_string())
+                .unwrap_or_default(),
+            names: names_to_proto(names),
+            heartbeat_interval_ms: heartbeat_interval.map_or(0, |interval| {
+                u64::try_from(interval.as_millis()).unwrap_or(u64::MAX)
+            }),
+        };
+
+        async_stream::try_stream! {
+            let mut responses = client.subscribe(request).await?.into_inner();
+
+            while let Some(response) = responses.message().await? {
+                let resume_token = response
+                    .resume_token
+                    .parse()
+                    .map_err(ClientError::ResumeToken)?;
+
+                match response.message.ok_or(ClientError::MissingField("message"))? {
+                    proto::subscribe_response::Message::Event(event) => {
+                        let event = sequenced_from_proto(serde.as_ref(), event)?;
+                        yield Polled::Event { event, resume_token };
+                    },
+                    proto::subscribe_response::Message::Heartbeat(()) => {
+                        yield Polled::Heartbeat { resume_token };
+                    },
+                }
+            }
+        }
+        .boxed()
+    }
+}
+
+fn names_to_proto(names: event::NameSelect) -> Vec<String> {
+    match names {
+        event::NameSelect::All => Vec::new(),
+        event::NameSelect::Only(names) => names,
+    }
+}
+
+fn stream_all_request(
+    select: event::SequenceSelect,
+    names: event::NameSelect,
+) -> proto::StreamAllRequest {
+    proto::StreamAllRequest {
+        from_sequence: match select {
+            event::SequenceSelect::All => 0,
+            event::SequenceSelect::From(sequence) => sequence,
+        },
+        names: names_to_proto(names),
+    }
+}
+
+fn persisted_from_proto<Id, Evt>(
+    serde: &impl serde::Deserializer<Evt>,
+    event: proto::PersistedEvent,
+) -> Result<event::Persisted<Id, Evt>, ClientError>
+where
+    Id: IdSerde,
+    Evt: Message,
+{
+    let stream_id =
+        Id::decode_id(&event.stream_id).map_err(|error| ClientError::DecodeStreamId {
+            id: event.stream_id.clone(),
+            error,
+        })?;
+
+    let inner = event.event.ok_or(ClientError::MissingField("event"))?;
+
+    Ok(event::Persisted {
+        stream_id,
+        version: event.version,
+        event: event::Envelope {
+            message: serde
+                .deserialize(&inner.payload)
+                .map_err(ClientError::DeserializeEvent)?,
+            metadata: inner.metadata,
+        },
+    })
+}
+
+fn sequenced_from_proto<Id, Evt>(
+    serde: &impl serde::Deserializer<Evt>,
+    event: proto::SequencedEvent,
+) -> Result<event::Sequenced<Id, Evt>, ClientError>
+where
+    Id: IdSerde,
+    Evt: Message,
+{
+    let persisted = event.event.ok_or(ClientError::MissingField("event"))?;
+
+    Ok(event::Sequenced {
+        sequence: event.sequence,
+        event: persisted_from_proto(serde, persisted)?,
+    })
+}
+
+fn sequenced_stream<'a, Id, Evt, Serde, F>(
+    serde: Arc<Serde>,
+    response: F,
+) -> event::SequencedStream<'a, Id, Evt, ClientError>
+where
+    Id: IdSerde + Send + 'a,
+    Evt: Message + Send + 'a,
+    Serde: serde::Deserializer<Evt> + 'a,
+    F: std::future::Future<
+            Output = Result<tonic::Response<tonic::Streaming<proto::SequencedEvent>>, Status>,
+        > + Send
+        + 'a,
+{
+    async_stream::try_stream! {
+        let mut events = response.await?.into_inner();
+
+        while let Some(event) = events.message().await? {
+            yield sequenced_from_proto(serde.as_ref(), event)?;
+        }
+    }
+    .boxed()
+}
+
+fn status_to_append_error(status: Status) -> AppendError {
+    let version_from_metadata = |key: &str| {
+        status
+            .metadata()
+            .get(key)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<version::Version>().ok())
+    };
+
+    match status.code() {
+        Code::Aborted => {
+            match (
+                version_from_metadata(crate::EXPECTED_VERSION_KEY),
+                version_from_metadata(crate::ACTUAL_VERSION_KEY),
+            ) {
+                (Some(expected), Some(actual)) => {
+                    AppendError::Conflict(version::ConflictError { expected, actual })
+                },
+                _ => AppendError::Internal(ClientError::Status(status).into()),
+            }
+        },
+        Code::Unimplemented => AppendError::Unsupported,
+        _ => AppendError::Internal(ClientError::Status(status).into()),
+    }
+}
+
+impl<Id, Evt, Serde> store::Streamer<Id, Evt> for Client<Id, Evt, Serde>
+where
+    Id: IdSerde + Send + Sync,
+    Evt: Message + Send + Sync,
+    Serde: serde::Serde<Evt>,
+{
+    type Error = ClientError;
+
+    fn stream(
+        &self,
+        id: &Id,
+        select: event::VersionSelect,
+    ) -> event::Stream<'_, Id, Evt, Self::Error> {
+        self.stream_filtered(id, select, event::NameSelect::All)
+    }
+
+    fn stream_filtered<'a>(
+        &'a self,
+        id: &Id,
+        select: event::VersionSelect,
+        names: event::NameSelect,
+    ) -> event::Stream<'a, Id, Evt, Self::Error>
+    where
+        Id: 'a,
+        Evt: 'a,
+        Self::Error: 'a,
+    {
+        let mut client = self.inner.clone();
+        let request = proto::StreamRequest {
+            stream_id: id.encode_id(),
+            from_version: match select {
+                event::VersionSelect::All => 0,
+                event::VersionSelect::From(version) => version,
+            },
+            names: names_to_proto(names),
+        };
+
+        async_stream::try_stream! {
+            let mut events = client.stream(request).await?.into_inner();
+
+            while let Some(event) = events.message().await? {
+                yield persisted_from_proto(self.serde.as_ref(), event)?;
+            }
+        }
+        .boxed()
+    }
+}
+
+impl<Id, Evt, Serde> GlobalStreamer<Id, Evt> for Client<Id, Evt, Serde>
+where
+    Id: IdSerde + Send + Sync,
+    Evt: Message + Send + Sync,
+    Serde: serde::Serde<Evt>,
+{
+    type Error = ClientError;
+
+    fn stream_all(
+        &self,
+        select: event::SequenceSelect,
+    ) -> event::SequencedStream<'_, Id, Evt, Self::Error> {
+        self.stream_all_filtered(select, event::NameSelect::All)
+    }
+
+    fn stream_all_filtered<'a>(
+        &'a self,
+        select: event::SequenceSelect,
+        names: event::NameSelect,
+    ) -> event::SequencedStream<'a, Id, Evt, Self::Error>
+    where
+        Id: 'a,
+        Evt: 'a,
+        Self::Error: 'a,
+    {
+        let mut client = self.inner.clone();
+        let request = stream_all_request(select, names);
+
+        sequenced_stream(self.serde.clone(), async move {
+            client.stream_all(request).await
+        })
+    }
+}
+
+#[async_trait]
+impl<Id, Evt, Serde> store::Appender<Id, Evt> for Client<Id, Evt, Serde>
+where
+    Id: IdSerde + Send + Sync,
+    Evt: Message + Send + Sync,
+    Serde: serde::Serde<Evt>,
+{
+    async fn append(
+        &self,
+        id: Id,
+        version_check: version::Check,
+        events: Vec<event::Envelope<Evt>>,
+    ) -> Result<version::Version, AppendError> {
+        let events = events
+            .into_iter()
+            .map(|event| {
+                Ok(proto::Event {
+                    payload: self.serde.serialize(event.message)?,
+                    metadata: event.metadata,
+                })
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        let request = proto::AppendRequest {
+            stream_id: id.encode_id(),
+            version_check: Some(match version_check {
+                version::Check::Any => proto::append_request::VersionCheck::Any(()),
+                version::Check::MustBe(v) => proto::append_request::VersionCheck::MustBe(v),
+            }),
+            events,
+        };
+
+        let response = self
+            .inner
+            .clone()
+            .append(request)
+            .await
+            .map_err(status_to_append_error)?;
+
+        Ok(response.into_inner().version)
+    }
+}
@@ -0,0 +1,33 @@
+//! `eventually-grpc` exposes an [`eventually::event::Store`] over gRPC,
+//! to build a standalone Event Store service.
+//!
+//! The [`server::Server`] wraps any Event Store implementation into a [tonic] service,
+//! while the [`client::Client`] implements the Event Store traits on top of it,
+//! so that applications can use the remote Event Store as if it was a local one.
+//!
+//! Domain Events are sent over the wire already serialized: both the client
+//! and the server must use the same [`eventually::serde::Serde`] implementation.
+
+#![deny(unsafe_code, unused_qualifications, trivial_casts)]
+#![deny(clippy::all, clippy::pedantic, clippy::cargo)]
+#![allow(clippy::multiple_crate_versions)]
+// tonic::Status is the error type of all the gRPC calls, there is no point in boxing it.
+#![allow(clippy::result_large_err)]
+#![warn(missing_docs)]
+
+pub mod client;
+pub mod server;
+
+/// Protobuf messages and gRPC service definitions, generated from
+/// the `eventually.v1` package.
+#[allow(missing_docs, unused_qualifications)]
+#[allow(clippy::all, clippy::pedantic)] // Cannot really check the sanity of generated code :shrugs:
+pub mod proto {
+    tonic::include_proto!("eventually.v1");
+}
+
+/// gRPC metadata key used to return the expected version of a failed version check.
+pub(crate) const EXPECTED_VERSION_KEY: &str = "x-eventually-expected-version";
+
+/// gRPC metadata key used to return the actual version of a failed version check.
+pub(crate) const ACTUAL_VERSION_KEY: &str = "x-eventually-actual-version";
@@ -0,0 +1,318 @@
+//! Module containing the [Server] that exposes an [`eventually::event::Store`]
+//! through the `eventually.v1.EventStore` gRPC service.
+
+use std::fmt::Display;
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+
+use eventually::aggregate::IdSerde;
+use eventually::event::store::GlobalStreamer;
+use eventually::message::Message;
+use eventually::subscription::{self, Polled, Polling};
+use eventually::{event, serde, version};
+use futures::future::ready;
+use futures::{Stream, StreamExt, TryStreamExt};
+use tonic::metadata::MetadataValue;
+use tonic::{Request, Response, Status};
+
+use crate::proto;
+use crate::proto::event_store_server::EventStoreServer;
+
+/// Default interval between two polls of the Event Store
+/// for new Domain Events, used by the `Subscribe` RPC.
+pub const DEFAULT_POLL_INTERVAL: Duration = subscription::DEFAULT_POLL_INTERVAL;
+
+type ResponseStream<T> = Pin<Box<dyn Stream<Item = Result<T, Status>> + Send>>;
+
+/// Implementation of the `eventually.v1.EventStore` gRPC service,
+/// backed by the specified [`event::Store`].
+///
+/// The `Subscribe` RPC is implemented by a [`Polling`] subscription,
+/// so that it works with any [`GlobalStreamer`] implementation, including
+/// the ones committing Domain Events out of [Sequence][event::Sequence] order:
+/// clients can resume it from the token of the last response they have processed,
+/// and ask for heartbeats to persist their position while no Domain Event is being appended.
+#[derive(Debug)]
+pub struct Server<S, Id, Evt, Serde> {
+    store: Arc<S>,
+    serde: Arc<Serde>,
+    poll_interval: Duration,
+    id: PhantomData<fn() -> Id>,
+    evt: PhantomData<fn() -> Evt>,
+}
+
+impl<S, Id, Evt, Serde> Clone for Server<S, Id, Evt, Serde> {
+    fn clone(&self) -> Self {
+        Self {
+            store: self.store.clone(),
+            serde: self.serde.clone(),
+            poll_interval: self.poll_interval,
+            id: PhantomData,
+            evt: PhantomData,
+        }
+    }
+}
+
+impl<S, Id, Evt, Serde> Server<S, Id, Evt, Serde>
+where
+    S: event::Store<Id, Evt> + GlobalStreamer<Id, Evt> + 'static,
+    <S as event::store::Streamer<Id, Evt>>::Error: Display,
+    <S as GlobalStreamer<Id, Evt>>::Error: Display,
+    Id: IdSerde + Clone + Send + Sync + 'static,
+    Evt: Message + Send + Sync + 'static,
+    Serde: serde::Serde<Evt> + 'static,
+{
+    /// Creates a new [Server] for the specified Event Store, using the
+    /// [`serde::Serde`] implementation to exchange Domain Events with the clients.
+    pub fn new(store: S, serde: Serde) -> Self {
+        Self {
+            store: Arc::new(store),
+            serde: Arc::new(serde),
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            id: PhantomData,
+            evt: PhantomData,
+        }
+    }
+
+    /// Sets the interval between two polls of the Event Store
+    /// for new Domain Events, used by the `Subscribe` RPC.
+    ///
+    /// Defaults to [`DEFAULT_POLL_INTERVAL`].
+    #[must_use]
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// Returns the [tonic] service to add to a [`tonic::transport::Server`].
+    #[must_use]
+    pub fn into_service(self) -> EventStoreServer<Self> {
+        EventStoreServer::new(self)
+    }
+}
+
+fn names_select(names: Vec<String>) -> event::NameSelect {
+    if names.is_empty() {
+        event::NameSelect::All
+    } else {
+        event::NameSelect::Only(names)
+    }
+}
+
+fn persisted_to_proto<Id, Evt>(
+    serde: &impl serde::Serializer<Evt>,
+    event: event::Persisted<Id, Evt>,
+) -> Result<proto::PersistedEvent, Status>
+where
+    Id: IdSerde,
+    Evt: Message,
+{
+    let payload = serde
+        .serialize(event.event.message)
+        .map_err(|err| Status::internal(format!("failed to serialize event: {err}")))?;
+
+    Ok(proto::PersistedEvent {
+        stream_id: event.stream_id.encode_id(),
+        version: event.version,
+        event: Some(proto::Event {
+            payload,
+            metadata: event.event.metadata,
+        }),
+    })
+}
+
+fn sequenced_to_proto<Id, Evt>(
+    serde: &impl serde::Serializer<Evt>,
+    event: event::Sequenced<Id, Evt>,
+) -> Result<proto::SequencedEvent, Status>
+where
+    Id: IdSerde,
+    Evt: Message,
+{
+    Ok(proto::SequencedEvent {
+        sequence: event.sequence,
+        event: Some(persisted_to_proto(serde, event.event)?),
+    })
+}
+
+fn polled_to_proto<Id, Evt>(
+    serde: &impl serde::Serializer<Evt>,
+    polled: Polled<Id, Evt>,
+) -> Result<proto::SubscribeResponse, Status>
+where
+    Id: IdSerde,
+    Evt: Message,
+{
+    let resume_token = polled.resume_token().to_string();
+
+    let message = match polled {
+        Polled::Event { event, .. } => {
+            proto::subscribe_response::Message::Event(sequenced_to_proto(serde, event)?)
+        },
+        Polled::Heartbeat { .. } => proto::subscribe_response::Message::Heartbeat(()),
+    };
+
+    Ok(proto::SubscribeResponse {
+        resume_token,
+        message: Some(message),
+    })
+}
+
+fn append_error_to_status(err: event::store::AppendError) -> Status {
+    match err {
+        event::store::AppendError::Conflict(err) => {
+            let mut status = Status::aborted(err.to_string());
+            let metadata = status.metadata_mut();
+            metadata.insert(
+                crate::EXPECTED_VERSION_KEY,
+                MetadataValue::from(err.expected),
+            );
+            metadata.insert(crate::ACTUAL_VERSION_KEY, MetadataValue::from(err.actual));
+            status
+        },
+        err @ event::store::AppendError::Unsupported => Status::unimplemented(err.to_string()),
+        event::store::AppendError::Internal(err) => Status::internal(err.to_string()),
+    }
+}
+
+#[tonic::async_trait]
+impl<S, Id, Evt, Serde> proto::event_store_server::EventStore for Server<S, Id, Evt, Serde>
+where
+    S: event::Store<Id, Evt> + GlobalStreamer<Id, Evt> + 'static,
+    <S as event::store::Streamer<Id, Evt>>::Error: Display,
+    <S as GlobalStreamer<Id, Evt>>::Error: Display,
+    Id: IdSerde + Clone + Send + Sync + 'static,
+    Evt: Message + Send + Sync + 'static,
+    Serde: serde::Serde<Evt> + 'static,
+{
+    type StreamStream = ResponseStream<proto::PersistedEvent>;
+    type StreamAllStream = ResponseStream<proto::SequencedEvent>;
+    type SubscribeStream = ResponseStream<proto::SubscribeResponse>;
+
+    async fn append(
+        &self,
+        request: Request<proto::AppendRequest>,
+    ) -> Result<Response<proto::AppendResponse>, Status> {
+        let request = request.into_inner();
+
+        let id = Id::decode_id(&request.stream_id)
+            .map_err(|err| Status::invalid_argument(format!("invalid stream id: {err}")))?;
+
+        let version_check = match request.version_check {
+            None | Some(proto::append_request::VersionCheck::Any(())) => version::Check::Any,
+            Some(proto::append_request::VersionCheck::MustBe(v)) => version::Check::MustBe(v),
+        };
+
+        let events = request
+            .events
+            .into_iter()
+            .map(|event| {
+                Ok(event::Envelope {
+                    message: self.serde.deserialize(&event.payload).map_err(|err| {
+                        Status::invalid_argument(format!("failed to deserialize event: {err}"))
+                    })?,
+                    metadata: event.metadata,
+                })
+            })
+            .collect::<Result<Vec<_>, Status>>()?;
+
+        let version = self
+            .store
+            .append(id, version_check, events)
+            .await
+            .map_err(append_error_to_status)?;
+
+        Ok(Response::new(proto::AppendResponse { version }))
+    }
+
+    async fn stream(
+        &self,
+        request: Request<proto::StreamRequest>,
+    ) -> Result<Response<Self::StreamStream>, Status> {
+        let request = request.into_inner();
+
+        let id = Id::decode_id(&request.stream_id)
+            .map_err(|err| Status::invalid_argument(format!("invalid stream id: {err}")))?;
+
+        let select = match request.from_version {
+            0 => event::VersionSelect::All,
+            v => event::VersionSelect::From(v),
+        };
+
+        let names = names_select(request.names);
+        let store = self.store.clone();
+        let serde = self.serde.clone();
+
+        let stream = async_stream::try_stream! {
+            let mut events = store.stream_filtered(&id, select, names);
+
+            while let Some(event) = events.next().await {
+                let event = event.map_err(|err| Status::internal(err.to_string()))?;
+                yield persisted_to_proto(serde.as_ref(), event)?;
+            }
+        };
+
+        Ok(Response::new(Box::pin(stream)))
+    }
+
+    async fn stream_all(
+        &self,
+        request: Request<proto::StreamAllRequest>,
+    ) -> Result<Response<Self::StreamAllStream>, Status> {
+        let request = request.into_inner();
+
+        let select = match request.from_sequence {
+            0 => event::SequenceSelect::All,
+            s => event::SequenceSelect::From(s),
+        };
+
+        let names = names_select(request.names);
+        let store = self.store.clone();
+        let serde = self.serde.clone();
+
+        let stream = async_stream::try_stream! {
+            let mut events = store.stream_all_filtered(select, names);
+
+            while let Some(event) = events.next().await {
+                let event = event.map_err(|err| Status::internal(err.to_string()))?;
+                yield sequenced_to_proto(serde.as_ref(), event)?;
+            }
+        };
+
+        Ok(Response::new(Box::pin(stream)))
+    }
+
+    async fn subscribe(
+        &self,
+        request: Request<proto::SubscribeRequest>,
+    ) -> Result<Response<Self::SubscribeStream>, Status> {
+        let request = request.into_inner();
+
+        let select = if request.resume_token.is_empty() {
+            event::SequenceSelect::All
+        } else {
+            request
+                .resume_token
+                .parse::<event::ResumeToken>()
+                .and_then(event::SequenceSelect::try_from)
+                .map_err(|err| Status::invalid_argument(format!("invalid resume token: {err}")))?
+        };
+
+        let mut polling = Polling::new(self.store.clone()).with_poll_interval(self.poll_interval);
+
+        if request.heartbeat_interval_ms > 0 {
+            polling = polling
+                .with_heartbeat_interval(Duration::from_millis(request.heartbeat_interval_ms));
+        }
+
+        let serde = self.serde.clone();
+        let stream = polling
+            .into_stream(select, names_select(request.names))
+            .map_err(|err| Status::internal(err.to_string()))
+            .and_then(move |polled| ready(polled_to_proto(serde.as_ref(), polled)));
+
+        Ok(Response::new(Box::pin(stream)))
+    }
+}
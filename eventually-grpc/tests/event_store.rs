@@ -0,0 +1,284 @@
+use std::time::Duration;
+
+use eventually::event::store::{AppendError, Appender, GlobalStreamer, InMemory, Streamer};
+use eventually::event::{NameSelect, SequenceSelect, VersionSelect};
+use eventually::message::Message;
+use eventually::subscription::Polled;
+use eventually::{event, serde, version};
+use eventually_grpc::client::{Client, ClientError};
+use eventually_grpc::server::Server;
+use futures::{StreamExt, TryStreamExt};
+use tokio::net::TcpListener;
+use tokio_stream::wrappers::TcpListenerStream;
+use tonic::transport::Endpoint;
+
+#[derive(Debug, Clone, PartialEq, Eq, ::serde::Serialize, ::serde::Deserialize)]
+enum TestEvent {
+    WasCreated(String),
+    WasRenamed(String),
+}
+
+impl Message for TestEvent {
+    fn name(&self) -> &'static str {
+        match self {
+            TestEvent::WasCreated(_) => "TestWasCreated",
+            TestEvent::WasRenamed(_) => "TestWasRenamed",
+        }
+    }
+}
+
+type TestClient = Client<String, TestEvent, serde::Json<TestEvent>>;
+
+async fn start_server() -> TestClient {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+
+    let server = Server::new(
+        InMemory::<String, TestEvent>::default(),
+        serde::Json::<TestEvent>::default(),
+    )
+    .with_poll_interval(Duration::from_millis(10));
+
+    tokio::spawn(
+        tonic::transport::Server::builder()
+            .add_service(server.into_service())
+            .serve_with_incoming(TcpListenerStream::new(listener)),
+    );
+
+    let channel = Endpoint::from_shared(format!("http://{address}"))
+        .unwrap()
+        .connect()
+        .await
+        .unwrap();
+
+    Client::new(channel, serde::Json::<TestEvent>::default())
+}
+
+fn events(events: &[TestEvent]) -> Vec<event::Envelope<TestEvent>> {
+    events.iter().cloned().map(event::Envelope::from).collect()
+}
+
+#[tokio::test]
+async fn append_and_stream_through_the_server_works() {
+    let client = start_server().await;
+    let id = "stream:a".to_owned();
+
+    let new_version = client
+        .append(
+            id.clone(),
+            version::Check::MustBe(0),
+            events(&[
+                TestEvent::WasCreated("a".to_owned()),
+                TestEvent::WasRenamed("b".to_owned()),
+            ]),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(2, new_version);
+
+    let streamed: Vec<_> = client
+        .stream(&id, VersionSelect::From(2))
+        .map_ok(|event| (event.stream_id, event.version, event.event.message))
+        .try_collect()
+        .await
+        .unwrap();
+
+    assert_eq!(
+        vec![(id.clone(), 2, TestEvent::WasRenamed("b".to_owned()))],
+        streamed
+    );
+
+    let created: Vec<_> = client
+        .stream_filtered(
+            &id,
+            VersionSelect::All,
+            NameSelect::Only(vec!["TestWasCreated".to_owned()]),
+        )
+        .map_ok(|event| event.event.message)
+        .try_collect()
+        .await
+        .unwrap();
+
+    assert_eq!(vec![TestEvent::WasCreated("a".to_owned())], created);
+}
+
+#[tokio::test]
+async fn append_conflicts_are_returned_to_the_client() {
+    let client = start_server().await;
+    let id = "stream:a".to_owned();
+
+    client
+        .append(
+            id.clone(),
+            version::Check::Any,
+            events(&[TestEvent::WasCreated("a".to_owned())]),
+        )
+        .await
+        .unwrap();
+
+    let error = client
+        .append(
+            id,
+            version::Check::MustBe(0),
+            events(&[TestEvent::WasCreated("a".to_owned())]),
+        )
+        .await
+        .unwrap_err();
+
+    assert!(matches!(
+        error,
+        AppendError::Conflict(version::ConflictError {
+            expected: 0,
+            actual: 1
+        })
+    ));
+}
+
+#[tokio::test]
+async fn stream_all_and_subscribe_return_events_across_streams() {
+    let client = start_server().await;
+
+    let mut subscription = client.subscribe(None, NameSelect::All, None);
+
+    for id in ["stream:a", "stream:b"] {
+        client
+            .append(
+                id.to_owned(),
+                version::Check::Any,
+                events(&[TestEvent::WasCreated(id.to_owned())]),
+            )
+            .await
+            .unwrap();
+    }
+
+    let all: Vec<_> = client
+        .stream_all(SequenceSelect::From(2))
+        .map_ok(|event| (event.sequence, event.event.stream_id))
+        .try_collect()
+        .await
+        .unwrap();
+
+    assert_eq!(vec![(2, "stream:b".to_owned())], all);
+
+    let mut subscribed = Vec::new();
+    while subscribed.len() < 2 {
+        let event = tokio::time::timeout(Duration::from_secs(5), subscription.next())
+            .await
+            .expect("subscription should receive new events")
+            .unwrap()
+            .unwrap();
+
+        let Polled::Event { event, .. } = event else {
+            panic!("subscription should not send heartbeats unless asked to");
+        };
+
+        subscribed.push((event.sequence, event.event.stream_id));
+    }
+
+    assert_eq!(
+        vec![(1, "stream:a".to_owned()), (2, "stream:b".to_owned())],
+        subscribed
+    );
+}
+
+#[tokio::test]
+async fn subscribe_sends_heartbeats_and_resumes_from_their_token() {
+    let client = start_server().await;
+
+    client
+        .append(
+            "stream:a".to_owned(),
+            version::Check::Any,
+            events(&[
+                TestEvent::WasCreated("a".to_owned()),
+                TestEvent::WasRenamed("b".to_owned()),
+            ]),
+        )
+        .await
+        .unwrap();
+
+    let renamed = NameSelect::Only(vec!["TestWasRenamed".to_owned()]);
+
+    let delivered: Vec<_> = tokio::time::timeout(
+        Duration::from_secs(5),
+        client
+            .subscribe(None, renamed.clone(), Some(Duration::from_millis(20)))
+            .take(2)
+            .try_collect(),
+    )
+    .await
+    .expect("subscription should send a heartbeat")
+    .unwrap();
+
+    let [Polled::Event { event, .. }, Polled::Heartbeat { resume_token }] = delivered.as_slice()
+    else {
+        panic!("subscription should send the domain event, then a heartbeat");
+    };
+
+    assert_eq!(2, event.sequence);
+    assert_eq!(event.resume_token(), *resume_token);
+
+    client
+        .append(
+            "stream:b".to_owned(),
+            version::Check::Any,
+            events(&[
+                TestEvent::WasCreated("c".to_owned()),
+                TestEvent::WasRenamed("d".to_owned()),
+            ]),
+        )
+        .await
+        .unwrap();
+
+    let resumed = tokio::time::timeout(
+        Duration::from_secs(5),
+        client.subscribe(Some(*resume_token), renamed, None).next(),
+    )
+    .await
+    .expect("subscription should receive new events")
+    .unwrap()
+    .unwrap();
+
+    let Polled::Event { event, .. } = resumed else {
+        panic!("subscription should not send heartbeats unless asked to");
+    };
+
+    assert_eq!(
+        (4, TestEvent::WasRenamed("d".to_owned())),
+        (event.sequence, event.event.event.message)
+    );
+}
+
+#[tokio::test]
+async fn subscribe_rejects_resume_tokens_of_a_single_event_stream() {
+    let client = start_server().await;
+    let id = "stream:a".to_owned();
+
+    client
+        .append(
+            id.clone(),
+            version::Check::Any,
+            events(&[TestEvent::WasCreated("a".to_owned())]),
+        )
+        .await
+        .unwrap();
+
+    let event = client
+        .stream(&id, VersionSelect::All)
+        .try_next()
+        .await
+        .unwrap()
+        .unwrap();
+
+    let error = client
+        .subscribe(Some(event.resume_token()), NameSelect::All, None)
+        .try_next()
+        .await
+        .unwrap_err();
+
+    assert!(matches!(
+        error,
+        ClientError::Status(status) if status.code() == tonic::Code::InvalidArgument
+    ));
+}
@@ -0,0 +1,498 @@
+//! Module containing the [Api] that exposes an [`event::Store`] over HTTP,
+//! using JSON as the wire format.
+
+use std::fmt::Display;
+use std::marker::PhantomData;
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::extract::{Path, Query, State};
+use axum::http::{header, HeaderMap, HeaderValue, StatusCode};
+use axum::response::sse::{self, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use eventually::aggregate::IdSerde;
+use eventually::event::store::{AppendError, GlobalStreamer};
+use eventually::message::{Message, Metadata};
+use eventually::subscription::{self, Polled, Polling};
+use eventually::{event, version};
+use futures::{Stream, StreamExt, TryStreamExt};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+/// Default maximum number of Domain Events returned in a single page.
+pub const DEFAULT_PAGE_SIZE: usize = 100;
+
+/// Default interval between two polls of the Event Store
+/// for new Domain Events, used by the subscription endpoint.
+pub const DEFAULT_POLL_INTERVAL: Duration = subscription::DEFAULT_POLL_INTERVAL;
+
+/// HTTP/JSON API exposing an [`event::Store`], to be served with [axum].
+///
+/// The API exposes the following endpoints:
+/// - `GET /streams/:id?from_version=&resume_token=&limit=`, to read a page of Domain Events
+///   from an Event Stream,
+/// - `POST /streams/:id`, to append a JSON array of `{ "payload", "metadata" }`
+///   Domain Events to an Event Stream, using the `If-Match` header as
+///   the expected version of the Event Stream,
+/// - `GET /events?from_sequence=&resume_token=&limit=`, to read a page of Domain Events
+///   across all Event Streams,
+/// - `GET /events/subscribe?from_sequence=&resume_token=`, to subscribe to all the Domain Events
+///   appended to the Event Store using Server-Sent Events.
+///
+/// Pages include the `next_resume_token` to pass as `resume_token` to read the next page,
+/// if there is one: see [`event::ResumeToken`].
+///
+/// The `id` of each Server-Sent Event is the [`event::ResumeToken`] to resume the subscription
+/// right after it, so that clients can resume their subscription through
+/// the `Last-Event-ID` header. Plain [Sequence][event::Sequence] numbers are also accepted,
+/// to resume right after the Domain Event with that Sequence number.
+#[derive(Debug)]
+pub struct Api<S, Id, Evt> {
+    store: Arc<S>,
+    page_size: usize,
+    poll_interval: Duration,
+    id: PhantomData<fn() -> Id>,
+    evt: PhantomData<fn() -> Evt>,
+}
+
+impl<S, Id, Evt> Api<S, Id, Evt>
+where
+    S: event::Store<Id, Evt> + GlobalStreamer<Id, Evt> + 'static,
+    <S as event::store::Streamer<Id, Evt>>::Error: Display,
+    <S as GlobalStreamer<Id, Evt>>::Error: Display,
+    Id: IdSerde + Send + Sync + 'static,
+    Evt: Message + Serialize + DeserializeOwned + Send + Sync + 'static,
+{
+    /// Creates a new [Api] for the specified Event Store.
+    pub fn new(store: S) -> Self {
+        Self {
+            store: Arc::new(store),
+            page_size: DEFAULT_PAGE_SIZE,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            id: PhantomData,
+            evt: PhantomData,
+        }
+    }
+
+    /// Sets the maximum number of Domain Events returned in a single page,
+    /// which is also the default page size when no `limit` is requested.
+    ///
+    /// Defaults to [`DEFAULT_PAGE_SIZE`].
+    ///
+    /// # Panics
+    ///
+    /// The method panics if the page size is zero.
+    #[must_use]
+    pub fn with_page_size(mut self, page_size: usize) -> Self {
+        assert!(page_size > 0, "page size should be greater than zero");
+        self.page_size = page_size;
+        self
+    }
+
+    /// Sets the interval between two polls of the Event Store
+    /// for new Domain Events, used by the subscription endpoint.
+    ///
+    /// Defaults to [`DEFAULT_POLL_INTERVAL`].
+    #[must_use]
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// Returns the [Router] serving the API endpoints, which can be
+    /// nested in a bigger application through [`Router::nest`].
+    pub fn into_router(self) -> Router {
+        Router::new()
+            .route(
+                "/streams/:id",
+                get(read_stream::<S, Id, Evt>).post(append_to_stream::<S, Id, Evt>),
+            )
+            .route("/events", get(read_all::<S, Id, Evt>))
+            .route("/events/subscribe", get(subscribe::<S, Id, Evt>))
+            .with_state(Arc::new(self))
+    }
+
+    fn limit(&self, requested: Option<usize>) -> usize {
+        requested.map_or(self.page_size, |limit| limit.clamp(1, self.page_size))
+    }
+}
+
+/// A Domain Event persisted in an Event Stream, as returned by the [Api].
+#[derive(Debug, Serialize)]
+struct PersistedEvent<Evt> {
+    stream_id: String,
+    version: version::Version,
+    name: &'static str,
+    payload: Evt,
+    metadata: Metadata,
+}
+
+impl<Id, Evt> From<event::Persisted<Id, Evt>> for PersistedEvent<Evt>
+where
+    Id: IdSerde,
+    Evt: Message,
+{
+    fn from(event: event::Persisted<Id, Evt>) -> Self {
+        Self {
+            stream_id: event.stream_id.encode_id(),
+            version: event.version,
+            name: event.event.message.name(),
+            payload: event.event.message,
+            metadata: event.event.metadata,
+        }
+    }
+}
+
+/// A Domain Event together with its global [Sequence][event::Sequence] number,
+/// as returned by the [Api].
+#[derive(Debug, Serialize)]
+struct SequencedEvent<Evt> {
+    sequence: event::Sequence,
+    #[serde(flatten)]
+    event: PersistedEvent<Evt>,
+}
+
+impl<Id, Evt> From<event::Sequenced<Id, Evt>> for SequencedEvent<Evt>
+where
+    Id: IdSerde,
+    Evt: Message,
+{
+    fn from(event: event::Sequenced<Id, Evt>) -> Self {
+        Self {
+            sequence: event.sequence,
+            event: event.event.into(),
+        }
+    }
+}
+
+/// A new Domain Event to append to an Event Stream.
+#[derive(Debug, Deserialize)]
+struct NewEvent<Evt> {
+    payload: Evt,
+    #[serde(default)]
+    metadata: Metadata,
+}
+
+#[derive(Debug, Serialize)]
+struct StreamPage<Evt> {
+    events: Vec<PersistedEvent<Evt>>,
+    next_resume_token: Option<event::ResumeToken>,
+}
+
+#[derive(Debug, Serialize)]
+struct AllPage<Evt> {
+    events: Vec<SequencedEvent<Evt>>,
+    next_resume_token: Option<event::ResumeToken>,
+}
+
+#[derive(Debug, Serialize)]
+struct Appended {
+    version: version::Version,
+}
+
+#[derive(Debug, Deserialize)]
+struct StreamQuery {
+    from_version: Option<version::Version>,
+    resume_token: Option<event::ResumeToken>,
+    limit: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AllQuery {
+    from_sequence: Option<event::Sequence>,
+    resume_token: Option<event::ResumeToken>,
+    limit: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SubscribeQuery {
+    from_sequence: Option<event::Sequence>,
+    resume_token: Option<event::ResumeToken>,
+}
+
+/// All possible errors returned by the [Api], which are sent to the client
+/// as a JSON object with an `error` field.
+#[derive(Debug)]
+enum ApiError {
+    BadRequest(String),
+    Conflict(version::ConflictError),
+    Unsupported,
+    Internal(String),
+}
+
+impl From<AppendError> for ApiError {
+    fn from(err: AppendError) -> Self {
+        match err {
+            AppendError::Conflict(err) => Self::Conflict(err),
+            AppendError::Unsupported => Self::Unsupported,
+            AppendError::Internal(err) => Self::Internal(err.to_string()),
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let (status, body) = match self {
+            Self::BadRequest(error) => (
+                StatusCode::BAD_REQUEST,
+                serde_json::json!({ "error": error }),
+            ),
+            Self::Conflict(err) => (
+                StatusCode::PRECONDITION_FAILED,
+                serde_json::json!({
+                    "error": err.to_string(),
+                    "expected_version": err.expected,
+                    "actual_version": err.actual,
+                }),
+            ),
+            Self::Unsupported => (
+                StatusCode::NOT_IMPLEMENTED,
+                serde_json::json!({ "error": "operation not supported by the event store" }),
+            ),
+            Self::Internal(error) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                serde_json::json!({ "error": error }),
+            ),
+        };
+
+        (status, Json(body)).into_response()
+    }
+}
+
+fn decode_stream_id<Id>(id: &str) -> Result<Id, ApiError>
+where
+    Id: IdSerde,
+{
+    Id::decode_id(id).map_err(|err| ApiError::BadRequest(format!("invalid stream id: {err}")))
+}
+
+/// Parses the `If-Match` header into a [`version::Check`]: a missing header or `*`
+/// means any version, otherwise the header must contain the expected version, e.g. `"3"`.
+fn version_check(headers: &HeaderMap) -> Result<version::Check, ApiError> {
+    let Some(value) = headers.get(header::IF_MATCH) else {
+        return Ok(version::Check::Any);
+    };
+
+    let value = value
+        .to_str()
+        .map_err(|_| ApiError::BadRequest("invalid If-Match header".to_owned()))?
+        .trim();
+
+    if value == "*" {
+        return Ok(version::Check::Any);
+    }
+
+    value
+        .trim_matches('"')
+        .parse()
+        .map(version::Check::MustBe)
+        .map_err(|_| ApiError::BadRequest(format!("invalid version in If-Match header: {value}")))
+}
+
+/// Returns the selection requested through either a `from_*` query parameter
+/// or the `resume_token` one, which cannot be used together.
+fn select<T>(
+    from: Option<T>,
+    resume_token: Option<event::ResumeToken>,
+    all: T,
+) -> Result<T, ApiError>
+where
+    T: TryFrom<event::ResumeToken, Error = event::ResumeTokenError>,
+{
+    match (from, resume_token) {
+        (Some(_), Some(_)) => Err(ApiError::BadRequest(
+            "resume_token cannot be used together with a starting position".to_owned(),
+        )),
+        (None, Some(token)) => T::try_from(token)
+            .map_err(|err| ApiError::BadRequest(format!("invalid resume token: {err}"))),
+        (from, None) => Ok(from.unwrap_or(all)),
+    }
+}
+
+/// Parses the `Last-Event-ID` header into the [`event::SequenceSelect`] to resume
+/// the subscription from: the header should contain either the [`event::ResumeToken`]
+/// sent as Server-Sent Event `id`, or the Sequence number of the last received Domain Event.
+fn last_event_id(headers: &HeaderMap) -> Result<Option<event::SequenceSelect>, ApiError> {
+    let Some(value) = headers.get("last-event-id") else {
+        return Ok(None);
+    };
+
+    let invalid = || ApiError::BadRequest("invalid Last-Event-ID header".to_owned());
+    let value = value.to_str().map_err(|_| invalid())?;
+
+    if let Ok(token) = value.parse::<event::ResumeToken>() {
+        return select(None, Some(token), event::SequenceSelect::All).map(Some);
+    }
+
+    value
+        .parse::<event::Sequence>()
+        .ok()
+        .and_then(|sequence| sequence.checked_add(1))
+        .map(|sequence| Some(event::SequenceSelect::From(sequence)))
+        .ok_or_else(invalid)
+}
+
+fn etag(version: version::Version) -> HeaderValue {
+    HeaderValue::from_str(&format!("\"{version}\"")).expect("a quoted number is a valid header")
+}
+
+async fn read_stream<S, Id, Evt>(
+    State(api): State<Arc<Api<S, Id, Evt>>>,
+    Path(id): Path<String>,
+    Query(query): Query<StreamQuery>,
+) -> Result<Json<StreamPage<Evt>>, ApiError>
+where
+    S: event::Store<Id, Evt> + GlobalStreamer<Id, Evt> + 'static,
+    <S as event::store::Streamer<Id, Evt>>::Error: Display,
+    <S as GlobalStreamer<Id, Evt>>::Error: Display,
+    Id: IdSerde + Send + Sync + 'static,
+    Evt: Message + Serialize + DeserializeOwned + Send + Sync + 'static,
+{
+    let id: Id = decode_stream_id(&id)?;
+    let limit = api.limit(query.limit);
+
+    let select = select(
+        query.from_version.map(event::VersionSelect::From),
+        query.resume_token,
+        event::VersionSelect::All,
+    )?;
+
+    let mut events: Vec<_> = api
+        .store
+        .stream(&id, select)
+        .take(limit + 1)
+        .try_collect()
+        .await
+        .map_err(|err| ApiError::Internal(err.to_string()))?;
+
+    let next_resume_token = (events.len() > limit)
+        .then(|| {
+            events.truncate(limit);
+            events.last().map(event::Persisted::resume_token)
+        })
+        .flatten();
+
+    Ok(Json(StreamPage {
+        events: events.into_iter().map(PersistedEvent::from).collect(),
+        next_resume_token,
+    }))
+}
+
+async fn append_to_stream<S, Id, Evt>(
+    State(api): State<Arc<Api<S, Id, Evt>>>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    Json(events): Json<Vec<NewEvent<Evt>>>,
+) -> Result<Response, ApiError>
+where
+    S: event::Store<Id, Evt> + GlobalStreamer<Id, Evt> + 'static,
+    <S as event::store::Streamer<Id, Evt>>::Error: Display,
+    <S as GlobalStreamer<Id, Evt>>::Error: Display,
+    Id: IdSerde + Send + Sync + 'static,
+    Evt: Message + Serialize + DeserializeOwned + Send + Sync + 'static,
+{
+    let id: Id = decode_stream_id(&id)?;
+    let version_check = version_check(&headers)?;
+
+    let events = events
+        .into_iter()
+        .map(|event| event::Envelope {
+            message: event.payload,
+            metadata: event.metadata,
+        })
+        .collect();
+
+    let version = api.store.append(id, version_check, events).await?;
+
+    Ok(([(header::ETAG, etag(version))], Json(Appended { version })).into_response())
+}
+
+async fn read_all<S, Id, Evt>(
+    State(api): State<Arc<Api<S, Id, Evt>>>,
+    Query(query): Query<AllQuery>,
+) -> Result<Json<AllPage<Evt>>, ApiError>
+where
+    S: event::Store<Id, Evt> + GlobalStreamer<Id, Evt> + 'static,
+    <S as event::store::Streamer<Id, Evt>>::Error: Display,
+    <S as GlobalStreamer<Id, Evt>>::Error: Display,
+    Id: IdSerde + Send + Sync + 'static,
+    Evt: Message + Serialize + DeserializeOwned + Send + Sync + 'static,
+{
+    let limit = api.limit(query.limit);
+
+    let select = select(
+        query.from_sequence.map(event::SequenceSelect::From),
+        query.resume_token,
+        event::SequenceSelect::All,
+    )?;
+
+    let mut events: Vec<_> = api
+        .store
+        .stream_all(select)
+        .take(limit + 1)
+        .try_collect()
+        .await
+        .map_err(|err| ApiError::Internal(err.to_string()))?;
+
+    let next_resume_token = (events.len() > limit)
+        .then(|| {
+            events.truncate(limit);
+            events.last().map(event::Sequenced::resume_token)
+        })
+        .flatten();
+
+    Ok(Json(AllPage {
+        events: events.into_iter().map(SequencedEvent::from).collect(),
+        next_resume_token,
+    }))
+}
+
+async fn subscribe<S, Id, Evt>(
+    State(api): State<Arc<Api<S, Id, Evt>>>,
+    Query(query): Query<SubscribeQuery>,
+    headers: HeaderMap,
+) -> Result<Sse<impl Stream<Item = Result<sse::Event, anyhow::Error>>>, ApiError>
+where
+    S: event::Store<Id, Evt> + GlobalStreamer<Id, Evt> + 'static,
+    <S as event::store::Streamer<Id, Evt>>::Error: Display,
+    <S as GlobalStreamer<Id, Evt>>::Error: Display,
+    Id: IdSerde + Send + Sync + 'static,
+    Evt: Message + Serialize + DeserializeOwned + Send + Sync + 'static,
+{
+    // Clients reconnecting after a failure send the id of the last event
+    // they have received, so the subscription resumes right after it.
+    let select = match last_event_id(&headers)? {
+        Some(select) => select,
+        None => select(
+            query.from_sequence.map(event::SequenceSelect::From),
+            query.resume_token,
+            event::SequenceSelect::All,
+        )?,
+    };
+
+    let stream = Polling::new(api.store.clone())
+        .with_poll_interval(api.poll_interval)
+        .into_stream(select, event::NameSelect::All)
+        .map_err(|err| anyhow::anyhow!("failed to stream events: {err}"))
+        .try_filter_map(|polled| async move {
+            // Heartbeats are disabled, as the keep-alive comments take their place.
+            let Polled::Event {
+                event,
+                resume_token,
+            } = polled
+            else {
+                return Ok(None);
+            };
+
+            let sse_event = sse::Event::default()
+                .id(resume_token.to_string())
+                .event(event.event.event.message.name())
+                .json_data(SequencedEvent::from(event))?;
+
+            Ok(Some(sse_event))
+        });
+
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
@@ -0,0 +1,13 @@
+//! `eventually-http` exposes an [`eventually::event::Store`] through an HTTP/JSON API,
+//! so that non-Rust consumers can read and write Event Streams directly.
+//!
+//! Check out the [`api::Api`] type to know more about the available endpoints,
+//! and the [`live`] module to forward live subscriptions to front-ends.
+
+#![deny(unsafe_code, unused_qualifications, trivial_casts)]
+#![deny(clippy::all, clippy::pedantic, clippy::cargo)]
+#![allow(clippy::multiple_crate_versions)]
+#![warn(missing_docs)]
+
+pub mod api;
+pub mod live;
@@ -0,0 +1,131 @@
+//! Module containing adapters to forward a live subscription of Domain Events
+//! to front-ends, through Server-Sent Events or, with the `ws` feature, WebSocket frames.
+//!
+//! Domain Events are serialized using the specified [`serde::Serializer`].
+
+use std::fmt::Display;
+
+#[cfg(feature = "ws")]
+use axum::extract::ws;
+use axum::response::sse::{self, KeepAlive, Sse};
+use eventually::message::Message;
+use eventually::{event, serde};
+use futures::stream::BoxStream;
+use futures::StreamExt;
+
+/// All possible errors returned by the live subscription adapters,
+/// which terminate the subscription.
+#[derive(Debug, thiserror::Error)]
+pub enum LiveError {
+    /// Error returned when the subscription has failed to stream the next Domain Event.
+    #[error("failed to receive the next event from the subscription: {0}")]
+    Subscription(String),
+    /// Error returned when a Domain Event cannot be serialized.
+    #[error("failed to serialize event: {0}")]
+    Serialize(#[source] anyhow::Error),
+    /// Error returned when a Domain Event has been serialized into binary data,
+    /// which cannot be sent in a Server-Sent Event.
+    #[error("failed to send event: server-sent events only support textual data, e.g. json")]
+    NotText,
+    /// Error returned when the WebSocket connection fails.
+    #[cfg(feature = "ws")]
+    #[error("failed to send event through the websocket: {0}")]
+    WebSocket(#[source] axum::Error),
+}
+
+fn serialize<Id, Evt, Err, S>(
+    serde: &S,
+    event: Result<event::Sequenced<Id, Evt>, Err>,
+) -> Result<(event::Sequence, &'static str, Vec<u8>), LiveError>
+where
+    Evt: Message,
+    Err: Display,
+    S: serde::Serializer<Evt>,
+{
+    let event = event.map_err(|err| LiveError::Subscription(err.to_string()))?;
+    let name = event.event.event.message.name();
+    let payload = serde
+        .serialize(event.event.event.message)
+        .map_err(LiveError::Serialize)?;
+
+    Ok((event.sequence, name, payload))
+}
+
+/// Forwards the Domain Events of a subscription as Server-Sent Events,
+/// to be returned by an [axum] handler.
+///
+/// Each Server-Sent Event has the [Sequence][event::Sequence] of the Domain Event as `id`,
+/// its [name][Message::name] as `event`, and the serialized Domain Event as `data`.
+/// As Server-Sent Events only support textual data, the [`serde::Serializer`]
+/// should produce UTF-8 data, e.g. JSON.
+pub fn sse<Id, Evt, Err, S>(
+    events: event::SequencedStream<'static, Id, Evt, Err>,
+    serde: S,
+) -> Sse<BoxStream<'static, Result<sse::Event, LiveError>>>
+where
+    Id: Send + 'static,
+    Evt: Message + Send + 'static,
+    Err: Display + Send + 'static,
+    S: serde::Serializer<Evt> + 'static,
+{
+    let events = events
+        .map(move |event| {
+            let (sequence, name, payload) = serialize(&serde, event)?;
+            let data = String::from_utf8(payload).map_err(|_| LiveError::NotText)?;
+
+            Ok(sse::Event::default()
+                .id(sequence.to_string())
+                .event(name)
+                .data(data))
+        })
+        .boxed();
+
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
+
+/// Forwards the Domain Events of a subscription through the specified [`WebSocket`][ws::WebSocket],
+/// usually obtained through [`WebSocketUpgrade::on_upgrade`][ws::WebSocketUpgrade::on_upgrade].
+///
+/// Each Domain Event is sent in its own frame: a text frame if the serialized data
+/// is valid UTF-8, a binary frame otherwise. Messages sent by the client are ignored.
+///
+/// The function returns when the subscription ends, or when the client closes the connection.
+///
+/// # Errors
+///
+/// An error is returned if the subscription fails, a Domain Event cannot be serialized,
+/// or the WebSocket connection fails.
+#[cfg(feature = "ws")]
+pub async fn websocket<Id, Evt, Err, S>(
+    mut socket: ws::WebSocket,
+    mut events: event::SequencedStream<'_, Id, Evt, Err>,
+    serde: S,
+) -> Result<(), LiveError>
+where
+    Evt: Message,
+    Err: Display,
+    S: serde::Serializer<Evt>,
+{
+    loop {
+        tokio::select! {
+            event = events.next() => {
+                let Some(event) = event else {
+                    return socket.close().await.map_err(LiveError::WebSocket);
+                };
+
+                let (_, _, payload) = serialize(&serde, event)?;
+                let frame = match String::from_utf8(payload) {
+                    Ok(text) => ws::Message::Text(text),
+                    Err(err) => ws::Message::Binary(err.into_bytes()),
+                };
+
+                socket.send(frame).await.map_err(LiveError::WebSocket)?;
+            },
+            message = socket.recv() => match message {
+                None | Some(Ok(ws::Message::Close(_))) => return Ok(()),
+                Some(Err(err)) => return Err(LiveError::WebSocket(err)),
+                Some(Ok(_)) => {},
+            },
+        }
+    }
+}
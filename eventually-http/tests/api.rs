@@ -0,0 +1,246 @@
+use std::time::Duration;
+
+use axum::body::{Body, HttpBody};
+use axum::http::{header, Request, StatusCode};
+use axum::Router;
+use eventually::event::store::InMemory;
+use eventually::message::Message;
+use eventually_http::api::Api;
+use serde_json::{json, Value};
+use tower::ServiceExt;
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type")]
+enum TestEvent {
+    WasCreated { name: String },
+    WasRenamed { name: String },
+}
+
+impl Message for TestEvent {
+    fn name(&self) -> &'static str {
+        match self {
+            TestEvent::WasCreated { .. } => "TestWasCreated",
+            TestEvent::WasRenamed { .. } => "TestWasRenamed",
+        }
+    }
+}
+
+fn router() -> Router {
+    Api::new(InMemory::<String, TestEvent>::default())
+        .with_page_size(2)
+        .with_poll_interval(Duration::from_millis(10))
+        .into_router()
+}
+
+async fn send(router: &Router, request: Request<Body>) -> (StatusCode, Value) {
+    let response = router.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+
+    (status, serde_json::from_slice(&body).unwrap())
+}
+
+fn append(stream_id: &str, if_match: Option<&str>, events: &Value) -> Request<Body> {
+    let mut request = Request::post(format!("/streams/{stream_id}"))
+        .header(header::CONTENT_TYPE, "application/json");
+
+    if let Some(if_match) = if_match {
+        request = request.header(header::IF_MATCH, if_match);
+    }
+
+    request.body(Body::from(events.to_string())).unwrap()
+}
+
+fn get(uri: &str) -> Request<Body> {
+    Request::get(uri).body(Body::empty()).unwrap()
+}
+
+#[tokio::test]
+async fn append_and_read_a_stream_with_pagination() {
+    let router = router();
+
+    let events = json!([
+        { "payload": { "type": "WasCreated", "name": "a" }, "metadata": { "Key": "value" } },
+        { "payload": { "type": "WasRenamed", "name": "b" } },
+        { "payload": { "type": "WasRenamed", "name": "c" } },
+    ]);
+
+    let (status, body) = send(&router, append("stream:a", Some("\"0\""), &events)).await;
+    assert_eq!(StatusCode::OK, status);
+    assert_eq!(json!({ "version": 3 }), body);
+
+    let (status, mut body) = send(&router, get("/streams/stream:a")).await;
+    assert_eq!(StatusCode::OK, status);
+
+    // The Event Store records the append time of each Domain Event in its metadata.
+    for event in body["events"].as_array_mut().unwrap() {
+        let metadata = event["metadata"].as_object_mut().unwrap();
+        assert!(metadata.remove("Recorded-At").is_some());
+    }
+
+    assert_eq!(
+        json!({
+            "events": [
+                {
+                    "stream_id": "stream:a",
+                    "version": 1,
+                    "name": "TestWasCreated",
+                    "payload": { "type": "WasCreated", "name": "a" },
+                    "metadata": { "Key": "value" },
+                },
+                {
+                    "stream_id": "stream:a",
+                    "version": 2,
+                    "name": "TestWasRenamed",
+                    "payload": { "type": "WasRenamed", "name": "b" },
+                    "metadata": {},
+                },
+            ],
+            "next_resume_token": body["next_resume_token"],
+        }),
+        body
+    );
+
+    let next_page = format!(
+        "/streams/stream:a?resume_token={}",
+        body["next_resume_token"].as_str().unwrap()
+    );
+
+    let (status, body) = send(&router, get(&next_page)).await;
+    assert_eq!(StatusCode::OK, status);
+    assert_eq!(1, body["events"].as_array().unwrap().len());
+    assert_eq!(3, body["events"][0]["version"]);
+    assert_eq!(Value::Null, body["next_resume_token"]);
+
+    let (status, body) = send(&router, get("/streams/stream:a?from_version=3")).await;
+    assert_eq!(StatusCode::OK, status);
+    assert_eq!(1, body["events"].as_array().unwrap().len());
+
+    let (status, body) = send(&router, get("/events?from_sequence=2&limit=1")).await;
+    assert_eq!(StatusCode::OK, status);
+    assert_eq!(2, body["events"][0]["sequence"]);
+
+    let next_page = format!(
+        "/events?resume_token={}",
+        body["next_resume_token"].as_str().unwrap()
+    );
+
+    let (status, body) = send(&router, get(&next_page)).await;
+    assert_eq!(StatusCode::OK, status);
+    assert_eq!(3, body["events"][0]["sequence"]);
+    assert_eq!(Value::Null, body["next_resume_token"]);
+}
+
+#[tokio::test]
+async fn resume_tokens_are_validated() {
+    let router = router();
+    let events = json!([
+        { "payload": { "type": "WasCreated", "name": "a" } },
+        { "payload": { "type": "WasRenamed", "name": "b" } },
+        { "payload": { "type": "WasRenamed", "name": "c" } },
+    ]);
+
+    send(&router, append("stream:a", None, &events)).await;
+
+    let (_, body) = send(&router, get("/streams/stream:a")).await;
+    let stream_token = body["next_resume_token"].as_str().unwrap().to_owned();
+
+    // Tokens of a single Event Stream cannot be used to read all the Domain Events.
+    let (status, _) = send(
+        &router,
+        get(&format!("/events?resume_token={stream_token}")),
+    )
+    .await;
+    assert_eq!(StatusCode::BAD_REQUEST, status);
+
+    let (status, _) = send(
+        &router,
+        get(&format!(
+            "/streams/stream:a?from_version=1&resume_token={stream_token}"
+        )),
+    )
+    .await;
+    assert_eq!(StatusCode::BAD_REQUEST, status);
+
+    for last_event_id in [stream_token, u64::MAX.to_string(), "nope".to_owned()] {
+        let request = Request::get("/events/subscribe")
+            .header("last-event-id", last_event_id)
+            .body(Body::empty())
+            .unwrap();
+
+        let (status, _) = send(&router, request).await;
+        assert_eq!(StatusCode::BAD_REQUEST, status);
+    }
+}
+
+#[tokio::test]
+async fn append_with_a_stale_if_match_version_fails_with_precondition_failed() {
+    let router = router();
+    let events = json!([{ "payload": { "type": "WasCreated", "name": "a" } }]);
+
+    let (status, _) = send(&router, append("stream:a", None, &events)).await;
+    assert_eq!(StatusCode::OK, status);
+
+    let (status, body) = send(&router, append("stream:a", Some("\"0\""), &events)).await;
+    assert_eq!(StatusCode::PRECONDITION_FAILED, status);
+    assert_eq!(0, body["expected_version"]);
+    assert_eq!(1, body["actual_version"]);
+
+    let (status, _) = send(&router, append("stream:a", Some("not-a-version"), &events)).await;
+    assert_eq!(StatusCode::BAD_REQUEST, status);
+}
+
+#[tokio::test]
+async fn subscribe_sends_new_events_as_server_sent_events() {
+    let router = router();
+    let events = json!([{ "payload": { "type": "WasCreated", "name": "a" } }]);
+
+    send(&router, append("stream:a", None, &events)).await;
+
+    let request = Request::get("/events/subscribe")
+        .header("last-event-id", "1")
+        .body(Body::empty())
+        .unwrap();
+
+    let response = router.clone().oneshot(request).await.unwrap();
+    assert_eq!(StatusCode::OK, response.status());
+
+    send(&router, append("stream:b", None, &events)).await;
+
+    let mut body = response.into_body();
+    let chunk = tokio::time::timeout(Duration::from_secs(5), body.data())
+        .await
+        .expect("subscription should receive new events")
+        .unwrap()
+        .unwrap();
+
+    let chunk = String::from_utf8(chunk.to_vec()).unwrap();
+    assert!(chunk.contains("event:TestWasCreated\n"), "{chunk}");
+    assert!(chunk.contains(r#""sequence":2"#), "{chunk}");
+    assert!(chunk.contains(r#""stream_id":"stream:b""#), "{chunk}");
+
+    // The id of the event can be used to resume the subscription right after it.
+    let id = chunk
+        .lines()
+        .find_map(|line| line.strip_prefix("id:"))
+        .expect("event should have an id");
+
+    let request = Request::get("/events/subscribe")
+        .header("last-event-id", id)
+        .body(Body::empty())
+        .unwrap();
+
+    let response = router.clone().oneshot(request).await.unwrap();
+    assert_eq!(StatusCode::OK, response.status());
+
+    send(&router, append("stream:c", None, &events)).await;
+
+    let chunk = tokio::time::timeout(Duration::from_secs(5), response.into_body().data())
+        .await
+        .expect("subscription should receive new events")
+        .unwrap()
+        .unwrap();
+
+    let chunk = String::from_utf8(chunk.to_vec()).unwrap();
+    assert!(chunk.contains(r#""stream_id":"stream:c""#), "{chunk}");
+}
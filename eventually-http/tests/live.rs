@@ -0,0 +1,129 @@
+use std::convert::Infallible;
+use std::time::Duration;
+
+use axum::body::{Body, HttpBody};
+use axum::http::{Request, StatusCode};
+use axum::routing::get;
+use axum::Router;
+use eventually::event;
+use eventually::message::Message;
+use eventually::serde::Json;
+use eventually_http::live;
+use futures::stream::{self, StreamExt};
+use tower::ServiceExt;
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+struct WasCreated {
+    name: String,
+}
+
+impl Message for WasCreated {
+    fn name(&self) -> &'static str {
+        "WasCreated"
+    }
+}
+
+/// Returns a subscription that delivers the specified Domain Events,
+/// and then stays open forever, like a live subscription does.
+fn subscription(names: &[&str]) -> event::SequencedStream<'static, String, WasCreated, Infallible> {
+    let events: Vec<_> = names
+        .iter()
+        .zip(1..)
+        .map(|(name, sequence)| {
+            Ok(event::Sequenced {
+                sequence,
+                event: event::Persisted {
+                    stream_id: "stream:a".to_owned(),
+                    version: sequence,
+                    event: WasCreated {
+                        name: (*name).to_owned(),
+                    }
+                    .into(),
+                },
+            })
+        })
+        .collect();
+
+    stream::iter(events).chain(stream::pending()).boxed()
+}
+
+#[tokio::test]
+async fn sse_forwards_subscription_events() {
+    let router = Router::new().route(
+        "/subscribe",
+        get(|| async { live::sse(subscription(&["a"]), Json::<WasCreated>::default()) }),
+    );
+
+    let response = router
+        .oneshot(Request::get("/subscribe").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+
+    assert_eq!(StatusCode::OK, response.status());
+
+    let mut body = response.into_body();
+    let chunk = tokio::time::timeout(Duration::from_secs(5), body.data())
+        .await
+        .expect("subscription should send the event")
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(
+        "id:1\nevent:WasCreated\ndata:{\"name\":\"a\"}\n\n",
+        String::from_utf8(chunk.to_vec()).unwrap()
+    );
+}
+
+#[cfg(feature = "ws")]
+#[tokio::test]
+async fn websocket_forwards_subscription_events() {
+    use axum::extract::ws::WebSocketUpgrade;
+    use tokio_tungstenite::tungstenite;
+
+    let router = Router::new().route(
+        "/subscribe",
+        get(|upgrade: WebSocketUpgrade| async {
+            upgrade.on_upgrade(|socket| async {
+                live::websocket(
+                    socket,
+                    subscription(&["a", "b"]),
+                    Json::<WasCreated>::default(),
+                )
+                .await
+                .unwrap();
+            })
+        }),
+    );
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+
+    tokio::spawn(
+        axum::Server::from_tcp(listener)
+            .unwrap()
+            .serve(router.into_make_service()),
+    );
+
+    let (mut socket, _) = tokio_tungstenite::connect_async(format!("ws://{address}/subscribe"))
+        .await
+        .unwrap();
+
+    let mut frames = Vec::new();
+    while frames.len() < 2 {
+        let frame = tokio::time::timeout(Duration::from_secs(5), socket.next())
+            .await
+            .expect("subscription should send the events")
+            .unwrap()
+            .unwrap();
+
+        frames.push(frame);
+    }
+
+    assert_eq!(
+        vec![
+            tungstenite::Message::Text(r#"{"name":"a"}"#.to_owned()),
+            tungstenite::Message::Text(r#"{"name":"b"}"#.to_owned()),
+        ],
+        frames
+    );
+}
@@ -3,10 +3,15 @@
 
 #![deny(unsafe_code, unused_qualifications, trivial_casts, missing_docs)]
 #![deny(clippy::all, clippy::pedantic, clippy::cargo)]
+#![allow(clippy::multiple_crate_versions)]
 
 use proc_macro::TokenStream;
-use quote::quote;
-use syn::{parse_macro_input, AttributeArgs, Fields, ItemStruct, Meta, NestedMeta, Path};
+use quote::{format_ident, quote};
+use syn::punctuated::Punctuated;
+use syn::{
+    parse_macro_input, Attribute, AttributeArgs, Data, DeriveInput, Fields, Ident, ItemStruct, Lit,
+    LitStr, Meta, NestedMeta, Path, Token,
+};
 
 /// Implements a newtype to use the [`eventually::aggregate::Root`] instance with
 /// user-defined [`eventually::aggregate::Aggregate`] types.
@@ -81,3 +86,647 @@ pub fn aggregate_root(args: TokenStream, item: TokenStream) -> TokenStream {
 
     result.into()
 }
+
+/// Arguments of the [`macro@command_handler`] attribute macro.
+struct CommandHandlerArgs {
+    service: Path,
+    root: Path,
+    repository: Ident,
+    action: Action,
+    args: Option<Vec<Ident>>,
+    name: Option<String>,
+}
+
+/// How the Aggregate Root is obtained and the Command is evaluated.
+enum Action {
+    /// Creates a new Aggregate Root through an associated function.
+    Create(Ident),
+    /// Loads the Aggregate Root with the specified id field, then calls a method on it.
+    Method { method: Ident, id: Ident },
+}
+
+impl CommandHandlerArgs {
+    fn parse(args: AttributeArgs) -> syn::Result<Self> {
+        let mut service = None;
+        let mut root = None;
+        let mut repository = None;
+        let mut create = None;
+        let mut method = None;
+        let mut id = None;
+        let mut handler_args = None;
+        let mut name = None;
+
+        for arg in args {
+            let NestedMeta::Meta(Meta::NameValue(arg)) = arg else {
+                return Err(syn::Error::new_spanned(
+                    arg,
+                    "expected `key = \"value\"` argument",
+                ));
+            };
+
+            let Lit::Str(value) = &arg.lit else {
+                return Err(syn::Error::new_spanned(arg.lit, "expected string literal"));
+            };
+
+            let key = arg.path.get_ident().map(ToString::to_string);
+
+            match key.as_deref() {
+                Some("service") => service = Some(value.parse::<Path>()?),
+                Some("root") => root = Some(value.parse::<Path>()?),
+                Some("repository") => repository = Some(value.parse::<Ident>()?),
+                Some("create") => create = Some(value.parse::<Ident>()?),
+                Some("method") => method = Some(value.parse::<Ident>()?),
+                Some("id") => id = Some(value.parse::<Ident>()?),
+                Some("args") => {
+                    handler_args = Some(
+                        value
+                            .parse_with(Punctuated::<Ident, Token![,]>::parse_terminated)?
+                            .into_iter()
+                            .collect(),
+                    );
+                },
+                Some("name") => name = Some(value.value()),
+                _ => return Err(syn::Error::new_spanned(arg.path, "unknown argument")),
+            }
+        }
+
+        let span = proc_macro2::Span::call_site();
+
+        let action = match (create, method, id) {
+            (Some(create), None, None) => Action::Create(create),
+            (None, Some(method), Some(id)) => Action::Method { method, id },
+            (None, Some(_), None) => {
+                return Err(syn::Error::new(
+                    span,
+                    "`id` is required when using `method`",
+                ))
+            },
+            _ => {
+                return Err(syn::Error::new(
+                    span,
+                    "either `create` or `method` (with `id`) must be specified",
+                ))
+            },
+        };
+
+        Ok(Self {
+            service: service.ok_or_else(|| syn::Error::new(span, "`service` is required"))?,
+            root: root.ok_or_else(|| syn::Error::new(span, "`root` is required"))?,
+            repository: repository.unwrap_or_else(|| format_ident!("repository")),
+            action,
+            args: handler_args,
+            name,
+        })
+    }
+}
+
+/// Generates the [`eventually::message::Message`] implementation for a Command struct,
+/// and the [`eventually::command::Handler`] implementation handling it through
+/// an Aggregate Root, i.e. a type annotated with [`macro@aggregate_root`].
+///
+/// The generated Handler is implemented on the specified `service` type, which must
+/// have a field (named `repository` by default) implementing [`eventually::aggregate::Repository`].
+/// The Handler marks the Aggregate Root as caused by the Command, evaluates
+/// the Command and saves the Aggregate Root back to the Repository, returning
+/// an [`anyhow::Error`] on failure.
+///
+/// # Arguments
+///
+/// - `service`: the type to implement the Command Handler on,
+/// - `root`: the Aggregate Root type,
+/// - `create`: the Aggregate Root associated function creating a new Aggregate Root, or
+/// - `method`: the Aggregate Root method to call on an existing Aggregate Root,
+///   loaded using the Command field specified in `id`,
+/// - `args` (optional): the comma-separated Command fields to pass to `create`/`method`,
+///   defaulting to all the fields (except `id`) in declaration order,
+/// - `repository` (optional): the name of the Repository field of the `service`,
+/// - `name` (optional): the name of the Command message, defaulting to the struct name.
+///
+/// # Example
+///
+/// ```text
+/// #[command_handler(
+///     service = "Service",
+///     root = "BankAccountRoot",
+///     method = "deposit",
+///     id = "bank_account_id"
+/// )]
+/// pub struct DepositInBankAccount {
+///     pub bank_account_id: BankAccountId,
+///     pub amount: Decimal,
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn command_handler(args: TokenStream, item: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(args as AttributeArgs);
+    let item = parse_macro_input!(item as ItemStruct);
+
+    match expand_command_handler(args, &item) {
+        Ok(result) => result.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+fn expand_command_handler(
+    args: AttributeArgs,
+    item: &ItemStruct,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let CommandHandlerArgs {
+        service,
+        root,
+        repository,
+        action,
+        args,
+        name,
+    } = CommandHandlerArgs::parse(args)?;
+
+    let Fields::Named(fields) = &item.fields else {
+        return Err(syn::Error::new_spanned(
+            &item.fields,
+            "commands must be structs with named fields",
+        ));
+    };
+
+    let item_ident = &item.ident;
+    let name = name.unwrap_or_else(|| item_ident.to_string());
+
+    let id = match &action {
+        Action::Create(_) => None,
+        Action::Method { id, .. } => Some(id),
+    };
+
+    let args = args.unwrap_or_else(|| {
+        fields
+            .named
+            .iter()
+            .filter_map(|field| field.ident.clone())
+            .filter(|field| Some(field) != id)
+            .collect()
+    });
+
+    let evaluation = match &action {
+        Action::Create(create) => quote! {
+            let mut root = <#root>::#create(#(command.message.#args),*)?;
+            root.caused_by(&metadata);
+        },
+        Action::Method { method, id } => quote! {
+            let mut root: #root = <_ as eventually::aggregate::repository::Getter<_>>::get(
+                &self.#repository,
+                &command.message.#id,
+            )
+            .await?
+            .into();
+            root.caused_by(&metadata);
+            root.#method(#(command.message.#args),*)?;
+        },
+    };
+
+    Ok(quote! {
+        #item
+
+        impl eventually::message::Message for #item_ident {
+            fn name(&self) -> &'static str {
+                #name
+            }
+        }
+
+        #[eventually::__private::async_trait]
+        impl eventually::command::Handler<#item_ident> for #service {
+            type Error = eventually::__private::anyhow::Error;
+
+            async fn handle(
+                &self,
+                command: eventually::command::Envelope<#item_ident>,
+            ) -> Result<(), Self::Error> {
+                // Only the metadata is needed to mark the Aggregate Root as caused by the Command.
+                let metadata = eventually::message::Envelope {
+                    message: eventually::__private::CommandName(#name),
+                    metadata: command.metadata.clone(),
+                };
+
+                #evaluation
+
+                <_ as eventually::aggregate::repository::Saver<_>>::save(
+                    &self.#repository,
+                    &mut root,
+                )
+                .await?;
+
+                Ok(())
+            }
+        }
+    })
+}
+
+/// Implements [`eventually::message::Message`] for a struct or an enum.
+///
+/// The message name defaults to the struct name or, for enums, to the name
+/// of each variant. It can be customized with the `#[message(...)]` attribute:
+///
+/// - `#[message(name = "...")]` on the struct or on an enum variant
+///   sets the name of the message explicitly,
+/// - `#[message(rename_all = "...")]` on the struct or enum changes the naming strategy
+///   used for the default names, and can be one of `PascalCase` (the default),
+///   `camelCase`, `snake_case`, `SCREAMING_SNAKE_CASE` or `kebab-case`.
+///
+/// # Example
+///
+/// ```text
+/// #[derive(Message)]
+/// #[message(rename_all = "kebab-case")]
+/// enum UserEvent {
+///     WasCreated { id: String },             // "was-created"
+///     #[message(name = "user-was-deleted")]
+///     WasDeleted { id: String },             // "user-was-deleted"
+/// }
+/// ```
+#[proc_macro_derive(Message, attributes(message))]
+pub fn derive_message(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    match expand_message(&input) {
+        Ok(result) => result.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+fn expand_message(input: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let rename_all = match message_attribute(&input.attrs, "rename_all")? {
+        None => None,
+        Some(rename_all) => Some(naming_strategy(&rename_all)?),
+    };
+
+    let message_name = |attrs: &[Attribute], ident: &Ident| -> syn::Result<String> {
+        let name = message_attribute(attrs, "name")?.map(|name| name.value());
+        let ident = ident.to_string();
+
+        Ok(name.unwrap_or_else(|| rename_all.map_or_else(|| ident.clone(), |f| f(&ident))))
+    };
+
+    let body = match &input.data {
+        Data::Struct(_) => {
+            let name = message_name(&input.attrs, ident)?;
+            quote! { #name }
+        },
+        Data::Enum(data) => {
+            let arms = data
+                .variants
+                .iter()
+                .map(|variant| {
+                    let variant_ident = &variant.ident;
+                    let name = message_name(&variant.attrs, variant_ident)?;
+
+                    Ok(quote! { Self::#variant_ident { .. } => #name, })
+                })
+                .collect::<syn::Result<Vec<_>>>()?;
+
+            quote! {
+                match self {
+                    #(#arms)*
+                }
+            }
+        },
+        Data::Union(data) => {
+            return Err(syn::Error::new_spanned(
+                data.union_token,
+                "Message cannot be derived for unions",
+            ))
+        },
+    };
+
+    Ok(quote! {
+        impl #impl_generics eventually::message::Message for #ident #ty_generics #where_clause {
+            fn name(&self) -> &'static str {
+                #body
+            }
+        }
+    })
+}
+
+/// Implements the conversions needed to use a newtype as an Aggregate id,
+/// e.g. `struct UserId(i64);`, to and from its string representation.
+///
+/// The generated implementations are:
+/// - [`std::fmt::Display`], printing the inner value,
+/// - [`std::str::FromStr`] and `TryFrom<String>`, parsing the inner value
+///   and failing with an [`anyhow::Error`],
+/// - [`eventually::aggregate::IdSerde`], using the implementations above, as expected
+///   by the data stores that persist Event Stream ids as strings,
+/// - `serde::Serialize` and `serde::Deserialize`, delegating to the inner value,
+///   so that Domain Events payloads are not affected by the id prefix.
+///
+/// The string representation can be prefixed using the
+/// `#[aggregate_id(prefix = "...")]` attribute, in which case the prefix is
+/// separated from the inner value by a `:`, e.g. `user:42`.
+///
+/// # Example
+///
+/// ```text
+/// #[derive(Debug, Clone, Copy, PartialEq, Eq, AggregateId)]
+/// #[aggregate_id(prefix = "user")]
+/// struct UserId(i64); // "user:42"
+/// ```
+#[proc_macro_derive(AggregateId, attributes(aggregate_id))]
+pub fn derive_aggregate_id(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    match expand_aggregate_id(&input) {
+        Ok(result) => result.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+fn expand_aggregate_id(input: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let ident = &input.ident;
+
+    let inner = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Unnamed(fields) if fields.unnamed.len() == 1 => &fields.unnamed[0].ty,
+            fields => {
+                return Err(syn::Error::new_spanned(
+                    fields,
+                    "AggregateId can only be derived for newtypes, e.g. `struct UserId(i64);`",
+                ))
+            },
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                ident,
+                "AggregateId can only be derived for newtypes, e.g. `struct UserId(i64);`",
+            ))
+        },
+    };
+
+    let prefix = attribute_value(&input.attrs, "aggregate_id", &["prefix"], "prefix")?
+        .map(|prefix| format!("{}:", prefix.value()));
+
+    let (display, parse) = match prefix {
+        None => (
+            quote! { ::std::fmt::Display::fmt(&self.0, f) },
+            quote! { s },
+        ),
+        Some(prefix) => (
+            quote! { write!(f, "{}{}", #prefix, self.0) },
+            quote! {
+                s.strip_prefix(#prefix).ok_or_else(|| {
+                    eventually::__private::anyhow::anyhow!(
+                        "aggregate id '{}' does not start with '{}'", s, #prefix
+                    )
+                })?
+            },
+        ),
+    };
+
+    Ok(quote! {
+        impl ::std::fmt::Display for #ident {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                #display
+            }
+        }
+
+        impl ::std::str::FromStr for #ident {
+            type Err = eventually::__private::anyhow::Error;
+
+            fn from_str(s: &str) -> ::std::result::Result<Self, Self::Err> {
+                let value = #parse;
+
+                value.parse::<#inner>().map(Self).map_err(|err| {
+                    eventually::__private::anyhow::anyhow!(
+                        "failed to parse aggregate id '{}': {}", s, err
+                    )
+                })
+            }
+        }
+
+        impl eventually::aggregate::IdSerde for #ident {
+            fn encode_id(&self) -> String {
+                self.to_string()
+            }
+
+            fn decode_id(id: &str) -> eventually::__private::anyhow::Result<Self> {
+                id.parse()
+            }
+        }
+
+        impl ::std::convert::TryFrom<String> for #ident {
+            type Error = eventually::__private::anyhow::Error;
+
+            fn try_from(value: String) -> ::std::result::Result<Self, Self::Error> {
+                value.parse()
+            }
+        }
+
+        impl eventually::__private::serde::Serialize for #ident {
+            fn serialize<S>(&self, serializer: S) -> ::std::result::Result<S::Ok, S::Error>
+            where
+                S: eventually::__private::serde::Serializer,
+            {
+                eventually::__private::serde::Serialize::serialize(&self.0, serializer)
+            }
+        }
+
+        impl<'de> eventually::__private::serde::Deserialize<'de> for #ident {
+            fn deserialize<D>(deserializer: D) -> ::std::result::Result<Self, D::Error>
+            where
+                D: eventually::__private::serde::Deserializer<'de>,
+            {
+                <#inner as eventually::__private::serde::Deserialize>::deserialize(deserializer)
+                    .map(Self)
+            }
+        }
+    })
+}
+
+/// Implements `eventually::interop::grpc::IntoStatus` for an error struct or enum,
+/// using its [`std::fmt::Display`] implementation as the status message.
+///
+/// The gRPC status code defaults to `internal`, and can be customized with
+/// the `#[status(code = "...")]` attribute on the struct, on the enum (as the default
+/// for all its variants) or on an enum variant, using the `snake_case` name
+/// of any canonical gRPC code, e.g. `invalid_argument` or `failed_precondition`.
+///
+/// The `grpc` feature of the `eventually` crate must be enabled.
+///
+/// # Example
+///
+/// ```text
+/// #[derive(Debug, thiserror::Error, IntoStatus)]
+/// #[status(code = "failed_precondition")]
+/// enum BankAccountError {
+///     #[error("bank account id cannot be empty")]
+///     #[status(code = "invalid_argument")]
+///     EmptyAccountId,                         // InvalidArgument
+///     #[error("bank account is closed")]
+///     Closed,                                 // FailedPrecondition
+/// }
+/// ```
+#[proc_macro_derive(IntoStatus, attributes(status))]
+pub fn derive_into_status(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    match expand_into_status(&input) {
+        Ok(result) => result.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+/// Canonical gRPC status codes, by their `snake_case` name.
+const STATUS_CODES: [&str; 17] = [
+    "ok",
+    "cancelled",
+    "unknown",
+    "invalid_argument",
+    "deadline_exceeded",
+    "not_found",
+    "already_exists",
+    "permission_denied",
+    "resource_exhausted",
+    "failed_precondition",
+    "aborted",
+    "out_of_range",
+    "unimplemented",
+    "internal",
+    "unavailable",
+    "data_loss",
+    "unauthenticated",
+];
+
+fn expand_into_status(input: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    use heck::ToUpperCamelCase;
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    // Returns the status code set by the `#[status(code = "...")]` attribute, if any.
+    let status_code = |attrs: &[Attribute]| -> syn::Result<Option<Ident>> {
+        let Some(code) = attribute_value(attrs, "status", &["code"], "code")? else {
+            return Ok(None);
+        };
+
+        if !STATUS_CODES.contains(&code.value().as_str()) {
+            return Err(syn::Error::new_spanned(
+                code,
+                format!(
+                    "unknown gRPC status code, expected one of: {}",
+                    STATUS_CODES.join(", ")
+                ),
+            ));
+        }
+
+        Ok(Some(format_ident!(
+            "{}",
+            code.value().to_upper_camel_case()
+        )))
+    };
+
+    let default_code = status_code(&input.attrs)?.unwrap_or_else(|| format_ident!("Internal"));
+
+    let code = match &input.data {
+        Data::Struct(_) => quote! { eventually::interop::grpc::Code::#default_code },
+        Data::Enum(data) => {
+            let arms = data
+                .variants
+                .iter()
+                .map(|variant| {
+                    let variant_ident = &variant.ident;
+                    let code = status_code(&variant.attrs)?.unwrap_or_else(|| default_code.clone());
+
+                    Ok(quote! {
+                        Self::#variant_ident { .. } => eventually::interop::grpc::Code::#code,
+                    })
+                })
+                .collect::<syn::Result<Vec<_>>>()?;
+
+            quote! {
+                match &self {
+                    #(#arms)*
+                }
+            }
+        },
+        Data::Union(data) => {
+            return Err(syn::Error::new_spanned(
+                data.union_token,
+                "IntoStatus cannot be derived for unions",
+            ))
+        },
+    };
+
+    Ok(quote! {
+        impl #impl_generics eventually::interop::grpc::IntoStatus for #ident #ty_generics #where_clause {
+            fn into_status(self) -> eventually::interop::grpc::Status {
+                let code = #code;
+                eventually::interop::grpc::Status::new(code, self.to_string())
+            }
+        }
+    })
+}
+
+/// Returns the value of the `key = "..."` argument from the `#[message(...)]` attributes.
+fn message_attribute(attrs: &[Attribute], key: &str) -> syn::Result<Option<LitStr>> {
+    attribute_value(attrs, "message", &["name", "rename_all"], key)
+}
+
+/// Returns the value of the `key = "..."` argument from the `#[attribute(...)]` attributes,
+/// failing on arguments other than the allowed ones.
+fn attribute_value(
+    attrs: &[Attribute],
+    attribute: &str,
+    allowed: &[&str],
+    key: &str,
+) -> syn::Result<Option<LitStr>> {
+    let mut value = None;
+
+    for attr in attrs.iter().filter(|attr| attr.path.is_ident(attribute)) {
+        let Meta::List(list) = attr.parse_meta()? else {
+            return Err(syn::Error::new_spanned(
+                attr,
+                format!("expected `#[{attribute}(...)]`"),
+            ));
+        };
+
+        for nested in list.nested {
+            let NestedMeta::Meta(Meta::NameValue(arg)) = nested else {
+                return Err(syn::Error::new_spanned(
+                    nested,
+                    "expected `key = \"value\"` argument",
+                ));
+            };
+
+            if !allowed.iter().any(|allowed| arg.path.is_ident(allowed)) {
+                return Err(syn::Error::new_spanned(arg.path, "unknown argument"));
+            }
+
+            if !arg.path.is_ident(key) {
+                continue;
+            }
+
+            let Lit::Str(string) = arg.lit else {
+                return Err(syn::Error::new_spanned(arg.lit, "expected string literal"));
+            };
+
+            value = Some(string);
+        }
+    }
+
+    Ok(value)
+}
+
+fn naming_strategy(rename_all: &LitStr) -> syn::Result<fn(&str) -> String> {
+    use heck::{ToKebabCase, ToLowerCamelCase, ToShoutySnakeCase, ToSnakeCase, ToUpperCamelCase};
+
+    match rename_all.value().as_str() {
+        "PascalCase" => Ok(ToUpperCamelCase::to_upper_camel_case),
+        "camelCase" => Ok(ToLowerCamelCase::to_lower_camel_case),
+        "snake_case" => Ok(ToSnakeCase::to_snake_case),
+        "SCREAMING_SNAKE_CASE" => Ok(ToShoutySnakeCase::to_shouty_snake_case),
+        "kebab-case" => Ok(ToKebabCase::to_kebab_case),
+        _ => Err(syn::Error::new_spanned(
+            rename_all,
+            "unsupported naming strategy, expected one of: \
+             PascalCase, camelCase, snake_case, SCREAMING_SNAKE_CASE, kebab-case",
+        )),
+    }
+}
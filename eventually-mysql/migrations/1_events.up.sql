@@ -0,0 +1,17 @@
+CREATE TABLE event_streams (
+    event_stream_id VARCHAR(255) NOT NULL PRIMARY KEY,
+    `version`       INTEGER      NOT NULL CHECK (`version` > 0)
+);
+
+CREATE TABLE events (
+    event_stream_id VARCHAR(255) NOT NULL,
+    `type`          VARCHAR(255) NOT NULL,
+    `version`       INTEGER      NOT NULL CHECK (`version` > 0),
+    `event`         LONGBLOB     NOT NULL,
+    metadata        JSON,
+    -- Global position of each Domain Event, in insertion order.
+    `sequence`      BIGINT       NOT NULL AUTO_INCREMENT UNIQUE,
+
+    PRIMARY KEY (event_stream_id, `version`),
+    FOREIGN KEY (event_stream_id) REFERENCES event_streams (event_stream_id) ON DELETE CASCADE
+);
@@ -0,0 +1,8 @@
+CREATE TABLE aggregates (
+    aggregate_id VARCHAR(255) NOT NULL PRIMARY KEY,
+    `type`       VARCHAR(255) NOT NULL,
+    `version`    INTEGER      NOT NULL CHECK (`version` > 0),
+    `state`      LONGBLOB     NOT NULL,
+
+    FOREIGN KEY (aggregate_id) REFERENCES event_streams (event_stream_id) ON DELETE CASCADE
+);
@@ -0,0 +1,7 @@
+-- Single row locked while inserting Domain Events, so that their sequence
+-- numbers are committed in the same order they are assigned.
+CREATE TABLE events_sequence_lock (
+    id TINYINT NOT NULL PRIMARY KEY
+);
+
+INSERT INTO events_sequence_lock (id) VALUES (1);
@@ -0,0 +1,251 @@
+//! This module contains the implementation of the [`eventually::aggregate::Repository`] trait,
+//! to work specifically with `MySQL` and `MariaDB` databases.
+//!
+//! Check out the [Repository] type for more information.
+
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use eventually::aggregate::{Aggregate, IdSerde};
+use eventually::clock::{Clock, SystemClock};
+use eventually::version::Version;
+use eventually::{aggregate, serde, version};
+use sqlx::{MySql, MySqlPool, Row};
+
+/// Implements the [`eventually::aggregate::Repository`] trait for
+/// `MySQL` and `MariaDB` databases.
+#[derive(Debug, Clone)]
+pub struct Repository<T, Serde, EvtSerde>
+where
+    T: Aggregate,
+    <T as Aggregate>::Id: IdSerde,
+    Serde: serde::Serde<T>,
+    EvtSerde: serde::Serde<T::Event>,
+{
+    pool: MySqlPool,
+    aggregate_serde: Serde,
+    event_serde: EvtSerde,
+    clock: Arc<dyn Clock>,
+    t: PhantomData<T>,
+}
+
+impl<T, Serde, EvtSerde> Repository<T, Serde, EvtSerde>
+where
+    T: Aggregate,
+    <T as Aggregate>::Id: IdSerde,
+    Serde: serde::Serde<T>,
+    EvtSerde: serde::Serde<T::Event>,
+{
+    /// Runs the latest migrations necessary for the implementation to work,
+    /// then returns a new [`Repository`] instance.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the migrations fail to run.
+    pub async fn new(
+        pool: MySqlPool,
+        aggregate_serde: Serde,
+        event_serde: EvtSerde,
+    ) -> Result<Self, sqlx::migrate::MigrateError> {
+        // Make sure the latest migrations are used before using the Repository instance.
+        crate::MIGRATIONS.run(&pool).await?;
+
+        Ok(Self {
+            pool,
+            aggregate_serde,
+            event_serde,
+            clock: Arc::new(SystemClock),
+            t: PhantomData,
+        })
+    }
+
+    /// Uses the specified [Clock] to stamp the appended Domain Events
+    /// in the [`eventually::event::RECORDED_AT_KEY`] metadata entry, instead of the system time.
+    #[must_use]
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+}
+
+impl<T, Serde, EvtSerde> Repository<T, Serde, EvtSerde>
+where
+    T: Aggregate + Send + Sync,
+    <T as Aggregate>::Id: IdSerde,
+    Serde: serde::Serde<T> + Send + Sync,
+    EvtSerde: serde::Serde<T::Event> + Send + Sync,
+{
+    async fn save_aggregate_state(
+        &self,
+        tx: &mut sqlx::Transaction<'_, MySql>,
+        aggregate_id: &str,
+        expected_version: Version,
+        root: &mut aggregate::Root<T>,
+    ) -> Result<(), aggregate::repository::SaveError> {
+        let out_state = root.to_aggregate_type::<T>();
+        let bytes_state = self
+            .aggregate_serde
+            .serialize(out_state)
+            .map_err(|err| anyhow!("failed to serialize aggregate root state: {err}"))?;
+
+        // NOTE: the row lock is held until the end of the transaction,
+        // so that concurrent saves of the same Aggregate Root are serialized.
+        let current_version: Option<i32> = sqlx::query_scalar(
+            "SELECT `version` FROM aggregates WHERE aggregate_id = ? FOR UPDATE",
+        )
+        .bind(aggregate_id)
+        .fetch_optional(&mut **tx)
+        .await
+        .map_err(|err| anyhow!("failed to fetch the current aggregate version: {err}"))?;
+
+        #[allow(clippy::cast_sign_loss)]
+        let current_version = current_version.map_or(0, |v| v as Version);
+
+        if current_version != expected_version {
+            return Err(version::ConflictError {
+                expected: expected_version,
+                actual: current_version,
+            }
+            .into());
+        }
+
+        let map_err = |err: sqlx::Error| -> aggregate::repository::SaveError {
+            if crate::is_concurrent_write_error(&err) {
+                return version::ConflictError {
+                    expected: expected_version,
+                    actual: root.version(),
+                }
+                .into();
+            }
+
+            anyhow!("failed to save aggregate state: {err}").into()
+        };
+
+        // An Aggregate Root is also an Event Stream.
+        #[allow(clippy::cast_possible_truncation)]
+        sqlx::query(
+            "INSERT INTO event_streams (event_stream_id, `version`) VALUES (?, ?)
+             ON DUPLICATE KEY UPDATE `version` = VALUES(`version`)",
+        )
+        .bind(aggregate_id)
+        .bind(root.version() as i32)
+        .execute(&mut **tx)
+        .await
+        .map_err(map_err)?;
+
+        #[allow(clippy::cast_possible_truncation)]
+        sqlx::query(
+            "INSERT INTO aggregates (aggregate_id, `type`, `version`, `state`) VALUES (?, ?, ?, ?)
+             ON DUPLICATE KEY UPDATE `version` = VALUES(`version`), `state` = VALUES(`state`)",
+        )
+        .bind(aggregate_id)
+        .bind(T::type_name())
+        .bind(root.version() as i32)
+        .bind(bytes_state)
+        .execute(&mut **tx)
+        .await
+        .map_err(map_err)?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<T, Serde, EvtSerde> aggregate::repository::Getter<T> for Repository<T, Serde, EvtSerde>
+where
+    T: Aggregate + Send + Sync,
+    <T as Aggregate>::Id: IdSerde,
+    Serde: serde::Serde<T> + Send + Sync,
+    EvtSerde: serde::Serde<T::Event> + Send + Sync,
+{
+    async fn get(&self, id: &T::Id) -> Result<aggregate::Root<T>, aggregate::repository::GetError> {
+        let aggregate_id = id.encode_id();
+
+        let row = sqlx::query(
+            "SELECT `version`, `state`
+             FROM aggregates
+             WHERE aggregate_id = ? AND `type` = ?",
+        )
+        .bind(&aggregate_id)
+        .bind(T::type_name())
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|err| match err {
+            sqlx::Error::RowNotFound => aggregate::repository::GetError::NotFound,
+            _ => anyhow!("failed to fetch the aggregate state row: {err}").into(),
+        })?;
+
+        let version: i32 = row
+            .try_get("version")
+            .map_err(|err| anyhow!("failed to get 'version' column from row: {err}"))?;
+
+        let bytes_state: Vec<u8> = row
+            .try_get("state")
+            .map_err(|err| anyhow!("failed to get 'state' column from row: {err}"))?;
+
+        let aggregate: T = self
+            .aggregate_serde
+            .deserialize(&bytes_state)
+            .map_err(|err| {
+                anyhow!("failed to deserialize the aggregate state from the database row: {err}")
+            })?;
+
+        #[allow(clippy::cast_sign_loss)]
+        Ok(aggregate::Root::rehydrate_from_state(
+            version as Version,
+            aggregate,
+        ))
+    }
+}
+
+#[async_trait]
+impl<T, Serde, EvtSerde> aggregate::repository::Saver<T> for Repository<T, Serde, EvtSerde>
+where
+    T: Aggregate + Send + Sync,
+    <T as Aggregate>::Id: IdSerde,
+    Serde: serde::Serde<T> + Send + Sync,
+    EvtSerde: serde::Serde<T::Event> + Send + Sync,
+{
+    async fn save(
+        &self,
+        root: &mut aggregate::Root<T>,
+    ) -> Result<(), aggregate::repository::SaveError> {
+        let events_to_commit = root.take_uncommitted_events();
+
+        if events_to_commit.is_empty() {
+            return Ok(());
+        }
+
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|err| anyhow!("failed to begin transaction: {err}"))?;
+
+        let aggregate_id = root.aggregate_id().encode_id();
+        let expected_root_version = root.version() - (events_to_commit.len() as Version);
+
+        self.save_aggregate_state(&mut tx, &aggregate_id, expected_root_version, root)
+            .await?;
+
+        #[allow(clippy::cast_possible_truncation)]
+        crate::event::append_domain_events(
+            &mut tx,
+            &self.event_serde,
+            self.clock.now(),
+            &aggregate_id,
+            root.version() as i32,
+            events_to_commit,
+        )
+        .await
+        .map_err(|err| anyhow!("failed to append aggregate root domain events: {err}"))?;
+
+        tx.commit()
+            .await
+            .map_err(|err| anyhow!("failed to commit transaction: {err}"))?;
+
+        Ok(())
+    }
+}
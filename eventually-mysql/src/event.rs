@@ -0,0 +1,492 @@
+//! This module contains the implementation of the [`eventually::event::Store`] trait,
+//! to work specifically with `MySQL` and `MariaDB` databases.
+//!
+//! Check out the [Store] type for more information.
+
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use eventually::aggregate::IdSerde;
+use eventually::clock::{Clock, SystemClock};
+use eventually::message::{Message, Metadata};
+use eventually::version::Version;
+use eventually::{event, serde, version};
+use futures::future::ready;
+use futures::{stream, StreamExt, TryStreamExt};
+use sqlx::mysql::MySqlRow;
+use sqlx::{MySql, MySqlPool, Row, Transaction};
+
+/// All possible errors returned by [`Store`] while streaming Domain Events
+/// through [`event::store::Streamer::stream`].
+#[derive(Debug, thiserror::Error)]
+pub enum StreamError {
+    /// Error returned when the Domain Event payload could not be deserialized.
+    #[error("failed to deserialize event from database: {0}")]
+    DeserializeEvent(#[source] anyhow::Error),
+    /// Error returned when a column could not be read from the result row.
+    #[error("failed to get column '{name}' from result row: {error}")]
+    ReadColumn {
+        /// The name of the column that could not be read.
+        name: &'static str,
+        /// The underlying database error.
+        #[source]
+        error: sqlx::Error,
+    },
+    /// Error returned when the Event Stream id could not be converted
+    /// into the Event Stream id type used by the Event Store.
+    #[error("failed to parse event stream id '{id}': {error}")]
+    ParseStreamId {
+        /// The Event Stream id, as stored in the database.
+        id: String,
+        /// The underlying conversion error.
+        #[source]
+        error: anyhow::Error,
+    },
+    /// Error returned when the database returned an error.
+    #[error("db returned an error: {0}")]
+    Database(#[source] sqlx::Error),
+}
+
+pub(crate) async fn append_domain_events<Evt>(
+    tx: &mut Transaction<'_, MySql>,
+    serde: &impl serde::Serializer<Evt>,
+    recorded_at: DateTime<Utc>,
+    event_stream_id: &str,
+    new_version: i32,
+    events: Vec<event::Envelope<Evt>>,
+) -> anyhow::Result<()>
+where
+    Evt: Message,
+{
+    #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+    let current_event_stream_version = new_version - (events.len() as i32);
+
+    // NOTE: the sequence numbers are assigned on insert, so the inserts are
+    // serialized until their transaction ends, for the sequence numbers
+    // to be committed in order.
+    sqlx::query("SELECT id FROM events_sequence_lock WHERE id = 1 FOR UPDATE")
+        .execute(&mut **tx)
+        .await?;
+
+    for (i, event) in events.into_iter().enumerate() {
+        #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+        let event_version = current_event_stream_version + (i as i32) + 1;
+        let event_type = event.message.name();
+        let mut metadata = event.metadata;
+
+        metadata.insert(event::RECORDED_AT_KEY.to_owned(), recorded_at.to_rfc3339());
+        metadata.insert(
+            "Recorded-With-New-Version".to_owned(),
+            new_version.to_string(),
+        );
+
+        let serialized_event = serde
+            .serialize(event.message)
+            .map_err(|err| anyhow!("failed to serialize event message: {err}"))?;
+
+        sqlx::query(
+            "INSERT INTO events (event_stream_id, `type`, `version`, `event`, metadata) VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(event_stream_id)
+        .bind(event_type)
+        .bind(event_version)
+        .bind(serialized_event)
+        .bind(sqlx::types::Json(metadata))
+        .execute(&mut **tx)
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// Default number of Domain Events fetched from the database per page
+/// by [`event::store::Streamer::stream`].
+pub const DEFAULT_STREAM_PAGE_SIZE: u32 = 1000;
+
+/// Implements the [`eventually::event::Store`] trait for
+/// `MySQL` and `MariaDB` databases.
+///
+/// Version checks are performed by locking the Event Stream row
+/// with `SELECT ... FOR UPDATE`, so that concurrent appends to the same
+/// Event Stream are serialized and the losing ones fail with a conflict error.
+#[derive(Debug, Clone)]
+pub struct Store<Id, Evt, Serde>
+where
+    Id: IdSerde + Clone,
+    Serde: serde::Serde<Evt>,
+{
+    pool: MySqlPool,
+    serde: Serde,
+    stream_page_size: u32,
+    clock: Arc<dyn Clock>,
+    id_type: PhantomData<Id>,
+    evt_type: PhantomData<Evt>,
+}
+
+impl<Id, Evt, Serde> Store<Id, Evt, Serde>
+where
+    Id: IdSerde + Clone,
+    Serde: serde::Serde<Evt>,
+{
+    /// Runs the latest migrations necessary for the implementation to work,
+    /// then returns a new [`Store`] instance.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the migrations fail to run.
+    pub async fn new(pool: MySqlPool, serde: Serde) -> Result<Self, sqlx::migrate::MigrateError> {
+        // Make sure the latest migrations are used before using the Store instance.
+        crate::MIGRATIONS.run(&pool).await?;
+
+        Ok(Self {
+            pool,
+            serde,
+            stream_page_size: DEFAULT_STREAM_PAGE_SIZE,
+            clock: Arc::new(SystemClock),
+            id_type: PhantomData,
+            evt_type: PhantomData,
+        })
+    }
+
+    /// Sets the maximum number of Domain Events fetched from the database
+    /// in a single query while streaming an Event Stream.
+    ///
+    /// Defaults to [`DEFAULT_STREAM_PAGE_SIZE`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the page size is zero.
+    #[must_use]
+    pub fn with_stream_page_size(mut self, page_size: u32) -> Self {
+        assert!(page_size > 0, "stream page size must be greater than zero");
+        self.stream_page_size = page_size;
+        self
+    }
+
+    /// Uses the specified [Clock] to stamp the appended Domain Events
+    /// in the [`event::RECORDED_AT_KEY`] metadata entry, instead of the system time.
+    #[must_use]
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+}
+
+fn try_get_column<T>(row: &MySqlRow, name: &'static str) -> Result<T, StreamError>
+where
+    for<'a> T: sqlx::Type<MySql> + sqlx::Decode<'a, MySql>,
+{
+    row.try_get(name)
+        .map_err(|err| StreamError::ReadColumn { name, error: err })
+}
+
+fn event_row_to_persisted_event<Id, Evt>(
+    serde: &impl serde::Deserializer<Evt>,
+    stream_id: Id,
+    row: &MySqlRow,
+) -> Result<event::Persisted<Id, Evt>, StreamError>
+where
+    Evt: Message,
+{
+    let version_column: i32 = try_get_column(row, "version")?;
+    let event_column: Vec<u8> = try_get_column(row, "event")?;
+    let metadata_column: Option<sqlx::types::Json<Metadata>> = try_get_column(row, "metadata")?;
+
+    #[allow(clippy::cast_sign_loss)]
+    Ok(event::Persisted {
+        stream_id,
+        version: version_column as Version,
+        event: event::Envelope {
+            message: serde
+                .deserialize(&event_column)
+                .map_err(StreamError::DeserializeEvent)?,
+            metadata: metadata_column.map(|json| json.0).unwrap_or_default(),
+        },
+    })
+}
+
+fn event_row_to_sequenced_event<Id, Evt>(
+    serde: &impl serde::Deserializer<Evt>,
+    row: &MySqlRow,
+) -> Result<event::Sequenced<Id, Evt>, StreamError>
+where
+    Id: IdSerde,
+    Evt: Message,
+{
+    let stream_id: String = try_get_column(row, "event_stream_id")?;
+    let stream_id = Id::decode_id(&stream_id).map_err(|error| StreamError::ParseStreamId {
+        id: stream_id,
+        error,
+    })?;
+    let sequence_column: i64 = try_get_column(row, "sequence")?;
+
+    #[allow(clippy::cast_sign_loss)]
+    Ok(event::Sequenced {
+        sequence: sequence_column as event::Sequence,
+        event: event_row_to_persisted_event(serde, stream_id, row)?,
+    })
+}
+
+impl<Id, Evt, Serde> event::store::Streamer<Id, Evt> for Store<Id, Evt, Serde>
+where
+    Id: IdSerde + Clone + Send + Sync,
+    Evt: Message + Send + Sync,
+    Serde: serde::Serde<Evt> + Send + Sync,
+{
+    type Error = StreamError;
+
+    fn stream(
+        &self,
+        id: &Id,
+        select: event::VersionSelect,
+    ) -> event::Stream<'_, Id, Evt, Self::Error> {
+        #[allow(clippy::cast_possible_truncation)]
+        let from_version: i32 = match select {
+            event::VersionSelect::All => 0,
+            event::VersionSelect::From(v) => v as i32,
+        };
+
+        let id = id.clone();
+        let string_id = id.encode_id();
+        let page_size = i64::from(self.stream_page_size);
+
+        // NOTE: the Event Stream is paginated using the version as keyset,
+        // the state keeps track of the next version to fetch, if any.
+        stream::try_unfold(Some(from_version), move |next_version| {
+            let string_id = string_id.clone();
+
+            async move {
+                let Some(next_version) = next_version else {
+                    return Ok(None);
+                };
+
+                let rows = sqlx::query(
+                    "SELECT `version`, `event`, metadata
+                     FROM events
+                     WHERE event_stream_id = ? AND `version` >= ?
+                     ORDER BY `version`
+                     LIMIT ?",
+                )
+                .bind(string_id)
+                .bind(next_version)
+                .bind(page_size)
+                .fetch_all(&self.pool)
+                .await
+                .map_err(StreamError::Database)?;
+
+                let last_version = match rows.last() {
+                    None => return Ok(None),
+                    Some(row) => try_get_column::<i32>(row, "version")?,
+                };
+
+                #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+                let has_next_page = rows.len() as i64 == page_size;
+
+                Ok(Some((rows, has_next_page.then_some(last_version + 1))))
+            }
+        })
+        .map_ok(|rows| stream::iter(rows.into_iter().map(Ok)))
+        .try_flatten()
+        .and_then(move |row| ready(event_row_to_persisted_event(&self.serde, id.clone(), &row)))
+        .boxed()
+    }
+}
+
+impl<Id, Evt, Serde> event::store::GlobalStreamer<Id, Evt> for Store<Id, Evt, Serde>
+where
+    Id: IdSerde + Clone + Send + Sync,
+    Evt: Message + Send + Sync,
+    Serde: serde::Serde<Evt> + Send + Sync,
+{
+    type Error = StreamError;
+
+    /// Streams all the Domain Events in the `events` table, paginated by
+    /// [Sequence][event::Sequence] number.
+    ///
+    /// Sequence numbers are assigned in commit order, as Domain Events are inserted
+    /// holding the lock on the `events_sequence_lock` row: once a Domain Event is visible,
+    /// no Domain Event with a lower sequence number can be committed anymore.
+    fn stream_all(
+        &self,
+        select: event::SequenceSelect,
+    ) -> event::SequencedStream<'_, Id, Evt, Self::Error> {
+        #[allow(clippy::cast_possible_wrap)]
+        let from_sequence: i64 = match select {
+            event::SequenceSelect::All => 0,
+            event::SequenceSelect::From(s) => s as i64,
+        };
+
+        let page_size = i64::from(self.stream_page_size);
+
+        stream::try_unfold(Some(from_sequence), move |next_sequence| async move {
+            let Some(next_sequence) = next_sequence else {
+                return Ok(None);
+            };
+
+            let rows = sqlx::query(
+                "SELECT event_stream_id, `version`, `event`, metadata, `sequence`
+                 FROM events
+                 WHERE `sequence` >= ?
+                 ORDER BY `sequence`
+                 LIMIT ?",
+            )
+            .bind(next_sequence)
+            .bind(page_size)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(StreamError::Database)?;
+
+            let last_sequence = match rows.last() {
+                None => return Ok(None),
+                Some(row) => try_get_column::<i64>(row, "sequence")?,
+            };
+
+            #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+            let has_next_page = rows.len() as i64 == page_size;
+
+            Ok(Some((rows, has_next_page.then_some(last_sequence + 1))))
+        })
+        .map_ok(|rows| stream::iter(rows.into_iter().map(Ok)))
+        .try_flatten()
+        .and_then(move |row| ready(event_row_to_sequenced_event(&self.serde, &row)))
+        .boxed()
+    }
+}
+
+impl<Id, Evt, Serde> Store<Id, Evt, Serde>
+where
+    Id: IdSerde + Clone + Send + Sync,
+    Evt: Message + Send + Sync,
+    Serde: serde::Serde<Evt> + Send + Sync,
+{
+    async fn append_to_event_stream(
+        &self,
+        tx: &mut Transaction<'_, MySql>,
+        id: Id,
+        version_check: version::Check,
+        events: Vec<event::Envelope<Evt>>,
+    ) -> Result<Version, event::store::AppendError> {
+        let string_id = id.encode_id();
+
+        // NOTE: the row lock is held until the end of the transaction,
+        // so that concurrent appends to the same Event Stream are serialized.
+        let current_version: Option<i32> = sqlx::query_scalar(
+            "SELECT `version` FROM event_streams WHERE event_stream_id = ? FOR UPDATE",
+        )
+        .bind(&string_id)
+        .fetch_optional(&mut **tx)
+        .await
+        .map_err(|err| anyhow!("failed to fetch the current event stream version: {err}"))?;
+
+        #[allow(clippy::cast_sign_loss)]
+        let current_version = current_version.map_or(0, |v| v as Version);
+        let new_version = current_version + (events.len() as Version);
+
+        if let version::Check::MustBe(expected) = version_check {
+            if current_version != expected {
+                return Err(event::store::AppendError::Conflict(
+                    version::ConflictError {
+                        expected,
+                        actual: current_version,
+                    },
+                ));
+            }
+        }
+
+        #[allow(clippy::cast_possible_truncation)]
+        sqlx::query(
+            "INSERT INTO event_streams (event_stream_id, `version`) VALUES (?, ?)
+             ON DUPLICATE KEY UPDATE `version` = VALUES(`version`)",
+        )
+        .bind(&string_id)
+        .bind(new_version as i32)
+        .execute(&mut **tx)
+        .await
+        .map_err(|err| match version_check {
+            version::Check::MustBe(expected) if crate::is_concurrent_write_error(&err) => {
+                event::store::AppendError::Conflict(version::ConflictError {
+                    expected,
+                    actual: new_version,
+                })
+            },
+            _ => event::store::AppendError::Internal(anyhow!(
+                "failed to upsert new event stream version: {err}"
+            )),
+        })?;
+
+        #[allow(clippy::cast_possible_truncation)]
+        append_domain_events(
+            tx,
+            &self.serde,
+            self.clock.now(),
+            &string_id,
+            new_version as i32,
+            events,
+        )
+        .await
+        .map_err(|err| anyhow!("failed to append new domain events: {err}"))?;
+
+        Ok(new_version)
+    }
+}
+
+#[async_trait]
+impl<Id, Evt, Serde> event::store::Appender<Id, Evt> for Store<Id, Evt, Serde>
+where
+    Id: IdSerde + Clone + Send + Sync,
+    Evt: Message + Send + Sync,
+    Serde: serde::Serde<Evt> + Send + Sync,
+{
+    async fn append(
+        &self,
+        id: Id,
+        version_check: version::Check,
+        events: Vec<event::Envelope<Evt>>,
+    ) -> Result<Version, event::store::AppendError> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|err| anyhow!("failed to begin transaction: {err}"))?;
+
+        let new_version = self
+            .append_to_event_stream(&mut tx, id, version_check, events)
+            .await?;
+
+        tx.commit()
+            .await
+            .map_err(|err| anyhow!("failed to commit transaction: {err}"))?;
+
+        Ok(new_version)
+    }
+
+    async fn append_multi(
+        &self,
+        appends: Vec<event::store::StreamAppend<Id, Evt>>,
+    ) -> Result<Vec<Version>, event::store::AppendError> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|err| anyhow!("failed to begin transaction: {err}"))?;
+
+        let mut new_versions = Vec::with_capacity(appends.len());
+
+        for append in appends {
+            let new_version = self
+                .append_to_event_stream(&mut tx, append.id, append.version_check, append.events)
+                .await?;
+
+            new_versions.push(new_version);
+        }
+
+        tx.commit()
+            .await
+            .map_err(|err| anyhow!("failed to commit transaction: {err}"))?;
+
+        Ok(new_versions)
+    }
+}
@@ -0,0 +1,36 @@
+//! `eventually-mysql` contains different implementations of traits
+//! from the [eventually] crate that are specific for `MySQL` and `MariaDB` databases.
+//!
+//! Check out the [`aggregate::Repository`] and [`event::Store`] implementations
+//! to know more.
+
+#![deny(unsafe_code, unused_qualifications, trivial_casts)]
+#![deny(clippy::all, clippy::pedantic, clippy::cargo)]
+#![allow(clippy::multiple_crate_versions)]
+#![warn(missing_docs)]
+
+pub mod aggregate;
+pub mod event;
+
+pub(crate) static MIGRATIONS: sqlx::migrate::Migrator = sqlx::migrate!("./migrations");
+
+use sqlx::mysql::MySqlDatabaseError;
+
+/// Error number returned by `MySQL` when a transaction has been chosen
+/// as the victim of a deadlock, and has been rolled back.
+const ER_LOCK_DEADLOCK: u16 = 1213;
+
+/// Error number returned by `MySQL` when inserting a row with a duplicated key.
+const ER_DUP_ENTRY: u16 = 1062;
+
+/// Returns true if the error has been caused by a concurrent transaction
+/// writing the same rows, i.e. the same Event Stream or Aggregate Root.
+///
+/// Two transactions creating the same Event Stream would either deadlock
+/// on the gap locks taken while checking the current version,
+/// or fail to insert the same primary key.
+pub(crate) fn is_concurrent_write_error(err: &sqlx::Error) -> bool {
+    err.as_database_error()
+        .and_then(|err| err.try_downcast_ref::<MySqlDatabaseError>())
+        .is_some_and(|err| matches!(err.number(), ER_LOCK_DEADLOCK | ER_DUP_ENTRY))
+}
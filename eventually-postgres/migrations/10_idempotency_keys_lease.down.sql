@@ -0,0 +1,5 @@
+DELETE FROM idempotency_keys WHERE completed_at IS NULL;
+
+ALTER TABLE idempotency_keys
+    DROP COLUMN lease_expires_at,
+    DROP COLUMN completed_at;
@@ -0,0 +1,8 @@
+-- Idempotency keys are reserved with a lease while their Command is being handled,
+-- and marked as completed once it has been handled successfully.
+ALTER TABLE idempotency_keys
+    ADD COLUMN lease_expires_at TIMESTAMPTZ,
+    ADD COLUMN completed_at     TIMESTAMPTZ;
+
+-- The keys recorded so far belong to Commands that have already been handled.
+UPDATE idempotency_keys SET completed_at = recorded_at;
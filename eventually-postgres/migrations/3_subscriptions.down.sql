@@ -0,0 +1,4 @@
+DROP TRIGGER events_notify_appended ON events;
+DROP FUNCTION notify_event_appended;
+DROP INDEX events_sequence_idx;
+ALTER TABLE events DROP COLUMN "sequence";
@@ -0,0 +1,26 @@
+-- Global position of each Domain Event, in insertion order.
+ALTER TABLE events ADD COLUMN "sequence" BIGINT GENERATED ALWAYS AS IDENTITY;
+
+CREATE UNIQUE INDEX events_sequence_idx ON events ("sequence");
+
+CREATE FUNCTION notify_event_appended()
+RETURNS TRIGGER
+LANGUAGE PLPGSQL
+AS $$
+BEGIN
+    -- Notifications are delivered on commit, in commit order.
+    -- The stream type is the type of the Aggregate owning the Event Stream, if any.
+    PERFORM pg_notify('eventually_events', json_build_object(
+        'event_stream_id', NEW.event_stream_id,
+        'version', NEW."version",
+        'sequence', NEW."sequence",
+        'stream_type', (SELECT a."type" FROM aggregates a WHERE a.aggregate_id = NEW.event_stream_id)
+    )::TEXT);
+
+    RETURN NEW;
+END;
+$$;
+
+CREATE TRIGGER events_notify_appended
+AFTER INSERT ON events
+FOR EACH ROW EXECUTE FUNCTION notify_event_appended();
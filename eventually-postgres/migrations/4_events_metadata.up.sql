@@ -0,0 +1,2 @@
+-- Supports filtering Domain Events by metadata entries, e.g. metadata @> '{"Tenant-Id": "..."}'.
+CREATE INDEX events_metadata_idx ON events USING GIN (metadata jsonb_path_ops);
@@ -0,0 +1,8 @@
+-- Keys of the Commands processed by an idempotent command handler, used to detect duplicates.
+CREATE TABLE idempotency_keys (
+    command_name TEXT        NOT NULL,
+    "key"        TEXT        NOT NULL,
+    recorded_at  TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    PRIMARY KEY (command_name, "key")
+);
@@ -0,0 +1,10 @@
+-- Commands scheduled to be handled at a future time, claimed by the command scheduler pollers.
+CREATE TABLE scheduled_commands (
+    id       BIGSERIAL   NOT NULL PRIMARY KEY,
+    "name"   TEXT        NOT NULL,
+    payload  BYTEA       NOT NULL,
+    metadata JSONB       NOT NULL,
+    run_at   TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX scheduled_commands_run_at_idx ON scheduled_commands (run_at);
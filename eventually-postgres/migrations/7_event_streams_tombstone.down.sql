@@ -0,0 +1,3 @@
+DROP TRIGGER event_streams_tombstone ON event_streams;
+DROP FUNCTION reject_tombstoned_event_stream_update;
+ALTER TABLE event_streams DROP COLUMN deleted_at;
@@ -0,0 +1,18 @@
+-- Tombstoned Event Streams, e.g. of deleted Aggregate Roots, keep their Domain Events
+-- but reject any new one.
+ALTER TABLE event_streams ADD COLUMN deleted_at TIMESTAMPTZ;
+
+CREATE FUNCTION reject_tombstoned_event_stream_update()
+RETURNS TRIGGER
+LANGUAGE PLPGSQL
+AS $$
+BEGIN
+    RAISE EXCEPTION 'event stream has been deleted: %', OLD.event_stream_id;
+END;
+$$;
+
+CREATE TRIGGER event_streams_tombstone
+BEFORE UPDATE OF "version" ON event_streams
+FOR EACH ROW
+WHEN (OLD.deleted_at IS NOT NULL)
+EXECUTE FUNCTION reject_tombstoned_event_stream_update();
@@ -0,0 +1,7 @@
+-- Sequence number of the last Domain Event applied to each read model,
+-- updated in the same transaction as the read model itself.
+CREATE TABLE projection_checkpoints (
+    projection TEXT        PRIMARY KEY,
+    "sequence" BIGINT      NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,2 @@
+DELETE FROM projection_checkpoints WHERE "sequence" IS NULL;
+ALTER TABLE projection_checkpoints ALTER COLUMN "sequence" SET NOT NULL;
@@ -0,0 +1,4 @@
+-- The checkpoint row of a read model is created before applying its first
+-- Domain Events, so that concurrent Projectors can lock it: a NULL sequence
+-- number means that no Domain Event has been applied yet.
+ALTER TABLE projection_checkpoints ALTER COLUMN "sequence" DROP NOT NULL;
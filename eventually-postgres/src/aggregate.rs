@@ -4,34 +4,40 @@
 //! Check out the [Repository] type for more information.
 
 use std::marker::PhantomData;
+use std::sync::Arc;
 
 use anyhow::anyhow;
 use async_trait::async_trait;
-use eventually::aggregate::Aggregate;
+use eventually::aggregate::{Aggregate, IdSerde};
+use eventually::clock::{Clock, SystemClock};
 use eventually::version::Version;
-use eventually::{aggregate, serde, version};
+use eventually::{aggregate, event, health, serde, version};
 use sqlx::{PgPool, Postgres, Row};
 
+use crate::pool::{ConnectError, PoolConfig};
+use crate::unit_of_work::UnitOfWork;
+
 /// Implements the [`eventually::aggregate::Repository`] trait for
 /// `PostgreSQL` databases.
 #[derive(Debug, Clone)]
 pub struct Repository<T, Serde, EvtSerde>
 where
     T: Aggregate,
-    <T as Aggregate>::Id: ToString,
+    <T as Aggregate>::Id: IdSerde,
     Serde: serde::Serde<T>,
     EvtSerde: serde::Serde<T::Event>,
 {
     pool: PgPool,
     aggregate_serde: Serde,
     event_serde: EvtSerde,
+    clock: Arc<dyn Clock>,
     t: PhantomData<T>,
 }
 
 impl<T, Serde, EvtSerde> Repository<T, Serde, EvtSerde>
 where
     T: Aggregate,
-    <T as Aggregate>::Id: ToString,
+    <T as Aggregate>::Id: IdSerde,
     Serde: serde::Serde<T>,
     EvtSerde: serde::Serde<T::Event>,
 {
@@ -53,18 +59,101 @@ where
             pool,
             aggregate_serde,
             event_serde,
+            clock: Arc::new(SystemClock),
             t: PhantomData,
         })
     }
+
+    /// Connects to the database at the specified url with a new pool,
+    /// tuned through the specified [`PoolConfig`], then returns a new [`Repository`]
+    /// instance as [`Repository::new`].
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the database is not reachable,
+    /// or the migrations fail to run.
+    pub async fn connect(
+        url: &str,
+        config: &PoolConfig,
+        aggregate_serde: Serde,
+        event_serde: EvtSerde,
+    ) -> Result<Self, ConnectError> {
+        let pool = config.connect(url).await.map_err(ConnectError::Connect)?;
+
+        Ok(Self::new(pool, aggregate_serde, event_serde).await?)
+    }
+
+    /// Uses the specified [Clock] to stamp the appended Domain Events
+    /// in the [`eventually::event::RECORDED_AT_KEY`] metadata entry, instead of the system time.
+    #[must_use]
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
 }
 
 impl<T, Serde, EvtSerde> Repository<T, Serde, EvtSerde>
 where
     T: Aggregate + Send + Sync,
-    <T as Aggregate>::Id: ToString,
+    <T as Aggregate>::Id: IdSerde,
     Serde: serde::Serde<T> + Send + Sync,
     EvtSerde: serde::Serde<T::Event> + Send + Sync,
 {
+    /// Saves the Aggregate Root state and appends its uncommitted Domain Events
+    /// as part of the [`UnitOfWork`], like [`aggregate::repository::Saver::save`] does.
+    ///
+    /// The changes are persisted only once the [`UnitOfWork`] is committed.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the Aggregate Root has been concurrently modified,
+    /// or if its state or Domain Events could not be saved.
+    pub async fn save_in(
+        &self,
+        unit_of_work: &mut UnitOfWork<'_>,
+        root: &mut aggregate::Root<T>,
+    ) -> Result<(), aggregate::repository::SaveError> {
+        let events_to_commit = root.take_uncommitted_events();
+
+        if events_to_commit.is_empty() {
+            return Ok(());
+        }
+
+        self.save_events_in(unit_of_work, root, events_to_commit)
+            .await
+    }
+
+    async fn save_events_in(
+        &self,
+        unit_of_work: &mut UnitOfWork<'_>,
+        root: &mut aggregate::Root<T>,
+        events_to_commit: Vec<event::Envelope<T::Event>>,
+    ) -> Result<(), aggregate::repository::SaveError> {
+        let tx = unit_of_work.transaction();
+        let aggregate_id = root.aggregate_id().encode_id();
+        let expected_root_version = root.version() - (events_to_commit.len() as Version);
+
+        self.save_aggregate_state(tx, &aggregate_id, expected_root_version, root)
+            .await?;
+
+        #[allow(clippy::cast_possible_truncation)]
+        crate::event::append_domain_events(
+            tx,
+            &self.event_serde,
+            crate::event::AppendOptions {
+                envelope_schema_version: None,
+                recorded_at: self.clock.now(),
+            },
+            &aggregate_id,
+            root.version() as i32,
+            events_to_commit,
+        )
+        .await
+        .map_err(|err| anyhow!("failed to append aggregate root domain events: {err}"))?;
+
+        Ok(())
+    }
+
     async fn save_aggregate_state(
         &self,
         tx: &mut sqlx::Transaction<'_, Postgres>,
@@ -76,7 +165,7 @@ where
         let bytes_state = self
             .aggregate_serde
             .serialize(out_state)
-            .map_err(|err| anyhow!("failed to serialize aggregate root state: {}", err))?;
+            .map_err(|err| anyhow!("failed to serialize aggregate root state: {err}"))?;
 
         #[allow(clippy::cast_possible_truncation)]
         sqlx::query("CALL upsert_aggregate($1, $2, $3, $4, $5)")
@@ -98,7 +187,7 @@ where
                         actual: root.version(),
                     }
                     .into(),
-                    _ => anyhow!("failed to save aggregate state: {}", err).into(),
+                    _ => anyhow!("failed to save aggregate state: {err}").into(),
                 },
             })?;
 
@@ -110,12 +199,12 @@ where
 impl<T, Serde, EvtSerde> aggregate::repository::Getter<T> for Repository<T, Serde, EvtSerde>
 where
     T: Aggregate + Send + Sync,
-    <T as Aggregate>::Id: ToString,
+    <T as Aggregate>::Id: IdSerde,
     Serde: serde::Serde<T> + Send + Sync,
     EvtSerde: serde::Serde<T::Event> + Send + Sync,
 {
     async fn get(&self, id: &T::Id) -> Result<aggregate::Root<T>, aggregate::repository::GetError> {
-        let aggregate_id = id.to_string();
+        let aggregate_id = id.encode_id();
 
         let row = sqlx::query(
             r#"SELECT version, state
@@ -128,25 +217,22 @@ where
         .await
         .map_err(|err| match err {
             sqlx::Error::RowNotFound => aggregate::repository::GetError::NotFound,
-            _ => anyhow!("failed to fetch the aggregate state row: {}", err).into(),
+            _ => anyhow!("failed to fetch the aggregate state row: {err}").into(),
         })?;
 
         let version: i32 = row
             .try_get("version")
-            .map_err(|err| anyhow!("failed to get 'version' column from row: {}", err))?;
+            .map_err(|err| anyhow!("failed to get 'version' column from row: {err}"))?;
 
         let bytes_state: Vec<u8> = row
             .try_get("state")
-            .map_err(|err| anyhow!("failed to get 'state' column from row: {}", err))?;
+            .map_err(|err| anyhow!("failed to get 'state' column from row: {err}"))?;
 
         let aggregate: T = self
             .aggregate_serde
             .deserialize(&bytes_state)
             .map_err(|err| {
-                anyhow!(
-                    "failed to deserialize the aggregate state from the database row: {}",
-                    err
-                )
+                anyhow!("failed to deserialize the aggregate state from the database row: {err}")
             })?;
 
         #[allow(clippy::cast_sign_loss)]
@@ -161,7 +247,7 @@ where
 impl<T, Serde, EvtSerde> aggregate::repository::Saver<T> for Repository<T, Serde, EvtSerde>
 where
     T: Aggregate + Send + Sync,
-    <T as Aggregate>::Id: ToString,
+    <T as Aggregate>::Id: IdSerde,
     Serde: serde::Serde<T> + Send + Sync,
     EvtSerde: serde::Serde<T::Event> + Send + Sync,
 {
@@ -175,38 +261,89 @@ where
             return Ok(());
         }
 
-        let mut tx = self
-            .pool
-            .begin()
+        let mut unit_of_work = UnitOfWork::begin(&self.pool)
             .await
-            .map_err(|err| anyhow!("failed to begin transaction: {}", err))?;
+            .map_err(|err| anyhow!("failed to begin transaction: {err}"))?;
+
+        self.save_events_in(&mut unit_of_work, root, events_to_commit)
+            .await?;
 
-        sqlx::query("SET TRANSACTION ISOLATION LEVEL SERIALIZABLE DEFERRABLE")
-            .execute(&mut *tx)
+        unit_of_work
+            .commit()
             .await
-            .map_err(|err| anyhow!("failed to begin transaction: {}", err))?;
+            .map_err(|err| anyhow!("failed to commit transaction: {err}"))?;
 
-        let aggregate_id = root.aggregate_id().to_string();
-        let expected_root_version = root.version() - (events_to_commit.len() as Version);
+        Ok(())
+    }
+}
 
-        self.save_aggregate_state(&mut tx, &aggregate_id, expected_root_version, root)
-            .await?;
+#[async_trait]
+impl<T, Serde, EvtSerde> aggregate::repository::Deleter<T> for Repository<T, Serde, EvtSerde>
+where
+    T: Aggregate + Send + Sync,
+    <T as Aggregate>::Id: IdSerde,
+    Serde: serde::Serde<T> + Send + Sync,
+    EvtSerde: serde::Serde<T::Event> + Send + Sync,
+{
+    /// Removes the Aggregate Root state from the `aggregates` table and tombstones
+    /// its Event Stream, keeping its Domain Events in the `events` table.
+    async fn delete(
+        &self,
+        root: aggregate::Root<T>,
+    ) -> Result<(), aggregate::repository::DeleteError> {
+        let aggregate_id = root.aggregate_id().encode_id();
+        let saved_version = root.version() - root.uncommitted_events().len() as Version;
 
-        #[allow(clippy::cast_possible_truncation)]
-        crate::event::append_domain_events(
-            &mut tx,
-            &self.event_serde,
+        let mut unit_of_work = UnitOfWork::begin(&self.pool)
+            .await
+            .map_err(|err| anyhow!("failed to begin transaction: {err}"))?;
+
+        let tx = unit_of_work.transaction();
+
+        // NOTE: the Event Stream of an Aggregate Root is always at the same version
+        // of its state, so its version check covers the state as well.
+        crate::event::tombstone_event_stream(
+            tx,
             &aggregate_id,
-            root.version() as i32,
-            events_to_commit,
+            version::Check::MustBe(saved_version),
+            self.clock.now(),
         )
         .await
-        .map_err(|err| anyhow!("failed to append aggregate root domain events: {}", err))?;
+        .map_err(|err| match err {
+            event::store::TombstoneError::Conflict(err) => {
+                aggregate::repository::DeleteError::Conflict(err)
+            },
+            event::store::TombstoneError::Internal(err) => {
+                aggregate::repository::DeleteError::Internal(err)
+            },
+        })?;
+
+        sqlx::query(r#"DELETE FROM aggregates WHERE aggregate_id = $1 AND "type" = $2"#)
+            .bind(&aggregate_id)
+            .bind(T::type_name())
+            .execute(&mut **tx)
+            .await
+            .map_err(|err| anyhow!("failed to delete aggregate state: {err}"))?;
 
-        tx.commit()
+        unit_of_work
+            .commit()
             .await
-            .map_err(|err| anyhow!("failed to commit transaction: {}", err))?;
+            .map_err(|err| anyhow!("failed to commit transaction: {err}"))?;
 
         Ok(())
     }
 }
+
+#[async_trait]
+impl<T, Serde, EvtSerde> health::Check for Repository<T, Serde, EvtSerde>
+where
+    T: Aggregate + Send + Sync,
+    <T as Aggregate>::Id: IdSerde,
+    Serde: serde::Serde<T> + Send + Sync,
+    EvtSerde: serde::Serde<T::Event> + Send + Sync,
+{
+    /// Verifies the database backing the [`Repository`] is reachable.
+    async fn check(&self) -> anyhow::Result<()> {
+        crate::ping(&self.pool).await
+    }
+}
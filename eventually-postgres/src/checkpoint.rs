@@ -0,0 +1,124 @@
+//! This module contains the implementation of the
+//! [`eventually::subscription::CheckpointStore`] trait,
+//! to work specifically with `PostgreSQL` databases.
+//!
+//! Check out the [Store] type for more information.
+
+use async_trait::async_trait;
+use eventually::subscription::CheckpointStore;
+use eventually::{event, health};
+use sqlx::{PgConnection, PgExecutor, PgPool};
+
+/// Implements the [`CheckpointStore`] trait for `PostgreSQL` databases,
+/// recording the checkpoints in the `projection_checkpoints` table,
+/// shared with the [`Projector`][crate::readmodel::Projector].
+///
+/// Checkpoints never move backwards: saving a checkpoint lower than
+/// the stored one leaves it unchanged.
+#[derive(Debug, Clone)]
+pub struct Store {
+    pool: PgPool,
+}
+
+impl Store {
+    /// Runs the latest migrations necessary for the implementation to work,
+    /// then returns a new [`Store`] instance.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the migrations fail to run.
+    pub async fn new(pool: PgPool) -> Result<Self, sqlx::migrate::MigrateError> {
+        // Make sure the latest migrations are used before using the Store instance.
+        crate::MIGRATIONS.run(&pool).await?;
+
+        Ok(Self { pool })
+    }
+}
+
+pub(crate) async fn load_checkpoint<'e>(
+    executor: impl PgExecutor<'e>,
+    name: &str,
+) -> Result<Option<event::Sequence>, sqlx::Error> {
+    let checkpoint: Option<Option<i64>> = sqlx::query_scalar(
+        r#"SELECT "sequence" FROM projection_checkpoints WHERE projection = $1"#,
+    )
+    .bind(name)
+    .fetch_optional(executor)
+    .await?;
+
+    #[allow(clippy::cast_sign_loss)]
+    Ok(checkpoint
+        .flatten()
+        .map(|sequence| sequence as event::Sequence))
+}
+
+/// Creates the checkpoint row of the named subscription, if missing,
+/// and locks it until the end of the transaction.
+///
+/// Returns the checkpoint, or nothing if no Domain Event has been processed yet.
+pub(crate) async fn lock_checkpoint(
+    conn: &mut PgConnection,
+    name: &str,
+) -> Result<Option<event::Sequence>, sqlx::Error> {
+    // NOTE: SELECT ... FOR UPDATE locks nothing if the row does not exist,
+    // so it is created first for concurrent transactions to wait on it.
+    sqlx::query(
+        r#"INSERT INTO projection_checkpoints (projection, "sequence") VALUES ($1, NULL)
+           ON CONFLICT (projection) DO NOTHING"#,
+    )
+    .bind(name)
+    .execute(&mut *conn)
+    .await?;
+
+    let checkpoint: Option<i64> = sqlx::query_scalar(
+        r#"SELECT "sequence" FROM projection_checkpoints WHERE projection = $1 FOR UPDATE"#,
+    )
+    .bind(name)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    #[allow(clippy::cast_sign_loss)]
+    Ok(checkpoint.map(|sequence| sequence as event::Sequence))
+}
+
+pub(crate) async fn save_checkpoint<'e>(
+    executor: impl PgExecutor<'e>,
+    name: &str,
+    sequence: event::Sequence,
+) -> Result<(), sqlx::Error> {
+    #[allow(clippy::cast_possible_wrap)]
+    sqlx::query(
+        r#"INSERT INTO projection_checkpoints (projection, "sequence")
+           VALUES ($1, $2)
+           ON CONFLICT (projection) DO UPDATE
+           SET "sequence" = GREATEST(projection_checkpoints."sequence", EXCLUDED."sequence"),
+               updated_at = NOW()"#,
+    )
+    .bind(name)
+    .bind(sequence as i64)
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+#[async_trait]
+impl CheckpointStore for Store {
+    type Error = sqlx::Error;
+
+    async fn load(&self, name: &str) -> Result<Option<event::Sequence>, Self::Error> {
+        load_checkpoint(&self.pool, name).await
+    }
+
+    async fn save(&self, name: &str, sequence: event::Sequence) -> Result<(), Self::Error> {
+        save_checkpoint(&self.pool, name, sequence).await
+    }
+}
+
+#[async_trait]
+impl health::Check for Store {
+    /// Verifies the database backing the [`Store`] is reachable.
+    async fn check(&self) -> anyhow::Result<()> {
+        crate::ping(&self.pool).await
+    }
+}
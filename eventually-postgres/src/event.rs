@@ -1,71 +1,187 @@
+//! This module contains the implementation of the [`eventually::event::Store`] trait,
+//! to work specifically with `PostgreSQL` databases.
+//!
+//! Check out the [Store] type for more information.
+
 use std::marker::PhantomData;
-use std::string::ToString;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
 
 use anyhow::anyhow;
 use async_trait::async_trait;
-use chrono::Utc;
+use chrono::{DateTime, Utc};
+use eventually::aggregate::IdSerde;
+use eventually::clock::{Clock, SystemClock};
 use eventually::message::{Message, Metadata};
+use eventually::serde::{Deserializer as _, Serializer as _};
 use eventually::version::Version;
-use eventually::{event, serde, version};
+use eventually::{event, health, serde, version};
 use futures::future::ready;
-use futures::{StreamExt, TryStreamExt};
-use sqlx::postgres::PgRow;
-use sqlx::{PgPool, Postgres, Row, Transaction};
+use futures::{stream, StreamExt, TryStreamExt};
+use sqlx::pool::PoolConnection;
+use sqlx::postgres::{PgArguments, PgRow};
+use sqlx::query::Query;
+use sqlx::{PgConnection, PgPool, Postgres, Row, Transaction};
+
+use crate::pool::{ConnectError, PoolConfig};
+use crate::unit_of_work::UnitOfWork;
 
+/// All possible errors returned by [`Store`] while streaming Domain Events
+/// through [`event::store::Streamer::stream`].
 #[derive(Debug, thiserror::Error)]
 pub enum StreamError {
+    /// Error returned when the Domain Event payload could not be deserialized.
     #[error("failed to deserialize event from database: {0}")]
     DeserializeEvent(#[source] anyhow::Error),
+    /// Error returned when a column could not be read from the result row.
     #[error("failed to get column '{name}' from result row: {error}")]
     ReadColumn {
+        /// The name of the column that could not be read.
         name: &'static str,
+        /// The underlying database error.
         #[source]
         error: sqlx::Error,
     },
+    /// Error returned when the Event Stream id could not be converted
+    /// into the Event Stream id type used by the Event Store.
+    #[error("failed to parse event stream id '{id}': {error}")]
+    ParseStreamId {
+        /// The Event Stream id, as stored in the database.
+        id: String,
+        /// The underlying conversion error.
+        #[source]
+        error: anyhow::Error,
+    },
+    /// Error returned when the database returned an error.
     #[error("db returned an error: {0}")]
     Database(#[source] sqlx::Error),
+    /// Error returned when the next page of Domain Events has not been fetched
+    /// within the timeout set through [`Store::with_stream_idle_timeout`].
+    #[error("timed out after {0:?} while waiting for the next page of events")]
+    Timeout(Duration),
 }
 
-pub(crate) async fn append_domain_event<Evt>(
-    tx: &mut Transaction<'_, Postgres>,
-    serde: &impl serde::Serializer<Evt>,
-    event_stream_id: &str,
-    event_version: i32,
-    new_event_stream_version: i32,
-    event: event::Envelope<Evt>,
-) -> anyhow::Result<()>
-where
-    Evt: Message,
-{
-    let event_type = event.message.name();
-    let mut metadata = event.metadata;
-    let serialized_event = serde
-        .serialize(event.message)
-        .map_err(|err| anyhow!("failed to serialize event message: {}", err))?;
-
-    metadata.insert("Recorded-At".to_owned(), Utc::now().to_rfc3339());
-    metadata.insert(
-        "Recorded-With-New-Version".to_owned(),
-        new_event_stream_version.to_string(),
-    );
-
-    sqlx::query(
-            r#"INSERT INTO events (event_stream_id, "type", "version", event, metadata) VALUES ($1, $2, $3, $4, $5)"#,
-        )
-            .bind(event_stream_id)
-            .bind(event_type)
-            .bind(event_version)
-            .bind(serialized_event)
-            .bind(sqlx::types::Json(metadata))
+/// Key of the transaction-level advisory lock held while inserting Domain Events,
+/// using the two-keys space to avoid clashing with the Event Stream locks
+/// acquired through [`event::store::Locker`].
+const EVENTS_SEQUENCE_LOCK_KEY: i32 = 0x6576_6e74;
+
+/// Options used when appending new Domain Events to the `events` table.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct AppendOptions {
+    /// The schema version of the persisted envelopes, if the full
+    /// [Envelope][event::Envelope] should be persisted.
+    pub(crate) envelope_schema_version: Option<u32>,
+    /// The time the Domain Events are recorded at.
+    pub(crate) recorded_at: DateTime<Utc>,
+}
+
+/// A batch of Domain Events to insert in the `events` table with a single statement,
+/// possibly spanning multiple Event Streams, to save a round trip per Domain Event.
+#[derive(Debug, Default)]
+pub(crate) struct EventBatch {
+    event_stream_ids: Vec<String>,
+    types: Vec<String>,
+    versions: Vec<i32>,
+    payloads: Vec<Vec<u8>>,
+    metadata: Vec<sqlx::types::Json<Metadata>>,
+}
+
+impl EventBatch {
+    /// Serializes and adds the Domain Events appended to the specified Event Stream,
+    /// which is at version `new_version` after the append, to the batch.
+    pub(crate) fn push<Evt>(
+        &mut self,
+        serde: &impl serde::Serializer<Evt>,
+        options: AppendOptions,
+        event_stream_id: &str,
+        new_version: i32,
+        events: Vec<event::Envelope<Evt>>,
+    ) -> anyhow::Result<()>
+    where
+        Evt: Message,
+    {
+        #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+        let current_event_stream_version = new_version - (events.len() as i32);
+
+        for (i, event) in events.into_iter().enumerate() {
+            let event_type = event.message.name();
+            let mut metadata = event.metadata;
+
+            metadata.insert(
+                event::RECORDED_AT_KEY.to_owned(),
+                options.recorded_at.to_rfc3339(),
+            );
+            metadata.insert(
+                "Recorded-With-New-Version".to_owned(),
+                new_version.to_string(),
+            );
+
+            let serialized_event = match options.envelope_schema_version {
+                None => serde.serialize(event.message),
+                Some(schema_version) => serde::EnvelopeSerde::new(serde)
+                    .with_schema_version(schema_version)
+                    .serialize(event::Envelope {
+                        message: event.message,
+                        metadata: metadata.clone(),
+                    }),
+            }
+            .map_err(|err| anyhow!("failed to serialize event message: {err}"))?;
+
+            #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+            self.versions
+                .push(current_event_stream_version + (i as i32) + 1);
+            self.event_stream_ids.push(event_stream_id.to_owned());
+            self.types.push(event_type.to_owned());
+            self.payloads.push(serialized_event);
+            self.metadata.push(sqlx::types::Json(metadata));
+        }
+
+        Ok(())
+    }
+
+    /// Inserts all the Domain Events in the batch, in the order they have been added.
+    pub(crate) async fn insert(
+        self,
+        tx: &mut Transaction<'_, Postgres>,
+    ) -> Result<(), sqlx::Error> {
+        if self.versions.is_empty() {
+            return Ok(());
+        }
+
+        // NOTE: the sequence numbers are assigned on insert, so the inserts are
+        // serialized until their transaction ends, for the sequence numbers
+        // to be committed in order: otherwise a transaction committing after
+        // a concurrent one could make a lower sequence number visible to
+        // the readers that have already streamed past it.
+        sqlx::query("SELECT pg_advisory_xact_lock($1, 0)")
+            .bind(EVENTS_SEQUENCE_LOCK_KEY)
             .execute(&mut **tx)
             .await?;
 
-    Ok(())
+        // NOTE: UNNEST preserves the order of the arrays, so the sequence numbers
+        // are assigned in the same order as the Domain Events have been added.
+        sqlx::query(
+            r#"INSERT INTO events (event_stream_id, "type", "version", event, metadata)
+               SELECT * FROM UNNEST($1::text[], $2::text[], $3::integer[], $4::bytea[], $5::jsonb[])"#,
+        )
+        .bind(self.event_stream_ids)
+        .bind(self.types)
+        .bind(self.versions)
+        .bind(self.payloads)
+        .bind(self.metadata)
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(())
+    }
 }
 
 pub(crate) async fn append_domain_events<Evt>(
     tx: &mut Transaction<'_, Postgres>,
     serde: &impl serde::Serializer<Evt>,
+    options: AppendOptions,
     event_stream_id: &str,
     new_version: i32,
     events: Vec<event::Envelope<Evt>>,
@@ -73,42 +189,180 @@ pub(crate) async fn append_domain_events<Evt>(
 where
     Evt: Message,
 {
-    #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
-    let current_event_stream_version = new_version - (events.len() as i32);
-
-    for (i, event) in events.into_iter().enumerate() {
-        #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
-        let event_version = current_event_stream_version + (i as i32) + 1;
+    let mut batch = EventBatch::default();
 
-        append_domain_event(
-            tx,
-            serde,
-            event_stream_id,
-            event_version,
-            new_version,
-            event,
-        )
-        .await?;
-    }
+    batch.push(serde, options, event_stream_id, new_version, events)?;
+    batch.insert(tx).await?;
 
     Ok(())
 }
 
+/// Default number of Domain Events fetched from the database per page
+/// by [`event::store::Streamer::stream`].
+pub const DEFAULT_STREAM_PAGE_SIZE: u32 = 1000;
+
+/// WAL position tracked when reading the one of the last append failed:
+/// greater than any valid position, so that all the subsequent reads
+/// are routed to the primary database.
+const FORCE_PRIMARY_LSN: u64 = u64::MAX;
+
+/// Implements the [`eventually::event::Store`] trait for
+/// `PostgreSQL` databases.
+///
+/// Reads can be routed to a read replica through [`Store::with_read_pool`],
+/// while Domain Events are always appended to the primary database.
 #[derive(Debug, Clone)]
 pub struct Store<Id, Evt, Serde>
 where
-    Id: ToString + Clone,
+    Id: IdSerde + Clone,
     Serde: serde::Serde<Evt>,
 {
     pool: PgPool,
+    read_pool: Option<PgPool>,
+    read_your_writes: bool,
+    last_written_lsn: Arc<AtomicU64>,
     serde: Serde,
+    stream_page_size: u32,
+    envelope_schema_version: Option<u32>,
+    metadata_filter: Option<Metadata>,
+    statement_timeout: Option<Duration>,
+    stream_idle_timeout: Option<Duration>,
+    clock: Arc<dyn Clock>,
     id_type: PhantomData<Id>,
     evt_type: PhantomData<Evt>,
 }
 
+impl<Id, Evt, Serde> event::store::GlobalStreamer<Id, Evt> for Store<Id, Evt, Serde>
+where
+    Id: IdSerde + Clone + Send + Sync,
+    Evt: Message + Send + Sync,
+    Serde: serde::Serde<Evt> + Send + Sync,
+{
+    type Error = StreamError;
+
+    /// Streams all the Domain Events in the `events` table, paginated by
+    /// [Sequence][event::Sequence] number.
+    ///
+    /// Sequence numbers are assigned in commit order, as Domain Events are inserted
+    /// holding a transaction-level advisory lock: once a Domain Event is visible,
+    /// no Domain Event with a lower sequence number can be committed anymore.
+    fn stream_all(
+        &self,
+        select: event::SequenceSelect,
+    ) -> event::SequencedStream<'_, Id, Evt, Self::Error> {
+        self.stream_sequenced(None, select, None)
+    }
+
+    /// Streams all the Domain Events in the `events` table, selecting only the ones
+    /// with the specified names through the `type` column.
+    fn stream_all_filtered<'a>(
+        &'a self,
+        select: event::SequenceSelect,
+        names: event::NameSelect,
+    ) -> event::SequencedStream<'a, Id, Evt, Self::Error>
+    where
+        Id: 'a,
+        Evt: 'a,
+        Self::Error: 'a,
+    {
+        self.stream_sequenced(None, select, selected_names(names))
+    }
+}
+
+impl<Id, Evt, Serde> event::store::TypeStreamer<Id, Evt> for Store<Id, Evt, Serde>
+where
+    Id: IdSerde + Clone + Send + Sync,
+    Evt: Message + Send + Sync,
+    Serde: serde::Serde<Evt> + Send + Sync,
+{
+    type Error = StreamError;
+
+    /// Streams the Domain Events of the Aggregate Roots of the specified type,
+    /// i.e. [`Aggregate::type_name`][eventually::aggregate::Aggregate::type_name],
+    /// paginated by [Sequence][event::Sequence] number.
+    ///
+    /// Only the Event Streams saved through the
+    /// [`aggregate::Repository`][crate::aggregate::Repository] have a type.
+    fn stream_by_type(
+        &self,
+        type_name: &str,
+        select: event::SequenceSelect,
+    ) -> event::SequencedStream<'_, Id, Evt, Self::Error> {
+        self.stream_sequenced(Some(type_name.to_owned()), select, None)
+    }
+}
+
+impl<Id, Evt, Serde> Store<Id, Evt, Serde>
+where
+    Id: IdSerde + Clone + Send + Sync,
+    Evt: Message + Send + Sync,
+    Serde: serde::Serde<Evt> + Send + Sync,
+{
+    fn stream_sequenced(
+        &self,
+        stream_type: Option<String>,
+        select: event::SequenceSelect,
+        names: Option<Vec<String>>,
+    ) -> event::SequencedStream<'_, Id, Evt, StreamError> {
+        #[allow(clippy::cast_possible_wrap)]
+        let from_sequence: i64 = match select {
+            event::SequenceSelect::All => 0,
+            event::SequenceSelect::From(s) => s as i64,
+        };
+
+        let page_size = i64::from(self.stream_page_size);
+
+        stream::try_unfold(Some(from_sequence), move |next_sequence| {
+            let stream_type = stream_type.clone();
+            let names = names.clone();
+
+            async move {
+                let Some(next_sequence) = next_sequence else {
+                    return Ok(None);
+                };
+
+                let query = sqlx::query(
+                    r#"SELECT e.event_stream_id, e.version, e.event, e.metadata, e."sequence"
+                       FROM events e
+                       WHERE e."sequence" >= $1
+                         AND ($3::jsonb IS NULL OR e.metadata @> $3::jsonb)
+                         AND ($4::text IS NULL OR EXISTS (
+                             SELECT 1 FROM aggregates a
+                             WHERE a.aggregate_id = e.event_stream_id AND a."type" = $4
+                         ))
+                         AND ($5::text[] IS NULL OR e."type" = ANY($5))
+                       ORDER BY e."sequence"
+                       LIMIT $2"#,
+                )
+                .bind(next_sequence)
+                .bind(page_size)
+                .bind(self.metadata_filter.as_ref().map(sqlx::types::Json))
+                .bind(stream_type)
+                .bind(names);
+
+                let rows = self.fetch_page(query).await?;
+
+                let last_sequence = match rows.last() {
+                    None => return Ok(None),
+                    Some(row) => try_get_column::<i64>(row, "sequence")?,
+                };
+
+                #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+                let has_next_page = rows.len() as i64 == page_size;
+
+                Ok(Some((rows, has_next_page.then_some(last_sequence + 1))))
+            }
+        })
+        .map_ok(|rows| stream::iter(rows.into_iter().map(Ok)))
+        .try_flatten()
+        .and_then(move |row| ready(event_row_to_sequenced_event(&self.serde, &row)))
+        .boxed()
+    }
+}
+
 impl<Id, Evt, Serde> Store<Id, Evt, Serde>
 where
-    Id: ToString + Clone,
+    Id: IdSerde + Clone,
     Serde: serde::Serde<Evt>,
 {
     /// Runs the latest migrations necessary for the implementation to work,
@@ -123,11 +377,238 @@ where
 
         Ok(Self {
             pool,
+            read_pool: None,
+            read_your_writes: false,
+            last_written_lsn: Arc::new(AtomicU64::new(0)),
             serde,
+            stream_page_size: DEFAULT_STREAM_PAGE_SIZE,
+            envelope_schema_version: None,
+            metadata_filter: None,
+            statement_timeout: None,
+            stream_idle_timeout: None,
+            clock: Arc::new(SystemClock),
             id_type: PhantomData,
             evt_type: PhantomData,
         })
     }
+
+    /// Connects to the database at the specified url with a new pool,
+    /// tuned through the specified [`PoolConfig`], then returns a new [`Store`]
+    /// instance as [`Store::new`].
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the database is not reachable,
+    /// or the migrations fail to run.
+    pub async fn connect(
+        url: &str,
+        config: &PoolConfig,
+        serde: Serde,
+    ) -> Result<Self, ConnectError> {
+        let pool = config.connect(url).await.map_err(ConnectError::Connect)?;
+
+        Ok(Self::new(pool, serde).await?)
+    }
+
+    /// Sets the maximum number of Domain Events fetched from the database
+    /// in a single query while streaming an Event Stream.
+    ///
+    /// Event Streams are paginated by version, so that streaming very long
+    /// Event Streams does not require loading all the rows at once.
+    /// Defaults to [`DEFAULT_STREAM_PAGE_SIZE`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the page size is zero.
+    #[must_use]
+    pub fn with_stream_page_size(mut self, page_size: u32) -> Self {
+        assert!(page_size > 0, "stream page size must be greater than zero");
+        self.stream_page_size = page_size;
+        self
+    }
+
+    /// Persists the full Domain Event [Envelope][event::Envelope] in the `event` column,
+    /// i.e. the payload together with its metadata and the specified schema version,
+    /// using [`serde::EnvelopeSerde`].
+    ///
+    /// Domain Events persisted this way are recognized when streamed, regardless of
+    /// this option, so it can be enabled on an Event Store that already contains
+    /// Domain Events persisted with their payload only.
+    #[must_use]
+    pub fn with_envelope_schema_version(mut self, schema_version: u32) -> Self {
+        self.envelope_schema_version = Some(schema_version);
+        self
+    }
+
+    /// Uses the specified [Clock] to stamp the appended Domain Events
+    /// in the [`event::RECORDED_AT_KEY`] metadata entry, instead of the system time.
+    #[must_use]
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    /// Sets the `statement_timeout` of the queries run by the [Store], so that
+    /// the database aborts the ones taking longer than the specified duration.
+    ///
+    /// Defaults to the `statement_timeout` configured in the database.
+    #[must_use]
+    pub fn with_statement_timeout(mut self, timeout: Duration) -> Self {
+        self.statement_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the maximum time an Event Stream can wait for the next page of
+    /// Domain Events to be fetched from the database, including the time spent
+    /// waiting for a connection from the pool.
+    ///
+    /// When the timeout expires, the Event Stream fails with [`StreamError::Timeout`].
+    /// By default, Event Streams wait indefinitely.
+    #[must_use]
+    pub fn with_stream_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.stream_idle_timeout = Some(timeout);
+        self
+    }
+
+    /// Routes the reads of [`event::store::Streamer::stream`] and
+    /// [`event::store::GlobalStreamer::stream_all`], and so the ones of the projections
+    /// built on top of them, to the specified pool, e.g. connected to a read replica.
+    ///
+    /// Domain Events are still appended through the pool used to create the [`Store`].
+    /// No migrations are run on the read pool, which must be connected to a replica
+    /// of the primary database.
+    #[must_use]
+    pub fn with_read_pool(mut self, pool: PgPool) -> Self {
+        self.read_pool = Some(pool);
+        self
+    }
+
+    /// Makes the Domain Events appended through this [`Store`], or any of its clones,
+    /// visible to its subsequent reads, even when a read pool is used.
+    ///
+    /// The WAL position of the primary database is tracked after every append,
+    /// and reads fall back to the primary database until the read replica
+    /// has replayed it. Domain Events appended as part of a [`UnitOfWork`]
+    /// through [`Store::append_in`] are not tracked.
+    #[must_use]
+    pub fn with_read_your_writes(mut self) -> Self {
+        self.read_your_writes = true;
+        self
+    }
+
+    /// Returns the pool to use for the next read, either the read pool,
+    /// or the primary one if the read replica is lagging behind the last append.
+    async fn read_pool(&self) -> Result<&PgPool, sqlx::Error> {
+        let Some(read_pool) = &self.read_pool else {
+            return Ok(&self.pool);
+        };
+
+        let last_written_lsn = self.last_written_lsn.load(Ordering::Acquire);
+
+        if !self.read_your_writes || last_written_lsn == 0 {
+            return Ok(read_pool);
+        }
+
+        // NOTE: this also covers FORCE_PRIMARY_LSN, which is out of range.
+        let Ok(last_written_lsn) = i64::try_from(last_written_lsn) else {
+            return Ok(&self.pool);
+        };
+
+        // NOTE: pg_last_wal_replay_lsn() is NULL when the read pool is not
+        // connected to a replica, which is then as up to date as the primary.
+        let caught_up: bool = sqlx::query_scalar(
+            "SELECT (COALESCE(pg_last_wal_replay_lsn(), pg_current_wal_lsn()) - '0/0'::pg_lsn) >= $1",
+        )
+        .bind(last_written_lsn)
+        .fetch_one(read_pool)
+        .await?;
+
+        Ok(if caught_up { read_pool } else { &self.pool })
+    }
+
+    /// Tracks the current WAL position of the primary database after an append,
+    /// if read-your-writes consistency has been requested.
+    async fn track_written_lsn(&self) {
+        if self.read_pool.is_none() || !self.read_your_writes {
+            return;
+        }
+
+        let lsn =
+            sqlx::query_scalar::<_, i64>("SELECT (pg_current_wal_lsn() - '0/0'::pg_lsn)::bigint")
+                .fetch_one(&self.pool)
+                .await;
+
+        // NOTE: the append has already been committed at this point, so failing to
+        // read the WAL position routes all the subsequent reads to the primary instead.
+        #[allow(clippy::cast_sign_loss)]
+        let lsn = lsn.map_or(FORCE_PRIMARY_LSN, |lsn| lsn as u64);
+
+        self.last_written_lsn.fetch_max(lsn, Ordering::AcqRel);
+    }
+
+    async fn set_statement_timeout(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+    ) -> Result<(), sqlx::Error> {
+        if let Some(timeout) = self.statement_timeout {
+            sqlx::query("SELECT set_config('statement_timeout', $1, true)")
+                .bind(timeout.as_millis().to_string())
+                .execute(&mut **tx)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn fetch_page(
+        &self,
+        query: Query<'_, Postgres, PgArguments>,
+    ) -> Result<Vec<PgRow>, StreamError> {
+        let fetch = async {
+            let pool = self.read_pool().await?;
+
+            if self.statement_timeout.is_none() {
+                return query.fetch_all(pool).await;
+            }
+
+            // NOTE: the statement timeout is set locally to a transaction,
+            // to avoid leaking it to the other users of the pooled connection.
+            let mut tx = pool.begin().await?;
+            self.set_statement_timeout(&mut tx).await?;
+            let rows = query.fetch_all(&mut *tx).await?;
+            tx.commit().await?;
+
+            Ok(rows)
+        };
+
+        match self.stream_idle_timeout {
+            None => fetch.await.map_err(StreamError::Database),
+            Some(timeout) => tokio::time::timeout(timeout, fetch)
+                .await
+                .map_err(|_| StreamError::Timeout(timeout))?
+                .map_err(StreamError::Database),
+        }
+    }
+
+    /// Only streams the Domain Events whose metadata contains the specified entry
+    /// through [`event::store::GlobalStreamer::stream_all`], e.g. the ones of a single tenant.
+    ///
+    /// The filter is applied by the database, and multiple filters must all match.
+    /// Event Streams opened through [`event::store::Streamer::stream`] are not filtered.
+    #[must_use]
+    pub fn with_metadata_filter(mut self, key: String, value: String) -> Self {
+        self.metadata_filter
+            .get_or_insert_with(Metadata::default)
+            .insert(key, value);
+        self
+    }
+}
+
+fn selected_names(names: event::NameSelect) -> Option<Vec<String>> {
+    match names {
+        event::NameSelect::All => None,
+        event::NameSelect::Only(names) => Some(names),
+    }
 }
 
 fn try_get_column<T>(row: &PgRow, name: &'static str) -> Result<T, StreamError>
@@ -138,120 +619,236 @@ where
         .map_err(|err| StreamError::ReadColumn { name, error: err })
 }
 
-impl<Id, Evt, Serde> Store<Id, Evt, Serde>
+fn parse_stream_id<Id>(id: String) -> Result<Id, StreamError>
 where
-    Id: ToString + Clone + Send + Sync,
-    Evt: Message + Send + Sync,
-    Serde: serde::Serde<Evt> + Send + Sync,
+    Id: IdSerde,
 {
-    fn event_row_to_persisted_event(
-        &self,
-        stream_id: Id,
-        row: &PgRow,
-    ) -> Result<event::Persisted<Id, Evt>, StreamError> {
-        let version_column: i32 = try_get_column(row, "version")?;
-        let event_column: Vec<u8> = try_get_column(row, "event")?;
-        let metadata_column: sqlx::types::Json<Metadata> = try_get_column(row, "metadata")?;
-
-        let deserialized_event = self
-            .serde
+    Id::decode_id(&id).map_err(|error| StreamError::ParseStreamId { id, error })
+}
+
+pub(crate) fn event_row_to_persisted_event<Id, Evt>(
+    serde: &impl serde::Deserializer<Evt>,
+    stream_id: Id,
+    row: &PgRow,
+) -> Result<event::Persisted<Id, Evt>, StreamError>
+where
+    Evt: Message,
+{
+    let version_column: i32 = try_get_column(row, "version")?;
+    let event_column: Vec<u8> = try_get_column(row, "event")?;
+    let metadata_column: sqlx::types::Json<Metadata> = try_get_column(row, "metadata")?;
+
+    let event = if serde::is_envelope(&event_column) {
+        let mut event: event::Envelope<Evt> = serde::EnvelopeSerde::new(serde)
             .deserialize(&event_column)
             .map_err(StreamError::DeserializeEvent)?;
 
-        #[allow(clippy::cast_sign_loss)]
-        Ok(event::Persisted {
-            stream_id,
-            version: version_column as Version,
-            event: event::Envelope {
-                message: deserialized_event,
-                metadata: metadata_column.0,
-            },
-        })
-    }
+        for (key, value) in metadata_column.0 {
+            event.metadata.entry(key).or_insert(value);
+        }
+
+        event
+    } else {
+        event::Envelope {
+            message: serde
+                .deserialize(&event_column)
+                .map_err(StreamError::DeserializeEvent)?,
+            metadata: metadata_column.0,
+        }
+    };
+
+    #[allow(clippy::cast_sign_loss)]
+    Ok(event::Persisted {
+        stream_id,
+        version: version_column as Version,
+        event,
+    })
+}
+
+pub(crate) fn event_row_to_sequenced_event<Id, Evt>(
+    serde: &impl serde::Deserializer<Evt>,
+    row: &PgRow,
+) -> Result<event::Sequenced<Id, Evt>, StreamError>
+where
+    Id: IdSerde,
+    Evt: Message,
+{
+    let stream_id = parse_stream_id(try_get_column(row, "event_stream_id")?)?;
+    let sequence_column: i64 = try_get_column(row, "sequence")?;
+
+    #[allow(clippy::cast_sign_loss)]
+    Ok(event::Sequenced {
+        sequence: sequence_column as event::Sequence,
+        event: event_row_to_persisted_event(serde, stream_id, row)?,
+    })
 }
 
 impl<Id, Evt, Serde> event::store::Streamer<Id, Evt> for Store<Id, Evt, Serde>
 where
-    Id: ToString + Clone + Send + Sync,
+    Id: IdSerde + Clone + Send + Sync,
     Evt: Message + Send + Sync,
     Serde: serde::Serde<Evt> + Send + Sync,
 {
     type Error = StreamError;
 
-    fn stream(&self, id: &Id, select: event::VersionSelect) -> event::Stream<Id, Evt, Self::Error> {
+    fn stream(
+        &self,
+        id: &Id,
+        select: event::VersionSelect,
+    ) -> event::Stream<'_, Id, Evt, Self::Error> {
+        self.stream_events(id, select, None)
+    }
+
+    /// Opens an Event Stream, selecting only the Domain Events with the specified names
+    /// through the `type` column of the `events` table.
+    fn stream_filtered<'a>(
+        &'a self,
+        id: &Id,
+        select: event::VersionSelect,
+        names: event::NameSelect,
+    ) -> event::Stream<'a, Id, Evt, Self::Error>
+    where
+        Id: 'a,
+        Evt: 'a,
+        Self::Error: 'a,
+    {
+        self.stream_events(id, select, selected_names(names))
+    }
+}
+
+impl<Id, Evt, Serde> Store<Id, Evt, Serde>
+where
+    Id: IdSerde + Clone + Send + Sync,
+    Evt: Message + Send + Sync,
+    Serde: serde::Serde<Evt> + Send + Sync,
+{
+    fn stream_events(
+        &self,
+        id: &Id,
+        select: event::VersionSelect,
+        names: Option<Vec<String>>,
+    ) -> event::Stream<'_, Id, Evt, StreamError> {
         #[allow(clippy::cast_possible_truncation)]
         let from_version: i32 = match select {
             event::VersionSelect::All => 0,
             event::VersionSelect::From(v) => v as i32,
         };
 
-        let query = sqlx::query(
-            r"SELECT version, event, metadata
-               FROM events
-               WHERE event_stream_id = $1 AND version >= $2
-               ORDER BY version",
-        );
-
         let id = id.clone();
+        let string_id = id.encode_id();
+        let page_size = i64::from(self.stream_page_size);
+
+        // NOTE: the Event Stream is paginated using the version as keyset,
+        // the state keeps track of the next version to fetch, if any.
+        stream::try_unfold(Some(from_version), move |next_version| {
+            let string_id = string_id.clone();
+            let names = names.clone();
+
+            async move {
+                let Some(next_version) = next_version else {
+                    return Ok(None);
+                };
+
+                let query = sqlx::query(
+                    r#"SELECT version, event, metadata
+                       FROM events
+                       WHERE event_stream_id = $1 AND version >= $2
+                         AND ($4::text[] IS NULL OR "type" = ANY($4))
+                         AND NOT EXISTS (
+                             SELECT 1 FROM event_streams es
+                             WHERE es.event_stream_id = $1 AND es.deleted_at IS NOT NULL
+                         )
+                       ORDER BY version
+                       LIMIT $3"#,
+                )
+                .bind(string_id)
+                .bind(next_version)
+                .bind(page_size)
+                .bind(names);
+
+                let rows = self.fetch_page(query).await?;
+
+                let last_version = match rows.last() {
+                    None => return Ok(None),
+                    Some(row) => try_get_column::<i32>(row, "version")?,
+                };
 
-        query
-            .bind(id.to_string())
-            .bind(from_version)
-            .fetch(&self.pool)
-            .map_err(StreamError::Database)
-            .and_then(move |row| ready(self.event_row_to_persisted_event(id.clone(), &row)))
-            .boxed()
+                #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+                let has_next_page = rows.len() as i64 == page_size;
+
+                Ok(Some((rows, has_next_page.then_some(last_version + 1))))
+            }
+        })
+        .map_ok(|rows| stream::iter(rows.into_iter().map(Ok)))
+        .try_flatten()
+        .and_then(move |row| ready(event_row_to_persisted_event(&self.serde, id.clone(), &row)))
+        .boxed()
     }
 }
 
-#[async_trait]
-impl<Id, Evt, Serde> event::store::Appender<Id, Evt> for Store<Id, Evt, Serde>
+impl<Id, Evt, Serde> Store<Id, Evt, Serde>
 where
-    Id: ToString + Clone + Send + Sync,
+    Id: IdSerde + Clone + Send + Sync,
     Evt: Message + Send + Sync,
     Serde: serde::Serde<Evt> + Send + Sync,
 {
-    async fn append(
+    async fn begin_serializable_transaction(
         &self,
-        id: Id,
-        version_check: version::Check,
-        events: Vec<event::Envelope<Evt>>,
-    ) -> Result<Version, event::store::AppendError> {
+    ) -> Result<Transaction<'_, Postgres>, event::store::AppendError> {
         let mut tx = self
             .pool
             .begin()
             .await
-            .map_err(|err| anyhow!("failed to begin transaction: {}", err))?;
+            .map_err(|err| anyhow!("failed to begin transaction: {err}"))?;
 
         sqlx::query("SET TRANSACTION ISOLATION LEVEL SERIALIZABLE DEFERRABLE")
             .execute(&mut *tx)
             .await
-            .map_err(|err| anyhow!("failed to begin transaction: {}", err))?;
+            .map_err(|err| anyhow!("failed to begin transaction: {err}"))?;
 
-        let string_id = id.to_string();
+        self.set_statement_timeout(&mut tx)
+            .await
+            .map_err(|err| anyhow!("failed to set statement timeout: {err}"))?;
+
+        Ok(tx)
+    }
 
-        let new_version: i32 = match version_check {
+    async fn upsert_event_stream_version(
+        tx: &mut Transaction<'_, Postgres>,
+        string_id: &str,
+        version_check: version::Check,
+        events_len: usize,
+    ) -> Result<i32, event::store::AppendError> {
+        match version_check {
             version::Check::Any => {
                 #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
-                let events_len = events.len() as i32;
+                let events_len = events_len as i32;
 
-                sqlx::query("SELECT * FROM upsert_event_stream_with_no_version_check($1, $2)")
-                    .bind(&string_id)
-                    .bind(events_len)
-                    .fetch_one(&mut *tx)
-                    .await
-                    .and_then(|row| row.try_get(0))
-                    .map_err(|err| anyhow!("failed to upsert new event stream version: {}", err))?
+                Ok(
+                    sqlx::query("SELECT * FROM upsert_event_stream_with_no_version_check($1, $2)")
+                        .bind(string_id)
+                        .bind(events_len)
+                        .fetch_one(&mut **tx)
+                        .await
+                        .and_then(|row| row.try_get(0))
+                        .map_err(|err| {
+                            if crate::is_tombstoned_error(&err) {
+                                return event::store::StreamTombstonedError.into();
+                            }
+
+                            anyhow!("failed to upsert new event stream version: {err}")
+                        })?,
+                )
             },
             version::Check::MustBe(v) => {
-                let new_version = v + (events.len() as Version);
+                let new_version = v + (events_len as Version);
 
                 #[allow(clippy::cast_possible_truncation)]
                 sqlx::query("CALL upsert_event_stream($1, $2, $3)")
-                    .bind(&string_id)
+                    .bind(string_id)
                     .bind(v as i32)
                     .bind(new_version as i32)
-                    .execute(&mut *tx)
+                    .execute(&mut **tx)
                     .await
                     .map_err(|err| match crate::check_for_conflict_error(&err) {
                         Some(err) => event::store::AppendError::Conflict(err),
@@ -265,25 +862,377 @@ where
                                     actual: new_version,
                                 })
                             },
+                            _ if crate::is_tombstoned_error(&err) => {
+                                event::store::AppendError::Internal(
+                                    event::store::StreamTombstonedError.into(),
+                                )
+                            },
                             _ => event::store::AppendError::Internal(anyhow!(
-                                "failed to upsert new event stream version: {}",
-                                err
+                                "failed to upsert new event stream version: {err}"
                             )),
                         },
                     })
-                    .map(|_| new_version as i32)?
+                    .map(|_| new_version as i32)
             },
+        }
+    }
+
+    /// Appends the Domain Events of all the specified [`event::store::StreamAppend`]s
+    /// in the transaction, checking the version of every Event Stream first,
+    /// and then inserting all the Domain Events with a single statement.
+    async fn append_batch(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        appends: Vec<event::store::StreamAppend<Id, Evt>>,
+    ) -> Result<Vec<Version>, event::store::AppendError> {
+        let options = AppendOptions {
+            envelope_schema_version: self.envelope_schema_version,
+            recorded_at: self.clock.now(),
         };
 
-        append_domain_events(&mut tx, &self.serde, &string_id, new_version, events)
+        let mut batch = EventBatch::default();
+        let mut new_versions = Vec::with_capacity(appends.len());
+
+        for append in appends {
+            let string_id = append.id.encode_id();
+
+            let new_version = Self::upsert_event_stream_version(
+                tx,
+                &string_id,
+                append.version_check,
+                append.events.len(),
+            )
+            .await?;
+
+            batch
+                .push(&self.serde, options, &string_id, new_version, append.events)
+                .map_err(|err| anyhow!("failed to append new domain events: {err}"))?;
+
+            #[allow(clippy::cast_sign_loss)]
+            new_versions.push(new_version as Version);
+        }
+
+        batch
+            .insert(tx)
+            .await
+            .map_err(|err| anyhow!("failed to append new domain events: {err}"))?;
+
+        Ok(new_versions)
+    }
+
+    async fn append_to_event_stream(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        id: Id,
+        version_check: version::Check,
+        events: Vec<event::Envelope<Evt>>,
+    ) -> Result<Version, event::store::AppendError> {
+        let new_versions = self
+            .append_batch(
+                tx,
+                vec![event::store::StreamAppend {
+                    id,
+                    version_check,
+                    events,
+                }],
+            )
+            .await?;
+
+        Ok(new_versions[0])
+    }
+}
+
+#[async_trait]
+impl<Id, Evt, Serde> event::store::Appender<Id, Evt> for Store<Id, Evt, Serde>
+where
+    Id: IdSerde + Clone + Send + Sync,
+    Evt: Message + Send + Sync,
+    Serde: serde::Serde<Evt> + Send + Sync,
+{
+    async fn append(
+        &self,
+        id: Id,
+        version_check: version::Check,
+        events: Vec<event::Envelope<Evt>>,
+    ) -> Result<Version, event::store::AppendError> {
+        let mut tx = self.begin_serializable_transaction().await?;
+
+        let new_version = self
+            .append_to_event_stream(&mut tx, id, version_check, events)
+            .await?;
+
+        tx.commit()
             .await
-            .map_err(|err| anyhow!("failed to append new domain events: {}", err))?;
+            .map_err(|err| anyhow!("failed to commit transaction: {err}"))?;
+
+        self.track_written_lsn().await;
+
+        Ok(new_version)
+    }
+
+    async fn append_multi(
+        &self,
+        appends: Vec<event::store::StreamAppend<Id, Evt>>,
+    ) -> Result<Vec<Version>, event::store::AppendError> {
+        let mut tx = self.begin_serializable_transaction().await?;
+        let new_versions = self.append_batch(&mut tx, appends).await?;
 
         tx.commit()
             .await
-            .map_err(|err| anyhow!("failed to commit transaction: {}", err))?;
+            .map_err(|err| anyhow!("failed to commit transaction: {err}"))?;
 
-        #[allow(clippy::cast_sign_loss)]
-        Ok(new_version as Version)
+        self.track_written_lsn().await;
+
+        Ok(new_versions)
+    }
+}
+
+impl<Id, Evt, Serde> Store<Id, Evt, Serde>
+where
+    Id: IdSerde + Clone + Send + Sync,
+    Evt: Message + Send + Sync,
+    Serde: serde::Serde<Evt> + Send + Sync,
+{
+    /// Appends new Domain Events to the specified Event Stream as part of the
+    /// [`UnitOfWork`], like [`event::store::Appender::append`] does.
+    ///
+    /// The Domain Events are persisted only once the [`UnitOfWork`] is committed.
+    /// Appending Domain Events holds a lock until then, to assign their
+    /// [Sequence][event::Sequence] numbers in commit order: all the other appends
+    /// wait for the [`UnitOfWork`] to end, so keep it short.
+    /// The statement timeout set through [`Store::with_statement_timeout`]
+    /// is not applied, as it would affect the other statements of the [`UnitOfWork`].
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the version check fails, or if the Domain Events
+    /// could not be appended.
+    pub async fn append_in(
+        &self,
+        unit_of_work: &mut UnitOfWork<'_>,
+        id: Id,
+        version_check: version::Check,
+        events: Vec<event::Envelope<Evt>>,
+    ) -> Result<Version, event::store::AppendError> {
+        self.append_to_event_stream(unit_of_work.transaction(), id, version_check, events)
+            .await
+    }
+}
+
+#[async_trait]
+impl<Id, Evt, Serde> event::store::StreamDeleter<Id, Evt> for Store<Id, Evt, Serde>
+where
+    Id: IdSerde + Clone + Send + Sync,
+    Evt: Message + Send + Sync,
+    Serde: serde::Serde<Evt> + Send + Sync,
+{
+    async fn delete(&self, id: &Id) -> Result<(), event::store::DeleteError> {
+        // NOTE: Domain Events are removed through the ON DELETE CASCADE constraint.
+        sqlx::query("DELETE FROM event_streams WHERE event_stream_id = $1")
+            .bind(id.encode_id())
+            .execute(&self.pool)
+            .await
+            .map_err(|err| anyhow!("failed to delete event stream: {err}"))?;
+
+        Ok(())
+    }
+
+    async fn truncate(
+        &self,
+        id: &Id,
+        before_version: Version,
+    ) -> Result<(), event::store::DeleteError> {
+        #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+        sqlx::query("DELETE FROM events WHERE event_stream_id = $1 AND \"version\" < $2")
+            .bind(id.encode_id())
+            .bind(before_version.min(i32::MAX as Version) as i32)
+            .execute(&self.pool)
+            .await
+            .map_err(|err| anyhow!("failed to truncate event stream: {err}"))?;
+
+        Ok(())
+    }
+}
+
+/// Marks the Event Stream as deleted, if it is at the expected version,
+/// through the `deleted_at` column of the `event_streams` table.
+pub(crate) async fn tombstone_event_stream(
+    conn: &mut PgConnection,
+    event_stream_id: &str,
+    version_check: version::Check,
+    deleted_at: DateTime<Utc>,
+) -> Result<(), event::store::TombstoneError> {
+    #[allow(clippy::cast_possible_truncation)]
+    let expected_version = match version_check {
+        version::Check::Any => None,
+        version::Check::MustBe(v) => Some(v as i32),
+    };
+
+    let tombstoned = sqlx::query(
+        r#"UPDATE event_streams SET deleted_at = COALESCE(deleted_at, $3)
+           WHERE event_stream_id = $1 AND ($2::integer IS NULL OR "version" = $2)"#,
+    )
+    .bind(event_stream_id)
+    .bind(expected_version)
+    .bind(deleted_at)
+    .execute(&mut *conn)
+    .await
+    .map_err(|err| anyhow!("failed to tombstone event stream: {err}"))?
+    .rows_affected();
+
+    let Some(expected) = expected_version.filter(|_| tombstoned == 0) else {
+        return Ok(());
+    };
+
+    let actual: Option<i32> =
+        sqlx::query_scalar(r#"SELECT "version" FROM event_streams WHERE event_stream_id = $1"#)
+            .bind(event_stream_id)
+            .fetch_optional(&mut *conn)
+            .await
+            .map_err(|err| anyhow!("failed to read event stream version: {err}"))?;
+
+    // NOTE: an Event Stream that does not exist is at version zero,
+    // and there is nothing to tombstone if that was the expected version.
+    let actual = actual.unwrap_or_default();
+
+    if actual == expected {
+        return Ok(());
+    }
+
+    #[allow(clippy::cast_sign_loss)]
+    Err(event::store::TombstoneError::Conflict(
+        version::ConflictError {
+            expected: expected as Version,
+            actual: actual as Version,
+        },
+    ))
+}
+
+#[async_trait]
+impl<Id, Evt, Serde> event::store::Tombstoner<Id> for Store<Id, Evt, Serde>
+where
+    Id: IdSerde + Clone + Send + Sync,
+    Evt: Message + Send + Sync,
+    Serde: serde::Serde<Evt> + Send + Sync,
+{
+    /// Marks the Event Stream as deleted through the `deleted_at` column
+    /// of the `event_streams` table, keeping its Domain Events in the `events` table.
+    async fn tombstone(
+        &self,
+        id: &Id,
+        version_check: version::Check,
+    ) -> Result<(), event::store::TombstoneError> {
+        let mut conn = self
+            .pool
+            .acquire()
+            .await
+            .map_err(|err| anyhow!("failed to acquire connection: {err}"))?;
+
+        tombstone_event_stream(&mut conn, &id.encode_id(), version_check, self.clock.now()).await
+    }
+}
+
+/// The [`event::store::Locker::Lock`] of the [`Store`], holding a session-level
+/// `PostgreSQL` advisory lock on the Event Stream id.
+///
+/// The lock is held on a dedicated connection from the pool: use [`AdvisoryLock::release`]
+/// to release it and return the connection to the pool. When dropped instead,
+/// the connection is closed, which releases the lock as well.
+#[derive(Debug)]
+pub struct AdvisoryLock {
+    key: String,
+    conn: Option<PoolConnection<Postgres>>,
+}
+
+impl AdvisoryLock {
+    /// Releases the advisory lock and returns its connection to the pool.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the lock could not be released, in which case
+    /// the connection is closed to release the lock anyway.
+    pub async fn release(mut self) -> Result<(), event::store::LockError> {
+        let Some(mut conn) = self.conn.take() else {
+            return Ok(());
+        };
+
+        if let Err(err) = sqlx::query("SELECT pg_advisory_unlock(hashtextextended($1, 0))")
+            .bind(&self.key)
+            .execute(&mut *conn)
+            .await
+        {
+            drop(conn.detach());
+            return Err(anyhow!("failed to release advisory lock: {err}").into());
+        }
+
+        Ok(())
+    }
+}
+
+impl Drop for AdvisoryLock {
+    fn drop(&mut self) {
+        // NOTE: closing the session releases all the advisory locks held by it.
+        if let Some(conn) = self.conn.take() {
+            drop(conn.detach());
+        }
+    }
+}
+
+#[async_trait]
+impl<Id, Evt, Serde> event::store::Locker<Id> for Store<Id, Evt, Serde>
+where
+    Id: IdSerde + Clone + Send + Sync,
+    Evt: Message + Send + Sync,
+    Serde: serde::Serde<Evt> + Send + Sync,
+{
+    type Lock = AdvisoryLock;
+
+    async fn lock(&self, id: &Id) -> Result<Self::Lock, event::store::LockError> {
+        let key = id.encode_id();
+
+        let conn = self
+            .pool
+            .acquire()
+            .await
+            .map_err(|err| anyhow!("failed to acquire connection for advisory lock: {err}"))?;
+
+        // NOTE: the lock owns the connection before acquiring the advisory lock,
+        // so that if this future is dropped while waiting (e.g. on a timeout),
+        // the connection is closed rather than returned to the pool,
+        // where it might end up holding the advisory lock indefinitely.
+        let mut lock = AdvisoryLock {
+            key,
+            conn: Some(conn),
+        };
+
+        let conn = lock.conn.as_mut().expect("connection is set on creation");
+
+        sqlx::query("SELECT pg_advisory_lock(hashtextextended($1, 0))")
+            .bind(&lock.key)
+            .execute(&mut **conn)
+            .await
+            .map_err(|err| anyhow!("failed to acquire advisory lock: {err}"))?;
+
+        Ok(lock)
+    }
+}
+
+#[async_trait]
+impl<Id, Evt, Serde> health::Check for Store<Id, Evt, Serde>
+where
+    Id: IdSerde + Clone + Send + Sync,
+    Evt: Send + Sync,
+    Serde: serde::Serde<Evt> + Send + Sync,
+{
+    /// Verifies the database backing the [`Store`], and its read replica
+    /// if any, are reachable.
+    async fn check(&self) -> anyhow::Result<()> {
+        crate::ping(&self.pool).await?;
+
+        if let Some(read_pool) = &self.read_pool {
+            crate::ping(read_pool).await?;
+        }
+
+        Ok(())
     }
 }
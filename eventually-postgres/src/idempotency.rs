@@ -0,0 +1,122 @@
+//! This module contains the implementation of the
+//! [`eventually::command::handler::IdempotencyStore`] trait,
+//! to work specifically with `PostgreSQL` databases.
+//!
+//! Check out the [Store] type for more information.
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use eventually::command::handler::{IdempotencyStore, Reservation};
+use eventually::health;
+use sqlx::PgPool;
+
+/// Implements the [`IdempotencyStore`] trait for `PostgreSQL` databases,
+/// recording the idempotency keys in the `idempotency_keys` table.
+///
+/// Keys are reserved with a lease in the `lease_expires_at` column, and marked
+/// as completed in the `completed_at` column. They are never removed once the Command
+/// has been processed successfully: old keys can be pruned by deleting the rows
+/// with an old `completed_at` timestamp.
+#[derive(Debug, Clone)]
+pub struct Store {
+    pool: PgPool,
+}
+
+impl Store {
+    /// Runs the latest migrations necessary for the implementation to work,
+    /// then returns a new [`Store`] instance.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the migrations fail to run.
+    pub async fn new(pool: PgPool) -> Result<Self, sqlx::migrate::MigrateError> {
+        // Make sure the latest migrations are used before using the Store instance.
+        crate::MIGRATIONS.run(&pool).await?;
+
+        Ok(Self { pool })
+    }
+}
+
+#[async_trait]
+impl IdempotencyStore for Store {
+    type Error = sqlx::Error;
+
+    async fn reserve(
+        &self,
+        command_name: &str,
+        key: &str,
+        lease: Duration,
+    ) -> Result<Reservation, Self::Error> {
+        // NOTE: the key is reserved if missing, or if its lease has expired
+        // without being completed, in which case the row is returned.
+        let acquired = sqlx::query(
+            r#"INSERT INTO idempotency_keys (command_name, "key", lease_expires_at)
+               VALUES ($1, $2, NOW() + make_interval(secs => $3))
+               ON CONFLICT (command_name, "key") DO UPDATE
+               SET lease_expires_at = EXCLUDED.lease_expires_at, recorded_at = NOW()
+               WHERE idempotency_keys.completed_at IS NULL
+                 AND idempotency_keys.lease_expires_at <= NOW()
+               RETURNING "key""#,
+        )
+        .bind(command_name)
+        .bind(key)
+        .bind(lease.as_secs_f64())
+        .fetch_optional(&self.pool)
+        .await?;
+
+        if acquired.is_some() {
+            return Ok(Reservation::Acquired);
+        }
+
+        let completed: Option<bool> = sqlx::query_scalar(
+            r#"SELECT completed_at IS NOT NULL FROM idempotency_keys
+               WHERE command_name = $1 AND "key" = $2"#,
+        )
+        .bind(command_name)
+        .bind(key)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        // NOTE: a key released in the meantime is reported as in flight,
+        // so that the Command is delivered again and reserves it then.
+        Ok(match completed {
+            Some(true) => Reservation::Completed,
+            Some(false) | None => Reservation::InFlight,
+        })
+    }
+
+    async fn complete(&self, command_name: &str, key: &str) -> Result<(), Self::Error> {
+        sqlx::query(
+            r#"UPDATE idempotency_keys SET completed_at = NOW(), lease_expires_at = NULL
+               WHERE command_name = $1 AND "key" = $2"#,
+        )
+        .bind(command_name)
+        .bind(key)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn release(&self, command_name: &str, key: &str) -> Result<(), Self::Error> {
+        sqlx::query(
+            r#"DELETE FROM idempotency_keys
+               WHERE command_name = $1 AND "key" = $2 AND completed_at IS NULL"#,
+        )
+        .bind(command_name)
+        .bind(key)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl health::Check for Store {
+    /// Verifies the database backing the [`Store`] is reachable.
+    async fn check(&self) -> anyhow::Result<()> {
+        crate::ping(&self.pool).await
+    }
+}
@@ -6,29 +6,61 @@
 
 #![deny(unsafe_code, unused_qualifications, trivial_casts)]
 #![deny(clippy::all, clippy::pedantic, clippy::cargo)]
+#![allow(clippy::multiple_crate_versions)]
 #![warn(missing_docs)]
 
 pub mod aggregate;
+pub mod checkpoint;
 pub mod event;
+pub mod idempotency;
+pub mod maintenance;
+pub mod pool;
+pub mod readmodel;
+pub mod scheduler;
+pub mod subscription;
+pub mod unit_of_work;
 
 pub(crate) static MIGRATIONS: sqlx::migrate::Migrator = sqlx::migrate!("./migrations");
 
+use std::sync::LazyLock;
+
 use eventually::version::{ConflictError, Version};
-use lazy_static::lazy_static;
 use regex::Regex;
 
-lazy_static! {
-    static ref CONFLICT_ERROR_REGEX: Regex =
-        Regex::new(r"version check failed, expected: (?P<expected>\d), got: (?P<got>\d)")
-            .expect("regex compiles successfully");
+static CONFLICT_ERROR_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"version check failed, expected: (?P<expected>\d+), got: (?P<got>\d+|<NULL>)")
+        .expect("regex compiles successfully")
+});
+
+/// Verifies the database is reachable, used by the [`eventually::health::Check`]
+/// implementations of this crate.
+pub(crate) async fn ping(pool: &sqlx::PgPool) -> anyhow::Result<()> {
+    sqlx::query("SELECT 1")
+        .execute(pool)
+        .await
+        .map_err(|err| anyhow::anyhow!("failed to ping the database: {err}"))?;
+
+    Ok(())
+}
+
+/// Returns true if the error has been raised by appending to an Event Stream
+/// that has been tombstoned, as the `event_streams_tombstone` trigger does.
+pub(crate) fn is_tombstoned_error(err: &sqlx::Error) -> bool {
+    err.as_database_error()
+        .is_some_and(|err| err.message().starts_with("event stream has been deleted"))
 }
 
 pub(crate) fn check_for_conflict_error(err: &sqlx::Error) -> Option<ConflictError> {
     fn capture_to_version(captures: &regex::Captures, name: &'static str) -> Version {
-        let v: i32 = captures
-            .name(name)
-            .expect("field is captured")
-            .as_str()
+        let capture = captures.name(name).expect("field is captured").as_str();
+
+        // A NULL version is reported when the version check runs on a non-existing
+        // row, which is equivalent to an empty Event Stream.
+        if capture == "<NULL>" {
+            return 0;
+        }
+
+        let v: i32 = capture
             .parse::<i32>()
             .expect("field should be a valid integer");
 
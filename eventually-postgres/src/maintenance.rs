@@ -0,0 +1,259 @@
+//! This module contains administrative utilities to inspect the health
+//! of the `events` table used by the [`event::Store`][crate::event::Store],
+//! and to run maintenance operations on long-running installations.
+//!
+//! Check out the [Maintenance] type for more information.
+
+use chrono::{DateTime, Utc};
+use sqlx::{PgPool, Row};
+
+/// Name of the table where the Domain Events are stored.
+const EVENTS_TABLE: &str = "events";
+
+/// Name of the index on `(event_stream_id, version)`, used to stream
+/// the Domain Events of a single Event Stream in order.
+const EVENTS_PRIMARY_KEY_INDEX: &str = "events_pkey";
+
+/// Usage statistics of the `events` table, as reported by `PostgreSQL`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableStatistics {
+    /// Estimated number of live rows.
+    pub live_tuples: i64,
+    /// Estimated number of dead rows, which can be reclaimed by a `VACUUM`.
+    pub dead_tuples: i64,
+    /// Size of the table data on disk, in bytes.
+    pub table_size_bytes: i64,
+    /// Size of all the table indexes on disk, in bytes.
+    pub indexes_size_bytes: i64,
+    /// Last time the table has been manually vacuumed, if ever.
+    pub last_vacuum: Option<DateTime<Utc>>,
+    /// Last time the table has been vacuumed by the autovacuum daemon, if ever.
+    pub last_autovacuum: Option<DateTime<Utc>>,
+    /// Statistical correlation between the physical order of the rows and
+    /// the order of the `event_stream_id` column, between -1 and 1.
+    ///
+    /// `None` if the table has not been analyzed yet.
+    pub event_stream_id_correlation: Option<f32>,
+    /// Usage statistics of each index on the table.
+    pub indexes: Vec<IndexStatistics>,
+}
+
+impl TableStatistics {
+    /// Returns the ratio of dead rows over the total number of rows in the table.
+    #[must_use]
+    pub fn dead_tuples_ratio(&self) -> f64 {
+        let total = self.live_tuples + self.dead_tuples;
+
+        if total == 0 {
+            return 0.0;
+        }
+
+        #[allow(clippy::cast_precision_loss)]
+        {
+            self.dead_tuples as f64 / total as f64
+        }
+    }
+}
+
+/// Usage statistics of a single index on the `events` table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexStatistics {
+    /// The name of the index.
+    pub name: String,
+    /// Number of index scans initiated on this index.
+    pub scans: i64,
+    /// Size of the index on disk, in bytes.
+    pub size_bytes: i64,
+}
+
+/// Thresholds used by [`Maintenance::advise`] to decide whether
+/// a maintenance operation should be suggested.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Thresholds {
+    /// Suggest a `VACUUM` when the ratio of dead rows exceeds this value.
+    pub max_dead_tuples_ratio: f64,
+    /// Suggest a `REINDEX` when the size of the primary key index exceeds
+    /// this ratio over the size of the table data.
+    pub max_index_to_table_size_ratio: f64,
+    /// Suggest a `CLUSTER` when the absolute correlation of the `event_stream_id`
+    /// column falls below this value.
+    pub min_event_stream_id_correlation: f32,
+    /// Minimum number of live rows for the table to be considered, as
+    /// maintenance on small tables is seldom worth it.
+    pub min_live_tuples: i64,
+}
+
+impl Default for Thresholds {
+    fn default() -> Self {
+        Self {
+            max_dead_tuples_ratio: 0.2,
+            max_index_to_table_size_ratio: 1.0,
+            min_event_stream_id_correlation: 0.5,
+            min_live_tuples: 10_000,
+        }
+    }
+}
+
+/// A maintenance operation suggested by [`Maintenance::advise`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Advisory {
+    /// Reclaim the space used by dead rows and refresh the planner statistics.
+    Vacuum,
+    /// Rebuild the `(event_stream_id, version)` index to remove bloat.
+    Reindex,
+    /// Physically reorder the table following the `(event_stream_id, version)` index,
+    /// to speed up streaming of long Event Streams.
+    ///
+    /// Note that `CLUSTER` holds an exclusive lock on the table while running.
+    Cluster,
+}
+
+impl Advisory {
+    /// Returns the SQL statement that carries out the maintenance operation.
+    #[must_use]
+    pub fn statement(&self) -> String {
+        match self {
+            Advisory::Vacuum => format!("VACUUM (ANALYZE) {EVENTS_TABLE}"),
+            Advisory::Reindex => format!("REINDEX INDEX CONCURRENTLY {EVENTS_PRIMARY_KEY_INDEX}"),
+            Advisory::Cluster => {
+                format!("CLUSTER {EVENTS_TABLE} USING {EVENTS_PRIMARY_KEY_INDEX}")
+            },
+        }
+    }
+}
+
+/// Administrative handle to inspect and maintain the `events` table
+/// used by the [`event::Store`][crate::event::Store].
+#[derive(Debug, Clone)]
+pub struct Maintenance {
+    pool: PgPool,
+}
+
+impl From<PgPool> for Maintenance {
+    fn from(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+impl Maintenance {
+    /// Returns the current usage statistics of the `events` table.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the statistics could not be queried,
+    /// e.g. if the migrations have not been run yet.
+    pub async fn statistics(&self) -> Result<TableStatistics, sqlx::Error> {
+        let row = sqlx::query(
+            r"SELECT
+                n_live_tup,
+                n_dead_tup,
+                pg_table_size(relid) AS table_size,
+                pg_indexes_size(relid) AS indexes_size,
+                last_vacuum,
+                last_autovacuum
+            FROM pg_stat_user_tables
+            WHERE relid = $1::regclass",
+        )
+        .bind(EVENTS_TABLE)
+        .fetch_one(&self.pool)
+        .await?;
+
+        let event_stream_id_correlation: Option<f32> = sqlx::query_scalar(
+            r"SELECT correlation
+            FROM pg_stats
+            WHERE schemaname = current_schema() AND tablename = $1 AND attname = 'event_stream_id'",
+        )
+        .bind(EVENTS_TABLE)
+        .fetch_optional(&self.pool)
+        .await?
+        .flatten();
+
+        let indexes = sqlx::query(
+            r"SELECT
+                indexrelname,
+                idx_scan,
+                pg_relation_size(indexrelid) AS index_size
+            FROM pg_stat_user_indexes
+            WHERE relid = $1::regclass
+            ORDER BY indexrelname",
+        )
+        .bind(EVENTS_TABLE)
+        .fetch_all(&self.pool)
+        .await?
+        .into_iter()
+        .map(|row| {
+            Ok(IndexStatistics {
+                name: row.try_get("indexrelname")?,
+                scans: row.try_get("idx_scan")?,
+                size_bytes: row.try_get("index_size")?,
+            })
+        })
+        .collect::<Result<Vec<_>, sqlx::Error>>()?;
+
+        Ok(TableStatistics {
+            live_tuples: row.try_get("n_live_tup")?,
+            dead_tuples: row.try_get("n_dead_tup")?,
+            table_size_bytes: row.try_get("table_size")?,
+            indexes_size_bytes: row.try_get("indexes_size")?,
+            last_vacuum: row.try_get("last_vacuum")?,
+            last_autovacuum: row.try_get("last_autovacuum")?,
+            event_stream_id_correlation,
+            indexes,
+        })
+    }
+
+    /// Returns the list of maintenance operations suggested for the
+    /// specified [`TableStatistics`], according to the given [`Thresholds`].
+    #[must_use]
+    pub fn advise(statistics: &TableStatistics, thresholds: &Thresholds) -> Vec<Advisory> {
+        let mut advisories = Vec::new();
+
+        if statistics.live_tuples < thresholds.min_live_tuples {
+            return advisories;
+        }
+
+        if statistics.dead_tuples_ratio() > thresholds.max_dead_tuples_ratio {
+            advisories.push(Advisory::Vacuum);
+        }
+
+        let primary_key_size = statistics
+            .indexes
+            .iter()
+            .find(|index| index.name == EVENTS_PRIMARY_KEY_INDEX)
+            .map_or(0, |index| index.size_bytes);
+
+        #[allow(clippy::cast_precision_loss)]
+        if statistics.table_size_bytes > 0
+            && primary_key_size as f64 / statistics.table_size_bytes as f64
+                > thresholds.max_index_to_table_size_ratio
+        {
+            advisories.push(Advisory::Reindex);
+        }
+
+        if statistics
+            .event_stream_id_correlation
+            .is_some_and(|c| c.abs() < thresholds.min_event_stream_id_correlation)
+        {
+            advisories.push(Advisory::Cluster);
+        }
+
+        advisories
+    }
+
+    /// Executes the specified maintenance operation.
+    ///
+    /// These operations can be expensive on large tables, and some
+    /// (e.g. [`Advisory::Cluster`]) lock the table for their whole duration:
+    /// make sure to run them during low-traffic periods.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the database fails to run the operation.
+    pub async fn execute(&self, advisory: Advisory) -> Result<(), sqlx::Error> {
+        sqlx::query(&advisory.statement())
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,104 @@
+//! This module contains the [`PoolConfig`] type, used to tune the connection pool
+//! shared by the [`event::Store`][crate::event::Store] and
+//! [`aggregate::Repository`][crate::aggregate::Repository] implementations.
+//!
+//! All the queries on the hot append and stream paths use static SQL statements,
+//! which are prepared once per connection and then reused from the connection
+//! statement cache: make sure its capacity, set through
+//! [`PoolConfig::with_statement_cache_capacity`], is large enough to hold them all.
+
+use std::time::Duration;
+
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+use sqlx::PgPool;
+
+/// Default maximum number of connections kept open by the pool.
+pub const DEFAULT_MAX_CONNECTIONS: u32 = 10;
+
+/// Default maximum time spent waiting for a connection to become available.
+pub const DEFAULT_ACQUIRE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Default number of prepared statements cached by each connection.
+pub const DEFAULT_STATEMENT_CACHE_CAPACITY: usize = 100;
+
+/// All possible errors returned while connecting the `PostgreSQL` implementations
+/// through [`PoolConfig`].
+#[derive(Debug, thiserror::Error)]
+pub enum ConnectError {
+    /// Error returned when the connection pool could not be created.
+    #[error("failed to connect to the database: {0}")]
+    Connect(#[source] sqlx::Error),
+    /// Error returned when the migrations failed to run.
+    #[error("failed to run database migrations: {0}")]
+    Migrate(#[from] sqlx::migrate::MigrateError),
+}
+
+/// Configuration of the [`PgPool`] used by the `PostgreSQL` implementations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolConfig {
+    max_connections: u32,
+    acquire_timeout: Duration,
+    statement_cache_capacity: usize,
+}
+
+impl Default for PoolConfig {
+    fn default() -> Self {
+        Self {
+            max_connections: DEFAULT_MAX_CONNECTIONS,
+            acquire_timeout: DEFAULT_ACQUIRE_TIMEOUT,
+            statement_cache_capacity: DEFAULT_STATEMENT_CACHE_CAPACITY,
+        }
+    }
+}
+
+impl PoolConfig {
+    /// Sets the maximum number of connections kept open by the pool.
+    /// Defaults to [`DEFAULT_MAX_CONNECTIONS`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the maximum number of connections is zero.
+    #[must_use]
+    pub fn with_max_connections(mut self, max_connections: u32) -> Self {
+        assert!(
+            max_connections > 0,
+            "max connections must be greater than zero"
+        );
+        self.max_connections = max_connections;
+        self
+    }
+
+    /// Sets the maximum time spent waiting for a connection to become available,
+    /// after which acquiring a connection fails.
+    /// Defaults to [`DEFAULT_ACQUIRE_TIMEOUT`].
+    #[must_use]
+    pub fn with_acquire_timeout(mut self, timeout: Duration) -> Self {
+        self.acquire_timeout = timeout;
+        self
+    }
+
+    /// Sets the number of prepared statements cached by each connection.
+    /// Statements evicted from the cache are prepared again on their next use.
+    /// Defaults to [`DEFAULT_STATEMENT_CACHE_CAPACITY`].
+    #[must_use]
+    pub fn with_statement_cache_capacity(mut self, capacity: usize) -> Self {
+        self.statement_cache_capacity = capacity;
+        self
+    }
+
+    /// Creates a new [`PgPool`] connected to the database at the specified url,
+    /// using this configuration.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the url is invalid, or the database is not reachable.
+    pub async fn connect(&self, url: &str) -> Result<PgPool, sqlx::Error> {
+        let options: PgConnectOptions = url.parse()?;
+
+        PgPoolOptions::new()
+            .max_connections(self.max_connections)
+            .acquire_timeout(self.acquire_timeout)
+            .connect_with(options.statement_cache_capacity(self.statement_cache_capacity))
+            .await
+    }
+}
@@ -0,0 +1,231 @@
+//! This module contains the [`ReadModel`] trait and the [`Projector`] type,
+//! to build read models stored in `PostgreSQL` databases with exactly-once semantics.
+//!
+//! Check out the [Projector] type for more information.
+
+use async_trait::async_trait;
+use eventually::message::Message;
+use eventually::projection::{Checkpointed, Projection, Resettable};
+use eventually::{event, health};
+use sqlx::{PgConnection, PgPool};
+
+use crate::unit_of_work::UnitOfWork;
+
+/// A read model stored in the same `PostgreSQL` database as its checkpoint,
+/// and updated through a [`Projector`].
+#[async_trait]
+pub trait ReadModel<Id, Evt>: Send + Sync
+where
+    Id: Send + Sync,
+    Evt: Message + Send + Sync,
+{
+    /// Applies the Domain Event to the read model, using the connection
+    /// of the transaction that also updates its checkpoint.
+    async fn apply(
+        &self,
+        conn: &mut PgConnection,
+        event: event::Sequenced<Id, Evt>,
+    ) -> anyhow::Result<()>;
+
+    /// Clears the state of the read model, using the connection
+    /// of the transaction that also removes its checkpoint.
+    async fn reset(&self, conn: &mut PgConnection) -> anyhow::Result<()>;
+}
+
+/// All possible errors returned by the [`Projector`].
+#[derive(Debug, thiserror::Error)]
+pub enum ReadModelError {
+    /// Error returned when the [`ReadModel`] failed to apply a Domain Event.
+    #[error("failed to apply domain event to the read model: {0}")]
+    Apply(#[source] anyhow::Error),
+    /// Error returned when the [`ReadModel`] could not be reset.
+    #[error("failed to reset the read model: {0}")]
+    Reset(#[source] anyhow::Error),
+    /// Error returned when the database returned an error.
+    #[error("db returned an error: {0}")]
+    Database(#[source] sqlx::Error),
+}
+
+/// Implements the [`Projection`] trait for a [`ReadModel`], updating it
+/// and its checkpoint, stored in the `projection_checkpoints` table,
+/// in the same database transaction.
+///
+/// Domain Events with a [Sequence][event::Sequence] number not greater than
+/// the checkpoint are skipped, so that redeliveries, e.g. from an at-least-once
+/// subscription or after a crash, are never applied twice. This relies on
+/// the [Sequence][event::Sequence] numbers being assigned in commit order,
+/// as required by [`GlobalStreamer`][eventually::event::store::GlobalStreamer].
+///
+/// The checkpoint row is locked for the whole transaction, and created beforehand
+/// if missing, so that concurrent [`Projector`]s of the same read model
+/// apply the Domain Events one at a time.
+/// A batch of Domain Events passed to [`Projection::project_batch`]
+/// is applied in a single transaction.
+#[derive(Debug, Clone)]
+pub struct Projector<M> {
+    pool: PgPool,
+    name: String,
+    model: M,
+}
+
+impl<M> Projector<M> {
+    /// Runs the latest migrations necessary for the implementation to work,
+    /// then returns a new [`Projector`] instance for the [`ReadModel`],
+    /// whose checkpoint is stored under the specified unique name.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the migrations fail to run.
+    pub async fn new(
+        pool: PgPool,
+        name: impl Into<String>,
+        model: M,
+    ) -> Result<Self, sqlx::migrate::MigrateError> {
+        // Make sure the latest migrations are used before using the Projector instance.
+        crate::MIGRATIONS.run(&pool).await?;
+
+        Ok(Self {
+            pool,
+            name: name.into(),
+            model,
+        })
+    }
+
+    /// Returns the name the checkpoint of the [`ReadModel`] is stored under.
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the projected [`ReadModel`].
+    #[must_use]
+    pub fn model(&self) -> &M {
+        &self.model
+    }
+
+    async fn apply_all<Id, Evt>(
+        &self,
+        events: Vec<event::Sequenced<Id, Evt>>,
+    ) -> Result<(), ReadModelError>
+    where
+        Id: Send + Sync,
+        Evt: Message + Send + Sync,
+        M: ReadModel<Id, Evt>,
+    {
+        let mut unit_of_work = UnitOfWork::begin(&self.pool)
+            .await
+            .map_err(ReadModelError::Database)?;
+
+        // NOTE: locking the checkpoint serializes the Projectors of the same read model.
+        let checkpoint = crate::checkpoint::lock_checkpoint(unit_of_work.connection(), &self.name)
+            .await
+            .map_err(ReadModelError::Database)?;
+
+        let mut last_sequence = None;
+
+        for event in events {
+            if checkpoint.is_some_and(|checkpoint| event.sequence <= checkpoint) {
+                continue;
+            }
+
+            last_sequence = last_sequence.max(Some(event.sequence));
+
+            self.model
+                .apply(unit_of_work.connection(), event)
+                .await
+                .map_err(ReadModelError::Apply)?;
+        }
+
+        // All the Domain Events have already been applied: dropping the
+        // UnitOfWork rolls back the empty transaction.
+        let Some(last_sequence) = last_sequence else {
+            return Ok(());
+        };
+
+        crate::checkpoint::save_checkpoint(unit_of_work.connection(), &self.name, last_sequence)
+            .await
+            .map_err(ReadModelError::Database)?;
+
+        unit_of_work
+            .commit()
+            .await
+            .map_err(ReadModelError::Database)
+    }
+}
+
+#[async_trait]
+impl<M, Id, Evt> Projection<Id, Evt> for Projector<M>
+where
+    M: ReadModel<Id, Evt>,
+    Id: Send + Sync + 'static,
+    Evt: Message + Send + Sync + 'static,
+{
+    type Error = ReadModelError;
+
+    async fn project(&self, event: event::Sequenced<Id, Evt>) -> Result<(), Self::Error> {
+        self.apply_all(vec![event]).await
+    }
+
+    async fn project_batch(&self, events: Vec<event::Sequenced<Id, Evt>>) -> Result<(), Self::Error>
+    where
+        Id: 'async_trait,
+        Evt: 'async_trait,
+    {
+        self.apply_all(events).await
+    }
+}
+
+#[async_trait]
+impl<M, Id, Evt> Resettable<Id, Evt> for Projector<M>
+where
+    M: ReadModel<Id, Evt>,
+    Id: Send + Sync + 'static,
+    Evt: Message + Send + Sync + 'static,
+{
+    async fn reset(&self) -> Result<(), Self::Error> {
+        let mut unit_of_work = UnitOfWork::begin(&self.pool)
+            .await
+            .map_err(ReadModelError::Database)?;
+
+        self.model
+            .reset(unit_of_work.connection())
+            .await
+            .map_err(ReadModelError::Reset)?;
+
+        sqlx::query("DELETE FROM projection_checkpoints WHERE projection = $1")
+            .bind(&self.name)
+            .execute(unit_of_work.connection())
+            .await
+            .map_err(ReadModelError::Database)?;
+
+        unit_of_work
+            .commit()
+            .await
+            .map_err(ReadModelError::Database)
+    }
+}
+
+#[async_trait]
+impl<M> Checkpointed for Projector<M>
+where
+    M: Send + Sync,
+{
+    type Error = ReadModelError;
+
+    async fn checkpoint(&self) -> Result<Option<event::Sequence>, Self::Error> {
+        crate::checkpoint::load_checkpoint(&self.pool, &self.name)
+            .await
+            .map_err(ReadModelError::Database)
+    }
+}
+
+#[async_trait]
+impl<M> health::Check for Projector<M>
+where
+    M: Send + Sync,
+{
+    /// Verifies the database backing the [`Projector`] is reachable.
+    async fn check(&self) -> anyhow::Result<()> {
+        crate::ping(&self.pool).await
+    }
+}
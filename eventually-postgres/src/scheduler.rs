@@ -0,0 +1,187 @@
+//! This module contains the implementation of the
+//! [`eventually::command::scheduler::Schedule`] trait,
+//! to work specifically with `PostgreSQL` databases.
+//!
+//! Check out the [Schedule] type for more information.
+
+use std::marker::PhantomData;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use eventually::command::scheduler::{self, Scheduled};
+use eventually::command::Envelope;
+use eventually::message::{Message, Metadata};
+use eventually::{health, serde};
+use sqlx::{PgPool, Row};
+
+/// All possible errors returned by the [Schedule].
+#[derive(Debug, thiserror::Error)]
+pub enum ScheduleError {
+    /// Error returned when the Command could not be serialized.
+    #[error("failed to serialize command: {0}")]
+    SerializeCommand(#[source] anyhow::Error),
+    /// Error returned when the Command payload could not be deserialized.
+    #[error("failed to deserialize command from database: {0}")]
+    DeserializeCommand(#[source] anyhow::Error),
+    /// Error returned when the database returned an error.
+    #[error("db returned an error: {0}")]
+    Database(#[from] sqlx::Error),
+}
+
+/// Implements the [`scheduler::Schedule`] trait for `PostgreSQL` databases,
+/// storing the scheduled Commands in the `scheduled_commands` table.
+///
+/// Due Commands are claimed with `FOR UPDATE SKIP LOCKED`, so multiple
+/// [Pollers][scheduler::Poller] can share the same [Schedule] safely.
+///
+/// Since the table is shared by all the Command types, each [Schedule]
+/// only claims the Commands with the [name][Message::name] it has been created with.
+#[derive(Debug, Clone)]
+pub struct Schedule<T, Serde>
+where
+    Serde: serde::Serde<T>,
+{
+    pool: PgPool,
+    serde: Serde,
+    name: String,
+    t: PhantomData<T>,
+}
+
+impl<T, Serde> Schedule<T, Serde>
+where
+    Serde: serde::Serde<T>,
+{
+    /// Runs the latest migrations necessary for the implementation to work,
+    /// then returns a new [`Schedule`] instance for the Commands with the specified name.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the migrations fail to run.
+    pub async fn new(
+        pool: PgPool,
+        serde: Serde,
+        name: impl Into<String>,
+    ) -> Result<Self, sqlx::migrate::MigrateError> {
+        // Make sure the latest migrations are used before using the Schedule instance.
+        crate::MIGRATIONS.run(&pool).await?;
+
+        Ok(Self {
+            pool,
+            serde,
+            name: name.into(),
+            t: PhantomData,
+        })
+    }
+}
+
+#[async_trait]
+impl<T, Serde> scheduler::Schedule<T> for Schedule<T, Serde>
+where
+    T: Message + Send + Sync,
+    Serde: serde::Serde<T> + Send + Sync,
+{
+    type Error = ScheduleError;
+
+    async fn schedule(&self, command: Envelope<T>, at: DateTime<Utc>) -> Result<(), Self::Error> {
+        let payload = self
+            .serde
+            .serialize(command.message)
+            .map_err(ScheduleError::SerializeCommand)?;
+
+        sqlx::query(
+            r#"INSERT INTO scheduled_commands ("name", payload, metadata, run_at)
+               VALUES ($1, $2, $3, $4)"#,
+        )
+        .bind(&self.name)
+        .bind(payload)
+        .bind(sqlx::types::Json(command.metadata))
+        .bind(at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn claim_due(
+        &self,
+        now: DateTime<Utc>,
+        retry_at: DateTime<Utc>,
+        limit: usize,
+    ) -> Result<Vec<Scheduled<T>>, Self::Error> {
+        let rows = sqlx::query(
+            r#"WITH due AS (
+                   SELECT id, run_at FROM scheduled_commands
+                   WHERE "name" = $1 AND run_at <= $2
+                   ORDER BY run_at, id
+                   LIMIT $4
+                   FOR UPDATE SKIP LOCKED
+               )
+               UPDATE scheduled_commands SET run_at = $3
+               FROM due
+               WHERE scheduled_commands.id = due.id
+               RETURNING scheduled_commands.id, payload, metadata, due.run_at AS due_at"#,
+        )
+        .bind(&self.name)
+        .bind(now)
+        .bind(retry_at)
+        .bind(i64::try_from(limit).unwrap_or(i64::MAX))
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut claimed = rows
+            .into_iter()
+            .map(|row| {
+                let id: i64 = row.try_get("id")?;
+                let due_at: DateTime<Utc> = row.try_get("due_at")?;
+                let payload: Vec<u8> = row.try_get("payload")?;
+                let metadata: sqlx::types::Json<Metadata> = row.try_get("metadata")?;
+
+                let message = self
+                    .serde
+                    .deserialize(&payload)
+                    .map_err(ScheduleError::DeserializeCommand)?;
+
+                #[allow(clippy::cast_sign_loss)]
+                let scheduled = Scheduled {
+                    id: id as u64,
+                    command: Envelope {
+                        message,
+                        metadata: metadata.0,
+                    },
+                };
+
+                Ok((due_at, scheduled))
+            })
+            .collect::<Result<Vec<_>, ScheduleError>>()?;
+
+        // NOTE: the order of the rows returned by UPDATE is not guaranteed.
+        claimed.sort_by_key(|(due_at, scheduled)| (*due_at, scheduled.id));
+
+        Ok(claimed
+            .into_iter()
+            .map(|(_, scheduled)| scheduled)
+            .collect())
+    }
+
+    async fn complete(&self, id: u64) -> Result<(), Self::Error> {
+        #[allow(clippy::cast_possible_wrap)]
+        sqlx::query("DELETE FROM scheduled_commands WHERE id = $1")
+            .bind(id as i64)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<T, Serde> health::Check for Schedule<T, Serde>
+where
+    T: Send + Sync,
+    Serde: serde::Serde<T> + Send + Sync,
+{
+    /// Verifies the database backing the [`Schedule`] is reachable.
+    async fn check(&self) -> anyhow::Result<()> {
+        crate::ping(&self.pool).await
+    }
+}
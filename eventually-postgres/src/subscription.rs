@@ -0,0 +1,532 @@
+//! This module contains a live subscription to the Domain Events appended
+//! to the `events` table, based on `PostgreSQL` `LISTEN/NOTIFY`.
+//!
+//! Check out the [Subscriber] type for more information.
+
+use std::collections::{BTreeSet, VecDeque};
+use std::marker::PhantomData;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::Utc;
+use eventually::aggregate::IdSerde;
+use eventually::message::{Message, Metadata};
+use eventually::{event, health, serde, subscription};
+use futures::stream::{self, StreamExt, TryStreamExt};
+use sqlx::postgres::PgListener;
+use sqlx::PgPool;
+
+use crate::event::StreamError;
+
+/// Name of the channel used by the `events` table trigger to notify new Domain Events.
+const NOTIFICATION_CHANNEL: &str = "eventually_events";
+
+/// Name of the `events` table trigger that notifies new Domain Events.
+const NOTIFICATION_TRIGGER: &str = "events_notify_appended";
+
+/// Default interval a [Subscriber] with fallback polling waits for a notification
+/// before polling the `events` table, right after a Domain Event has been found.
+pub const DEFAULT_MIN_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Default interval a [Subscriber] with fallback polling backs off to,
+/// while no Domain Event is found by polling the `events` table.
+pub const DEFAULT_MAX_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Maximum number of Domain Events read from the `events` table in a single query.
+const POLL_PAGE_SIZE: i64 = 1000;
+
+/// Number of Sequence numbers remembered by a subscription with fallback polling,
+/// to avoid delivering the same Domain Event twice.
+const DELIVERED_WINDOW: usize = 10_000;
+
+/// All possible errors returned by [`Subscriber`] while streaming live Domain Events.
+#[derive(Debug, thiserror::Error)]
+pub enum SubscriptionError {
+    /// Error returned when the notification payload could not be decoded.
+    #[error("failed to decode event notification payload: {0}")]
+    DecodeNotification(#[source] serde_json::Error),
+    /// Error returned when the notified Domain Event could not be read.
+    #[error("failed to read notified event: {0}")]
+    Stream(#[from] StreamError),
+    /// Error returned when the database returned an error.
+    #[error("db returned an error: {0}")]
+    Database(#[source] sqlx::Error),
+}
+
+#[derive(Debug, ::serde::Deserialize)]
+struct Notification {
+    sequence: i64,
+    stream_type: Option<String>,
+}
+
+/// Metrics of the Domain Events delivered by the subscriptions of a [Subscriber],
+/// returned by [`Subscriber::metrics`].
+///
+/// Counters are cumulative since the creation of the [Subscriber],
+/// and shared with its clones.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SubscriptionMetrics {
+    /// Number of Domain Events delivered.
+    pub delivered: u64,
+    /// Number of Domain Events delivered by the fallback polling,
+    /// since their notification has been missed.
+    pub missed_notifications: u64,
+    /// Number of delivered Domain Events whose delivery delay has been measured,
+    /// i.e. the ones with the [`event::RECORDED_AT_KEY`] metadata entry.
+    pub measured: u64,
+    /// Sum of the delays between the time the measured Domain Events
+    /// have been recorded and their delivery.
+    pub total_delivery_delay: Duration,
+    /// Longest delay between the time a measured Domain Event
+    /// has been recorded and its delivery.
+    pub max_delivery_delay: Duration,
+}
+
+impl SubscriptionMetrics {
+    /// Returns the mean delay between the time the measured Domain Events
+    /// have been recorded and their delivery, if any has been measured.
+    #[must_use]
+    pub fn mean_delivery_delay(&self) -> Option<Duration> {
+        let measured = u32::try_from(self.measured).unwrap_or(u32::MAX);
+
+        (measured > 0).then(|| self.total_delivery_delay / measured)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct FallbackPolling {
+    min_interval: Duration,
+    max_interval: Duration,
+}
+
+struct PollingState {
+    listener: PgListener,
+    stream_type: Option<String>,
+    interval: Duration,
+    /// The greatest Sequence number delivered so far, or the last one
+    /// in the `events` table when the subscription started.
+    watermark: i64,
+    delivered: BTreeSet<i64>,
+}
+
+impl PollingState {
+    /// Records the Sequence number as delivered, returning `false`
+    /// if it had already been delivered.
+    fn deliver(&mut self, sequence: i64) -> bool {
+        if !self.is_pending(sequence) {
+            return false;
+        }
+
+        self.delivered.insert(sequence);
+        self.watermark = self.watermark.max(sequence);
+
+        while self.delivered.len() > DELIVERED_WINDOW {
+            self.delivered.pop_first();
+        }
+
+        true
+    }
+
+    fn is_pending(&self, sequence: i64) -> bool {
+        !self.delivered.contains(&sequence)
+            && (self.delivered.len() < DELIVERED_WINDOW
+                || self
+                    .delivered
+                    .first()
+                    .is_some_and(|first| sequence > *first))
+    }
+}
+
+/// Live subscription to the Domain Events appended to the `events` table,
+/// used by an [`event::Store`][crate::event::Store]
+/// or an [`aggregate::Repository`][crate::aggregate::Repository].
+///
+/// Domain Events are delivered in commit order, as soon as the transaction
+/// that appended them has been committed, so projectors no longer need to poll.
+///
+/// The subscription only delivers Domain Events committed after it has started
+/// listening: Domain Events committed while the connection is being
+/// re-established are not delivered, unless fallback polling is enabled
+/// with [`Subscriber::with_fallback_polling`].
+///
+/// The number of delivered Domain Events and their delivery delay
+/// are recorded in the [`SubscriptionMetrics`].
+#[derive(Debug, Clone)]
+pub struct Subscriber<Id, Evt, Serde>
+where
+    Serde: serde::Deserializer<Evt>,
+{
+    pool: PgPool,
+    serde: Serde,
+    metadata_filter: Option<Metadata>,
+    fallback_polling: Option<FallbackPolling>,
+    metrics: Arc<Mutex<SubscriptionMetrics>>,
+    id_type: PhantomData<Id>,
+    evt_type: PhantomData<Evt>,
+}
+
+impl<Id, Evt, Serde> Subscriber<Id, Evt, Serde>
+where
+    Serde: serde::Deserializer<Evt>,
+{
+    /// Runs the latest migrations necessary for the implementation to work,
+    /// then returns a new [`Subscriber`] instance.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the migrations fail to run.
+    pub async fn new(pool: PgPool, serde: Serde) -> Result<Self, sqlx::migrate::MigrateError> {
+        // Make sure the latest migrations are used before using the Subscriber instance.
+        crate::MIGRATIONS.run(&pool).await?;
+
+        Ok(Self {
+            pool,
+            serde,
+            metadata_filter: None,
+            fallback_polling: None,
+            metrics: Arc::default(),
+            id_type: PhantomData,
+            evt_type: PhantomData,
+        })
+    }
+
+    /// Only delivers the Domain Events whose metadata contains the specified entry,
+    /// e.g. the ones of a single tenant or correlated to the same request.
+    ///
+    /// The filter is applied by the database, and multiple filters must all match.
+    #[must_use]
+    pub fn with_metadata_filter(mut self, key: String, value: String) -> Self {
+        self.metadata_filter
+            .get_or_insert_with(Metadata::default)
+            .insert(key, value);
+        self
+    }
+
+    /// Polls the `events` table for the Domain Events following the last delivered one
+    /// whenever no notification is received for a while, so that the delivery latency
+    /// stays bounded even when notifications are dropped, e.g. on connection resets.
+    ///
+    /// The polling interval starts from `min_interval`, e.g. [`DEFAULT_MIN_POLL_INTERVAL`],
+    /// and doubles up to `max_interval`, e.g. [`DEFAULT_MAX_POLL_INTERVAL`],
+    /// while no Domain Event is found. It is reset to `min_interval` whenever
+    /// a notification is received or a Domain Event is found.
+    ///
+    /// Domain Events committed after others holding greater Sequence numbers
+    /// are only delivered through their notification.
+    #[must_use]
+    pub fn with_fallback_polling(mut self, min_interval: Duration, max_interval: Duration) -> Self {
+        self.fallback_polling = Some(FallbackPolling {
+            min_interval,
+            max_interval: max_interval.max(min_interval),
+        });
+        self
+    }
+
+    /// Returns the [`SubscriptionMetrics`] of the Domain Events delivered so far.
+    #[must_use]
+    pub fn metrics(&self) -> SubscriptionMetrics {
+        *self.lock_metrics()
+    }
+
+    // NOTE: metrics are only updated with simple additions, so they are still
+    // consistent even if a thread panicked while holding the lock.
+    fn lock_metrics(&self) -> MutexGuard<'_, SubscriptionMetrics> {
+        self.metrics.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<Id, Evt, Serde> Subscriber<Id, Evt, Serde>
+where
+    Id: IdSerde + Send + Sync,
+    Evt: Message + Send + Sync,
+    Serde: serde::Deserializer<Evt> + Send + Sync,
+{
+    /// Opens a live stream of the Domain Events appended to the Event Streams
+    /// of the Aggregate Roots of the specified type,
+    /// i.e. [`Aggregate::type_name`][eventually::aggregate::Aggregate::type_name].
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the subscription could not start listening
+    /// for new Domain Events.
+    pub async fn subscribe(
+        &self,
+        stream_type: &str,
+    ) -> Result<event::SequencedStream<'_, Id, Evt, SubscriptionError>, SubscriptionError> {
+        self.listen(Some(stream_type.to_owned())).await
+    }
+
+    async fn listen(
+        &self,
+        stream_type: Option<String>,
+    ) -> Result<event::SequencedStream<'_, Id, Evt, SubscriptionError>, SubscriptionError> {
+        let mut listener = PgListener::connect_with(&self.pool)
+            .await
+            .map_err(SubscriptionError::Database)?;
+
+        listener
+            .listen(NOTIFICATION_CHANNEL)
+            .await
+            .map_err(SubscriptionError::Database)?;
+
+        if let Some(fallback_polling) = self.fallback_polling {
+            return self
+                .listen_with_fallback_polling(listener, stream_type, fallback_polling)
+                .await;
+        }
+
+        Ok(listener
+            .into_stream()
+            .map_err(SubscriptionError::Database)
+            .try_filter_map(move |notification| {
+                let stream_type = stream_type.clone();
+
+                async move {
+                    let notification: Notification =
+                        serde_json::from_str(notification.payload())
+                            .map_err(SubscriptionError::DecodeNotification)?;
+
+                    if stream_type.is_some() && notification.stream_type != stream_type {
+                        return Ok(None);
+                    }
+
+                    self.fetch_event(notification.sequence).await
+                }
+            })
+            .inspect_ok(move |event| self.record_delivery(event, false))
+            .boxed())
+    }
+
+    async fn listen_with_fallback_polling(
+        &self,
+        listener: PgListener,
+        stream_type: Option<String>,
+        fallback_polling: FallbackPolling,
+    ) -> Result<event::SequencedStream<'_, Id, Evt, SubscriptionError>, SubscriptionError> {
+        // NOTE: the last Sequence number is read after listening, so that the Domain Events
+        // committed in the meantime are either notified or already in the table.
+        let watermark: i64 =
+            sqlx::query_scalar(r#"SELECT COALESCE(MAX("sequence"), 0) FROM events"#)
+                .fetch_one(&self.pool)
+                .await
+                .map_err(SubscriptionError::Database)?;
+
+        let state = PollingState {
+            listener,
+            stream_type,
+            interval: fallback_polling.min_interval,
+            watermark,
+            delivered: BTreeSet::new(),
+        };
+
+        Ok(stream::try_unfold(
+            (state, VecDeque::new()),
+            move |(mut state, mut page)| async move {
+                loop {
+                    if let Some((event, missed_notification)) = page.pop_front() {
+                        self.record_delivery(&event, missed_notification);
+                        return Ok(Some((event, (state, page))));
+                    }
+
+                    let received =
+                        tokio::time::timeout(state.interval, state.listener.try_recv()).await;
+
+                    match received {
+                        Ok(Ok(Some(notification))) => {
+                            state.interval = fallback_polling.min_interval;
+
+                            let notification: Notification =
+                                serde_json::from_str(notification.payload())
+                                    .map_err(SubscriptionError::DecodeNotification)?;
+
+                            if state.stream_type.is_some()
+                                && notification.stream_type != state.stream_type
+                            {
+                                continue;
+                            }
+
+                            if notification.sequence > state.watermark {
+                                page = self.poll(&mut state, false).await?;
+                            } else if state.is_pending(notification.sequence) {
+                                // The Domain Event has been committed late.
+                                let event = self.fetch_event(notification.sequence).await?;
+
+                                if let Some(event) = event {
+                                    if state.deliver(notification.sequence) {
+                                        page.push_back((event, false));
+                                    }
+                                }
+                            }
+                        },
+                        // The connection has been lost and re-established on the next receive:
+                        // the notifications sent in the meantime have been dropped.
+                        Ok(Ok(None)) => {
+                            state.interval = fallback_polling.min_interval;
+                            page = self.poll(&mut state, true).await?;
+                        },
+                        Ok(Err(err)) => return Err(SubscriptionError::Database(err)),
+                        // No notification has been received for a whole polling interval.
+                        Err(_) => {
+                            page = self.poll(&mut state, true).await?;
+
+                            state.interval = if page.is_empty() {
+                                (state.interval * 2).min(fallback_polling.max_interval)
+                            } else {
+                                fallback_polling.min_interval
+                            };
+                        },
+                    }
+                }
+            },
+        )
+        .boxed())
+    }
+
+    /// Reads the Domain Events following the last delivered one,
+    /// flagging whether their notification has been missed.
+    async fn poll(
+        &self,
+        state: &mut PollingState,
+        missed_notification: bool,
+    ) -> Result<VecDeque<(event::Sequenced<Id, Evt>, bool)>, SubscriptionError> {
+        let mut page = VecDeque::new();
+
+        loop {
+            let rows = sqlx::query(
+                r#"SELECT e.event_stream_id, e."version", e.event, e.metadata, e."sequence"
+                   FROM events e
+                   WHERE e."sequence" > $1
+                     AND ($2::jsonb IS NULL OR e.metadata @> $2::jsonb)
+                     AND ($3::text IS NULL OR EXISTS (
+                         SELECT 1 FROM aggregates a
+                         WHERE a.aggregate_id = e.event_stream_id AND a."type" = $3
+                     ))
+                   ORDER BY e."sequence"
+                   LIMIT $4"#,
+            )
+            .bind(state.watermark)
+            .bind(self.metadata_filter.as_ref().map(sqlx::types::Json))
+            .bind(state.stream_type.as_deref())
+            .bind(POLL_PAGE_SIZE)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(SubscriptionError::Database)?;
+
+            #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+            let has_next_page = rows.len() as i64 == POLL_PAGE_SIZE;
+
+            for row in &rows {
+                let event = crate::event::event_row_to_sequenced_event(&self.serde, row)?;
+
+                #[allow(clippy::cast_possible_wrap)]
+                if state.deliver(event.sequence as i64) {
+                    page.push_back((event, missed_notification));
+                }
+            }
+
+            if !has_next_page {
+                return Ok(page);
+            }
+        }
+    }
+
+    fn record_delivery(&self, event: &event::Sequenced<Id, Evt>, missed_notification: bool) {
+        let delay = event
+            .event
+            .recorded_at()
+            .map(|recorded_at| (Utc::now() - recorded_at).to_std().unwrap_or_default());
+
+        let mut metrics = self.lock_metrics();
+        metrics.delivered += 1;
+
+        if missed_notification {
+            metrics.missed_notifications += 1;
+        }
+
+        if let Some(delay) = delay {
+            metrics.measured += 1;
+            metrics.total_delivery_delay += delay;
+            metrics.max_delivery_delay = metrics.max_delivery_delay.max(delay);
+        }
+    }
+
+    async fn fetch_event(
+        &self,
+        sequence: i64,
+    ) -> Result<Option<event::Sequenced<Id, Evt>>, SubscriptionError> {
+        let row = sqlx::query(
+            r#"SELECT event_stream_id, version, event, metadata, "sequence"
+               FROM events
+               WHERE "sequence" = $1 AND ($2::jsonb IS NULL OR metadata @> $2::jsonb)"#,
+        )
+        .bind(sequence)
+        .bind(self.metadata_filter.as_ref().map(sqlx::types::Json))
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(SubscriptionError::Database)?;
+
+        // NOTE: the Domain Event might have been deleted in the meantime,
+        // or filtered out by its metadata.
+        let Some(row) = row else {
+            return Ok(None);
+        };
+
+        Ok(Some(crate::event::event_row_to_sequenced_event(
+            &self.serde,
+            &row,
+        )?))
+    }
+}
+
+#[async_trait]
+impl<Id, Evt, Serde> subscription::Subscriber<Id, Evt> for Subscriber<Id, Evt, Serde>
+where
+    Id: IdSerde + Send + Sync,
+    Evt: Message + Send + Sync,
+    Serde: serde::Deserializer<Evt> + Send + Sync,
+{
+    type Error = SubscriptionError;
+
+    /// Opens a live stream of all the Domain Events appended to the `events` table.
+    ///
+    /// All the Domain Events are deserialized using the [`serde::Deserializer`]
+    /// of the [`Subscriber`], so this is best suited for databases used
+    /// by a single Domain Event type.
+    async fn subscribe_all(
+        &self,
+    ) -> Result<event::SequencedStream<'_, Id, Evt, Self::Error>, Self::Error> {
+        self.listen(None).await
+    }
+}
+
+#[async_trait]
+impl<Id, Evt, Serde> health::Check for Subscriber<Id, Evt, Serde>
+where
+    Id: Send + Sync,
+    Evt: Send + Sync,
+    Serde: serde::Deserializer<Evt> + Send + Sync,
+{
+    /// Verifies the database is reachable, and that the `events` table trigger
+    /// notifying new Domain Events is installed, as without it
+    /// the subscriptions would never receive any Domain Event.
+    async fn check(&self) -> anyhow::Result<()> {
+        crate::ping(&self.pool).await?;
+
+        let installed: bool = sqlx::query_scalar(
+            "SELECT EXISTS (SELECT 1 FROM pg_trigger WHERE tgname = $1 AND NOT tgisinternal)",
+        )
+        .bind(NOTIFICATION_TRIGGER)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|err| anyhow::anyhow!("failed to look up the notification trigger: {err}"))?;
+
+        if !installed {
+            return Err(anyhow::anyhow!(
+                "notification trigger '{NOTIFICATION_TRIGGER}' is missing from the events table"
+            ));
+        }
+
+        Ok(())
+    }
+}
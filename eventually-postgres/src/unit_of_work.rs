@@ -0,0 +1,71 @@
+//! This module contains the [`UnitOfWork`] type, used to save Aggregate Roots
+//! or append Domain Events in the same database transaction as other
+//! application-level SQL statements (e.g. updating a read model).
+//!
+//! Check out the [`UnitOfWork`] type for more information.
+
+use sqlx::{PgConnection, PgPool, Postgres, Transaction};
+
+/// A database transaction shared between the transaction-scoped variants of the
+/// [`event::Store`][crate::event::Store] and [`aggregate::Repository`][crate::aggregate::Repository]
+/// APIs, i.e. [`Store::append_in`][crate::event::Store::append_in] and
+/// [`Repository::save_in`][crate::aggregate::Repository::save_in],
+/// and the application's own SQL statements, run through [`UnitOfWork::connection`].
+///
+/// Nothing is persisted until [`UnitOfWork::commit`] is called:
+/// dropping the [`UnitOfWork`] rolls back the transaction.
+///
+/// Like the non-transactional APIs, the transaction uses the `SERIALIZABLE` isolation level,
+/// so that concurrent writes to the same Event Stream are detected as conflicts.
+#[derive(Debug)]
+pub struct UnitOfWork<'c> {
+    tx: Transaction<'c, Postgres>,
+}
+
+impl UnitOfWork<'static> {
+    /// Begins a new [`UnitOfWork`] on a connection from the pool.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the transaction could not be started.
+    pub async fn begin(pool: &PgPool) -> Result<Self, sqlx::Error> {
+        let mut tx = pool.begin().await?;
+
+        sqlx::query("SET TRANSACTION ISOLATION LEVEL SERIALIZABLE DEFERRABLE")
+            .execute(&mut *tx)
+            .await?;
+
+        Ok(Self { tx })
+    }
+}
+
+impl<'c> UnitOfWork<'c> {
+    /// Returns the connection of the transaction, to run the application's
+    /// own SQL statements as part of the [`UnitOfWork`].
+    pub fn connection(&mut self) -> &mut PgConnection {
+        &mut self.tx
+    }
+
+    pub(crate) fn transaction(&mut self) -> &mut Transaction<'c, Postgres> {
+        &mut self.tx
+    }
+
+    /// Commits all the changes made as part of the [`UnitOfWork`].
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the transaction could not be committed,
+    /// e.g. because of a serialization failure with a concurrent transaction.
+    pub async fn commit(self) -> Result<(), sqlx::Error> {
+        self.tx.commit().await
+    }
+
+    /// Discards all the changes made as part of the [`UnitOfWork`].
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the transaction could not be rolled back.
+    pub async fn rollback(self) -> Result<(), sqlx::Error> {
+        self.tx.rollback().await
+    }
+}
@@ -1,6 +1,7 @@
-use eventually::aggregate::repository::{self, GetError, Getter, Saver};
+use eventually::aggregate::repository::{self, Deleter, GetError, Getter, Saver};
 use eventually::serde;
 use eventually_postgres::aggregate;
+use eventually_postgres::unit_of_work::UnitOfWork;
 use rand::Rng;
 
 mod setup;
@@ -28,11 +29,8 @@ async fn it_works() {
 
     match result {
         GetError::NotFound => (),
-        _ => panic!(
-            "unexpected error received, should be 'not found': {:?}",
-            result
-        ),
-    };
+        _ => panic!("unexpected error received, should be 'not found': {result:?}"),
+    }
 
     let mut root = setup::TestAggregateRoot::create(aggregate_id, "John Dee".to_owned())
         .expect("aggregate root should be created");
@@ -88,9 +86,149 @@ async fn it_detects_data_races_and_returns_conflict_error() {
     match result {
         (Ok(()), Err(repository::SaveError::Conflict(_))) => (),
         (Err(repository::SaveError::Conflict(_)), Ok(())) => (),
-        (first, second) => panic!(
-            "invalid state detected, first: {:?}, second: {:?}",
-            first, second
-        ),
+        (first, second) => panic!("invalid state detected, first: {first:?}, second: {second:?}"),
+    }
+}
+
+#[tokio::test]
+async fn save_in_unit_of_work_commits_together_with_application_statements() {
+    let pool = setup::connect_to_database()
+        .await
+        .expect("connection to the database should work");
+
+    let aggregate_repository = aggregate::Repository::new(
+        pool.clone(),
+        serde::Json::<setup::TestAggregate>::default(),
+        serde::Json::<setup::TestDomainEvent>::default(),
+    )
+    .await
+    .unwrap();
+
+    sqlx::query("CREATE TABLE IF NOT EXISTS test_read_model_names (id BIGINT PRIMARY KEY, name TEXT NOT NULL)")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let id = rand::thread_rng().gen::<i64>();
+    let aggregate_id = setup::TestAggregateId(id);
+
+    let save_with_read_model = |name: &'static str| {
+        let aggregate_repository = aggregate_repository.clone();
+        let pool = pool.clone();
+
+        async move {
+            let mut root = setup::TestAggregateRoot::create(aggregate_id, name.to_owned()).unwrap();
+            let mut unit_of_work = UnitOfWork::begin(&pool).await.unwrap();
+
+            aggregate_repository
+                .save_in(&mut unit_of_work, &mut root)
+                .await
+                .expect("saving in the unit of work should not fail");
+
+            sqlx::query("INSERT INTO test_read_model_names (id, name) VALUES ($1, $2)")
+                .bind(id)
+                .bind(name)
+                .execute(unit_of_work.connection())
+                .await
+                .unwrap();
+
+            unit_of_work
+        }
     };
+
+    save_with_read_model("John Dee")
+        .await
+        .rollback()
+        .await
+        .unwrap();
+
+    assert!(matches!(
+        aggregate_repository.get(&aggregate_id).await,
+        Err(GetError::NotFound)
+    ));
+
+    save_with_read_model("Jane Dee")
+        .await
+        .commit()
+        .await
+        .unwrap();
+
+    let found_root = aggregate_repository
+        .get(&aggregate_id)
+        .await
+        .expect("the aggregate root should be found after the commit");
+
+    assert_eq!(1, found_root.version());
+
+    let name: String = sqlx::query_scalar("SELECT name FROM test_read_model_names WHERE id = $1")
+        .bind(id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+
+    assert_eq!("Jane Dee", name);
+}
+
+#[tokio::test]
+async fn deleted_aggregate_root_is_not_found() {
+    let pool = setup::connect_to_database()
+        .await
+        .expect("connection to the database should work");
+
+    let aggregate_repository = aggregate::Repository::new(
+        pool,
+        serde::Json::<setup::TestAggregate>::default(),
+        serde::Json::<setup::TestDomainEvent>::default(),
+    )
+    .await
+    .unwrap();
+
+    let aggregate_id = setup::TestAggregateId(rand::thread_rng().gen::<i64>());
+
+    let mut root = setup::TestAggregateRoot::create(aggregate_id, "John Dee".to_owned())
+        .expect("aggregate root should be created");
+
+    aggregate_repository
+        .save(&mut root)
+        .await
+        .expect("storing the new aggregate root should be successful");
+
+    let stale_root = aggregate_repository
+        .get(&aggregate_id)
+        .await
+        .expect("the aggregate root should be found successfully");
+
+    root.delete().unwrap();
+
+    aggregate_repository
+        .save(&mut root)
+        .await
+        .expect("storing the aggregate root should be successful");
+
+    let error = aggregate_repository
+        .delete(stale_root)
+        .await
+        .expect_err("the aggregate root has been modified in the meantime");
+
+    assert!(matches!(error, repository::DeleteError::Conflict(_)));
+
+    aggregate_repository
+        .delete(root.clone().into())
+        .await
+        .expect("the aggregate root should be deleted successfully");
+
+    let result = aggregate_repository
+        .get(&aggregate_id)
+        .await
+        .expect_err("the aggregate root should not be found");
+
+    assert!(matches!(result, GetError::NotFound));
+
+    let mut recreated_root = setup::TestAggregateRoot::create(aggregate_id, "John Dee".to_owned())
+        .expect("aggregate root should be created");
+
+    aggregate_repository
+        .save(&mut recreated_root)
+        .await
+        .expect_err("the event stream of a deleted aggregate root is closed");
 }
@@ -0,0 +1,27 @@
+use eventually::subscription::CheckpointStore;
+use eventually_postgres::checkpoint;
+use rand::Rng;
+
+mod setup;
+
+#[tokio::test]
+async fn store_saves_the_checkpoint_of_each_subscription() {
+    let pool = setup::connect_to_database()
+        .await
+        .expect("connection to the database should work");
+
+    let store = checkpoint::Store::new(pool).await.unwrap();
+    let name = format!("test-subscription-{}", rand::thread_rng().gen::<i64>());
+
+    assert_eq!(None, store.load(&name).await.unwrap());
+
+    store.save(&name, 1).await.unwrap();
+    store.save(&name, 42).await.unwrap();
+
+    assert_eq!(Some(42), store.load(&name).await.unwrap());
+
+    // Checkpoints never move backwards.
+    store.save(&name, 7).await.unwrap();
+    assert_eq!(Some(42), store.load(&name).await.unwrap());
+    assert_eq!(None, store.load(&format!("{name}-other")).await.unwrap());
+}
@@ -1,12 +1,22 @@
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-use eventually::event::store::{self, AppendError, Appender, Streamer};
-use eventually::event::{Persisted, VersionSelect};
+use eventually::aggregate::repository::Saver;
+use eventually::aggregate::Aggregate;
+use eventually::clock::TestClock;
+use eventually::event::ordering::StrictOrdering;
+use eventually::event::store::{
+    self, AppendError, Appender, GlobalStreamer, Locker, StreamDeleter, StreamTombstonedError,
+    Streamer, TombstoneError, Tombstoner, TypeStreamer,
+};
+use eventually::event::{NameSelect, Persisted, SequenceSelect, VersionSelect};
 use eventually::version::Version;
 use eventually::{serde, version};
-use eventually_postgres::event;
+use eventually_postgres::pool::PoolConfig;
+use eventually_postgres::unit_of_work::UnitOfWork;
+use eventually_postgres::{aggregate, event};
 use futures::TryStreamExt;
 use rand::Rng;
+use sqlx::postgres::PgPoolOptions;
 
 mod setup;
 
@@ -21,7 +31,7 @@ async fn append_with_no_version_check_works() {
         .unwrap();
 
     let id = rand::thread_rng().gen::<i64>();
-    let event_stream_id = format!("test-event-stream-{}", id);
+    let event_stream_id = format!("test-event-stream-{id}");
 
     let expected_events = vec![setup::TestDomainEvent::WasCreated {
         id: setup::TestAggregateId(id),
@@ -77,7 +87,7 @@ async fn it_works_with_version_check_for_conflict() {
         .unwrap();
 
     let id = rand::thread_rng().gen::<i64>();
-    let event_stream_id = format!("test-event-stream-{}", id);
+    let event_stream_id = format!("test-event-stream-{id}");
 
     let expected_events = vec![setup::TestDomainEvent::WasCreated {
         id: setup::TestAggregateId(id),
@@ -138,7 +148,7 @@ async fn it_works_with_version_check_for_conflict() {
         );
     }
 
-    panic!("unexpected error received: {}", error);
+    panic!("unexpected error received: {error}");
 }
 
 #[tokio::test]
@@ -152,7 +162,7 @@ async fn it_handles_concurrent_writes_to_the_same_stream() {
         .unwrap();
 
     let id = rand::thread_rng().gen::<i64>();
-    let event_stream_id = format!("test-event-stream-{}", id);
+    let event_stream_id = format!("test-event-stream-{id}");
 
     let expected_events = vec![setup::TestDomainEvent::WasCreated {
         id: setup::TestAggregateId(id),
@@ -182,9 +192,962 @@ async fn it_handles_concurrent_writes_to_the_same_stream() {
         | (Err(store::AppendError::Conflict(_)), Ok(_)) => {
             // This is the expected scenario :)
         },
-        (first, second) => panic!(
-            "invalid state detected, first: {:?}, second: {:?}",
-            first, second
-        ),
+        (first, second) => panic!("invalid state detected, first: {first:?}, second: {second:?}"),
+    }
+}
+
+#[tokio::test]
+async fn append_multi_does_not_append_anything_when_a_version_check_fails() {
+    let pool = setup::connect_to_database()
+        .await
+        .expect("connection to the database should work");
+
+    let event_store = event::Store::new(pool, serde::Json::<setup::TestDomainEvent>::default())
+        .await
+        .unwrap();
+
+    let id = rand::thread_rng().gen::<i64>();
+    let sender_stream_id = format!("test-event-stream-sender-{id}");
+    let receiver_stream_id = format!("test-event-stream-receiver-{id}");
+
+    let events: Vec<_> = vec![setup::TestDomainEvent::WasCreated {
+        id: setup::TestAggregateId(id),
+        name: "test something".to_owned(),
+        at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_millis(),
+    }
+    .into()];
+
+    let error = event_store
+        .append_multi(vec![
+            store::StreamAppend {
+                id: sender_stream_id.clone(),
+                version_check: version::Check::MustBe(0),
+                events: events.clone(),
+            },
+            store::StreamAppend {
+                id: receiver_stream_id.clone(),
+                version_check: version::Check::MustBe(1),
+                events: events.clone(),
+            },
+        ])
+        .await
+        .expect_err("the receiver event stream should not exist");
+
+    assert!(matches!(error, AppendError::Conflict(_)));
+
+    let sender_events: Vec<_> = event_store
+        .stream(&sender_stream_id, VersionSelect::All)
+        .try_collect()
+        .await
+        .expect("opening an event stream should not fail");
+
+    assert!(sender_events.is_empty());
+
+    let new_versions = event_store
+        .append_multi(vec![
+            store::StreamAppend {
+                id: sender_stream_id.clone(),
+                version_check: version::Check::MustBe(0),
+                events: events.clone(),
+            },
+            store::StreamAppend {
+                id: receiver_stream_id.clone(),
+                version_check: version::Check::MustBe(0),
+                events,
+            },
+        ])
+        .await
+        .expect("append_multi should not fail");
+
+    assert_eq!(vec![1, 1], new_versions);
+}
+
+#[tokio::test]
+async fn append_multi_inserts_all_domain_events_in_order() {
+    let pool = setup::connect_to_database()
+        .await
+        .expect("connection to the database should work");
+
+    let event_store = event::Store::new(pool, serde::Json::<setup::TestDomainEvent>::default())
+        .await
+        .unwrap();
+
+    let id = rand::thread_rng().gen::<i64>();
+    let stream_ids = [
+        format!("test-event-stream-batch-a-{id}"),
+        format!("test-event-stream-batch-b-{id}"),
+    ];
+
+    let events = |stream: &str| -> Vec<_> {
+        (0..50)
+            .map(|i| {
+                setup::TestDomainEvent::WasCreated {
+                    id: setup::TestAggregateId(id),
+                    name: format!("{stream}-{i}"),
+                    at: 0,
+                }
+                .into()
+            })
+            .collect()
+    };
+
+    let new_versions = event_store
+        .append_multi(
+            stream_ids
+                .iter()
+                .map(|stream_id| store::StreamAppend {
+                    id: stream_id.clone(),
+                    version_check: version::Check::MustBe(0),
+                    events: events(stream_id),
+                })
+                .collect(),
+        )
+        .await
+        .expect("append_multi should not fail");
+
+    assert_eq!(vec![50, 50], new_versions);
+
+    let persisted: Vec<_> = event_store
+        .stream_all(SequenceSelect::All)
+        .try_filter(|event| futures::future::ready(stream_ids.contains(&event.event.stream_id)))
+        .try_collect()
+        .await
+        .expect("streaming all the events should not fail");
+
+    assert!(persisted
+        .windows(2)
+        .all(|pair| pair[0].sequence < pair[1].sequence));
+
+    assert_eq!(
+        stream_ids
+            .iter()
+            .flat_map(|stream_id| (1..=50).map(move |version| (stream_id.clone(), version)))
+            .collect::<Vec<_>>(),
+        persisted
+            .into_iter()
+            .map(|event| (event.event.stream_id, event.event.version))
+            .collect::<Vec<_>>()
+    );
+}
+
+#[tokio::test]
+async fn truncate_and_delete_remove_domain_events_from_the_event_stream() {
+    let pool = setup::connect_to_database()
+        .await
+        .expect("connection to the database should work");
+
+    let event_store = event::Store::new(pool, serde::Json::<setup::TestDomainEvent>::default())
+        .await
+        .unwrap();
+
+    let id = rand::thread_rng().gen::<i64>();
+    let event_stream_id = format!("test-event-stream-{id}");
+
+    let events: Vec<_> = vec![
+        setup::TestDomainEvent::WasCreated {
+            id: setup::TestAggregateId(id),
+            name: "test something".to_owned(),
+            at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_millis(),
+        }
+        .into(),
+        setup::TestDomainEvent::WasDeleted {
+            id: setup::TestAggregateId(id),
+        }
+        .into(),
+    ];
+
+    event_store
+        .append(event_stream_id.clone(), version::Check::MustBe(0), events)
+        .await
+        .expect("append should not fail");
+
+    event_store
+        .truncate(&event_stream_id, 2)
+        .await
+        .expect("truncate should not fail");
+
+    let versions: Vec<Version> = event_store
+        .stream(&event_stream_id, VersionSelect::All)
+        .map_ok(|event| event.version)
+        .try_collect()
+        .await
+        .expect("opening an event stream should not fail");
+
+    assert_eq!(vec![2], versions);
+
+    event_store
+        .delete(&event_stream_id)
+        .await
+        .expect("delete should not fail");
+
+    let remaining_events: Vec<_> = event_store
+        .stream(&event_stream_id, VersionSelect::All)
+        .try_collect()
+        .await
+        .expect("opening an event stream should not fail");
+
+    assert!(remaining_events.is_empty());
+}
+
+#[tokio::test]
+async fn lock_excludes_other_lockers_until_the_advisory_lock_is_released() {
+    let pool = setup::connect_to_database()
+        .await
+        .expect("connection to the database should work");
+
+    let event_store = event::Store::new(pool, serde::Json::<setup::TestDomainEvent>::default())
+        .await
+        .unwrap();
+
+    let id = rand::thread_rng().gen::<i64>();
+    let event_stream_id = format!("test-event-stream-{id}");
+
+    let lock = event_store
+        .lock(&event_stream_id)
+        .await
+        .expect("lock should not fail");
+
+    assert!(
+        tokio::time::timeout(
+            Duration::from_millis(100),
+            event_store.lock(&event_stream_id)
+        )
+        .await
+        .is_err(),
+        "the event stream should not be locked twice"
+    );
+
+    lock.release().await.expect("release should not fail");
+
+    let lock = event_store
+        .lock(&event_stream_id)
+        .await
+        .expect("lock should not fail after the release");
+
+    drop(lock);
+
+    event_store
+        .lock(&event_stream_id)
+        .await
+        .expect("lock should not fail after the lock is dropped")
+        .release()
+        .await
+        .expect("release should not fail");
+}
+
+#[tokio::test]
+async fn sequence_numbers_are_assigned_in_commit_order() {
+    let pool = setup::connect_to_database()
+        .await
+        .expect("connection to the database should work");
+
+    let event_store = event::Store::new(
+        pool.clone(),
+        serde::Json::<setup::TestDomainEvent>::default(),
+    )
+    .await
+    .unwrap();
+
+    let id = rand::thread_rng().gen::<i64>();
+    let first_stream_id = format!("test-event-stream-{id}-first");
+    let second_stream_id = format!("test-event-stream-{id}-second");
+
+    let event = |id: i64| -> eventually::event::Envelope<setup::TestDomainEvent> {
+        setup::TestDomainEvent::WasCreated {
+            id: setup::TestAggregateId(id),
+            name: "test something".to_owned(),
+            at: 0,
+        }
+        .into()
+    };
+
+    // The first transaction inserts its Domain Event, but does not commit yet.
+    let mut unit_of_work = UnitOfWork::begin(&pool).await.unwrap();
+
+    event_store
+        .append_in(
+            &mut unit_of_work,
+            first_stream_id.clone(),
+            version::Check::MustBe(0),
+            vec![event(id)],
+        )
+        .await
+        .expect("append should not fail");
+
+    // The second transaction cannot take the next sequence number and commit
+    // before the first one, as it would become visible ahead of the first Domain Event.
+    let second_append = tokio::spawn({
+        let event_store = event_store.clone();
+        let second_stream_id = second_stream_id.clone();
+
+        async move {
+            event_store
+                .append(second_stream_id, version::Check::MustBe(0), vec![event(id)])
+                .await
+        }
+    });
+
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(
+        !second_append.is_finished(),
+        "the second append should wait for the first transaction to end"
+    );
+
+    unit_of_work.commit().await.expect("commit should not fail");
+
+    second_append
+        .await
+        .unwrap()
+        .expect("append should not fail after the first transaction has committed");
+
+    let sequence_of = |stream_id: String| {
+        sqlx::query_scalar::<_, i64>(r#"SELECT "sequence" FROM events WHERE event_stream_id = $1"#)
+            .bind(stream_id)
+            .fetch_one(&pool)
+    };
+
+    assert!(
+        sequence_of(first_stream_id).await.unwrap() < sequence_of(second_stream_id).await.unwrap()
+    );
+}
+
+#[tokio::test]
+async fn stream_paginates_through_the_whole_event_stream() {
+    let pool = setup::connect_to_database()
+        .await
+        .expect("connection to the database should work");
+
+    let event_store = StrictOrdering::from(
+        event::Store::new(pool, serde::Json::<setup::TestDomainEvent>::default())
+            .await
+            .unwrap()
+            .with_stream_page_size(2),
+    );
+
+    let id = rand::thread_rng().gen::<i64>();
+    let event_stream_id = format!("test-event-stream-{id}");
+
+    let events: Vec<_> = (0..5)
+        .map(|_| {
+            setup::TestDomainEvent::WasDeleted {
+                id: setup::TestAggregateId(id),
+            }
+            .into()
+        })
+        .collect();
+
+    event_store
+        .append(event_stream_id.clone(), version::Check::MustBe(0), events)
+        .await
+        .expect("append should not fail");
+
+    let versions: Vec<Version> = event_store
+        .stream(&event_stream_id, VersionSelect::All)
+        .map_ok(|event| event.version)
+        .try_collect()
+        .await
+        .expect("opening an event stream should not fail");
+
+    assert_eq!(vec![1, 2, 3, 4, 5], versions);
+
+    let versions: Vec<Version> = event_store
+        .stream(&event_stream_id, VersionSelect::From(4))
+        .map_ok(|event| event.version)
+        .try_collect()
+        .await
+        .expect("opening an event stream should not fail");
+
+    assert_eq!(vec![4, 5], versions);
+}
+
+#[tokio::test]
+async fn stream_fails_when_the_next_page_is_not_fetched_within_the_idle_timeout() {
+    let url = std::env::var("DATABASE_URL").expect("the env var DATABASE_URL is required");
+    let pool = PgPoolOptions::new()
+        .max_connections(1)
+        .connect(&url)
+        .await
+        .expect("connection to the database should work");
+
+    let event_store = event::Store::new(
+        pool.clone(),
+        serde::Json::<setup::TestDomainEvent>::default(),
+    )
+    .await
+    .unwrap()
+    .with_statement_timeout(Duration::from_secs(5))
+    .with_stream_idle_timeout(Duration::from_millis(100));
+
+    let id = rand::thread_rng().gen::<i64>();
+    let event_stream_id = format!("test-event-stream-{id}");
+
+    event_store
+        .append(
+            event_stream_id.clone(),
+            version::Check::MustBe(0),
+            vec![setup::TestDomainEvent::WasDeleted {
+                id: setup::TestAggregateId(id),
+            }
+            .into()],
+        )
+        .await
+        .expect("append should not fail");
+
+    let events: Vec<_> = event_store
+        .stream(&event_stream_id, VersionSelect::All)
+        .try_collect()
+        .await
+        .expect("opening an event stream should not fail");
+
+    assert_eq!(1, events.len());
+
+    // NOTE: holding the only connection of the pool makes the next page wait forever.
+    let _connection = pool.acquire().await.expect("connection should be acquired");
+
+    let error = event_store
+        .stream(&event_stream_id, VersionSelect::All)
+        .try_collect::<Vec<_>>()
+        .await
+        .expect_err("the event stream should time out");
+
+    assert!(matches!(
+        error,
+        event::StreamError::Timeout(timeout) if timeout == Duration::from_millis(100)
+    ));
+}
+
+#[tokio::test]
+async fn stream_restores_events_persisted_with_their_envelope() {
+    let pool = setup::connect_to_database()
+        .await
+        .expect("connection to the database should work");
+
+    let event_store = event::Store::new(
+        pool.clone(),
+        serde::Json::<setup::TestDomainEvent>::default(),
+    )
+    .await
+    .unwrap()
+    .with_envelope_schema_version(2);
+
+    let id = rand::thread_rng().gen::<i64>();
+    let event_stream_id = format!("test-event-stream-{id}");
+
+    let event = eventually::event::Envelope::from(setup::TestDomainEvent::WasDeleted {
+        id: setup::TestAggregateId(id),
+    })
+    .with_metadata("Tenant-Id".to_owned(), "tenant-1".to_owned());
+
+    event_store
+        .append(
+            event_stream_id.clone(),
+            version::Check::MustBe(0),
+            vec![event.clone()],
+        )
+        .await
+        .expect("append should not fail");
+
+    // Domain Events persisted with their envelope are readable by any Event Store.
+    let payload_only_event_store =
+        event::Store::new(pool, serde::Json::<setup::TestDomainEvent>::default())
+            .await
+            .unwrap();
+
+    let persisted_events: Vec<_> = payload_only_event_store
+        .stream(&event_stream_id, VersionSelect::All)
+        .try_collect()
+        .await
+        .expect("opening an event stream should not fail");
+
+    assert_eq!(1, persisted_events.len());
+
+    let persisted_event = &persisted_events[0].event;
+    assert_eq!(event, *persisted_event);
+    assert_eq!(
+        Some("tenant-1"),
+        persisted_event
+            .metadata
+            .get("Tenant-Id")
+            .map(String::as_str)
+    );
+    assert_eq!(
+        Some("2"),
+        persisted_event
+            .metadata
+            .get(serde::SCHEMA_VERSION_KEY)
+            .map(String::as_str)
+    );
+}
+
+#[tokio::test]
+async fn appended_events_are_stamped_with_the_clock_time() {
+    let pool = setup::connect_to_database()
+        .await
+        .expect("connection to the database should work");
+
+    let recorded_at = chrono::DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+
+    let event_store = event::Store::new(pool, serde::Json::<setup::TestDomainEvent>::default())
+        .await
+        .unwrap()
+        .with_clock(TestClock::new(recorded_at));
+
+    let id = rand::thread_rng().gen::<i64>();
+    let event_stream_id = format!("test-event-stream-{id}");
+
+    event_store
+        .append(
+            event_stream_id.clone(),
+            version::Check::MustBe(0),
+            vec![setup::TestDomainEvent::WasDeleted {
+                id: setup::TestAggregateId(id),
+            }
+            .into()],
+        )
+        .await
+        .expect("append should not fail");
+
+    let persisted_events: Vec<_> = event_store
+        .stream(&event_stream_id, VersionSelect::All)
+        .try_collect()
+        .await
+        .expect("opening an event stream should not fail");
+
+    assert_eq!(1, persisted_events.len());
+    assert_eq!(Some(recorded_at), persisted_events[0].recorded_at());
+}
+
+#[tokio::test]
+async fn stream_by_type_streams_only_events_of_the_specified_aggregate_type() {
+    let pool = setup::connect_to_database()
+        .await
+        .expect("connection to the database should work");
+
+    let aggregate_repository = aggregate::Repository::new(
+        pool.clone(),
+        serde::Json::<setup::TestAggregate>::default(),
+        serde::Json::<setup::TestDomainEvent>::default(),
+    )
+    .await
+    .unwrap();
+
+    let event_store = event::Store::new(pool, serde::Json::<setup::TestDomainEvent>::default())
+        .await
+        .unwrap();
+
+    let event_stream_id = format!("test-event-stream-{}", rand::thread_rng().gen::<i64>());
+    let aggregate_id = setup::TestAggregateId(rand::thread_rng().gen::<i64>());
+
+    // Events appended to an Event Stream not owned by an Aggregate Root have no type.
+    event_store
+        .append(
+            event_stream_id.clone(),
+            version::Check::MustBe(0),
+            vec![setup::TestDomainEvent::WasDeleted { id: aggregate_id }.into()],
+        )
+        .await
+        .expect("append should not fail");
+
+    let mut root = setup::TestAggregateRoot::create(aggregate_id, "John Dee".to_owned())
+        .expect("aggregate root should be created");
+
+    aggregate_repository
+        .save(&mut root)
+        .await
+        .expect("storing the new aggregate root should be successful");
+
+    let expected_stream_ids = [event_stream_id, aggregate_id.to_string()];
+    let stream_ids: Vec<String> = event_store
+        .stream_by_type(setup::TestAggregate::type_name(), SequenceSelect::All)
+        .map_ok(|event| event.event.stream_id)
+        .try_filter(|stream_id| futures::future::ready(expected_stream_ids.contains(stream_id)))
+        .try_collect()
+        .await
+        .expect("streaming by type should not fail");
+
+    assert_eq!(vec![aggregate_id.to_string()], stream_ids);
+}
+
+#[tokio::test]
+async fn stream_filtered_selects_only_events_with_the_specified_names() {
+    let pool = setup::connect_to_database()
+        .await
+        .expect("connection to the database should work");
+
+    let event_store = event::Store::new(pool, serde::Json::<setup::TestDomainEvent>::default())
+        .await
+        .unwrap()
+        .with_stream_page_size(1);
+
+    let id = rand::thread_rng().gen::<i64>();
+    let event_stream_id = format!("test-event-stream-{id}");
+
+    event_store
+        .append(
+            event_stream_id.clone(),
+            version::Check::MustBe(0),
+            vec![
+                setup::TestDomainEvent::WasCreated {
+                    id: setup::TestAggregateId(id),
+                    name: "test something".to_owned(),
+                    at: 0,
+                }
+                .into(),
+                setup::TestDomainEvent::WasDeleted {
+                    id: setup::TestAggregateId(id),
+                }
+                .into(),
+            ],
+        )
+        .await
+        .expect("append should not fail");
+
+    let names = NameSelect::only(["TestDomainSomethingWasDeleted"]);
+
+    let versions: Vec<Version> = event_store
+        .stream_filtered(&event_stream_id, VersionSelect::All, names.clone())
+        .map_ok(|event| event.version)
+        .try_collect()
+        .await
+        .expect("opening an event stream should not fail");
+
+    assert_eq!(vec![2], versions);
+
+    let versions: Vec<Version> = event_store
+        .stream_all_filtered(SequenceSelect::All, names)
+        .try_filter(|event| futures::future::ready(event.event.stream_id == event_stream_id))
+        .map_ok(|event| event.event.version)
+        .try_collect()
+        .await
+        .expect("streaming all events should not fail");
+
+    assert_eq!(vec![2], versions);
+}
+
+#[tokio::test]
+async fn stream_parses_event_stream_ids_into_typed_aggregate_ids() {
+    let pool = setup::connect_to_database()
+        .await
+        .expect("connection to the database should work");
+
+    let aggregate_repository = aggregate::Repository::new(
+        pool.clone(),
+        serde::Json::<setup::TestAggregate>::default(),
+        serde::Json::<setup::TestDomainEvent>::default(),
+    )
+    .await
+    .unwrap();
+
+    let event_store = event::Store::<setup::TestAggregateId, _, _>::new(
+        pool,
+        serde::Json::<setup::TestDomainEvent>::default(),
+    )
+    .await
+    .unwrap();
+
+    let aggregate_id = setup::TestAggregateId(rand::thread_rng().gen::<i64>());
+    let mut root = setup::TestAggregateRoot::create(aggregate_id, "John Dee".to_owned())
+        .expect("aggregate root should be created");
+
+    root.delete().unwrap();
+
+    aggregate_repository
+        .save(&mut root)
+        .await
+        .expect("storing the new aggregate root should be successful");
+
+    let events: Vec<_> = event_store
+        .stream_by_type(setup::TestAggregate::type_name(), SequenceSelect::All)
+        .map_ok(|event| event.event)
+        .try_filter(|event| futures::future::ready(event.stream_id == aggregate_id))
+        .try_collect()
+        .await
+        .expect("streaming by type should not fail");
+
+    assert_eq!(2, events.len());
+    assert_eq!(
+        setup::TestDomainEvent::WasDeleted { id: aggregate_id },
+        events[1].event.message
+    );
+    assert_eq!(
+        Ok(aggregate_id),
+        aggregate_id
+            .to_string()
+            .parse::<setup::TestAggregateId>()
+            .map_err(|err| err.to_string())
+    );
+    assert!("test-event-stream-1"
+        .parse::<setup::TestAggregateId>()
+        .is_err());
+}
+
+#[tokio::test]
+async fn event_store_passes_the_conformance_suite() {
+    let pool = setup::connect_to_database()
+        .await
+        .expect("connection to the database should work");
+
+    let event_store = event::Store::new(
+        pool,
+        serde::Json::<eventually::event::test::ConformanceEvent>::default(),
+    )
+    .await
+    .unwrap();
+
+    let conformance = eventually::event::test::Conformance::new(event_store);
+
+    conformance.run().await;
+    conformance.clean_up().await.unwrap();
+}
+
+#[tokio::test]
+async fn append_reuses_the_prepared_statements_of_the_connection() {
+    let url = std::env::var("DATABASE_URL").expect("the env var DATABASE_URL is required");
+    let pool = PoolConfig::default()
+        .with_max_connections(1)
+        .with_acquire_timeout(Duration::from_secs(5))
+        .connect(&url)
+        .await
+        .expect("connection to the database should work");
+
+    let event_store = event::Store::new(
+        pool.clone(),
+        serde::Json::<setup::TestDomainEvent>::default(),
+    )
+    .await
+    .unwrap();
+
+    let id = rand::thread_rng().gen::<i64>();
+    let event_stream_id = format!("test-event-stream-{id}");
+
+    for _ in 0..3 {
+        event_store
+            .append(
+                event_stream_id.clone(),
+                version::Check::Any,
+                vec![setup::TestDomainEvent::WasDeleted {
+                    id: setup::TestAggregateId(id),
+                }
+                .into()],
+            )
+            .await
+            .expect("the event store should append the events");
+    }
+
+    // NOTE: prepared statements are local to the connection, which is
+    // the only one in the pool, and the same used by the appends above.
+    let prepared: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM pg_prepared_statements WHERE statement LIKE 'INSERT INTO events %'",
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+
+    assert_eq!(1, prepared);
+}
+
+#[tokio::test]
+async fn stream_reads_from_the_read_pool_with_read_your_writes() {
+    let url = std::env::var("DATABASE_URL").expect("the env var DATABASE_URL is required");
+    let pool = setup::connect_to_database()
+        .await
+        .expect("connection to the database should work");
+    let read_pool = PoolConfig::default()
+        .with_max_connections(1)
+        .with_acquire_timeout(Duration::from_millis(200))
+        .connect(&url)
+        .await
+        .expect("connection to the database should work");
+
+    let event_store = event::Store::new(pool, serde::Json::<setup::TestDomainEvent>::default())
+        .await
+        .unwrap()
+        .with_read_pool(read_pool.clone())
+        .with_read_your_writes();
+
+    let id = rand::thread_rng().gen::<i64>();
+    let event_stream_id = format!("test-event-stream-{id}");
+
+    // NOTE: holding the only connection of the read pool makes the reads time out,
+    // while appends go to the primary pool.
+    let connection = read_pool.acquire().await.unwrap();
+
+    event_store
+        .append(
+            event_stream_id.clone(),
+            version::Check::MustBe(0),
+            vec![setup::TestDomainEvent::WasDeleted {
+                id: setup::TestAggregateId(id),
+            }
+            .into()],
+        )
+        .await
+        .expect("the event store should append the events");
+
+    let result = event_store
+        .stream(&event_stream_id, VersionSelect::All)
+        .try_collect::<Vec<_>>()
+        .await;
+
+    assert!(matches!(
+        result,
+        Err(event::StreamError::Database(sqlx::Error::PoolTimedOut))
+    ));
+
+    drop(connection);
+
+    let events = event_store
+        .stream(&event_stream_id, VersionSelect::All)
+        .try_collect::<Vec<_>>()
+        .await
+        .expect("the event store should stream the events back");
+
+    assert_eq!(1, events.len());
+}
+
+#[tokio::test]
+async fn stream_reads_from_the_primary_when_the_written_wal_position_is_unknown() {
+    let url = std::env::var("DATABASE_URL").expect("the env var DATABASE_URL is required");
+    let pool = setup::connect_to_database()
+        .await
+        .expect("connection to the database should work");
+
+    // NOTE: shadowing pg_current_wal_lsn() on the primary pool makes reading
+    // the WAL position after an append fail, while the append itself succeeds.
+    sqlx::query("CREATE SCHEMA IF NOT EXISTS test_failing_wal_lsn")
+        .execute(&pool)
+        .await
+        .unwrap();
+    sqlx::query(
+        r#"CREATE OR REPLACE FUNCTION test_failing_wal_lsn.pg_current_wal_lsn() RETURNS pg_lsn
+           LANGUAGE PLPGSQL AS $$ BEGIN RAISE EXCEPTION 'wal position unavailable'; END; $$"#,
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let primary_pool = PgPoolOptions::new()
+        .after_connect(|conn, _| {
+            Box::pin(async move {
+                sqlx::query("SET search_path = test_failing_wal_lsn, public, pg_catalog")
+                    .execute(conn)
+                    .await
+                    .map(|_| ())
+            })
+        })
+        .connect(&url)
+        .await
+        .expect("connection to the database should work");
+    let read_pool = PoolConfig::default()
+        .with_max_connections(1)
+        .with_acquire_timeout(Duration::from_millis(200))
+        .connect(&url)
+        .await
+        .expect("connection to the database should work");
+
+    let event_store = event::Store::new(
+        primary_pool,
+        serde::Json::<setup::TestDomainEvent>::default(),
+    )
+    .await
+    .unwrap()
+    .with_read_pool(read_pool.clone())
+    .with_read_your_writes();
+
+    let id = rand::thread_rng().gen::<i64>();
+    let event_stream_id = format!("test-event-stream-{id}");
+
+    event_store
+        .append(
+            event_stream_id.clone(),
+            version::Check::MustBe(0),
+            vec![setup::TestDomainEvent::WasDeleted {
+                id: setup::TestAggregateId(id),
+            }
+            .into()],
+        )
+        .await
+        .expect("the event store should append the events");
+
+    // NOTE: holding the only connection of the read pool makes the reads
+    // time out, if they are routed there.
+    let _connection = read_pool.acquire().await.unwrap();
+
+    let events = event_store
+        .stream(&event_stream_id, VersionSelect::All)
+        .try_collect::<Vec<_>>()
+        .await
+        .expect("the event store should read from the primary");
+
+    assert_eq!(1, events.len());
+}
+
+#[tokio::test]
+async fn tombstone_hides_the_event_stream_and_rejects_new_events() {
+    let pool = setup::connect_to_database()
+        .await
+        .expect("connection to the database should work");
+
+    let event_store = event::Store::new(pool, serde::Json::<setup::TestDomainEvent>::default())
+        .await
+        .unwrap();
+
+    let id = rand::thread_rng().gen::<i64>();
+    let event_stream_id = format!("test-event-stream-{id}");
+
+    let events = || {
+        vec![setup::TestDomainEvent::WasDeleted {
+            id: setup::TestAggregateId(id),
+        }
+        .into()]
     };
+
+    event_store
+        .append(event_stream_id.clone(), version::Check::MustBe(0), events())
+        .await
+        .expect("the event store should append the events");
+
+    let error = event_store
+        .tombstone(&event_stream_id, version::Check::MustBe(2))
+        .await
+        .expect_err("the event stream is at a different version");
+
+    assert!(matches!(
+        error,
+        TombstoneError::Conflict(version::ConflictError {
+            expected: 2,
+            actual: 1
+        })
+    ));
+
+    event_store
+        .tombstone(&event_stream_id, version::Check::MustBe(1))
+        .await
+        .expect("the event stream should be tombstoned");
+
+    let persisted_events = event_store
+        .stream(&event_stream_id, VersionSelect::All)
+        .try_collect::<Vec<_>>()
+        .await
+        .expect("the event store should stream the events back");
+
+    assert!(persisted_events.is_empty());
+
+    for version_check in [version::Check::Any, version::Check::MustBe(1)] {
+        let error = event_store
+            .append(event_stream_id.clone(), version_check, events())
+            .await
+            .expect_err("the event stream has been tombstoned");
+
+        assert!(matches!(
+            error,
+            AppendError::Internal(err) if err.is::<StreamTombstonedError>()
+        ));
+    }
 }
@@ -0,0 +1,129 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use eventually::command::handler::{IdempotencyStore, Idempotent, Reservation};
+use eventually::command::{Envelope, Handler};
+use eventually::message::Message;
+use eventually_postgres::idempotency;
+use rand::Rng;
+
+mod setup;
+
+#[derive(Debug, Clone)]
+struct CreateSomething;
+
+impl Message for CreateSomething {
+    fn name(&self) -> &'static str {
+        "CreateSomething"
+    }
+}
+
+#[tokio::test]
+async fn store_reserves_each_key_until_completed_or_released() {
+    let pool = setup::connect_to_database()
+        .await
+        .expect("connection to the database should work");
+
+    let store = idempotency::Store::new(pool).await.unwrap();
+    let key = format!("key-{}", rand::thread_rng().gen::<i64>());
+    let lease = Duration::from_secs(30);
+
+    let reserve = |command_name: &'static str, lease: Duration| {
+        let store = &store;
+        let key = &key;
+
+        async move { store.reserve(command_name, key, lease).await.unwrap() }
+    };
+
+    assert_eq!(
+        Reservation::Acquired,
+        reserve("CreateSomething", lease).await
+    );
+    assert_eq!(
+        Reservation::InFlight,
+        reserve("CreateSomething", lease).await
+    );
+    assert_eq!(
+        Reservation::Acquired,
+        reserve("DeleteSomething", lease).await
+    );
+
+    store.release("CreateSomething", &key).await.unwrap();
+    assert_eq!(
+        Reservation::Acquired,
+        reserve("CreateSomething", lease).await
+    );
+
+    store.complete("CreateSomething", &key).await.unwrap();
+    assert_eq!(
+        Reservation::Completed,
+        reserve("CreateSomething", lease).await
+    );
+
+    // Completed keys are not released anymore.
+    store.release("CreateSomething", &key).await.unwrap();
+    assert_eq!(
+        Reservation::Completed,
+        reserve("CreateSomething", lease).await
+    );
+}
+
+#[tokio::test]
+async fn store_reserves_a_key_again_once_its_lease_has_expired() {
+    let pool = setup::connect_to_database()
+        .await
+        .expect("connection to the database should work");
+
+    let store = idempotency::Store::new(pool).await.unwrap();
+    let key = format!("key-{}", rand::thread_rng().gen::<i64>());
+
+    assert_eq!(
+        Reservation::Acquired,
+        store
+            .reserve("CreateSomething", &key, Duration::from_millis(50))
+            .await
+            .unwrap()
+    );
+
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    assert_eq!(
+        Reservation::Acquired,
+        store
+            .reserve("CreateSomething", &key, Duration::from_secs(30))
+            .await
+            .unwrap()
+    );
+}
+
+#[tokio::test]
+async fn idempotent_handler_short_circuits_duplicate_commands() {
+    let pool = setup::connect_to_database()
+        .await
+        .expect("connection to the database should work");
+
+    let handled = Arc::new(AtomicUsize::default());
+    let handler = Idempotent::new(
+        |_: Envelope<CreateSomething>| {
+            let handled = handled.clone();
+
+            async move {
+                handled.fetch_add(1, Ordering::SeqCst);
+                Ok::<(), anyhow::Error>(())
+            }
+        },
+        idempotency::Store::new(pool).await.unwrap(),
+    );
+
+    let key = format!("key-{}", rand::thread_rng().gen::<i64>());
+
+    for _ in 0..3 {
+        handler
+            .handle(Envelope::from(CreateSomething).with_idempotency_key(key.clone()))
+            .await
+            .expect("the command should be handled");
+    }
+
+    assert_eq!(1, handled.load(Ordering::SeqCst));
+}
@@ -0,0 +1,77 @@
+use eventually::serde;
+use eventually_postgres::event;
+use eventually_postgres::maintenance::{
+    Advisory, IndexStatistics, Maintenance, TableStatistics, Thresholds,
+};
+
+mod setup;
+
+fn healthy_table_statistics() -> TableStatistics {
+    TableStatistics {
+        live_tuples: 100_000,
+        dead_tuples: 0,
+        table_size_bytes: 1_000_000,
+        indexes_size_bytes: 500_000,
+        last_vacuum: None,
+        last_autovacuum: None,
+        event_stream_id_correlation: Some(0.9),
+        indexes: vec![IndexStatistics {
+            name: "events_pkey".to_owned(),
+            scans: 10,
+            size_bytes: 500_000,
+        }],
+    }
+}
+
+#[tokio::test]
+async fn statistics_reports_the_events_table_indexes() {
+    let pool = setup::connect_to_database()
+        .await
+        .expect("connection to the database should work");
+
+    // Creating the Event Store runs the migrations.
+    event::Store::<String, setup::TestDomainEvent, _>::new(
+        pool.clone(),
+        serde::Json::<setup::TestDomainEvent>::default(),
+    )
+    .await
+    .unwrap();
+
+    let maintenance = Maintenance::from(pool);
+
+    maintenance
+        .execute(Advisory::Vacuum)
+        .await
+        .expect("vacuum should not fail");
+
+    let statistics = maintenance
+        .statistics()
+        .await
+        .expect("statistics should be available");
+
+    assert!(statistics
+        .indexes
+        .iter()
+        .any(|index| index.name == "events_pkey"));
+}
+
+#[test]
+fn advise_suggests_maintenance_only_when_thresholds_are_exceeded() {
+    let mut statistics = healthy_table_statistics();
+
+    assert!(Maintenance::advise(&statistics, &Thresholds::default()).is_empty());
+
+    statistics.dead_tuples = 50_000;
+    statistics.indexes[0].size_bytes = 2_000_000;
+    statistics.event_stream_id_correlation = Some(0.1);
+
+    assert_eq!(
+        vec![Advisory::Vacuum, Advisory::Reindex, Advisory::Cluster],
+        Maintenance::advise(&statistics, &Thresholds::default())
+    );
+
+    // Small tables are not worth the maintenance.
+    statistics.live_tuples = 100;
+
+    assert!(Maintenance::advise(&statistics, &Thresholds::default()).is_empty());
+}
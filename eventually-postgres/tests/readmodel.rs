@@ -0,0 +1,218 @@
+use anyhow::anyhow;
+use async_trait::async_trait;
+use eventually::event;
+use eventually::projection::{Checkpointed, Projection, Resettable};
+use eventually_postgres::readmodel::{Projector, ReadModel};
+use rand::Rng;
+use sqlx::PgConnection;
+
+mod setup;
+
+/// Example read model, counting the Domain Events of each Event Stream.
+struct EventsPerStream {
+    projection: String,
+}
+
+#[async_trait]
+impl ReadModel<String, setup::TestDomainEvent> for EventsPerStream {
+    async fn apply(
+        &self,
+        conn: &mut PgConnection,
+        event: event::Sequenced<String, setup::TestDomainEvent>,
+    ) -> anyhow::Result<()> {
+        sqlx::query(
+            r#"INSERT INTO readmodel_events_per_stream (projection, event_stream_id, events)
+               VALUES ($1, $2, 1)
+               ON CONFLICT (projection, event_stream_id) DO UPDATE
+               SET events = readmodel_events_per_stream.events + 1"#,
+        )
+        .bind(&self.projection)
+        .bind(event.event.stream_id)
+        .execute(conn)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn reset(&self, conn: &mut PgConnection) -> anyhow::Result<()> {
+        sqlx::query("DELETE FROM readmodel_events_per_stream WHERE projection = $1")
+            .bind(&self.projection)
+            .execute(conn)
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// Read model failing to apply the Domain Events past the specified sequence number.
+struct FailingAfter(event::Sequence);
+
+#[async_trait]
+impl ReadModel<String, setup::TestDomainEvent> for FailingAfter {
+    async fn apply(
+        &self,
+        _conn: &mut PgConnection,
+        event: event::Sequenced<String, setup::TestDomainEvent>,
+    ) -> anyhow::Result<()> {
+        if event.sequence > self.0 {
+            return Err(anyhow!("failed to apply domain event #{}", event.sequence));
+        }
+
+        Ok(())
+    }
+
+    async fn reset(&self, _conn: &mut PgConnection) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+fn sequenced_events(
+    stream_id: &str,
+    sequences: impl IntoIterator<Item = event::Sequence>,
+) -> Vec<event::Sequenced<String, setup::TestDomainEvent>> {
+    sequences
+        .into_iter()
+        .map(|sequence| event::Sequenced {
+            sequence,
+            event: event::Persisted {
+                stream_id: stream_id.to_owned(),
+                version: sequence,
+                event: event::Envelope::from(setup::TestDomainEvent::WasDeleted {
+                    id: setup::TestAggregateId(0),
+                }),
+            },
+        })
+        .collect()
+}
+
+async fn events_per_stream(pool: &sqlx::PgPool, projection: &str, stream_id: &str) -> i64 {
+    sqlx::query_scalar(
+        "SELECT events FROM readmodel_events_per_stream WHERE projection = $1 AND event_stream_id = $2",
+    )
+    .bind(projection)
+    .bind(stream_id)
+    .fetch_optional(pool)
+    .await
+    .expect("read model should be readable")
+    .unwrap_or_default()
+}
+
+async fn create_events_per_stream_table(pool: &sqlx::PgPool) {
+    sqlx::query(
+        r#"CREATE TABLE IF NOT EXISTS readmodel_events_per_stream (
+               projection      TEXT   NOT NULL,
+               event_stream_id TEXT   NOT NULL,
+               events          BIGINT NOT NULL,
+               PRIMARY KEY (projection, event_stream_id)
+           )"#,
+    )
+    .execute(pool)
+    .await
+    .expect("read model table should be created");
+}
+
+#[tokio::test]
+async fn projector_applies_each_event_exactly_once() {
+    let pool = setup::connect_to_database()
+        .await
+        .expect("connection to the database should work");
+
+    create_events_per_stream_table(&pool).await;
+
+    let name = format!("test-projection-{}", rand::thread_rng().gen::<i64>());
+    let projector = Projector::new(
+        pool.clone(),
+        name.clone(),
+        EventsPerStream {
+            projection: name.clone(),
+        },
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(None, projector.checkpoint().await.unwrap());
+
+    projector
+        .project_batch(sequenced_events("stream:a", [1, 2, 3]))
+        .await
+        .expect("batch should be projected");
+
+    // Redelivered Domain Events are skipped.
+    for event in sequenced_events("stream:a", [2, 3, 4]) {
+        projector
+            .project(event)
+            .await
+            .expect("event should be projected");
+    }
+
+    assert_eq!(Some(4), projector.checkpoint().await.unwrap());
+    assert_eq!(4, events_per_stream(&pool, &name, "stream:a").await);
+
+    projector.reset().await.expect("reset should not fail");
+
+    assert_eq!(None, projector.checkpoint().await.unwrap());
+    assert_eq!(0, events_per_stream(&pool, &name, "stream:a").await);
+}
+
+#[tokio::test]
+async fn concurrent_projectors_apply_the_first_batch_once() {
+    let pool = setup::connect_to_database()
+        .await
+        .expect("connection to the database should work");
+
+    create_events_per_stream_table(&pool).await;
+
+    let name = format!("test-projection-{}", rand::thread_rng().gen::<i64>());
+    let projector = || {
+        Projector::new(
+            pool.clone(),
+            name.clone(),
+            EventsPerStream {
+                projection: name.clone(),
+            },
+        )
+    };
+
+    let (first, second) = (projector().await.unwrap(), projector().await.unwrap());
+
+    let (first_result, second_result) = tokio::join!(
+        first.project_batch(sequenced_events("stream:a", [1, 2, 3])),
+        second.project_batch(sequenced_events("stream:a", [1, 2, 3])),
+    );
+
+    // The Projector waiting for the checkpoint lock might fail with
+    // a serialization error, and retry.
+    for (projector, result) in [(&first, first_result), (&second, second_result)] {
+        if result.is_err() {
+            projector
+                .project_batch(sequenced_events("stream:a", [1, 2, 3]))
+                .await
+                .expect("batch should be projected on retry");
+        }
+    }
+
+    assert_eq!(Some(3), first.checkpoint().await.unwrap());
+    assert_eq!(3, events_per_stream(&pool, &name, "stream:a").await);
+}
+
+#[tokio::test]
+async fn projector_does_not_move_the_checkpoint_of_a_failed_batch() {
+    let pool = setup::connect_to_database()
+        .await
+        .expect("connection to the database should work");
+
+    let name = format!("test-projection-{}", rand::thread_rng().gen::<i64>());
+    let projector = Projector::new(pool, name, FailingAfter(2)).await.unwrap();
+
+    projector
+        .project_batch(sequenced_events("stream:a", [1, 2]))
+        .await
+        .expect("batch should be projected");
+
+    projector
+        .project_batch(sequenced_events("stream:a", [3, 4]))
+        .await
+        .expect_err("batch should fail");
+
+    assert_eq!(Some(2), projector.checkpoint().await.unwrap());
+}
@@ -0,0 +1,89 @@
+use chrono::{Duration, Utc};
+use eventually::command::scheduler::Schedule as _;
+use eventually::command::Envelope;
+use eventually::message::Message;
+use eventually::serde;
+use eventually_postgres::scheduler;
+use rand::Rng;
+
+mod setup;
+
+#[derive(Debug, Clone, PartialEq, Eq, ::serde::Serialize, ::serde::Deserialize)]
+struct ExpireReservation {
+    reservation_id: i64,
+}
+
+impl Message for ExpireReservation {
+    fn name(&self) -> &'static str {
+        "ExpireReservation"
+    }
+}
+
+#[tokio::test]
+async fn schedule_claims_due_commands_only_once_until_the_retry_time() {
+    let pool = setup::connect_to_database()
+        .await
+        .expect("connection to the database should work");
+
+    // NOTE: a random name isolates this test from the Commands scheduled by other runs.
+    let name = format!("ExpireReservation-{}", rand::thread_rng().gen::<i64>());
+    let schedule =
+        scheduler::Schedule::new(pool, serde::Json::<ExpireReservation>::default(), name)
+            .await
+            .unwrap();
+
+    let now = Utc::now();
+
+    for (reservation_id, due_in) in [(1, 20), (2, 10), (3, 60)] {
+        schedule
+            .schedule(
+                Envelope::from(ExpireReservation { reservation_id })
+                    .with_metadata("Reservation-Id".to_owned(), reservation_id.to_string()),
+                now + Duration::minutes(due_in),
+            )
+            .await
+            .unwrap();
+    }
+
+    let in_30_minutes = now + Duration::minutes(30);
+    let retry_at = in_30_minutes + Duration::minutes(1);
+
+    let claimed = schedule
+        .claim_due(in_30_minutes, retry_at, 10)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        vec![2, 1],
+        claimed
+            .iter()
+            .map(|scheduled| scheduled.command.message.reservation_id)
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(
+        Some("2"),
+        claimed[0]
+            .command
+            .metadata
+            .get("Reservation-Id")
+            .map(String::as_str)
+    );
+
+    assert!(schedule
+        .claim_due(in_30_minutes, retry_at, 10)
+        .await
+        .unwrap()
+        .is_empty());
+
+    schedule.complete(claimed[0].id).await.unwrap();
+
+    let retried = schedule.claim_due(retry_at, retry_at, 10).await.unwrap();
+
+    assert_eq!(
+        vec![1],
+        retried
+            .iter()
+            .map(|scheduled| scheduled.command.message.reservation_id)
+            .collect::<Vec<_>>()
+    );
+}
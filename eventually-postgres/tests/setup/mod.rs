@@ -1,10 +1,9 @@
-use std::fmt::{Display, Formatter, Result as FmtResult};
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use eventually::aggregate;
 use eventually::aggregate::Aggregate;
 use eventually::message::Message;
-use eventually_macros::aggregate_root;
+use eventually_macros::{aggregate_root, AggregateId};
 use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
 
@@ -14,15 +13,10 @@ pub async fn connect_to_database() -> Result<PgPool, sqlx::Error> {
     sqlx::PgPool::connect(&url).await
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, AggregateId)]
+#[aggregate_id(prefix = "test-aggregate")]
 pub struct TestAggregateId(pub i64);
 
-impl Display for TestAggregateId {
-    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
-        write!(f, "test-aggregate:{}", self.0)
-    }
-}
-
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum TestDomainEvent {
     WasCreated {
@@ -0,0 +1,429 @@
+use std::time::Duration;
+
+use eventually::aggregate::repository::Saver;
+use eventually::aggregate::Aggregate;
+use eventually::event::store::Appender;
+use eventually::health::{self, Check};
+use eventually::message::{Message, TENANT_ID_KEY};
+use eventually::serde::Serializer;
+use eventually::subscription::{CatchUp, Subscriber};
+use eventually::{event as domain_event, serde, version};
+use eventually_postgres::{aggregate, event, subscription};
+use futures::{StreamExt, TryStreamExt};
+use rand::Rng;
+
+mod setup;
+
+const RECEIVE_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[tokio::test]
+async fn subscribe_all_streams_newly_appended_events() {
+    let pool = setup::connect_to_database()
+        .await
+        .expect("connection to the database should work");
+
+    let event_store = event::Store::new(
+        pool.clone(),
+        serde::Json::<setup::TestDomainEvent>::default(),
+    )
+    .await
+    .unwrap();
+
+    let subscriber = subscription::Subscriber::<String, _, _>::new(
+        pool,
+        serde::Json::<setup::TestDomainEvent>::default(),
+    )
+    .await
+    .unwrap();
+
+    let id = rand::thread_rng().gen::<i64>();
+    let event_stream_id = format!("test-event-stream-{id}");
+
+    let subscription = subscriber
+        .subscribe_all()
+        .await
+        .expect("subscription should start");
+
+    let events: Vec<_> = vec![
+        setup::TestDomainEvent::WasCreated {
+            id: setup::TestAggregateId(id),
+            name: "test something".to_owned(),
+            at: 0,
+        }
+        .into(),
+        setup::TestDomainEvent::WasDeleted {
+            id: setup::TestAggregateId(id),
+        }
+        .into(),
+    ];
+
+    event_store
+        .append(
+            event_stream_id.clone(),
+            version::Check::MustBe(0),
+            events.clone(),
+        )
+        .await
+        .expect("append should not fail");
+
+    let received_events: Vec<_> = tokio::time::timeout(
+        RECEIVE_TIMEOUT,
+        subscription
+            .map_ok(|event| event.event)
+            .try_filter(|event| futures::future::ready(event.stream_id == event_stream_id))
+            .take(2)
+            .try_collect::<Vec<_>>(),
+    )
+    .await
+    .expect("events should be received in time")
+    .expect("subscription should not fail");
+
+    assert_eq!(
+        vec![1, 2],
+        received_events
+            .iter()
+            .map(|event| event.version)
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(
+        events,
+        received_events
+            .into_iter()
+            .map(|event| event.event)
+            .collect::<Vec<_>>()
+    );
+}
+
+#[tokio::test]
+async fn subscribe_streams_only_events_of_the_specified_stream_type() {
+    let pool = setup::connect_to_database()
+        .await
+        .expect("connection to the database should work");
+
+    let aggregate_repository = aggregate::Repository::new(
+        pool.clone(),
+        serde::Json::<setup::TestAggregate>::default(),
+        serde::Json::<setup::TestDomainEvent>::default(),
+    )
+    .await
+    .unwrap();
+
+    let event_store = event::Store::new(
+        pool.clone(),
+        serde::Json::<setup::TestDomainEvent>::default(),
+    )
+    .await
+    .unwrap();
+
+    let subscriber = subscription::Subscriber::<String, _, _>::new(
+        pool,
+        serde::Json::<setup::TestDomainEvent>::default(),
+    )
+    .await
+    .unwrap();
+
+    let event_stream_id = format!("test-event-stream-{}", rand::thread_rng().gen::<i64>());
+    let aggregate_id = setup::TestAggregateId(rand::thread_rng().gen::<i64>());
+
+    let subscription = subscriber
+        .subscribe(setup::TestAggregate::type_name())
+        .await
+        .expect("subscription should start");
+
+    // Events appended to an Event Stream not owned by an Aggregate Root are skipped.
+    event_store
+        .append(
+            event_stream_id.clone(),
+            version::Check::MustBe(0),
+            vec![setup::TestDomainEvent::WasDeleted { id: aggregate_id }.into()],
+        )
+        .await
+        .expect("append should not fail");
+
+    let mut root = setup::TestAggregateRoot::create(aggregate_id, "John Dee".to_owned())
+        .expect("aggregate root should be created");
+
+    aggregate_repository
+        .save(&mut root)
+        .await
+        .expect("storing the new aggregate root should be successful");
+
+    let expected_stream_ids = [event_stream_id, aggregate_id.to_string()];
+    let received_events: Vec<_> = tokio::time::timeout(
+        RECEIVE_TIMEOUT,
+        subscription
+            .map_ok(|event| event.event)
+            .try_filter(|event| {
+                futures::future::ready(expected_stream_ids.contains(&event.stream_id))
+            })
+            .take(1)
+            .try_collect::<Vec<_>>(),
+    )
+    .await
+    .expect("events should be received in time")
+    .expect("subscription should not fail");
+
+    assert_eq!(1, received_events.len());
+    assert_eq!(aggregate_id.to_string(), received_events[0].stream_id);
+}
+
+#[tokio::test]
+async fn catch_up_streams_stored_and_newly_appended_events() {
+    let pool = setup::connect_to_database()
+        .await
+        .expect("connection to the database should work");
+
+    let event_store = event::Store::new(
+        pool.clone(),
+        serde::Json::<setup::TestDomainEvent>::default(),
+    )
+    .await
+    .unwrap();
+
+    let subscriber = subscription::Subscriber::<String, _, _>::new(
+        pool,
+        serde::Json::<setup::TestDomainEvent>::default(),
+    )
+    .await
+    .unwrap();
+
+    let id = rand::thread_rng().gen::<i64>();
+    let event_stream_id = format!("test-event-stream-{id}");
+
+    let events: Vec<_> = vec![
+        setup::TestDomainEvent::WasCreated {
+            id: setup::TestAggregateId(id),
+            name: "test something".to_owned(),
+            at: 0,
+        }
+        .into(),
+        setup::TestDomainEvent::WasDeleted {
+            id: setup::TestAggregateId(id),
+        }
+        .into(),
+    ];
+
+    event_store
+        .append(
+            event_stream_id.clone(),
+            version::Check::MustBe(0),
+            events[..1].to_vec(),
+        )
+        .await
+        .expect("append should not fail");
+
+    let subscription = CatchUp::new(event_store.clone(), subscriber);
+    let mut stream = subscription
+        .stream(domain_event::SequenceSelect::All)
+        .try_filter(|event| futures::future::ready(event.event.stream_id == event_stream_id));
+
+    let stored_event = tokio::time::timeout(RECEIVE_TIMEOUT, stream.try_next())
+        .await
+        .expect("stored event should be received in time")
+        .expect("subscription should not fail")
+        .expect("subscription should not end");
+
+    event_store
+        .append(
+            event_stream_id.clone(),
+            version::Check::MustBe(1),
+            events[1..].to_vec(),
+        )
+        .await
+        .expect("append should not fail");
+
+    let live_event = tokio::time::timeout(RECEIVE_TIMEOUT, stream.try_next())
+        .await
+        .expect("live event should be received in time")
+        .expect("subscription should not fail")
+        .expect("subscription should not end");
+
+    assert!(stored_event.sequence < live_event.sequence);
+    assert_eq!(
+        events,
+        vec![stored_event.event.event, live_event.event.event]
+    );
+}
+
+#[tokio::test]
+async fn catch_up_streams_only_events_matching_the_metadata_filter() {
+    let pool = setup::connect_to_database()
+        .await
+        .expect("connection to the database should work");
+
+    let event_store = event::Store::new(
+        pool.clone(),
+        serde::Json::<setup::TestDomainEvent>::default(),
+    )
+    .await
+    .unwrap();
+
+    let subscriber = subscription::Subscriber::<String, _, _>::new(
+        pool,
+        serde::Json::<setup::TestDomainEvent>::default(),
+    )
+    .await
+    .unwrap();
+
+    let id = rand::thread_rng().gen::<i64>();
+    let tenant_id = format!("tenant-{id}");
+    let event_stream_id = format!("test-event-stream-{id}");
+
+    let event = |tenant_id: &str| -> domain_event::Envelope<setup::TestDomainEvent> {
+        domain_event::Envelope::from(setup::TestDomainEvent::WasDeleted {
+            id: setup::TestAggregateId(id),
+        })
+        .with_tenant_id(tenant_id.to_owned())
+    };
+
+    event_store
+        .append(
+            event_stream_id.clone(),
+            version::Check::MustBe(0),
+            vec![event("another-tenant"), event(&tenant_id)],
+        )
+        .await
+        .expect("append should not fail");
+
+    let subscription = CatchUp::new(
+        event_store
+            .clone()
+            .with_metadata_filter(TENANT_ID_KEY.to_owned(), tenant_id.clone()),
+        subscriber.with_metadata_filter(TENANT_ID_KEY.to_owned(), tenant_id.clone()),
+    );
+
+    let mut stream = subscription.stream(domain_event::SequenceSelect::All);
+
+    let stored_event = tokio::time::timeout(RECEIVE_TIMEOUT, stream.try_next())
+        .await
+        .expect("stored event should be received in time")
+        .expect("subscription should not fail")
+        .expect("subscription should not end");
+
+    event_store
+        .append(
+            event_stream_id.clone(),
+            version::Check::MustBe(2),
+            vec![event("another-tenant"), event(&tenant_id)],
+        )
+        .await
+        .expect("append should not fail");
+
+    let live_event = tokio::time::timeout(RECEIVE_TIMEOUT, stream.try_next())
+        .await
+        .expect("live event should be received in time")
+        .expect("subscription should not fail")
+        .expect("subscription should not end");
+
+    assert_eq!(
+        vec![2, 4],
+        vec![stored_event.event.version, live_event.event.version]
+    );
+}
+
+#[tokio::test]
+async fn fallback_polling_delivers_events_whose_notification_has_been_missed() {
+    let pool = setup::connect_to_database()
+        .await
+        .expect("connection to the database should work");
+
+    let subscriber = subscription::Subscriber::<String, _, _>::new(
+        pool.clone(),
+        serde::Json::<setup::TestDomainEvent>::default(),
+    )
+    .await
+    .unwrap()
+    .with_fallback_polling(Duration::from_millis(50), Duration::from_millis(200));
+
+    let id = rand::thread_rng().gen::<i64>();
+    let event_stream_id = format!("test-event-stream-{id}");
+
+    let subscription = subscriber
+        .subscribe_all()
+        .await
+        .expect("subscription should start");
+
+    let event = setup::TestDomainEvent::WasDeleted {
+        id: setup::TestAggregateId(id),
+    };
+    let payload = serde::Json::<setup::TestDomainEvent>::default()
+        .serialize(event.clone())
+        .expect("serialization should not fail");
+
+    // Triggers are disabled in replica mode, so no notification is sent for the Domain Event.
+    let mut tx = pool.begin().await.expect("transaction should begin");
+
+    sqlx::query("SET LOCAL session_replication_role = replica")
+        .execute(&mut *tx)
+        .await
+        .expect("replica mode should be set");
+
+    sqlx::query(r#"INSERT INTO event_streams (event_stream_id, "version") VALUES ($1, 1)"#)
+        .bind(&event_stream_id)
+        .execute(&mut *tx)
+        .await
+        .expect("event stream should be inserted");
+
+    sqlx::query(
+        r#"INSERT INTO events (event_stream_id, "type", "version", event, metadata) VALUES ($1, $2, 1, $3, '{}')"#,
+    )
+    .bind(&event_stream_id)
+    .bind(event.name())
+    .bind(payload)
+    .execute(&mut *tx)
+    .await
+    .expect("event should be inserted");
+
+    tx.commit().await.expect("transaction should commit");
+
+    let received_events: Vec<_> = tokio::time::timeout(
+        RECEIVE_TIMEOUT,
+        subscription
+            .map_ok(|event| event.event)
+            .try_filter(|event| futures::future::ready(event.stream_id == event_stream_id))
+            .take(1)
+            .try_collect::<Vec<_>>(),
+    )
+    .await
+    .expect("events should be received in time")
+    .expect("subscription should not fail");
+
+    assert_eq!(event, received_events[0].event.message);
+
+    let metrics = subscriber.metrics();
+    assert!(metrics.missed_notifications >= 1, "{metrics:?}");
+    assert!(
+        metrics.delivered >= metrics.missed_notifications,
+        "{metrics:?}"
+    );
+}
+
+#[tokio::test]
+async fn health_checks_pass_when_the_database_is_set_up() {
+    let pool = setup::connect_to_database()
+        .await
+        .expect("connection to the database should work");
+
+    let event_store = event::Store::<String, _, _>::new(
+        pool.clone(),
+        serde::Json::<setup::TestDomainEvent>::default(),
+    )
+    .await
+    .unwrap();
+
+    let subscriber = subscription::Subscriber::<String, _, _>::new(
+        pool,
+        serde::Json::<setup::TestDomainEvent>::default(),
+    )
+    .await
+    .unwrap();
+
+    let checks = health::Checks::default()
+        .with("event-store", event_store)
+        .with("subscriber", subscriber);
+
+    let report = checks.run().await;
+
+    assert!(report.is_healthy(), "{report:?}");
+    assert!(checks.check().await.is_ok());
+}
@@ -0,0 +1,160 @@
+//! Benchmarks of the recording and rehydration of an Aggregate Root
+//! with a growing state, through the in-memory Event Store.
+
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use eventually::aggregate::repository::{Getter, Saver};
+use eventually::aggregate::{self, Aggregate};
+use eventually::event::store::InMemory;
+use eventually::message;
+
+#[derive(Debug, Clone)]
+struct Ledger {
+    id: String,
+    entries: Vec<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum LedgerEvent {
+    WasOpened { id: String },
+    EntryWasAdded { amount: u64 },
+}
+
+impl message::Message for LedgerEvent {
+    fn name(&self) -> &'static str {
+        match self {
+            LedgerEvent::WasOpened { .. } => "LedgerWasOpened",
+            LedgerEvent::EntryWasAdded { .. } => "LedgerEntryWasAdded",
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("ledger has not been opened yet")]
+struct LedgerError;
+
+impl Aggregate for Ledger {
+    type Id = String;
+    type Event = LedgerEvent;
+    type Error = LedgerError;
+
+    fn type_name() -> &'static str {
+        "Ledger"
+    }
+
+    fn aggregate_id(&self) -> &Self::Id {
+        &self.id
+    }
+
+    fn apply(state: Option<Self>, event: Self::Event) -> Result<Self, Self::Error> {
+        match (state, event) {
+            (None, LedgerEvent::WasOpened { id }) => Ok(Ledger {
+                id,
+                entries: Vec::new(),
+            }),
+            (Some(mut ledger), LedgerEvent::EntryWasAdded { amount }) => {
+                ledger.entries.push(amount);
+                Ok(ledger)
+            },
+            _ => Err(LedgerError),
+        }
+    }
+}
+
+/// Same as [Ledger], but overriding [`Aggregate::apply_in_place`]
+/// to avoid cloning the entries on every recorded Domain Event.
+#[derive(Debug, Clone)]
+struct InPlaceLedger(Ledger);
+
+impl Aggregate for InPlaceLedger {
+    type Id = String;
+    type Event = LedgerEvent;
+    type Error = LedgerError;
+
+    fn type_name() -> &'static str {
+        "InPlaceLedger"
+    }
+
+    fn aggregate_id(&self) -> &Self::Id {
+        &self.0.id
+    }
+
+    fn apply(state: Option<Self>, event: Self::Event) -> Result<Self, Self::Error> {
+        Ledger::apply(state.map(|ledger| ledger.0), event).map(InPlaceLedger)
+    }
+
+    fn apply_in_place(&mut self, event: Self::Event) -> Result<(), Self::Error> {
+        match event {
+            LedgerEvent::EntryWasAdded { amount } => {
+                self.0.entries.push(amount);
+                Ok(())
+            },
+            LedgerEvent::WasOpened { .. } => Err(LedgerError),
+        }
+    }
+}
+
+const SIZES: [u64; 3] = [10, 100, 1_000];
+
+fn record<T>(entries: u64) -> aggregate::Root<T>
+where
+    T: Aggregate<Id = String, Event = LedgerEvent>,
+    T::Error: std::fmt::Debug,
+{
+    let mut root = aggregate::Root::<T>::record_new(
+        LedgerEvent::WasOpened {
+            id: "ledger".to_owned(),
+        }
+        .into(),
+    )
+    .unwrap();
+
+    for amount in 0..entries {
+        root.record_that(LedgerEvent::EntryWasAdded { amount }.into())
+            .unwrap();
+    }
+
+    root
+}
+
+fn bench_record_that(c: &mut Criterion) {
+    let mut group = c.benchmark_group("record_that");
+
+    for entries in SIZES {
+        group.bench_with_input(BenchmarkId::new("clone", entries), &entries, |b, &n| {
+            b.iter(|| record::<Ledger>(n));
+        });
+
+        group.bench_with_input(BenchmarkId::new("in_place", entries), &entries, |b, &n| {
+            b.iter(|| record::<InPlaceLedger>(n));
+        });
+    }
+
+    group.finish();
+}
+
+fn bench_rehydrate(c: &mut Criterion) {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let mut group = c.benchmark_group("rehydrate");
+
+    for entries in SIZES {
+        let store = InMemory::<String, LedgerEvent>::default();
+        let repository = aggregate::EventSourcedRepository::<Ledger, _>::from(store);
+
+        runtime
+            .block_on(repository.save(&mut record::<Ledger>(entries)))
+            .unwrap();
+
+        group.bench_with_input(BenchmarkId::from_parameter(entries), &entries, |b, _| {
+            b.iter_batched(
+                || "ledger".to_owned(),
+                |id| runtime.block_on(repository.get(&id)).unwrap(),
+                BatchSize::SmallInput,
+            );
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_record_that, bench_rehydrate);
+criterion_main!(benches);
@@ -0,0 +1,416 @@
+//! Module containing support for the Decider pattern, a functional alternative
+//! to implementing [Aggregate]s and their [Aggregate Root][Root] methods.
+//!
+//! A [Decider] is described by three functions:
+//! - [`Decider::initial_state`], the state before any Domain Event happened,
+//! - [`Decider::decide`], which turns a Command into the Domain Events it produces,
+//!   given the current state,
+//! - [`Decider::evolve`], which folds a Domain Event on the current state.
+//!
+//! Deciders can be used anywhere an [Aggregate] is expected through the [Decided]
+//! adapter, e.g. with a [Repository][crate::aggregate::Repository], while
+//! [`DecidedRoot`] can be used to run Commands and to write [`Scenario`] tests.
+//!
+//! [`Scenario`]: crate::aggregate::test::Scenario
+
+use std::fmt::{Debug, Formatter, Result as FmtResult};
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+
+use crate::aggregate::{Aggregate, Root};
+use crate::message;
+
+/// A Decider describes the behavior of a Domain Entity through pure functions,
+/// deciding which Domain Events a Command produces and evolving its state through them.
+///
+/// Deciders are usually implemented on a marker type, e.g. `struct BankAccountDecider;`.
+pub trait Decider: Send + Sync {
+    /// The type used to uniquely identify the Domain Entity.
+    type Id: Send + Sync;
+
+    /// The state of the Domain Entity, folded from its Domain Events.
+    type State: Clone + Send + Sync;
+
+    /// The type of Commands handled by the Decider.
+    type Command;
+
+    /// The type of Domain Events produced by the Decider.
+    type Event: message::Message + Clone + Send + Sync;
+
+    /// The error type returned by [`Decider::decide`] when a Command is rejected.
+    type Error: Send + Sync;
+
+    /// A unique name identifier for this Decider type.
+    fn type_name() -> &'static str;
+
+    /// Returns the unique identifier of the Domain Entity from its state.
+    ///
+    /// Only called on states that have evolved through at least one Domain Event,
+    /// e.g. when saving the Domain Entity in a [Repository][crate::aggregate::Repository].
+    fn id(state: &Self::State) -> &Self::Id;
+
+    /// Returns the state of the Domain Entity before any Domain Event happened.
+    fn initial_state() -> Self::State;
+
+    /// Decides which Domain Events the specified Command produces, given the current state.
+    ///
+    /// # Errors
+    ///
+    /// An error should be returned if the Command is not valid for the current state.
+    fn decide(
+        command: &Self::Command,
+        state: &Self::State,
+    ) -> Result<Vec<Self::Event>, Self::Error>;
+
+    /// Folds a Domain Event on the current state, producing the next state.
+    fn evolve(state: Self::State, event: Self::Event) -> Self::State;
+}
+
+/// Adapter that implements the [Aggregate] trait for a [Decider],
+/// holding its current [`Decider::State`].
+pub struct Decided<D>
+where
+    D: Decider,
+{
+    state: D::State,
+    decider: PhantomData<D>,
+}
+
+impl<D> Decided<D>
+where
+    D: Decider,
+{
+    /// Returns the current state of the [Decider].
+    pub fn state(&self) -> &D::State {
+        &self.state
+    }
+}
+
+impl<D> Clone for Decided<D>
+where
+    D: Decider,
+{
+    fn clone(&self) -> Self {
+        Self {
+            state: self.state.clone(),
+            decider: PhantomData,
+        }
+    }
+}
+
+impl<D> Debug for Decided<D>
+where
+    D: Decider,
+    D::State: Debug,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_tuple("Decided").field(&self.state).finish()
+    }
+}
+
+impl<D> PartialEq for Decided<D>
+where
+    D: Decider,
+    D::State: PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.state == other.state
+    }
+}
+
+impl<D> Aggregate for Decided<D>
+where
+    D: Decider,
+{
+    type Id = D::Id;
+    type Event = D::Event;
+    type Error = D::Error;
+
+    fn type_name() -> &'static str {
+        D::type_name()
+    }
+
+    fn aggregate_id(&self) -> &Self::Id {
+        D::id(&self.state)
+    }
+
+    fn apply(state: Option<Self>, event: Self::Event) -> Result<Self, Self::Error> {
+        let state = state.map_or_else(D::initial_state, |decided| decided.state);
+
+        Ok(Self {
+            state: D::evolve(state, event),
+            decider: PhantomData,
+        })
+    }
+
+    fn apply_in_place(&mut self, event: Self::Event) -> Result<(), Self::Error> {
+        let state = std::mem::replace(&mut self.state, D::initial_state());
+        self.state = D::evolve(state, event);
+        Ok(())
+    }
+}
+
+/// An [Aggregate Root][Root] for a [Decider], used to run Commands through it.
+///
+/// It converts from and dereferences to [`Root<Decided<D>>`][Root], so it can be
+/// used with any [Repository][crate::aggregate::Repository] and with the
+/// [`Scenario`][crate::aggregate::test::Scenario] test helper.
+pub struct DecidedRoot<D>(Root<Decided<D>>)
+where
+    D: Decider;
+
+impl<D> DecidedRoot<D>
+where
+    D: Decider,
+{
+    /// Runs the specified Command on the [`Decider::initial_state`], creating
+    /// a new [`DecidedRoot`] with the Domain Events produced by the Command.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the [Decider] rejects the Command.
+    pub fn decide_new(command: &D::Command) -> Result<Self, D::Error> {
+        let mut root = Self(Root::rehydrate_from_state(
+            0,
+            Decided {
+                state: D::initial_state(),
+                decider: PhantomData,
+            },
+        ));
+
+        root.decide(command)?;
+
+        Ok(root)
+    }
+
+    /// Runs the specified Command on the current state, recording
+    /// the Domain Events produced by the Command.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the [Decider] rejects the Command.
+    pub fn decide(&mut self, command: &D::Command) -> Result<(), D::Error> {
+        for event in D::decide(command, self.0.state())? {
+            self.0.record_that(event.into())?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<D> Clone for DecidedRoot<D>
+where
+    D: Decider,
+{
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<D> Debug for DecidedRoot<D>
+where
+    D: Decider,
+    D::State: Debug,
+    D::Event: Debug,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_tuple("DecidedRoot").field(&self.0).finish()
+    }
+}
+
+impl<D> From<Root<Decided<D>>> for DecidedRoot<D>
+where
+    D: Decider,
+{
+    fn from(root: Root<Decided<D>>) -> Self {
+        Self(root)
+    }
+}
+
+impl<D> Deref for DecidedRoot<D>
+where
+    D: Decider,
+{
+    type Target = Root<Decided<D>>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<D> DerefMut for DecidedRoot<D>
+where
+    D: Decider,
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aggregate::repository::{Getter, Saver};
+    use crate::aggregate::test::Scenario;
+    use crate::aggregate::EventSourcedRepository;
+    use crate::event::store::InMemory;
+
+    struct CounterDecider;
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    struct Counter {
+        id: String,
+        value: u64,
+    }
+
+    enum CounterCommand {
+        Start { id: String },
+        Increment { by: u64 },
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    enum CounterEvent {
+        WasStarted { id: String },
+        WasIncremented { by: u64 },
+    }
+
+    impl message::Message for CounterEvent {
+        fn name(&self) -> &'static str {
+            match self {
+                CounterEvent::WasStarted { .. } => "CounterWasStarted",
+                CounterEvent::WasIncremented { .. } => "CounterWasIncremented",
+            }
+        }
+    }
+
+    #[derive(Debug, PartialEq, Eq, thiserror::Error)]
+    enum CounterError {
+        #[error("counter was already started")]
+        AlreadyStarted,
+        #[error("counter was not yet started")]
+        NotYetStarted,
+    }
+
+    impl Decider for CounterDecider {
+        type Id = String;
+        type State = Counter;
+        type Command = CounterCommand;
+        type Event = CounterEvent;
+        type Error = CounterError;
+
+        fn type_name() -> &'static str {
+            "Counter"
+        }
+
+        fn id(state: &Self::State) -> &Self::Id {
+            &state.id
+        }
+
+        fn initial_state() -> Self::State {
+            Counter {
+                id: String::new(),
+                value: 0,
+            }
+        }
+
+        fn decide(
+            command: &Self::Command,
+            state: &Self::State,
+        ) -> Result<Vec<Self::Event>, Self::Error> {
+            match command {
+                CounterCommand::Start { .. } if !state.id.is_empty() => {
+                    Err(CounterError::AlreadyStarted)
+                },
+                CounterCommand::Start { id } => {
+                    Ok(vec![CounterEvent::WasStarted { id: id.clone() }])
+                },
+                CounterCommand::Increment { .. } if state.id.is_empty() => {
+                    Err(CounterError::NotYetStarted)
+                },
+                CounterCommand::Increment { by } => {
+                    Ok(vec![CounterEvent::WasIncremented { by: *by }])
+                },
+            }
+        }
+
+        fn evolve(mut state: Self::State, event: Self::Event) -> Self::State {
+            match event {
+                CounterEvent::WasStarted { id } => state.id = id,
+                CounterEvent::WasIncremented { by } => state.value += by,
+            }
+
+            state
+        }
+    }
+
+    #[test]
+    fn decider_runs_through_scenario_tests() {
+        Scenario::<Decided<CounterDecider>>::new()
+            .when(|| {
+                DecidedRoot::<CounterDecider>::decide_new(&CounterCommand::Start {
+                    id: "counter:1".to_owned(),
+                })
+            })
+            .then(vec![CounterEvent::WasStarted {
+                id: "counter:1".to_owned(),
+            }
+            .into()])
+            .assert();
+
+        Scenario::<Decided<CounterDecider>>::new()
+            .given(vec![CounterEvent::WasStarted {
+                id: "counter:1".to_owned(),
+            }
+            .into()])
+            .when(|root: &mut DecidedRoot<CounterDecider>| {
+                root.decide(&CounterCommand::Increment { by: 2 })
+            })
+            .then(vec![CounterEvent::WasIncremented { by: 2 }.into()])
+            .assert();
+
+        Scenario::<Decided<CounterDecider>>::new()
+            .when(|| {
+                DecidedRoot::<CounterDecider>::decide_new(&CounterCommand::Increment { by: 1 })
+            })
+            .then_error(CounterError::NotYetStarted)
+            .assert();
+    }
+
+    #[tokio::test]
+    async fn decider_runs_through_the_repository() {
+        let repository = EventSourcedRepository::<Decided<CounterDecider>, _>::from(InMemory::<
+            String,
+            CounterEvent,
+        >::default(
+        ));
+
+        let mut root = DecidedRoot::<CounterDecider>::decide_new(&CounterCommand::Start {
+            id: "counter:1".to_owned(),
+        })
+        .expect("counter should be started");
+
+        root.decide(&CounterCommand::Increment { by: 3 })
+            .expect("counter should be incremented");
+
+        repository
+            .save(&mut root)
+            .await
+            .expect("counter should be saved");
+
+        let mut root: DecidedRoot<CounterDecider> = repository
+            .get(&"counter:1".to_owned())
+            .await
+            .expect("counter should be found")
+            .into();
+
+        assert_eq!(2, root.version());
+        assert_eq!(3, root.state().value);
+
+        assert_eq!(
+            Err(CounterError::AlreadyStarted),
+            root.decide(&CounterCommand::Start {
+                id: "counter:1".to_owned(),
+            })
+        );
+    }
+}
@@ -0,0 +1,82 @@
+//! Module containing the [`IdSerde`] trait, which controls how Aggregate ids
+//! are encoded to and decoded from their string representation by the data stores.
+
+/// Encodes and decodes an id, such as an [`Aggregate::Id`][crate::aggregate::Aggregate::Id],
+/// to and from the string representation used by data stores to identify
+/// Event Streams and Aggregate Roots.
+///
+/// Implementations must round-trip, i.e. decoding an encoded id must return the same id.
+///
+/// Newtype ids can implement it through the `#[derive(AggregateId)]` macro
+/// of the `eventually-macros` crate.
+pub trait IdSerde: Sized {
+    /// Encodes the id into its string representation.
+    fn encode_id(&self) -> String;
+
+    /// Decodes the id from its string representation.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the string is not a valid representation of the id.
+    fn decode_id(id: &str) -> anyhow::Result<Self>;
+}
+
+impl IdSerde for String {
+    fn encode_id(&self) -> String {
+        self.clone()
+    }
+
+    fn decode_id(id: &str) -> anyhow::Result<Self> {
+        Ok(id.to_owned())
+    }
+}
+
+impl IdSerde for u64 {
+    fn encode_id(&self) -> String {
+        self.to_string()
+    }
+
+    fn decode_id(id: &str) -> anyhow::Result<Self> {
+        id.parse()
+            .map_err(|err| anyhow::anyhow!("failed to decode u64 id '{id}': {err}"))
+    }
+}
+
+#[cfg(feature = "uuid")]
+impl IdSerde for uuid::Uuid {
+    fn encode_id(&self) -> String {
+        self.hyphenated().to_string()
+    }
+
+    fn decode_id(id: &str) -> anyhow::Result<Self> {
+        uuid::Uuid::parse_str(id)
+            .map_err(|err| anyhow::anyhow!("failed to decode uuid id '{id}': {err}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip<T>(id: &T) -> T
+    where
+        T: IdSerde,
+    {
+        T::decode_id(&id.encode_id()).expect("encoded id should be decoded")
+    }
+
+    #[test]
+    fn built_in_ids_round_trip() {
+        assert_eq!("user:1", round_trip(&"user:1".to_owned()));
+        assert_eq!(u64::MAX, round_trip(&u64::MAX));
+        assert!(u64::decode_id("user:1").is_err());
+
+        #[cfg(feature = "uuid")]
+        {
+            let id = uuid::Uuid::from_u128(0x1234_5678_9abc_def0_1234_5678_9abc_def0);
+
+            assert_eq!("12345678-9abc-def0-1234-56789abcdef0", id.encode_id());
+            assert_eq!(id, round_trip(&id));
+        }
+    }
+}
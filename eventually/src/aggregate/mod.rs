@@ -25,14 +25,22 @@
 //! Aggregates should provide a way to **fold** Domain Events on the
 //! current value of the state, to produce the next state.
 
+use std::ops::RangeInclusive;
+
 use crate::version::Version;
 use crate::{event, message};
 
+pub mod decider;
+pub mod id;
 pub mod repository;
+pub mod state_machine;
 pub mod test;
 
 use futures::TryStreamExt;
-pub use repository::{EventSourced as EventSourcedRepository, Repository};
+pub use id::IdSerde;
+pub use repository::{
+    Cached as CachedRepository, EventSourced as EventSourcedRepository, Repository,
+};
 
 /// An Aggregate represents a Domain Model that, through an Aggregate [Root],
 /// acts as a _transactional boundary_.
@@ -71,6 +79,98 @@ pub trait Aggregate: Sized + Send + Sync + Clone {
     /// The method can return an error if the event to apply is unexpected
     /// given the current state of the Aggregate.
     fn apply(state: Option<Self>, event: Self::Event) -> Result<Self, Self::Error>;
+
+    /// Mutates the state of an existing Aggregate in place through a Domain Event,
+    /// used by [`Root::record_that`] when recording new Domain Events.
+    ///
+    /// Since the state must be left untouched when the Domain Event is rejected,
+    /// the default implementation clones the whole state to call [`Aggregate::apply`]:
+    /// Aggregates with a large state should override it to avoid the clone.
+    /// Rehydration always uses [`Aggregate::apply`], which takes the state by value.
+    ///
+    /// # Errors
+    ///
+    /// The method can return an error if the event to apply is unexpected
+    /// given the current state of the Aggregate, in which case the state
+    /// must not be modified.
+    fn apply_in_place(&mut self, event: Self::Event) -> Result<(), Self::Error> {
+        *self = Self::apply(Some(self.clone()), event)?;
+        Ok(())
+    }
+}
+
+/// An opt-in alternative to [Aggregate] for the common case of Aggregates
+/// created by a Domain Event and mutated in place by all the following ones,
+/// which does not require matching on the [`Option`] state in [`Aggregate::apply`].
+///
+/// Every [`FromInitialEvent`] type is an [Aggregate] through a blanket implementation,
+/// so it can be used with [Root] and any [Repository] implementation.
+///
+/// Since [`FromInitialEvent`] and [Aggregate] have methods with the same names,
+/// import only the former in the modules implementing it.
+pub trait FromInitialEvent: Sized + Send + Sync + Clone {
+    /// The type used to uniquely identify the Aggregate.
+    type Id: Send + Sync;
+
+    /// The type of Domain Events that interest this Aggregate.
+    type Event: message::Message + Send + Sync + Clone;
+
+    /// The error type that can be returned when creating or mutating
+    /// the Aggregate state.
+    type Error: Send + Sync;
+
+    /// A unique name identifier for this Aggregate type.
+    fn type_name() -> &'static str;
+
+    /// Returns the unique identifier for the Aggregate instance.
+    fn aggregate_id(&self) -> &Self::Id;
+
+    /// Creates the Aggregate from the first Domain Event of its Event Stream.
+    ///
+    /// # Errors
+    ///
+    /// The method can return an error if the Domain Event cannot create the Aggregate.
+    fn init(event: Self::Event) -> Result<Self, Self::Error>;
+
+    /// Mutates the state of the existing Aggregate through a Domain Event.
+    ///
+    /// # Errors
+    ///
+    /// The method can return an error if the event to apply is unexpected
+    /// given the current state of the Aggregate, in which case the state
+    /// must not be modified.
+    fn apply(&mut self, event: Self::Event) -> Result<(), Self::Error>;
+}
+
+impl<T> Aggregate for T
+where
+    T: FromInitialEvent,
+{
+    type Id = <T as FromInitialEvent>::Id;
+    type Event = <T as FromInitialEvent>::Event;
+    type Error = <T as FromInitialEvent>::Error;
+
+    fn type_name() -> &'static str {
+        <T as FromInitialEvent>::type_name()
+    }
+
+    fn aggregate_id(&self) -> &Self::Id {
+        <T as FromInitialEvent>::aggregate_id(self)
+    }
+
+    fn apply(state: Option<Self>, event: Self::Event) -> Result<Self, Self::Error> {
+        match state {
+            None => T::init(event),
+            Some(mut state) => {
+                <T as FromInitialEvent>::apply(&mut state, event)?;
+                Ok(state)
+            },
+        }
+    }
+
+    fn apply_in_place(&mut self, event: Self::Event) -> Result<(), Self::Error> {
+        <T as FromInitialEvent>::apply(self, event)
+    }
 }
 
 /// An Aggregate Root represents the Domain Entity object used to
@@ -123,6 +223,13 @@ pub trait Aggregate: Sized + Send + Sync + Clone {
 ///     }
 /// }
 /// ```
+///
+/// # Equality
+///
+/// The [`PartialEq`] implementation compares the whole [Root], including the
+/// Domain Events recorded and not yet saved. Use [`Root::same_identity`] or
+/// [`Root::same_state`] for more lenient comparisons, and [`Root::identity`]
+/// to compare or hash [Root]s by id and version.
 #[derive(Debug, Clone, PartialEq)]
 #[must_use]
 pub struct Root<T>
@@ -132,6 +239,19 @@ where
     aggregate: T,
     version: Version,
     recorded_events: Vec<event::Envelope<T::Event>>,
+    causation: message::Metadata,
+}
+
+/// Identity of an [Aggregate Root][Root], made of its id and version.
+///
+/// Use it to opt into identity-based equality and hashing of [Aggregate Root][Root]s,
+/// e.g. as a key in a [`HashSet`][std::collections::HashSet].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Identity<Id> {
+    /// The unique identifier of the [Aggregate].
+    pub id: Id,
+    /// The version of the [Aggregate Root][Root].
+    pub version: Version,
 }
 
 impl<T> std::ops::Deref for Root<T>
@@ -159,6 +279,35 @@ where
         self.aggregate.aggregate_id()
     }
 
+    /// Returns the [Identity] of the [Aggregate Root][Root], i.e. its id and version.
+    pub fn identity(&self) -> Identity<T::Id>
+    where
+        T::Id: Clone,
+    {
+        Identity {
+            id: self.aggregate_id().clone(),
+            version: self.version,
+        }
+    }
+
+    /// Returns true if both [Aggregate Root][Root]s have the same id and version,
+    /// regardless of the Domain Events recorded and not yet saved.
+    pub fn same_identity(&self, other: &Self) -> bool
+    where
+        T::Id: PartialEq,
+    {
+        self.aggregate_id() == other.aggregate_id() && self.version == other.version
+    }
+
+    /// Returns true if both [Aggregate Root][Root]s have the same [Aggregate] state
+    /// and version, regardless of the Domain Events recorded and not yet saved.
+    pub fn same_state(&self, other: &Self) -> bool
+    where
+        T: PartialEq,
+    {
+        self.aggregate == other.aggregate && self.version == other.version
+    }
+
     /// Maps the [Aggregate] value contained within [Root]
     /// to a different type, that can be converted through [From] trait.
     ///
@@ -173,9 +322,101 @@ where
 
     /// Returns the list of uncommitted, recorded Domain [Event]s from the [Root]
     /// and resets the internal list to its default value.
+    ///
+    /// The returned Domain Events carry the correlation and causation ids of
+    /// the Command being handled, as explained in [`Root::caused_by`].
     #[doc(hidden)]
     pub fn take_uncommitted_events(&mut self) -> Vec<event::Envelope<T::Event>> {
-        std::mem::take(&mut self.recorded_events)
+        let mut events = std::mem::take(&mut self.recorded_events);
+
+        for event in &mut events {
+            self.stamp_causation(event);
+        }
+
+        self.causation = message::Metadata::default();
+
+        events
+    }
+
+    /// Returns the uncommitted, recorded Domain [Event]s from the [Root],
+    /// without consuming them, e.g. to log them before saving the [Root].
+    ///
+    /// The returned Domain Events do not carry the correlation and causation ids
+    /// of the Command, which are added only when they are saved.
+    pub fn uncommitted_events(&self) -> &[event::Envelope<T::Event>] {
+        &self.recorded_events
+    }
+
+    /// Returns the range of [Version]s the uncommitted, recorded Domain [Event]s
+    /// will have once saved, or [None] if there are no Domain Events to save.
+    ///
+    /// The Event Stream is expected to be at the version preceding the range start
+    /// when the [Root] is saved.
+    pub fn uncommitted_versions(&self) -> Option<RangeInclusive<Version>> {
+        if self.recorded_events.is_empty() {
+            return None;
+        }
+
+        let first_version = self.version + 1 - self.recorded_events.len() as Version;
+
+        Some(first_version..=self.version)
+    }
+
+    /// Returns a copy of the uncommitted, recorded Domain [Event]s from the [Root],
+    /// as they would be returned by [`Root::take_uncommitted_events`].
+    pub(crate) fn uncommitted_events_with_causation(&self) -> Vec<event::Envelope<T::Event>> {
+        self.recorded_events
+            .iter()
+            .cloned()
+            .map(|mut event| {
+                self.stamp_causation(&mut event);
+                event
+            })
+            .collect()
+    }
+
+    /// Adds the correlation and causation ids of the Command to the Domain Event,
+    /// keeping the ones already set when recording it.
+    fn stamp_causation(&self, event: &mut event::Envelope<T::Event>) {
+        let causation = message::current_causation().unwrap_or_default();
+
+        for (key, value) in self.causation.iter().chain(&causation) {
+            event
+                .metadata
+                .entry(key.clone())
+                .or_insert_with(|| value.clone());
+        }
+    }
+
+    /// Marks all the Domain Events recorded by the [Root] as caused by
+    /// the specified [Command][crate::command::Envelope].
+    ///
+    /// The Command correlation id and message id are stamped on the uncommitted
+    /// Domain Events when they get committed, so that the flow of messages
+    /// can be traced across different Aggregates.
+    ///
+    /// The Commands dispatched through the [Bus][crate::command::Bus], or handled
+    /// by a [`StampCausation`][crate::command::handler::StampCausation] Handler,
+    /// are stamped automatically on all the [Root]s saved while handling them:
+    /// this method is only needed to mark the Domain Events as caused by
+    /// a different message.
+    ///
+    /// Example of usage:
+    /// ```text
+    /// async fn handle(&self, command: command::Envelope<ChangeName>) -> Result<(), Self::Error> {
+    ///     let mut root = self.repository.get(&command.message.id).await?;
+    ///
+    ///     root.caused_by(&command);
+    ///     root.change_name(command.message.name)?;
+    ///
+    ///     self.repository.save(&mut root).await?;
+    /// }
+    /// ```
+    pub fn caused_by<C>(&mut self, command: &message::Envelope<C>)
+    where
+        C: message::Message,
+    {
+        self.causation = message::causation_metadata(command);
     }
 
     /// Creates a new [Aggregate] [Root] instance by applying the specified
@@ -203,6 +444,7 @@ where
             version: 1,
             aggregate: T::apply(None, event.message.clone())?,
             recorded_events: vec![event],
+            causation: message::Metadata::default(),
         })
     }
 
@@ -234,7 +476,7 @@ where
     /// The method can return an error if the event to apply is unexpected
     /// given the current state of the Aggregate.
     pub fn record_that(&mut self, event: event::Envelope<T::Event>) -> Result<(), T::Error> {
-        self.aggregate = T::apply(Some(self.aggregate.clone()), event.message.clone())?;
+        self.aggregate.apply_in_place(event.message.clone())?;
         self.recorded_events.push(event);
         self.version += 1;
 
@@ -246,15 +488,15 @@ where
 #[derive(Debug, thiserror::Error)]
 pub enum RehydrateError<T, I> {
     /// Error returned during rehydration when the [Aggregate Root][Root]
-    /// is applying a Domain Event using [Aggregate::apply].
+    /// is applying a Domain Event using [`Aggregate::apply`].
     ///
     /// This usually implies the Event Stream for the [Aggregate]
     /// contains corrupted or unexpected data.
     #[error("failed to apply domain event while rehydrating aggregate: {0}")]
     Domain(#[source] T),
 
-    /// This error is returned by [Root::rehydrate_async] when the underlying
-    /// [futures::TryStream] has returned an error.
+    /// This error is returned by [`Root::rehydrate_async`] when the underlying
+    /// [`futures::TryStream`] has returned an error.
     #[error("failed to rehydrate aggregate from event stream: {0}")]
     Inner(#[source] I),
 }
@@ -271,6 +513,7 @@ where
             version,
             aggregate,
             recorded_events: Vec::default(),
+            causation: message::Metadata::default(),
         }
     }
 
@@ -320,6 +563,7 @@ where
             version: 1,
             aggregate: T::apply(None, event.message)?,
             recorded_events: Vec::default(),
+            causation: message::Metadata::default(),
         })
     }
 
@@ -355,6 +599,12 @@ pub(crate) mod test_user_domain {
         password: String,
     }
 
+    impl User {
+        pub(crate) fn password(&self) -> &str {
+            &self.password
+        }
+    }
+
     #[derive(Debug, Clone, PartialEq, Eq)]
     pub(crate) enum UserEvent {
         WasCreated { email: String, password: String },
@@ -442,10 +692,170 @@ pub(crate) mod test_user_domain {
 mod tests {
     use std::error::Error;
 
-    use crate::aggregate::repository::{Getter, Saver};
+    use futures::StreamExt;
+
+    use crate::aggregate::repository::{GetError, Getter, Saver};
     use crate::aggregate::test_user_domain::{User, UserEvent};
+    use crate::event::store::Appender;
     use crate::event::store::EventStoreExt;
-    use crate::{aggregate, event, version};
+    use crate::message::tests::StringMessage;
+    use crate::{aggregate, event, message, version};
+
+    #[test]
+    fn roots_compare_by_identity_regardless_of_recorded_events() {
+        let mut user =
+            aggregate::Root::<User>::create("test@email.com".to_owned(), "not-a-secret".to_owned())
+                .expect("user should be created successfully");
+
+        user.take_uncommitted_events();
+
+        let mut changed_user = user.clone();
+        changed_user
+            .change_password("new-password".to_owned())
+            .expect("password should be changed successfully");
+
+        let mut rehydrated_user = changed_user.clone();
+        rehydrated_user.take_uncommitted_events();
+
+        assert!(!user.same_identity(&changed_user));
+        assert!(changed_user.same_identity(&rehydrated_user));
+        assert_eq!(changed_user.identity(), rehydrated_user.identity());
+        assert_eq!(
+            aggregate::Identity {
+                id: "test@email.com".to_owned(),
+                version: 2,
+            },
+            rehydrated_user.identity()
+        );
+    }
+
+    #[test]
+    fn rejected_events_leave_the_aggregate_root_untouched() {
+        let mut user =
+            aggregate::Root::<User>::create("test@email.com".to_owned(), "not-a-secret".to_owned())
+                .expect("user should be created successfully");
+
+        user.record_that(
+            UserEvent::WasCreated {
+                email: "test@email.com".to_owned(),
+                password: "another-secret".to_owned(),
+            }
+            .into(),
+        )
+        .expect_err("user should not be created twice");
+
+        assert_eq!(1, user.version());
+        assert_eq!("not-a-secret", user.password());
+        assert_eq!(1, user.take_uncommitted_events().len());
+    }
+
+    #[test]
+    fn uncommitted_events_can_be_inspected_without_consuming_them() {
+        let mut user =
+            aggregate::Root::<User>::create("test@email.com".to_owned(), "not-a-secret".to_owned())
+                .expect("user should be created successfully");
+
+        user.change_password("new-secret".to_owned())
+            .expect("user password should be changed successfully");
+
+        assert_eq!(2, user.uncommitted_events().len());
+        assert_eq!(Some(1..=2), user.uncommitted_versions());
+
+        user.take_uncommitted_events();
+
+        user.change_password("newer-secret".to_owned())
+            .expect("user password should be changed successfully");
+
+        assert_eq!(
+            &[event::Envelope::from(UserEvent::PasswordWasChanged {
+                password: "newer-secret".to_owned(),
+            })],
+            user.uncommitted_events()
+        );
+        assert_eq!(Some(3..=3), user.uncommitted_versions());
+
+        user.take_uncommitted_events();
+
+        assert!(user.uncommitted_events().is_empty());
+        assert_eq!(None, user.uncommitted_versions());
+    }
+
+    #[test]
+    fn from_initial_event_aggregates_are_created_and_mutated_in_place() {
+        #[derive(Debug, Clone, PartialEq, Eq)]
+        enum CounterEvent {
+            Started { id: u64 },
+            Incremented,
+        }
+
+        impl message::Message for CounterEvent {
+            fn name(&self) -> &'static str {
+                match self {
+                    CounterEvent::Started { .. } => "CounterStarted",
+                    CounterEvent::Incremented => "CounterIncremented",
+                }
+            }
+        }
+
+        #[derive(Debug, Clone, PartialEq, Eq)]
+        struct Counter {
+            id: u64,
+            value: u64,
+        }
+
+        impl aggregate::FromInitialEvent for Counter {
+            type Id = u64;
+            type Event = CounterEvent;
+            type Error = &'static str;
+
+            fn type_name() -> &'static str {
+                "Counter"
+            }
+
+            fn aggregate_id(&self) -> &Self::Id {
+                &self.id
+            }
+
+            fn init(event: Self::Event) -> Result<Self, Self::Error> {
+                match event {
+                    CounterEvent::Started { id } => Ok(Self { id, value: 0 }),
+                    CounterEvent::Incremented => Err("counter not started yet"),
+                }
+            }
+
+            fn apply(&mut self, event: Self::Event) -> Result<(), Self::Error> {
+                match event {
+                    CounterEvent::Started { .. } => Err("counter already started"),
+                    CounterEvent::Incremented => {
+                        self.value += 1;
+                        Ok(())
+                    },
+                }
+            }
+        }
+
+        assert_eq!(
+            Err("counter not started yet"),
+            aggregate::Root::<Counter>::record_new(CounterEvent::Incremented.into())
+        );
+
+        let mut counter =
+            aggregate::Root::<Counter>::record_new(CounterEvent::Started { id: 1 }.into())
+                .expect("counter should be started successfully");
+
+        counter
+            .record_that(CounterEvent::Incremented.into())
+            .expect("counter should be incremented successfully");
+
+        assert_eq!(
+            Err("counter already started"),
+            counter.record_that(CounterEvent::Started { id: 2 }.into())
+        );
+
+        assert_eq!(1, counter.value);
+        assert_eq!(2, counter.version());
+        assert_eq!("Counter", <Counter as aggregate::Aggregate>::type_name());
+    }
 
     #[tokio::test]
     async fn repository_persists_new_aggregate_root() {
@@ -521,6 +931,166 @@ mod tests {
         assert_eq!(expected_events, tracking_event_store.recorded_events());
     }
 
+    #[tokio::test]
+    async fn repository_persists_events_with_the_causing_command_metadata() {
+        let event_store = event::store::InMemory::<String, UserEvent>::default();
+        let tracking_event_store = event_store.with_recorded_events_tracking();
+        let user_repository =
+            aggregate::EventSourcedRepository::<User, _>::from(tracking_event_store.clone());
+
+        let command = message::Envelope::from(StringMessage("CreateUser"))
+            .with_message_id("command-1".to_owned())
+            .with_correlation_id("request-1".to_owned());
+
+        let mut user =
+            aggregate::Root::<User>::create("test@email.com".to_owned(), "secret".to_owned())
+                .expect("user should be created successfully");
+
+        user.caused_by(&command);
+
+        user_repository
+            .save(&mut user)
+            .await
+            .expect("user should be saved successfully");
+
+        let recorded_events = tracking_event_store.recorded_events();
+        let event = &recorded_events
+            .first()
+            .expect("an event should have been recorded")
+            .event;
+
+        assert_eq!(Some("request-1"), event.correlation_id());
+        assert_eq!(Some("command-1"), event.causation_id());
+    }
+
+    #[tokio::test]
+    async fn repository_keeps_the_correlation_id_recorded_by_the_domain() {
+        let event_store = event::store::InMemory::<String, UserEvent>::default();
+        let tracking_event_store = event_store.with_recorded_events_tracking();
+        let user_repository =
+            aggregate::EventSourcedRepository::<User, _>::from(tracking_event_store.clone());
+
+        let command = message::Envelope::from(StringMessage("CreateUser"))
+            .with_message_id("command-1".to_owned())
+            .with_correlation_id("request-1".to_owned());
+
+        let mut user =
+            aggregate::Root::<User>::create("test@email.com".to_owned(), "secret".to_owned())
+                .expect("user should be created successfully");
+
+        user.caused_by(&command);
+
+        user_repository
+            .save(&mut user)
+            .await
+            .expect("user should be saved successfully");
+
+        // The causation of the saved Domain Events is not carried over.
+        user.record_that(
+            event::Envelope::from(UserEvent::PasswordWasChanged {
+                password: "new-secret".to_owned(),
+            })
+            .with_correlation_id("domain-1".to_owned()),
+        )
+        .expect("password should be changed successfully");
+
+        user_repository
+            .save(&mut user)
+            .await
+            .expect("user should be saved successfully");
+
+        let recorded_events = tracking_event_store.recorded_events();
+        let event = &recorded_events
+            .last()
+            .expect("an event should have been recorded")
+            .event;
+
+        assert_eq!(Some("domain-1"), event.correlation_id());
+        assert_eq!(None, event.causation_id());
+    }
+
+    #[tokio::test]
+    async fn repository_get_many_reports_results_for_each_id() {
+        let event_store = event::store::InMemory::<String, UserEvent>::default();
+        let user_repository = aggregate::EventSourcedRepository::<User, _>::from(event_store);
+
+        let emails: Vec<String> = (0..3).map(|i| format!("test-{i}@email.com")).collect();
+
+        for email in &emails[..2] {
+            let mut user = aggregate::Root::<User>::create(email.clone(), "secret".to_owned())
+                .expect("user should be created successfully");
+
+            user_repository
+                .save(&mut user)
+                .await
+                .expect("user should be saved successfully");
+        }
+
+        let mut results: Vec<_> = user_repository.get_many(&emails, 2).collect().await;
+        results.sort_by_key(|(id, _)| (*id).clone());
+
+        assert_eq!(emails.len(), results.len());
+        assert!(
+            matches!(&results[0], (id, Ok(user)) if *id == &emails[0] && user.aggregate_id() == &emails[0])
+        );
+        assert!(
+            matches!(&results[1], (id, Ok(user)) if *id == &emails[1] && user.aggregate_id() == &emails[1])
+        );
+        assert!(matches!(&results[2], (id, Err(GetError::NotFound)) if *id == &emails[2]));
+    }
+
+    #[tokio::test]
+    async fn cached_repository_warm_loads_aggregate_roots_in_the_cache() {
+        let event_store = event::store::InMemory::<String, UserEvent>::default();
+        let user_repository =
+            aggregate::EventSourcedRepository::<User, _>::from(event_store.clone());
+
+        let emails: Vec<String> = (0..5).map(|i| format!("test-{i}@email.com")).collect();
+
+        for email in &emails {
+            let mut user = aggregate::Root::<User>::create(email.clone(), "secret".to_owned())
+                .expect("user should be created successfully");
+
+            user_repository
+                .save(&mut user)
+                .await
+                .expect("user should be saved successfully");
+        }
+
+        let cached_repository = aggregate::CachedRepository::from(user_repository);
+
+        cached_repository
+            .warm(
+                emails
+                    .iter()
+                    .cloned()
+                    .chain(std::iter::once("unknown@email.com".to_owned())),
+                2,
+            )
+            .await
+            .expect("warming up the cache should not fail");
+
+        // Appending directly to the Event Store bypasses the cache,
+        // so the cached Aggregate Root is returned instead.
+        event_store
+            .append(
+                emails[0].clone(),
+                version::Check::MustBe(1),
+                vec![event::Envelope::from(UserEvent::PasswordWasChanged {
+                    password: "new-secret".to_owned(),
+                })],
+            )
+            .await
+            .expect("append should not fail");
+
+        let user = cached_repository
+            .get(&emails[0])
+            .await
+            .expect("user should be retrieved from the cache");
+
+        assert_eq!(1, user.version());
+    }
+
     #[tokio::test]
     async fn repository_returns_conflict_error_from_store_when_data_race_happens() {
         let event_store = event::store::InMemory::<String, UserEvent>::default();
@@ -556,7 +1126,7 @@ mod tests {
         {
             assert!(error
                 .source()
-                .map_or(false, |src| src.is::<version::ConflictError>()));
+                .is_some_and(|src| src.is::<version::ConflictError>()));
         }
     }
 }
@@ -4,11 +4,16 @@
 //! If you are looking for the Event-sourced implementation of an Aggregate Repository,
 //! take a look at [`EventSourced`].
 
+use std::collections::{BTreeMap, HashMap};
 use std::fmt::Debug;
+use std::hash::Hash;
 use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, Mutex, MutexGuard};
 
 use async_trait::async_trait;
-use futures::TryStreamExt;
+use futures::stream::BoxStream;
+use futures::{StreamExt, TryStreamExt};
 
 use crate::aggregate::Aggregate;
 use crate::{aggregate, event, version};
@@ -24,22 +29,46 @@ pub enum GetError {
     Internal(#[from] anyhow::Error),
 }
 
+/// Stream of the results returned by [`Getter::get_many`], each one
+/// paired with the id of the [`aggregate::Root`] it refers to.
+pub type GetManyStream<'a, T> = BoxStream<
+    'a,
+    (
+        &'a <T as Aggregate>::Id,
+        Result<aggregate::Root<T>, GetError>,
+    ),
+>;
+
 /// Trait used to implement read access to a data store from which
-/// to load an [aggregate::Root] instance, given its id.
+/// to load an [`aggregate::Root`] instance, given its id.
 #[async_trait]
 pub trait Getter<T>: Send + Sync
 where
     T: Aggregate,
 {
-    /// Loads an [aggregate::Root] instance from the data store,
+    /// Loads an [`aggregate::Root`] instance from the data store,
     /// referenced by its unique identifier.
     async fn get(&self, id: &T::Id) -> Result<aggregate::Root<T>, GetError>;
+
+    /// Loads multiple [`aggregate::Root`] instances from the data store,
+    /// running at most `concurrency` loads at the same time.
+    ///
+    /// The result of each load is streamed back as soon as it is available,
+    /// together with the id it refers to, so that partial failures can be reported
+    /// without interrupting the other loads. The order of the results
+    /// is not guaranteed to match the order of the ids.
+    fn get_many<'a>(&'a self, ids: &'a [T::Id], concurrency: usize) -> GetManyStream<'a, T> {
+        futures::stream::iter(ids)
+            .map(move |id| async move { (id, self.get(id).await) })
+            .buffer_unordered(concurrency.max(1))
+            .boxed()
+    }
 }
 
 /// All possible errors returned by [`Saver::save`].
 #[derive(Debug, thiserror::Error)]
 pub enum SaveError {
-    /// Error returned when [Saver::save] encounters a conflict error while saving the new Aggregate Root.
+    /// Error returned when [`Saver::save`] encounters a conflict error while saving the new Aggregate Root.
     #[error("failed to save aggregate root: {0}")]
     Conflict(#[from] version::ConflictError),
     /// Error returned when the [Saver] implementation has encountered an error.
@@ -48,14 +77,76 @@ pub enum SaveError {
 }
 
 /// Trait used to implement write access to a data store, which can be used
-/// to save the latest state of an [aggregate::Root] instance.
+/// to save the latest state of an [`aggregate::Root`] instance.
 #[async_trait]
 pub trait Saver<T>: Send + Sync
 where
     T: Aggregate,
 {
-    /// Saves a new version of an [aggregate::Root] instance to the data store.
+    /// Saves a new version of an [`aggregate::Root`] instance to the data store.
     async fn save(&self, root: &mut aggregate::Root<T>) -> Result<(), SaveError>;
+
+    /// Saves a new version of an [`aggregate::Root`] instance to the data store,
+    /// like [`Saver::save`], returning the Domain Events that have been committed
+    /// together with their final [Version][version::Version].
+    ///
+    /// Useful to publish, log or return the committed Domain Events to clients
+    /// without having to stream them back from the data store.
+    ///
+    /// The returned Domain Events carry the metadata recorded by the [`aggregate::Root`],
+    /// but not the metadata that the data store might add while persisting them,
+    /// such as [`event::RECORDED_AT_KEY`].
+    async fn save_and_return_events(
+        &self,
+        root: &mut aggregate::Root<T>,
+    ) -> Result<Vec<event::Persisted<T::Id, T::Event>>, SaveError>
+    where
+        T::Id: Clone,
+    {
+        let events = root.uncommitted_events_with_causation();
+
+        self.save(root).await?;
+
+        // NOTE: the version is computed after saving, as the Domain Events
+        // could have been rebased on a newer version of the Aggregate Root.
+        let first_version = root.version() + 1 - events.len() as version::Version;
+
+        Ok((first_version..)
+            .zip(events)
+            .map(|(version, event)| event::Persisted {
+                stream_id: root.aggregate_id().clone(),
+                version,
+                event,
+            })
+            .collect())
+    }
+}
+
+/// All possible errors returned by [`Deleter::delete`].
+#[derive(Debug, thiserror::Error)]
+pub enum DeleteError {
+    /// Error returned when the [Aggregate Root][aggregate::Root] has been modified
+    /// since it has been loaded, and it should be loaded again before deleting it.
+    #[error("failed to delete aggregate root: {0}")]
+    Conflict(#[from] version::ConflictError),
+    /// Error returned when the [Deleter] implementation has encountered an error.
+    #[error("failed to delete aggregate root, an error occurred: {0}")]
+    Internal(#[from] anyhow::Error),
+}
+
+/// Trait used to implement the deletion of an [`aggregate::Root`] instance
+/// from a data store.
+#[async_trait]
+pub trait Deleter<T>: Send + Sync
+where
+    T: Aggregate,
+{
+    /// Marks the [`aggregate::Root`] instance as deleted in the data store,
+    /// after which [`Getter::get`] returns [`GetError::NotFound`] for its id.
+    ///
+    /// The [`aggregate::Root`] must be at the version saved in the data store:
+    /// the Domain Events it has recorded and not saved yet are discarded.
+    async fn delete(&self, root: aggregate::Root<T>) -> Result<(), DeleteError>;
 }
 
 /// A Repository is an object that allows to load and save
@@ -73,21 +164,127 @@ where
 {
 }
 
+#[async_trait]
+impl<T, R> Getter<T> for Arc<R>
+where
+    T: Aggregate,
+    T::Id: Sync,
+    R: Getter<T> + ?Sized,
+{
+    async fn get(&self, id: &T::Id) -> Result<aggregate::Root<T>, GetError> {
+        (**self).get(id).await
+    }
+
+    fn get_many<'a>(&'a self, ids: &'a [T::Id], concurrency: usize) -> GetManyStream<'a, T> {
+        (**self).get_many(ids, concurrency)
+    }
+}
+
+#[async_trait]
+impl<T, R> Saver<T> for Arc<R>
+where
+    T: Aggregate,
+    R: Saver<T> + ?Sized,
+{
+    async fn save(&self, root: &mut aggregate::Root<T>) -> Result<(), SaveError> {
+        (**self).save(root).await
+    }
+
+    async fn save_and_return_events(
+        &self,
+        root: &mut aggregate::Root<T>,
+    ) -> Result<Vec<event::Persisted<T::Id, T::Event>>, SaveError>
+    where
+        T::Id: Clone,
+    {
+        (**self).save_and_return_events(root).await
+    }
+}
+
+#[async_trait]
+impl<T, R> Deleter<T> for Arc<R>
+where
+    T: Aggregate + 'static,
+    R: Deleter<T> + ?Sized,
+{
+    async fn delete(&self, root: aggregate::Root<T>) -> Result<(), DeleteError> {
+        (**self).delete(root).await
+    }
+}
+
+/// Maximum number of times [`EventSourced`] rebases the uncommitted Domain Events
+/// of an [`aggregate::Root`] on a conflict, before returning [`SaveError::Conflict`].
+const MAX_REBASE_ATTEMPTS: usize = 3;
+
+/// Decides whether the Domain Events recorded by an [`aggregate::Root`]
+/// can be saved on top of the Domain Events appended to its Event Stream
+/// since the [`aggregate::Root`] has been loaded, used by [`EventSourced`]
+/// to automatically merge commutative operations on a conflict.
+///
+/// The trait is implemented by any closure with the same signature
+/// as [`ConflictResolver::commutes`].
+pub trait ConflictResolver<T>: Send + Sync
+where
+    T: Aggregate,
+{
+    /// Returns true if the uncommitted Domain Events commute with the Domain Events
+    /// appended concurrently, i.e. applying them in any order leads to the same state,
+    /// so that they can be rebased on the latest version of the Event Stream.
+    fn commutes(
+        &self,
+        uncommitted: &[event::Envelope<T::Event>],
+        concurrent: &[event::Envelope<T::Event>],
+    ) -> bool;
+}
+
+impl<T, F> ConflictResolver<T> for F
+where
+    T: Aggregate,
+    F: Fn(&[event::Envelope<T::Event>], &[event::Envelope<T::Event>]) -> bool + Send + Sync,
+{
+    fn commutes(
+        &self,
+        uncommitted: &[event::Envelope<T::Event>],
+        concurrent: &[event::Envelope<T::Event>],
+    ) -> bool {
+        self(uncommitted, concurrent)
+    }
+}
+
 /// An Event-sourced implementation of the [Repository] interface.
 ///
 /// It uses an [Event Store][event::Store] instance to stream Domain Events
 /// for a particular Aggregate, and append uncommitted Domain Events
 /// recorded by an Aggregate Root.
-#[derive(Debug, Clone)]
+///
+/// By default, saving an Aggregate Root that has been modified concurrently
+/// returns [`SaveError::Conflict`]. Use [`EventSourced::with_conflict_resolver`]
+/// to rebase the uncommitted Domain Events instead, when they commute
+/// with the concurrent ones.
+#[derive(Clone)]
 pub struct EventSourced<T, S>
 where
     T: Aggregate,
     S: event::Store<T::Id, T::Event>,
 {
     store: S,
+    conflict_resolver: Option<Arc<dyn ConflictResolver<T>>>,
     aggregate: PhantomData<T>,
 }
 
+impl<T, S> Debug for EventSourced<T, S>
+where
+    T: Aggregate,
+    S: event::Store<T::Id, T::Event> + Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EventSourced")
+            .field("store", &self.store)
+            .field("conflict_resolver", &self.conflict_resolver.is_some())
+            .finish()
+    }
+}
+
 impl<T, S> From<S> for EventSourced<T, S>
 where
     T: Aggregate,
@@ -96,11 +293,31 @@ where
     fn from(store: S) -> Self {
         Self {
             store,
+            conflict_resolver: None,
             aggregate: PhantomData,
         }
     }
 }
 
+impl<T, S> EventSourced<T, S>
+where
+    T: Aggregate,
+    S: event::Store<T::Id, T::Event>,
+{
+    /// Sets the [`ConflictResolver`] used by [`Saver::save`] on a conflict:
+    /// if the uncommitted Domain Events commute with the ones appended since
+    /// the [`aggregate::Root`] has been loaded, they are applied on top of
+    /// the latest version of the [`aggregate::Root`] and saved again.
+    #[must_use]
+    pub fn with_conflict_resolver<R>(mut self, resolver: R) -> Self
+    where
+        R: ConflictResolver<T> + 'static,
+    {
+        self.conflict_resolver = Some(Arc::new(resolver));
+        self
+    }
+}
+
 #[async_trait]
 impl<T, S> Getter<T> for EventSourced<T, S>
 where
@@ -126,36 +343,799 @@ where
     }
 }
 
+/// An [`aggregate::Root`] loaded through [`EventSourced::get_for_update`],
+/// together with the exclusive lock held on its Event Stream.
+///
+/// The lock is released when this value is dropped, so it should be kept around
+/// until the [`aggregate::Root`] has been saved.
+pub struct Locked<T, L>
+where
+    T: Aggregate,
+{
+    root: aggregate::Root<T>,
+    lock: L,
+}
+
+impl<T, L> Locked<T, L>
+where
+    T: Aggregate,
+{
+    /// Returns the [`aggregate::Root`] and the lock held on its Event Stream.
+    pub fn into_parts(self) -> (aggregate::Root<T>, L) {
+        (self.root, self.lock)
+    }
+}
+
+impl<T, L> Debug for Locked<T, L>
+where
+    T: Aggregate + Debug,
+    T::Event: Debug,
+    L: Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Locked")
+            .field("root", &self.root)
+            .field("lock", &self.lock)
+            .finish()
+    }
+}
+
+impl<T, L> Deref for Locked<T, L>
+where
+    T: Aggregate,
+{
+    type Target = aggregate::Root<T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.root
+    }
+}
+
+impl<T, L> DerefMut for Locked<T, L>
+where
+    T: Aggregate,
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.root
+    }
+}
+
+impl<T, S> EventSourced<T, S>
+where
+    T: Aggregate,
+    T::Id: Clone,
+    T::Error: std::error::Error + Send + Sync + 'static,
+    S: event::Store<T::Id, T::Event> + event::store::Locker<T::Id>,
+    <S as event::store::Streamer<T::Id, T::Event>>::Error:
+        std::error::Error + Send + Sync + 'static,
+{
+    /// Loads an [`aggregate::Root`] instance while holding an exclusive lock
+    /// on its Event Stream, for workflows where conflict-retry loops are not acceptable.
+    ///
+    /// The lock only excludes other callers of [`EventSourced::get_for_update`]:
+    /// saving the [`aggregate::Root`] is still subject to the usual
+    /// optimistic concurrency checks.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the lock could not be acquired, or if
+    /// the [`aggregate::Root`] could not be loaded.
+    pub async fn get_for_update(
+        &self,
+        id: &T::Id,
+    ) -> Result<Locked<T, <S as event::store::Locker<T::Id>>::Lock>, GetError> {
+        let lock = self.store.lock(id).await.map_err(|err| match err {
+            event::store::LockError::Internal(err) => GetError::Internal(err),
+        })?;
+
+        let root = self.get(id).await?;
+
+        Ok(Locked { root, lock })
+    }
+}
+
+impl<T, S> EventSourced<T, S>
+where
+    T: Aggregate,
+    T::Error: std::error::Error + Send + Sync + 'static,
+    S: event::Store<T::Id, T::Event>,
+    <S as event::store::Streamer<T::Id, T::Event>>::Error:
+        std::error::Error + Send + Sync + 'static,
+{
+    /// Loads the latest version of the [`aggregate::Root`] and records the uncommitted
+    /// Domain Events on top of it, if they commute with the Domain Events
+    /// appended after the specified version.
+    ///
+    /// Returns [None] if the Domain Events cannot be rebased.
+    async fn rebase(
+        &self,
+        resolver: &dyn ConflictResolver<T>,
+        id: &T::Id,
+        version: version::Version,
+        events: &[event::Envelope<T::Event>],
+    ) -> Result<Option<aggregate::Root<T>>, SaveError> {
+        let persisted: Vec<_> = self
+            .store
+            .stream(id, event::VersionSelect::All)
+            .try_collect()
+            .await
+            .map_err(anyhow::Error::from)?;
+
+        let concurrent: Vec<_> = persisted
+            .iter()
+            .filter(|persisted| persisted.version > version)
+            .map(|persisted| persisted.event.clone())
+            .collect();
+
+        if !resolver.commutes(events, &concurrent) {
+            return Ok(None);
+        }
+
+        let stream = futures::stream::iter(persisted)
+            .map(|persisted| Ok::<_, std::convert::Infallible>(persisted.event));
+
+        let Some(mut root) = aggregate::Root::<T>::rehydrate_async(stream)
+            .await
+            .map_err(anyhow::Error::from)?
+        else {
+            return Ok(None);
+        };
+
+        for event in events {
+            if root.record_that(event.clone()).is_err() {
+                return Ok(None);
+            }
+        }
+
+        Ok(Some(root))
+    }
+}
+
+fn save_error(err: event::store::AppendError) -> SaveError {
+    match err {
+        event::store::AppendError::Conflict(err) => SaveError::Conflict(err),
+        event::store::AppendError::Internal(err) => SaveError::Internal(err),
+        err @ event::store::AppendError::Unsupported => SaveError::Internal(err.into()),
+    }
+}
+
 #[async_trait]
 impl<T, S> Saver<T> for EventSourced<T, S>
 where
     T: Aggregate,
     T::Id: Clone,
+    T::Error: std::error::Error + Send + Sync + 'static,
     S: event::Store<T::Id, T::Event>,
+    <S as event::store::Streamer<T::Id, T::Event>>::Error:
+        std::error::Error + Send + Sync + 'static,
 {
+    /// Saves the uncommitted Domain Events of the [`aggregate::Root`].
+    ///
+    /// If a [`ConflictResolver`] has been set and the Domain Events get rebased,
+    /// the [`aggregate::Root`] is replaced with the latest version saved.
     async fn save(&self, root: &mut aggregate::Root<T>) -> Result<(), SaveError> {
-        let events_to_commit = root.take_uncommitted_events();
-        let aggregate_id = root.aggregate_id();
+        let mut events_to_commit = root.take_uncommitted_events();
+        let aggregate_id = root.aggregate_id().clone();
 
         if events_to_commit.is_empty() {
             return Ok(());
         }
 
-        let current_event_stream_version =
+        let mut current_event_stream_version =
             root.version() - (events_to_commit.len() as version::Version);
+        let mut rebased_root = None;
+
+        for attempt in 0.. {
+            let events = match self.conflict_resolver {
+                Some(_) => events_to_commit.clone(),
+                None => std::mem::take(&mut events_to_commit),
+            };
+
+            let err = match self
+                .store
+                .append(
+                    aggregate_id.clone(),
+                    version::Check::MustBe(current_event_stream_version),
+                    events,
+                )
+                .await
+            {
+                Ok(_) => break,
+                Err(event::store::AppendError::Conflict(err)) => err,
+                Err(err) => return Err(save_error(err)),
+            };
+
+            let Some(resolver) = self.conflict_resolver.as_deref() else {
+                return Err(SaveError::Conflict(err));
+            };
+
+            if attempt == MAX_REBASE_ATTEMPTS {
+                return Err(SaveError::Conflict(err));
+            }
+
+            let Some(mut rebased) = self
+                .rebase(
+                    resolver,
+                    &aggregate_id,
+                    current_event_stream_version,
+                    &events_to_commit,
+                )
+                .await?
+            else {
+                return Err(SaveError::Conflict(err));
+            };
+
+            // The rebased Domain Events are the ones in events_to_commit.
+            rebased.take_uncommitted_events();
+            current_event_stream_version =
+                rebased.version() - (events_to_commit.len() as version::Version);
+            rebased_root = Some(rebased);
+        }
+
+        if let Some(rebased) = rebased_root {
+            *root = rebased;
+        }
+
+        Ok(())
+    }
+}
+
+/// Deletes the [`aggregate::Root`] by [tombstoning][event::store::Tombstoner]
+/// its Event Stream, so that its Domain Events are kept in the Event Store.
+#[async_trait]
+impl<T, S> Deleter<T> for EventSourced<T, S>
+where
+    T: Aggregate,
+    S: event::Store<T::Id, T::Event> + event::store::Tombstoner<T::Id>,
+{
+    async fn delete(&self, root: aggregate::Root<T>) -> Result<(), DeleteError> {
+        let saved_version = root.version() - root.uncommitted_events().len() as version::Version;
 
         self.store
-            .append(
-                aggregate_id.clone(),
-                version::Check::MustBe(current_event_stream_version),
-                events_to_commit,
-            )
+            .tombstone(root.aggregate_id(), version::Check::MustBe(saved_version))
             .await
             .map_err(|err| match err {
-                event::store::AppendError::Conflict(err) => SaveError::Conflict(err),
-                event::store::AppendError::Internal(err) => SaveError::Internal(err),
-            })?;
+                event::store::TombstoneError::Conflict(err) => DeleteError::Conflict(err),
+                event::store::TombstoneError::Internal(err) => DeleteError::Internal(err),
+            })
+    }
+}
 
-        Ok(())
+/// Default maximum number of [Aggregate Root][aggregate::Root]s kept
+/// in the cache of a [Cached] [Repository].
+pub const DEFAULT_CACHE_CAPACITY: usize = 10_000;
+
+/// The [Aggregate Root][aggregate::Root]s cached by a [Cached] [Repository],
+/// together with the order they have been last used in.
+struct CachedRoots<T>
+where
+    T: Aggregate,
+{
+    roots: HashMap<T::Id, (aggregate::Root<T>, u64)>,
+    last_used: BTreeMap<u64, T::Id>,
+    next_use: u64,
+}
+
+impl<T> Default for CachedRoots<T>
+where
+    T: Aggregate,
+{
+    fn default() -> Self {
+        Self {
+            roots: HashMap::default(),
+            last_used: BTreeMap::default(),
+            next_use: 0,
+        }
+    }
+}
+
+impl<T> CachedRoots<T>
+where
+    T: Aggregate,
+    T::Id: Clone + Eq + Hash,
+{
+    fn use_next(&mut self, id: &T::Id) -> u64 {
+        let next_use = self.next_use;
+        self.next_use += 1;
+        self.last_used.insert(next_use, id.clone());
+        next_use
+    }
+
+    fn get(&mut self, id: &T::Id) -> Option<aggregate::Root<T>> {
+        let previous_use = self.roots.get(id)?.1;
+        self.last_used.remove(&previous_use);
+
+        let next_use = self.use_next(id);
+        let (root, last_used) = self.roots.get_mut(id)?;
+        *last_used = next_use;
+
+        Some(root.clone())
+    }
+
+    // NOTE: concurrent loads and saves might complete out of order,
+    // so an Aggregate Root never replaces a newer version in the cache.
+    fn insert(&mut self, root: aggregate::Root<T>, capacity: usize) {
+        if let Some((cached_root, _)) = self.roots.get(root.aggregate_id()) {
+            if cached_root.version() >= root.version() {
+                return;
+            }
+        }
+
+        let id = root.aggregate_id().clone();
+        self.remove(&id);
+
+        let next_use = self.use_next(&id);
+        self.roots.insert(id, (root, next_use));
+
+        while self.roots.len() > capacity {
+            let Some((_, least_recently_used)) = self.last_used.pop_first() else {
+                break;
+            };
+
+            self.roots.remove(&least_recently_used);
+        }
+    }
+
+    fn remove(&mut self, id: &T::Id) {
+        if let Some((_, last_used)) = self.roots.remove(id) {
+            self.last_used.remove(&last_used);
+        }
+    }
+}
+
+/// A [Repository] decorator that keeps an in-memory cache of the
+/// [Aggregate Root][aggregate::Root] instances loaded or saved through it.
+///
+/// The cache is updated on every successful [`Saver::save`], and the cached
+/// instance is evicted when a conflict error is detected, as it is stale.
+/// The cache holds at most [`DEFAULT_CACHE_CAPACITY`] instances, evicting
+/// the least recently used ones first: use [`Cached::with_capacity`] to change it.
+///
+/// Use [`Cached::warm`] to load a batch of [Aggregate Root][aggregate::Root]s
+/// ahead of expected traffic.
+#[derive(Clone)]
+pub struct Cached<T, R>
+where
+    T: Aggregate,
+    R: Repository<T>,
+{
+    inner: R,
+    capacity: usize,
+    cache: Arc<Mutex<CachedRoots<T>>>,
+}
+
+impl<T, R> From<R> for Cached<T, R>
+where
+    T: Aggregate,
+    R: Repository<T>,
+{
+    fn from(inner: R) -> Self {
+        Self {
+            inner,
+            capacity: DEFAULT_CACHE_CAPACITY,
+            cache: Arc::default(),
+        }
+    }
+}
+
+impl<T, R> Cached<T, R>
+where
+    T: Aggregate,
+    T::Id: Clone + Eq + Hash,
+    R: Repository<T>,
+{
+    /// Keeps at most the specified number of [Aggregate Root][aggregate::Root]s
+    /// in the cache, evicting the least recently used ones first.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the capacity is zero.
+    #[must_use]
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        assert!(capacity > 0, "capacity must be greater than zero");
+        self.capacity = capacity;
+        self
+    }
+
+    /// Loads the [Aggregate Root][aggregate::Root]s with the specified ids
+    /// from the inner [Repository] into the cache, running at most
+    /// `concurrency` loads at the same time.
+    ///
+    /// [Aggregate Root][aggregate::Root]s that could not be found are skipped.
+    ///
+    /// # Errors
+    ///
+    /// The first [`GetError::Internal`] error returned by the inner [Repository]
+    /// is returned, although all the other loads are still completed.
+    pub async fn warm<I>(&self, ids: I, concurrency: usize) -> Result<(), GetError>
+    where
+        I: IntoIterator<Item = T::Id>,
+    {
+        let ids: Vec<T::Id> = ids.into_iter().collect();
+        let results: Vec<_> = self.inner.get_many(&ids, concurrency).collect().await;
+
+        let mut cache = self.lock_cache();
+        let mut first_error = None;
+
+        for (_, result) in results {
+            match result {
+                Ok(root) => cache.insert(root, self.capacity),
+                Err(GetError::NotFound) => (),
+                Err(err) => {
+                    first_error.get_or_insert(err);
+                },
+            }
+        }
+
+        first_error.map_or(Ok(()), Err)
+    }
+
+    // NOTE: a thread panicking while holding the lock might have left the cache
+    // in an inconsistent state: since the inner Repository is the source of truth,
+    // the cache is emptied instead of propagating the panic.
+    fn lock_cache(&self) -> MutexGuard<'_, CachedRoots<T>> {
+        self.cache.lock().unwrap_or_else(|poisoned| {
+            let mut cache = poisoned.into_inner();
+            *cache = CachedRoots::default();
+            self.cache.clear_poison();
+            cache
+        })
+    }
+}
+
+#[async_trait]
+impl<T, R> Getter<T> for Cached<T, R>
+where
+    T: Aggregate,
+    T::Id: Clone + Eq + Hash,
+    R: Repository<T>,
+{
+    async fn get(&self, id: &T::Id) -> Result<aggregate::Root<T>, GetError> {
+        let cached_root = self.lock_cache().get(id);
+
+        if let Some(root) = cached_root {
+            return Ok(root);
+        }
+
+        let root = self.inner.get(id).await?;
+
+        self.lock_cache().insert(root.clone(), self.capacity);
+
+        Ok(root)
+    }
+}
+
+#[async_trait]
+impl<T, R> Saver<T> for Cached<T, R>
+where
+    T: Aggregate,
+    T::Id: Clone + Eq + Hash,
+    R: Repository<T>,
+{
+    async fn save(&self, root: &mut aggregate::Root<T>) -> Result<(), SaveError> {
+        let result = self.inner.save(root).await;
+        let mut cache = self.lock_cache();
+
+        match result {
+            Ok(()) => {
+                cache.insert(root.clone(), self.capacity);
+                Ok(())
+            },
+            Err(err) => {
+                cache.remove(root.aggregate_id());
+                Err(err)
+            },
+        }
+    }
+}
+
+/// Evicts the [`aggregate::Root`] from the cache, whether or not
+/// the inner [Repository] deleted it successfully.
+#[async_trait]
+impl<T, R> Deleter<T> for Cached<T, R>
+where
+    T: Aggregate,
+    T::Id: Clone + Eq + Hash,
+    R: Repository<T> + Deleter<T>,
+{
+    async fn delete(&self, root: aggregate::Root<T>) -> Result<(), DeleteError> {
+        let id = root.aggregate_id().clone();
+        let result = self.inner.delete(root).await;
+
+        self.lock_cache().remove(&id);
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aggregate::test_user_domain::{User, UserEvent};
+    use crate::event::store::Streamer;
+    use crate::message::Message;
+
+    #[tokio::test]
+    async fn cached_repository_recovers_from_a_poisoned_cache() {
+        let cached_repository =
+            Cached::from(EventSourced::<User, _>::from(event::store::InMemory::<
+                String,
+                _,
+            >::default()));
+
+        let mut user =
+            aggregate::Root::<User>::create("test@email.com".to_owned(), "not-a-secret".to_owned())
+                .expect("user should be created successfully");
+
+        cached_repository
+            .save(&mut user)
+            .await
+            .expect("user should be saved successfully");
+
+        let cache = cached_repository.cache.clone();
+        std::thread::spawn(move || {
+            let _cache = cache.lock().unwrap();
+            panic!("poisoning the cache");
+        })
+        .join()
+        .expect_err("the thread should panic");
+
+        let cached_user = cached_repository
+            .get(user.aggregate_id())
+            .await
+            .expect("user should be retrieved from the inner repository");
+
+        assert_eq!(user.aggregate_id(), cached_user.aggregate_id());
+        assert_eq!(1, cached_user.version());
+        assert!(!cached_repository.cache.is_poisoned());
+    }
+
+    #[tokio::test]
+    async fn cached_repository_evicts_the_least_recently_used_roots() {
+        let cached_repository =
+            Cached::from(EventSourced::<User, _>::from(event::store::InMemory::<
+                String,
+                _,
+            >::default()))
+            .with_capacity(2);
+
+        for email in ["a@email.com", "b@email.com"] {
+            let mut user =
+                aggregate::Root::<User>::create(email.to_owned(), "not-a-secret".to_owned())
+                    .expect("user should be created successfully");
+
+            cached_repository
+                .save(&mut user)
+                .await
+                .expect("user should be saved successfully");
+        }
+
+        let _user = cached_repository
+            .get(&"a@email.com".to_owned())
+            .await
+            .expect("user should be retrieved successfully");
+
+        let mut user =
+            aggregate::Root::<User>::create("c@email.com".to_owned(), "not-a-secret".to_owned())
+                .expect("user should be created successfully");
+
+        cached_repository
+            .save(&mut user)
+            .await
+            .expect("user should be saved successfully");
+
+        let cache = cached_repository.lock_cache();
+        let mut cached_ids: Vec<_> = cache.roots.keys().map(String::as_str).collect();
+        cached_ids.sort_unstable();
+
+        assert_eq!(vec!["a@email.com", "c@email.com"], cached_ids);
+        assert_eq!(2, cache.last_used.len());
+    }
+
+    #[tokio::test]
+    async fn save_and_return_events_returns_the_committed_events() {
+        let event_store = event::store::InMemory::<String, UserEvent>::default();
+        let repository = EventSourced::<User, _>::from(event_store.clone());
+
+        let mut user =
+            aggregate::Root::<User>::create("test@email.com".to_owned(), "not-a-secret".to_owned())
+                .expect("user should be created successfully");
+
+        user.change_password("still-not-a-secret".to_owned())
+            .expect("password should be changed successfully");
+
+        let committed = repository
+            .save_and_return_events(&mut user)
+            .await
+            .expect("user should be saved successfully");
+
+        let persisted: Vec<_> = event_store
+            .stream(user.aggregate_id(), event::VersionSelect::All)
+            .try_collect()
+            .await
+            .expect("events should be streamed successfully");
+
+        assert_eq!(
+            vec![(1, "UserWasCreated"), (2, "UserPasswordWasChanged")],
+            committed
+                .iter()
+                .map(|event| (event.version, event.event.message.name()))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            persisted
+                .into_iter()
+                .map(|event| (event.stream_id, event.version, event.event.message))
+                .collect::<Vec<_>>(),
+            committed
+                .into_iter()
+                .map(|event| (event.stream_id, event.version, event.event.message))
+                .collect::<Vec<_>>()
+        );
+
+        assert!(repository
+            .save_and_return_events(&mut user)
+            .await
+            .expect("saving no events should succeed")
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn deleted_aggregate_roots_are_not_found() {
+        let repository = Cached::from(EventSourced::<User, _>::from(event::store::InMemory::<
+            String,
+            UserEvent,
+        >::default()));
+
+        let mut user =
+            aggregate::Root::<User>::create("test@email.com".to_owned(), "not-a-secret".to_owned())
+                .expect("user should be created successfully");
+
+        repository
+            .save(&mut user)
+            .await
+            .expect("user should be saved successfully");
+
+        let mut stale_user = repository
+            .get(user.aggregate_id())
+            .await
+            .expect("user should be found");
+
+        user.change_password("still-not-a-secret".to_owned())
+            .expect("password should be changed successfully");
+
+        repository
+            .save(&mut user)
+            .await
+            .expect("user should be saved successfully");
+
+        let error = repository
+            .delete(stale_user.clone())
+            .await
+            .expect_err("the user has been modified in the meantime");
+
+        assert!(matches!(error, DeleteError::Conflict(_)));
+
+        repository
+            .delete(user.clone())
+            .await
+            .expect("user should be deleted successfully");
+
+        assert!(matches!(
+            repository.get(user.aggregate_id()).await,
+            Err(GetError::NotFound)
+        ));
+
+        stale_user
+            .change_password("yet-another-secret".to_owned())
+            .expect("password should be changed successfully");
+
+        assert!(repository.save(&mut stale_user).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn conflict_resolver_rebases_commuting_domain_events() {
+        let event_store = event::store::InMemory::<String, UserEvent>::default();
+        let repository = EventSourced::<User, _>::from(event_store.clone());
+        let merging_repository = EventSourced::<User, _>::from(event_store).with_conflict_resolver(
+            |uncommitted: &[event::Envelope<UserEvent>],
+             concurrent: &[event::Envelope<UserEvent>]| {
+                uncommitted.len() == 1 && concurrent.len() == 1
+            },
+        );
+
+        let mut user =
+            aggregate::Root::<User>::create("test@email.com".to_owned(), "not-a-secret".to_owned())
+                .expect("user should be created successfully");
+
+        repository
+            .save(&mut user)
+            .await
+            .expect("user should be saved successfully");
+
+        let mut stale_user = user.clone();
+
+        user.change_password("still-not-a-secret".to_owned())
+            .expect("password should be changed successfully");
+
+        repository
+            .save(&mut user)
+            .await
+            .expect("user should be saved successfully");
+
+        stale_user
+            .change_password("yet-another-secret".to_owned())
+            .expect("password should be changed successfully");
+
+        let mut conflicting_user = stale_user.clone();
+
+        let committed = merging_repository
+            .save_and_return_events(&mut stale_user)
+            .await
+            .expect("password change should be rebased");
+
+        assert_eq!(3, stale_user.version());
+        assert_eq!("yet-another-secret", stale_user.password());
+        assert_eq!(
+            vec![3],
+            committed
+                .iter()
+                .map(|event| event.version)
+                .collect::<Vec<_>>()
+        );
+
+        // Two Domain Events have been appended concurrently by now.
+        assert!(matches!(
+            merging_repository.save(&mut conflicting_user).await,
+            Err(SaveError::Conflict(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn get_for_update_excludes_other_lockers_until_the_lock_is_dropped() {
+        let repository =
+            EventSourced::<User, _>::from(event::store::InMemory::<String, UserEvent>::default());
+
+        let mut user =
+            aggregate::Root::<User>::create("test@email.com".to_owned(), "not-a-secret".to_owned())
+                .expect("user should be created successfully");
+
+        repository
+            .save(&mut user)
+            .await
+            .expect("user should be saved successfully");
+
+        let mut locked_user = repository
+            .get_for_update(user.aggregate_id())
+            .await
+            .expect("user should be locked successfully");
+
+        assert!(
+            tokio::time::timeout(
+                std::time::Duration::from_millis(50),
+                repository.get_for_update(user.aggregate_id()),
+            )
+            .await
+            .is_err(),
+            "the user should not be locked twice"
+        );
+
+        locked_user
+            .change_password("still-not-a-secret".to_owned())
+            .expect("password should be changed successfully");
+
+        repository
+            .save(&mut locked_user)
+            .await
+            .expect("locked user should be saved successfully");
+
+        drop(locked_user);
+
+        let locked_user = repository
+            .get_for_update(user.aggregate_id())
+            .await
+            .expect("user should be locked again after the lock is dropped");
+
+        assert_eq!(2, locked_user.version());
     }
 }
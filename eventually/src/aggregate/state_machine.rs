@@ -0,0 +1,294 @@
+//! Module containing the [`state_machine!`][crate::state_machine] macro, a DSL
+//! to declare [Aggregate][crate::aggregate::Aggregate]s that are essentially state machines.
+//!
+//! The macro declares the states, the Domain Event that creates the Aggregate
+//! and the allowed transitions, and generates:
+//! - the Aggregate type, holding its id and current state,
+//! - the states `enum`,
+//! - the error type returned on invalid transitions,
+//! - the [`Aggregate`][crate::aggregate::Aggregate] implementation, with an
+//!   [`Aggregate::apply`][crate::aggregate::Aggregate::apply] that only accepts
+//!   the declared transitions.
+//!
+//! Example of usage:
+//! ```text
+//! eventually::state_machine! {
+//!     #[derive(Debug, Clone, PartialEq, Eq)]
+//!     pub aggregate LightSwitch {
+//!         type_name: "LightSwitch",
+//!         id: String,
+//!         event: LightSwitchEvent,
+//!         error: LightSwitchError,
+//!         states: LightSwitchState { Off, On },
+//!         created_by: LightSwitchEvent::Installed { id } => (id, Off),
+//!         transitions: {
+//!             Off => LightSwitchEvent::SwitchedOn => On,
+//!             On => LightSwitchEvent::SwitchedOff => Off,
+//!         },
+//!     }
+//! }
+//! ```
+
+/// Declares an [Aggregate][crate::aggregate::Aggregate] that is a state machine,
+/// generating its type, states, transition errors and
+/// [`Aggregate::apply`][crate::aggregate::Aggregate::apply] implementation.
+///
+/// Domain Events that are not declared as transitions from the current state
+/// are rejected with the `InvalidTransition` variant of the generated error type,
+/// while Domain Events other than the creation one are rejected with the
+/// `NotYetCreated` variant when the Aggregate does not exist yet.
+///
+/// Check the [module documentation][crate::aggregate::state_machine] for an example.
+#[macro_export]
+macro_rules! state_machine {
+    (
+        $(#[$meta:meta])*
+        $vis:vis aggregate $aggregate:ident {
+            type_name: $type_name:expr,
+            id: $id:ty,
+            event: $event:ty,
+            error: $error:ident,
+            states: $state:ident { $($states:ident),+ $(,)? },
+            created_by: $created_by:pat => ($created_id:expr, $initial:ident),
+            transitions: {
+                $($from:ident => $transition:pat => $to:ident),+ $(,)?
+            } $(,)?
+        }
+    ) => {
+        /// The states of the
+        #[doc = concat!("[`", stringify!($aggregate), "`]")]
+        /// state machine.
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        $vis enum $state {
+            $(
+                #[allow(missing_docs)]
+                $states,
+            )+
+        }
+
+        /// Errors returned when applying a Domain Event that is not an allowed
+        /// transition of the
+        #[doc = concat!("[`", stringify!($aggregate), "`]")]
+        /// state machine.
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        $vis enum $error {
+            /// The Domain Event cannot be applied, since the Aggregate has not been created yet.
+            NotYetCreated {
+                /// The name of the rejected Domain Event.
+                event: &'static str,
+            },
+            /// The Domain Event is not an allowed transition from the current state.
+            InvalidTransition {
+                /// The state of the Aggregate when the Domain Event was applied.
+                from: $state,
+                /// The name of the rejected Domain Event.
+                event: &'static str,
+            },
+        }
+
+        impl ::std::fmt::Display for $error {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                match self {
+                    Self::NotYetCreated { event } => write!(
+                        f,
+                        "cannot apply {} to {}: not created yet",
+                        event,
+                        $type_name,
+                    ),
+                    Self::InvalidTransition { from, event } => write!(
+                        f,
+                        "cannot apply {} to {} in state {:?}",
+                        event,
+                        $type_name,
+                        from,
+                    ),
+                }
+            }
+        }
+
+        impl ::std::error::Error for $error {}
+
+        $(#[$meta])*
+        $vis struct $aggregate {
+            id: $id,
+            state: $state,
+        }
+
+        impl $aggregate {
+            /// Returns the current state of the state machine.
+            #[must_use]
+            pub fn state(&self) -> $state {
+                self.state
+            }
+        }
+
+        impl $crate::aggregate::Aggregate for $aggregate {
+            type Id = $id;
+            type Event = $event;
+            type Error = $error;
+
+            fn type_name() -> &'static str {
+                $type_name
+            }
+
+            fn aggregate_id(&self) -> &Self::Id {
+                &self.id
+            }
+
+            fn apply(state: Option<Self>, event: Self::Event) -> Result<Self, Self::Error> {
+                let name = $crate::message::Message::name(&event);
+
+                match state {
+                    None => match event {
+                        $created_by => Ok(Self {
+                            id: $created_id,
+                            state: $state::$initial,
+                        }),
+                        #[allow(unreachable_patterns)]
+                        _ => Err($error::NotYetCreated { event: name }),
+                    },
+                    Some(this) => match (this.state, &event) {
+                        $(
+                            ($state::$from, $transition) => Ok(Self {
+                                state: $state::$to,
+                                ..this
+                            }),
+                        )+
+                        #[allow(unreachable_patterns)]
+                        (from, _) => Err($error::InvalidTransition { from, event: name }),
+                    },
+                }
+            }
+
+            fn apply_in_place(&mut self, event: Self::Event) -> Result<(), Self::Error> {
+                let name = $crate::message::Message::name(&event);
+
+                self.state = match (self.state, &event) {
+                    $(
+                        ($state::$from, $transition) => $state::$to,
+                    )+
+                    #[allow(unreachable_patterns)]
+                    (from, _) => return Err($error::InvalidTransition { from, event: name }),
+                };
+
+                Ok(())
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::aggregate::test::Scenario;
+    use crate::aggregate::{Aggregate, Root};
+    use crate::message;
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    enum LightSwitchEvent {
+        Installed { id: String },
+        SwitchedOn,
+        SwitchedOff,
+    }
+
+    impl message::Message for LightSwitchEvent {
+        fn name(&self) -> &'static str {
+            match self {
+                LightSwitchEvent::Installed { .. } => "LightSwitchInstalled",
+                LightSwitchEvent::SwitchedOn => "LightSwitchSwitchedOn",
+                LightSwitchEvent::SwitchedOff => "LightSwitchSwitchedOff",
+            }
+        }
+    }
+
+    crate::state_machine! {
+        #[derive(Debug, Clone, PartialEq, Eq)]
+        aggregate LightSwitch {
+            type_name: "LightSwitch",
+            id: String,
+            event: LightSwitchEvent,
+            error: LightSwitchError,
+            states: LightSwitchState { Off, On },
+            created_by: LightSwitchEvent::Installed { id } => (id, Off),
+            transitions: {
+                Off => LightSwitchEvent::SwitchedOn => On,
+                On => LightSwitchEvent::SwitchedOff => Off,
+            },
+        }
+    }
+
+    #[test]
+    fn state_machine_applies_declared_transitions() {
+        let root = Root::<LightSwitch>::rehydrate(
+            vec![
+                LightSwitchEvent::Installed {
+                    id: "switch:1".to_owned(),
+                }
+                .into(),
+                LightSwitchEvent::SwitchedOn.into(),
+                LightSwitchEvent::SwitchedOff.into(),
+                LightSwitchEvent::SwitchedOn.into(),
+            ]
+            .into_iter(),
+        )
+        .expect("transitions should be allowed")
+        .expect("light switch should be installed");
+
+        assert_eq!("switch:1", root.aggregate_id());
+        assert_eq!(LightSwitchState::On, root.state());
+    }
+
+    #[test]
+    fn state_machine_rejects_undeclared_transitions() {
+        assert_eq!(
+            Err(LightSwitchError::NotYetCreated {
+                event: "LightSwitchSwitchedOn"
+            }),
+            LightSwitch::apply(None, LightSwitchEvent::SwitchedOn)
+        );
+
+        Scenario::<LightSwitch>::new()
+            .given(vec![LightSwitchEvent::Installed {
+                id: "switch:1".to_owned(),
+            }
+            .into()])
+            .when(|root: &mut LightSwitchRoot| {
+                root.record_that(LightSwitchEvent::SwitchedOff.into())
+            })
+            .then_error(LightSwitchError::InvalidTransition {
+                from: LightSwitchState::Off,
+                event: "LightSwitchSwitchedOff",
+            })
+            .assert();
+
+        assert_eq!(
+            "cannot apply LightSwitchInstalled to LightSwitch in state Off",
+            LightSwitchError::InvalidTransition {
+                from: LightSwitchState::Off,
+                event: "LightSwitchInstalled",
+            }
+            .to_string()
+        );
+    }
+
+    struct LightSwitchRoot(Root<LightSwitch>);
+
+    impl From<Root<LightSwitch>> for LightSwitchRoot {
+        fn from(root: Root<LightSwitch>) -> Self {
+            Self(root)
+        }
+    }
+
+    impl std::ops::Deref for LightSwitchRoot {
+        type Target = Root<LightSwitch>;
+
+        fn deref(&self) -> &Self::Target {
+            &self.0
+        }
+    }
+
+    impl std::ops::DerefMut for LightSwitchRoot {
+        fn deref_mut(&mut self) -> &mut Self::Target {
+            &mut self.0
+        }
+    }
+}
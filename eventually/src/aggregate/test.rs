@@ -7,7 +7,7 @@ use std::ops::Deref;
 use std::sync::Arc;
 
 use crate::aggregate::{Aggregate, Root};
-use crate::event;
+use crate::{event, message};
 
 /// A test scenario that can be used to test an [Aggregate] and [Aggregate Root][Root]
 /// using a [given-then-when canvas](https://www.agilealliance.org/glossary/gwt/) approach.
@@ -162,7 +162,7 @@ where
     pub fn then(self, result: Vec<event::Envelope<T::Event>>) -> ScenarioThen<T, R, F, Err> {
         ScenarioThen {
             mutate: self.mutate,
-            expected: Ok(result),
+            expected: ScenarioThenCase::Produces(result),
             marker: PhantomData,
         }
     }
@@ -172,15 +172,46 @@ where
     /// Use this method to assert the specific Error value that the
     /// [Aggregate Root][Root] method should return.
     #[must_use]
-    pub fn then_error(self, err: Err) -> ScenarioThen<T, R, F, Err> {
+    pub fn then_error(self, err: Err) -> ScenarioThen<T, R, F, Err>
+    where
+        Err: PartialEq,
+    {
+        ScenarioThen {
+            mutate: self.mutate,
+            expected: ScenarioThenCase::Error {
+                expected: err,
+                eq: Err::eq,
+            },
+            marker: PhantomData,
+        }
+    }
+
+    /// Specifies that the outcome of the [Scenario] is negative, without
+    /// asserting on the returned Error value.
+    ///
+    /// Use this method when the Error type does not implement [`PartialEq`].
+    #[must_use]
+    pub fn then_fails(self) -> ScenarioThen<T, R, F, Err> {
         ScenarioThen {
             mutate: self.mutate,
-            expected: Err(err),
+            expected: ScenarioThenCase::Fails,
             marker: PhantomData,
         }
     }
 }
 
+enum ScenarioThenCase<Evt, Err>
+where
+    Evt: message::Message,
+{
+    Produces(Vec<event::Envelope<Evt>>),
+    Error {
+        expected: Err,
+        eq: fn(&Err, &Err) -> bool,
+    },
+    Fails,
+}
+
 #[doc(hidden)]
 pub struct ScenarioThen<T, R, F, Err>
 where
@@ -190,7 +221,7 @@ where
     F: Fn() -> Result<R, Err>,
 {
     mutate: F,
-    expected: Result<Vec<event::Envelope<T::Event>>, Err>,
+    expected: ScenarioThenCase<T::Event, Err>,
     marker: PhantomData<R>,
 }
 
@@ -200,7 +231,7 @@ where
     T::Event: Debug + PartialEq,
     R: From<Root<T>> + Deref<Target = Root<T>>,
     F: Fn() -> Result<R, Err>,
-    Err: PartialEq + Debug,
+    Err: Debug,
 {
     /// Runs the [Scenario] and performs the various assertion for the test.
     ///
@@ -210,6 +241,96 @@ where
     /// the test fail.
     pub fn assert(self) {
         let result = (self.mutate)().map(|root| root.recorded_events.clone());
-        assert_eq!(self.expected, result);
+
+        match (self.expected, result) {
+            (ScenarioThenCase::Produces(expected), Ok(events)) => assert_eq!(expected, events),
+            (ScenarioThenCase::Error { expected, eq }, Err(err)) => assert!(
+                eq(&expected, &err),
+                "expected error {expected:?}, but {err:?} was returned"
+            ),
+            (ScenarioThenCase::Fails, Err(_)) => {},
+            (_, Ok(events)) => panic!("expected an error, but events {events:?} were recorded"),
+            (_, Err(err)) => panic!("expected domain events, but error {err:?} was returned"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::{Deref, DerefMut};
+
+    use super::*;
+    use crate::aggregate::test_user_domain::{User, UserEvent};
+
+    struct UserRoot(Root<User>);
+
+    impl From<Root<User>> for UserRoot {
+        fn from(root: Root<User>) -> Self {
+            Self(root)
+        }
+    }
+
+    impl Deref for UserRoot {
+        type Target = Root<User>;
+
+        fn deref(&self) -> &Self::Target {
+            &self.0
+        }
+    }
+
+    impl DerefMut for UserRoot {
+        fn deref_mut(&mut self) -> &mut Self::Target {
+            &mut self.0
+        }
+    }
+
+    fn user_was_created() -> event::Envelope<UserEvent> {
+        UserEvent::WasCreated {
+            email: "test@email.com".to_owned(),
+            password: "secret".to_owned(),
+        }
+        .into()
+    }
+
+    #[test]
+    fn scenario_asserts_events_recorded_by_a_new_root() {
+        Scenario::<User>::new()
+            .when(|| {
+                Root::<User>::create("test@email.com".to_owned(), "secret".to_owned())
+                    .map(UserRoot::from)
+            })
+            .then(vec![user_was_created()])
+            .assert();
+    }
+
+    #[test]
+    fn scenario_asserts_events_recorded_on_an_existing_root() {
+        Scenario::<User>::new()
+            .given(vec![user_was_created()])
+            .when(|user: &mut UserRoot| user.change_password("new-secret".to_owned()))
+            .then(vec![UserEvent::PasswordWasChanged {
+                password: "new-secret".to_owned(),
+            }
+            .into()])
+            .assert();
+    }
+
+    #[test]
+    fn scenario_asserts_returned_errors() {
+        Scenario::<User>::new()
+            .given(vec![user_was_created()])
+            .when(|user: &mut UserRoot| user.change_password(String::new()))
+            .then_fails()
+            .assert();
+    }
+
+    #[test]
+    #[should_panic(expected = "expected an error")]
+    fn scenario_fails_when_an_expected_error_is_not_returned() {
+        Scenario::<User>::new()
+            .given(vec![user_was_created()])
+            .when(|user: &mut UserRoot| user.change_password("new-secret".to_owned()))
+            .then_fails()
+            .assert();
     }
 }
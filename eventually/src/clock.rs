@@ -0,0 +1,118 @@
+//! Module `clock` contains the [Clock] abstraction, to be used by the application
+//! instead of reading the system time directly, so that time-dependent
+//! domain logic can be tested deterministically using a [`TestClock`].
+
+use std::fmt::Debug;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use chrono::{DateTime, Duration, Utc};
+
+/// A source of the current time.
+pub trait Clock: Debug + Send + Sync {
+    /// Returns the current time.
+    fn now(&self) -> DateTime<Utc>;
+}
+
+impl<C> Clock for Arc<C>
+where
+    C: Clock + ?Sized,
+{
+    fn now(&self) -> DateTime<Utc> {
+        (**self).now()
+    }
+}
+
+/// [Clock] implementation that returns the system time.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// [Clock] implementation that returns a time controlled by the test,
+/// which only changes through [`TestClock::set`] and [`TestClock::advance`].
+///
+/// Clones share the same time, so a clone can be handed to the code under test
+/// while the test keeps controlling it.
+#[derive(Debug, Clone)]
+pub struct TestClock {
+    now: Arc<Mutex<DateTime<Utc>>>,
+}
+
+impl TestClock {
+    /// Returns a new [`TestClock`] set at the specified time.
+    #[must_use]
+    pub fn new(now: DateTime<Utc>) -> Self {
+        Self {
+            now: Arc::new(Mutex::new(now)),
+        }
+    }
+
+    /// Sets the time returned by the [`TestClock`].
+    pub fn set(&self, now: DateTime<Utc>) {
+        *self.lock_now() = now;
+    }
+
+    /// Moves the time returned by the [`TestClock`] forward by the specified duration.
+    pub fn advance(&self, duration: Duration) {
+        *self.lock_now() += duration;
+    }
+
+    // NOTE: the time is a plain value, which a panicking thread cannot leave
+    // half-updated: it is used anyway, since the clock is shared with store code.
+    fn lock_now(&self) -> MutexGuard<'_, DateTime<Utc>> {
+        self.now.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Default for TestClock {
+    fn default() -> Self {
+        Self::new(DateTime::UNIX_EPOCH)
+    }
+}
+
+impl Clock for TestClock {
+    fn now(&self) -> DateTime<Utc> {
+        *self.lock_now()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clock_is_shared_between_clones() {
+        let clock = TestClock::default();
+        let handler_clock: Arc<dyn Clock> = Arc::new(clock.clone());
+
+        clock.advance(Duration::minutes(5));
+        assert_eq!(
+            DateTime::UNIX_EPOCH + Duration::minutes(5),
+            handler_clock.now()
+        );
+
+        let now = Utc::now();
+        clock.set(now);
+        assert_eq!(now, handler_clock.now());
+    }
+
+    #[test]
+    fn test_clock_keeps_working_after_a_poisoned_lock() {
+        let clock = TestClock::default();
+        let poisoner = clock.clone();
+
+        std::thread::spawn(move || {
+            let _now = poisoner.now.lock().unwrap();
+            panic!("poisoning the test clock");
+        })
+        .join()
+        .expect_err("the thread should panic");
+
+        clock.advance(Duration::minutes(5));
+        assert_eq!(DateTime::UNIX_EPOCH + Duration::minutes(5), clock.now());
+    }
+}
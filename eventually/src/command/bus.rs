@@ -0,0 +1,452 @@
+//! Module containing a Command [Bus], which dispatches [Command][Envelope]s
+//! to the [Handler] registered for their type, so that callers (e.g. gRPC or HTTP
+//! layers) do not need to hold a concrete [Handler] per Command type.
+//!
+//! Cross-cutting concerns (e.g. logging, retries, validation) can be plugged in
+//! the [Bus] through [Middleware]s, which are called in registration order
+//! before reaching the [Handler].
+
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+
+use crate::command::validate::{ValidationError, Validator};
+use crate::command::{Envelope, Handler};
+use crate::message;
+
+/// All possible errors returned by the [Bus] when dispatching a [Command][Envelope].
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// Error returned when no [Handler] has been registered for the Command type.
+    #[error("no handler registered for command: {0}")]
+    HandlerNotFound(&'static str),
+    /// Error returned by the [Validation] middleware when the Command is invalid.
+    #[error("invalid command: {0}")]
+    Validation(#[source] ValidationError),
+    /// Error returned when the [Handler] has failed to handle the Command.
+    #[error("failed to handle command: {0}")]
+    Handler(#[source] anyhow::Error),
+}
+
+/// A type-erased [Command][Envelope], as seen by the [Middleware]s of the [Bus].
+#[derive(Clone)]
+pub struct Command {
+    /// The name of the Command message.
+    pub name: &'static str,
+    /// The metadata of the Command [Envelope].
+    pub metadata: message::Metadata,
+    type_id: TypeId,
+    payload: Arc<dyn Any + Send + Sync>,
+}
+
+impl<T> From<Envelope<T>> for Command
+where
+    T: message::Message + Send + Sync + 'static,
+{
+    fn from(command: Envelope<T>) -> Self {
+        Self {
+            name: command.message.name(),
+            metadata: command.metadata,
+            type_id: TypeId::of::<T>(),
+            payload: Arc::new(command.message),
+        }
+    }
+}
+
+impl Command {
+    /// Returns a reference to the Command message, if it is of type `T`.
+    #[must_use]
+    pub fn downcast_ref<T>(&self) -> Option<&T>
+    where
+        T: 'static,
+    {
+        self.payload.downcast_ref()
+    }
+}
+
+/// A Middleware wraps the handling of all the [Command][Envelope]s
+/// dispatched through the [Bus], regardless of their type.
+#[async_trait]
+pub trait Middleware: Send + Sync {
+    /// Handles the [Command], usually by calling [`Next::run`] to continue
+    /// the handling down the chain.
+    ///
+    /// # Errors
+    ///
+    /// The Middleware can stop the handling early by returning an [Error].
+    async fn handle(&self, command: Command, next: Next<'_>) -> Result<(), Error>;
+}
+
+/// The remaining [Middleware]s and the [Handler] that will handle the [Command].
+#[derive(Clone, Copy)]
+pub struct Next<'a> {
+    middlewares: &'a [Arc<dyn Middleware>],
+    handler: &'a dyn ErasedHandler,
+}
+
+impl Next<'_> {
+    /// Continues the handling of the [Command] down the chain.
+    ///
+    /// [Next] is [Copy], so the rest of the chain can be run more than once
+    /// (e.g. by the [Retry] middleware).
+    ///
+    /// # Errors
+    ///
+    /// The error returned by the next [Middleware] or [Handler].
+    pub async fn run(self, command: Command) -> Result<(), Error> {
+        match self.middlewares.split_first() {
+            Some((middleware, middlewares)) => {
+                middleware
+                    .handle(
+                        command,
+                        Next {
+                            middlewares,
+                            handler: self.handler,
+                        },
+                    )
+                    .await
+            },
+            None => self.handler.handle(command).await,
+        }
+    }
+}
+
+#[async_trait]
+trait ErasedHandler: Send + Sync {
+    async fn handle(&self, command: Command) -> Result<(), Error>;
+}
+
+struct TypedHandler<T, H> {
+    handler: H,
+    command: PhantomData<fn() -> T>,
+}
+
+#[async_trait]
+impl<T, H> ErasedHandler for TypedHandler<T, H>
+where
+    T: message::Message + Clone + Send + Sync + 'static,
+    H: Handler<T>,
+    H::Error: Into<anyhow::Error>,
+{
+    async fn handle(&self, command: Command) -> Result<(), Error> {
+        let name = command.name;
+        let message = command
+            .payload
+            .downcast::<T>()
+            .map_err(|_| Error::HandlerNotFound(name))?;
+
+        let command = Envelope {
+            message: Arc::try_unwrap(message).unwrap_or_else(|msg| msg.as_ref().clone()),
+            metadata: command.metadata,
+        };
+
+        let causation = message::causation_metadata(&command);
+
+        message::with_causation(causation, self.handler.handle(command))
+            .await
+            .map_err(|err| Error::Handler(err.into()))
+    }
+}
+
+/// Dispatches [Command][Envelope]s to the [Handler] registered for their type,
+/// through the registered [Middleware]s.
+///
+/// The Domain Events saved by the [Handler]s carry the correlation and causation ids
+/// of the dispatched [Command][Envelope], as with [`StampCausation`][crate::command::handler::StampCausation].
+#[derive(Default, Clone)]
+pub struct Bus {
+    handlers: HashMap<TypeId, Arc<dyn ErasedHandler>>,
+    middlewares: Vec<Arc<dyn Middleware>>,
+}
+
+impl Bus {
+    /// Registers the [Handler] to use for all the Commands of type `T`,
+    /// replacing any previously registered one.
+    #[must_use]
+    pub fn register<T, H>(mut self, handler: H) -> Self
+    where
+        T: message::Message + Clone + Send + Sync + 'static,
+        H: Handler<T> + 'static,
+        H::Error: Into<anyhow::Error>,
+    {
+        self.handlers.insert(
+            TypeId::of::<T>(),
+            Arc::new(TypedHandler {
+                handler,
+                command: PhantomData,
+            }),
+        );
+
+        self
+    }
+
+    /// Adds a new [Middleware] to the [Bus].
+    ///
+    /// Middlewares are called in the same order they have been added.
+    #[must_use]
+    pub fn with_middleware<M>(mut self, middleware: M) -> Self
+    where
+        M: Middleware + 'static,
+    {
+        self.middlewares.push(Arc::new(middleware));
+        self
+    }
+
+    /// Dispatches the [Command][Envelope] to the [Handler] registered for its type.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if no [Handler] has been registered for the Command,
+    /// or if the handling fails in either a [Middleware] or the [Handler].
+    pub async fn dispatch<T>(&self, command: Envelope<T>) -> Result<(), Error>
+    where
+        T: message::Message + Send + Sync + 'static,
+    {
+        self.dispatch_command(command.into()).await
+    }
+
+    /// Dispatches a type-erased [Command] to the [Handler] registered for its type,
+    /// e.g. one of the Commands returned by a [`reactor::Policy`][crate::reactor::Policy].
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if no [Handler] has been registered for the Command,
+    /// or if the handling fails in either a [Middleware] or the [Handler].
+    pub async fn dispatch_command(&self, command: Command) -> Result<(), Error> {
+        let handler = self
+            .handlers
+            .get(&command.type_id)
+            .ok_or(Error::HandlerNotFound(command.name))?;
+
+        let next = Next {
+            middlewares: &self.middlewares,
+            handler: handler.as_ref(),
+        };
+
+        next.run(command).await
+    }
+}
+
+/// [Middleware] that retries the handling of a [Command] when the [Handler]
+/// fails, up to a maximum number of attempts.
+///
+/// Useful to transparently recover from optimistic concurrency conflicts.
+#[derive(Debug, Clone, Copy)]
+pub struct Retry {
+    max_attempts: usize,
+    backoff: Duration,
+    predicate: fn(&anyhow::Error) -> bool,
+}
+
+impl Retry {
+    /// Returns a new [Retry] middleware that attempts the handling of
+    /// a [Command] at most `max_attempts` times, for any [`Error::Handler`] error.
+    #[must_use]
+    pub fn new(max_attempts: usize) -> Self {
+        Self {
+            max_attempts,
+            backoff: Duration::ZERO,
+            predicate: |_| true,
+        }
+    }
+
+    /// Waits the specified duration between two consecutive attempts.
+    #[must_use]
+    pub fn with_backoff(mut self, backoff: Duration) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Only retries the [Handler] errors that satisfy the specified predicate.
+    #[must_use]
+    pub fn when(mut self, should_retry: fn(&anyhow::Error) -> bool) -> Self {
+        self.predicate = should_retry;
+        self
+    }
+}
+
+#[async_trait]
+impl Middleware for Retry {
+    async fn handle(&self, command: Command, next: Next<'_>) -> Result<(), Error> {
+        let mut attempt = 1;
+
+        loop {
+            match next.run(command.clone()).await {
+                Err(Error::Handler(err))
+                    if attempt < self.max_attempts && (self.predicate)(&err) =>
+                {
+                    attempt += 1;
+
+                    if !self.backoff.is_zero() {
+                        futures_timer::Delay::new(self.backoff).await;
+                    }
+                },
+                result => return result,
+            }
+        }
+    }
+}
+
+type CommandValidator = Arc<dyn Fn(&Command) -> Result<(), ValidationError> + Send + Sync>;
+
+/// [Middleware] that validates [Command]s before they reach their [Handler],
+/// failing the dispatch with [`Error::Validation`] if a validation rule fails.
+#[derive(Default, Clone)]
+pub struct Validation {
+    validators: HashMap<TypeId, CommandValidator>,
+}
+
+impl Validation {
+    /// Adds a [Validator] for all the Commands of type `T`, e.g. a closure
+    /// or [`Constraints`][crate::command::validate::Constraints].
+    #[must_use]
+    pub fn validate<T, V>(mut self, validator: V) -> Self
+    where
+        T: message::Message + 'static,
+        V: Validator<T> + 'static,
+    {
+        self.validators.insert(
+            TypeId::of::<T>(),
+            Arc::new(move |command| {
+                command
+                    .downcast_ref::<T>()
+                    .map_or(Ok(()), |command| validator.validate(command))
+            }),
+        );
+
+        self
+    }
+}
+
+#[async_trait]
+impl Middleware for Validation {
+    async fn handle(&self, command: Command, next: Next<'_>) -> Result<(), Error> {
+        if let Some(validator) = self.validators.get(&command.type_id) {
+            validator(&command).map_err(Error::Validation)?;
+        }
+
+        next.run(command).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use anyhow::anyhow;
+
+    use super::*;
+    use crate::command::validate::Violation;
+
+    #[derive(Debug, Clone)]
+    struct RenameUser(String);
+
+    impl message::Message for RenameUser {
+        fn name(&self) -> &'static str {
+            "RenameUser"
+        }
+    }
+
+    #[derive(Default)]
+    struct FlakyUserService {
+        failures: usize,
+        attempts: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl Handler<RenameUser> for Arc<FlakyUserService> {
+        type Error = anyhow::Error;
+
+        async fn handle(&self, _: Envelope<RenameUser>) -> Result<(), Self::Error> {
+            if self.attempts.fetch_add(1, Ordering::SeqCst) < self.failures {
+                return Err(anyhow!("version conflict"));
+            }
+
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn bus_dispatches_commands_to_the_registered_handler() {
+        let service = Arc::new(FlakyUserService::default());
+        let bus = Bus::default().register(service.clone());
+
+        bus.dispatch(Envelope::from(RenameUser("john".to_owned())))
+            .await
+            .expect("command should be handled");
+
+        assert_eq!(1, service.attempts.load(Ordering::SeqCst));
+
+        let error = Bus::default()
+            .dispatch(Envelope::from(RenameUser("john".to_owned())))
+            .await
+            .expect_err("no handler has been registered");
+
+        assert!(matches!(error, Error::HandlerNotFound("RenameUser")));
+    }
+
+    #[tokio::test]
+    async fn retry_middleware_retries_failed_commands() {
+        let service = Arc::new(FlakyUserService {
+            failures: 2,
+            ..FlakyUserService::default()
+        });
+
+        let bus = Bus::default()
+            .register(service.clone())
+            .with_middleware(Retry::new(3));
+
+        bus.dispatch(Envelope::from(RenameUser("john".to_owned())))
+            .await
+            .expect("command should be handled on the third attempt");
+
+        assert_eq!(3, service.attempts.load(Ordering::SeqCst));
+
+        let bus = Bus::default()
+            .register(Arc::new(FlakyUserService {
+                failures: 2,
+                ..FlakyUserService::default()
+            }))
+            .with_middleware(Retry::new(3).when(|_| false));
+
+        let error = bus
+            .dispatch(Envelope::from(RenameUser("john".to_owned())))
+            .await
+            .expect_err("the handler error should not be retried");
+
+        assert!(matches!(error, Error::Handler(_)));
+    }
+
+    #[tokio::test]
+    async fn validation_middleware_rejects_invalid_commands() {
+        let service = Arc::new(FlakyUserService::default());
+        let bus = Bus::default().register(service.clone()).with_middleware(
+            Validation::default().validate(|command: &RenameUser| {
+                if command.0.is_empty() {
+                    return Err(Violation::field("name", "must not be empty").into());
+                }
+
+                Ok(())
+            }),
+        );
+
+        let error = bus
+            .dispatch(Envelope::from(RenameUser(String::new())))
+            .await
+            .expect_err("the command should be invalid");
+
+        let Error::Validation(error) = error else {
+            panic!("expected a validation error, got: {error}");
+        };
+
+        assert_eq!(
+            vec![Violation::field("name", "must not be empty")],
+            error.violations
+        );
+        assert_eq!(0, service.attempts.load(Ordering::SeqCst));
+    }
+}
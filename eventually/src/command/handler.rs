@@ -0,0 +1,609 @@
+//! Module containing decorators for [Handler] implementations.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+
+use crate::aggregate::repository::SaveError;
+use crate::clock::{Clock, SystemClock};
+use crate::command::{Envelope, Handler};
+use crate::event::store::PoisonedError;
+use crate::{message, version};
+
+/// Used by [`RetryOnConflict`] to find out whether a [Handler] error
+/// has been caused by a [`version::ConflictError`].
+pub trait IsConflictError {
+    /// Returns `true` if the error is, or has been caused by, a [`version::ConflictError`].
+    fn is_conflict_error(&self) -> bool;
+}
+
+impl IsConflictError for version::ConflictError {
+    fn is_conflict_error(&self) -> bool {
+        true
+    }
+}
+
+impl IsConflictError for SaveError {
+    fn is_conflict_error(&self) -> bool {
+        matches!(self, SaveError::Conflict(_))
+    }
+}
+
+impl IsConflictError for anyhow::Error {
+    fn is_conflict_error(&self) -> bool {
+        self.chain()
+            .any(|err| err.downcast_ref::<version::ConflictError>().is_some())
+    }
+}
+
+/// Decorator for a [Handler] that retries the handling of a Command
+/// when it fails due to a [`version::ConflictError`].
+///
+/// Since the decorated [Handler] loads the Aggregate Root from the Repository
+/// when handling the Command, each retry evaluates the Command against
+/// the latest version of the Aggregate Root.
+#[derive(Debug, Clone)]
+pub struct RetryOnConflict<H> {
+    handler: H,
+    max_attempts: usize,
+    backoff: Duration,
+}
+
+impl<H> From<H> for RetryOnConflict<H> {
+    fn from(handler: H) -> Self {
+        Self {
+            handler,
+            max_attempts: 3,
+            backoff: Duration::ZERO,
+        }
+    }
+}
+
+impl<H> RetryOnConflict<H> {
+    /// Sets the maximum number of times the Command handling is attempted,
+    /// including the first one. Defaults to 3.
+    #[must_use]
+    pub fn with_max_attempts(mut self, max_attempts: usize) -> Self {
+        self.max_attempts = max_attempts;
+        self
+    }
+
+    /// Sets the duration to wait before the first retry, doubled on every
+    /// subsequent retry. Defaults to no wait.
+    #[must_use]
+    pub fn with_backoff(mut self, backoff: Duration) -> Self {
+        self.backoff = backoff;
+        self
+    }
+}
+
+#[async_trait]
+impl<T, H> Handler<T> for RetryOnConflict<H>
+where
+    T: message::Message + Clone + Send + Sync + 'static,
+    H: Handler<T>,
+    H::Error: IsConflictError,
+{
+    type Error = H::Error;
+
+    async fn handle(&self, command: Envelope<T>) -> Result<(), Self::Error> {
+        let mut attempt = 1;
+        let mut backoff = self.backoff;
+
+        loop {
+            match self.handler.handle(command.clone()).await {
+                Err(err) if attempt < self.max_attempts && err.is_conflict_error() => {
+                    attempt += 1;
+
+                    if !backoff.is_zero() {
+                        futures_timer::Delay::new(backoff).await;
+                        backoff *= 2;
+                    }
+                },
+                result => return result,
+            }
+        }
+    }
+}
+
+/// The state of an idempotency key, returned by [`IdempotencyStore::reserve`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reservation {
+    /// The key has been reserved: the Command can be handled.
+    Acquired,
+    /// The Command is still being handled by another delivery,
+    /// whose lease on the key has not expired yet.
+    InFlight,
+    /// The Command has already been handled successfully.
+    Completed,
+}
+
+/// Stores the idempotency keys of the Commands processed by an [Idempotent] [Handler].
+///
+/// Keys are scoped by the [name][message::Message::name] of the Command,
+/// so the same key can be used by Commands of different types.
+#[async_trait]
+pub trait IdempotencyStore: Send + Sync {
+    /// The error type returned by the store.
+    type Error: Send + Sync;
+
+    /// Reserves the idempotency key for the handling of the Command, atomically,
+    /// until the specified lease expires.
+    ///
+    /// A key that is not recorded, or whose lease has expired without being completed
+    /// (e.g. because the process handling the Command has crashed), is [acquired][Reservation::Acquired].
+    async fn reserve(
+        &self,
+        command_name: &str,
+        key: &str,
+        lease: Duration,
+    ) -> Result<Reservation, Self::Error>;
+
+    /// Marks a reserved idempotency key as completed, once the Command
+    /// has been handled successfully, so that its duplicates are short-circuited.
+    async fn complete(&self, command_name: &str, key: &str) -> Result<(), Self::Error>;
+
+    /// Releases a reserved idempotency key, so that the Command
+    /// can be processed again (e.g. because the handling has failed).
+    async fn release(&self, command_name: &str, key: &str) -> Result<(), Self::Error>;
+}
+
+#[derive(Debug, Clone, Copy)]
+enum KeyState {
+    Pending { expires_at: DateTime<Utc> },
+    Completed,
+}
+
+/// In-memory implementation of an [`IdempotencyStore`],
+/// best suited for testing and single-instance deployments.
+///
+/// Cloning an [`InMemoryIdempotencyStore`] returns a handle to the same keys.
+#[derive(Debug, Clone)]
+pub struct InMemoryIdempotencyStore {
+    clock: Arc<dyn Clock>,
+    keys: Arc<Mutex<HashMap<(String, String), KeyState>>>,
+}
+
+impl Default for InMemoryIdempotencyStore {
+    fn default() -> Self {
+        Self {
+            clock: Arc::new(SystemClock),
+            keys: Arc::default(),
+        }
+    }
+}
+
+impl InMemoryIdempotencyStore {
+    /// Uses the specified [Clock] to expire the leases on the idempotency keys,
+    /// instead of the system time.
+    #[must_use]
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+}
+
+#[async_trait]
+impl IdempotencyStore for InMemoryIdempotencyStore {
+    type Error = PoisonedError;
+
+    async fn reserve(
+        &self,
+        command_name: &str,
+        key: &str,
+        lease: Duration,
+    ) -> Result<Reservation, Self::Error> {
+        let now = self.clock.now();
+        let mut keys = self.keys.lock().map_err(|_| PoisonedError)?;
+
+        match keys.get(&(command_name.to_owned(), key.to_owned())) {
+            Some(KeyState::Completed) => return Ok(Reservation::Completed),
+            Some(KeyState::Pending { expires_at }) if *expires_at > now => {
+                return Ok(Reservation::InFlight)
+            },
+            _ => {},
+        }
+
+        let lease = chrono::Duration::from_std(lease).unwrap_or(chrono::Duration::MAX);
+
+        keys.insert(
+            (command_name.to_owned(), key.to_owned()),
+            KeyState::Pending {
+                expires_at: now
+                    .checked_add_signed(lease)
+                    .unwrap_or(DateTime::<Utc>::MAX_UTC),
+            },
+        );
+
+        Ok(Reservation::Acquired)
+    }
+
+    async fn complete(&self, command_name: &str, key: &str) -> Result<(), Self::Error> {
+        self.keys.lock().map_err(|_| PoisonedError)?.insert(
+            (command_name.to_owned(), key.to_owned()),
+            KeyState::Completed,
+        );
+
+        Ok(())
+    }
+
+    async fn release(&self, command_name: &str, key: &str) -> Result<(), Self::Error> {
+        let mut keys = self.keys.lock().map_err(|_| PoisonedError)?;
+        let key = (command_name.to_owned(), key.to_owned());
+
+        if let Some(KeyState::Pending { .. }) = keys.get(&key) {
+            keys.remove(&key);
+        }
+
+        Ok(())
+    }
+}
+
+/// All possible errors returned by the [Idempotent] [Handler].
+#[derive(Debug, thiserror::Error)]
+pub enum IdempotencyError<E, S> {
+    /// Error returned when the [`IdempotencyStore`] has failed.
+    #[error("failed to access the idempotency store: {0}")]
+    Store(S),
+    /// Error returned when a Command with the same idempotency key
+    /// is still being handled: the Command should be delivered again later.
+    #[error("command with idempotency key '{0}' is already being handled")]
+    InFlight(String),
+    /// Error returned by the decorated [Handler].
+    #[error("{0}")]
+    Handler(E),
+}
+
+impl<E, S> IsConflictError for IdempotencyError<E, S>
+where
+    E: IsConflictError,
+{
+    fn is_conflict_error(&self) -> bool {
+        match self {
+            IdempotencyError::Store(_) | IdempotencyError::InFlight(_) => false,
+            IdempotencyError::Handler(err) => err.is_conflict_error(),
+        }
+    }
+}
+
+/// Default duration of the lease on the idempotency key of a Command
+/// being handled by an [Idempotent] [Handler].
+pub const DEFAULT_IDEMPOTENCY_LEASE: Duration = Duration::from_secs(30);
+
+/// Decorator for a [Handler] that short-circuits the Commands whose
+/// [idempotency key][crate::message::IDEMPOTENCY_KEY] has already been processed,
+/// as it happens with at-least-once message-driven dispatch.
+///
+/// The key is reserved in the [`IdempotencyStore`] before handling the Command,
+/// with a lease, and marked as completed only once the Command has been handled.
+/// If the handling fails, the key is released so that a redelivery can be
+/// processed again; if the process crashes instead, the key can be reserved
+/// again once its lease has expired.
+///
+/// Duplicates received while the first delivery is still being handled fail
+/// with [`IdempotencyError::InFlight`], to be delivered again later,
+/// as the first delivery might still fail.
+///
+/// Commands without an idempotency key are always handled.
+#[derive(Debug, Clone)]
+pub struct Idempotent<H, S> {
+    handler: H,
+    store: S,
+    lease: Duration,
+}
+
+impl<H, S> Idempotent<H, S> {
+    /// Decorates the [Handler] with the specified [`IdempotencyStore`].
+    pub fn new(handler: H, store: S) -> Self {
+        Self {
+            handler,
+            store,
+            lease: DEFAULT_IDEMPOTENCY_LEASE,
+        }
+    }
+
+    /// Sets the duration of the lease on the idempotency key while the Command
+    /// is being handled, which should be longer than the handling itself.
+    /// Defaults to [`DEFAULT_IDEMPOTENCY_LEASE`].
+    #[must_use]
+    pub fn with_lease(mut self, lease: Duration) -> Self {
+        self.lease = lease;
+        self
+    }
+}
+
+#[async_trait]
+impl<T, H, S> Handler<T> for Idempotent<H, S>
+where
+    T: message::Message + Send + Sync + 'static,
+    H: Handler<T>,
+    S: IdempotencyStore,
+{
+    type Error = IdempotencyError<H::Error, S::Error>;
+
+    async fn handle(&self, command: Envelope<T>) -> Result<(), Self::Error> {
+        let Some(key) = command.idempotency_key().map(ToOwned::to_owned) else {
+            return self
+                .handler
+                .handle(command)
+                .await
+                .map_err(IdempotencyError::Handler);
+        };
+
+        let name = command.message.name();
+
+        match self
+            .store
+            .reserve(name, &key, self.lease)
+            .await
+            .map_err(IdempotencyError::Store)?
+        {
+            Reservation::Acquired => {},
+            Reservation::InFlight => return Err(IdempotencyError::InFlight(key)),
+            Reservation::Completed => return Ok(()),
+        }
+
+        if let Err(err) = self.handler.handle(command).await {
+            self.store
+                .release(name, &key)
+                .await
+                .map_err(IdempotencyError::Store)?;
+
+            return Err(IdempotencyError::Handler(err));
+        }
+
+        self.store
+            .complete(name, &key)
+            .await
+            .map_err(IdempotencyError::Store)
+    }
+}
+
+/// Decorator for a [Handler] that stamps the correlation and causation ids
+/// of the Command on all the Domain Events saved while handling it,
+/// through any [Aggregate Root][crate::aggregate::Root], without the [Handler]
+/// having to call [`Root::caused_by`][crate::aggregate::Root::caused_by].
+///
+/// The Domain Events keep the correlation and causation ids already set
+/// when they have been recorded.
+///
+/// The Commands dispatched through the [Bus][crate::command::Bus] are stamped
+/// the same way, so this decorator is only needed to call a [Handler] directly.
+#[derive(Debug, Clone)]
+pub struct StampCausation<H> {
+    handler: H,
+}
+
+impl<H> From<H> for StampCausation<H> {
+    fn from(handler: H) -> Self {
+        Self { handler }
+    }
+}
+
+#[async_trait]
+impl<T, H> Handler<T> for StampCausation<H>
+where
+    T: message::Message + Send + Sync + 'static,
+    H: Handler<T>,
+{
+    type Error = H::Error;
+
+    async fn handle(&self, command: Envelope<T>) -> Result<(), Self::Error> {
+        let causation = message::causation_metadata(&command);
+
+        message::with_causation(causation, self.handler.handle(command)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use anyhow::anyhow;
+
+    use super::*;
+    use crate::clock::TestClock;
+
+    #[derive(Debug, Clone)]
+    struct Deposit;
+
+    impl message::Message for Deposit {
+        fn name(&self) -> &'static str {
+            "Deposit"
+        }
+    }
+
+    #[derive(Default)]
+    struct ConflictingService {
+        conflicts: usize,
+        fails: bool,
+        attempts: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl Handler<Deposit> for Arc<ConflictingService> {
+        type Error = anyhow::Error;
+
+        async fn handle(&self, _: Envelope<Deposit>) -> Result<(), Self::Error> {
+            let attempt = self.attempts.fetch_add(1, Ordering::SeqCst);
+
+            if self.fails {
+                return Err(anyhow!("unexpected error"));
+            }
+
+            if attempt < self.conflicts {
+                return Err(SaveError::Conflict(version::ConflictError {
+                    expected: 1,
+                    actual: 2,
+                })
+                .into());
+            }
+
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn it_retries_the_command_on_conflict_errors() {
+        let service = Arc::new(ConflictingService {
+            conflicts: 2,
+            ..ConflictingService::default()
+        });
+
+        RetryOnConflict::from(service.clone())
+            .handle(Envelope::from(Deposit))
+            .await
+            .expect("the command should succeed on the third attempt");
+
+        assert_eq!(3, service.attempts.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn it_returns_the_conflict_error_when_attempts_are_exhausted() {
+        let service = Arc::new(ConflictingService {
+            conflicts: 5,
+            ..ConflictingService::default()
+        });
+
+        let error = RetryOnConflict::from(service.clone())
+            .with_max_attempts(2)
+            .handle(Envelope::from(Deposit))
+            .await
+            .expect_err("the command should fail after two attempts");
+
+        assert!(error.is_conflict_error());
+        assert_eq!(2, service.attempts.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn it_does_not_retry_other_errors() {
+        let service = Arc::new(ConflictingService {
+            fails: true,
+            ..ConflictingService::default()
+        });
+
+        let error = RetryOnConflict::from(service.clone())
+            .handle(Envelope::from(Deposit))
+            .await
+            .expect_err("the command should fail");
+
+        assert!(!error.is_conflict_error());
+        assert_eq!(1, service.attempts.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn it_short_circuits_duplicate_commands() {
+        let service = Arc::new(ConflictingService::default());
+        let handler = Idempotent::new(service.clone(), InMemoryIdempotencyStore::default());
+
+        for _ in 0..2 {
+            handler
+                .handle(Envelope::from(Deposit).with_idempotency_key("deposit-1".to_owned()))
+                .await
+                .expect("the command should succeed");
+        }
+
+        assert_eq!(1, service.attempts.load(Ordering::SeqCst));
+
+        handler
+            .handle(Envelope::from(Deposit))
+            .await
+            .expect("commands without key should always be handled");
+
+        assert_eq!(2, service.attempts.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn it_handles_the_command_again_if_the_first_attempt_failed() {
+        let store = InMemoryIdempotencyStore::default();
+        let command = Envelope::from(Deposit).with_idempotency_key("deposit-1".to_owned());
+
+        let failing = Arc::new(ConflictingService {
+            fails: true,
+            ..ConflictingService::default()
+        });
+
+        Idempotent::new(failing, store.clone())
+            .handle(command.clone())
+            .await
+            .expect_err("the command should fail");
+
+        let service = Arc::new(ConflictingService::default());
+
+        Idempotent::new(service.clone(), store)
+            .handle(command)
+            .await
+            .expect("the redelivered command should succeed");
+
+        assert_eq!(1, service.attempts.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn it_rejects_duplicates_while_the_first_delivery_is_in_flight() {
+        let store = InMemoryIdempotencyStore::default();
+        let service = Arc::new(ConflictingService::default());
+        let handler = Idempotent::new(service.clone(), store.clone());
+        let command = Envelope::from(Deposit).with_idempotency_key("deposit-1".to_owned());
+
+        // The first delivery is still being handled.
+        assert_eq!(
+            Reservation::Acquired,
+            store
+                .reserve("Deposit", "deposit-1", DEFAULT_IDEMPOTENCY_LEASE)
+                .await
+                .unwrap()
+        );
+
+        let error = handler
+            .handle(command.clone())
+            .await
+            .expect_err("the duplicate should not be acknowledged");
+
+        assert!(matches!(error, IdempotencyError::InFlight(key) if key == "deposit-1"));
+        assert_eq!(0, service.attempts.load(Ordering::SeqCst));
+
+        // The first delivery fails, so the redelivered duplicate is handled.
+        store.release("Deposit", "deposit-1").await.unwrap();
+
+        handler
+            .handle(command)
+            .await
+            .expect("the redelivered command should succeed");
+
+        assert_eq!(1, service.attempts.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn it_handles_the_command_again_once_the_lease_of_a_crashed_delivery_expires() {
+        let clock = TestClock::default();
+        let store = InMemoryIdempotencyStore::default().with_clock(clock.clone());
+        let service = Arc::new(ConflictingService::default());
+        let handler =
+            Idempotent::new(service.clone(), store.clone()).with_lease(Duration::from_secs(10));
+        let command = Envelope::from(Deposit).with_idempotency_key("deposit-1".to_owned());
+
+        // The process handling the first delivery crashes after reserving the key.
+        store
+            .reserve("Deposit", "deposit-1", Duration::from_secs(10))
+            .await
+            .unwrap();
+
+        handler
+            .handle(command.clone())
+            .await
+            .expect_err("the lease of the first delivery has not expired yet");
+
+        clock.advance(chrono::Duration::seconds(10));
+
+        handler
+            .handle(command)
+            .await
+            .expect("the command should be handled once the lease has expired");
+
+        assert_eq!(1, service.attempts.load(Ordering::SeqCst));
+    }
+}
@@ -11,13 +11,20 @@
 //!
 //! Check out the type documentation exported in this module.
 
+pub mod bus;
+pub mod handler;
+pub mod scheduler;
 pub mod test;
+pub mod validate;
 
 use std::future::Future;
 
 use async_trait::async_trait;
+pub use bus::Bus;
 
-use crate::message;
+use crate::aggregate::Aggregate;
+use crate::version::Version;
+use crate::{aggregate, event, message};
 
 /// A Command represents an intent by an Actor (e.g. a User, or a System)
 /// to mutate the state of the system.
@@ -62,6 +69,90 @@ where
     }
 }
 
+/// Summary of a Domain Event produced while handling a [Command], part of an [Outcome].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EventSummary {
+    /// The [name][message::Message::name] of the Domain Event.
+    pub name: &'static str,
+    /// The [Version] of the Event Stream after the Domain Event has been recorded.
+    pub version: Version,
+}
+
+/// The result of handling a [Command] with a [`HandlerWithOutcome`],
+/// e.g. to return the new [Version] of the Aggregate to clients as an `ETag` header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Outcome<Id> {
+    /// The id of the Event Stream modified by the [Command].
+    pub stream_id: Id,
+    /// The [Version] of the Event Stream after handling the [Command].
+    pub version: Version,
+    /// The Domain Events produced by the [Command], in the order they have been recorded.
+    pub events: Vec<EventSummary>,
+}
+
+impl<Id> Outcome<Id> {
+    /// Returns the [Outcome] of saving the [`aggregate::Root`] with the
+    /// Domain Events returned by [`Saver::save_and_return_events`].
+    ///
+    /// [`Saver::save_and_return_events`]: crate::aggregate::repository::Saver::save_and_return_events
+    pub fn saved<T>(
+        root: &aggregate::Root<T>,
+        committed: &[event::Persisted<T::Id, T::Event>],
+    ) -> Self
+    where
+        T: Aggregate<Id = Id>,
+        Id: Clone,
+    {
+        Self {
+            stream_id: root.aggregate_id().clone(),
+            version: root.version(),
+            events: committed
+                .iter()
+                .map(|event| EventSummary {
+                    name: message::Message::name(&event.event.message),
+                    version: event.version,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// A Command [Handler] that also returns the [Outcome] of handling the [Command],
+/// for the APIs that need to return it to the caller.
+#[async_trait]
+pub trait HandlerWithOutcome<T>: Send + Sync
+where
+    T: message::Message,
+{
+    /// The type of the id of the Event Stream modified by the Handler.
+    type StreamId: Send + Sync;
+    /// The error type returned by the Handler while handling a [Command].
+    type Error: Send + Sync;
+
+    /// Handles a [Command] and returns its [Outcome], or an error if the handling has failed.
+    async fn handle_with_outcome(
+        &self,
+        command: Envelope<T>,
+    ) -> Result<Outcome<Self::StreamId>, Self::Error>;
+}
+
+#[async_trait]
+impl<T, Id, Err, F, Fut> HandlerWithOutcome<T> for F
+where
+    T: message::Message + Send + Sync + 'static,
+    Id: Send + Sync,
+    Err: Send + Sync,
+    F: Send + Sync + Fn(Envelope<T>) -> Fut,
+    Fut: Send + Sync + Future<Output = Result<Outcome<Id>, Err>>,
+{
+    type StreamId = Id;
+    type Error = Err;
+
+    async fn handle_with_outcome(&self, command: Envelope<T>) -> Result<Outcome<Id>, Err> {
+        self(command).await
+    }
+}
+
 #[cfg(test)]
 mod test_user_domain {
     use std::sync::Arc;
@@ -69,6 +160,8 @@ mod test_user_domain {
     use async_trait::async_trait;
 
     use crate::aggregate::test_user_domain::{User, UserEvent};
+    use crate::command::HandlerWithOutcome;
+    use crate::event::store::EventStoreExt;
     use crate::{aggregate, command, event, message};
 
     struct UserService(Arc<dyn aggregate::Repository<User>>);
@@ -107,6 +200,7 @@ mod test_user_domain {
         }
     }
 
+    #[derive(Clone)]
     struct ChangeUserPassword {
         email: String,
         password: String,
@@ -126,10 +220,9 @@ mod test_user_domain {
             &self,
             command: command::Envelope<ChangeUserPassword>,
         ) -> Result<(), Self::Error> {
-            let command = command.message;
-
-            let mut user = self.0.get(&command.email).await?;
+            let mut user = self.0.get(&command.message.email).await?;
 
+            let command = command.message;
             user.change_password(command.password)?;
 
             self.0.save(&mut user).await?;
@@ -138,6 +231,47 @@ mod test_user_domain {
         }
     }
 
+    #[async_trait]
+    impl HandlerWithOutcome<ChangeUserPassword> for UserService {
+        type StreamId = String;
+        type Error = anyhow::Error;
+
+        async fn handle_with_outcome(
+            &self,
+            command: command::Envelope<ChangeUserPassword>,
+        ) -> Result<command::Outcome<String>, Self::Error> {
+            let mut user = self.0.get(&command.message.email).await?;
+            user.change_password(command.message.password)?;
+
+            let committed = self.0.save_and_return_events(&mut user).await?;
+
+            Ok(command::Outcome::saved(&user, &committed))
+        }
+    }
+
+    /// Rejects the passwords found in a list of breached passwords,
+    /// provided as an external service.
+    struct BreachAwareUserService {
+        users: UserService,
+        breached_passwords: Vec<String>,
+    }
+
+    #[async_trait]
+    impl command::Handler<ChangeUserPassword> for BreachAwareUserService {
+        type Error = anyhow::Error;
+
+        async fn handle(
+            &self,
+            command: command::Envelope<ChangeUserPassword>,
+        ) -> Result<(), Self::Error> {
+            if self.breached_passwords.contains(&command.message.password) {
+                anyhow::bail!("password has been breached");
+            }
+
+            self.users.handle(command).await
+        }
+    }
+
     #[tokio::test]
     async fn it_creates_a_new_user_successfully() {
         command::test::Scenario
@@ -209,6 +343,101 @@ mod test_user_domain {
             .await;
     }
 
+    #[tokio::test]
+    async fn it_updates_the_password_on_behalf_of_the_command() {
+        command::test::Scenario
+            .given(vec![event::Persisted {
+                stream_id: "test@test.com".to_owned(),
+                version: 1,
+                event: event::Envelope::from(UserEvent::WasCreated {
+                    email: "test@test.com".to_owned(),
+                    password: "not-a-secret".to_owned(),
+                }),
+            }])
+            .when(
+                command::Envelope::from(ChangeUserPassword {
+                    email: "test@test.com".to_owned(),
+                    password: "new-password".to_owned(),
+                })
+                .with_message_id("command-1".to_owned()),
+            )
+            .then_succeeds()
+            .and_state("test@test.com".to_owned(), |user: &User| {
+                user.password() == "new-password"
+            })
+            .and_metadata(|metadata| {
+                metadata.get(message::CAUSATION_ID_KEY).map(String::as_str) == Some("command-1")
+            })
+            .assert_on(|event_store| {
+                command::handler::StampCausation::from(UserService::from(
+                    aggregate::EventSourcedRepository::from(event_store),
+                ))
+            })
+            .await;
+    }
+
+    #[tokio::test]
+    async fn bus_stamps_the_command_metadata_on_the_saved_events() {
+        let event_store = event::store::InMemory::<String, UserEvent>::default();
+        let tracking_event_store = event_store.with_recorded_events_tracking();
+        let service = UserService::from(aggregate::EventSourcedRepository::from(
+            tracking_event_store.clone(),
+        ));
+
+        let mut user =
+            aggregate::Root::<User>::create("test@test.com".to_owned(), "not-a-secret".to_owned())
+                .expect("user should be created successfully");
+        service.0.save(&mut user).await.unwrap();
+
+        let bus = command::Bus::default().register::<ChangeUserPassword, _>(service);
+
+        bus.dispatch(
+            command::Envelope::from(ChangeUserPassword {
+                email: "test@test.com".to_owned(),
+                password: "new-password".to_owned(),
+            })
+            .with_message_id("command-1".to_owned())
+            .with_correlation_id("request-1".to_owned()),
+        )
+        .await
+        .expect("password should be updated");
+
+        let recorded_events = tracking_event_store.recorded_events();
+        let created = &recorded_events[0].event;
+        let changed = &recorded_events[1].event;
+
+        assert_eq!(None, created.causation_id());
+        assert_eq!(Some("request-1"), changed.correlation_id());
+        assert_eq!(Some("command-1"), changed.causation_id());
+    }
+
+    #[tokio::test]
+    async fn it_fails_to_update_the_password_if_it_has_been_breached() {
+        command::test::Scenario
+            .given(vec![event::Persisted {
+                stream_id: "test@test.com".to_owned(),
+                version: 1,
+                event: event::Envelope::from(UserEvent::WasCreated {
+                    email: "test@test.com".to_owned(),
+                    password: "not-a-secret".to_owned(),
+                }),
+            }])
+            .when(command::Envelope::from(ChangeUserPassword {
+                email: "test@test.com".to_owned(),
+                password: "password123".to_owned(),
+            }))
+            .then_fails()
+            .assert_on_with_services(vec!["password123".to_owned()], |context| {
+                BreachAwareUserService {
+                    users: UserService::from(aggregate::EventSourcedRepository::from(
+                        context.event_store,
+                    )),
+                    breached_passwords: context.services,
+                }
+            })
+            .await;
+    }
+
     #[tokio::test]
     async fn it_fails_to_update_the_password_if_the_user_does_not_exist() {
         command::test::Scenario
@@ -222,4 +451,35 @@ mod test_user_domain {
             })
             .await;
     }
+
+    #[tokio::test]
+    async fn it_returns_the_outcome_of_updating_the_password() {
+        let event_store = event::store::InMemory::<String, UserEvent>::default();
+        let service = UserService::from(aggregate::EventSourcedRepository::from(event_store));
+
+        let mut user =
+            aggregate::Root::<User>::create("test@test.com".to_owned(), "not-a-secret".to_owned())
+                .expect("user should be created successfully");
+        service.0.save(&mut user).await.unwrap();
+
+        let outcome = service
+            .handle_with_outcome(command::Envelope::from(ChangeUserPassword {
+                email: "test@test.com".to_owned(),
+                password: "new-password".to_owned(),
+            }))
+            .await
+            .expect("password should be updated");
+
+        assert_eq!(
+            command::Outcome {
+                stream_id: "test@test.com".to_owned(),
+                version: 2,
+                events: vec![command::EventSummary {
+                    name: "UserPasswordWasChanged",
+                    version: 2,
+                }],
+            },
+            outcome
+        );
+    }
 }
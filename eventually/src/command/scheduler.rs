@@ -0,0 +1,431 @@
+//! Module containing support for scheduling [Command][Envelope]s to be handled
+//! at a future time, e.g. "expire the reservation after 15 minutes".
+//!
+//! Commands are persisted in a [Schedule], and dispatched to a [Handler]
+//! by a [Poller] once they are due.
+
+use std::collections::BTreeMap;
+use std::convert::Infallible;
+use std::marker::PhantomData;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+
+use crate::clock::{Clock, SystemClock};
+use crate::command::{Envelope, Handler};
+use crate::event::store::PoisonedError;
+use crate::message;
+use crate::runner::{Runner, Shutdown};
+
+/// A [Command][Envelope] stored in a [Schedule], claimed for dispatching.
+#[derive(Debug, Clone)]
+pub struct Scheduled<T>
+where
+    T: message::Message,
+{
+    /// The identifier assigned to the scheduled Command by the [Schedule].
+    pub id: u64,
+    /// The Command to dispatch.
+    pub command: Envelope<T>,
+}
+
+/// Persists [Command][Envelope]s of type `T` to be handled at a future time.
+///
+/// Due Commands are claimed by a [Poller] through [`Schedule::claim_due`],
+/// and removed through [`Schedule::complete`] once they have been handled:
+/// a claimed Command that is not completed (e.g. because its handling has failed,
+/// or the [Poller] has crashed) becomes due again at the retry time set by the claim.
+#[async_trait]
+pub trait Schedule<T>: Send + Sync
+where
+    T: message::Message,
+{
+    /// The error type returned by the [Schedule].
+    type Error: Send + Sync;
+
+    /// Schedules the Command to be handled at the specified time.
+    async fn schedule(&self, command: Envelope<T>, at: DateTime<Utc>) -> Result<(), Self::Error>;
+
+    /// Claims at most `limit` Commands due at the time `now`, in order of due time,
+    /// and reschedules them at `retry_at` in case they are not completed.
+    ///
+    /// Implementations shared by multiple [Poller]s must make sure
+    /// that a Command is claimed by only one of them.
+    async fn claim_due(
+        &self,
+        now: DateTime<Utc>,
+        retry_at: DateTime<Utc>,
+        limit: usize,
+    ) -> Result<Vec<Scheduled<T>>, Self::Error>;
+
+    /// Removes a claimed Command from the [Schedule], after it has been handled.
+    async fn complete(&self, id: u64) -> Result<(), Self::Error>;
+}
+
+#[derive(Debug)]
+struct InMemoryBackend<T>
+where
+    T: message::Message,
+{
+    next_id: u64,
+    commands: BTreeMap<u64, (DateTime<Utc>, Envelope<T>)>,
+}
+
+impl<T> Default for InMemoryBackend<T>
+where
+    T: message::Message,
+{
+    fn default() -> Self {
+        Self {
+            next_id: 1,
+            commands: BTreeMap::default(),
+        }
+    }
+}
+
+/// In-memory implementation of a [Schedule], best suited for testing
+/// and single-instance deployments, as the scheduled Commands are lost on restart.
+///
+/// Cloning an [`InMemorySchedule`] returns a handle to the same Commands.
+#[derive(Debug, Clone)]
+pub struct InMemorySchedule<T>
+where
+    T: message::Message,
+{
+    backend: Arc<Mutex<InMemoryBackend<T>>>,
+}
+
+impl<T> Default for InMemorySchedule<T>
+where
+    T: message::Message,
+{
+    fn default() -> Self {
+        Self {
+            backend: Arc::default(),
+        }
+    }
+}
+
+#[async_trait]
+impl<T> Schedule<T> for InMemorySchedule<T>
+where
+    T: message::Message + Clone + Send + Sync,
+{
+    type Error = PoisonedError;
+
+    async fn schedule(&self, command: Envelope<T>, at: DateTime<Utc>) -> Result<(), Self::Error> {
+        let mut backend = self.backend.lock().map_err(|_| PoisonedError)?;
+
+        let id = backend.next_id;
+        backend.next_id += 1;
+        backend.commands.insert(id, (at, command));
+
+        Ok(())
+    }
+
+    async fn claim_due(
+        &self,
+        now: DateTime<Utc>,
+        retry_at: DateTime<Utc>,
+        limit: usize,
+    ) -> Result<Vec<Scheduled<T>>, Self::Error> {
+        let mut backend = self.backend.lock().map_err(|_| PoisonedError)?;
+
+        let mut due: Vec<_> = backend
+            .commands
+            .iter_mut()
+            .filter(|(_, (at, _))| *at <= now)
+            .collect();
+
+        due.sort_by_key(|(id, (at, _))| (*at, **id));
+
+        Ok(due
+            .into_iter()
+            .take(limit)
+            .map(|(id, (at, command))| {
+                *at = retry_at;
+
+                Scheduled {
+                    id: *id,
+                    command: command.clone(),
+                }
+            })
+            .collect())
+    }
+
+    async fn complete(&self, id: u64) -> Result<(), Self::Error> {
+        self.backend
+            .lock()
+            .map_err(|_| PoisonedError)?
+            .commands
+            .remove(&id);
+
+        Ok(())
+    }
+}
+
+type ErrorCallback<E> = Box<dyn Fn(u64, &E) + Send + Sync>;
+
+/// Periodically claims the due [Command][Envelope]s from a [Schedule],
+/// and dispatches them to the [Handler].
+///
+/// Commands whose handling fails are retried after the retry delay,
+/// and their errors are reported through the callback set with
+/// [`Poller::with_error_callback`], if any.
+pub struct Poller<T, S, H>
+where
+    T: message::Message,
+    H: Handler<T>,
+{
+    schedule: S,
+    handler: H,
+    clock: Arc<dyn Clock>,
+    interval: Duration,
+    batch_size: usize,
+    retry_delay: Duration,
+    on_error: Option<ErrorCallback<H::Error>>,
+    t: PhantomData<fn() -> T>,
+}
+
+impl<T, S, H> Poller<T, S, H>
+where
+    T: message::Message + Send + Sync + 'static,
+    S: Schedule<T>,
+    H: Handler<T>,
+{
+    /// Creates a new [Poller], checking for due Commands every second,
+    /// 100 at a time, and retrying failed Commands after 30 seconds by default.
+    pub fn new(schedule: S, handler: H) -> Self {
+        Self {
+            schedule,
+            handler,
+            clock: Arc::new(SystemClock),
+            interval: Duration::from_secs(1),
+            batch_size: 100,
+            retry_delay: Duration::from_secs(30),
+            on_error: None,
+            t: PhantomData,
+        }
+    }
+
+    /// Uses the specified [Clock] to find out which Commands are due,
+    /// instead of the system time.
+    #[must_use]
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    /// Sets how often the due Commands are checked by [`Poller::run`].
+    #[must_use]
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Sets the maximum number of Commands claimed at once.
+    #[must_use]
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size;
+        self
+    }
+
+    /// Sets how long to wait before retrying a Command whose handling has failed.
+    #[must_use]
+    pub fn with_retry_delay(mut self, retry_delay: Duration) -> Self {
+        self.retry_delay = retry_delay;
+        self
+    }
+
+    /// Calls the specified callback with the id and the error of every
+    /// scheduled Command whose handling has failed.
+    #[must_use]
+    pub fn with_error_callback<F>(mut self, on_error: F) -> Self
+    where
+        F: Fn(u64, &H::Error) + Send + Sync + 'static,
+    {
+        self.on_error = Some(Box::new(on_error));
+        self
+    }
+
+    /// Claims the Commands that are currently due and dispatches them to the [Handler],
+    /// returning the number of Commands claimed.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the [Schedule] fails.
+    pub async fn poll(&self) -> Result<usize, S::Error> {
+        let now = self.clock.now();
+        let retry_at = chrono::Duration::from_std(self.retry_delay)
+            .ok()
+            .and_then(|delay| now.checked_add_signed(delay))
+            .unwrap_or(DateTime::<Utc>::MAX_UTC);
+
+        let due = self
+            .schedule
+            .claim_due(now, retry_at, self.batch_size)
+            .await?;
+
+        let claimed = due.len();
+
+        for scheduled in due {
+            match self.handler.handle(scheduled.command).await {
+                Ok(()) => self.schedule.complete(scheduled.id).await?,
+                Err(err) => {
+                    if let Some(on_error) = &self.on_error {
+                        on_error(scheduled.id, &err);
+                    }
+                },
+            }
+        }
+
+        Ok(claimed)
+    }
+
+    /// Dispatches the due Commands at every interval, until an error occurs.
+    ///
+    /// When a whole batch of Commands was due, the next batch is claimed
+    /// right away instead of waiting for the next interval.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the [Schedule] fails.
+    pub async fn run(&self) -> Result<Infallible, S::Error> {
+        loop {
+            if self.poll().await? < self.batch_size {
+                futures_timer::Delay::new(self.interval).await;
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl<T, S, H> Runner for Poller<T, S, H>
+where
+    T: message::Message + Send + Sync + 'static,
+    S: Schedule<T>,
+    H: Handler<T>,
+{
+    type Error = S::Error;
+
+    /// Dispatches the due Commands at every interval, like [`Poller::run`],
+    /// until the [Shutdown] is requested or an error occurs.
+    ///
+    /// The Commands already claimed are always dispatched before returning.
+    async fn run_until(&self, shutdown: Shutdown) -> Result<(), Self::Error> {
+        while !shutdown.is_requested() {
+            if self.poll().await? < self.batch_size {
+                shutdown.sleep(self.interval).await;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    use anyhow::anyhow;
+
+    use super::*;
+    use crate::clock::TestClock;
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    struct ExpireReservation(&'static str);
+
+    impl message::Message for ExpireReservation {
+        fn name(&self) -> &'static str {
+            "ExpireReservation"
+        }
+    }
+
+    #[derive(Default)]
+    struct ReservationService {
+        fails: AtomicBool,
+        expired: Mutex<Vec<&'static str>>,
+    }
+
+    #[async_trait]
+    impl Handler<ExpireReservation> for Arc<ReservationService> {
+        type Error = anyhow::Error;
+
+        async fn handle(&self, command: Envelope<ExpireReservation>) -> Result<(), Self::Error> {
+            if self.fails.load(Ordering::SeqCst) {
+                return Err(anyhow!("reservation service is unavailable"));
+            }
+
+            self.expired.lock().unwrap().push(command.message.0);
+
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn poller_dispatches_commands_once_they_are_due() {
+        let clock = TestClock::new(DateTime::<Utc>::UNIX_EPOCH);
+        let schedule = InMemorySchedule::default();
+        let service = Arc::new(ReservationService::default());
+
+        let in_minutes = |minutes| clock.now() + chrono::Duration::minutes(minutes);
+
+        schedule
+            .schedule(Envelope::from(ExpireReservation("b")), in_minutes(30))
+            .await
+            .unwrap();
+
+        schedule
+            .schedule(Envelope::from(ExpireReservation("a")), in_minutes(15))
+            .await
+            .unwrap();
+
+        let poller = Poller::new(schedule, service.clone()).with_clock(clock.clone());
+
+        assert_eq!(0, poller.poll().await.unwrap());
+
+        clock.advance(chrono::Duration::minutes(30));
+
+        assert_eq!(2, poller.poll().await.unwrap());
+        assert_eq!(vec!["a", "b"], *service.expired.lock().unwrap());
+
+        assert_eq!(0, poller.poll().await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn poller_retries_failed_commands_after_the_retry_delay() {
+        let clock = TestClock::new(DateTime::<Utc>::UNIX_EPOCH);
+        let schedule = InMemorySchedule::default();
+        let service = Arc::new(ReservationService::default());
+        let failures = Arc::new(Mutex::new(Vec::new()));
+
+        schedule
+            .schedule(Envelope::from(ExpireReservation("a")), clock.now())
+            .await
+            .unwrap();
+
+        let poller = Poller::new(schedule, service.clone())
+            .with_clock(clock.clone())
+            .with_retry_delay(Duration::from_mins(1))
+            .with_error_callback({
+                let failures = failures.clone();
+                move |id, _| failures.lock().unwrap().push(id)
+            });
+
+        service.fails.store(true, Ordering::SeqCst);
+
+        assert_eq!(1, poller.poll().await.unwrap());
+        assert_eq!(vec![1], *failures.lock().unwrap());
+
+        service.fails.store(false, Ordering::SeqCst);
+
+        assert_eq!(0, poller.poll().await.unwrap());
+
+        clock.advance(chrono::Duration::seconds(60));
+
+        assert_eq!(1, poller.poll().await.unwrap());
+        assert_eq!(vec!["a"], *service.expired.lock().unwrap());
+    }
+}
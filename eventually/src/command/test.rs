@@ -4,6 +4,10 @@
 use std::fmt::Debug;
 use std::hash::Hash;
 
+use chrono::{DateTime, Utc};
+
+use crate::aggregate::Aggregate;
+use crate::clock::TestClock;
 use crate::event::store::{Appender, EventStoreExt};
 use crate::{command, event, message, version};
 
@@ -90,6 +94,23 @@ where
             given: self.given,
             when: self.when,
             case: ScenarioThenCase::Produces(events),
+            assertions: Vec::default(),
+        }
+    }
+
+    /// Sets the expectation on the result of the [Scenario] to be positive,
+    /// without asserting on the exact list of Domain [Event]s produced.
+    ///
+    /// Use this method together with [`ScenarioThen::and_state`] and
+    /// [`ScenarioThen::and_metadata`] when the produced Domain Events are not
+    /// deterministic, e.g. when they contain timestamps.
+    #[must_use]
+    pub fn then_succeeds(self) -> ScenarioThen<Id, Evt, Cmd> {
+        ScenarioThen {
+            given: self.given,
+            when: self.when,
+            case: ScenarioThenCase::Succeeds,
+            assertions: Vec::default(),
         }
     }
 
@@ -100,6 +121,7 @@ where
             given: self.given,
             when: self.when,
             case: ScenarioThenCase::Fails,
+            assertions: Vec::default(),
         }
     }
 }
@@ -109,9 +131,15 @@ where
     Evt: message::Message,
 {
     Produces(Vec<event::Persisted<Id, Evt>>),
+    Succeeds,
     Fails,
 }
 
+/// Assertion run on the Domain Events of the whole [Scenario] history,
+/// followed by the ones produced by the [Command][command::Envelope] only.
+type ScenarioAssertion<Id, Evt> =
+    Box<dyn Fn(&[event::Persisted<Id, Evt>], &[event::Persisted<Id, Evt>]) + Send + Sync>;
+
 #[doc(hidden)]
 pub struct ScenarioThen<Id, Evt, Cmd>
 where
@@ -121,6 +149,7 @@ where
     given: Vec<event::Persisted<Id, Evt>>,
     when: command::Envelope<Cmd>,
     case: ScenarioThenCase<Id, Evt>,
+    assertions: Vec<ScenarioAssertion<Id, Evt>>,
 }
 
 impl<Id, Evt, Cmd> ScenarioThen<Id, Evt, Cmd>
@@ -129,6 +158,65 @@ where
     Evt: message::Message + Clone + PartialEq + Send + Sync + Debug,
     Cmd: message::Message,
 {
+    /// Asserts that the state of the [Aggregate] with the specified id,
+    /// rehydrated from all the Domain [Event]s in the [Scenario], satisfies the predicate.
+    ///
+    /// # Panics
+    ///
+    /// The check runs in [`ScenarioThen::assert_on`], which panics if no state
+    /// could be rehydrated for the id, or if it does not satisfy the predicate.
+    #[must_use]
+    pub fn and_state<T, F>(mut self, id: Id, predicate: F) -> Self
+    where
+        Id: 'static,
+        Evt: 'static,
+        T: Aggregate<Id = Id, Event = Evt>,
+        T::Error: Debug,
+        F: Fn(&T) -> bool + Send + Sync + 'static,
+    {
+        self.assertions.push(Box::new(move |history, _| {
+            let state = history
+                .iter()
+                .filter(|event| event.stream_id == id)
+                .try_fold(None, |state, event| {
+                    T::apply(state, event.event.message.clone()).map(Some)
+                })
+                .expect("domain events should be applied to the aggregate state")
+                .unwrap_or_else(|| panic!("no aggregate state found for id {id:?}"));
+
+            assert!(
+                predicate(&state),
+                "aggregate state for id {id:?} does not satisfy the predicate"
+            );
+        }));
+
+        self
+    }
+
+    /// Asserts that the [Metadata][message::Metadata] of every Domain [Event]
+    /// produced by the [Command][command::Envelope] satisfies the predicate.
+    ///
+    /// # Panics
+    ///
+    /// The check runs in [`ScenarioThen::assert_on`], which panics if the
+    /// [Metadata][message::Metadata] of any produced Domain Event does not satisfy the predicate.
+    #[must_use]
+    pub fn and_metadata<F>(mut self, predicate: F) -> Self
+    where
+        F: Fn(&message::Metadata) -> bool + Send + Sync + 'static,
+    {
+        self.assertions.push(Box::new(move |_, recorded_events| {
+            for event in recorded_events {
+                assert!(
+                    predicate(&event.event.metadata),
+                    "metadata of domain event {event:?} does not satisfy the predicate"
+                );
+            }
+        }));
+
+        self
+    }
+
     /// Executes the whole [Scenario] by constructing a Command [Handler][command::Handler]
     /// with the provided closure function and running the specified assertions.
     ///
@@ -139,10 +227,53 @@ where
     where
         F: Fn(event::store::Tracking<event::store::InMemory<Id, Evt>, Id, Evt>) -> H,
         H: command::Handler<Cmd>,
+    {
+        self.assert_on_with_clock(DateTime::UNIX_EPOCH, |event_store, _| {
+            handler_factory(event_store)
+        })
+        .await;
+    }
+
+    /// Executes the whole [Scenario] like [`ScenarioThen::assert_on`], but also
+    /// provides a [`TestClock`] set at the specified time to the closure function,
+    /// so that the Command [Handler][command::Handler] observes a deterministic time.
+    ///
+    /// # Panics
+    ///
+    /// The method panics if the assertion fails.
+    pub async fn assert_on_with_clock<F, H>(self, now: DateTime<Utc>, handler_factory: F)
+    where
+        F: Fn(event::store::Tracking<event::store::InMemory<Id, Evt>, Id, Evt>, TestClock) -> H,
+        H: command::Handler<Cmd>,
+    {
+        self.assert_on_with_services((), |context| {
+            context.clock.set(now);
+            handler_factory(context.event_store, context.clock)
+        })
+        .await;
+    }
+
+    /// Executes the whole [Scenario] like [`ScenarioThen::assert_on`], but provides
+    /// a [`ScenarioContext`] to the closure function, holding the specified services
+    /// together with the Event Store and a [`TestClock`] set at the Unix epoch.
+    ///
+    /// Use this method to test Command [Handler][command::Handler]s that depend on
+    /// other services, such as id generators or external gateways, replacing them
+    /// with test doubles.
+    ///
+    /// # Panics
+    ///
+    /// The method panics if the assertion fails.
+    pub async fn assert_on_with_services<Svc, F, H>(self, services: Svc, handler_factory: F)
+    where
+        F: FnOnce(ScenarioContext<Id, Evt, Svc>) -> H,
+        H: command::Handler<Cmd>,
     {
         let event_store = event::store::InMemory::<Id, Evt>::default();
         let tracking_event_store = event_store.clone().with_recorded_events_tracking();
 
+        let mut history = self.given.clone();
+
         for event in self.given {
             event_store
                 .append(
@@ -154,15 +285,41 @@ where
                 .expect("domain event in 'given' should be inserted in the event store");
         }
 
-        let handler = handler_factory(tracking_event_store.clone());
+        let handler = handler_factory(ScenarioContext {
+            event_store: tracking_event_store.clone(),
+            clock: TestClock::new(DateTime::UNIX_EPOCH),
+            services,
+        });
         let result = handler.handle(self.when).await;
 
+        let recorded_events = tracking_event_store.recorded_events();
+
         match self.case {
-            ScenarioThenCase::Produces(events) => {
-                let recorded_events = tracking_event_store.recorded_events();
-                assert_eq!(events, recorded_events);
-            },
+            ScenarioThenCase::Produces(events) => assert_eq!(events, recorded_events),
+            ScenarioThenCase::Succeeds => assert!(result.is_ok()),
             ScenarioThenCase::Fails => assert!(result.is_err()),
-        };
+        }
+
+        history.extend(recorded_events.iter().cloned());
+
+        for assertion in self.assertions {
+            assertion(&history, &recorded_events);
+        }
     }
 }
+
+/// The dependencies provided to the closure function that constructs the
+/// Command [Handler][command::Handler] in [`ScenarioThen::assert_on_with_services`].
+#[derive(Debug)]
+pub struct ScenarioContext<Id, Evt, Svc>
+where
+    Id: Clone + Eq + Hash + Send + Sync + Debug,
+    Evt: message::Message + Clone + Send + Sync + Debug,
+{
+    /// The Event Store of the [Scenario], already containing the 'given' Domain Events.
+    pub event_store: event::store::Tracking<event::store::InMemory<Id, Evt>, Id, Evt>,
+    /// The [`TestClock`] controlling the time observed by the Command Handler.
+    pub clock: TestClock,
+    /// The services passed to [`ScenarioThen::assert_on_with_services`].
+    pub services: Svc,
+}
@@ -0,0 +1,355 @@
+//! Module containing the [Validate] decorator, which validates the payload
+//! of a Command before it reaches the decorated [Handler].
+//!
+//! Validation failures are returned as a structured [`ValidationError`],
+//! distinct from the domain errors returned by the [Handler], so that callers
+//! (e.g. gRPC or HTTP layers) can map them to the appropriate response.
+//!
+//! With the `validator` feature, the constraints declared through the
+//! [`validator::Validate`] trait can be used through [Constraints].
+
+use std::fmt::{Display, Formatter};
+
+use async_trait::async_trait;
+
+use crate::command::handler::IsConflictError;
+use crate::command::{Envelope, Handler};
+use crate::message;
+
+/// A single validation rule broken by a Command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    /// The path of the Command field that broke the rule, e.g. `address.city`,
+    /// or [None] if the rule applies to the Command as a whole.
+    pub field: Option<String>,
+    /// A description of the broken rule.
+    pub message: String,
+}
+
+impl Violation {
+    /// Returns a [Violation] of a rule on the specified Command field.
+    pub fn field(field: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            field: Some(field.into()),
+            message: message.into(),
+        }
+    }
+
+    /// Returns a [Violation] of a rule on the Command as a whole.
+    pub fn command(message: impl Into<String>) -> Self {
+        Self {
+            field: None,
+            message: message.into(),
+        }
+    }
+}
+
+impl Display for Violation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self.field {
+            Some(field) => write!(f, "{field}: {}", self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+/// Error returned by a [Validator] when a Command is invalid,
+/// containing all the [Violation]s found.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub struct ValidationError {
+    /// All the validation rules broken by the Command.
+    pub violations: Vec<Violation>,
+}
+
+impl Display for ValidationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (i, violation) in self.violations.iter().enumerate() {
+            if i > 0 {
+                write!(f, "; ")?;
+            }
+
+            write!(f, "{violation}")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl From<Violation> for ValidationError {
+    fn from(violation: Violation) -> Self {
+        Self {
+            violations: vec![violation],
+        }
+    }
+}
+
+/// Validates the payload of Commands of type `T`.
+pub trait Validator<T>: Send + Sync {
+    /// Validates the Command, returning all the broken rules in a [`ValidationError`].
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the Command is invalid.
+    fn validate(&self, command: &T) -> Result<(), ValidationError>;
+}
+
+impl<T, F> Validator<T> for F
+where
+    F: Fn(&T) -> Result<(), ValidationError> + Send + Sync,
+{
+    fn validate(&self, command: &T) -> Result<(), ValidationError> {
+        self(command)
+    }
+}
+
+/// All possible errors returned by the [Validate] decorator.
+#[derive(Debug, thiserror::Error)]
+pub enum Error<E> {
+    /// Error returned when the Command is invalid, without reaching the [Handler].
+    #[error("invalid command: {0}")]
+    Validation(#[source] ValidationError),
+    /// Error returned by the decorated [Handler].
+    #[error("{0}")]
+    Handler(E),
+}
+
+impl<E> IsConflictError for Error<E>
+where
+    E: IsConflictError,
+{
+    fn is_conflict_error(&self) -> bool {
+        match self {
+            Error::Validation(_) => false,
+            Error::Handler(err) => err.is_conflict_error(),
+        }
+    }
+}
+
+/// Decorator for a [Handler] that runs a [Validator] on the Command payload,
+/// calling the decorated [Handler] only if the Command is valid.
+#[derive(Debug, Clone)]
+pub struct Validate<H, V> {
+    handler: H,
+    validator: V,
+}
+
+impl<H, V> Validate<H, V> {
+    /// Decorates the [Handler] with the specified [Validator].
+    pub fn new(handler: H, validator: V) -> Self {
+        Self { handler, validator }
+    }
+}
+
+#[async_trait]
+impl<T, H, V> Handler<T> for Validate<H, V>
+where
+    T: message::Message + Send + Sync + 'static,
+    H: Handler<T>,
+    V: Validator<T>,
+{
+    type Error = Error<H::Error>;
+
+    async fn handle(&self, command: Envelope<T>) -> Result<(), Self::Error> {
+        self.validator
+            .validate(&command.message)
+            .map_err(Error::Validation)?;
+
+        self.handler.handle(command).await.map_err(Error::Handler)
+    }
+}
+
+/// [Validator] that checks the constraints declared on the Command
+/// through the [`validator::Validate`] trait, usually derived.
+#[cfg(feature = "validator")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Constraints;
+
+#[cfg(feature = "validator")]
+impl<T> Validator<T> for Constraints
+where
+    T: validator::Validate,
+{
+    fn validate(&self, command: &T) -> Result<(), ValidationError> {
+        command.validate().map_err(ValidationError::from)
+    }
+}
+
+#[cfg(feature = "validator")]
+impl From<validator::ValidationErrors> for ValidationError {
+    fn from(errors: validator::ValidationErrors) -> Self {
+        fn collect(
+            prefix: &str,
+            errors: validator::ValidationErrors,
+            violations: &mut Vec<Violation>,
+        ) {
+            for (field, kind) in errors.into_errors() {
+                let path = if prefix.is_empty() {
+                    field.to_owned()
+                } else {
+                    format!("{prefix}.{field}")
+                };
+
+                match kind {
+                    validator::ValidationErrorsKind::Field(errors) => {
+                        violations.extend(errors.into_iter().map(|error| {
+                            Violation::field(
+                                path.clone(),
+                                error.message.unwrap_or(error.code).into_owned(),
+                            )
+                        }));
+                    },
+                    validator::ValidationErrorsKind::Struct(errors) => {
+                        collect(&path, *errors, violations);
+                    },
+                    validator::ValidationErrorsKind::List(errors) => {
+                        for (i, errors) in errors {
+                            collect(&format!("{path}[{i}]"), *errors, violations);
+                        }
+                    },
+                }
+            }
+        }
+
+        let mut violations = Vec::new();
+        collect("", errors, &mut violations);
+
+        // NOTE: validator errors are kept in a HashMap, so the order is not stable.
+        violations.sort_by(|a, b| a.field.cmp(&b.field));
+
+        Self { violations }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use super::*;
+
+    #[derive(Debug, Clone)]
+    struct RegisterUser {
+        email: String,
+        age: u8,
+    }
+
+    impl message::Message for RegisterUser {
+        fn name(&self) -> &'static str {
+            "RegisterUser"
+        }
+    }
+
+    #[cfg(feature = "validator")]
+    impl validator::Validate for RegisterUser {
+        fn validate(&self) -> Result<(), validator::ValidationErrors> {
+            let mut errors = validator::ValidationErrors::new();
+
+            if !validator::ValidateEmail::validate_email(&self.email) {
+                errors.add("email", validator::ValidationError::new("email"));
+            }
+
+            if self.age < 18 {
+                errors.add(
+                    "age",
+                    validator::ValidationError::new("range")
+                        .with_message("must be at least 18".into()),
+                );
+            }
+
+            if errors.is_empty() {
+                return Ok(());
+            }
+
+            Err(errors)
+        }
+    }
+
+    #[derive(Default)]
+    struct UserService {
+        handled: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl Handler<RegisterUser> for Arc<UserService> {
+        type Error = anyhow::Error;
+
+        async fn handle(&self, _: Envelope<RegisterUser>) -> Result<(), Self::Error> {
+            self.handled.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+    fn validate_user(command: &RegisterUser) -> Result<(), ValidationError> {
+        let mut violations = Vec::new();
+
+        if !command.email.contains('@') {
+            violations.push(Violation::field("email", "must be a valid email"));
+        }
+
+        if command.age < 18 {
+            violations.push(Violation::field("age", "must be at least 18"));
+        }
+
+        if violations.is_empty() {
+            return Ok(());
+        }
+
+        Err(ValidationError { violations })
+    }
+
+    #[tokio::test]
+    async fn it_rejects_invalid_commands_before_reaching_the_handler() {
+        let service = Arc::new(UserService::default());
+        let handler = Validate::new(service.clone(), validate_user);
+
+        let error = handler
+            .handle(Envelope::from(RegisterUser {
+                email: "john".to_owned(),
+                age: 16,
+            }))
+            .await
+            .expect_err("the command should be invalid");
+
+        let Error::Validation(error) = error else {
+            panic!("expected a validation error, got: {error}");
+        };
+
+        assert_eq!(
+            vec![
+                Violation::field("email", "must be a valid email"),
+                Violation::field("age", "must be at least 18"),
+            ],
+            error.violations
+        );
+        assert_eq!(0, service.handled.load(Ordering::SeqCst));
+
+        handler
+            .handle(Envelope::from(RegisterUser {
+                email: "john@example.com".to_owned(),
+                age: 30,
+            }))
+            .await
+            .expect("the command should be valid");
+
+        assert_eq!(1, service.handled.load(Ordering::SeqCst));
+    }
+
+    #[cfg(feature = "validator")]
+    #[test]
+    fn validator_errors_are_converted_into_violations() {
+        let error = Constraints
+            .validate(&RegisterUser {
+                email: "john".to_owned(),
+                age: 16,
+            })
+            .expect_err("the command should be invalid");
+
+        assert_eq!(
+            vec![
+                Violation::field("age", "must be at least 18"),
+                Violation::field("email", "email"),
+            ],
+            error.violations
+        );
+    }
+}
@@ -0,0 +1,605 @@
+//! Module containing support for archiving old Domain Events from the
+//! Event Store (the "hot" tier) to a cheaper [`ColdStorage`], such as
+//! an object storage bucket or the filesystem.
+//!
+//! The [Archiver] moves the Domain Events older than a threshold to the [`ColdStorage`],
+//! truncating them from the Event Store, while the [Tiered] Event Store chains
+//! the archived and the live segments of an Event Stream back together on read.
+
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures::future::ready;
+use futures::stream::{iter, once, StreamExt, TryStreamExt};
+
+use crate::clock::{Clock, SystemClock};
+use crate::event::store::{
+    AppendError, Appender, DeleteError, PoisonedError, StreamAppend, StreamDeleter, Streamer,
+};
+use crate::{event, message, version};
+
+/// Storage for the archived Domain Events of the Event Streams.
+///
+/// Archived segments always start from the beginning of an Event Stream,
+/// so the [`ColdStorage`] holds the oldest Domain Events, and the Event Store
+/// the most recent ones.
+#[async_trait]
+pub trait ColdStorage<StreamId, Event>: Send + Sync
+where
+    StreamId: Send + Sync,
+    Event: message::Message + Send + Sync,
+{
+    /// The error type returned by the [`ColdStorage`].
+    type Error: Send + Sync;
+
+    /// Stores a segment of Domain Events of the specified Event Stream,
+    /// sorted by [Version][version::Version].
+    ///
+    /// Since an archival might be interrupted after the segment has been stored,
+    /// storing Domain Events with an already-archived [Version][version::Version]
+    /// must replace them instead of duplicating them.
+    async fn store(
+        &self,
+        id: &StreamId,
+        events: Vec<event::Persisted<StreamId, Event>>,
+    ) -> Result<(), Self::Error>;
+
+    /// Streams the archived Domain Events of the specified Event Stream,
+    /// sorted by [Version][version::Version].
+    fn load(
+        &self,
+        id: &StreamId,
+        select: event::VersionSelect,
+    ) -> event::Stream<'_, StreamId, Event, Self::Error>;
+}
+
+/// In-memory implementation of a [`ColdStorage`], best suited for testing.
+///
+/// Cloning an [`InMemoryColdStorage`] returns a handle to the same archived Domain Events.
+#[derive(Debug, Clone)]
+pub struct InMemoryColdStorage<Id, Evt>
+where
+    Evt: message::Message,
+{
+    #[allow(clippy::type_complexity)] // It is a complex type but still readable.
+    segments: Arc<Mutex<HashMap<Id, BTreeMap<version::Version, event::Persisted<Id, Evt>>>>>,
+}
+
+impl<Id, Evt> Default for InMemoryColdStorage<Id, Evt>
+where
+    Evt: message::Message,
+{
+    fn default() -> Self {
+        Self {
+            segments: Arc::default(),
+        }
+    }
+}
+
+#[async_trait]
+impl<Id, Evt> ColdStorage<Id, Evt> for InMemoryColdStorage<Id, Evt>
+where
+    Id: Clone + Eq + Hash + Send + Sync,
+    Evt: message::Message + Clone + Send + Sync,
+{
+    type Error = PoisonedError;
+
+    async fn store(
+        &self,
+        id: &Id,
+        events: Vec<event::Persisted<Id, Evt>>,
+    ) -> Result<(), Self::Error> {
+        self.segments
+            .lock()
+            .map_err(|_| PoisonedError)?
+            .entry(id.clone())
+            .or_default()
+            .extend(events.into_iter().map(|event| (event.version, event)));
+
+        Ok(())
+    }
+
+    fn load(
+        &self,
+        id: &Id,
+        select: event::VersionSelect,
+    ) -> event::Stream<'_, Id, Evt, Self::Error> {
+        let from = match select {
+            event::VersionSelect::All => 0,
+            event::VersionSelect::From(version) => version,
+        };
+
+        let Ok(segments) = self.segments.lock() else {
+            return iter([Err(PoisonedError)]).boxed();
+        };
+
+        let events: Vec<_> = segments
+            .get(id)
+            .map(|segment| {
+                segment
+                    .range(from..)
+                    .map(|(_, event)| event.clone())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        iter(events).map(Ok).boxed()
+    }
+}
+
+/// All possible errors returned by the [Archiver].
+#[derive(Debug, thiserror::Error)]
+pub enum ArchiveError<HotErr, ColdErr> {
+    /// Error returned when the Domain Events could not be read from the Event Store.
+    #[error("failed to read domain events from the event store: {0}")]
+    Stream(#[source] HotErr),
+    /// Error returned when the Domain Events could not be stored in the [`ColdStorage`].
+    #[error("failed to store domain events in cold storage: {0}")]
+    Store(#[source] ColdErr),
+    /// Error returned when the archived Domain Events could not be truncated
+    /// from the Event Store.
+    #[error("failed to truncate archived domain events: {0}")]
+    Truncate(#[source] DeleteError),
+}
+
+/// Moves the Domain Events recorded before a threshold age from the Event Store
+/// to a [`ColdStorage`], using their [recording time][event::Persisted::recorded_at].
+///
+/// Only the oldest segment of an Event Stream is archived: archival stops at
+/// the first Domain Event that is too recent, or that has no recording time.
+pub struct Archiver<H, C> {
+    hot: H,
+    cold: C,
+    threshold: Duration,
+    clock: Arc<dyn Clock>,
+}
+
+impl<H, C> Archiver<H, C> {
+    /// Creates a new [Archiver], moving the Domain Events older than the specified threshold.
+    pub fn new(hot: H, cold: C, threshold: Duration) -> Self {
+        Self {
+            hot,
+            cold,
+            threshold,
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Uses the specified [Clock] to find out the age of the Domain Events,
+    /// instead of the system time.
+    #[must_use]
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    /// Archives the Domain Events of the specified Event Stream older than the threshold,
+    /// returning the number of Domain Events moved to the [`ColdStorage`].
+    ///
+    /// Domain Events are truncated from the Event Store only after
+    /// they have been stored in the [`ColdStorage`].
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if either the Event Store or the [`ColdStorage`] fails.
+    pub async fn archive<Id, Evt>(&self, id: &Id) -> Result<usize, ArchiveError<H::Error, C::Error>>
+    where
+        Id: Send + Sync,
+        Evt: message::Message + Send + Sync,
+        H: Streamer<Id, Evt> + StreamDeleter<Id, Evt>,
+        C: ColdStorage<Id, Evt>,
+    {
+        let cutoff = chrono::Duration::from_std(self.threshold)
+            .ok()
+            .and_then(|threshold| self.clock.now().checked_sub_signed(threshold))
+            .unwrap_or(DateTime::<Utc>::MIN_UTC);
+
+        let events: Vec<_> = self
+            .hot
+            .stream(id, event::VersionSelect::All)
+            .try_take_while(|event| {
+                ready(Ok(event
+                    .recorded_at()
+                    .is_some_and(|recorded_at| recorded_at < cutoff)))
+            })
+            .try_collect()
+            .await
+            .map_err(ArchiveError::Stream)?;
+
+        let Some(last_version) = events.last().map(|event| event.version) else {
+            return Ok(0);
+        };
+
+        let archived = events.len();
+
+        self.cold
+            .store(id, events)
+            .await
+            .map_err(ArchiveError::Store)?;
+
+        self.hot
+            .truncate(id, last_version + 1)
+            .await
+            .map_err(ArchiveError::Truncate)?;
+
+        Ok(archived)
+    }
+}
+
+/// All possible errors returned when streaming from a [Tiered] Event Store.
+#[derive(Debug, thiserror::Error)]
+pub enum TieredError<HotErr, ColdErr> {
+    /// Error returned by the Event Store.
+    #[error("failed to stream domain events from the event store: {0}")]
+    Hot(#[source] HotErr),
+    /// Error returned by the [`ColdStorage`].
+    #[error("failed to stream domain events from cold storage: {0}")]
+    Cold(#[source] ColdErr),
+    /// Error returned when some Domain Events of the Event Stream are missing
+    /// from both the Event Store and the [`ColdStorage`], e.g. because they have
+    /// been truncated from the Event Store without being archived.
+    #[error("domain events are missing from the event stream: expected version {expected}, found {actual}")]
+    Gap {
+        /// The [Version][version::Version] of the next expected Domain Event.
+        expected: version::Version,
+        /// The [Version][version::Version] of the Domain Event found instead.
+        actual: version::Version,
+    },
+}
+
+/// Event Store decorator that streams the archived Domain Events
+/// from a [`ColdStorage`] before the ones still in the Event Store,
+/// so that archived Event Streams can be read transparently.
+///
+/// New Domain Events are always appended to the Event Store.
+///
+/// When the Event Store does not continue where the cold segment left off,
+/// e.g. because an [Archiver] has run while streaming, the missing Domain Events
+/// are read again from the [`ColdStorage`], failing with [`TieredError::Gap`]
+/// if they cannot be found there either.
+#[derive(Debug, Clone)]
+pub struct Tiered<H, C> {
+    hot: H,
+    cold: C,
+}
+
+impl<H, C> Tiered<H, C> {
+    /// Chains the specified [`ColdStorage`] to the Event Store on read.
+    pub fn new(hot: H, cold: C) -> Self {
+        Self { hot, cold }
+    }
+}
+
+impl<H, C> Tiered<H, C> {
+    async fn load_archived_gap<Id, Evt>(
+        &self,
+        id: &Id,
+        next: version::Version,
+        first_live: Option<version::Version>,
+    ) -> Result<Vec<event::Persisted<Id, Evt>>, TieredError<H::Error, C::Error>>
+    where
+        Id: Send + Sync,
+        Evt: message::Message + Send + Sync,
+        H: Streamer<Id, Evt>,
+        C: ColdStorage<Id, Evt>,
+    {
+        let archived: Vec<_> = self
+            .cold
+            .load(id, event::VersionSelect::From(next))
+            .try_take_while(|event| {
+                ready(Ok(
+                    first_live.is_none_or(|first_live| event.version < first_live)
+                ))
+            })
+            .try_collect()
+            .await
+            .map_err(TieredError::Cold)?;
+
+        let mut expected = next;
+
+        for event in &archived {
+            if event.version != expected {
+                return Err(TieredError::Gap {
+                    expected,
+                    actual: event.version,
+                });
+            }
+
+            expected += 1;
+        }
+
+        match first_live {
+            Some(actual) if actual != expected => Err(TieredError::Gap { expected, actual }),
+            _ => Ok(archived),
+        }
+    }
+}
+
+impl<Id, Evt, H, C> Streamer<Id, Evt> for Tiered<H, C>
+where
+    Id: Clone + Send + Sync + 'static,
+    Evt: message::Message + Send + Sync + 'static,
+    H: Streamer<Id, Evt>,
+    H::Error: 'static,
+    C: ColdStorage<Id, Evt>,
+    C::Error: 'static,
+{
+    type Error = TieredError<H::Error, C::Error>;
+
+    fn stream(
+        &self,
+        id: &Id,
+        select: event::VersionSelect,
+    ) -> event::Stream<'_, Id, Evt, Self::Error> {
+        let last_archived = Arc::new(AtomicU64::new(0));
+        let id = id.clone();
+
+        let cold = self.cold.load(&id, select).map_err(TieredError::Cold);
+
+        let cold = {
+            let last_archived = last_archived.clone();
+            cold.inspect_ok(move |event| last_archived.store(event.version, Ordering::SeqCst))
+        };
+
+        // NOTE: the Event Store is opened only after the cold segment has been exhausted,
+        // so that Domain Events both archived and not yet truncated are streamed only once.
+        let hot = once(async move {
+            let next = match (last_archived.load(Ordering::SeqCst), select) {
+                (0, event::VersionSelect::All) => 1,
+                (0, event::VersionSelect::From(version)) => version.max(1),
+                (last, _) => last + 1,
+            };
+
+            let mut hot = self
+                .hot
+                .stream(&id, event::VersionSelect::From(next))
+                .map_err(TieredError::Hot)
+                .peekable();
+
+            let first_live = match Pin::new(&mut hot).peek().await {
+                Some(Ok(event)) if event.version == next => return hot.boxed(),
+                Some(Ok(event)) => Some(event.version),
+                Some(Err(_)) => return hot.boxed(),
+                None => None,
+            };
+
+            // NOTE: an Archiver might have moved more Domain Events to the cold storage
+            // after it has been read, and truncated them from the Event Store:
+            // they are read again from the cold storage, to fill the gap.
+            match self.load_archived_gap(&id, next, first_live).await {
+                Ok(archived) => iter(archived.into_iter().map(Ok)).chain(hot).boxed(),
+                Err(err) => once(ready(Err(err))).boxed(),
+            }
+        })
+        .flatten();
+
+        cold.chain(hot).boxed()
+    }
+}
+
+#[async_trait]
+impl<Id, Evt, H, C> Appender<Id, Evt> for Tiered<H, C>
+where
+    Id: Send + Sync + 'static,
+    Evt: message::Message + Send + Sync + 'static,
+    H: Appender<Id, Evt>,
+    C: Send + Sync,
+{
+    async fn append(
+        &self,
+        id: Id,
+        version_check: version::Check,
+        events: Vec<event::Envelope<Evt>>,
+    ) -> Result<version::Version, AppendError> {
+        self.hot.append(id, version_check, events).await
+    }
+
+    async fn append_multi(
+        &self,
+        appends: Vec<StreamAppend<Id, Evt>>,
+    ) -> Result<Vec<version::Version>, AppendError>
+    where
+        Id: 'async_trait,
+        Evt: 'async_trait,
+    {
+        self.hot.append_multi(appends).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::TryStreamExt;
+
+    use super::*;
+    use crate::clock::TestClock;
+    use crate::event::store::InMemory;
+    use crate::message::tests::StringMessage;
+
+    const STREAM_ID: &str = "stream:archive";
+
+    async fn append(store: &impl Appender<&'static str, StringMessage>, event: &'static str) {
+        store
+            .append(
+                STREAM_ID,
+                version::Check::Any,
+                vec![event::Envelope::from(StringMessage(event))],
+            )
+            .await
+            .expect("append should not fail");
+    }
+
+    fn versions(
+        events: Vec<event::Persisted<&'static str, StringMessage>>,
+    ) -> Vec<version::Version> {
+        events.into_iter().map(|event| event.version).collect()
+    }
+
+    #[tokio::test]
+    async fn archived_events_are_streamed_before_the_live_ones() {
+        let clock = TestClock::new(DateTime::<Utc>::UNIX_EPOCH);
+        let hot = InMemory::<&'static str, StringMessage>::default().with_clock(clock.clone());
+        let cold = InMemoryColdStorage::default();
+        let archiver = Archiver::new(hot.clone(), cold.clone(), Duration::from_hours(24))
+            .with_clock(clock.clone());
+
+        append(&hot, "event-1").await;
+        append(&hot, "event-2").await;
+
+        clock.advance(chrono::Duration::days(2));
+        append(&hot, "event-3").await;
+
+        assert_eq!(2, archiver.archive(&STREAM_ID).await.unwrap());
+        assert_eq!(0, archiver.archive(&STREAM_ID).await.unwrap());
+
+        let live: Vec<_> = hot
+            .stream(&STREAM_ID, event::VersionSelect::All)
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert_eq!(vec![3], versions(live));
+
+        let tiered = Tiered::new(hot.clone(), cold);
+        append(&tiered, "event-4").await;
+
+        let all: Vec<_> = tiered
+            .stream(&STREAM_ID, event::VersionSelect::All)
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert_eq!(vec![1, 2, 3, 4], versions(all));
+
+        let from: Vec<_> = tiered
+            .stream(&STREAM_ID, event::VersionSelect::From(2))
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert_eq!(vec![2, 3, 4], versions(from));
+    }
+
+    #[tokio::test]
+    async fn events_archived_but_not_truncated_are_streamed_once() {
+        let hot = InMemory::<&'static str, StringMessage>::default();
+        let cold = InMemoryColdStorage::default();
+
+        append(&hot, "event-1").await;
+        append(&hot, "event-2").await;
+
+        let archived: Vec<_> = hot
+            .stream(&STREAM_ID, event::VersionSelect::All)
+            .try_collect()
+            .await
+            .unwrap();
+
+        cold.store(&STREAM_ID, archived).await.unwrap();
+
+        let events: Vec<_> = Tiered::new(hot, cold)
+            .stream(&STREAM_ID, event::VersionSelect::All)
+            .map_ok(|event| event.event.message)
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert_eq!(
+            vec![StringMessage("event-1"), StringMessage("event-2")],
+            events
+        );
+    }
+
+    /// [`ColdStorage`] that misses all the archived Domain Events on the first load,
+    /// as if they were archived right after the cold segment has been read.
+    struct ArchivedWhileStreaming {
+        cold: InMemoryColdStorage<&'static str, StringMessage>,
+        loaded: std::sync::atomic::AtomicBool,
+    }
+
+    #[async_trait]
+    impl ColdStorage<&'static str, StringMessage> for ArchivedWhileStreaming {
+        type Error = PoisonedError;
+
+        async fn store(
+            &self,
+            id: &&'static str,
+            events: Vec<event::Persisted<&'static str, StringMessage>>,
+        ) -> Result<(), Self::Error> {
+            self.cold.store(id, events).await
+        }
+
+        fn load(
+            &self,
+            id: &&'static str,
+            select: event::VersionSelect,
+        ) -> event::Stream<'_, &'static str, StringMessage, Self::Error> {
+            if self.loaded.swap(true, Ordering::SeqCst) {
+                return self.cold.load(id, select);
+            }
+
+            iter(Vec::new()).boxed()
+        }
+    }
+
+    #[tokio::test]
+    async fn events_archived_while_streaming_are_read_again_from_cold_storage() {
+        let clock = TestClock::new(DateTime::<Utc>::UNIX_EPOCH);
+        let hot = InMemory::<&'static str, StringMessage>::default().with_clock(clock.clone());
+        let cold = InMemoryColdStorage::default();
+        let archiver = Archiver::new(hot.clone(), cold.clone(), Duration::from_hours(24))
+            .with_clock(clock.clone());
+
+        append(&hot, "event-1").await;
+        append(&hot, "event-2").await;
+
+        clock.advance(chrono::Duration::days(2));
+        append(&hot, "event-3").await;
+
+        assert_eq!(2, archiver.archive(&STREAM_ID).await.unwrap());
+
+        let tiered = Tiered::new(
+            hot,
+            ArchivedWhileStreaming {
+                cold,
+                loaded: std::sync::atomic::AtomicBool::default(),
+            },
+        );
+
+        let all: Vec<_> = tiered
+            .stream(&STREAM_ID, event::VersionSelect::All)
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert_eq!(vec![1, 2, 3], versions(all));
+    }
+
+    #[tokio::test]
+    async fn events_missing_from_both_tiers_are_reported_as_a_gap() {
+        let hot = InMemory::<&'static str, StringMessage>::default();
+
+        append(&hot, "event-1").await;
+        append(&hot, "event-2").await;
+        append(&hot, "event-3").await;
+
+        hot.truncate(&STREAM_ID, 3).await.unwrap();
+
+        let result: Result<Vec<_>, _> = Tiered::new(hot, InMemoryColdStorage::default())
+            .stream(&STREAM_ID, event::VersionSelect::All)
+            .try_collect()
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(TieredError::Gap {
+                expected: 1,
+                actual: 3
+            })
+        ));
+    }
+}
@@ -0,0 +1,286 @@
+//! Module `enrich` contains the [`Enriched`] decorator for an Event [Store],
+//! which adds cross-cutting metadata to the Domain Events before they are appended,
+//! so that it does not have to be set in every Domain method.
+//!
+//! Enrichers usually read ambient values, such as the id of the user
+//! or of the request being served, from task-local or thread-local storage.
+//!
+//! [Store]: crate::event::Store
+
+use std::fmt::{Debug, Formatter, Result as FmtResult};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use crate::event::store::{AppendError, Appender, GlobalStreamer, StreamAppend, Streamer};
+use crate::version::Version;
+use crate::{event, message, version};
+
+/// Function called by the [`Enriched`] Event Store on every Domain Event
+/// before it is appended, together with the id of its Event Stream.
+pub type EnricherFn<StreamId, Evt> = dyn Fn(&StreamId, &mut event::Envelope<Evt>) + Send + Sync;
+
+/// [`event::Store`] decorator that runs the configured enrichers,
+/// in the order they have been added, on every Domain Event appended through it.
+///
+/// Cloning an [`Enriched`] Event Store shares its enrichers with the clone.
+pub struct Enriched<S, StreamId, Evt>
+where
+    Evt: message::Message,
+{
+    store: S,
+    enrichers: Vec<Arc<EnricherFn<StreamId, Evt>>>,
+}
+
+impl<S, StreamId, Evt> Clone for Enriched<S, StreamId, Evt>
+where
+    S: Clone,
+    Evt: message::Message,
+{
+    fn clone(&self) -> Self {
+        Self {
+            store: self.store.clone(),
+            enrichers: self.enrichers.clone(),
+        }
+    }
+}
+
+impl<S, StreamId, Evt> Debug for Enriched<S, StreamId, Evt>
+where
+    S: Debug,
+    Evt: message::Message,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_struct("Enriched")
+            .field("store", &self.store)
+            .field("enrichers", &self.enrichers.len())
+            .finish()
+    }
+}
+
+impl<S, StreamId, Evt> From<S> for Enriched<S, StreamId, Evt>
+where
+    Evt: message::Message,
+{
+    fn from(store: S) -> Self {
+        Self {
+            store,
+            enrichers: Vec::new(),
+        }
+    }
+}
+
+impl<S, StreamId, Evt> Enriched<S, StreamId, Evt>
+where
+    Evt: message::Message,
+{
+    /// Adds an enricher, which can modify the whole [Envelope][event::Envelope]
+    /// of every Domain Event appended to the Event Store.
+    #[must_use]
+    pub fn with_enricher<F>(mut self, enricher: F) -> Self
+    where
+        F: Fn(&StreamId, &mut event::Envelope<Evt>) + Send + Sync + 'static,
+    {
+        self.enrichers.push(Arc::new(enricher));
+        self
+    }
+
+    /// Adds an enricher setting the specified metadata entry to the value
+    /// returned by the function, if any, e.g. read from a task-local variable.
+    ///
+    /// Metadata entries already set on the Domain Event are not overwritten.
+    #[must_use]
+    pub fn with_metadata<F>(self, key: impl Into<String>, value: F) -> Self
+    where
+        F: Fn() -> Option<String> + Send + Sync + 'static,
+    {
+        let key = key.into();
+
+        self.with_enricher(move |_, event| {
+            if event.metadata.contains_key(&key) {
+                return;
+            }
+
+            if let Some(value) = value() {
+                event.metadata.insert(key.clone(), value);
+            }
+        })
+    }
+
+    /// Returns the underlying Event Store.
+    pub fn into_inner(self) -> S {
+        self.store
+    }
+
+    fn enrich(&self, stream_id: &StreamId, events: &mut [event::Envelope<Evt>]) {
+        for event in events {
+            for enricher in &self.enrichers {
+                enricher(stream_id, event);
+            }
+        }
+    }
+}
+
+impl<S, StreamId, Evt> Streamer<StreamId, Evt> for Enriched<S, StreamId, Evt>
+where
+    S: Streamer<StreamId, Evt>,
+    StreamId: Send + Sync,
+    Evt: message::Message + Send + Sync,
+{
+    type Error = S::Error;
+
+    fn stream(
+        &self,
+        id: &StreamId,
+        select: event::VersionSelect,
+    ) -> event::Stream<'_, StreamId, Evt, Self::Error> {
+        self.store.stream(id, select)
+    }
+
+    fn stream_filtered<'a>(
+        &'a self,
+        id: &StreamId,
+        select: event::VersionSelect,
+        names: event::NameSelect,
+    ) -> event::Stream<'a, StreamId, Evt, Self::Error>
+    where
+        StreamId: 'a,
+        Evt: 'a,
+        Self::Error: 'a,
+    {
+        self.store.stream_filtered(id, select, names)
+    }
+}
+
+impl<S, StreamId, Evt> GlobalStreamer<StreamId, Evt> for Enriched<S, StreamId, Evt>
+where
+    S: GlobalStreamer<StreamId, Evt>,
+    StreamId: Send + Sync,
+    Evt: message::Message + Send + Sync,
+{
+    type Error = S::Error;
+
+    fn stream_all(
+        &self,
+        select: event::SequenceSelect,
+    ) -> event::SequencedStream<'_, StreamId, Evt, Self::Error> {
+        self.store.stream_all(select)
+    }
+
+    fn stream_all_filtered<'a>(
+        &'a self,
+        select: event::SequenceSelect,
+        names: event::NameSelect,
+    ) -> event::SequencedStream<'a, StreamId, Evt, Self::Error>
+    where
+        StreamId: 'a,
+        Evt: 'a,
+        Self::Error: 'a,
+    {
+        self.store.stream_all_filtered(select, names)
+    }
+}
+
+#[async_trait]
+impl<S, StreamId, Evt> Appender<StreamId, Evt> for Enriched<S, StreamId, Evt>
+where
+    S: Appender<StreamId, Evt>,
+    StreamId: Send + Sync + 'static,
+    Evt: message::Message + Send + Sync + 'static,
+{
+    async fn append(
+        &self,
+        id: StreamId,
+        version_check: version::Check,
+        mut events: Vec<event::Envelope<Evt>>,
+    ) -> Result<Version, AppendError> {
+        self.enrich(&id, &mut events);
+        self.store.append(id, version_check, events).await
+    }
+
+    async fn append_multi(
+        &self,
+        mut appends: Vec<StreamAppend<StreamId, Evt>>,
+    ) -> Result<Vec<Version>, AppendError>
+    where
+        StreamId: 'async_trait,
+        Evt: 'async_trait,
+    {
+        for append in &mut appends {
+            self.enrich(&append.id, &mut append.events);
+        }
+
+        self.store.append_multi(appends).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::TryStreamExt;
+
+    use super::*;
+    use crate::event::store::InMemory;
+    use crate::message::tests::StringMessage;
+
+    tokio::task_local! {
+        static REQUEST_ID: String;
+    }
+
+    #[tokio::test]
+    async fn enrichers_stamp_the_appended_events_in_order() {
+        let event_store = Enriched::<_, &'static str, StringMessage>::from(InMemory::default())
+            .with_metadata("Request-Id", || REQUEST_ID.try_with(Clone::clone).ok())
+            .with_enricher(|stream_id, event| {
+                event
+                    .metadata
+                    .insert("Stream".to_owned(), (*stream_id).to_owned());
+            });
+
+        REQUEST_ID
+            .scope(
+                "request-1".to_owned(),
+                event_store.append(
+                    "stream:a",
+                    version::Check::MustBe(0),
+                    vec![
+                        event::Envelope::from(StringMessage("first")),
+                        event::Envelope::from(StringMessage("second"))
+                            .with_metadata("Request-Id".to_owned(), "explicit".to_owned()),
+                    ],
+                ),
+            )
+            .await
+            .expect("append should not fail");
+
+        event_store
+            .append_multi(vec![StreamAppend {
+                id: "stream:b",
+                version_check: version::Check::MustBe(0),
+                events: vec![event::Envelope::from(StringMessage("third"))],
+            }])
+            .await
+            .expect("append should not fail");
+
+        let metadata: Vec<_> = event_store
+            .stream_all(event::SequenceSelect::All)
+            .map_ok(|event| {
+                let metadata = event.event.event.metadata;
+                (
+                    metadata.get("Request-Id").cloned(),
+                    metadata.get("Stream").cloned(),
+                )
+            })
+            .try_collect()
+            .await
+            .expect("opening the global event stream should not fail");
+
+        assert_eq!(
+            vec![
+                (Some("request-1".to_owned()), Some("stream:a".to_owned())),
+                (Some("explicit".to_owned()), Some("stream:a".to_owned())),
+                (None, Some("stream:b".to_owned())),
+            ],
+            metadata
+        );
+    }
+}
@@ -0,0 +1,389 @@
+//! Module containing the [Exporter] and [Importer] types, used to back up
+//! the Domain Events of an Event Store to a file and restore them, possibly
+//! into a different Event Store implementation (e.g. to migrate between backends).
+//!
+//! Domain Events are exported as JSON lines, one [Record] per line, in their
+//! global [Sequence][event::Sequence] order. The Domain Event payloads are encoded
+//! with the specified [Serializer], so they can use any format supported by the crate,
+//! e.g. [Protobuf][crate::serde::Protobuf].
+
+use std::io::{self, BufRead, Write};
+
+use futures::TryStreamExt;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::event::store::{AppendError, Appender, GlobalStreamer};
+use crate::serde::{Deserializer, Serializer};
+use crate::{event, message, version};
+
+/// A Domain Event as exported by the [Exporter], written as a single JSON line.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Record<Id> {
+    /// The global position of the Domain Event in the source Event Store.
+    pub sequence: event::Sequence,
+    /// The id of the Event Stream the Domain Event belongs to.
+    pub stream_id: Id,
+    /// The [Version][version::Version] of the Domain Event in its Event Stream.
+    pub version: version::Version,
+    /// The [name][message::Message::name] of the Domain Event.
+    pub name: String,
+    /// The metadata of the Domain Event [Envelope][event::Envelope].
+    pub metadata: message::Metadata,
+    /// The Domain Event payload, encoded with the [Exporter] serializer.
+    pub payload: Vec<u8>,
+}
+
+/// All possible errors returned by the [Exporter].
+#[derive(Debug, thiserror::Error)]
+pub enum ExportError<E> {
+    /// Error returned when the Domain Events could not be streamed from the Event Store.
+    #[error("failed to stream domain events from the event store: {0}")]
+    Stream(#[source] E),
+    /// Error returned when a Domain Event payload could not be serialized.
+    #[error("failed to serialize domain event payload: {0}")]
+    Serialize(anyhow::Error),
+    /// Error returned when a [Record] could not be encoded.
+    #[error("failed to encode exported record: {0}")]
+    Encoding(#[from] serde_json::Error),
+    /// Error returned when a [Record] could not be written.
+    #[error("failed to write exported record: {0}")]
+    Io(#[from] io::Error),
+}
+
+/// Exports all the Domain Events of an Event Store, or only the ones
+/// of the selected Event Streams, preserving their global order.
+#[derive(Debug, Clone)]
+pub struct Exporter<Id, S, Z> {
+    store: S,
+    serde: Z,
+    streams: Option<Vec<Id>>,
+}
+
+impl<Id, S, Z> Exporter<Id, S, Z> {
+    /// Creates a new [Exporter] of all the Domain Events in the Event Store,
+    /// encoding their payloads with the specified [Serializer].
+    pub fn new(store: S, serde: Z) -> Self {
+        Self {
+            store,
+            serde,
+            streams: None,
+        }
+    }
+
+    /// Exports only the Domain Events of the specified Event Streams.
+    #[must_use]
+    pub fn with_streams(mut self, streams: impl IntoIterator<Item = Id>) -> Self {
+        self.streams = Some(streams.into_iter().collect());
+        self
+    }
+
+    /// Writes the selected Domain Events to the specified writer, one JSON-encoded
+    /// [Record] per line, returning the number of Domain Events exported.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the Event Store fails, or a Domain Event
+    /// could not be encoded or written.
+    pub async fn export<Evt>(&self, mut writer: impl Write) -> Result<usize, ExportError<S::Error>>
+    where
+        Id: PartialEq + Serialize + Send + Sync,
+        Evt: message::Message + Send + Sync,
+        S: GlobalStreamer<Id, Evt>,
+        Z: Serializer<Evt>,
+    {
+        let mut events = self.store.stream_all(event::SequenceSelect::All);
+        let mut exported = 0;
+
+        while let Some(event) = events.try_next().await.map_err(ExportError::Stream)? {
+            let event::Sequenced { sequence, event } = event;
+
+            if let Some(streams) = &self.streams {
+                if !streams.contains(&event.stream_id) {
+                    continue;
+                }
+            }
+
+            let record = Record {
+                sequence,
+                stream_id: event.stream_id,
+                version: event.version,
+                name: event.event.message.name().to_owned(),
+                metadata: event.event.metadata,
+                payload: self
+                    .serde
+                    .serialize(event.event.message)
+                    .map_err(ExportError::Serialize)?,
+            };
+
+            serde_json::to_writer(&mut writer, &record)?;
+            writer.write_all(b"\n")?;
+            exported += 1;
+        }
+
+        writer.flush()?;
+
+        Ok(exported)
+    }
+}
+
+/// All possible errors returned by the [Importer].
+#[derive(Debug, thiserror::Error)]
+pub enum ImportError {
+    /// Error returned when a [Record] could not be read.
+    #[error("failed to read exported record: {0}")]
+    Io(#[from] io::Error),
+    /// Error returned when a [Record] could not be decoded.
+    #[error("failed to decode exported record: {0}")]
+    Encoding(#[from] serde_json::Error),
+    /// Error returned when a Domain Event payload could not be deserialized.
+    #[error("failed to deserialize domain event payload: {0}")]
+    Deserialize(anyhow::Error),
+    /// Error returned when the Domain Events could not be appended to the Event Store,
+    /// e.g. because the imported Event Streams already exist in it.
+    #[error("failed to import domain events: {0}")]
+    Append(#[from] AppendError),
+}
+
+/// Imports the Domain Events written by an [Exporter] into an Event Store,
+/// in the same order they have been exported.
+///
+/// Domain Events keep the [Version][version::Version] they had in the source
+/// Event Store, so the imported Event Streams must not exist in the target one.
+/// Their global [Sequence][event::Sequence] numbers are assigned by the target Event Store.
+#[derive(Debug, Clone)]
+pub struct Importer<S, Z> {
+    store: S,
+    serde: Z,
+}
+
+impl<S, Z> Importer<S, Z> {
+    /// Creates a new [Importer] into the Event Store, decoding the Domain Event
+    /// payloads with the specified [Deserializer].
+    pub fn new(store: S, serde: Z) -> Self {
+        Self { store, serde }
+    }
+
+    /// Reads the [Record]s written by [`Exporter::export`] from the specified reader
+    /// and appends them to the Event Store, returning the number of Domain Events imported.
+    ///
+    /// Consecutive Domain Events of the same Event Stream are appended together.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if a [Record] could not be read or decoded,
+    /// or the Event Store fails.
+    pub async fn import<Id, Evt>(&self, reader: impl BufRead) -> Result<usize, ImportError>
+    where
+        Id: PartialEq + DeserializeOwned + Send + Sync,
+        Evt: message::Message + Send + Sync,
+        S: Appender<Id, Evt>,
+        Z: Deserializer<Evt>,
+    {
+        let mut imported = 0;
+        let mut batch: Option<(Id, version::Version, Vec<event::Envelope<Evt>>)> = None;
+
+        for line in reader.lines() {
+            let line = line?;
+
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let record: Record<Id> = serde_json::from_str(&line)?;
+
+            let event = event::Envelope {
+                message: self
+                    .serde
+                    .deserialize(&record.payload)
+                    .map_err(ImportError::Deserialize)?,
+                metadata: record.metadata,
+            };
+
+            match &mut batch {
+                Some((id, from, events))
+                    if *id == record.stream_id
+                        && *from + events.len() as version::Version == record.version =>
+                {
+                    events.push(event);
+                },
+                _ => {
+                    if let Some(batch) = batch.take() {
+                        imported += self.append(batch).await?;
+                    }
+
+                    batch = Some((record.stream_id, record.version, vec![event]));
+                },
+            }
+        }
+
+        if let Some(batch) = batch {
+            imported += self.append(batch).await?;
+        }
+
+        Ok(imported)
+    }
+
+    async fn append<Id, Evt>(
+        &self,
+        (id, from, events): (Id, version::Version, Vec<event::Envelope<Evt>>),
+    ) -> Result<usize, ImportError>
+    where
+        Id: Send + Sync,
+        Evt: message::Message + Send + Sync,
+        S: Appender<Id, Evt>,
+    {
+        let appended = events.len();
+
+        self.store
+            .append(id, version::Check::MustBe(from.saturating_sub(1)), events)
+            .await?;
+
+        Ok(appended)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::TryStreamExt;
+
+    use super::*;
+    use crate::event::store::InMemory;
+    use crate::serde::Json;
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    enum AccountEvent {
+        Opened { owner: String },
+        Deposited { amount: u64 },
+    }
+
+    impl message::Message for AccountEvent {
+        fn name(&self) -> &'static str {
+            match self {
+                AccountEvent::Opened { .. } => "AccountOpened",
+                AccountEvent::Deposited { .. } => "AccountDeposited",
+            }
+        }
+    }
+
+    async fn append(store: &InMemory<String, AccountEvent>, id: &str, event: AccountEvent) {
+        store
+            .append(
+                id.to_owned(),
+                version::Check::Any,
+                vec![event::Envelope::from(event)],
+            )
+            .await
+            .expect("append should not fail");
+    }
+
+    async fn all_events(
+        store: &InMemory<String, AccountEvent>,
+    ) -> Vec<(String, version::Version, AccountEvent)> {
+        store
+            .stream_all(event::SequenceSelect::All)
+            .map_ok(|event| {
+                (
+                    event.event.stream_id,
+                    event.event.version,
+                    event.event.event.message,
+                )
+            })
+            .try_collect()
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn exported_events_are_imported_in_the_same_order() {
+        let source = InMemory::<String, AccountEvent>::default();
+
+        append(
+            &source,
+            "account:1",
+            AccountEvent::Opened {
+                owner: "john".to_owned(),
+            },
+        )
+        .await;
+        append(
+            &source,
+            "account:2",
+            AccountEvent::Opened {
+                owner: "jane".to_owned(),
+            },
+        )
+        .await;
+        append(&source, "account:1", AccountEvent::Deposited { amount: 10 }).await;
+        append(&source, "account:1", AccountEvent::Deposited { amount: 20 }).await;
+
+        let mut file = Vec::new();
+
+        let exported = Exporter::new(source.clone(), Json::<AccountEvent>::default())
+            .export(&mut file)
+            .await
+            .unwrap();
+
+        assert_eq!(4, exported);
+
+        let target = InMemory::<String, AccountEvent>::default();
+
+        let imported = Importer::new(target.clone(), Json::<AccountEvent>::default())
+            .import::<String, AccountEvent>(file.as_slice())
+            .await
+            .unwrap();
+
+        assert_eq!(4, imported);
+        assert_eq!(all_events(&source).await, all_events(&target).await);
+
+        let error = Importer::new(target, Json::<AccountEvent>::default())
+            .import::<String, AccountEvent>(file.as_slice())
+            .await
+            .expect_err("the event streams already exist");
+
+        assert!(matches!(
+            error,
+            ImportError::Append(AppendError::Conflict(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn exporter_selects_only_the_specified_streams() {
+        let source = InMemory::<String, AccountEvent>::default();
+
+        append(
+            &source,
+            "account:1",
+            AccountEvent::Opened {
+                owner: "john".to_owned(),
+            },
+        )
+        .await;
+        append(
+            &source,
+            "account:2",
+            AccountEvent::Opened {
+                owner: "jane".to_owned(),
+            },
+        )
+        .await;
+
+        let mut file = Vec::new();
+
+        Exporter::new(source, Json::<AccountEvent>::default())
+            .with_streams(vec!["account:2".to_owned()])
+            .export(&mut file)
+            .await
+            .unwrap();
+
+        let records: Vec<Record<String>> = file
+            .split(|byte| *byte == b'\n')
+            .filter(|line| !line.is_empty())
+            .map(|line| serde_json::from_slice(line).unwrap())
+            .collect();
+
+        assert_eq!(1, records.len());
+        assert_eq!("account:2", records[0].stream_id);
+        assert_eq!("AccountOpened", records[0].name);
+    }
+}
@@ -0,0 +1,228 @@
+//! Module `fan_in` contains the [`FanIn`] combinator, which merges the Domain Events
+//! of different types, e.g. of different [Aggregate][crate::aggregate::Aggregate]s,
+//! into a single stream of a user-defined sum type, so that projections
+//! spanning multiple Aggregates can be written type-safely.
+
+use futures::stream::{self, BoxStream, StreamExt, TryStreamExt};
+
+use crate::{event, message};
+
+type SourceStream<'a, T, E> = BoxStream<'a, Result<(event::Sequence, T), E>>;
+
+struct Source<'a, K, T, E> {
+    events: BoxStream<'a, Result<(K, T), E>>,
+    next: Option<(K, T)>,
+    done: bool,
+}
+
+/// Merges multiple streams, each ordered by the key paired with its items,
+/// into a single stream ordered by that key, picking the first stream
+/// among the ones with the same key.
+///
+/// The merged stream yields the errors of the underlying streams as they occur,
+/// and keeps merging the items of the other ones.
+pub(crate) fn merge_by_key<'a, K, T, E>(
+    sources: Vec<BoxStream<'a, Result<(K, T), E>>>,
+) -> BoxStream<'a, Result<T, E>>
+where
+    K: Ord + Send + 'a,
+    T: Send + 'a,
+    E: Send + 'a,
+{
+    let sources: Vec<_> = sources
+        .into_iter()
+        .map(|events| Source {
+            events,
+            next: None,
+            done: false,
+        })
+        .collect();
+
+    stream::unfold(sources, |mut sources| async move {
+        for source in &mut sources {
+            if source.next.is_some() || source.done {
+                continue;
+            }
+
+            match source.events.try_next().await {
+                Ok(Some(item)) => source.next = Some(item),
+                Ok(None) => source.done = true,
+                Err(err) => {
+                    source.done = true;
+                    return Some((Err(err), sources));
+                },
+            }
+        }
+
+        let (_, source) = sources
+            .iter()
+            .enumerate()
+            .filter_map(|(i, source)| Some((&source.next.as_ref()?.0, i)))
+            .min()?;
+
+        let (_, item) = sources[source].next.take()?;
+
+        Some((Ok(item), sources))
+    })
+    .boxed()
+}
+
+/// Merges multiple [`event::SequencedStream`]s with different Domain Event types
+/// into a single stream of the sum type `T`, ordered by the global
+/// [Sequence][event::Sequence] number of the Domain Events.
+///
+/// The merged streams are expected to come from the same Event Store,
+/// e.g. opened with [`TypeStreamer::stream_by_type`][crate::event::store::TypeStreamer::stream_by_type]
+/// for each Aggregate type, so that their [Sequence][event::Sequence] numbers are comparable.
+///
+/// Example of usage:
+/// ```text
+/// enum BankEvent {
+///     Account(event::Sequenced<AccountId, AccountEvent>),
+///     Transfer(event::Sequenced<TransferId, TransferEvent>),
+/// }
+///
+/// let events = FanIn::<BankEvent, anyhow::Error>::default()
+///     .with_stream(accounts.stream_by_type(Account::type_name(), select), BankEvent::Account)
+///     .with_stream(transfers.stream_by_type(Transfer::type_name(), select), BankEvent::Transfer)
+///     .into_stream();
+/// ```
+pub struct FanIn<'a, T, E> {
+    sources: Vec<SourceStream<'a, T, E>>,
+}
+
+impl<T, E> Default for FanIn<'_, T, E> {
+    fn default() -> Self {
+        Self {
+            sources: Vec::default(),
+        }
+    }
+}
+
+impl<'a, T, E> FanIn<'a, T, E>
+where
+    T: Send + 'a,
+    E: Send + 'a,
+{
+    /// Adds a stream of Domain Events to merge, converting each of them
+    /// into the sum type `T` with the specified function.
+    #[must_use]
+    pub fn with_stream<Id, Evt, Err, F>(
+        mut self,
+        events: event::SequencedStream<'a, Id, Evt, Err>,
+        into: F,
+    ) -> Self
+    where
+        Id: Send + 'a,
+        Evt: message::Message + Send + 'a,
+        Err: Send + 'a,
+        E: From<Err>,
+        F: Fn(event::Sequenced<Id, Evt>) -> T + Send + 'a,
+    {
+        self.sources.push(
+            events
+                .map_ok(move |event| (event.sequence, into(event)))
+                .map_err(E::from)
+                .boxed(),
+        );
+
+        self
+    }
+
+    /// Returns the merged stream of Domain Events, ordered by their
+    /// [Sequence][event::Sequence] number.
+    ///
+    /// The merged stream yields the errors of the underlying streams as they occur,
+    /// and keeps merging the Domain Events of the other ones.
+    #[must_use]
+    pub fn into_stream(self) -> BoxStream<'a, Result<T, E>> {
+        merge_by_key(self.sources)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+
+    use super::*;
+    use crate::message::tests::StringMessage;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    struct CounterEvent(u64);
+
+    impl message::Message for CounterEvent {
+        fn name(&self) -> &'static str {
+            "CounterEvent"
+        }
+    }
+
+    #[derive(Debug, PartialEq)]
+    enum FannedEvent {
+        Message(&'static str, event::Sequence),
+        Counter(u64, event::Sequence),
+    }
+
+    fn sequenced<Id, Evt>(
+        stream_id: Id,
+        events: Vec<(event::Sequence, Evt)>,
+    ) -> event::SequencedStream<'static, Id, Evt, Infallible>
+    where
+        Id: Clone + Send + 'static,
+        Evt: message::Message + Send + 'static,
+    {
+        stream::iter(
+            events
+                .into_iter()
+                .zip(1..)
+                .map(move |((sequence, message), version)| {
+                    Ok(event::Sequenced {
+                        sequence,
+                        event: event::Persisted {
+                            stream_id: stream_id.clone(),
+                            version,
+                            event: event::Envelope::from(message),
+                        },
+                    })
+                }),
+        )
+        .boxed()
+    }
+
+    #[tokio::test]
+    async fn fan_in_merges_typed_streams_by_sequence() {
+        let events: Vec<_> = FanIn::<FannedEvent, Infallible>::default()
+            .with_stream(
+                sequenced(
+                    "message",
+                    vec![(1, StringMessage("a")), (4, StringMessage("b"))],
+                ),
+                |event| FannedEvent::Message(event.event.event.message.0, event.sequence),
+            )
+            .with_stream(
+                sequenced(
+                    1_u64,
+                    vec![
+                        (2, CounterEvent(1)),
+                        (3, CounterEvent(2)),
+                        (5, CounterEvent(3)),
+                    ],
+                ),
+                |event| FannedEvent::Counter(event.event.event.message.0, event.sequence),
+            )
+            .into_stream()
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert_eq!(
+            vec![
+                FannedEvent::Message("a", 1),
+                FannedEvent::Counter(1, 2),
+                FannedEvent::Counter(2, 3),
+                FannedEvent::Message("b", 4),
+                FannedEvent::Counter(3, 5),
+            ],
+            events
+        );
+    }
+}
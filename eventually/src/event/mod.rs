@@ -1,9 +1,19 @@
 //! Module `event` contains types and abstractions helpful for working
 //! with Domain Events.
 
+pub mod archive;
+pub mod enrich;
+#[cfg(feature = "serde-json")]
+pub mod export;
+pub mod fan_in;
+pub mod ordering;
+pub mod quota;
+pub mod shard;
 pub mod store;
+pub mod test;
 use std::fmt::Debug;
 
+use chrono::{DateTime, Utc};
 use futures::stream::BoxStream;
 use serde::{Deserialize, Serialize};
 
@@ -15,6 +25,10 @@ use crate::{message, version};
 /// that is being implemented.
 pub type Envelope<T> = message::Envelope<T>;
 
+/// [Metadata][message::Metadata] key used by the Event [Store] implementations
+/// to record when a Domain Event has been persisted, in RFC 3339 format.
+pub const RECORDED_AT_KEY: &str = "Recorded-At";
+
 /// An [Event] that has been persisted to the Event [Store].
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Persisted<Id, Evt>
@@ -36,6 +50,29 @@ where
     pub event: Envelope<Evt>,
 }
 
+impl<Id, Evt> Persisted<Id, Evt>
+where
+    Evt: message::Message,
+{
+    /// Returns when the Domain Event has been persisted by the Event [Store],
+    /// if it has been recorded in the [`RECORDED_AT_KEY`] metadata entry.
+    #[must_use]
+    pub fn recorded_at(&self) -> Option<DateTime<Utc>> {
+        self.event
+            .metadata
+            .get(RECORDED_AT_KEY)
+            .and_then(|recorded_at| DateTime::parse_from_rfc3339(recorded_at).ok())
+            .map(|recorded_at| recorded_at.with_timezone(&Utc))
+    }
+
+    /// Returns the [`ResumeToken`] to continue streaming the Event Stream
+    /// right after this Domain Event.
+    #[must_use]
+    pub fn resume_token(&self) -> ResumeToken {
+        ResumeToken(Position::Version(self.version.saturating_add(1)))
+    }
+}
+
 /// Specifies the slice of the Event Stream to select when calling [`Store::stream`].
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum VersionSelect {
@@ -47,5 +84,188 @@ pub enum VersionSelect {
     From(version::Version),
 }
 
+/// Specifies which Domain Events to select by their [name][message::Message::name]
+/// when calling [`store::Streamer::stream_filtered`] or [`store::GlobalStreamer::stream_all_filtered`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum NameSelect {
+    /// Selects all the Domain Events, regardless of their name.
+    #[default]
+    All,
+
+    /// Selects only the Domain Events with one of the specified names.
+    Only(Vec<String>),
+}
+
+impl NameSelect {
+    /// Selects only the Domain Events with one of the specified names.
+    pub fn only<I, S>(names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self::Only(names.into_iter().map(Into::into).collect())
+    }
+
+    /// Returns true if a Domain Event with the specified name is selected.
+    #[must_use]
+    pub fn matches(&self, name: &str) -> bool {
+        match self {
+            Self::All => true,
+            Self::Only(names) => names.iter().any(|selected| selected == name),
+        }
+    }
+}
+
 /// Stream is a stream of [Persisted] Domain Events.
 pub type Stream<'a, Id, Evt, Err> = BoxStream<'a, Result<Persisted<Id, Evt>, Err>>;
+
+/// The position of a Domain Event across all the Event Streams of an Event [Store],
+/// assigned in insertion order.
+pub type Sequence = u64;
+
+/// A [Persisted] Domain Event, together with its global [Sequence] number.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Sequenced<Id, Evt>
+where
+    Evt: message::Message,
+{
+    /// The global position of the Domain Event in the Event [Store].
+    pub sequence: Sequence,
+
+    /// The Domain Event, as persisted in its Event Stream.
+    pub event: Persisted<Id, Evt>,
+}
+
+/// Specifies the slice of all the Domain Events in the Event [Store] to select
+/// when calling [`store::GlobalStreamer::stream_all`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SequenceSelect {
+    /// Selects all the Domain Events in the Event [Store].
+    All,
+
+    /// Selects all the Domain Events in the Event [Store] starting from
+    /// the one with the specified [Sequence] number.
+    From(Sequence),
+}
+
+/// A stream of [Sequenced] Domain Events, ordered by their [Sequence] number.
+pub type SequencedStream<'a, Id, Evt, Err> = BoxStream<'a, Result<Sequenced<Id, Evt>, Err>>;
+
+impl<Id, Evt> Sequenced<Id, Evt>
+where
+    Evt: message::Message,
+{
+    /// Returns the [`ResumeToken`] to continue streaming all the Domain Events
+    /// in the Event [Store] right after this one.
+    #[must_use]
+    pub fn resume_token(&self) -> ResumeToken {
+        ResumeToken(Position::Sequence(self.sequence.saturating_add(1)))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Position {
+    Version(version::Version),
+    Sequence(Sequence),
+}
+
+/// An opaque token pointing right after a streamed Domain Event, which clients
+/// can persist and pass back later to resume streaming from where they left off.
+///
+/// Tokens obtained through [`Persisted::resume_token`] can be converted into a
+/// [`VersionSelect`], while tokens obtained through [`Sequenced::resume_token`]
+/// can be converted into a [`SequenceSelect`]: since the conversion happens
+/// before reaching the Event [Store], pagination works the same way on all backends.
+///
+/// Tokens are (de)serialized as strings, whose format should not be relied upon.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub struct ResumeToken(Position);
+
+impl ResumeToken {
+    /// Returns the [`ResumeToken`] to stream all the Domain Events in the Event [Store]
+    /// starting from the specified [Sequence] number, e.g. when no Domain Event
+    /// has been streamed yet.
+    pub(crate) fn from_sequence(sequence: Sequence) -> Self {
+        Self(Position::Sequence(sequence))
+    }
+}
+
+/// All possible errors returned when decoding or using a [`ResumeToken`].
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ResumeTokenError {
+    /// The token could not be decoded.
+    #[error("malformed resume token: {0}")]
+    Malformed(String),
+    /// The token belongs to a different kind of stream, e.g. a token obtained
+    /// from a single Event Stream used to stream all Domain Events.
+    #[error("resume token cannot be used to resume this kind of stream")]
+    WrongKind,
+}
+
+const VERSION_TOKEN_PREFIX: &str = "v1.v.";
+const SEQUENCE_TOKEN_PREFIX: &str = "v1.s.";
+
+impl std::fmt::Display for ResumeToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.0 {
+            Position::Version(version) => write!(f, "{VERSION_TOKEN_PREFIX}{version:x}"),
+            Position::Sequence(sequence) => write!(f, "{SEQUENCE_TOKEN_PREFIX}{sequence:x}"),
+        }
+    }
+}
+
+impl std::str::FromStr for ResumeToken {
+    type Err = ResumeTokenError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let malformed = || ResumeTokenError::Malformed(s.to_owned());
+        let parse = |position: &str| u64::from_str_radix(position, 16).map_err(|_| malformed());
+
+        if let Some(version) = s.strip_prefix(VERSION_TOKEN_PREFIX) {
+            return parse(version).map(|version| Self(Position::Version(version)));
+        }
+
+        if let Some(sequence) = s.strip_prefix(SEQUENCE_TOKEN_PREFIX) {
+            return parse(sequence).map(|sequence| Self(Position::Sequence(sequence)));
+        }
+
+        Err(malformed())
+    }
+}
+
+impl From<ResumeToken> for String {
+    fn from(token: ResumeToken) -> Self {
+        token.to_string()
+    }
+}
+
+impl TryFrom<String> for ResumeToken {
+    type Error = ResumeTokenError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl TryFrom<ResumeToken> for VersionSelect {
+    type Error = ResumeTokenError;
+
+    fn try_from(token: ResumeToken) -> Result<Self, Self::Error> {
+        match token.0 {
+            Position::Version(version) => Ok(Self::From(version)),
+            Position::Sequence(_) => Err(ResumeTokenError::WrongKind),
+        }
+    }
+}
+
+impl TryFrom<ResumeToken> for SequenceSelect {
+    type Error = ResumeTokenError;
+
+    fn try_from(token: ResumeToken) -> Result<Self, Self::Error> {
+        match token.0 {
+            Position::Sequence(sequence) => Ok(Self::From(sequence)),
+            Position::Version(_) => Err(ResumeTokenError::WrongKind),
+        }
+    }
+}
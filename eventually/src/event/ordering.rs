@@ -0,0 +1,286 @@
+//! Module `ordering` contains the [`StrictOrdering`] decorator for an Event [Store],
+//! which verifies the ordering of the Domain Events streamed by the underlying Event Store.
+//!
+//! It is meant to be used in integration tests of Event Store implementations,
+//! to catch bugs such as duplicated or skipped Domain Events at page boundaries
+//! as close to their source as possible.
+//!
+//! [Store]: crate::event::Store
+
+use async_trait::async_trait;
+use futures::stream::StreamExt;
+
+use crate::event::store::{AppendError, Appender, GlobalStreamer, StreamAppend, Streamer};
+use crate::version::Version;
+use crate::{event, message, version};
+
+/// All possible errors returned by the [`StrictOrdering`] decorator
+/// while streaming Domain Events.
+#[derive(Debug, thiserror::Error)]
+pub enum OrderingError<E> {
+    /// Error returned by the underlying Event Store.
+    #[error(transparent)]
+    Store(E),
+    /// A Domain Event has been streamed with a [Version] not greater than
+    /// the previous one, or lower than the one requested through [`event::VersionSelect`].
+    #[error("domain event streamed out of order: expected version greater than or equal to {expected}, got {actual}")]
+    Version {
+        /// The lowest [Version] allowed for the streamed Domain Event.
+        expected: Version,
+        /// The [Version] of the streamed Domain Event.
+        actual: Version,
+    },
+    /// A Domain Event has been streamed with a [Sequence][event::Sequence] number not greater
+    /// than the previous one, or lower than the one requested through [`event::SequenceSelect`].
+    #[error("domain event streamed out of order: expected sequence greater than or equal to {expected}, got {actual}")]
+    Sequence {
+        /// The lowest [Sequence][event::Sequence] number allowed for the streamed Domain Event.
+        expected: event::Sequence,
+        /// The [Sequence][event::Sequence] number of the streamed Domain Event.
+        actual: event::Sequence,
+    },
+}
+
+/// [`event::Store`] decorator that verifies every streamed Domain Event comes
+/// strictly after the previous one, returning an [`OrderingError`] otherwise.
+///
+/// Event Streams opened with [`Streamer::stream`] must be strictly increasing
+/// in [Version], while the global stream opened with [`GlobalStreamer::stream_all`]
+/// must be strictly increasing in [Sequence][event::Sequence] number.
+///
+/// When the `tracing` feature is enabled, an error is also logged on every violation.
+#[derive(Debug, Clone)]
+pub struct StrictOrdering<S> {
+    store: S,
+}
+
+impl<S> From<S> for StrictOrdering<S> {
+    fn from(store: S) -> Self {
+        Self { store }
+    }
+}
+
+impl<S> StrictOrdering<S> {
+    /// Returns the underlying Event Store.
+    pub fn into_inner(self) -> S {
+        self.store
+    }
+}
+
+fn report<E>(error: OrderingError<E>) -> OrderingError<E> {
+    #[cfg(feature = "tracing")]
+    match &error {
+        OrderingError::Version { expected, actual } => {
+            tracing::error!(expected, actual, "domain event streamed out of order");
+        },
+        OrderingError::Sequence { expected, actual } => {
+            tracing::error!(expected, actual, "domain event streamed out of order");
+        },
+        OrderingError::Store(_) => {},
+    }
+
+    error
+}
+
+impl<S, StreamId, Evt> Streamer<StreamId, Evt> for StrictOrdering<S>
+where
+    S: Streamer<StreamId, Evt>,
+    S::Error: Send + 'static,
+    StreamId: Send + Sync + 'static,
+    Evt: message::Message + Send + Sync + 'static,
+{
+    type Error = OrderingError<S::Error>;
+
+    fn stream(
+        &self,
+        id: &StreamId,
+        select: event::VersionSelect,
+    ) -> event::Stream<'_, StreamId, Evt, Self::Error> {
+        let mut expected = match select {
+            event::VersionSelect::All => 0,
+            event::VersionSelect::From(version) => version,
+        };
+
+        self.store
+            .stream(id, select)
+            .map(move |result| {
+                let event = result.map_err(OrderingError::Store)?;
+
+                if event.version < expected {
+                    return Err(report(OrderingError::Version {
+                        expected,
+                        actual: event.version,
+                    }));
+                }
+
+                expected = event.version + 1;
+
+                Ok(event)
+            })
+            .boxed()
+    }
+}
+
+impl<S, StreamId, Evt> GlobalStreamer<StreamId, Evt> for StrictOrdering<S>
+where
+    S: GlobalStreamer<StreamId, Evt>,
+    S::Error: Send + 'static,
+    StreamId: Send + Sync + 'static,
+    Evt: message::Message + Send + Sync + 'static,
+{
+    type Error = OrderingError<S::Error>;
+
+    fn stream_all(
+        &self,
+        select: event::SequenceSelect,
+    ) -> event::SequencedStream<'_, StreamId, Evt, Self::Error> {
+        let mut expected = match select {
+            event::SequenceSelect::All => 0,
+            event::SequenceSelect::From(sequence) => sequence,
+        };
+
+        self.store
+            .stream_all(select)
+            .map(move |result| {
+                let event = result.map_err(OrderingError::Store)?;
+
+                if event.sequence < expected {
+                    return Err(report(OrderingError::Sequence {
+                        expected,
+                        actual: event.sequence,
+                    }));
+                }
+
+                expected = event.sequence + 1;
+
+                Ok(event)
+            })
+            .boxed()
+    }
+}
+
+#[async_trait]
+impl<S, StreamId, Evt> Appender<StreamId, Evt> for StrictOrdering<S>
+where
+    S: Appender<StreamId, Evt>,
+    StreamId: Send + Sync + 'static,
+    Evt: message::Message + Send + Sync + 'static,
+{
+    async fn append(
+        &self,
+        id: StreamId,
+        version_check: version::Check,
+        events: Vec<event::Envelope<Evt>>,
+    ) -> Result<Version, AppendError> {
+        self.store.append(id, version_check, events).await
+    }
+
+    async fn append_multi(
+        &self,
+        appends: Vec<StreamAppend<StreamId, Evt>>,
+    ) -> Result<Vec<Version>, AppendError>
+    where
+        StreamId: 'async_trait,
+        Evt: 'async_trait,
+    {
+        self.store.append_multi(appends).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+
+    use futures::stream::iter;
+    use futures::{FutureExt, TryStreamExt};
+
+    use super::*;
+    use crate::event::store::InMemory;
+    use crate::message::tests::StringMessage;
+
+    /// An Event Store that streams the same Domain Events twice, like a
+    /// backend fetching the same page twice would do.
+    struct DuplicatingStore(InMemory<&'static str, StringMessage>);
+
+    impl Streamer<&'static str, StringMessage> for DuplicatingStore {
+        type Error = Infallible;
+
+        fn stream(
+            &self,
+            id: &&'static str,
+            select: event::VersionSelect,
+        ) -> event::Stream<'_, &'static str, StringMessage, Self::Error> {
+            let events: Vec<_> = self
+                .0
+                .stream(id, select)
+                .map(|result| result.expect("in-memory store should not fail"))
+                .collect::<Vec<_>>()
+                .now_or_never()
+                .expect("in-memory stream should be ready");
+
+            iter(events.clone().into_iter().chain(events).map(Ok)).boxed()
+        }
+    }
+
+    #[tokio::test]
+    async fn strict_ordering_lets_ordered_events_through() {
+        let event_store = StrictOrdering::from(InMemory::<&'static str, StringMessage>::default());
+
+        event_store
+            .append(
+                "stream:a",
+                version::Check::MustBe(0),
+                vec![event::Envelope::from(StringMessage("event")); 3],
+            )
+            .await
+            .expect("append should not fail");
+
+        let versions: Vec<Version> = event_store
+            .stream(&"stream:a", event::VersionSelect::From(2))
+            .map_ok(|event| event.version)
+            .try_collect()
+            .await
+            .expect("domain events should be in order");
+
+        assert_eq!(vec![2, 3], versions);
+
+        let sequences: Vec<event::Sequence> = event_store
+            .stream_all(event::SequenceSelect::All)
+            .map_ok(|event| event.sequence)
+            .try_collect()
+            .await
+            .expect("domain events should be in order");
+
+        assert_eq!(vec![1, 2, 3], sequences);
+    }
+
+    #[tokio::test]
+    async fn strict_ordering_fails_on_duplicated_events() {
+        let in_memory = InMemory::<&'static str, StringMessage>::default();
+
+        in_memory
+            .append(
+                "stream:a",
+                version::Check::MustBe(0),
+                vec![event::Envelope::from(StringMessage("event")); 2],
+            )
+            .await
+            .expect("append should not fail");
+
+        let event_store = StrictOrdering::from(DuplicatingStore(in_memory));
+
+        let error = event_store
+            .stream(&"stream:a", event::VersionSelect::All)
+            .try_collect::<Vec<_>>()
+            .await
+            .expect_err("duplicated domain events should be detected");
+
+        assert!(matches!(
+            error,
+            OrderingError::Version {
+                expected: 3,
+                actual: 1
+            }
+        ));
+    }
+}
@@ -0,0 +1,464 @@
+//! Module `quota` contains a tenant-aware [Quota] decorator for an Event [Store],
+//! useful to enforce usage limits in multi-tenant deployments, e.g. to sell different plans.
+//!
+//! Domain Events are attributed to a tenant through the
+//! [`TENANT_ID_KEY`][message::TENANT_ID_KEY] entry of their metadata:
+//! Domain Events with no tenant are not subject to any quota.
+//!
+//! [Store]: crate::event::Store
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use async_trait::async_trait;
+
+use crate::event::store::{AppendError, Appender, StreamAppend, Streamer};
+use crate::version::{self, Version};
+use crate::{event, message};
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// Usage limits for a tenant. Limits set to `None` are not enforced.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Limits {
+    /// Maximum number of Domain Events appended per day (UTC).
+    pub events_per_day: Option<u64>,
+    /// Maximum number of bytes of serialized Domain Events appended overall.
+    pub storage_bytes: Option<u64>,
+}
+
+/// Usage counters of a tenant, as tracked by the [Quota] decorator.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Usage {
+    /// Number of Domain Events appended in the current day (UTC).
+    pub events_today: u64,
+    /// Number of bytes of serialized Domain Events appended overall.
+    pub storage_bytes: u64,
+}
+
+/// Error returned by the [Quota] decorator, wrapped in [`AppendError::Internal`],
+/// when appending new Domain Events would exceed the [Limits] of a tenant.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum QuotaExceededError {
+    /// The daily number of Domain Events of the tenant would be exceeded.
+    #[error("tenant '{tenant}' would exceed its quota of {limit} domain events per day")]
+    EventsPerDay {
+        /// The tenant exceeding the quota.
+        tenant: String,
+        /// The daily limit of the tenant.
+        limit: u64,
+    },
+    /// The storage of the tenant would be exceeded.
+    #[error("tenant '{tenant}' would exceed its storage quota of {limit} bytes")]
+    StorageBytes {
+        /// The tenant exceeding the quota.
+        tenant: String,
+        /// The storage limit of the tenant.
+        limit: u64,
+    },
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+struct TenantUsage {
+    day: u64,
+    usage: Usage,
+}
+
+impl TenantUsage {
+    fn roll_over(&mut self, today: u64) {
+        if self.day != today {
+            self.day = today;
+            self.usage.events_today = 0;
+        }
+    }
+}
+
+/// [`event::Store`] decorator that enforces per-tenant [Limits] on the
+/// Domain Events appended to the underlying Event Store.
+///
+/// The size of the Domain Events is measured using the specified
+/// [Serializer][crate::serde::Serializer], which should be the same one
+/// used by the underlying Event Store.
+///
+/// Usage counters are kept in memory, starting from the moment the decorator
+/// has been created: use [`Quota::with_usage`] to restore them, e.g. from a read model.
+#[derive(Debug, Clone)]
+pub struct Quota<S, Serde> {
+    store: S,
+    serde: Serde,
+    default_limits: Limits,
+    tenant_limits: HashMap<String, Limits>,
+    usage: Arc<Mutex<HashMap<String, TenantUsage>>>,
+}
+
+impl<S, Serde> Quota<S, Serde> {
+    /// Returns a new [Quota] decorator that enforces the specified [Limits]
+    /// on all the tenants of the Event Store.
+    pub fn new(store: S, serde: Serde, limits: Limits) -> Self {
+        Self {
+            store,
+            serde,
+            default_limits: limits,
+            tenant_limits: HashMap::default(),
+            usage: Arc::default(),
+        }
+    }
+
+    /// Overrides the [Limits] enforced on the specified tenant.
+    #[must_use]
+    pub fn with_tenant_limits(mut self, tenant: String, limits: Limits) -> Self {
+        self.tenant_limits.insert(tenant, limits);
+        self
+    }
+
+    /// Sets the [Usage] counters of the specified tenant for the current day.
+    #[must_use]
+    pub fn with_usage(self, tenant: String, usage: Usage) -> Self {
+        self.lock_usage().insert(
+            tenant,
+            TenantUsage {
+                day: today(),
+                usage,
+            },
+        );
+
+        self
+    }
+
+    /// Returns the current [Usage] counters of the specified tenant.
+    #[must_use]
+    pub fn usage(&self, tenant: &str) -> Usage {
+        let mut usage = self.lock_usage();
+
+        usage
+            .get_mut(tenant)
+            .map_or_else(Usage::default, |tenant_usage| {
+                tenant_usage.roll_over(today());
+                tenant_usage.usage
+            })
+    }
+
+    // NOTE: the usage counters are only updated while holding the lock, and a thread
+    // panicking in between can at most leave some usage reserved: the counters are
+    // used anyway instead of propagating the panic, as they are reset every day.
+    fn lock_usage(&self) -> MutexGuard<'_, HashMap<String, TenantUsage>> {
+        self.usage.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn limits(&self, tenant: &str) -> Limits {
+        self.tenant_limits
+            .get(tenant)
+            .copied()
+            .unwrap_or(self.default_limits)
+    }
+
+    /// Reserves the usage requested by the new Domain Events, or fails
+    /// without reserving anything if any tenant would exceed its [Limits].
+    fn reserve(&self, requested: &HashMap<String, Usage>) -> Result<(), QuotaExceededError> {
+        let today = today();
+        let mut usage = self.lock_usage();
+
+        for (tenant, requested) in requested {
+            let limits = self.limits(tenant);
+            let current = usage
+                .get_mut(tenant)
+                .map_or_else(Usage::default, |tenant_usage| {
+                    tenant_usage.roll_over(today);
+                    tenant_usage.usage
+                });
+
+            if let Some(limit) = limits.events_per_day {
+                if current.events_today + requested.events_today > limit {
+                    return Err(QuotaExceededError::EventsPerDay {
+                        tenant: tenant.clone(),
+                        limit,
+                    });
+                }
+            }
+
+            if let Some(limit) = limits.storage_bytes {
+                if current.storage_bytes + requested.storage_bytes > limit {
+                    return Err(QuotaExceededError::StorageBytes {
+                        tenant: tenant.clone(),
+                        limit,
+                    });
+                }
+            }
+        }
+
+        for (tenant, requested) in requested {
+            let tenant_usage = usage.entry(tenant.clone()).or_insert(TenantUsage {
+                day: today,
+                usage: Usage::default(),
+            });
+
+            tenant_usage.usage.events_today += requested.events_today;
+            tenant_usage.usage.storage_bytes += requested.storage_bytes;
+        }
+
+        Ok(())
+    }
+
+    /// Releases the usage reserved for Domain Events that could not be appended.
+    fn release(&self, requested: &HashMap<String, Usage>) {
+        let mut usage = self.lock_usage();
+
+        for (tenant, requested) in requested {
+            if let Some(tenant_usage) = usage.get_mut(tenant) {
+                tenant_usage.usage.events_today = tenant_usage
+                    .usage
+                    .events_today
+                    .saturating_sub(requested.events_today);
+                tenant_usage.usage.storage_bytes = tenant_usage
+                    .usage
+                    .storage_bytes
+                    .saturating_sub(requested.storage_bytes);
+            }
+        }
+    }
+
+    fn requested_usage<'a, Evt>(
+        &self,
+        events: impl IntoIterator<Item = &'a event::Envelope<Evt>>,
+    ) -> Result<HashMap<String, Usage>, AppendError>
+    where
+        Evt: message::Message + Clone + 'a,
+        Serde: crate::serde::Serializer<Evt>,
+    {
+        let mut requested: HashMap<String, Usage> = HashMap::new();
+
+        for event in events {
+            let Some(tenant) = event.tenant_id() else {
+                continue;
+            };
+
+            let size = self.serde.serialize(event.message.clone())?.len() as u64;
+            let usage = requested.entry(tenant.to_owned()).or_default();
+
+            usage.events_today += 1;
+            usage.storage_bytes += size;
+        }
+
+        Ok(requested)
+    }
+}
+
+fn today() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs() / SECONDS_PER_DAY)
+}
+
+impl<S, Serde, StreamId, Evt> Streamer<StreamId, Evt> for Quota<S, Serde>
+where
+    S: Streamer<StreamId, Evt>,
+    Serde: Send + Sync,
+    StreamId: Send + Sync,
+    Evt: message::Message + Send + Sync,
+{
+    type Error = S::Error;
+
+    fn stream(
+        &self,
+        id: &StreamId,
+        select: event::VersionSelect,
+    ) -> event::Stream<'_, StreamId, Evt, Self::Error> {
+        self.store.stream(id, select)
+    }
+
+    fn stream_filtered<'a>(
+        &'a self,
+        id: &StreamId,
+        select: event::VersionSelect,
+        names: event::NameSelect,
+    ) -> event::Stream<'a, StreamId, Evt, Self::Error>
+    where
+        StreamId: 'a,
+        Evt: 'a,
+        Self::Error: 'a,
+    {
+        self.store.stream_filtered(id, select, names)
+    }
+}
+
+#[async_trait]
+impl<S, Serde, StreamId, Evt> Appender<StreamId, Evt> for Quota<S, Serde>
+where
+    S: Appender<StreamId, Evt>,
+    Serde: crate::serde::Serializer<Evt>,
+    StreamId: Send + Sync + 'static,
+    Evt: message::Message + Clone + Send + Sync + 'static,
+{
+    async fn append(
+        &self,
+        id: StreamId,
+        version_check: version::Check,
+        events: Vec<event::Envelope<Evt>>,
+    ) -> Result<Version, AppendError> {
+        let requested = self.requested_usage(&events)?;
+        self.reserve(&requested).map_err(anyhow::Error::from)?;
+
+        let result = self.store.append(id, version_check, events).await;
+
+        if result.is_err() {
+            self.release(&requested);
+        }
+
+        result
+    }
+
+    async fn append_multi(
+        &self,
+        appends: Vec<StreamAppend<StreamId, Evt>>,
+    ) -> Result<Vec<Version>, AppendError>
+    where
+        StreamId: 'async_trait,
+        Evt: 'async_trait,
+    {
+        let requested =
+            self.requested_usage(appends.iter().flat_map(|append| append.events.iter()))?;
+        self.reserve(&requested).map_err(anyhow::Error::from)?;
+
+        let result = self.store.append_multi(appends).await;
+
+        if result.is_err() {
+            self.release(&requested);
+        }
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::store::InMemory;
+    use crate::message::tests::StringMessage;
+
+    #[derive(Debug, Clone, Copy)]
+    struct StringSize;
+
+    impl crate::serde::Serializer<StringMessage> for StringSize {
+        fn serialize(&self, value: StringMessage) -> anyhow::Result<Vec<u8>> {
+            Ok(value.0.as_bytes().to_vec())
+        }
+    }
+
+    fn tenant_event(tenant: &str, message: &'static str) -> event::Envelope<StringMessage> {
+        event::Envelope::from(StringMessage(message)).with_tenant_id(tenant.to_owned())
+    }
+
+    #[tokio::test]
+    async fn quota_rejects_events_exceeding_the_daily_limit() {
+        let event_store = Quota::new(
+            InMemory::<&'static str, StringMessage>::default(),
+            StringSize,
+            Limits {
+                events_per_day: Some(2),
+                ..Limits::default()
+            },
+        );
+
+        event_store
+            .append(
+                "stream:a",
+                version::Check::Any,
+                vec![
+                    tenant_event("acme", "event-1"),
+                    tenant_event("acme", "event-2"),
+                ],
+            )
+            .await
+            .expect("append should not fail");
+
+        let error = event_store
+            .append(
+                "stream:a",
+                version::Check::Any,
+                vec![tenant_event("acme", "event-3")],
+            )
+            .await
+            .expect_err("append should exceed the quota");
+
+        let AppendError::Internal(error) = error else {
+            panic!("unexpected error: {error}");
+        };
+
+        assert_eq!(
+            Some(&QuotaExceededError::EventsPerDay {
+                tenant: "acme".to_owned(),
+                limit: 2,
+            }),
+            error.downcast_ref::<QuotaExceededError>()
+        );
+
+        // Other tenants, and events without a tenant, are not affected.
+        event_store
+            .append(
+                "stream:b",
+                version::Check::Any,
+                vec![
+                    tenant_event("globex", "event-1"),
+                    event::Envelope::from(StringMessage("event-2")),
+                ],
+            )
+            .await
+            .expect("append should not fail");
+
+        assert_eq!(2, event_store.usage("acme").events_today);
+        assert_eq!(1, event_store.usage("globex").events_today);
+    }
+
+    #[tokio::test]
+    async fn quota_tracks_the_storage_used_by_successful_appends() {
+        let event_store = Quota::new(
+            InMemory::<&'static str, StringMessage>::default(),
+            StringSize,
+            Limits::default(),
+        )
+        .with_tenant_limits(
+            "acme".to_owned(),
+            Limits {
+                storage_bytes: Some(10),
+                ..Limits::default()
+            },
+        );
+
+        event_store
+            .append(
+                "stream:a",
+                version::Check::MustBe(0),
+                vec![tenant_event("acme", "event-1")],
+            )
+            .await
+            .expect("append should not fail");
+
+        // Failed appends do not count towards the quota.
+        event_store
+            .append(
+                "stream:a",
+                version::Check::MustBe(0),
+                vec![tenant_event("acme", "123")],
+            )
+            .await
+            .expect_err("append should conflict");
+
+        assert_eq!(
+            Usage {
+                events_today: 1,
+                storage_bytes: 7,
+            },
+            event_store.usage("acme")
+        );
+
+        event_store
+            .append(
+                "stream:a",
+                version::Check::MustBe(1),
+                vec![tenant_event("acme", "event-2")],
+            )
+            .await
+            .expect_err("append should exceed the storage quota");
+    }
+}
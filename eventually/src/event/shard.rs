@@ -0,0 +1,556 @@
+//! Module `shard` contains the [`ShardedStore`], which partitions the Event Streams
+//! of very large deployments across multiple Event [Store]s.
+//!
+//! [Store]: crate::event::Store
+
+use std::collections::VecDeque;
+use std::hash::{Hash, Hasher};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures::stream::{self, BoxStream, StreamExt, TryStreamExt};
+
+use crate::event::fan_in;
+use crate::event::store::{AppendError, Appender, GlobalStreamer, StreamAppend, Streamer};
+use crate::version::Version;
+use crate::{event, message, version};
+
+/// FNV-1a hasher, used to route the Event Streams to their shard,
+/// since its output is stable across Rust releases and processes.
+struct Fnv1a(u64);
+
+impl Default for Fnv1a {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for Fnv1a {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+}
+
+/// [`event::Store`] combinator that routes every Event Stream to one of
+/// its underlying Event Stores (i.e. shards), by a hash of the Event Stream id.
+///
+/// Since the Event Streams are routed by the number of shards, adding or removing
+/// a shard moves most of the Event Streams to a different shard: they have to be
+/// copied over, using [`ShardedStore::shard_index`] to find their new shard,
+/// before the new [`ShardedStore`] is used.
+///
+/// Appending to Event Streams on different shards through [`Appender::append_multi`]
+/// is not atomic, and returns [`AppendError::Unsupported`].
+///
+/// The shards assign their [Sequence][event::Sequence] numbers independently,
+/// so [`ShardedStore`] does not implement [`GlobalStreamer`]: use
+/// [`ShardedStore::stream_all`] to stream all the Domain Events of all the shards.
+#[derive(Debug, Clone)]
+pub struct ShardedStore<S> {
+    shards: Vec<S>,
+}
+
+impl<S> ShardedStore<S> {
+    /// Creates a new [`ShardedStore`] routing the Event Streams to the specified shards.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no shard is specified.
+    #[must_use]
+    pub fn new(shards: Vec<S>) -> Self {
+        assert!(!shards.is_empty(), "at least one shard is required");
+        Self { shards }
+    }
+
+    /// Returns the index of the shard the specified Event Stream is routed to.
+    pub fn shard_index<StreamId>(&self, id: &StreamId) -> usize
+    where
+        StreamId: Hash,
+    {
+        let mut hasher = Fnv1a::default();
+        id.hash(&mut hasher);
+
+        #[allow(clippy::cast_possible_truncation)]
+        {
+            (hasher.finish() % self.shards.len() as u64) as usize
+        }
+    }
+
+    /// Returns the shard the specified Event Stream is routed to.
+    pub fn shard<StreamId>(&self, id: &StreamId) -> &S
+    where
+        StreamId: Hash,
+    {
+        &self.shards[self.shard_index(id)]
+    }
+
+    /// Returns the underlying shards.
+    #[must_use]
+    pub fn into_inner(self) -> Vec<S> {
+        self.shards
+    }
+}
+
+impl<S, StreamId, Evt> Streamer<StreamId, Evt> for ShardedStore<S>
+where
+    S: Streamer<StreamId, Evt>,
+    StreamId: Hash + Send + Sync,
+    Evt: message::Message + Send + Sync,
+{
+    type Error = S::Error;
+
+    fn stream(
+        &self,
+        id: &StreamId,
+        select: event::VersionSelect,
+    ) -> event::Stream<'_, StreamId, Evt, Self::Error> {
+        self.shard(id).stream(id, select)
+    }
+
+    fn stream_filtered<'a>(
+        &'a self,
+        id: &StreamId,
+        select: event::VersionSelect,
+        names: event::NameSelect,
+    ) -> event::Stream<'a, StreamId, Evt, Self::Error>
+    where
+        StreamId: 'a,
+        Evt: 'a,
+        Self::Error: 'a,
+    {
+        self.shard(id).stream_filtered(id, select, names)
+    }
+}
+
+/// The position of a Domain Event across all the shards of a [`ShardedStore`],
+/// since the [Sequence][event::Sequence] numbers assigned by the shards
+/// are not unique across shards.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Position {
+    /// The index of the shard the Domain Event has been appended to.
+    pub shard: usize,
+
+    /// The [Sequence][event::Sequence] number assigned to the Domain Event by its shard.
+    pub sequence: event::Sequence,
+}
+
+/// A [Persisted][event::Persisted] Domain Event, together with its [Position]
+/// across all the shards of a [`ShardedStore`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sharded<Id, Evt>
+where
+    Evt: message::Message,
+{
+    /// The position of the Domain Event across all the shards.
+    pub position: Position,
+
+    /// The Domain Event, as persisted in its Event Stream.
+    pub event: event::Persisted<Id, Evt>,
+}
+
+/// A stream of [Sharded] Domain Events, as returned by [`ShardedStore::stream_all`].
+pub type ShardedStream<'a, Id, Evt, Err> = BoxStream<'a, Result<Sharded<Id, Evt>, Err>>;
+
+type RecordedAtKeyed<StreamId, Evt> = (DateTime<Utc>, event::Sequenced<StreamId, Evt>);
+
+/// Pairs the Domain Events of a shard with the time they are merged at:
+/// their recorded time, or the one of the next Domain Event with a recorded time,
+/// never going backwards as required by [`fan_in::merge_by_key`].
+fn recorded_at_keys<'a, StreamId, Evt, Err>(
+    events: event::SequencedStream<'a, StreamId, Evt, Err>,
+) -> BoxStream<'a, Result<RecordedAtKeyed<StreamId, Evt>, Err>>
+where
+    StreamId: Send + 'a,
+    Evt: message::Message + Send + 'a,
+    Err: Send + 'a,
+{
+    let state = (events, VecDeque::new(), 0, DateTime::<Utc>::MIN_UTC);
+
+    stream::unfold(
+        state,
+        |(mut events, mut pending, mut resolved, mut recorded_at)| async move {
+            loop {
+                if resolved > 0 {
+                    resolved -= 1;
+                    let event = pending.pop_front()?;
+
+                    return Some((
+                        Ok((recorded_at, event)),
+                        (events, pending, resolved, recorded_at),
+                    ));
+                }
+
+                match events.try_next().await {
+                    Ok(Some(event)) => {
+                        if let Some(at) = event.event.recorded_at() {
+                            recorded_at = recorded_at.max(at);
+                            resolved = pending.len() + 1;
+                        }
+
+                        pending.push_back(event);
+                    },
+                    Ok(None) if pending.is_empty() => return None,
+                    Ok(None) => {
+                        recorded_at = DateTime::<Utc>::MAX_UTC;
+                        resolved = pending.len();
+                    },
+                    Err(err) => {
+                        return Some((Err(err), (events, pending, resolved, recorded_at)));
+                    },
+                }
+            }
+        },
+    )
+    .boxed()
+}
+
+impl<S> ShardedStore<S> {
+    /// Streams all the Domain Events of all the shards, interleaved by the time
+    /// they have been recorded, i.e. their [`RECORDED_AT_KEY`][event::RECORDED_AT_KEY]
+    /// metadata entry, or by shard if it is the same.
+    ///
+    /// Domain Events without a recorded time are interleaved right before
+    /// the next Domain Event of their shard with a recorded time, or after
+    /// all the other Domain Events if there is none.
+    ///
+    /// Since the [Sequence][event::Sequence] numbers are only unique within a shard,
+    /// consumers resume the stream with the [Position] of the last Domain Event
+    /// they have seen from each shard: the shards without a [Position]
+    /// are streamed from the start.
+    #[must_use]
+    pub fn stream_all<StreamId, Evt>(
+        &self,
+        after: &[Position],
+    ) -> ShardedStream<'_, StreamId, Evt, S::Error>
+    where
+        S: GlobalStreamer<StreamId, Evt>,
+        S::Error: Send + 'static,
+        StreamId: Send + Sync + 'static,
+        Evt: message::Message + Send + Sync + 'static,
+    {
+        let sources = self
+            .shards
+            .iter()
+            .enumerate()
+            .map(|(shard, events)| {
+                let select = after
+                    .iter()
+                    .filter(|position| position.shard == shard)
+                    .map(|position| position.sequence.saturating_add(1))
+                    .max()
+                    .map_or(event::SequenceSelect::All, event::SequenceSelect::From);
+
+                recorded_at_keys(events.stream_all(select))
+                    .map_ok(move |(recorded_at, event)| {
+                        let event = Sharded {
+                            position: Position {
+                                shard,
+                                sequence: event.sequence,
+                            },
+                            event: event.event,
+                        };
+
+                        (recorded_at, event)
+                    })
+                    .boxed()
+            })
+            .collect();
+
+        fan_in::merge_by_key(sources)
+    }
+}
+
+#[async_trait]
+impl<S, StreamId, Evt> Appender<StreamId, Evt> for ShardedStore<S>
+where
+    S: Appender<StreamId, Evt>,
+    StreamId: Hash + Send + Sync + 'static,
+    Evt: message::Message + Send + Sync + 'static,
+{
+    async fn append(
+        &self,
+        id: StreamId,
+        version_check: version::Check,
+        events: Vec<event::Envelope<Evt>>,
+    ) -> Result<Version, AppendError> {
+        self.shard(&id).append(id, version_check, events).await
+    }
+
+    async fn append_multi(
+        &self,
+        appends: Vec<StreamAppend<StreamId, Evt>>,
+    ) -> Result<Vec<Version>, AppendError>
+    where
+        StreamId: 'async_trait,
+        Evt: 'async_trait,
+    {
+        let Some(first) = appends.first() else {
+            return Ok(Vec::new());
+        };
+
+        let shard = self.shard_index(&first.id);
+
+        if appends
+            .iter()
+            .any(|append| self.shard_index(&append.id) != shard)
+        {
+            return Err(AppendError::Unsupported);
+        }
+
+        self.shards[shard].append_multi(appends).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::clock::TestClock;
+    use crate::event::store::InMemory;
+    use crate::message::tests::StringMessage;
+
+    const STREAM_IDS: [&str; 8] = [
+        "stream:a", "stream:b", "stream:c", "stream:d", "stream:e", "stream:f", "stream:g",
+        "stream:h",
+    ];
+
+    fn sharded_store(
+        shards: usize,
+        clock: &TestClock,
+    ) -> ShardedStore<InMemory<&'static str, StringMessage>> {
+        ShardedStore::new(
+            (0..shards)
+                .map(|_| InMemory::default().with_clock(clock.clone()))
+                .collect(),
+        )
+    }
+
+    #[tokio::test]
+    async fn sharded_store_routes_event_streams_and_interleaves_them_by_time() {
+        let clock = TestClock::default();
+        let event_store = sharded_store(3, &clock);
+
+        for stream_id in STREAM_IDS {
+            clock.advance(chrono::Duration::seconds(1));
+
+            event_store
+                .append(
+                    stream_id,
+                    version::Check::MustBe(0),
+                    vec![event::Envelope::from(StringMessage("event")); 2],
+                )
+                .await
+                .expect("append should not fail");
+        }
+
+        let used_shards: HashSet<_> = STREAM_IDS
+            .iter()
+            .map(|stream_id| event_store.shard_index(stream_id))
+            .collect();
+        assert!(used_shards.len() > 1, "event streams should be spread");
+
+        let events: Vec<_> = event_store
+            .stream(&"stream:c", event::VersionSelect::All)
+            .map_ok(|event| event.version)
+            .try_collect()
+            .await
+            .expect("stream should not fail");
+        assert_eq!(vec![1, 2], events);
+
+        let stream_ids: Vec<_> = event_store
+            .stream_all(&[])
+            .map_ok(|event| event.event.stream_id)
+            .try_collect()
+            .await
+            .expect("global stream should not fail");
+
+        let expected: Vec<_> = STREAM_IDS
+            .iter()
+            .flat_map(|stream_id| [*stream_id, *stream_id])
+            .collect();
+        assert_eq!(expected, stream_ids);
+    }
+
+    #[tokio::test]
+    async fn sharded_store_resumes_each_shard_from_its_position() {
+        let clock = TestClock::default();
+        let event_store = sharded_store(3, &clock);
+
+        for stream_id in STREAM_IDS {
+            clock.advance(chrono::Duration::seconds(1));
+
+            event_store
+                .append(
+                    stream_id,
+                    version::Check::MustBe(0),
+                    vec![event::Envelope::from(StringMessage("event"))],
+                )
+                .await
+                .expect("append should not fail");
+        }
+
+        let events: Vec<_> = event_store
+            .stream_all(&[])
+            .try_collect()
+            .await
+            .expect("global stream should not fail");
+
+        // Positions of the last Domain Event seen from each shard,
+        // after consuming the first half of the merged stream.
+        let mut positions: Vec<Position> = Vec::new();
+        for event in &events[..STREAM_IDS.len() / 2] {
+            positions.retain(|position| position.shard != event.position.shard);
+            positions.push(event.position);
+        }
+
+        let resumed: Vec<_> = event_store
+            .stream_all(&positions)
+            .try_collect()
+            .await
+            .expect("global stream should not fail");
+
+        assert_eq!(&events[STREAM_IDS.len() / 2..], resumed.as_slice());
+    }
+
+    /// Event Store yielding a fixed list of Domain Events, with
+    /// or without a recorded time, from the Unix epoch in seconds.
+    struct Recorded(Vec<(&'static str, Option<i64>)>);
+
+    impl GlobalStreamer<&'static str, StringMessage> for Recorded {
+        type Error = std::convert::Infallible;
+
+        fn stream_all(
+            &self,
+            _select: event::SequenceSelect,
+        ) -> event::SequencedStream<'_, &'static str, StringMessage, Self::Error> {
+            stream::iter(
+                self.0
+                    .iter()
+                    .zip(1..)
+                    .map(|(&(stream_id, recorded_at), sequence)| {
+                        let mut event = event::Envelope::from(StringMessage(stream_id));
+
+                        if let Some(recorded_at) = recorded_at {
+                            event = event.with_metadata(
+                                event::RECORDED_AT_KEY.to_owned(),
+                                DateTime::from_timestamp(recorded_at, 0)
+                                    .unwrap()
+                                    .to_rfc3339(),
+                            );
+                        }
+
+                        Ok(event::Sequenced {
+                            sequence,
+                            event: event::Persisted {
+                                stream_id,
+                                version: 1,
+                                event,
+                            },
+                        })
+                    }),
+            )
+            .boxed()
+        }
+    }
+
+    #[tokio::test]
+    async fn sharded_store_interleaves_events_without_recorded_time_before_the_next_one() {
+        let event_store = ShardedStore::new(vec![
+            Recorded(vec![("b", None), ("c", Some(3)), ("f", None)]),
+            Recorded(vec![("a", Some(2)), ("d", Some(4)), ("e", Some(5))]),
+        ]);
+
+        let stream_ids: Vec<_> = event_store
+            .stream_all(&[])
+            .map_ok(|event| event.event.stream_id)
+            .try_collect()
+            .await
+            .expect("global stream should not fail");
+
+        assert_eq!(vec!["a", "b", "c", "d", "e", "f"], stream_ids);
+    }
+
+    #[tokio::test]
+    async fn sharded_store_rejects_non_atomic_multi_stream_appends() {
+        let clock = TestClock::default();
+        let event_store = sharded_store(2, &clock);
+
+        let (a, b) = STREAM_IDS
+            .iter()
+            .flat_map(|a| STREAM_IDS.iter().map(move |b| (*a, *b)))
+            .find(|(a, b)| event_store.shard_index(a) != event_store.shard_index(b))
+            .expect("event streams should be spread");
+
+        let result = event_store
+            .append_multi(
+                [a, b]
+                    .into_iter()
+                    .map(|id| StreamAppend {
+                        id,
+                        version_check: version::Check::MustBe(0),
+                        events: vec![event::Envelope::from(StringMessage("event"))],
+                    })
+                    .collect(),
+            )
+            .await;
+
+        assert!(matches!(result, Err(AppendError::Unsupported)));
+    }
+
+    #[tokio::test]
+    async fn rebalancing_copies_the_event_streams_to_their_new_shard() {
+        let clock = TestClock::default();
+        let old_store = sharded_store(2, &clock);
+        let new_store = sharded_store(3, &clock);
+
+        for stream_id in STREAM_IDS {
+            old_store
+                .append(
+                    stream_id,
+                    version::Check::MustBe(0),
+                    vec![event::Envelope::from(StringMessage(stream_id))],
+                )
+                .await
+                .expect("append should not fail");
+        }
+
+        // Adding a shard moves some of the Event Streams to a different shard.
+        assert!(STREAM_IDS
+            .iter()
+            .any(|id| old_store.shard_index(id) != new_store.shard_index(id)));
+
+        for stream_id in STREAM_IDS {
+            let events: Vec<_> = old_store
+                .stream(&stream_id, event::VersionSelect::All)
+                .map_ok(|event| event.event)
+                .try_collect()
+                .await
+                .expect("stream should not fail");
+
+            new_store
+                .append(stream_id, version::Check::MustBe(0), events)
+                .await
+                .expect("append should not fail");
+        }
+
+        for stream_id in STREAM_IDS {
+            let messages: Vec<_> = new_store
+                .shard(&stream_id)
+                .stream(&stream_id, event::VersionSelect::All)
+                .map_ok(|event| event.event.message)
+                .try_collect()
+                .await
+                .expect("stream should not fail");
+
+            assert_eq!(vec![StringMessage(stream_id)], messages);
+        }
+    }
+}
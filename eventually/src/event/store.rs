@@ -1,14 +1,19 @@
 //! Contains implementations of the [`event::Store`] trait and connected abstractions,
 //! such as the [`std::collections::HashMap`]'s based [`InMemory`] Event Store implementation.
 
-use std::collections::HashMap;
-use std::convert::Infallible;
+use std::collections::{HashMap, VecDeque};
 use std::hash::Hash;
-use std::sync::{Arc, RwLock};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
 
 use async_trait::async_trait;
-use futures::stream::{iter, StreamExt};
+use chrono::{DateTime, Utc};
+use futures::future::{ready, BoxFuture, FutureExt};
+use futures::stream::{self, iter, once, StreamExt, TryStreamExt};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
 
+use crate::clock::{Clock, SystemClock};
+use crate::subscription::{LaggedError, Subscriber};
 use crate::{event, message, version};
 
 /// Interface used to stream [Persisted][event::Persisted] Domain Events
@@ -27,21 +32,168 @@ where
         &self,
         id: &StreamId,
         select: event::VersionSelect,
-    ) -> event::Stream<StreamId, Event, Self::Error>;
+    ) -> event::Stream<'_, StreamId, Event, Self::Error>;
+
+    /// Opens an Event Stream like [`Streamer::stream`], selecting only the Domain Events
+    /// whose [name][message::Message::name] matches the specified [`event::NameSelect`].
+    ///
+    /// The default implementation filters the Domain Events after they have been
+    /// streamed: Event Store implementations should push the filter down to their backend.
+    fn stream_filtered<'a>(
+        &'a self,
+        id: &StreamId,
+        select: event::VersionSelect,
+        names: event::NameSelect,
+    ) -> event::Stream<'a, StreamId, Event, Self::Error>
+    where
+        StreamId: 'a,
+        Event: 'a,
+        Self::Error: 'a,
+    {
+        self.stream(id, select)
+            .try_filter(move |event| ready(names.matches(event.event.message.name())))
+            .boxed()
+    }
+}
+
+/// Interface used to stream all the Domain Events in an Event Store,
+/// across all Event Streams, in the order they have been appended.
+///
+/// Implementations must assign [Sequence][event::Sequence] numbers in commit order:
+/// once a Domain Event can be streamed, no Domain Event with a lower
+/// [Sequence][event::Sequence] number can be appended anymore, so that consumers
+/// can resume from the last [Sequence][event::Sequence] number they have seen.
+pub trait GlobalStreamer<StreamId, Event>: Send + Sync
+where
+    StreamId: Send + Sync,
+    Event: message::Message + Send + Sync,
+{
+    /// The error type returned by the Store during a [`stream_all`] call.
+    type Error: Send + Sync;
+
+    /// Streams all the Domain Events in the Event Store, ordered by
+    /// their [Sequence][event::Sequence] number.
+    fn stream_all(
+        &self,
+        select: event::SequenceSelect,
+    ) -> event::SequencedStream<'_, StreamId, Event, Self::Error>;
+
+    /// Streams all the Domain Events in the Event Store like [`GlobalStreamer::stream_all`],
+    /// selecting only the ones whose [name][message::Message::name] matches
+    /// the specified [`event::NameSelect`].
+    ///
+    /// The default implementation filters the Domain Events after they have been
+    /// streamed: Event Store implementations should push the filter down to their backend.
+    fn stream_all_filtered<'a>(
+        &'a self,
+        select: event::SequenceSelect,
+        names: event::NameSelect,
+    ) -> event::SequencedStream<'a, StreamId, Event, Self::Error>
+    where
+        StreamId: 'a,
+        Event: 'a,
+        Self::Error: 'a,
+    {
+        self.stream_all(select)
+            .try_filter(move |event| ready(names.matches(event.event.event.message.name())))
+            .boxed()
+    }
+}
+
+impl<T, StreamId, Event> GlobalStreamer<StreamId, Event> for Arc<T>
+where
+    T: GlobalStreamer<StreamId, Event> + ?Sized,
+    StreamId: Send + Sync,
+    Event: message::Message + Send + Sync,
+{
+    type Error = T::Error;
+
+    fn stream_all(
+        &self,
+        select: event::SequenceSelect,
+    ) -> event::SequencedStream<'_, StreamId, Event, Self::Error> {
+        (**self).stream_all(select)
+    }
+
+    fn stream_all_filtered<'a>(
+        &'a self,
+        select: event::SequenceSelect,
+        names: event::NameSelect,
+    ) -> event::SequencedStream<'a, StreamId, Event, Self::Error>
+    where
+        StreamId: 'a,
+        Event: 'a,
+        Self::Error: 'a,
+    {
+        (**self).stream_all_filtered(select, names)
+    }
+}
+
+/// Interface used to stream all the Domain Events of the Event Streams of the same type
+/// (also known as category streams), e.g. all the Domain Events of the
+/// [Aggregate][crate::aggregate::Aggregate] type with the specified
+/// [`type_name`][crate::aggregate::Aggregate::type_name].
+pub trait TypeStreamer<StreamId, Event>: Send + Sync
+where
+    StreamId: Send + Sync,
+    Event: message::Message + Send + Sync,
+{
+    /// The error type returned by the Store during a [`stream_by_type`] call.
+    type Error: Send + Sync;
+
+    /// Streams all the Domain Events of the Event Streams with the specified type,
+    /// ordered by their [Sequence][event::Sequence] number.
+    fn stream_by_type(
+        &self,
+        type_name: &str,
+        select: event::SequenceSelect,
+    ) -> event::SequencedStream<'_, StreamId, Event, Self::Error>;
+}
+
+/// Interface used to read the [Sequence][event::Sequence] number of the last
+/// Domain Event appended to an Event Store (i.e. its head), e.g. to monitor
+/// how far behind a [Projection][crate::projection::Projection] is.
+#[async_trait]
+pub trait HeadSequence: Send + Sync {
+    /// The error type returned by the Store during a [`head_sequence`] call.
+    type Error: Send + Sync;
+
+    /// Returns the [Sequence][event::Sequence] number of the last Domain Event
+    /// appended to the Event Store, or nothing if the Event Store is empty.
+    async fn head_sequence(&self) -> Result<Option<event::Sequence>, Self::Error>;
 }
 
 /// All possible error types returned by [`Appender::append`].
 #[derive(Debug, thiserror::Error)]
 pub enum AppendError {
-    /// Error returned when [Appender::append] encounters a conflict error
+    /// Error returned when [`Appender::append`] encounters a conflict error
     /// while appending the new Domain Events.
     #[error("failed to append new domain events: {0}")]
     Conflict(#[from] version::ConflictError),
+    /// Error returned when the [Appender] implementation does not support
+    /// the requested operation, e.g. [`Appender::append_multi`].
+    #[error("failed to append new domain events: operation not supported by the event store")]
+    Unsupported,
     /// Error returned when the [Appender] implementation has encountered an error.
     #[error("failed to append new domain events, an error occurred: {0}")]
     Internal(#[from] anyhow::Error),
 }
 
+/// A single append operation on an Event Stream, used by [`Appender::append_multi`]
+/// to append Domain Events to multiple Event Streams atomically.
+#[derive(Debug, Clone)]
+pub struct StreamAppend<StreamId, Event>
+where
+    Event: message::Message,
+{
+    /// The id of the Event Stream to append the Domain Events to.
+    pub id: StreamId,
+    /// The optimistic locking check to perform on the Event Stream.
+    pub version_check: version::Check,
+    /// The Domain Events to append to the Event Stream.
+    pub events: Vec<event::Envelope<Event>>,
+}
+
 #[async_trait]
 /// Interface used to append new Domain Events in an Event Store.
 pub trait Appender<StreamId, Event>: Send + Sync
@@ -59,6 +211,26 @@ where
         version_check: version::Check,
         events: Vec<event::Envelope<Event>>,
     ) -> Result<version::Version, AppendError>;
+
+    /// Appends new Domain Events to multiple Event Streams atomically:
+    /// either all the [`StreamAppend`] operations succeed, or none of them is applied.
+    ///
+    /// The result of this operation is the list of new [Version][version::Version]s
+    /// of the Event Streams, in the same order of the specified operations.
+    ///
+    /// Event Store implementations that cannot guarantee atomicity across
+    /// multiple Event Streams return [`AppendError::Unsupported`], which is the default.
+    async fn append_multi(
+        &self,
+        appends: Vec<StreamAppend<StreamId, Event>>,
+    ) -> Result<Vec<version::Version>, AppendError>
+    where
+        StreamId: 'async_trait,
+        Event: 'async_trait,
+    {
+        let _ = appends;
+        Err(AppendError::Unsupported)
+    }
 }
 
 /// An [Event][event::Envelope] Store, used to store Domain Events in Event Streams -- a stream
@@ -81,97 +253,450 @@ where
 {
 }
 
+/// All possible error types returned by [`Locker`].
+#[derive(Debug, thiserror::Error)]
+pub enum LockError {
+    /// Error returned when the [`Locker`] implementation has encountered an error.
+    #[error("failed to lock event stream, an error occurred: {0}")]
+    Internal(#[from] anyhow::Error),
+}
+
+/// Interface used to acquire an exclusive lock on an Event Stream, for workflows
+/// where optimistic concurrency and conflict-retry loops are not acceptable.
+///
+/// Locks are advisory: they only exclude the other callers of [`Locker::lock`],
+/// while appending to a locked Event Stream is still allowed and subject
+/// to the usual [version checks][version::Check].
+#[async_trait]
+pub trait Locker<StreamId>: Send + Sync
+where
+    StreamId: Send + Sync,
+{
+    /// The lock held on the Event Stream, which is released when dropped.
+    type Lock: Send;
+
+    /// Acquires an exclusive lock on the specified Event Stream,
+    /// waiting until the lock is released if it is currently held.
+    async fn lock(&self, id: &StreamId) -> Result<Self::Lock, LockError>;
+}
+
+/// All possible error types returned by [`StreamDeleter`].
+#[derive(Debug, thiserror::Error)]
+pub enum DeleteError {
+    /// Error returned when the [`StreamDeleter`] implementation has encountered an error.
+    #[error("failed to delete domain events, an error occurred: {0}")]
+    Internal(#[from] anyhow::Error),
+}
+
+/// Interface used to remove Domain Events from an Event Store,
+/// e.g. to comply with data erasure requests.
+#[async_trait]
+pub trait StreamDeleter<StreamId, Event>: Send + Sync
+where
+    StreamId: Send + Sync,
+    Event: message::Message + Send + Sync,
+{
+    /// Deletes the specified Event Stream and all of its Domain Events.
+    ///
+    /// Deleting an Event Stream that does not exist is not an error.
+    async fn delete(&self, id: &StreamId) -> Result<(), DeleteError>;
+
+    /// Removes all the Domain Events of the specified Event Stream
+    /// with a [Version][version::Version] lower than `before_version`.
+    ///
+    /// The [Version][version::Version] of the Event Stream is not affected,
+    /// so new Domain Events can still be appended with the same optimistic
+    /// locking checks.
+    async fn truncate(
+        &self,
+        id: &StreamId,
+        before_version: version::Version,
+    ) -> Result<(), DeleteError>;
+}
+
+/// All possible error types returned by [`Tombstoner::tombstone`].
+#[derive(Debug, thiserror::Error)]
+pub enum TombstoneError {
+    /// Error returned when the Event Stream is not at the expected [Version][version::Version].
+    #[error("failed to tombstone event stream: {0}")]
+    Conflict(#[from] version::ConflictError),
+    /// Error returned when the [`Tombstoner`] implementation has encountered an error.
+    #[error("failed to tombstone event stream, an error occurred: {0}")]
+    Internal(#[from] anyhow::Error),
+}
+
+/// Error returned, as [`AppendError::Internal`], when appending new Domain Events
+/// to an Event Stream that has been marked as deleted through [`Tombstoner::tombstone`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("the event stream has been deleted")]
+pub struct StreamTombstonedError;
+
+/// Interface used to mark Event Streams as deleted, e.g. when the Aggregate Root
+/// they belong to has been deleted, without removing their Domain Events.
+///
+/// A tombstoned Event Stream is streamed as empty by [`Streamer::stream`],
+/// and rejects any new Domain Event with a [`StreamTombstonedError`].
+/// Its Domain Events are still returned by [`GlobalStreamer::stream_all`],
+/// so that the projections built on top of it are not affected.
+#[async_trait]
+pub trait Tombstoner<StreamId>: Send + Sync
+where
+    StreamId: Send + Sync,
+{
+    /// Marks the specified Event Stream as deleted, if it is at the expected
+    /// [Version][version::Version].
+    ///
+    /// Tombstoning an Event Stream twice, or one that does not exist, is not an error.
+    async fn tombstone(
+        &self,
+        id: &StreamId,
+        version_check: version::Check,
+    ) -> Result<(), TombstoneError>;
+}
+
+/// Behavior of the [`InMemory`] Event Store when appending new Domain Events
+/// would exceed its configured [Capacity].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// The append operation fails with a [`CapacityExceededError`].
+    #[default]
+    Reject,
+    /// The oldest Domain Events are evicted to make room for the new ones.
+    ///
+    /// When the `tracing` feature is enabled, a warning is logged on every eviction.
+    EvictOldest,
+}
+
+/// Optional limits on the number of Domain Events kept by the [`InMemory`] Event Store,
+/// useful to avoid running out of memory in long-running tests and simulations.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Capacity {
+    /// Maximum number of Domain Events kept for each Event Stream.
+    pub per_stream: Option<usize>,
+    /// Maximum number of Domain Events kept across all Event Streams.
+    pub global: Option<usize>,
+    /// What to do when appending new Domain Events would exceed the capacity.
+    pub on_overflow: OverflowPolicy,
+}
+
+/// Error returned by the [`InMemory`] Event Store, wrapped in [`AppendError::Internal`],
+/// when appending new Domain Events would exceed its [Capacity]
+/// and the [`OverflowPolicy::Reject`] policy is used.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum CapacityExceededError {
+    /// The capacity of the Event Stream would be exceeded.
+    #[error("event stream capacity of {0} domain events would be exceeded")]
+    PerStream(usize),
+    /// The capacity of the whole Event Store would be exceeded.
+    #[error("event store capacity of {0} domain events would be exceeded")]
+    Global(usize),
+}
+
+/// Counters of the [`InMemory`] Event Store, useful to inspect how its [Capacity]
+/// is used in long-running tests and simulations.
+///
+/// Counters are cumulative since the creation of the Event Store.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct InMemoryMetrics {
+    /// Number of Domain Events currently kept in the Event Store.
+    pub len: usize,
+    /// Number of Event Streams currently kept in the Event Store.
+    pub streams: usize,
+    /// Number of Domain Events evicted with the [`OverflowPolicy::EvictOldest`] policy.
+    pub evicted: u64,
+    /// Number of append operations rejected with the [`OverflowPolicy::Reject`] policy.
+    pub rejected: u64,
+    /// Number of evicted Domain Events appended to the archive Event Store,
+    /// set through [`InMemory::with_archive`].
+    pub archived: u64,
+    /// Number of evicted Domain Events that could not be appended
+    /// to the archive Event Store, and have been lost.
+    pub archive_failures: u64,
+}
+
+/// Snapshot of the whole content of an [`InMemory`] Event Store, returned by
+/// [`InMemory::export`] and loaded by [`InMemory::import`].
+///
+/// It can be serialized and stored as a test fixture, so that tests can start
+/// from a realistic history of Domain Events without replaying the Commands
+/// that produced it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InMemorySnapshot<Id, Evt>
+where
+    Evt: message::Message,
+{
+    /// The [Sequence][event::Sequence] number of the last Domain Event
+    /// appended to the Event Store.
+    pub sequence: event::Sequence,
+    /// The Event Streams in the Event Store.
+    pub event_streams: Vec<InMemoryStreamSnapshot<Id, Evt>>,
+}
+
+/// Snapshot of a single Event Stream of an [`InMemory`] Event Store,
+/// part of an [`InMemorySnapshot`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InMemoryStreamSnapshot<Id, Evt>
+where
+    Evt: message::Message,
+{
+    /// The id of the Event Stream.
+    pub id: Id,
+    /// The [Version][version::Version] of the Event Stream, which might be greater
+    /// than the one of its last Domain Event if they have been removed.
+    pub version: version::Version,
+    /// Whether the Event Stream has been [tombstoned][Tombstoner].
+    pub tombstoned: bool,
+    /// The Domain Events kept in the Event Stream.
+    pub events: Vec<event::Sequenced<Id, Evt>>,
+}
+
+/// Error returned by the in-memory implementations, such as the [`InMemory`] Event Store,
+/// when a thread panicked while holding the lock on their data,
+/// which might have been left in an inconsistent state.
+///
+/// It is the error type of the in-memory [`Streamer`] and [`GlobalStreamer`]
+/// implementations, which used to be [`std::convert::Infallible`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("in-memory storage has been poisoned by a panicking thread")]
+pub struct PoisonedError;
+
 #[derive(Debug)]
-struct InMemoryBackend<Id, Evt>
+struct InMemoryEventStream<Id, Evt>
 where
     Evt: message::Message,
 {
-    event_streams: HashMap<Id, Vec<event::Persisted<Id, Evt>>>,
+    version: version::Version,
+    events: VecDeque<event::Sequenced<Id, Evt>>,
+    tombstoned: bool,
 }
 
-impl<Id, Evt> Default for InMemoryBackend<Id, Evt>
+impl<Id, Evt> Default for InMemoryEventStream<Id, Evt>
 where
     Evt: message::Message,
 {
     fn default() -> Self {
         Self {
-            event_streams: HashMap::default(),
+            version: 0,
+            events: VecDeque::default(),
+            tombstoned: false,
         }
     }
 }
 
-/// In-memory implementation of [`event::Store`] trait,
-/// backed by a thread-safe [`std::collections::HashMap`].
-#[derive(Debug, Clone)]
-pub struct InMemory<Id, Evt>
+impl<Id, Evt> InMemoryEventStream<Id, Evt>
 where
     Evt: message::Message,
 {
-    backend: Arc<RwLock<InMemoryBackend<Id, Evt>>>,
+    fn contains(&self, version: version::Version) -> bool {
+        self.events
+            .front()
+            .is_some_and(|event| event.event.version <= version && version <= self.version)
+    }
 }
 
-impl<Id, Evt> Default for InMemory<Id, Evt>
+#[derive(Debug)]
+struct InMemoryBackend<Id, Evt>
+where
+    Evt: message::Message,
+{
+    event_streams: HashMap<Id, InMemoryEventStream<Id, Evt>>,
+    // Sequence number of the last Domain Event appended to the Event Store.
+    sequence: event::Sequence,
+    capacity: Capacity,
+    // Number of Domain Events currently kept in all the Event Streams.
+    len: usize,
+    // Insertion order of the Domain Events, used for global eviction.
+    // It might contain entries of Domain Events already removed, which are skipped.
+    log: VecDeque<(Id, version::Version)>,
+    metrics: InMemoryMetrics,
+    // Evicted Domain Events waiting to be archived, only kept when an archive is set.
+    keep_evicted: bool,
+    evicted: Vec<event::Persisted<Id, Evt>>,
+    // Appended Domain Events waiting to be delivered, only kept when there are subscribers.
+    keep_appended: bool,
+    appended: Vec<event::Sequenced<Id, Evt>>,
+}
+
+impl<Id, Evt> Default for InMemoryBackend<Id, Evt>
 where
     Evt: message::Message,
 {
     fn default() -> Self {
         Self {
-            backend: Arc::default(),
+            event_streams: HashMap::default(),
+            sequence: 0,
+            capacity: Capacity::default(),
+            len: 0,
+            log: VecDeque::default(),
+            metrics: InMemoryMetrics::default(),
+            keep_evicted: false,
+            evicted: Vec::default(),
+            keep_appended: false,
+            appended: Vec::default(),
         }
     }
 }
 
-impl<Id, Evt> Streamer<Id, Evt> for InMemory<Id, Evt>
+impl<Id, Evt> InMemoryBackend<Id, Evt>
 where
-    Id: Clone + Eq + Hash + Send + Sync,
-    Evt: message::Message + Clone + Send + Sync,
+    Id: Clone + Eq + Hash,
+    Evt: message::Message,
 {
-    type Error = Infallible;
+    fn last_version(&self, id: &Id) -> version::Version {
+        self.event_streams
+            .get(id)
+            .map(|event_stream| event_stream.version)
+            .unwrap_or_default()
+    }
 
-    fn stream(&self, id: &Id, select: event::VersionSelect) -> event::Stream<Id, Evt, Self::Error> {
-        let backend = self
-            .backend
-            .read()
-            .expect("acquire read lock on event store backend");
+    fn is_tombstoned(&self, id: &Id) -> bool {
+        self.event_streams
+            .get(id)
+            .is_some_and(|event_stream| event_stream.tombstoned)
+    }
 
-        let events = backend
-            .event_streams
+    fn stream_len(&self, id: &Id) -> usize {
+        self.event_streams
             .get(id)
-            .cloned()
-            .unwrap_or_default() // NOTE: the new Vec is empty, so there will be no memory allocation!
-            .into_iter()
-            .filter(move |evt| match select {
-                event::VersionSelect::All => true,
-                event::VersionSelect::From(v) => evt.version >= v,
-            });
+            .map_or(0, |event_stream| event_stream.events.len())
+    }
 
-        iter(events).map(Ok).boxed()
+    fn check_capacity<'a>(
+        &mut self,
+        appends: impl IntoIterator<Item = (&'a Id, usize)>,
+    ) -> Result<(), CapacityExceededError>
+    where
+        Id: 'a,
+    {
+        if self.capacity.on_overflow != OverflowPolicy::Reject {
+            return Ok(());
+        }
+
+        let result = self.check_reject_capacity(appends);
+
+        if result.is_err() {
+            self.metrics.rejected += 1;
+        }
+
+        result
     }
-}
 
-#[async_trait]
-impl<Id, Evt> Appender<Id, Evt> for InMemory<Id, Evt>
-where
-    Id: Clone + Eq + Hash + Send + Sync,
-    Evt: message::Message + Clone + Send + Sync,
-{
-    async fn append(
+    fn check_reject_capacity<'a>(
         &self,
+        appends: impl IntoIterator<Item = (&'a Id, usize)>,
+    ) -> Result<(), CapacityExceededError>
+    where
+        Id: 'a,
+    {
+        let mut added_per_stream: HashMap<&Id, usize> = HashMap::new();
+        let mut added = 0;
+
+        for (id, len) in appends {
+            let stream_added = added_per_stream.entry(id).or_default();
+            *stream_added += len;
+            added += len;
+
+            if let Some(capacity) = self.capacity.per_stream {
+                if self.stream_len(id) + *stream_added > capacity {
+                    return Err(CapacityExceededError::PerStream(capacity));
+                }
+            }
+        }
+
+        if let Some(capacity) = self.capacity.global {
+            if self.len + added > capacity {
+                return Err(CapacityExceededError::Global(capacity));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn evict(&mut self, id: &Id) -> usize {
+        let mut evicted = Vec::new();
+
+        if let Some(capacity) = self.capacity.per_stream {
+            if let Some(event_stream) = self.event_streams.get_mut(id) {
+                let excess = event_stream.events.len().saturating_sub(capacity);
+                evicted.extend(event_stream.events.drain(..excess));
+            }
+        }
+
+        self.len -= evicted.len();
+
+        if let Some(capacity) = self.capacity.global {
+            while self.len > capacity {
+                let Some((id, version)) = self.log.pop_front() else {
+                    break;
+                };
+
+                if let Some(event_stream) = self
+                    .event_streams
+                    .get_mut(&id)
+                    .filter(|event_stream| event_stream.contains(version))
+                {
+                    evicted.extend(event_stream.events.pop_front());
+                    self.len -= 1;
+                }
+            }
+
+            // Drop the entries of the Domain Events removed by other means,
+            // to keep the log from growing unbounded.
+            if self.log.len() > 2 * self.len {
+                let event_streams = &self.event_streams;
+
+                self.log.retain(|(id, version)| {
+                    event_streams
+                        .get(id)
+                        .is_some_and(|event_stream| event_stream.contains(*version))
+                });
+            }
+        }
+
+        let evicted_len = evicted.len();
+        self.metrics.evicted += evicted_len as u64;
+
+        if self.keep_evicted {
+            self.evicted
+                .extend(evicted.into_iter().map(|event| event.event));
+        }
+
+        evicted_len
+    }
+
+    fn remove(&mut self, id: &Id) {
+        if let Some(event_stream) = self.event_streams.remove(id) {
+            self.len -= event_stream.events.len();
+        }
+    }
+
+    fn truncate(&mut self, id: &Id, before_version: version::Version) {
+        if let Some(event_stream) = self.event_streams.get_mut(id) {
+            let previous_len = event_stream.events.len();
+
+            event_stream
+                .events
+                .retain(|event| event.event.version >= before_version);
+
+            self.len -= previous_len - event_stream.events.len();
+        }
+    }
+
+    fn append(
+        &mut self,
         id: Id,
         version_check: version::Check,
         events: Vec<event::Envelope<Evt>>,
-    ) -> Result<version::Version, AppendError> {
-        let mut backend = self
-            .backend
-            .write()
-            .expect("acquire write lock on event store backend");
+        recorded_at: DateTime<Utc>,
+    ) -> Result<version::Version, AppendError>
+    where
+        Evt: Clone,
+    {
+        if self.is_tombstoned(&id) {
+            return Err(AppendError::Internal(StreamTombstonedError.into()));
+        }
 
-        let last_event_stream_version = backend
-            .event_streams
-            .get(&id)
-            .and_then(|events| events.last())
-            .map(|event| event.version)
-            .unwrap_or_default();
+        let last_event_stream_version = self.last_version(&id);
 
         if let version::Check::MustBe(expected) = version_check {
             if last_event_stream_version != expected {
@@ -182,245 +707,1375 @@ where
             }
         }
 
-        let mut persisted_events: Vec<event::Persisted<Id, Evt>> = events
+        self.check_capacity([(&id, events.len())])
+            .map_err(anyhow::Error::from)?;
+
+        let first_sequence = self.sequence;
+        let persisted_events: Vec<event::Sequenced<Id, Evt>> = events
             .into_iter()
             .enumerate()
-            .map(|(i, event)| event::Persisted {
-                stream_id: id.clone(),
-                version: last_event_stream_version + (i as u64) + 1,
-                event,
+            .map(|(i, event)| event::Sequenced {
+                sequence: first_sequence + (i as u64) + 1,
+                event: event::Persisted {
+                    stream_id: id.clone(),
+                    version: last_event_stream_version + (i as u64) + 1,
+                    event: event
+                        .with_metadata(event::RECORDED_AT_KEY.to_owned(), recorded_at.to_rfc3339()),
+                },
             })
             .collect();
 
-        let new_last_event_stream_version = persisted_events
-            .last()
-            .map(|evt| evt.version)
-            .unwrap_or_default();
+        let new_last_event_stream_version =
+            last_event_stream_version + (persisted_events.len() as version::Version);
 
-        backend
-            .event_streams
-            .entry(id)
-            .and_modify(|events| events.append(&mut persisted_events))
-            .or_insert_with(|| persisted_events);
+        if self.capacity.global.is_some() {
+            self.log.extend(
+                persisted_events
+                    .iter()
+                    .map(|event| (id.clone(), event.event.version)),
+            );
+        }
+
+        self.sequence += persisted_events.len() as event::Sequence;
+        self.len += persisted_events.len();
+
+        if self.keep_appended {
+            self.appended.extend(persisted_events.iter().cloned());
+        }
+
+        let stream_id = id.clone();
+        let event_stream = self.event_streams.entry(id).or_default();
+        event_stream.version = new_last_event_stream_version;
+        event_stream.events.extend(persisted_events);
+
+        let evicted = self.evict(&stream_id);
+
+        #[cfg(feature = "tracing")]
+        if evicted > 0 {
+            tracing::warn!(
+                evicted,
+                "in-memory event store capacity exceeded, evicted the oldest domain events"
+            );
+        }
+
+        #[cfg(not(feature = "tracing"))]
+        let _ = evicted;
 
         Ok(new_last_event_stream_version)
     }
 }
 
-/// Decorator type for an [`event::Store`] implementation that tracks the list of
-/// recorded Domain Events through it.
+/// In-memory implementation of [`event::Store`] trait,
+/// backed by a thread-safe [`std::collections::HashMap`].
 ///
-/// Useful for testing purposes, i.e. asserting that Domain Events written throguh
-/// this Event Store instance are the ones expected.
+/// Persisted Domain Events are stamped with the time of the [Clock]
+/// in the [`event::RECORDED_AT_KEY`] metadata entry.
 #[derive(Debug, Clone)]
-pub struct Tracking<T, StreamId, Event>
+pub struct InMemory<Id, Evt>
 where
-    T: Store<StreamId, Event> + Send + Sync,
-    StreamId: Send + Sync,
-    Event: message::Message + Send + Sync,
+    Evt: message::Message,
 {
-    store: T,
-
+    backend: Arc<RwLock<InMemoryBackend<Id, Evt>>>,
+    clock: Arc<dyn Clock>,
     #[allow(clippy::type_complexity)] // It is a complex type but still readable.
-    events: Arc<RwLock<Vec<event::Persisted<StreamId, Event>>>>,
+    locks: Arc<Mutex<HashMap<Id, Arc<futures::lock::Mutex<()>>>>>,
+    archive: Option<Archive<Id, Evt>>,
+    subscribers: Arc<Mutex<broadcast::Sender<event::Sequenced<Id, Evt>>>>,
 }
 
-impl<T, StreamId, Event> Tracking<T, StreamId, Event>
+/// Default number of Domain Events the live subscriptions of an [`InMemory`]
+/// Event Store can lag behind, before [`LaggedError`] is returned.
+pub const DEFAULT_SUBSCRIPTION_CAPACITY: usize = 1024;
+
+type ArchiveFn<Id, Evt> = dyn Fn(Id, Vec<event::Envelope<Evt>>) -> BoxFuture<'static, Result<version::Version, AppendError>>
+    + Send
+    + Sync;
+
+/// Event Store used by [`InMemory::with_archive`], type-erased so that
+/// the [`InMemory`] type does not depend on it.
+#[derive(Clone)]
+struct Archive<Id, Evt>(Arc<ArchiveFn<Id, Evt>>)
 where
-    T: Store<StreamId, Event> + Send + Sync,
-    StreamId: Clone + Send + Sync,
-    Event: message::Message + Clone + Send + Sync,
+    Evt: message::Message;
+
+impl<Id, Evt> std::fmt::Debug for Archive<Id, Evt>
+where
+    Evt: message::Message,
 {
-    /// Returns the list of recoded Domain Events through this decorator so far.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Archive").finish_non_exhaustive()
+    }
+}
+
+impl<Id, Evt> Default for InMemory<Id, Evt>
+where
+    Id: Clone,
+    Evt: message::Message + Clone,
+{
+    fn default() -> Self {
+        Self {
+            backend: Arc::default(),
+            clock: Arc::new(SystemClock),
+            locks: Arc::default(),
+            archive: None,
+            subscribers: Arc::new(Mutex::new(
+                broadcast::channel(DEFAULT_SUBSCRIPTION_CAPACITY).0,
+            )),
+        }
+    }
+}
+
+impl<Id, Evt> InMemory<Id, Evt>
+where
+    Id: Clone,
+    Evt: message::Message + Clone,
+{
+    /// Returns a new [`InMemory`] Event Store that keeps at most
+    /// the number of Domain Events specified by the [Capacity].
+    #[must_use]
+    pub fn with_capacity(capacity: Capacity) -> Self {
+        Self {
+            backend: Arc::new(RwLock::new(InMemoryBackend {
+                capacity,
+                ..InMemoryBackend::default()
+            })),
+            ..Self::default()
+        }
+    }
+
+    /// Sets the number of Domain Events the live subscriptions started through
+    /// the [Subscriber] implementation can lag behind, instead of
+    /// [`DEFAULT_SUBSCRIPTION_CAPACITY`].
     ///
     /// # Panics
     ///
-    /// Since the internal data is thread-safe through an [`RwLock`], this method
-    /// could potentially panic while attempting to get a read-only lock on the data recorded.
-    pub fn recorded_events(&self) -> Vec<event::Persisted<StreamId, Event>> {
-        self.events
-            .read()
-            .expect("acquire lock on recorded events list")
-            .clone()
+    /// Panics if the capacity is zero.
+    #[must_use]
+    pub fn with_subscription_capacity(mut self, capacity: usize) -> Self {
+        self.subscribers = Arc::new(Mutex::new(broadcast::channel(capacity).0));
+        self
     }
+}
 
-    /// Resets the list of recorded Domain Events through this decorator.
+impl<Id, Evt> InMemory<Id, Evt>
+where
+    Evt: message::Message,
+{
+    /// Uses the specified [Clock] to stamp the persisted Domain Events,
+    /// instead of the system time.
+    #[must_use]
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    /// Appends the Domain Events evicted with the [`OverflowPolicy::EvictOldest`]
+    /// policy to the specified Event Store, instead of dropping them.
     ///
-    /// # Panics
+    /// Evicted Domain Events are appended with no version check, in the order
+    /// they were appended to this Event Store. Append failures are not returned
+    /// to the caller, since the new Domain Events have already been appended,
+    /// but they are counted in [`InMemoryMetrics::archive_failures`].
     ///
-    /// Since the internal data is thread-safe through an [`RwLock`], this method
-    /// could potentially panic while attempting to get a read-write lock to empty the internal store.
-    pub fn reset_recorded_events(&self) {
-        self.events
-            .write()
-            .expect("acquire lock on recorded events list")
-            .clear();
+    /// # Errors
+    ///
+    /// An error is returned if the Event Store has been poisoned by a panicking thread.
+    pub fn with_archive<S>(mut self, archive: S) -> Result<Self, PoisonedError>
+    where
+        S: Appender<Id, Evt> + 'static,
+        Id: Send + Sync + 'static,
+        Evt: Send + Sync + 'static,
+    {
+        let archive = Arc::new(archive);
+
+        self.write_backend()?.keep_evicted = true;
+
+        self.archive = Some(Archive(Arc::new(move |id, events| {
+            let archive = archive.clone();
+            async move { archive.append(id, version::Check::Any, events).await }.boxed()
+        })));
+
+        Ok(self)
+    }
+
+    /// Returns the current [`InMemoryMetrics`] of the Event Store.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the Event Store has been poisoned by a panicking thread.
+    pub fn metrics(&self) -> Result<InMemoryMetrics, PoisonedError> {
+        let backend = self.read_backend()?;
+
+        Ok(InMemoryMetrics {
+            len: backend.len,
+            streams: backend.event_streams.len(),
+            ..backend.metrics
+        })
+    }
+
+    async fn archive_evicted(&self, evicted: Vec<event::Persisted<Id, Evt>>)
+    where
+        Id: PartialEq,
+    {
+        let Some(Archive(archive)) = &self.archive else {
+            return;
+        };
+
+        // NOTE: evicted Domain Events are grouped by consecutive Event Stream,
+        // to preserve the order in which they were appended.
+        let mut groups: Vec<(Id, Vec<event::Envelope<Evt>>)> = Vec::new();
+
+        for event in evicted {
+            match groups.last_mut() {
+                Some((id, events)) if *id == event.stream_id => events.push(event.event),
+                _ => groups.push((event.stream_id, vec![event.event])),
+            }
+        }
+
+        for (id, events) in groups {
+            let len = events.len() as u64;
+            let result = archive(id, events).await;
+
+            #[cfg(feature = "tracing")]
+            if let Err(err) = &result {
+                tracing::warn!(
+                    error = %err,
+                    "in-memory event store failed to archive the evicted domain events"
+                );
+            }
+
+            if let Ok(mut backend) = self.write_backend() {
+                match result {
+                    Ok(_) => backend.metrics.archived += len,
+                    Err(_) => backend.metrics.archive_failures += len,
+                }
+            }
+        }
+    }
+
+    fn read_backend(&self) -> Result<RwLockReadGuard<'_, InMemoryBackend<Id, Evt>>, PoisonedError> {
+        self.backend.read().map_err(|_| PoisonedError)
+    }
+
+    fn write_backend(
+        &self,
+    ) -> Result<RwLockWriteGuard<'_, InMemoryBackend<Id, Evt>>, PoisonedError> {
+        self.backend.write().map_err(|_| PoisonedError)
+    }
+
+    // NOTE: the sender is never modified while holding the lock,
+    // which is only used to deliver the Domain Events in Sequence order.
+    fn lock_subscribers(&self) -> MutexGuard<'_, broadcast::Sender<event::Sequenced<Id, Evt>>> {
+        self.subscribers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    // NOTE: the lock on the subscribers is acquired while still holding the lock
+    // on the backend, so that the Domain Events are delivered in Sequence order
+    // without being sent while holding the lock on the backend.
+    fn notify(
+        subscribers: MutexGuard<'_, broadcast::Sender<event::Sequenced<Id, Evt>>>,
+        appended: Vec<event::Sequenced<Id, Evt>>,
+    ) {
+        for event in appended {
+            // An error is only returned when there are no subscribers left.
+            let _ = subscribers.send(event);
+        }
+
+        drop(subscribers);
     }
 }
 
-impl<T, StreamId, Event> Streamer<StreamId, Event> for Tracking<T, StreamId, Event>
+impl<Id, Evt> InMemory<Id, Evt>
 where
-    T: Store<StreamId, Event> + Send + Sync,
-    StreamId: Clone + Send + Sync,
-    Event: message::Message + Clone + Send + Sync,
+    Id: Clone + Eq + Hash,
+    Evt: message::Message + Clone,
 {
-    type Error = <T as Streamer<StreamId, Event>>::Error;
+    /// Returns an [`InMemorySnapshot`] of all the Event Streams in the Event Store,
+    /// ordered by the [Sequence][event::Sequence] number of their first Domain Event.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the Event Store has been poisoned by a panicking thread.
+    pub fn export(&self) -> Result<InMemorySnapshot<Id, Evt>, PoisonedError> {
+        let backend = self.read_backend()?;
+
+        let mut event_streams: Vec<_> = backend
+            .event_streams
+            .iter()
+            .map(|(id, event_stream)| InMemoryStreamSnapshot {
+                id: id.clone(),
+                version: event_stream.version,
+                tombstoned: event_stream.tombstoned,
+                events: event_stream.events.iter().cloned().collect(),
+            })
+            .collect();
+
+        event_streams
+            .sort_by_key(|event_stream| event_stream.events.first().map(|event| event.sequence));
+
+        Ok(InMemorySnapshot {
+            sequence: backend.sequence,
+            event_streams,
+        })
+    }
+
+    /// Replaces all the Event Streams in the Event Store with the ones
+    /// in the specified [`InMemorySnapshot`], e.g. a test fixture
+    /// previously returned by [`InMemory::export`].
+    ///
+    /// The Domain Events are loaded as they are, without checking
+    /// the [Capacity] of the Event Store.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the Event Store has been poisoned by a panicking thread.
+    pub fn import(&self, snapshot: InMemorySnapshot<Id, Evt>) -> Result<(), PoisonedError> {
+        let mut backend = self.write_backend()?;
+
+        let mut log: Vec<_> = snapshot
+            .event_streams
+            .iter()
+            .flat_map(|event_stream| event_stream.events.iter())
+            .map(|event| {
+                (
+                    event.sequence,
+                    event.event.stream_id.clone(),
+                    event.event.version,
+                )
+            })
+            .collect();
+
+        log.sort_unstable_by_key(|(sequence, _, _)| *sequence);
+
+        backend.sequence = snapshot.sequence;
+        backend.len = log.len();
+        backend.log = if backend.capacity.global.is_some() {
+            log.into_iter()
+                .map(|(_, id, version)| (id, version))
+                .collect()
+        } else {
+            VecDeque::default()
+        };
+        backend.event_streams = snapshot
+            .event_streams
+            .into_iter()
+            .map(|event_stream| {
+                (
+                    event_stream.id,
+                    InMemoryEventStream {
+                        version: event_stream.version,
+                        events: event_stream.events.into(),
+                        tombstoned: event_stream.tombstoned,
+                    },
+                )
+            })
+            .collect();
+
+        Ok(())
+    }
+}
+
+impl<Id, Evt> Streamer<Id, Evt> for InMemory<Id, Evt>
+where
+    Id: Clone + Eq + Hash + Send + Sync,
+    Evt: message::Message + Clone + Send + Sync,
+{
+    type Error = PoisonedError;
 
     fn stream(
         &self,
-        id: &StreamId,
+        id: &Id,
         select: event::VersionSelect,
-    ) -> event::Stream<StreamId, Event, Self::Error> {
-        self.store.stream(id, select)
+    ) -> event::Stream<'_, Id, Evt, Self::Error> {
+        let backend = match self.read_backend() {
+            Ok(backend) => backend,
+            Err(err) => return once(ready(Err(err))).boxed(),
+        };
+
+        let events = backend
+            .event_streams
+            .get(id)
+            .filter(|event_stream| !event_stream.tombstoned)
+            .map(|event_stream| event_stream.events.clone())
+            .unwrap_or_default() // NOTE: the new VecDeque is empty, so there will be no memory allocation!
+            .into_iter()
+            .map(|event| event.event)
+            .filter(move |evt| match select {
+                event::VersionSelect::All => true,
+                event::VersionSelect::From(v) => evt.version >= v,
+            });
+
+        iter(events).map(Ok).boxed()
     }
 }
 
-#[async_trait]
-impl<T, StreamId, Event> Appender<StreamId, Event> for Tracking<T, StreamId, Event>
+impl<Id, Evt> GlobalStreamer<Id, Evt> for InMemory<Id, Evt>
 where
-    T: Store<StreamId, Event> + Send + Sync,
-    StreamId: Clone + Send + Sync,
-    Event: message::Message + Clone + Send + Sync,
+    Id: Clone + Eq + Hash + Send + Sync,
+    Evt: message::Message + Clone + Send + Sync,
 {
-    async fn append(
-        &self,
-        id: StreamId,
-        version_check: version::Check,
-        events: Vec<event::Envelope<Event>>,
-    ) -> Result<version::Version, AppendError> {
-        let new_version = self
-            .store
-            .append(id.clone(), version_check, events.clone())
-            .await?;
+    type Error = PoisonedError;
 
-        let events_size = events.len();
-        let previous_version = new_version - (events_size as version::Version);
+    fn stream_all(
+        &self,
+        select: event::SequenceSelect,
+    ) -> event::SequencedStream<'_, Id, Evt, Self::Error> {
+        let backend = match self.read_backend() {
+            Ok(backend) => backend,
+            Err(err) => return once(ready(Err(err))).boxed(),
+        };
 
-        let mut persisted_events = events
-            .into_iter()
-            .enumerate()
-            .map(|(i, event)| event::Persisted {
-                stream_id: id.clone(),
-                version: previous_version + (i as version::Version) + 1,
-                event,
+        let mut events: Vec<_> = backend
+            .event_streams
+            .values()
+            .flat_map(|event_stream| event_stream.events.iter())
+            .filter(|event| match select {
+                event::SequenceSelect::All => true,
+                event::SequenceSelect::From(s) => event.sequence >= s,
             })
+            .cloned()
             .collect();
 
-        self.events
-            .write()
-            .expect("acquire lock on recorded events list")
-            .append(&mut persisted_events);
+        events.sort_unstable_by_key(|event| event.sequence);
 
-        Ok(new_version)
+        iter(events).map(Ok).boxed()
     }
 }
 
-/// Extension trait that can be used to pull in supertypes implemented
-/// in this module.
-pub trait EventStoreExt<StreamId, Event>: Store<StreamId, Event> + Send + Sync + Sized
+#[async_trait]
+impl<Id, Evt> HeadSequence for InMemory<Id, Evt>
 where
-    StreamId: Clone + Send + Sync,
-    Event: message::Message + Clone + Send + Sync,
+    Id: Clone + Eq + Hash + Send + Sync,
+    Evt: message::Message + Clone + Send + Sync,
 {
-    /// Returns a [`Tracking`] instance that decorates the original [`event::Store`]
-    /// instanca this method has been called on.
-    fn with_recorded_events_tracking(self) -> Tracking<Self, StreamId, Event> {
-        Tracking {
-            store: self,
-            events: Arc::default(),
-        }
+    type Error = PoisonedError;
+
+    async fn head_sequence(&self) -> Result<Option<event::Sequence>, Self::Error> {
+        let sequence = self.read_backend()?.sequence;
+
+        Ok((sequence > 0).then_some(sequence))
     }
 }
 
-impl<T, StreamId, Event> EventStoreExt<StreamId, Event> for T
+#[async_trait]
+impl<Id, Evt> Appender<Id, Evt> for InMemory<Id, Evt>
 where
-    T: Store<StreamId, Event> + Send + Sync,
-    StreamId: Clone + Send + Sync,
-    Event: message::Message + Clone + Send + Sync,
+    Id: Clone + Eq + Hash + Send + Sync,
+    Evt: message::Message + Clone + Send + Sync,
 {
-}
+    async fn append(
+        &self,
+        id: Id,
+        version_check: version::Check,
+        events: Vec<event::Envelope<Evt>>,
+    ) -> Result<version::Version, AppendError> {
+        let recorded_at = self.clock.now();
 
-#[allow(clippy::semicolon_if_nothing_returned)] // False positives :shrugs:
-#[cfg(test)]
-mod test {
-    use futures::TryStreamExt;
-    use lazy_static::lazy_static;
+        let (new_version, evicted) = {
+            let mut backend = self.write_backend().map_err(anyhow::Error::from)?;
+            let subscribers = self.lock_subscribers();
 
-    use super::*;
-    use crate::event;
-    use crate::event::store::{Appender, Streamer};
-    use crate::message::tests::StringMessage;
-    use crate::version::Version;
+            backend.keep_appended = subscribers.receiver_count() > 0;
+            let new_version = backend.append(id, version_check, events, recorded_at)?;
+            let appended = std::mem::take(&mut backend.appended);
+            let evicted = std::mem::take(&mut backend.evicted);
 
-    const STREAM_ID: &str = "stream:test";
+            drop(backend);
+            Self::notify(subscribers, appended);
 
-    lazy_static! {
-        static ref EVENTS: Vec<event::Envelope<StringMessage>> = vec![
-            event::Envelope::from(StringMessage("event-1")),
-            event::Envelope::from(StringMessage("event-2")),
-            event::Envelope::from(StringMessage("event-3")),
-        ];
+            (new_version, evicted)
+        };
+
+        self.archive_evicted(evicted).await;
+
+        Ok(new_version)
     }
 
-    #[tokio::test]
-    async fn it_works() {
-        let event_store = InMemory::<&'static str, StringMessage>::default();
+    async fn append_multi(
+        &self,
+        appends: Vec<StreamAppend<Id, Evt>>,
+    ) -> Result<Vec<version::Version>, AppendError> {
+        let recorded_at = self.clock.now();
 
-        let new_event_stream_version = event_store
-            .append(STREAM_ID, version::Check::MustBe(0), EVENTS.clone())
-            .await
-            .expect("append should not fail");
+        let (new_versions, evicted) = {
+            let mut backend = self.write_backend().map_err(anyhow::Error::from)?;
+            let subscribers = self.lock_subscribers();
 
-        let expected_version = EVENTS.len() as Version;
-        assert_eq!(expected_version, new_event_stream_version);
+            // Run all the version checks first, so that no Event Stream is modified
+            // if any of the append operations would fail.
+            let mut versions: HashMap<&Id, version::Version> = HashMap::new();
 
-        let expected_events = EVENTS
-            .clone()
-            .into_iter()
-            .enumerate()
-            .map(|(i, event)| event::Persisted {
-                stream_id: STREAM_ID,
-                version: (i as Version) + 1,
-                event,
-            })
-            .collect::<Vec<_>>();
+            for append in &appends {
+                if backend.is_tombstoned(&append.id) {
+                    return Err(AppendError::Internal(StreamTombstonedError.into()));
+                }
 
-        let event_stream: Vec<_> = event_store
-            .stream(&STREAM_ID, event::VersionSelect::All)
-            .try_collect()
-            .await
-            .expect("opening an event stream should not fail");
+                let current_version = *versions
+                    .entry(&append.id)
+                    .or_insert_with(|| backend.last_version(&append.id));
 
-        assert_eq!(expected_events, event_stream);
-    }
+                if let version::Check::MustBe(expected) = append.version_check {
+                    if current_version != expected {
+                        return Err(AppendError::Conflict(version::ConflictError {
+                            expected,
+                            actual: current_version,
+                        }));
+                    }
+                }
 
-    #[tokio::test]
-    async fn tracking_store_works() {
-        let event_store = InMemory::<&'static str, StringMessage>::default();
-        let tracking_event_store = event_store.with_recorded_events_tracking();
+                versions.insert(
+                    &append.id,
+                    current_version + (append.events.len() as version::Version),
+                );
+            }
 
-        tracking_event_store
-            .append(STREAM_ID, version::Check::MustBe(0), EVENTS.clone())
-            .await
-            .expect("append should not fail");
+            drop(versions);
 
-        let event_stream: Vec<_> = tracking_event_store
-            .stream(&STREAM_ID, event::VersionSelect::All)
-            .try_collect()
-            .await
-            .expect("opening an event stream should not fail");
+            backend
+                .check_capacity(
+                    appends
+                        .iter()
+                        .map(|append| (&append.id, append.events.len())),
+                )
+                .map_err(anyhow::Error::from)?;
 
-        assert_eq!(event_stream, tracking_event_store.recorded_events());
-    }
+            backend.keep_appended = subscribers.receiver_count() > 0;
+            let new_versions = appends
+                .into_iter()
+                .map(|append| {
+                    backend.append(append.id, version::Check::Any, append.events, recorded_at)
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            let appended = std::mem::take(&mut backend.appended);
+            let evicted = std::mem::take(&mut backend.evicted);
 
-    #[tokio::test]
-    async fn version_conflict_checks_work_as_expected() {
-        let event_store = InMemory::<&'static str, StringMessage>::default();
+            drop(backend);
+            Self::notify(subscribers, appended);
 
-        let append_error = event_store
-            .append(STREAM_ID, version::Check::MustBe(3), EVENTS.clone())
-            .await
-            .expect_err("the event stream version should be zero");
+            (new_versions, evicted)
+        };
+
+        self.archive_evicted(evicted).await;
+
+        Ok(new_versions)
+    }
+}
+
+/// Delivers the Domain Events appended to the [`InMemory`] Event Store
+/// after the subscription has started.
+///
+/// At most [`DEFAULT_SUBSCRIPTION_CAPACITY`] Domain Events, or the capacity set with
+/// [`InMemory::with_subscription_capacity`], are buffered until they are consumed:
+/// subscriptions lagging further behind receive a [`LaggedError`], after which
+/// a [`CatchUp`][crate::subscription::CatchUp] subscription reads the skipped
+/// Domain Events from the Event Store.
+#[async_trait]
+impl<Id, Evt> Subscriber<Id, Evt> for InMemory<Id, Evt>
+where
+    Id: Clone + Eq + Hash + Send + Sync,
+    Evt: message::Message + Clone + Send + Sync,
+{
+    type Error = LaggedError;
+
+    async fn subscribe_all(
+        &self,
+    ) -> Result<event::SequencedStream<'_, Id, Evt, Self::Error>, Self::Error> {
+        let receiver = self.lock_subscribers().subscribe();
+
+        Ok(stream::unfold(receiver, |mut receiver| async move {
+            match receiver.recv().await {
+                Ok(event) => Some((Ok(event), receiver)),
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    Some((Err(LaggedError { skipped }), receiver))
+                },
+                Err(broadcast::error::RecvError::Closed) => None,
+            }
+        })
+        .boxed())
+    }
+
+    fn is_lagged(_: &Self::Error) -> bool {
+        true
+    }
+}
+
+#[async_trait]
+impl<Id, Evt> StreamDeleter<Id, Evt> for InMemory<Id, Evt>
+where
+    Id: Clone + Eq + Hash + Send + Sync,
+    Evt: message::Message + Clone + Send + Sync,
+{
+    async fn delete(&self, id: &Id) -> Result<(), DeleteError> {
+        self.write_backend()
+            .map_err(anyhow::Error::from)?
+            .remove(id);
+
+        Ok(())
+    }
+
+    async fn truncate(&self, id: &Id, before_version: version::Version) -> Result<(), DeleteError> {
+        self.write_backend()
+            .map_err(anyhow::Error::from)?
+            .truncate(id, before_version);
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<Id, Evt> Tombstoner<Id> for InMemory<Id, Evt>
+where
+    Id: Clone + Eq + Hash + Send + Sync,
+    Evt: message::Message + Send + Sync,
+{
+    async fn tombstone(
+        &self,
+        id: &Id,
+        version_check: version::Check,
+    ) -> Result<(), TombstoneError> {
+        let mut backend = self.write_backend().map_err(anyhow::Error::from)?;
+        let last_event_stream_version = backend.last_version(id);
+
+        if let version::Check::MustBe(expected) = version_check {
+            if last_event_stream_version != expected {
+                return Err(TombstoneError::Conflict(version::ConflictError {
+                    expected,
+                    actual: last_event_stream_version,
+                }));
+            }
+        }
+
+        if let Some(event_stream) = backend.event_streams.get_mut(id) {
+            event_stream.tombstoned = true;
+        }
+
+        Ok(())
+    }
+}
+
+/// The [`Locker::Lock`] of the [`InMemory`] Event Store.
+#[derive(Debug)]
+pub struct InMemoryLock {
+    _guard: futures::lock::OwnedMutexGuard<()>,
+}
+
+#[async_trait]
+impl<Id, Evt> Locker<Id> for InMemory<Id, Evt>
+where
+    Id: Clone + Eq + Hash + Send + Sync,
+    Evt: message::Message + Send + Sync,
+{
+    type Lock = InMemoryLock;
+
+    async fn lock(&self, id: &Id) -> Result<Self::Lock, LockError> {
+        let mutex = {
+            let mut locks = self
+                .locks
+                .lock()
+                .map_err(|_| anyhow::Error::from(PoisonedError))?;
+
+            // NOTE: locks not held nor awaited by anyone are only referenced by the map.
+            locks.retain(|_, mutex| Arc::strong_count(mutex) > 1);
+
+            locks.entry(id.clone()).or_default().clone()
+        };
+
+        Ok(InMemoryLock {
+            _guard: mutex.lock_owned().await,
+        })
+    }
+}
+
+/// Decorator type for an [`event::Store`] implementation that tracks the list of
+/// recorded Domain Events through it.
+///
+/// Useful for testing purposes, i.e. asserting that Domain Events written throguh
+/// this Event Store instance are the ones expected.
+#[derive(Debug, Clone)]
+pub struct Tracking<T, StreamId, Event>
+where
+    T: Store<StreamId, Event> + Send + Sync,
+    StreamId: Send + Sync,
+    Event: message::Message + Send + Sync,
+{
+    store: T,
+
+    #[allow(clippy::type_complexity)] // It is a complex type but still readable.
+    events: Arc<RwLock<Vec<event::Persisted<StreamId, Event>>>>,
+}
+
+impl<T, StreamId, Event> Tracking<T, StreamId, Event>
+where
+    T: Store<StreamId, Event> + Send + Sync,
+    StreamId: Clone + Send + Sync,
+    Event: message::Message + Clone + Send + Sync,
+{
+    /// Returns the list of recoded Domain Events through this decorator so far.
+    ///
+    /// # Panics
+    ///
+    /// Since the internal data is thread-safe through an [`RwLock`], this method
+    /// could potentially panic while attempting to get a read-only lock on the data recorded.
+    pub fn recorded_events(&self) -> Vec<event::Persisted<StreamId, Event>> {
+        self.events
+            .read()
+            .expect("acquire lock on recorded events list")
+            .clone()
+    }
+
+    /// Resets the list of recorded Domain Events through this decorator.
+    ///
+    /// # Panics
+    ///
+    /// Since the internal data is thread-safe through an [`RwLock`], this method
+    /// could potentially panic while attempting to get a read-write lock to empty the internal store.
+    pub fn reset_recorded_events(&self) {
+        self.events
+            .write()
+            .expect("acquire lock on recorded events list")
+            .clear();
+    }
+
+    fn record(
+        &self,
+        id: StreamId,
+        new_version: version::Version,
+        events: Vec<event::Envelope<Event>>,
+    ) {
+        let events_size = events.len();
+        let previous_version = new_version - (events_size as version::Version);
+
+        let mut persisted_events = events
+            .into_iter()
+            .enumerate()
+            .map(|(i, event)| event::Persisted {
+                stream_id: id.clone(),
+                version: previous_version + (i as version::Version) + 1,
+                event,
+            })
+            .collect();
+
+        self.events
+            .write()
+            .expect("acquire lock on recorded events list")
+            .append(&mut persisted_events);
+    }
+}
+
+impl<T, StreamId, Event> Streamer<StreamId, Event> for Tracking<T, StreamId, Event>
+where
+    T: Store<StreamId, Event> + Send + Sync,
+    StreamId: Clone + Send + Sync,
+    Event: message::Message + Clone + Send + Sync,
+{
+    type Error = <T as Streamer<StreamId, Event>>::Error;
+
+    fn stream(
+        &self,
+        id: &StreamId,
+        select: event::VersionSelect,
+    ) -> event::Stream<'_, StreamId, Event, Self::Error> {
+        self.store.stream(id, select)
+    }
+
+    fn stream_filtered<'a>(
+        &'a self,
+        id: &StreamId,
+        select: event::VersionSelect,
+        names: event::NameSelect,
+    ) -> event::Stream<'a, StreamId, Event, Self::Error>
+    where
+        StreamId: 'a,
+        Event: 'a,
+        Self::Error: 'a,
+    {
+        self.store.stream_filtered(id, select, names)
+    }
+}
+
+#[async_trait]
+impl<T, StreamId, Event> Appender<StreamId, Event> for Tracking<T, StreamId, Event>
+where
+    T: Store<StreamId, Event> + Send + Sync,
+    StreamId: Clone + Send + Sync,
+    Event: message::Message + Clone + Send + Sync,
+{
+    async fn append(
+        &self,
+        id: StreamId,
+        version_check: version::Check,
+        events: Vec<event::Envelope<Event>>,
+    ) -> Result<version::Version, AppendError> {
+        let new_version = self
+            .store
+            .append(id.clone(), version_check, events.clone())
+            .await?;
+
+        self.record(id, new_version, events);
+
+        Ok(new_version)
+    }
+
+    async fn append_multi(
+        &self,
+        appends: Vec<StreamAppend<StreamId, Event>>,
+    ) -> Result<Vec<version::Version>, AppendError> {
+        let new_versions = self.store.append_multi(appends.clone()).await?;
+
+        for (append, new_version) in appends.into_iter().zip(new_versions.iter()) {
+            self.record(append.id, *new_version, append.events);
+        }
+
+        Ok(new_versions)
+    }
+}
+
+/// Extension trait that can be used to pull in supertypes implemented
+/// in this module.
+pub trait EventStoreExt<StreamId, Event>: Store<StreamId, Event> + Send + Sync + Sized
+where
+    StreamId: Clone + Send + Sync,
+    Event: message::Message + Clone + Send + Sync,
+{
+    /// Returns a [`Tracking`] instance that decorates the original [`event::Store`]
+    /// instanca this method has been called on.
+    fn with_recorded_events_tracking(self) -> Tracking<Self, StreamId, Event> {
+        Tracking {
+            store: self,
+            events: Arc::default(),
+        }
+    }
+}
+
+impl<T, StreamId, Event> EventStoreExt<StreamId, Event> for T
+where
+    T: Store<StreamId, Event> + Send + Sync,
+    StreamId: Clone + Send + Sync,
+    Event: message::Message + Clone + Send + Sync,
+{
+}
+
+/// A fault injected by the [`Faulty`] Event Store decorator in a call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fault {
+    /// The call fails with an [`InjectedFaultError`], without reaching
+    /// the underlying Event Store.
+    Error,
+    /// The call is delayed by the specified duration, then forwarded
+    /// to the underlying Event Store.
+    Latency(std::time::Duration),
+    /// The call partially succeeds: appended Domain Events are committed,
+    /// but an [`InjectedFaultError`] is returned as if the connection dropped,
+    /// while streams fail with an [`InjectedFaultError`] after the first Domain Event.
+    Partial,
+}
+
+/// Error returned by the [`Faulty`] Event Store decorator when a [Fault] is injected.
+#[derive(Debug, Clone, Copy, thiserror::Error)]
+#[error("fault injected by the event store")]
+pub struct InjectedFaultError;
+
+/// Error type returned by [`Faulty`] when streaming Domain Events.
+#[derive(Debug, thiserror::Error)]
+pub enum FaultyError<E> {
+    /// Error returned when a [Fault] has been injected in the stream.
+    #[error(transparent)]
+    Injected(#[from] InjectedFaultError),
+    /// Error returned when the [`FaultSchedule`] has been poisoned by a panicking thread.
+    #[error(transparent)]
+    Poisoned(#[from] PoisonedError),
+    /// Error returned by the underlying Event Store.
+    #[error(transparent)]
+    Inner(E),
+}
+
+/// Decides which [Fault], if any, is injected in every call
+/// to the [`Faulty`] Event Store decorator.
+#[derive(Debug, Clone, Default)]
+pub enum FaultSchedule {
+    /// Never injects a [Fault].
+    #[default]
+    Never,
+    /// Injects the specified [Fault]s in order, one for each call,
+    /// and never again once they are over.
+    Scripted(VecDeque<Option<Fault>>),
+    /// Injects the [Fault] with the specified probability in every call,
+    /// using a pseudo-random generator initialized with the seed,
+    /// so that failing test runs can be reproduced.
+    Random {
+        /// The state of the pseudo-random generator.
+        seed: u64,
+        /// The probability of injecting the [Fault], between 0 and 1.
+        probability: f64,
+        /// The [Fault] to inject.
+        fault: Fault,
+    },
+}
+
+impl FaultSchedule {
+    /// Returns a [`FaultSchedule::Scripted`] schedule injecting the specified [Fault]s in order.
+    pub fn scripted(faults: impl IntoIterator<Item = Option<Fault>>) -> Self {
+        Self::Scripted(faults.into_iter().collect())
+    }
+
+    /// Returns a [`FaultSchedule::Random`] schedule injecting the [Fault]
+    /// with the specified probability, using the seed for the pseudo-random generator.
+    #[must_use]
+    pub fn random(seed: u64, probability: f64, fault: Fault) -> Self {
+        Self::Random {
+            // NOTE: xorshift gets stuck at zero.
+            seed: seed.max(1),
+            probability,
+            fault,
+        }
+    }
+
+    fn next_fault(&mut self) -> Option<Fault> {
+        match self {
+            Self::Never => None,
+            Self::Scripted(faults) => faults.pop_front().flatten(),
+            Self::Random {
+                seed,
+                probability,
+                fault,
+            } => {
+                // xorshift64* pseudo-random generator.
+                *seed ^= *seed >> 12;
+                *seed ^= *seed << 25;
+                *seed ^= *seed >> 27;
+                let random = seed.wrapping_mul(0x2545_f491_4f6c_dd1d) >> 11;
+
+                #[allow(clippy::cast_precision_loss)]
+                let sample = random as f64 / (1_u64 << 53) as f64;
+
+                (sample < *probability).then_some(*fault)
+            },
+        }
+    }
+}
+
+/// Decorator type for an [`event::Store`] implementation that injects [Fault]s,
+/// such as errors, latency or partial failures, in the calls to the underlying
+/// Event Store according to a [`FaultSchedule`].
+///
+/// Useful for testing purposes, i.e. asserting that the retry and recovery logic
+/// of an application copes with an unreliable Event Store.
+#[derive(Debug, Clone)]
+pub struct Faulty<S> {
+    store: S,
+    append_faults: Arc<Mutex<FaultSchedule>>,
+    stream_faults: Arc<Mutex<FaultSchedule>>,
+}
+
+impl<S> From<S> for Faulty<S> {
+    fn from(store: S) -> Self {
+        Self {
+            store,
+            append_faults: Arc::default(),
+            stream_faults: Arc::default(),
+        }
+    }
+}
+
+impl<S> Faulty<S> {
+    /// Sets the [`FaultSchedule`] of the [`Appender`] calls.
+    #[must_use]
+    pub fn with_append_faults(mut self, schedule: FaultSchedule) -> Self {
+        self.append_faults = Arc::new(Mutex::new(schedule));
+        self
+    }
+
+    /// Sets the [`FaultSchedule`] of the [`Streamer`] calls.
+    #[must_use]
+    pub fn with_stream_faults(mut self, schedule: FaultSchedule) -> Self {
+        self.stream_faults = Arc::new(Mutex::new(schedule));
+        self
+    }
+
+    /// Returns the underlying Event Store.
+    pub fn into_inner(self) -> S {
+        self.store
+    }
+
+    fn next_append_fault(&self) -> Result<Option<Fault>, AppendError> {
+        let mut schedule = self
+            .append_faults
+            .lock()
+            .map_err(|_| anyhow::Error::from(PoisonedError))?;
+
+        Ok(schedule.next_fault())
+    }
+
+    fn next_stream_fault(&self) -> Result<Option<Fault>, PoisonedError> {
+        let mut schedule = self.stream_faults.lock().map_err(|_| PoisonedError)?;
+
+        Ok(schedule.next_fault())
+    }
+
+    fn inject<'a, StreamId, Event, E>(
+        fault: Result<Option<Fault>, PoisonedError>,
+        events: event::Stream<'a, StreamId, Event, E>,
+    ) -> event::Stream<'a, StreamId, Event, FaultyError<E>>
+    where
+        StreamId: Send + 'a,
+        Event: message::Message + Send + 'a,
+        E: Send + 'a,
+    {
+        let events = events.map_err(FaultyError::Inner);
+
+        match fault {
+            Err(err) => once(ready(Err(err.into()))).boxed(),
+            Ok(None) => events.boxed(),
+            Ok(Some(Fault::Error)) => once(ready(Err(InjectedFaultError.into()))).boxed(),
+            Ok(Some(Fault::Latency(duration))) => futures_timer::Delay::new(duration)
+                .map(move |()| events)
+                .flatten_stream()
+                .boxed(),
+            Ok(Some(Fault::Partial)) => events
+                .take(1)
+                .chain(once(ready(Err(InjectedFaultError.into()))))
+                .boxed(),
+        }
+    }
+}
+
+impl<S, StreamId, Event> Streamer<StreamId, Event> for Faulty<S>
+where
+    S: Streamer<StreamId, Event>,
+    S::Error: 'static,
+    StreamId: Send + Sync + 'static,
+    Event: message::Message + Send + Sync + 'static,
+{
+    type Error = FaultyError<S::Error>;
+
+    fn stream(
+        &self,
+        id: &StreamId,
+        select: event::VersionSelect,
+    ) -> event::Stream<'_, StreamId, Event, Self::Error> {
+        Self::inject(self.next_stream_fault(), self.store.stream(id, select))
+    }
+
+    fn stream_filtered<'a>(
+        &'a self,
+        id: &StreamId,
+        select: event::VersionSelect,
+        names: event::NameSelect,
+    ) -> event::Stream<'a, StreamId, Event, Self::Error>
+    where
+        StreamId: 'a,
+        Event: 'a,
+        Self::Error: 'a,
+    {
+        Self::inject(
+            self.next_stream_fault(),
+            self.store.stream_filtered(id, select, names),
+        )
+    }
+}
+
+#[async_trait]
+impl<S, StreamId, Event> Appender<StreamId, Event> for Faulty<S>
+where
+    S: Appender<StreamId, Event>,
+    StreamId: Send + Sync + 'static,
+    Event: message::Message + Send + Sync + 'static,
+{
+    async fn append(
+        &self,
+        id: StreamId,
+        version_check: version::Check,
+        events: Vec<event::Envelope<Event>>,
+    ) -> Result<version::Version, AppendError> {
+        match self.next_append_fault()? {
+            None => self.store.append(id, version_check, events).await,
+            Some(Fault::Error) => Err(anyhow::Error::from(InjectedFaultError).into()),
+            Some(Fault::Latency(duration)) => {
+                futures_timer::Delay::new(duration).await;
+                self.store.append(id, version_check, events).await
+            },
+            Some(Fault::Partial) => {
+                self.store.append(id, version_check, events).await?;
+                Err(anyhow::Error::from(InjectedFaultError).into())
+            },
+        }
+    }
+
+    async fn append_multi(
+        &self,
+        appends: Vec<StreamAppend<StreamId, Event>>,
+    ) -> Result<Vec<version::Version>, AppendError>
+    where
+        StreamId: 'async_trait,
+        Event: 'async_trait,
+    {
+        match self.next_append_fault()? {
+            None => self.store.append_multi(appends).await,
+            Some(Fault::Error) => Err(anyhow::Error::from(InjectedFaultError).into()),
+            Some(Fault::Latency(duration)) => {
+                futures_timer::Delay::new(duration).await;
+                self.store.append_multi(appends).await
+            },
+            Some(Fault::Partial) => {
+                self.store.append_multi(appends).await?;
+                Err(anyhow::Error::from(InjectedFaultError).into())
+            },
+        }
+    }
+}
+
+#[allow(clippy::semicolon_if_nothing_returned)] // False positives :shrugs:
+#[cfg(test)]
+mod test {
+    use std::sync::LazyLock;
+
+    use futures::TryStreamExt;
+
+    use super::*;
+    use crate::event;
+    use crate::event::store::{Appender, Streamer};
+    use crate::event::test::ConformanceEvent;
+    use crate::message::tests::StringMessage;
+    use crate::subscription::CatchUp;
+    use crate::version::Version;
+
+    const STREAM_ID: &str = "stream:test";
+
+    static EVENTS: LazyLock<Vec<event::Envelope<StringMessage>>> = LazyLock::new(|| {
+        vec![
+            event::Envelope::from(StringMessage("event-1")),
+            event::Envelope::from(StringMessage("event-2")),
+            event::Envelope::from(StringMessage("event-3")),
+        ]
+    });
+
+    #[tokio::test]
+    async fn it_works() {
+        let event_store = InMemory::<&'static str, StringMessage>::default();
+
+        let new_event_stream_version = event_store
+            .append(STREAM_ID, version::Check::MustBe(0), EVENTS.clone())
+            .await
+            .expect("append should not fail");
+
+        let expected_version = EVENTS.len() as Version;
+        assert_eq!(expected_version, new_event_stream_version);
+
+        let expected_events = EVENTS
+            .clone()
+            .into_iter()
+            .enumerate()
+            .map(|(i, event)| event::Persisted {
+                stream_id: STREAM_ID,
+                version: (i as Version) + 1,
+                event,
+            })
+            .collect::<Vec<_>>();
+
+        let event_stream: Vec<_> = event_store
+            .stream(&STREAM_ID, event::VersionSelect::All)
+            .try_collect()
+            .await
+            .expect("opening an event stream should not fail");
+
+        assert_eq!(expected_events, event_stream);
+    }
+
+    #[tokio::test]
+    async fn tracking_store_works() {
+        let event_store = InMemory::<&'static str, StringMessage>::default();
+        let tracking_event_store = event_store.with_recorded_events_tracking();
+
+        tracking_event_store
+            .append(STREAM_ID, version::Check::MustBe(0), EVENTS.clone())
+            .await
+            .expect("append should not fail");
+
+        let event_stream: Vec<_> = tracking_event_store
+            .stream(&STREAM_ID, event::VersionSelect::All)
+            .try_collect()
+            .await
+            .expect("opening an event stream should not fail");
+
+        assert_eq!(event_stream, tracking_event_store.recorded_events());
+    }
+
+    #[tokio::test]
+    async fn stream_filtered_selects_events_by_name() {
+        let event_store = InMemory::<&'static str, StringMessage>::default();
+
+        event_store
+            .append(STREAM_ID, version::Check::MustBe(0), EVENTS.clone())
+            .await
+            .expect("append should not fail");
+
+        let versions: Vec<Version> = event_store
+            .stream_filtered(
+                &STREAM_ID,
+                event::VersionSelect::From(2),
+                event::NameSelect::only(["string_payload"]),
+            )
+            .map_ok(|event| event.version)
+            .try_collect()
+            .await
+            .expect("opening an event stream should not fail");
+
+        assert_eq!(vec![2, 3], versions);
+
+        let events: Vec<_> = event_store
+            .stream_all_filtered(
+                event::SequenceSelect::All,
+                event::NameSelect::only(["another_payload"]),
+            )
+            .try_collect()
+            .await
+            .expect("streaming all events should not fail");
+
+        assert!(events.is_empty());
+    }
+
+    #[tokio::test]
+    async fn resume_tokens_continue_streaming_after_the_last_event() {
+        let event_store = InMemory::<&'static str, StringMessage>::default();
+
+        event_store
+            .append(STREAM_ID, version::Check::MustBe(0), EVENTS.clone())
+            .await
+            .expect("append should not fail");
+
+        let first_event = event_store
+            .stream(&STREAM_ID, event::VersionSelect::All)
+            .try_next()
+            .await
+            .expect("opening an event stream should not fail")
+            .expect("event stream should not be empty");
+
+        // Clients persist the token as a string, and pass it back later.
+        let token: event::ResumeToken = first_event
+            .resume_token()
+            .to_string()
+            .parse()
+            .expect("resume token should be parsed back");
+
+        let versions: Vec<Version> = event_store
+            .stream(
+                &STREAM_ID,
+                token.try_into().expect("token should select a version"),
+            )
+            .map_ok(|event| event.version)
+            .try_collect()
+            .await
+            .expect("opening an event stream should not fail");
+
+        assert_eq!(vec![2, 3], versions);
+
+        let last_event = event_store
+            .stream_all(event::SequenceSelect::All)
+            .try_collect::<Vec<_>>()
+            .await
+            .expect("streaming all events should not fail")
+            .pop()
+            .expect("event store should not be empty");
+
+        let token = last_event.resume_token();
+
+        assert_eq!(
+            Err(event::ResumeTokenError::WrongKind),
+            event::VersionSelect::try_from(token)
+        );
+
+        let remaining: Vec<_> = event_store
+            .stream_all(token.try_into().expect("token should select a sequence"))
+            .try_collect()
+            .await
+            .expect("streaming all events should not fail");
+
+        assert!(remaining.is_empty());
+    }
+
+    #[tokio::test]
+    async fn append_multi_appends_to_all_event_streams() {
+        const OTHER_STREAM_ID: &str = "stream:other";
+
+        let event_store = InMemory::<&'static str, StringMessage>::default();
+
+        let new_versions = event_store
+            .append_multi(vec![
+                StreamAppend {
+                    id: STREAM_ID,
+                    version_check: version::Check::MustBe(0),
+                    events: EVENTS.clone(),
+                },
+                StreamAppend {
+                    id: OTHER_STREAM_ID,
+                    version_check: version::Check::Any,
+                    events: EVENTS[..1].to_vec(),
+                },
+            ])
+            .await
+            .expect("append_multi should not fail");
+
+        assert_eq!(vec![EVENTS.len() as Version, 1], new_versions);
+
+        let other_event_stream: Vec<_> = event_store
+            .stream(&OTHER_STREAM_ID, event::VersionSelect::All)
+            .try_collect()
+            .await
+            .expect("opening an event stream should not fail");
+
+        assert_eq!(
+            vec![event::Persisted {
+                stream_id: OTHER_STREAM_ID,
+                version: 1,
+                event: EVENTS[0].clone(),
+            }],
+            other_event_stream
+        );
+    }
+
+    #[tokio::test]
+    async fn append_multi_does_not_append_anything_if_a_version_check_fails() {
+        const OTHER_STREAM_ID: &str = "stream:other";
+
+        let event_store = InMemory::<&'static str, StringMessage>::default();
+
+        let append_error = event_store
+            .append_multi(vec![
+                StreamAppend {
+                    id: STREAM_ID,
+                    version_check: version::Check::MustBe(0),
+                    events: EVENTS.clone(),
+                },
+                StreamAppend {
+                    id: OTHER_STREAM_ID,
+                    version_check: version::Check::MustBe(1),
+                    events: EVENTS.clone(),
+                },
+            ])
+            .await
+            .expect_err("the second event stream version should be zero");
+
+        assert!(matches!(append_error, AppendError::Conflict(_)));
+
+        let event_stream: Vec<_> = event_store
+            .stream(&STREAM_ID, event::VersionSelect::All)
+            .try_collect()
+            .await
+            .expect("opening an event stream should not fail");
+
+        assert!(event_stream.is_empty());
+    }
+
+    #[tokio::test]
+    async fn version_conflict_checks_work_as_expected() {
+        let event_store = InMemory::<&'static str, StringMessage>::default();
+
+        let append_error = event_store
+            .append(STREAM_ID, version::Check::MustBe(3), EVENTS.clone())
+            .await
+            .expect_err("the event stream version should be zero");
 
         if let AppendError::Conflict(err) = append_error {
             return assert_eq!(
@@ -434,4 +2089,621 @@ mod test {
 
         panic!("expected conflict error, received: {append_error}")
     }
+
+    #[tokio::test]
+    async fn truncate_removes_old_events_but_keeps_the_stream_version() {
+        let event_store = InMemory::<&'static str, StringMessage>::default();
+
+        event_store
+            .append(STREAM_ID, version::Check::MustBe(0), EVENTS.clone())
+            .await
+            .expect("append should not fail");
+
+        event_store
+            .truncate(&STREAM_ID, 2)
+            .await
+            .expect("truncate should not fail");
+
+        let versions: Vec<Version> = event_store
+            .stream(&STREAM_ID, event::VersionSelect::All)
+            .map_ok(|event| event.version)
+            .try_collect()
+            .await
+            .expect("opening an event stream should not fail");
+
+        assert_eq!(vec![2, 3], versions);
+
+        // Truncating all the events does not reset the Event Stream version.
+        event_store
+            .truncate(&STREAM_ID, 10)
+            .await
+            .expect("truncate should not fail");
+
+        let new_version = event_store
+            .append(STREAM_ID, version::Check::MustBe(3), EVENTS.clone())
+            .await
+            .expect("append should not fail");
+
+        assert_eq!(6, new_version);
+    }
+
+    #[tokio::test]
+    async fn tombstoned_event_streams_are_hidden_and_closed_to_new_events() {
+        let event_store = InMemory::<&'static str, StringMessage>::default();
+
+        event_store
+            .append(STREAM_ID, version::Check::MustBe(0), EVENTS.clone())
+            .await
+            .expect("append should not fail");
+
+        let error = event_store
+            .tombstone(&STREAM_ID, version::Check::MustBe(1))
+            .await
+            .expect_err("the event stream is at a different version");
+
+        assert!(matches!(
+            error,
+            TombstoneError::Conflict(version::ConflictError {
+                expected: 1,
+                actual: 3
+            })
+        ));
+
+        event_store
+            .tombstone(&STREAM_ID, version::Check::MustBe(3))
+            .await
+            .expect("tombstone should not fail");
+
+        let events: Vec<_> = event_store
+            .stream(&STREAM_ID, event::VersionSelect::All)
+            .try_collect()
+            .await
+            .expect("opening an event stream should not fail");
+
+        assert!(events.is_empty());
+
+        let error = event_store
+            .append(STREAM_ID, version::Check::Any, EVENTS.clone())
+            .await
+            .expect_err("the event stream has been tombstoned");
+
+        assert!(matches!(
+            error,
+            AppendError::Internal(err) if err.is::<StreamTombstonedError>()
+        ));
+
+        // The Domain Events are still part of the global Event Stream.
+        let all_events: Vec<_> = event_store
+            .stream_all(event::SequenceSelect::All)
+            .try_collect()
+            .await
+            .expect("opening the global event stream should not fail");
+
+        assert_eq!(3, all_events.len());
+    }
+
+    #[tokio::test]
+    async fn appended_events_are_stamped_with_the_clock_time() {
+        let clock = crate::clock::TestClock::default();
+        let event_store =
+            InMemory::<&'static str, StringMessage>::default().with_clock(clock.clone());
+
+        event_store
+            .append(STREAM_ID, version::Check::MustBe(0), EVENTS.clone())
+            .await
+            .expect("append should not fail");
+
+        clock.advance(chrono::Duration::minutes(5));
+
+        event_store
+            .append(STREAM_ID, version::Check::MustBe(3), EVENTS.clone())
+            .await
+            .expect("append should not fail");
+
+        let recorded_at: Vec<_> = event_store
+            .stream(&STREAM_ID, event::VersionSelect::All)
+            .map_ok(|event| event.recorded_at())
+            .try_collect()
+            .await
+            .expect("opening an event stream should not fail");
+
+        let first = DateTime::UNIX_EPOCH;
+        let second = first + chrono::Duration::minutes(5);
+
+        assert_eq!(
+            vec![Some(first); 3]
+                .into_iter()
+                .chain(vec![Some(second); 3])
+                .collect::<Vec<_>>(),
+            recorded_at
+        );
+    }
+
+    #[tokio::test]
+    async fn delete_removes_the_whole_event_stream() {
+        let event_store = InMemory::<&'static str, StringMessage>::default();
+
+        event_store
+            .append(STREAM_ID, version::Check::MustBe(0), EVENTS.clone())
+            .await
+            .expect("append should not fail");
+
+        event_store
+            .delete(&STREAM_ID)
+            .await
+            .expect("delete should not fail");
+
+        let event_stream: Vec<_> = event_store
+            .stream(&STREAM_ID, event::VersionSelect::All)
+            .try_collect()
+            .await
+            .expect("opening an event stream should not fail");
+
+        assert!(event_stream.is_empty());
+
+        event_store
+            .append(STREAM_ID, version::Check::MustBe(0), EVENTS.clone())
+            .await
+            .expect("a deleted event stream should start from version zero");
+    }
+
+    #[tokio::test]
+    async fn capacity_rejects_appends_exceeding_it() {
+        let event_store = InMemory::<&'static str, StringMessage>::with_capacity(Capacity {
+            per_stream: Some(4),
+            global: Some(5),
+            on_overflow: OverflowPolicy::Reject,
+        });
+
+        event_store
+            .append(STREAM_ID, version::Check::MustBe(0), EVENTS.clone())
+            .await
+            .expect("append should not fail");
+
+        let error = event_store
+            .append(STREAM_ID, version::Check::MustBe(3), EVENTS.clone())
+            .await
+            .expect_err("the event stream capacity should be exceeded");
+
+        assert!(matches!(
+            error,
+            AppendError::Internal(err)
+                if err.downcast_ref() == Some(&CapacityExceededError::PerStream(4))
+        ));
+
+        let error = event_store
+            .append("stream:other", version::Check::MustBe(0), EVENTS.clone())
+            .await
+            .expect_err("the event store capacity should be exceeded");
+
+        assert!(matches!(
+            error,
+            AppendError::Internal(err)
+                if err.downcast_ref() == Some(&CapacityExceededError::Global(5))
+        ));
+    }
+
+    #[tokio::test]
+    async fn capacity_evicts_the_oldest_events() {
+        const OTHER_STREAM_ID: &str = "stream:other";
+
+        let event_store = InMemory::<&'static str, StringMessage>::with_capacity(Capacity {
+            per_stream: Some(4),
+            global: Some(5),
+            on_overflow: OverflowPolicy::EvictOldest,
+        });
+
+        for version_check in [0, 3] {
+            event_store
+                .append(
+                    STREAM_ID,
+                    version::Check::MustBe(version_check),
+                    EVENTS.clone(),
+                )
+                .await
+                .expect("append should not fail");
+        }
+
+        event_store
+            .append(OTHER_STREAM_ID, version::Check::MustBe(0), EVENTS.clone())
+            .await
+            .expect("append should not fail");
+
+        let stream_versions = |id| {
+            event_store
+                .stream(id, event::VersionSelect::All)
+                .map_ok(|event| event.version)
+                .try_collect::<Vec<Version>>()
+        };
+
+        // Per-stream eviction keeps versions 3 to 6, then global eviction
+        // removes versions 3 and 4 to make room for the other stream.
+        assert_eq!(vec![5, 6], stream_versions(&STREAM_ID).await.unwrap());
+        assert_eq!(
+            vec![1, 2, 3],
+            stream_versions(&OTHER_STREAM_ID).await.unwrap()
+        );
+
+        // Evicting events does not affect the event stream version.
+        event_store
+            .append(STREAM_ID, version::Check::MustBe(6), EVENTS.clone())
+            .await
+            .expect("append should not fail");
+    }
+
+    #[tokio::test]
+    async fn metrics_count_rejected_evicted_and_archived_events() {
+        let rejecting_store = InMemory::<&'static str, StringMessage>::with_capacity(Capacity {
+            per_stream: Some(4),
+            ..Capacity::default()
+        });
+
+        for version_check in [0, 3] {
+            let _ = rejecting_store
+                .append(
+                    STREAM_ID,
+                    version::Check::MustBe(version_check),
+                    EVENTS.clone(),
+                )
+                .await;
+        }
+
+        assert_eq!(
+            InMemoryMetrics {
+                len: 3,
+                streams: 1,
+                rejected: 1,
+                ..InMemoryMetrics::default()
+            },
+            rejecting_store.metrics().unwrap()
+        );
+
+        let archive = InMemory::<&'static str, StringMessage>::default();
+        let evicting_store = InMemory::<&'static str, StringMessage>::with_capacity(Capacity {
+            per_stream: Some(4),
+            on_overflow: OverflowPolicy::EvictOldest,
+            ..Capacity::default()
+        })
+        .with_archive(archive.clone())
+        .unwrap();
+
+        for version_check in [0, 3, 6] {
+            evicting_store
+                .append(
+                    STREAM_ID,
+                    version::Check::MustBe(version_check),
+                    EVENTS.clone(),
+                )
+                .await
+                .expect("append should not fail");
+        }
+
+        assert_eq!(
+            InMemoryMetrics {
+                len: 4,
+                streams: 1,
+                evicted: 5,
+                archived: 5,
+                ..InMemoryMetrics::default()
+            },
+            evicting_store.metrics().unwrap()
+        );
+
+        let archived_versions: Vec<Version> = archive
+            .stream(&STREAM_ID, event::VersionSelect::All)
+            .map_ok(|event| event.version)
+            .try_collect()
+            .await
+            .expect("archived events should be streamed");
+
+        assert_eq!(vec![1, 2, 3, 4, 5], archived_versions);
+    }
+
+    #[tokio::test]
+    async fn poisoned_in_memory_store_returns_errors_instead_of_panicking() {
+        let event_store = InMemory::<&'static str, StringMessage>::default();
+        let poisoner = event_store.clone();
+
+        std::thread::spawn(move || {
+            let _backend = poisoner.backend.write().unwrap();
+            panic!("poisoning the event store backend");
+        })
+        .join()
+        .expect_err("the thread should panic");
+
+        let error = event_store
+            .append(STREAM_ID, version::Check::Any, EVENTS.clone())
+            .await
+            .expect_err("append should fail");
+
+        assert!(
+            matches!(error, AppendError::Internal(err) if err.downcast_ref::<PoisonedError>().is_some())
+        );
+
+        assert_eq!(
+            Err(PoisonedError),
+            event_store
+                .stream(&STREAM_ID, event::VersionSelect::All)
+                .try_collect::<Vec<_>>()
+                .await
+        );
+
+        assert_eq!(
+            Err(PoisonedError),
+            event_store
+                .stream_all(event::SequenceSelect::All)
+                .try_collect::<Vec<_>>()
+                .await
+        );
+
+        assert!(event_store.delete(&STREAM_ID).await.is_err());
+
+        assert!(matches!(
+            event_store.with_archive(InMemory::<&'static str, StringMessage>::default()),
+            Err(PoisonedError)
+        ));
+    }
+
+    #[tokio::test]
+    async fn poisoned_faulty_store_returns_errors_instead_of_panicking() {
+        let faulty_store = Faulty::from(InMemory::<&'static str, StringMessage>::default());
+        let poisoner = faulty_store.clone();
+
+        std::thread::spawn(move || {
+            let _append_faults = poisoner.append_faults.lock().unwrap();
+            let _stream_faults = poisoner.stream_faults.lock().unwrap();
+            panic!("poisoning the fault schedules");
+        })
+        .join()
+        .expect_err("the thread should panic");
+
+        let error = faulty_store
+            .append(STREAM_ID, version::Check::Any, EVENTS.clone())
+            .await
+            .expect_err("append should fail");
+
+        assert!(
+            matches!(error, AppendError::Internal(err) if err.downcast_ref::<PoisonedError>().is_some())
+        );
+
+        let error = faulty_store
+            .stream(&STREAM_ID, event::VersionSelect::All)
+            .try_collect::<Vec<_>>()
+            .await
+            .expect_err("stream should fail");
+
+        assert!(matches!(error, FaultyError::Poisoned(PoisonedError)));
+    }
+
+    #[tokio::test]
+    async fn faulty_store_injects_scripted_faults() {
+        let event_store = InMemory::<&'static str, StringMessage>::default();
+        let faulty_store = Faulty::from(event_store.clone())
+            .with_append_faults(FaultSchedule::scripted([
+                Some(Fault::Error),
+                Some(Fault::Partial),
+            ]))
+            .with_stream_faults(FaultSchedule::scripted([
+                Some(Fault::Latency(std::time::Duration::from_millis(1))),
+                Some(Fault::Partial),
+            ]));
+
+        let error = faulty_store
+            .append(STREAM_ID, version::Check::MustBe(0), EVENTS.clone())
+            .await
+            .expect_err("the first append should fail");
+        assert!(
+            matches!(error, AppendError::Internal(err) if err.downcast_ref::<InjectedFaultError>().is_some())
+        );
+        assert_eq!(
+            0,
+            event_store
+                .stream(&STREAM_ID, event::VersionSelect::All)
+                .count()
+                .await
+        );
+
+        faulty_store
+            .append(STREAM_ID, version::Check::MustBe(0), EVENTS.clone())
+            .await
+            .expect_err("the second append should fail after committing");
+        assert_eq!(
+            3,
+            event_store
+                .stream(&STREAM_ID, event::VersionSelect::All)
+                .count()
+                .await
+        );
+
+        let versions: Vec<_> = faulty_store
+            .stream(&STREAM_ID, event::VersionSelect::All)
+            .map_ok(|event| event.version)
+            .try_collect()
+            .await
+            .expect("the delayed stream should not fail");
+        assert_eq!(vec![1, 2, 3], versions);
+
+        let results: Vec<_> = faulty_store
+            .stream(&STREAM_ID, event::VersionSelect::All)
+            .collect()
+            .await;
+        assert_eq!(2, results.len());
+        assert!(results[0].is_ok());
+        assert!(matches!(results[1], Err(FaultyError::Injected(_))));
+
+        faulty_store
+            .append(STREAM_ID, version::Check::MustBe(3), EVENTS.clone())
+            .await
+            .expect("faults should not be injected once the script is over");
+    }
+
+    #[test]
+    fn random_fault_schedule_is_reproducible() {
+        let sample = |mut schedule: FaultSchedule| -> Vec<_> {
+            (0..100).map(|_| schedule.next_fault().is_some()).collect()
+        };
+
+        let faults = sample(FaultSchedule::random(42, 0.3, Fault::Error));
+        let injected = faults.iter().filter(|injected| **injected).count();
+
+        assert_eq!(faults, sample(FaultSchedule::random(42, 0.3, Fault::Error)));
+        assert!((10..50).contains(&injected), "{injected} faults injected");
+        assert!(!sample(FaultSchedule::random(42, 0.0, Fault::Error))
+            .into_iter()
+            .any(|injected| injected));
+        assert!(sample(FaultSchedule::random(42, 1.0, Fault::Error))
+            .into_iter()
+            .all(|injected| injected));
+    }
+
+    #[tokio::test]
+    async fn in_memory_store_can_be_exported_and_imported_as_a_fixture() {
+        let event_store = InMemory::<String, ConformanceEvent>::default();
+
+        for (id, values) in [
+            ("stream:a", [1, 2]),
+            ("stream:b", [3, 4]),
+            ("stream:a", [5, 6]),
+        ] {
+            event_store
+                .append(
+                    id.to_owned(),
+                    version::Check::Any,
+                    values
+                        .into_iter()
+                        .map(|value| event::Envelope::from(ConformanceEvent { value }))
+                        .collect(),
+                )
+                .await
+                .expect("append should not fail");
+        }
+
+        event_store
+            .tombstone(&"stream:b".to_owned(), version::Check::Any)
+            .await
+            .expect("tombstone should not fail");
+
+        let fixture = serde_json::to_string(&event_store.export().unwrap())
+            .expect("snapshot should be serialized");
+
+        let imported_store = InMemory::<String, ConformanceEvent>::default();
+        imported_store
+            .import(serde_json::from_str(&fixture).expect("snapshot should be deserialized"))
+            .unwrap();
+
+        assert_eq!(
+            event_store.export().unwrap(),
+            imported_store.export().unwrap()
+        );
+        assert_eq!(
+            event_store
+                .stream_all(event::SequenceSelect::All)
+                .try_collect::<Vec<_>>()
+                .await,
+            imported_store
+                .stream_all(event::SequenceSelect::All)
+                .try_collect::<Vec<_>>()
+                .await
+        );
+        assert_eq!(
+            InMemoryMetrics {
+                len: 6,
+                streams: 2,
+                ..InMemoryMetrics::default()
+            },
+            imported_store.metrics().unwrap()
+        );
+
+        let new_version = imported_store
+            .append(
+                "stream:a".to_owned(),
+                version::Check::MustBe(4),
+                vec![event::Envelope::from(ConformanceEvent { value: 7 })],
+            )
+            .await
+            .expect("append should continue from the imported version");
+        assert_eq!(5, new_version);
+        assert_eq!(Ok(Some(7)), imported_store.head_sequence().await);
+
+        assert!(imported_store
+            .append(
+                "stream:b".to_owned(),
+                version::Check::Any,
+                vec![event::Envelope::from(ConformanceEvent { value: 8 })],
+            )
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn in_memory_store_supports_catch_up_subscriptions() {
+        let event_store = InMemory::<&'static str, StringMessage>::default();
+
+        event_store
+            .append(STREAM_ID, version::Check::MustBe(0), EVENTS[..2].to_vec())
+            .await
+            .expect("append should not fail");
+
+        let catch_up = CatchUp::new(event_store.clone(), event_store.clone());
+        let mut subscription = catch_up.stream(event::SequenceSelect::All);
+
+        let dropped_subscription = event_store
+            .subscribe_all()
+            .await
+            .expect("subscription should start");
+        drop(dropped_subscription);
+
+        let mut sequences = Vec::new();
+
+        for _ in 0..2 {
+            let event = subscription.try_next().await.unwrap().unwrap();
+            sequences.push(event.sequence);
+        }
+
+        event_store
+            .append(STREAM_ID, version::Check::MustBe(2), EVENTS[2..].to_vec())
+            .await
+            .expect("append should not fail");
+
+        let event = subscription.try_next().await.unwrap().unwrap();
+        sequences.push(event.sequence);
+
+        assert_eq!(vec![1, 2, 3], sequences);
+        assert_eq!(1, event_store.lock_subscribers().receiver_count());
+    }
+
+    #[tokio::test]
+    async fn lagging_catch_up_subscriptions_read_skipped_events_from_the_store() {
+        let event_store =
+            InMemory::<&'static str, StringMessage>::default().with_subscription_capacity(1);
+
+        event_store
+            .append(STREAM_ID, version::Check::MustBe(0), EVENTS[..1].to_vec())
+            .await
+            .expect("append should not fail");
+
+        let catch_up = CatchUp::new(event_store.clone(), event_store.clone());
+        let mut subscription = catch_up.stream(event::SequenceSelect::All);
+
+        let event = subscription.try_next().await.unwrap().unwrap();
+        let mut sequences = vec![event.sequence];
+
+        for (i, event) in EVENTS[1..].iter().enumerate() {
+            event_store
+                .append(
+                    STREAM_ID,
+                    version::Check::MustBe(i as u64 + 1),
+                    vec![event.clone()],
+                )
+                .await
+                .expect("append should not fail");
+        }
+
+        for _ in 1..EVENTS.len() {
+            let event = subscription.try_next().await.unwrap().unwrap();
+            sequences.push(event.sequence);
+        }
+
+        assert_eq!(vec![1, 2, 3], sequences);
+    }
 }
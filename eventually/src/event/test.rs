@@ -0,0 +1,424 @@
+//! Module exposing a [Conformance] suite, a reusable set of behavior tests
+//! that any [Event Store][event::Store] implementation can run against itself
+//! to verify it complies with the expectations of the rest of the crate.
+//!
+//! The suite uses the [`ConformanceEvent`] type for its Domain Events, and writes
+//! to Event Streams with unique ids, so it can run against a shared backend
+//! (e.g. a database also used by other tests): use [`Conformance::clean_up`]
+//! to delete them once the checks have passed.
+
+use std::fmt::Debug;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use futures::future::{join_all, ready};
+use futures::TryStreamExt;
+use serde::{Deserialize, Serialize};
+
+use crate::event::store::{
+    AppendError, Appender, DeleteError, GlobalStreamer, StreamDeleter, Streamer,
+};
+use crate::{event, message, version};
+
+/// The Domain Event appended by the [Conformance] suite.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConformanceEvent {
+    /// A value identifying the Domain Event within the test that appended it.
+    pub value: u64,
+}
+
+const CONFORMANCE_EVENT_NAME: &str = "ConformanceEvent";
+
+impl message::Message for ConformanceEvent {
+    fn name(&self) -> &'static str {
+        CONFORMANCE_EVENT_NAME
+    }
+}
+
+const STREAM_NAMES: [&str; 8] = [
+    "versions",
+    "conflicts",
+    "concurrency",
+    "retries",
+    "ordering",
+    "ordering-other",
+    "global-a",
+    "global-b",
+];
+
+static RUNS: AtomicU64 = AtomicU64::new(0);
+
+fn events(values: impl IntoIterator<Item = u64>) -> Vec<event::Envelope<ConformanceEvent>> {
+    values
+        .into_iter()
+        .map(|value| event::Envelope::from(ConformanceEvent { value }))
+        .collect()
+}
+
+/// A suite of behavior tests for an [Event Store][event::Store] implementation,
+/// using [String]s as Event Stream ids.
+///
+/// Each check panics with a descriptive message when the Event Store does not behave
+/// as expected, so it can be called directly from a test function. [`Conformance::run`]
+/// runs all the checks, which can also be called one by one, e.g. for Event Stores
+/// that do not implement [`GlobalStreamer`].
+#[derive(Debug, Clone)]
+pub struct Conformance<S> {
+    store: S,
+    run_id: String,
+}
+
+impl<S> Conformance<S> {
+    /// Creates a new [Conformance] suite for the specified Event Store.
+    pub fn new(store: S) -> Self {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_nanos())
+            .unwrap_or_default();
+
+        Self {
+            store,
+            run_id: format!("{nanos:x}-{}", RUNS.fetch_add(1, Ordering::SeqCst)),
+        }
+    }
+
+    fn stream_id(&self, name: &str) -> String {
+        format!("conformance-{}-{name}", self.run_id)
+    }
+
+    /// Deletes all the Event Streams written by the checks, so that the
+    /// [`ConformanceEvent`]s do not interfere with other users of a shared backend.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if an Event Stream could not be deleted.
+    pub async fn clean_up(&self) -> Result<(), DeleteError>
+    where
+        S: StreamDeleter<String, ConformanceEvent>,
+    {
+        for name in STREAM_NAMES {
+            self.store.delete(&self.stream_id(name)).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn stream(
+        &self,
+        id: &String,
+        select: event::VersionSelect,
+    ) -> Vec<(version::Version, u64)>
+    where
+        S: Streamer<String, ConformanceEvent>,
+        <S as Streamer<String, ConformanceEvent>>::Error: Debug,
+    {
+        self.store
+            .stream(id, select)
+            .map_ok(|event| (event.version, event.event.message.value))
+            .try_collect()
+            .await
+            .expect("streaming the event stream should not fail")
+    }
+
+    /// Runs all the checks of the suite.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the Event Store fails any of the checks.
+    pub async fn run(&self)
+    where
+        S: event::Store<String, ConformanceEvent> + GlobalStreamer<String, ConformanceEvent>,
+        <S as Streamer<String, ConformanceEvent>>::Error: Debug,
+        <S as GlobalStreamer<String, ConformanceEvent>>::Error: Debug,
+    {
+        self.append_assigns_consecutive_versions().await;
+        self.version_conflicts_are_rejected().await;
+        self.concurrent_appends_are_serialized().await;
+        self.retried_appends_are_not_duplicated().await;
+        self.stream_ordering_is_preserved().await;
+        self.global_ordering_is_preserved().await;
+    }
+
+    /// Checks that appended Domain Events are assigned consecutive
+    /// [Version][version::Version]s, starting from 1 in a new Event Stream.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the Event Store fails the check.
+    pub async fn append_assigns_consecutive_versions(&self)
+    where
+        S: event::Store<String, ConformanceEvent>,
+        <S as Streamer<String, ConformanceEvent>>::Error: Debug,
+    {
+        let id = self.stream_id("versions");
+
+        assert!(
+            self.stream(&id, event::VersionSelect::All).await.is_empty(),
+            "a new event stream should be empty"
+        );
+
+        let version = self
+            .store
+            .append(id.clone(), version::Check::Any, events([1, 2]))
+            .await
+            .expect("appending to a new event stream should not fail");
+
+        assert_eq!(
+            2, version,
+            "appending to a new event stream should start from version 1"
+        );
+
+        let version = self
+            .store
+            .append(id.clone(), version::Check::MustBe(2), events([3]))
+            .await
+            .expect("appending with the current version should not fail");
+
+        assert_eq!(
+            3, version,
+            "appending should return the new event stream version"
+        );
+
+        assert_eq!(
+            vec![(1, 1), (2, 2), (3, 3)],
+            self.stream(&id, event::VersionSelect::All).await,
+            "the appended events should be streamed with consecutive versions"
+        );
+    }
+
+    /// Checks that appending with an outdated [`version::Check`] fails with
+    /// a [`version::ConflictError`], leaving the Event Stream untouched.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the Event Store fails the check.
+    pub async fn version_conflicts_are_rejected(&self)
+    where
+        S: event::Store<String, ConformanceEvent>,
+        <S as Streamer<String, ConformanceEvent>>::Error: Debug,
+    {
+        let id = self.stream_id("conflicts");
+
+        self.store
+            .append(id.clone(), version::Check::MustBe(0), events([1]))
+            .await
+            .expect("appending to a new event stream should not fail");
+
+        for expected in [0, 2] {
+            let result = self
+                .store
+                .append(id.clone(), version::Check::MustBe(expected), events([2]))
+                .await;
+
+            match result {
+                Err(AppendError::Conflict(err)) => assert_eq!(
+                    version::ConflictError {
+                        expected,
+                        actual: 1
+                    },
+                    err,
+                    "the conflict error should report the expected and actual versions"
+                ),
+                result => panic!(
+                    "appending with version {expected} should fail with a conflict, got: {result:?}"
+                ),
+            }
+        }
+
+        assert_eq!(
+            vec![(1, 1)],
+            self.stream(&id, event::VersionSelect::All).await,
+            "rejected appends should not modify the event stream"
+        );
+    }
+
+    /// Checks that, out of many concurrent appends expecting the same
+    /// [Version][version::Version], exactly one succeeds.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the Event Store fails the check.
+    pub async fn concurrent_appends_are_serialized(&self)
+    where
+        S: event::Store<String, ConformanceEvent>,
+        <S as Streamer<String, ConformanceEvent>>::Error: Debug,
+    {
+        const CONCURRENCY: u64 = 8;
+
+        let id = self.stream_id("concurrency");
+
+        let results = join_all((1..=CONCURRENCY).map(|value| {
+            self.store
+                .append(id.clone(), version::Check::MustBe(0), events([value]))
+        }))
+        .await;
+
+        let succeeded = results.iter().filter(|result| result.is_ok()).count();
+
+        assert_eq!(1, succeeded, "exactly one concurrent append should succeed");
+
+        for result in results.iter().filter(|result| result.is_err()) {
+            assert!(
+                matches!(result, Err(AppendError::Conflict(_))),
+                "concurrent appends should fail with a conflict, got: {result:?}"
+            );
+        }
+
+        assert_eq!(
+            1,
+            self.stream(&id, event::VersionSelect::All).await.len(),
+            "only the events of the successful append should be in the event stream"
+        );
+    }
+
+    /// Checks that retrying an append that has already succeeded, e.g. after
+    /// a network failure, is rejected instead of duplicating the Domain Events.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the Event Store fails the check.
+    pub async fn retried_appends_are_not_duplicated(&self)
+    where
+        S: event::Store<String, ConformanceEvent>,
+        <S as Streamer<String, ConformanceEvent>>::Error: Debug,
+    {
+        let id = self.stream_id("retries");
+
+        for attempt in 0..2 {
+            let result = self
+                .store
+                .append(id.clone(), version::Check::MustBe(0), events([1, 2]))
+                .await;
+
+            match attempt {
+                0 => assert_eq!(2, result.expect("the first attempt should not fail")),
+                _ => assert!(
+                    matches!(result, Err(AppendError::Conflict(_))),
+                    "the retried append should fail with a conflict, got: {result:?}"
+                ),
+            }
+        }
+
+        assert_eq!(
+            vec![(1, 1), (2, 2)],
+            self.stream(&id, event::VersionSelect::All).await,
+            "the retried append should not duplicate the events"
+        );
+    }
+
+    /// Checks that Domain Events are streamed in the order they have been appended,
+    /// honoring the [`event::VersionSelect`] and without leaking other Event Streams.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the Event Store fails the check.
+    pub async fn stream_ordering_is_preserved(&self)
+    where
+        S: event::Store<String, ConformanceEvent>,
+        <S as Streamer<String, ConformanceEvent>>::Error: Debug,
+    {
+        let id = self.stream_id("ordering");
+        let other_id = self.stream_id("ordering-other");
+
+        for values in [[1, 2], [3, 4], [5, 6]] {
+            self.store
+                .append(id.clone(), version::Check::Any, events(values))
+                .await
+                .expect("appending should not fail");
+
+            self.store
+                .append(other_id.clone(), version::Check::Any, events([0]))
+                .await
+                .expect("appending should not fail");
+        }
+
+        assert_eq!(
+            vec![(1, 1), (2, 2), (3, 3), (4, 4), (5, 5), (6, 6)],
+            self.stream(&id, event::VersionSelect::All).await,
+            "events should be streamed in the order they have been appended"
+        );
+
+        assert_eq!(
+            vec![(4, 4), (5, 5), (6, 6)],
+            self.stream(&id, event::VersionSelect::From(4)).await,
+            "events should be streamed from the selected version"
+        );
+
+        assert!(
+            self.stream(&id, event::VersionSelect::From(7))
+                .await
+                .is_empty(),
+            "no events should be streamed past the last version"
+        );
+    }
+
+    /// Checks that all the Domain Events are streamed across Event Streams in
+    /// the order they have been appended, with increasing [Sequence][event::Sequence]s.
+    ///
+    /// Only the [`ConformanceEvent`]s are streamed, through
+    /// [`GlobalStreamer::stream_all_filtered`], since the Event Store might
+    /// contain Domain Events of other types.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the Event Store fails the check.
+    pub async fn global_ordering_is_preserved(&self)
+    where
+        S: Appender<String, ConformanceEvent> + GlobalStreamer<String, ConformanceEvent>,
+        <S as GlobalStreamer<String, ConformanceEvent>>::Error: Debug,
+    {
+        let ids = [self.stream_id("global-a"), self.stream_id("global-b")];
+        let mut appended = Vec::new();
+
+        for value in 1..=6 {
+            let id = &ids[usize::from(value % 3 == 0)];
+
+            self.store
+                .append(id.clone(), version::Check::Any, events([value]))
+                .await
+                .expect("appending should not fail");
+
+            appended.push((id.clone(), value));
+        }
+
+        let streamed: Vec<_> = self
+            .store
+            .stream_all_filtered(
+                event::SequenceSelect::All,
+                event::NameSelect::only([CONFORMANCE_EVENT_NAME]),
+            )
+            .try_filter(|event| ready(ids.contains(&event.event.stream_id)))
+            .try_collect()
+            .await
+            .expect("streaming all the events should not fail");
+
+        assert!(
+            streamed
+                .windows(2)
+                .all(|pair| pair[0].sequence < pair[1].sequence),
+            "events should be streamed with increasing sequence numbers"
+        );
+
+        assert_eq!(
+            appended,
+            streamed
+                .into_iter()
+                .map(|event| (event.event.stream_id, event.event.event.message.value))
+                .collect::<Vec<_>>(),
+            "events should be streamed in the order they have been appended"
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::store::InMemory;
+
+    #[tokio::test]
+    async fn in_memory_event_store_passes_the_conformance_suite() {
+        Conformance::new(InMemory::<String, ConformanceEvent>::default())
+            .run()
+            .await;
+    }
+}
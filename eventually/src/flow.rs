@@ -0,0 +1,460 @@
+//! Module `flow` contains an optional runtime to collect the causal edges
+//! between the Commands and Domain Events flowing through the system,
+//! useful to debug workflows spanning multiple Aggregates (e.g. through sagas).
+//!
+//! Messages are grouped in a [Graph] by their correlation id, and linked
+//! through their causation id: make sure to propagate both using
+//! [`message::Envelope::caused_by`] and [`aggregate::Root::caused_by`][crate::aggregate::Root::caused_by].
+//!
+//! Commands are collected by wrapping the Command [Handler]s in a [`CollectingHandler`],
+//! while Domain Events are collected by wrapping the Event [Store][event::Store]
+//! in a [`CollectingEventStore`]. Both report to the same [Collector],
+//! which exports the [Graph] of a correlation id as DOT or JSON.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use crate::command::{self, Handler};
+use crate::version::{self, Version};
+use crate::{event, message};
+
+/// The kind of [Message][message::Message] represented by a [Node].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NodeKind {
+    /// A Command, collected by a [`CollectingHandler`].
+    Command,
+    /// A Domain Event, collected by a [`CollectingEventStore`].
+    Event,
+}
+
+/// A Command or Domain Event in a [Graph].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Node {
+    /// The message id of the Command or Domain Event, or a generated one if missing.
+    pub id: String,
+    /// Whether the node is a Command or a Domain Event.
+    pub kind: NodeKind,
+    /// The name of the Command or Domain Event message.
+    pub name: String,
+}
+
+/// A causal edge between two [Node]s of a [Graph].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Edge {
+    /// The id of the [Node] that caused the other one.
+    pub from: String,
+    /// The id of the [Node] caused by the other one.
+    pub to: String,
+}
+
+/// The causal graph of all the Commands and Domain Events sharing
+/// the same correlation id.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Graph {
+    /// The correlation id shared by all the [Node]s in the graph.
+    pub correlation_id: String,
+    /// The Commands and Domain Events, in the order they have been collected.
+    pub nodes: Vec<Node>,
+    /// The causal edges between the [Node]s.
+    pub edges: Vec<Edge>,
+}
+
+impl Graph {
+    /// Exports the [Graph] in the DOT format, to be rendered with Graphviz.
+    ///
+    /// Commands are rendered as boxes, Domain Events as ellipses.
+    #[must_use]
+    pub fn to_dot(&self) -> String {
+        let mut dot = format!("digraph {} {{\n", quote(&self.correlation_id));
+
+        for node in &self.nodes {
+            let shape = match node.kind {
+                NodeKind::Command => "box",
+                NodeKind::Event => "ellipse",
+            };
+
+            _ = writeln!(
+                dot,
+                "  {} [label={}, shape={shape}];",
+                quote(&node.id),
+                quote(&node.name)
+            );
+        }
+
+        for edge in &self.edges {
+            _ = writeln!(dot, "  {} -> {};", quote(&edge.from), quote(&edge.to));
+        }
+
+        dot.push('}');
+        dot
+    }
+
+    /// Exports the [Graph] in the JSON format.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the [Graph] could not be serialized.
+    #[cfg(feature = "serde-json")]
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string(self)
+    }
+
+    fn add(&mut self, node: Node, causation_id: Option<&str>) {
+        if let Some(causation_id) = causation_id {
+            self.edges.push(Edge {
+                from: causation_id.to_owned(),
+                to: node.id.clone(),
+            });
+        }
+
+        self.nodes.push(node);
+    }
+}
+
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Collects the Commands and Domain Events flowing through the system
+/// in a [Graph] per correlation id.
+///
+/// Messages with neither a correlation id nor a message id are not collected.
+/// Graphs are kept in memory until they are [taken][Collector::take].
+#[derive(Debug, Clone, Default)]
+pub struct Collector {
+    graphs: Arc<Mutex<HashMap<String, Graph>>>,
+}
+
+impl Collector {
+    /// Returns a copy of the [Graph] collected so far for the specified correlation id.
+    #[must_use]
+    pub fn graph(&self, correlation_id: &str) -> Option<Graph> {
+        self.lock_graphs().get(correlation_id).cloned()
+    }
+
+    /// Removes the [Graph] collected for the specified correlation id
+    /// from the [Collector], and returns it.
+    #[must_use]
+    pub fn take(&self, correlation_id: &str) -> Option<Graph> {
+        self.lock_graphs().remove(correlation_id)
+    }
+
+    /// Returns the correlation ids of all the [Graph]s collected so far.
+    #[must_use]
+    pub fn correlation_ids(&self) -> Vec<String> {
+        self.lock_graphs().keys().cloned().collect()
+    }
+
+    // NOTE: collecting is a debugging aid, so a thread panicking while holding the lock
+    // can at most leave a partially collected graph: the graphs are used anyway,
+    // instead of failing all the Commands and Domain Events flowing afterwards.
+    fn lock_graphs(&self) -> MutexGuard<'_, HashMap<String, Graph>> {
+        self.graphs.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn collect<T>(
+        &self,
+        kind: NodeKind,
+        envelope: &message::Envelope<T>,
+        fallback_id: impl FnOnce(usize) -> String,
+    ) where
+        T: message::Message,
+    {
+        // NOTE: a message with no correlation id starts its own flow,
+        // same as done by message::Envelope::caused_by.
+        let Some(correlation_id) = envelope.correlation_id().or(envelope.message_id()) else {
+            return;
+        };
+
+        let mut graphs = self.lock_graphs();
+        let graph = graphs
+            .entry(correlation_id.to_owned())
+            .or_insert_with(|| Graph {
+                correlation_id: correlation_id.to_owned(),
+                ..Graph::default()
+            });
+
+        let node = Node {
+            id: envelope
+                .message_id()
+                .map_or_else(|| fallback_id(graph.nodes.len()), str::to_owned),
+            kind,
+            name: envelope.message.name().to_owned(),
+        };
+
+        graph.add(node, envelope.causation_id());
+    }
+}
+
+/// Decorator for a Command [Handler] that collects all the Commands it handles
+/// in a [Collector].
+///
+/// Commands with no message id are identified by their name and their position
+/// in the [Graph], in the `<name>#<position>` format.
+#[derive(Debug, Clone)]
+pub struct CollectingHandler<H> {
+    handler: H,
+    collector: Collector,
+}
+
+impl<H> CollectingHandler<H> {
+    /// Returns a new [`CollectingHandler`] collecting the Commands handled by
+    /// the specified [Handler].
+    pub fn new(handler: H, collector: Collector) -> Self {
+        Self { handler, collector }
+    }
+}
+
+#[async_trait]
+impl<T, H> Handler<T> for CollectingHandler<H>
+where
+    T: message::Message + Send + Sync + 'static,
+    H: Handler<T>,
+{
+    type Error = H::Error;
+
+    async fn handle(&self, command: command::Envelope<T>) -> Result<(), Self::Error> {
+        self.collector
+            .collect(NodeKind::Command, &command, |position| {
+                format!("{}#{position}", command.message.name())
+            });
+
+        self.handler.handle(command).await
+    }
+}
+
+/// [`event::Store`] type wrapper that collects all the Domain Events
+/// successfully appended to the Event Store in a [Collector].
+///
+/// Domain Events with no message id are identified by their Event Stream id
+/// and version, in the `<stream id>@<version>` format.
+#[derive(Debug, Clone)]
+pub struct CollectingEventStore<S> {
+    store: S,
+    collector: Collector,
+}
+
+impl<S> CollectingEventStore<S> {
+    /// Returns a new [`CollectingEventStore`] collecting the Domain Events
+    /// appended to the specified Event Store.
+    pub fn new(store: S, collector: Collector) -> Self {
+        Self { store, collector }
+    }
+
+    fn collect_appended<Event>(
+        &self,
+        id: &str,
+        new_version: Version,
+        events: &[event::Envelope<Event>],
+    ) where
+        Event: message::Message,
+    {
+        let first_version = new_version - events.len() as Version + 1;
+
+        for (version, event) in (first_version..).zip(events) {
+            self.collector
+                .collect(NodeKind::Event, event, |_| format!("{id}@{version}"));
+        }
+    }
+}
+
+impl<S, StreamId, Event> event::store::Streamer<StreamId, Event> for CollectingEventStore<S>
+where
+    S: event::store::Streamer<StreamId, Event>,
+    StreamId: Send + Sync,
+    Event: message::Message + Send + Sync,
+{
+    type Error = S::Error;
+
+    fn stream(
+        &self,
+        id: &StreamId,
+        select: event::VersionSelect,
+    ) -> event::Stream<'_, StreamId, Event, Self::Error> {
+        self.store.stream(id, select)
+    }
+
+    fn stream_filtered<'a>(
+        &'a self,
+        id: &StreamId,
+        select: event::VersionSelect,
+        names: event::NameSelect,
+    ) -> event::Stream<'a, StreamId, Event, Self::Error>
+    where
+        StreamId: 'a,
+        Event: 'a,
+        Self::Error: 'a,
+    {
+        self.store.stream_filtered(id, select, names)
+    }
+}
+
+#[async_trait]
+impl<S, StreamId, Event> event::store::Appender<StreamId, Event> for CollectingEventStore<S>
+where
+    S: event::store::Appender<StreamId, Event>,
+    StreamId: ToString + Send + Sync + 'static,
+    Event: message::Message + Clone + Send + Sync + 'static,
+{
+    async fn append(
+        &self,
+        id: StreamId,
+        version_check: version::Check,
+        events: Vec<event::Envelope<Event>>,
+    ) -> Result<Version, event::store::AppendError> {
+        let stream_id = id.to_string();
+        let new_version = self.store.append(id, version_check, events.clone()).await?;
+
+        self.collect_appended(&stream_id, new_version, &events);
+
+        Ok(new_version)
+    }
+
+    async fn append_multi(
+        &self,
+        appends: Vec<event::store::StreamAppend<StreamId, Event>>,
+    ) -> Result<Vec<Version>, event::store::AppendError>
+    where
+        StreamId: 'async_trait,
+        Event: 'async_trait,
+    {
+        let collected: Vec<_> = appends
+            .iter()
+            .map(|append| (append.id.to_string(), append.events.clone()))
+            .collect();
+
+        let new_versions = self.store.append_multi(appends).await?;
+
+        for ((stream_id, events), new_version) in collected.iter().zip(&new_versions) {
+            self.collect_appended(stream_id, *new_version, events);
+        }
+
+        Ok(new_versions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::store::{Appender, InMemory};
+    use crate::message::tests::StringMessage;
+
+    struct OpenAccount(CollectingEventStore<InMemory<&'static str, StringMessage>>);
+
+    #[async_trait]
+    impl Handler<StringMessage> for OpenAccount {
+        type Error = event::store::AppendError;
+
+        async fn handle(
+            &self,
+            command: command::Envelope<StringMessage>,
+        ) -> Result<(), Self::Error> {
+            let event = event::Envelope::from(StringMessage("AccountOpened"))
+                .with_message_id(format!("{}:opened", command.message.0))
+                .caused_by(&command);
+
+            // The second Domain Event has no message id.
+            let another_event =
+                event::Envelope::from(StringMessage("BonusGranted")).caused_by(&command);
+
+            self.0
+                .append(
+                    command.message.0,
+                    version::Check::Any,
+                    vec![event, another_event],
+                )
+                .await
+                .map(|_| ())
+        }
+    }
+
+    #[tokio::test]
+    async fn collector_links_commands_and_events_by_causation() {
+        let collector = Collector::default();
+        let event_store = CollectingEventStore::new(InMemory::default(), collector.clone());
+        let handler = CollectingHandler::new(OpenAccount(event_store), collector.clone());
+
+        let command = command::Envelope::from(StringMessage("account:1"))
+            .with_message_id("command-1".to_owned())
+            .with_correlation_id("request-1".to_owned());
+
+        handler
+            .handle(command)
+            .await
+            .expect("command should be handled");
+
+        // A saga reacting to the Domain Event issues a new Command.
+        let saga_event = event::Envelope::from(StringMessage("AccountOpened"))
+            .with_message_id("account:1:opened".to_owned())
+            .with_correlation_id("request-1".to_owned());
+
+        handler
+            .handle(command::Envelope::from(StringMessage("account:2")).caused_by(&saga_event))
+            .await
+            .expect("command should be handled");
+
+        let graph = collector
+            .take("request-1")
+            .expect("graph should be collected");
+
+        assert_eq!(
+            vec![
+                ("command-1", NodeKind::Command),
+                ("account:1:opened", NodeKind::Event),
+                ("account:1@2", NodeKind::Event),
+                ("string_payload#3", NodeKind::Command),
+                ("account:2:opened", NodeKind::Event),
+                ("account:2@2", NodeKind::Event),
+            ],
+            graph
+                .nodes
+                .iter()
+                .map(|node| (node.id.as_str(), node.kind))
+                .collect::<Vec<_>>()
+        );
+
+        assert!(graph
+            .to_dot()
+            .contains(r#"  "command-1" -> "account:1:opened";"#));
+        assert!(graph
+            .to_dot()
+            .contains(r#"  "account:1:opened" -> "string_payload#3";"#));
+        assert!(collector.graph("request-1").is_none());
+    }
+
+    #[tokio::test]
+    async fn collector_keeps_collecting_after_a_poisoned_lock() {
+        let collector = Collector::default();
+        let poisoner = collector.clone();
+
+        std::thread::spawn(move || {
+            let _graphs = poisoner.graphs.lock().unwrap();
+            panic!("poisoning the collected graphs");
+        })
+        .join()
+        .expect_err("the thread should panic");
+
+        let handler = CollectingHandler::new(
+            OpenAccount(CollectingEventStore::new(
+                InMemory::default(),
+                collector.clone(),
+            )),
+            collector.clone(),
+        );
+
+        handler
+            .handle(
+                command::Envelope::from(StringMessage("account:1"))
+                    .with_message_id("command-1".to_owned()),
+            )
+            .await
+            .expect("command should be handled");
+
+        assert_eq!(vec!["command-1".to_owned()], collector.correlation_ids());
+    }
+}
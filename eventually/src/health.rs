@@ -0,0 +1,162 @@
+//! Module `health` contains the [Check] abstraction, implemented by the
+//! Event Stores and subscriptions that depend on external systems,
+//! and the [Checks] combinator to wire them into readiness probes.
+
+use std::fmt::Write;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use futures::future::join_all;
+
+/// A health check of a component, e.g. an Event Store, verifying that
+/// the external systems it depends on (like a database) are reachable and usable.
+#[async_trait]
+pub trait Check: Send + Sync {
+    /// Runs the health check, returning an error describing why
+    /// the component is unhealthy, if any.
+    async fn check(&self) -> anyhow::Result<()>;
+}
+
+#[async_trait]
+impl<T> Check for Arc<T>
+where
+    T: Check + ?Sized,
+{
+    async fn check(&self) -> anyhow::Result<()> {
+        (**self).check().await
+    }
+}
+
+/// The outcome of a single named [Check], as part of a [Report].
+#[derive(Debug)]
+pub struct Outcome {
+    /// The name the [Check] has been registered with.
+    pub name: String,
+    /// The result of the [Check].
+    pub result: anyhow::Result<()>,
+}
+
+/// The outcomes of all the [Check]s run by [`Checks::run`].
+#[derive(Debug)]
+pub struct Report {
+    /// The outcome of each [Check], in registration order.
+    pub outcomes: Vec<Outcome>,
+}
+
+impl Report {
+    /// Returns whether all the [Check]s have succeeded.
+    #[must_use]
+    pub fn is_healthy(&self) -> bool {
+        self.outcomes.iter().all(|outcome| outcome.result.is_ok())
+    }
+}
+
+/// Aggregates multiple named [Check]s, running them concurrently.
+///
+/// [Checks] implements [Check] itself, failing if any of its [Check]s fails,
+/// so it can be nested or passed to anything expecting a single [Check].
+#[derive(Default, Clone)]
+pub struct Checks {
+    checks: Vec<(String, Arc<dyn Check>)>,
+}
+
+impl std::fmt::Debug for Checks {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list()
+            .entries(self.checks.iter().map(|(name, _)| name))
+            .finish()
+    }
+}
+
+impl Checks {
+    /// Adds a new [Check] with the specified name.
+    #[must_use]
+    pub fn with<C>(mut self, name: impl Into<String>, check: C) -> Self
+    where
+        C: Check + 'static,
+    {
+        self.checks.push((name.into(), Arc::new(check)));
+        self
+    }
+
+    /// Runs all the [Check]s concurrently, returning the [Report] of their outcomes.
+    pub async fn run(&self) -> Report {
+        let outcomes = join_all(self.checks.iter().map(|(name, check)| async move {
+            Outcome {
+                name: name.clone(),
+                result: check.check().await,
+            }
+        }))
+        .await;
+
+        Report { outcomes }
+    }
+}
+
+#[async_trait]
+impl Check for Checks {
+    async fn check(&self) -> anyhow::Result<()> {
+        let report = self.run().await;
+
+        if report.is_healthy() {
+            return Ok(());
+        }
+
+        let mut message = String::from("unhealthy components:");
+
+        for outcome in report.outcomes {
+            if let Err(err) = outcome.result {
+                let _ = write!(message, " {}: {err};", outcome.name);
+            }
+        }
+
+        Err(anyhow::anyhow!(message.trim_end_matches(';').to_owned()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Static(Option<&'static str>);
+
+    #[async_trait]
+    impl Check for Static {
+        async fn check(&self) -> anyhow::Result<()> {
+            match self.0 {
+                None => Ok(()),
+                Some(err) => Err(anyhow::anyhow!(err)),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn checks_aggregate_the_outcome_of_all_checks() {
+        let checks = Checks::default()
+            .with("database", Static(None))
+            .with("broker", Static(Some("connection refused")));
+
+        let report = checks.run().await;
+
+        assert!(!report.is_healthy());
+        assert_eq!(
+            vec![("database", true), ("broker", false)],
+            report
+                .outcomes
+                .iter()
+                .map(|outcome| (outcome.name.as_str(), outcome.result.is_ok()))
+                .collect::<Vec<_>>()
+        );
+
+        assert_eq!(
+            "unhealthy components: broker: connection refused",
+            checks.check().await.unwrap_err().to_string()
+        );
+
+        assert!(Checks::default()
+            .with("database", Static(None))
+            .check()
+            .await
+            .is_ok());
+    }
+}
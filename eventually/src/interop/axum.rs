@@ -0,0 +1,454 @@
+//! Module `axum` contains the extractors to receive Commands and Queries
+//! in `axum` HTTP handlers, and the [Error] response mapping the errors
+//! returned by this crate into HTTP status codes.
+//!
+//! Routes only dispatching a Command or a Query through the [`command::Bus`]
+//! or the [`query::Bus`] can use [`dispatch_command`] and [`dispatch_query`]
+//! directly as their handler:
+//!
+//! ```ignore
+//! let app = Router::new()
+//!     .route("/accounts", post(dispatch_command::<OpenBankAccount>))
+//!     .route("/accounts/balance", get(dispatch_query::<GetBalance, Balance>))
+//!     .with_state(state); // Implementing FromRef for both Buses.
+//! ```
+
+use axum::async_trait;
+use axum::extract::rejection::{JsonRejection, QueryRejection};
+use axum::extract::{FromRequest, FromRequestParts, State};
+use axum::http::request::Parts;
+use axum::http::{HeaderMap, Request, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::aggregate::repository::GetError;
+use crate::command::validate::ValidationError;
+use crate::message::{self, Message};
+use crate::version::ConflictError;
+use crate::{command, query};
+
+/// Request headers copied into the [Metadata][message::Metadata] of the Commands
+/// and Queries extracted from a request, when present.
+pub const METADATA_HEADERS: [&str; 5] = [
+    message::MESSAGE_ID_KEY,
+    message::CORRELATION_ID_KEY,
+    message::CAUSATION_ID_KEY,
+    message::TENANT_ID_KEY,
+    message::IDEMPOTENCY_KEY,
+];
+
+fn metadata_from(headers: &HeaderMap) -> message::Metadata {
+    METADATA_HEADERS
+        .iter()
+        .filter_map(|key| {
+            let value = headers.get(*key)?.to_str().ok()?;
+            Some(((*key).to_owned(), value.to_owned()))
+        })
+        .collect()
+}
+
+/// Extractor of a [Command][command::Envelope] from the JSON body of a request,
+/// with the [`METADATA_HEADERS`] of the request as its metadata.
+#[derive(Debug, Clone)]
+pub struct Command<T>(pub command::Envelope<T>)
+where
+    T: Message;
+
+impl<T> Command<T>
+where
+    T: Message + Send + Sync + 'static,
+{
+    /// Dispatches the Command through the [`command::Bus`],
+    /// responding with `204 No Content` once it has been handled.
+    ///
+    /// # Errors
+    ///
+    /// The [Error] returned by the [`command::Bus`] is returned as the response.
+    pub async fn dispatch(self, bus: &command::Bus) -> Result<StatusCode, Error> {
+        bus.dispatch(self.0).await?;
+        Ok(StatusCode::NO_CONTENT)
+    }
+}
+
+#[async_trait]
+impl<T, S, B> FromRequest<S, B> for Command<T>
+where
+    T: Message + Send,
+    S: Send + Sync,
+    B: Send + 'static,
+    Json<T>: FromRequest<S, B, Rejection = JsonRejection>,
+{
+    type Rejection = Error;
+
+    async fn from_request(req: Request<B>, state: &S) -> Result<Self, Self::Rejection> {
+        let metadata = metadata_from(req.headers());
+        let Json(message) =
+            Json::<T>::from_request(req, state)
+                .await
+                .map_err(|rejection: JsonRejection| {
+                    Error::rejected(rejection.status(), rejection.body_text())
+                })?;
+
+        Ok(Self(command::Envelope { message, metadata }))
+    }
+}
+
+/// Extractor of a [Query][query::Envelope] from the query string of a request,
+/// with the [`METADATA_HEADERS`] of the request as its metadata.
+#[derive(Debug, Clone)]
+pub struct Query<T>(pub query::Envelope<T>)
+where
+    T: Message;
+
+impl<T> Query<T>
+where
+    T: Message + Send + Sync + 'static,
+{
+    /// Dispatches the Query through the [`query::Bus`],
+    /// responding with its output as JSON.
+    ///
+    /// # Errors
+    ///
+    /// The [Error] returned by the [`query::Bus`] is returned as the response.
+    pub async fn dispatch<R>(self, bus: &query::Bus) -> Result<Json<R>, Error>
+    where
+        R: Serialize + Clone + Send + Sync + 'static,
+    {
+        Ok(Json(bus.dispatch(self.0).await?))
+    }
+}
+
+#[async_trait]
+impl<T, S> FromRequestParts<S> for Query<T>
+where
+    T: Message + DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = Error;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let axum::extract::Query(message) =
+            axum::extract::Query::<T>::from_request_parts(parts, state)
+                .await
+                .map_err(|rejection: QueryRejection| {
+                    Error::rejected(rejection.status(), rejection.body_text())
+                })?;
+
+        Ok(Self(query::Envelope {
+            message,
+            metadata: metadata_from(&parts.headers),
+        }))
+    }
+}
+
+/// Handler dispatching the [Command] in the request through the [`command::Bus`]
+/// of the router state.
+///
+/// # Errors
+///
+/// The [Error] returned by the [`command::Bus`] is returned as the response.
+pub async fn dispatch_command<T>(
+    State(bus): State<command::Bus>,
+    command: Command<T>,
+) -> Result<StatusCode, Error>
+where
+    T: Message + Send + Sync + 'static,
+{
+    command.dispatch(&bus).await
+}
+
+/// Handler dispatching the [Query] in the request through the [`query::Bus`]
+/// of the router state, responding with its output of type `R` as JSON.
+///
+/// # Errors
+///
+/// The [Error] returned by the [`query::Bus`] is returned as the response.
+pub async fn dispatch_query<T, R>(
+    State(bus): State<query::Bus>,
+    query: Query<T>,
+) -> Result<Json<R>, Error>
+where
+    T: Message + Send + Sync + 'static,
+    R: Serialize + Clone + Send + Sync + 'static,
+{
+    query.dispatch(&bus).await
+}
+
+/// Message of the responses with `500 Internal Server Error` status code.
+const INTERNAL_SERVER_ERROR_MESSAGE: &str = "internal server error";
+
+/// Error response, sent to the client as a JSON object with an `error` field.
+///
+/// Any error can be converted into an [Error], looking for the errors of this crate
+/// in its chain of causes to pick the status code:
+/// - [`GetError::NotFound`] is mapped to `404 Not Found`,
+/// - [`ConflictError`] is mapped to `409 Conflict`,
+/// - [`ValidationError`], also returned by the [`command::bus::Validation`]
+///   middleware, is mapped to `422 Unprocessable Entity`,
+/// - [`query::bus::Error::Timeout`] is mapped to `504 Gateway Timeout`,
+///
+/// falling back to `500 Internal Server Error` if none is found.
+///
+/// The response of a `500 Internal Server Error` only carries a generic message,
+/// so that internal details are not leaked to the client: the whole error
+/// is logged instead, with the `tracing` feature enabled.
+#[derive(Debug)]
+pub struct Error {
+    status: StatusCode,
+    message: String,
+}
+
+impl Error {
+    /// Returns the status code of the response.
+    #[must_use]
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    fn rejected(status: StatusCode, message: String) -> Self {
+        Self { status, message }
+    }
+}
+
+impl<E> From<E> for Error
+where
+    E: Into<anyhow::Error>,
+{
+    fn from(err: E) -> Self {
+        let err: anyhow::Error = err.into();
+
+        let status = err
+            .chain()
+            .find_map(|err| {
+                if let Some(err) = err.downcast_ref::<GetError>() {
+                    return matches!(err, GetError::NotFound).then_some(StatusCode::NOT_FOUND);
+                }
+
+                if let Some(err) = err.downcast_ref::<query::bus::Error>() {
+                    return matches!(err, query::bus::Error::Timeout(_))
+                        .then_some(StatusCode::GATEWAY_TIMEOUT);
+                }
+
+                if err.is::<ConflictError>() {
+                    return Some(StatusCode::CONFLICT);
+                }
+
+                err.is::<ValidationError>()
+                    .then_some(StatusCode::UNPROCESSABLE_ENTITY)
+            })
+            .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+
+        if status == StatusCode::INTERNAL_SERVER_ERROR {
+            #[cfg(feature = "tracing")]
+            tracing::error!(error = format!("{err:#}"), "failed to handle http request");
+
+            return Self {
+                status,
+                message: INTERNAL_SERVER_ERROR_MESSAGE.to_owned(),
+            };
+        }
+
+        Self {
+            status,
+            message: format!("{err:#}"),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct ErrorBody {
+    error: String,
+}
+
+impl IntoResponse for Error {
+    fn into_response(self) -> Response {
+        let body = ErrorBody {
+            error: self.message,
+        };
+
+        (self.status, Json(body)).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::{Body, HttpBody};
+    use axum::extract::FromRef;
+    use axum::routing::{get, post};
+    use axum::Router;
+    use serde::Deserialize;
+    use tower::ServiceExt;
+
+    use super::*;
+    use crate::command::validate::Violation;
+
+    #[derive(Debug, Clone, Deserialize)]
+    struct RenameUser {
+        name: String,
+    }
+
+    impl Message for RenameUser {
+        fn name(&self) -> &'static str {
+            "RenameUser"
+        }
+    }
+
+    #[derive(Debug, Clone, Deserialize)]
+    struct GetUserName {
+        id: u32,
+    }
+
+    impl Message for GetUserName {
+        fn name(&self) -> &'static str {
+            "GetUserName"
+        }
+    }
+
+    #[derive(Clone)]
+    struct AppState {
+        commands: command::Bus,
+        queries: query::Bus,
+    }
+
+    impl FromRef<AppState> for command::Bus {
+        fn from_ref(state: &AppState) -> Self {
+            state.commands.clone()
+        }
+    }
+
+    impl FromRef<AppState> for query::Bus {
+        fn from_ref(state: &AppState) -> Self {
+            state.queries.clone()
+        }
+    }
+
+    fn app() -> Router {
+        let commands = command::Bus::default()
+            .register(|command: command::Envelope<RenameUser>| async move {
+                if command.message.name.is_empty() {
+                    return Err(anyhow::Error::new(ValidationError::from(Violation::field(
+                        "name",
+                        "must not be empty",
+                    ))));
+                }
+
+                if !command.metadata.contains_key(message::IDEMPOTENCY_KEY) {
+                    return Err(anyhow::Error::new(ConflictError {
+                        expected: 1,
+                        actual: 2,
+                    }));
+                }
+
+                Ok(())
+            })
+            .with_middleware(command::bus::Validation::default().validate(
+                |command: &RenameUser| {
+                    if command.name == "root" {
+                        return Err(Violation::field("name", "is reserved").into());
+                    }
+
+                    Ok(())
+                },
+            ));
+
+        let queries =
+            query::Bus::default().register(|query: query::Envelope<GetUserName>| async move {
+                match query.message.id {
+                    1 => Ok(format!("user-{}", query.message.id)),
+                    2 => Err(anyhow::anyhow!("connection refused to users-db:5432")),
+                    _ => Err(anyhow::Error::new(GetError::NotFound)),
+                }
+            });
+
+        Router::new()
+            .route("/users", post(dispatch_command::<RenameUser>))
+            .route("/users", get(dispatch_query::<GetUserName, String>))
+            .with_state(AppState { commands, queries })
+    }
+
+    async fn send(request: Request<Body>) -> (StatusCode, String) {
+        let response = app()
+            .oneshot(request)
+            .await
+            .expect("router should not fail");
+        let status = response.status();
+
+        let mut body = response.into_body();
+        let mut bytes = Vec::new();
+        while let Some(chunk) = body.data().await {
+            bytes.extend_from_slice(&chunk.expect("body should be readable"));
+        }
+
+        (
+            status,
+            String::from_utf8(bytes).expect("body should be utf-8"),
+        )
+    }
+
+    fn rename_user(name: &str, idempotency_key: Option<&str>) -> Request<Body> {
+        let mut request = Request::post("/users").header("Content-Type", "application/json");
+
+        if let Some(key) = idempotency_key {
+            request = request.header(message::IDEMPOTENCY_KEY, key);
+        }
+
+        request
+            .body(Body::from(format!(r#"{{"name":"{name}"}}"#)))
+            .expect("request should be valid")
+    }
+
+    #[tokio::test]
+    async fn commands_are_dispatched_with_their_metadata_from_headers() {
+        let (status, _) = send(rename_user("john", Some("key"))).await;
+        assert_eq!(StatusCode::NO_CONTENT, status);
+
+        let (status, body) = send(rename_user("john", None)).await;
+        assert_eq!(StatusCode::CONFLICT, status);
+        assert!(body.contains("conflict"), "unexpected body: {body}");
+
+        let (status, body) = send(rename_user("", Some("key"))).await;
+        assert_eq!(StatusCode::UNPROCESSABLE_ENTITY, status);
+        assert!(
+            body.contains("must not be empty"),
+            "unexpected body: {body}"
+        );
+
+        let (status, body) = send(rename_user("root", Some("key"))).await;
+        assert_eq!(StatusCode::UNPROCESSABLE_ENTITY, status);
+        assert!(body.contains("is reserved"), "unexpected body: {body}");
+
+        let (status, _) = send(
+            Request::post("/users")
+                .header("Content-Type", "application/json")
+                .body(Body::from("{}"))
+                .expect("request should be valid"),
+        )
+        .await;
+        assert_eq!(StatusCode::UNPROCESSABLE_ENTITY, status);
+    }
+
+    #[tokio::test]
+    async fn queries_are_dispatched_from_the_query_string() {
+        let get_user = |id: &str| {
+            Request::get(format!("/users?id={id}"))
+                .body(Body::empty())
+                .expect("request should be valid")
+        };
+
+        let (status, body) = send(get_user("1")).await;
+        assert_eq!(StatusCode::OK, status);
+        assert_eq!(r#""user-1""#, body);
+
+        let (status, _) = send(get_user("3")).await;
+        assert_eq!(StatusCode::NOT_FOUND, status);
+
+        let (status, body) = send(get_user("2")).await;
+        assert_eq!(StatusCode::INTERNAL_SERVER_ERROR, status);
+        assert_eq!(r#"{"error":"internal server error"}"#, body);
+
+        let (status, _) = send(get_user("invalid")).await;
+        assert_eq!(StatusCode::BAD_REQUEST, status);
+    }
+}
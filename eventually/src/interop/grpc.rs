@@ -0,0 +1,208 @@
+//! Module `grpc` contains the [`IntoStatus`] trait, to map the errors returned
+//! by this crate, and the Domain errors of an application, into canonical
+//! gRPC [Status]es returned by `tonic` services.
+//!
+//! Domain errors can implement [`IntoStatus`] through the `#[derive(IntoStatus)]`
+//! macro in the `eventually-macros` crate.
+
+use std::convert::Infallible;
+use std::fmt::Display;
+
+pub use tonic::{Code, Status};
+
+use crate::aggregate::repository::{DeleteError, GetError, SaveError};
+use crate::command::validate;
+use crate::event::store::AppendError;
+use crate::version::ConflictError;
+
+/// Message of the [Status]es with [`Code::Internal`].
+const INTERNAL_MESSAGE: &str = "internal error";
+
+/// Conversion of an error into a gRPC [Status], with the canonical [Code]
+/// that best describes it.
+///
+/// The [Status]es with [`Code::Internal`] returned by the implementations
+/// in this crate only carry a generic message, so that internal details
+/// are not leaked to the client: the whole error is logged instead,
+/// with the `tracing` feature enabled.
+pub trait IntoStatus {
+    /// Returns the gRPC [Status] describing the error.
+    fn into_status(self) -> Status;
+}
+
+fn internal(err: &dyn Display) -> Status {
+    #[cfg(feature = "tracing")]
+    tracing::error!(error = format!("{err:#}"), "failed to handle grpc request");
+    #[cfg(not(feature = "tracing"))]
+    let _ = err;
+
+    Status::internal(INTERNAL_MESSAGE)
+}
+
+impl IntoStatus for Status {
+    fn into_status(self) -> Status {
+        self
+    }
+}
+
+impl IntoStatus for Infallible {
+    fn into_status(self) -> Status {
+        match self {}
+    }
+}
+
+/// Optimistic concurrency conflicts are mapped to [`Code::Aborted`],
+/// as the client should retry the whole read-modify-write sequence.
+impl IntoStatus for ConflictError {
+    fn into_status(self) -> Status {
+        Status::aborted(self.to_string())
+    }
+}
+
+impl IntoStatus for GetError {
+    fn into_status(self) -> Status {
+        match self {
+            GetError::NotFound => Status::not_found(self.to_string()),
+            GetError::Internal(_) => internal(&self),
+        }
+    }
+}
+
+impl IntoStatus for SaveError {
+    fn into_status(self) -> Status {
+        match self {
+            SaveError::Conflict(_) => Status::aborted(self.to_string()),
+            SaveError::Internal(_) => internal(&self),
+        }
+    }
+}
+
+impl IntoStatus for DeleteError {
+    fn into_status(self) -> Status {
+        match self {
+            DeleteError::Conflict(_) => Status::aborted(self.to_string()),
+            DeleteError::Internal(_) => internal(&self),
+        }
+    }
+}
+
+impl IntoStatus for AppendError {
+    fn into_status(self) -> Status {
+        match self {
+            AppendError::Conflict(_) => Status::aborted(self.to_string()),
+            AppendError::Unsupported => Status::unimplemented(self.to_string()),
+            AppendError::Internal(_) => internal(&self),
+        }
+    }
+}
+
+impl IntoStatus for validate::ValidationError {
+    fn into_status(self) -> Status {
+        Status::invalid_argument(self.to_string())
+    }
+}
+
+impl<E> IntoStatus for validate::Error<E>
+where
+    E: IntoStatus,
+{
+    fn into_status(self) -> Status {
+        match self {
+            validate::Error::Validation(err) => err.into_status(),
+            validate::Error::Handler(err) => err.into_status(),
+        }
+    }
+}
+
+/// Looks for the errors of this crate in the chain of causes, e.g. of the errors
+/// returned by a Command Handler using [`anyhow::Error`], falling back to
+/// [`Code::Internal`] if none is found.
+///
+/// The message of the [Status] is the one of the whole [`anyhow::Error`],
+/// except for [`Code::Internal`].
+impl IntoStatus for anyhow::Error {
+    fn into_status(self) -> Status {
+        let code = self
+            .chain()
+            .find_map(|err| {
+                if let Some(err) = err.downcast_ref::<GetError>() {
+                    return matches!(err, GetError::NotFound).then_some(Code::NotFound);
+                }
+
+                if err.is::<ConflictError>() {
+                    return Some(Code::Aborted);
+                }
+
+                err.is::<validate::ValidationError>()
+                    .then_some(Code::InvalidArgument)
+            })
+            .unwrap_or(Code::Internal);
+
+        if code == Code::Internal {
+            return internal(&self);
+        }
+
+        Status::new(code, format!("{self:#}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Context;
+
+    use super::*;
+    use crate::command::validate::Violation;
+
+    #[test]
+    fn errors_are_mapped_to_canonical_codes() {
+        let conflict = ConflictError {
+            expected: 1,
+            actual: 2,
+        };
+
+        assert_eq!(Code::NotFound, GetError::NotFound.into_status().code());
+        assert_eq!(
+            Code::Aborted,
+            SaveError::Conflict(conflict).into_status().code()
+        );
+        assert_eq!(
+            Code::Unimplemented,
+            AppendError::Unsupported.into_status().code()
+        );
+        assert_eq!(
+            Code::InvalidArgument,
+            validate::Error::<Infallible>::Validation(
+                Violation::field("name", "should not be empty").into()
+            )
+            .into_status()
+            .code()
+        );
+
+        let status = Err::<(), _>(SaveError::Conflict(conflict))
+            .context("failed to rename user")
+            .unwrap_err()
+            .into_status();
+
+        assert_eq!(Code::Aborted, status.code());
+        assert!(status.message().starts_with("failed to rename user: "));
+        assert_eq!(
+            Code::Internal,
+            anyhow::anyhow!("connection reset").into_status().code()
+        );
+    }
+
+    #[test]
+    fn internal_errors_are_not_leaked_to_the_client() {
+        let status = anyhow::anyhow!("connection reset")
+            .context("failed to rename user")
+            .into_status();
+
+        assert_eq!(Code::Internal, status.code());
+        assert_eq!(INTERNAL_MESSAGE, status.message());
+
+        let status = GetError::Internal(anyhow::anyhow!("connection reset")).into_status();
+
+        assert_eq!(Code::Internal, status.code());
+        assert_eq!(INTERNAL_MESSAGE, status.message());
+    }
+}
@@ -0,0 +1,11 @@
+//! Module `interop` contains helpers to integrate the types of this crate
+//! with other frameworks, enabled through their own crate feature:
+//!
+//! - `grpc`: [`grpc`] module, to map errors into `tonic` gRPC statuses.
+//! - `axum`: [`axum`] module, to receive Commands and Queries in `axum` HTTP handlers.
+
+#[cfg(feature = "grpc")]
+pub mod grpc;
+
+#[cfg(feature = "axum")]
+pub mod axum;
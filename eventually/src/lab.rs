@@ -0,0 +1,469 @@
+//! Module `lab` contains a development-only [Lab], which observes the Event Stores
+//! and Projections of an application and exposes a [Snapshot] of their current status,
+//! e.g. to be served as a JSON API by example applications.
+//!
+//! Event Stores are observed by wrapping them in an [`ObservedEventStore`], through
+//! [`Lab::observe`], while Projections are observed by wrapping them in an
+//! [`ObservedProjection`], through [`Lab::observe_projection`].
+//!
+//! All the data is kept in memory, starting from the moment the [Lab] has been created.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use crate::event::store::{AppendError, Appender, GlobalStreamer, StreamAppend, Streamer};
+use crate::projection::{Projection, Resettable};
+use crate::version::{self, Version};
+use crate::{event, message};
+
+/// A Domain Event appended through an [`ObservedEventStore`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EventSummary {
+    /// The id of the Event Stream the Domain Event has been appended to.
+    pub stream_id: String,
+    /// The version of the Event Stream after the Domain Event has been appended.
+    pub version: Version,
+    /// The name of the Domain Event.
+    pub name: String,
+}
+
+/// The status of the observed Event Stores and Projections, as exposed by the [Lab].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Snapshot {
+    /// The current version of each Event Stream (e.g. of each Aggregate Root)
+    /// appended to since the [Lab] has been created.
+    pub stream_versions: BTreeMap<String, Version>,
+    /// The last Domain Events appended, from the oldest to the newest.
+    pub last_events: Vec<EventSummary>,
+    /// The [Sequence][event::Sequence] number of the last Domain Event
+    /// projected by each Projection.
+    pub projector_checkpoints: BTreeMap<String, event::Sequence>,
+    /// The number of version conflicts detected on each Event Stream.
+    pub conflicts: BTreeMap<String, u64>,
+}
+
+impl Snapshot {
+    /// Exports the [Snapshot] in the JSON format.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the [Snapshot] could not be serialized.
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string(self)
+    }
+}
+
+#[derive(Debug, Default)]
+struct LabState {
+    stream_versions: BTreeMap<String, Version>,
+    last_events: VecDeque<EventSummary>,
+    projector_checkpoints: BTreeMap<String, event::Sequence>,
+    conflicts: BTreeMap<String, u64>,
+}
+
+/// Observes Event Stores and Projections, collecting their status
+/// in a [Snapshot].
+#[derive(Debug, Clone)]
+pub struct Lab {
+    state: Arc<Mutex<LabState>>,
+    last_events_capacity: usize,
+}
+
+impl Default for Lab {
+    fn default() -> Self {
+        Self {
+            state: Arc::default(),
+            last_events_capacity: 20,
+        }
+    }
+}
+
+impl Lab {
+    /// Sets how many of the last appended Domain Events are kept in the [Snapshot].
+    /// Defaults to 20.
+    #[must_use]
+    pub fn with_last_events_capacity(mut self, capacity: usize) -> Self {
+        self.last_events_capacity = capacity;
+        self
+    }
+
+    /// Returns an [`ObservedEventStore`] reporting the Domain Events
+    /// appended to the specified Event Store to this [Lab].
+    pub fn observe<S>(&self, store: S) -> ObservedEventStore<S> {
+        ObservedEventStore {
+            store,
+            lab: self.clone(),
+        }
+    }
+
+    /// Returns an [`ObservedProjection`] reporting the checkpoint of the specified
+    /// Projection to this [Lab], using the specified name.
+    pub fn observe_projection<P>(
+        &self,
+        name: impl Into<String>,
+        projection: P,
+    ) -> ObservedProjection<P> {
+        ObservedProjection {
+            projection,
+            name: name.into(),
+            lab: self.clone(),
+        }
+    }
+
+    /// Returns the current [Snapshot] of the observed Event Stores and Projections.
+    #[must_use]
+    pub fn snapshot(&self) -> Snapshot {
+        let state = self.lock_state();
+
+        Snapshot {
+            stream_versions: state.stream_versions.clone(),
+            last_events: state.last_events.iter().cloned().collect(),
+            projector_checkpoints: state.projector_checkpoints.clone(),
+            conflicts: state.conflicts.clone(),
+        }
+    }
+
+    fn record_appended<Evt>(
+        &self,
+        stream_id: String,
+        new_version: Version,
+        events: &[event::Envelope<Evt>],
+    ) where
+        Evt: message::Message,
+    {
+        let mut state = self.lock_state();
+        let first_version = new_version - events.len() as Version + 1;
+
+        for (version, event) in (first_version..).zip(events) {
+            state.last_events.push_back(EventSummary {
+                stream_id: stream_id.clone(),
+                version,
+                name: event.message.name().to_owned(),
+            });
+        }
+
+        while state.last_events.len() > self.last_events_capacity {
+            state.last_events.pop_front();
+        }
+
+        state.stream_versions.insert(stream_id, new_version);
+    }
+
+    fn record_conflict(&self, stream_id: String) {
+        *self.lock_state().conflicts.entry(stream_id).or_default() += 1;
+    }
+
+    fn record_checkpoint(&self, name: &str, sequence: Option<event::Sequence>) {
+        let mut state = self.lock_state();
+
+        match sequence {
+            Some(sequence) => state
+                .projector_checkpoints
+                .insert(name.to_owned(), sequence),
+            None => state.projector_checkpoints.remove(name),
+        };
+    }
+
+    // NOTE: the Lab only reports the status of the application, so a thread panicking
+    // while holding the lock can at most leave a stale status: it is used anyway,
+    // instead of failing the appends and the Projections observed afterwards.
+    fn lock_state(&self) -> MutexGuard<'_, LabState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// [`event::Store`] decorator that reports the Domain Events appended,
+/// and the version conflicts detected, to a [Lab].
+#[derive(Debug, Clone)]
+pub struct ObservedEventStore<S> {
+    store: S,
+    lab: Lab,
+}
+
+impl<S, StreamId, Evt> Streamer<StreamId, Evt> for ObservedEventStore<S>
+where
+    S: Streamer<StreamId, Evt>,
+    StreamId: Send + Sync,
+    Evt: message::Message + Send + Sync,
+{
+    type Error = S::Error;
+
+    fn stream(
+        &self,
+        id: &StreamId,
+        select: event::VersionSelect,
+    ) -> event::Stream<'_, StreamId, Evt, Self::Error> {
+        self.store.stream(id, select)
+    }
+
+    fn stream_filtered<'a>(
+        &'a self,
+        id: &StreamId,
+        select: event::VersionSelect,
+        names: event::NameSelect,
+    ) -> event::Stream<'a, StreamId, Evt, Self::Error>
+    where
+        StreamId: 'a,
+        Evt: 'a,
+        Self::Error: 'a,
+    {
+        self.store.stream_filtered(id, select, names)
+    }
+}
+
+impl<S, StreamId, Evt> GlobalStreamer<StreamId, Evt> for ObservedEventStore<S>
+where
+    S: GlobalStreamer<StreamId, Evt>,
+    StreamId: Send + Sync,
+    Evt: message::Message + Send + Sync,
+{
+    type Error = S::Error;
+
+    fn stream_all(
+        &self,
+        select: event::SequenceSelect,
+    ) -> event::SequencedStream<'_, StreamId, Evt, Self::Error> {
+        self.store.stream_all(select)
+    }
+
+    fn stream_all_filtered<'a>(
+        &'a self,
+        select: event::SequenceSelect,
+        names: event::NameSelect,
+    ) -> event::SequencedStream<'a, StreamId, Evt, Self::Error>
+    where
+        StreamId: 'a,
+        Evt: 'a,
+        Self::Error: 'a,
+    {
+        self.store.stream_all_filtered(select, names)
+    }
+}
+
+#[async_trait]
+impl<S, StreamId, Evt> Appender<StreamId, Evt> for ObservedEventStore<S>
+where
+    S: Appender<StreamId, Evt>,
+    StreamId: ToString + Send + Sync + 'static,
+    Evt: message::Message + Clone + Send + Sync + 'static,
+{
+    async fn append(
+        &self,
+        id: StreamId,
+        version_check: version::Check,
+        events: Vec<event::Envelope<Evt>>,
+    ) -> Result<Version, AppendError> {
+        let stream_id = id.to_string();
+        let result = self.store.append(id, version_check, events.clone()).await;
+
+        match &result {
+            Ok(new_version) => self.lab.record_appended(stream_id, *new_version, &events),
+            Err(AppendError::Conflict(_)) => self.lab.record_conflict(stream_id),
+            Err(_) => {},
+        }
+
+        result
+    }
+
+    async fn append_multi(
+        &self,
+        appends: Vec<StreamAppend<StreamId, Evt>>,
+    ) -> Result<Vec<Version>, AppendError>
+    where
+        StreamId: 'async_trait,
+        Evt: 'async_trait,
+    {
+        let appended: Vec<_> = appends
+            .iter()
+            .map(|append| (append.id.to_string(), append.events.clone()))
+            .collect();
+
+        let result = self.store.append_multi(appends).await;
+
+        match &result {
+            Ok(new_versions) => {
+                for ((stream_id, events), new_version) in appended.into_iter().zip(new_versions) {
+                    self.lab.record_appended(stream_id, *new_version, &events);
+                }
+            },
+            // NOTE: the conflicting Event Stream is not known, so the conflict
+            // is attributed to all the Event Streams in the operation.
+            Err(AppendError::Conflict(_)) => {
+                for (stream_id, _) in appended {
+                    self.lab.record_conflict(stream_id);
+                }
+            },
+            Err(_) => {},
+        }
+
+        result
+    }
+}
+
+/// [Projection] decorator that reports the [Sequence][event::Sequence] number
+/// of the last Domain Event projected to a [Lab].
+#[derive(Debug, Clone)]
+pub struct ObservedProjection<P> {
+    projection: P,
+    name: String,
+    lab: Lab,
+}
+
+#[async_trait]
+impl<P, StreamId, Evt> Projection<StreamId, Evt> for ObservedProjection<P>
+where
+    P: Projection<StreamId, Evt>,
+    StreamId: Send + Sync + 'static,
+    Evt: message::Message + Send + Sync + 'static,
+{
+    type Error = P::Error;
+
+    async fn project(&self, event: event::Sequenced<StreamId, Evt>) -> Result<(), Self::Error> {
+        let sequence = event.sequence;
+
+        self.projection.project(event).await?;
+        self.lab.record_checkpoint(&self.name, Some(sequence));
+
+        Ok(())
+    }
+
+    async fn project_batch(
+        &self,
+        events: Vec<event::Sequenced<StreamId, Evt>>,
+    ) -> Result<(), Self::Error>
+    where
+        StreamId: 'async_trait,
+        Evt: 'async_trait,
+    {
+        let sequence = events.last().map(|event| event.sequence);
+
+        self.projection.project_batch(events).await?;
+
+        if sequence.is_some() {
+            self.lab.record_checkpoint(&self.name, sequence);
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<P, StreamId, Evt> Resettable<StreamId, Evt> for ObservedProjection<P>
+where
+    P: Resettable<StreamId, Evt>,
+    StreamId: Send + Sync + 'static,
+    Evt: message::Message + Send + Sync + 'static,
+{
+    async fn reset(&self) -> Result<(), Self::Error> {
+        self.projection.reset().await?;
+        self.lab.record_checkpoint(&self.name, None);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+
+    use super::*;
+    use crate::event::store::InMemory;
+    use crate::message::tests::StringMessage;
+    use crate::projection::Rebuilder;
+
+    struct Noop;
+
+    #[async_trait]
+    impl Projection<&'static str, StringMessage> for Noop {
+        type Error = Infallible;
+
+        async fn project(
+            &self,
+            _event: event::Sequenced<&'static str, StringMessage>,
+        ) -> Result<(), Self::Error> {
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl Resettable<&'static str, StringMessage> for Noop {
+        async fn reset(&self) -> Result<(), Self::Error> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn lab_observes_event_stores_and_projections() {
+        let lab = Lab::default().with_last_events_capacity(2);
+        let event_store = lab.observe(InMemory::<&'static str, StringMessage>::default());
+
+        event_store
+            .append(
+                "stream:a",
+                version::Check::MustBe(0),
+                vec![event::Envelope::from(StringMessage("event")); 3],
+            )
+            .await
+            .expect("append should not fail");
+
+        event_store
+            .append(
+                "stream:a",
+                version::Check::MustBe(0),
+                vec![event::Envelope::from(StringMessage("event"))],
+            )
+            .await
+            .expect_err("append should conflict");
+
+        Rebuilder::from(event_store)
+            .rebuild(&lab.observe_projection("noop", Noop))
+            .await
+            .expect("rebuild should not fail");
+
+        let event_summary = |version| EventSummary {
+            stream_id: "stream:a".to_owned(),
+            version,
+            name: "string_payload".to_owned(),
+        };
+
+        assert_eq!(
+            Snapshot {
+                stream_versions: BTreeMap::from([("stream:a".to_owned(), 3)]),
+                last_events: vec![event_summary(2), event_summary(3)],
+                projector_checkpoints: BTreeMap::from([("noop".to_owned(), 3)]),
+                conflicts: BTreeMap::from([("stream:a".to_owned(), 1)]),
+            },
+            lab.snapshot()
+        );
+    }
+
+    #[tokio::test]
+    async fn lab_keeps_observing_after_a_poisoned_lock() {
+        let lab = Lab::default();
+        let poisoner = lab.clone();
+
+        std::thread::spawn(move || {
+            let _state = poisoner.state.lock().unwrap();
+            panic!("poisoning the lab state");
+        })
+        .join()
+        .expect_err("the thread should panic");
+
+        lab.observe(InMemory::<&'static str, StringMessage>::default())
+            .append(
+                "stream:a",
+                version::Check::MustBe(0),
+                vec![event::Envelope::from(StringMessage("event"))],
+            )
+            .await
+            .expect("append should not fail");
+
+        assert_eq!(
+            BTreeMap::from([("stream:a".to_owned(), 1)]),
+            lab.snapshot().stream_versions
+        );
+    }
+}
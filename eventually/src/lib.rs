@@ -4,13 +4,52 @@
 
 #![deny(unsafe_code, unused_qualifications, trivial_casts, missing_docs)]
 #![deny(clippy::all, clippy::pedantic, clippy::cargo)]
+#![allow(clippy::multiple_crate_versions)]
 
 pub mod aggregate;
+pub mod clock;
 pub mod command;
 pub mod event;
+pub mod flow;
+pub mod health;
+pub mod interop;
+#[cfg(feature = "lab")]
+pub mod lab;
+pub mod maintenance;
 pub mod message;
+pub mod projection;
 pub mod query;
+pub mod reactor;
+#[cfg(feature = "serde-json")]
+pub mod replay;
+pub mod runner;
 pub mod serde;
+pub mod subscription;
+pub mod supervise;
+#[cfg(feature = "telemetry")]
+pub mod telemetry;
 #[cfg(feature = "tracing")]
 pub mod tracing;
 pub mod version;
+
+/// Items used by the code generated by the `eventually-macros` crate.
+/// Not part of the public API.
+#[doc(hidden)]
+pub mod __private {
+    pub use anyhow;
+    pub use async_trait::async_trait;
+    pub use serde;
+
+    use crate::message;
+
+    /// Placeholder [Message][message::Message] used to carry the metadata
+    /// of a Command whose payload has already been consumed.
+    #[derive(Debug, Clone, Copy)]
+    pub struct CommandName(pub &'static str);
+
+    impl message::Message for CommandName {
+        fn name(&self) -> &'static str {
+            self.0
+        }
+    }
+}
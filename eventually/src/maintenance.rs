@@ -0,0 +1,428 @@
+//! Module `maintenance` contains tools supporting operational procedures
+//! on the Event Store, such as repairing corrupted Domain Events.
+//!
+//! Since Event Streams are append-only, a corrupted Event Stream is repaired
+//! by copying its Domain Events, fixed, into a new Event Stream, and by closing
+//! the old one with a tombstone Domain Event. Check out [`StreamRewriter`]
+//! for more information.
+//!
+//! Domain Events whose payload cannot be deserialized anymore can be repaired
+//! through an Event Store using [`RawSerde`], which streams them as [`Raw::Corrupted`].
+
+use anyhow::anyhow;
+use futures::TryStreamExt;
+
+use crate::event::store::{AppendError, Appender, StreamAppend, Streamer};
+use crate::{event, message, serde, version};
+
+/// A Domain Event read by an Event Store using [`RawSerde`], which might have
+/// a payload that cannot be deserialized anymore.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Raw<T> {
+    /// A Domain Event with a valid payload.
+    Decoded(T),
+    /// A Domain Event whose payload could not be deserialized,
+    /// which must be repaired before being written again.
+    Corrupted {
+        /// The serialized payload, as found in the Event Store.
+        payload: Vec<u8>,
+        /// The deserialization error.
+        error: String,
+    },
+}
+
+impl<T> message::Message for Raw<T>
+where
+    T: message::Message,
+{
+    fn name(&self) -> &'static str {
+        match self {
+            Raw::Decoded(message) => message.name(),
+            Raw::Corrupted { .. } => "Corrupted",
+        }
+    }
+}
+
+/// [Serde][serde::Serde] for [Raw] Domain Events, which deserializes the payloads
+/// that the wrapped [Serde][serde::Serde] fails to deserialize as [`Raw::Corrupted`],
+/// instead of failing the whole Event Stream.
+///
+/// [`Raw::Corrupted`] Domain Events cannot be serialized, so that a [`StreamRewriter`]
+/// never writes them into the new Event Stream.
+#[derive(Debug, Clone, Copy)]
+pub struct RawSerde<S>(S);
+
+impl<S> RawSerde<S> {
+    /// Wraps the [Serde][serde::Serde] of the Domain Events in the Event Store.
+    pub fn new(serde: S) -> Self {
+        Self(serde)
+    }
+}
+
+impl<T, S> serde::Serializer<Raw<T>> for RawSerde<S>
+where
+    S: serde::Serializer<T>,
+{
+    fn serialize(&self, value: Raw<T>) -> anyhow::Result<Vec<u8>> {
+        match value {
+            Raw::Decoded(message) => self.0.serialize(message),
+            Raw::Corrupted { error, .. } => Err(anyhow!(
+                "corrupted domain events must be repaired before being written: {error}"
+            )),
+        }
+    }
+}
+
+impl<T, S> serde::Deserializer<Raw<T>> for RawSerde<S>
+where
+    S: serde::Deserializer<T>,
+{
+    fn deserialize(&self, data: &[u8]) -> anyhow::Result<Raw<T>> {
+        Ok(match self.0.deserialize(data) {
+            Ok(message) => Raw::Decoded(message),
+            Err(err) => Raw::Corrupted {
+                payload: data.to_vec(),
+                error: format!("{err:#}"),
+            },
+        })
+    }
+}
+
+/// All possible errors returned by a [`StreamRewriter`].
+#[derive(Debug, thiserror::Error)]
+pub enum RewriteError<E> {
+    /// Error returned when the Event Stream to rewrite does not exist.
+    #[error("the event stream to rewrite was not found")]
+    NotFound,
+    /// Error returned when the transformation drops all the Domain Events,
+    /// in which case the old Event Stream is left untouched.
+    #[error("the rewritten event stream would be empty")]
+    Empty,
+    /// Error returned when the Event Stream to rewrite could not be read.
+    #[error("failed to read the event stream to rewrite: {0}")]
+    Stream(#[source] E),
+    /// Error returned when the rewritten Event Stream or the tombstone could not
+    /// be appended, e.g. because the new Event Stream already exists,
+    /// or the old one has been modified during the rewrite.
+    #[error("failed to append the rewritten event stream: {0}")]
+    Append(#[source] AppendError),
+}
+
+/// The outcome of a successful [`StreamRewriter::rewrite`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rewritten {
+    /// The number of Domain Events read from the old Event Stream.
+    pub read: usize,
+    /// The number of Domain Events written to the new Event Stream.
+    pub written: usize,
+}
+
+/// Rewrites an Event Stream into a new one, passing each of its Domain Events
+/// through a user-defined transformation, which can drop, patch or split them.
+///
+/// After the new Event Stream has been written, a tombstone Domain Event is appended
+/// to the old one, so that it can be recognized as superseded. The tombstone is appended
+/// expecting the last [Version][version::Version] read, so that Domain Events appended
+/// to the old Event Stream during the rewrite are not lost silently.
+///
+/// When the Event Store supports [`Appender::append_multi`], the new Event Stream and
+/// the tombstone are appended atomically; otherwise, the new Event Stream is appended first.
+pub struct StreamRewriter<S, F, T> {
+    store: S,
+    transform: F,
+    tombstone: T,
+}
+
+impl<S, F, T> StreamRewriter<S, F, T> {
+    /// Creates a new [`StreamRewriter`] using the specified transformation,
+    /// and the function building the tombstone from the id of the new Event Stream.
+    ///
+    /// The transformation returns the Domain Events to write in place of the one
+    /// received: none to drop it, a patched one to fix it, or many to split it.
+    pub fn new(store: S, transform: F, tombstone: T) -> Self {
+        Self {
+            store,
+            transform,
+            tombstone,
+        }
+    }
+
+    /// Rewrites the Event Stream `from` into the new Event Stream `to`,
+    /// which must not exist yet.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the old Event Stream does not exist or could not be read,
+    /// if the transformation drops all its Domain Events, or if the new Event Stream
+    /// or the tombstone could not be appended.
+    pub async fn rewrite<Id, Evt>(
+        &self,
+        from: Id,
+        to: Id,
+    ) -> Result<Rewritten, RewriteError<<S as Streamer<Id, Evt>>::Error>>
+    where
+        Id: Clone + Send + Sync,
+        Evt: message::Message + Clone + Send + Sync,
+        S: Streamer<Id, Evt> + Appender<Id, Evt>,
+        F: Fn(event::Persisted<Id, Evt>) -> Vec<event::Envelope<Evt>> + Send + Sync,
+        T: Fn(&Id) -> event::Envelope<Evt> + Send + Sync,
+    {
+        let old_events: Vec<_> = self
+            .store
+            .stream(&from, event::VersionSelect::All)
+            .try_collect()
+            .await
+            .map_err(RewriteError::Stream)?;
+
+        let Some(last_version) = old_events.last().map(|event| event.version) else {
+            return Err(RewriteError::NotFound);
+        };
+
+        let read = old_events.len();
+        let new_events: Vec<_> = old_events.into_iter().flat_map(&self.transform).collect();
+        let written = new_events.len();
+
+        if written == 0 {
+            return Err(RewriteError::Empty);
+        }
+
+        let tombstone = StreamAppend {
+            id: from,
+            version_check: version::Check::MustBe(last_version),
+            events: vec![(self.tombstone)(&to)],
+        };
+
+        let rewritten = StreamAppend {
+            id: to,
+            version_check: version::Check::MustBe(0),
+            events: new_events,
+        };
+
+        let appends = vec![rewritten, tombstone];
+
+        // NOTE: the appends are retried one by one only if the Event Store
+        // does not support atomic appends, which is the only error not caused
+        // by the appends themselves.
+        match self.store.append_multi(appends.clone()).await {
+            Err(AppendError::Unsupported) => {
+                for append in appends {
+                    self.store
+                        .append(append.id, append.version_check, append.events)
+                        .await
+                        .map_err(RewriteError::Append)?;
+                }
+            },
+            result => {
+                result.map_err(RewriteError::Append)?;
+            },
+        }
+
+        Ok(Rewritten { read, written })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::store::InMemory;
+    use crate::message::tests::StringMessage;
+
+    fn repair(
+        event: event::Persisted<&'static str, StringMessage>,
+    ) -> Vec<event::Envelope<StringMessage>> {
+        match event.event.message.0 {
+            "corrupted" => Vec::new(),
+            "misspeled" => vec![event::Envelope::from(StringMessage("misspelled"))],
+            "first+second" => vec![
+                event::Envelope::from(StringMessage("first")),
+                event::Envelope::from(StringMessage("second")),
+            ],
+            _ => vec![event.event],
+        }
+    }
+
+    fn tombstone(_: &&'static str) -> event::Envelope<StringMessage> {
+        event::Envelope::from(StringMessage("rewritten"))
+    }
+
+    async fn messages(
+        store: &InMemory<&'static str, StringMessage>,
+        id: &'static str,
+    ) -> Vec<&'static str> {
+        store
+            .stream(&id, event::VersionSelect::All)
+            .map_ok(|event| event.event.message.0)
+            .try_collect()
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn rewrite_copies_the_transformed_events_and_closes_the_old_stream() {
+        let store = InMemory::<&'static str, StringMessage>::default();
+
+        store
+            .append(
+                "stream:old",
+                version::Check::MustBe(0),
+                ["created", "corrupted", "misspeled", "first+second"]
+                    .into_iter()
+                    .map(|message| event::Envelope::from(StringMessage(message)))
+                    .collect(),
+            )
+            .await
+            .unwrap();
+
+        let rewriter = StreamRewriter::new(store.clone(), repair, tombstone);
+
+        let rewritten = rewriter.rewrite("stream:old", "stream:new").await.unwrap();
+
+        assert_eq!(
+            Rewritten {
+                read: 4,
+                written: 4
+            },
+            rewritten
+        );
+        assert_eq!(
+            vec!["created", "misspelled", "first", "second"],
+            messages(&store, "stream:new").await
+        );
+        assert_eq!(
+            vec![
+                "created",
+                "corrupted",
+                "misspeled",
+                "first+second",
+                "rewritten"
+            ],
+            messages(&store, "stream:old").await
+        );
+
+        let error = rewriter
+            .rewrite("stream:old", "stream:new")
+            .await
+            .expect_err("the new stream already exists");
+
+        assert!(matches!(
+            error,
+            RewriteError::Append(AppendError::Conflict(_))
+        ));
+
+        assert!(matches!(
+            rewriter.rewrite("stream:missing", "stream:other").await,
+            Err(RewriteError::NotFound)
+        ));
+    }
+
+    #[tokio::test]
+    async fn rewrite_refuses_to_drop_all_the_events() {
+        let store = InMemory::<&'static str, StringMessage>::default();
+
+        store
+            .append(
+                "stream:old",
+                version::Check::MustBe(0),
+                vec![event::Envelope::from(StringMessage("corrupted"))],
+            )
+            .await
+            .unwrap();
+
+        let rewriter = StreamRewriter::new(store.clone(), repair, tombstone);
+
+        assert!(matches!(
+            rewriter.rewrite("stream:old", "stream:new").await,
+            Err(RewriteError::Empty)
+        ));
+        assert_eq!(vec!["corrupted"], messages(&store, "stream:old").await);
+        assert!(messages(&store, "stream:new").await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn rewrite_repairs_events_that_cannot_be_deserialized() {
+        let store = InMemory::<&'static str, Raw<StringMessage>>::default();
+
+        store
+            .append(
+                "stream:old",
+                version::Check::MustBe(0),
+                vec![
+                    event::Envelope::from(Raw::Decoded(StringMessage("created"))),
+                    event::Envelope::from(Raw::Corrupted {
+                        payload: b"{\"nmae\":".to_vec(),
+                        error: "unexpected end of input".to_owned(),
+                    }),
+                ],
+            )
+            .await
+            .unwrap();
+
+        let rewriter = StreamRewriter::new(
+            store.clone(),
+            |event: event::Persisted<&'static str, Raw<StringMessage>>| match event.event.message {
+                Raw::Corrupted { .. } => {
+                    vec![event::Envelope::from(Raw::Decoded(StringMessage(
+                        "renamed",
+                    )))]
+                },
+                Raw::Decoded(_) => vec![event.event],
+            },
+            |_: &&'static str| event::Envelope::from(Raw::Decoded(StringMessage("rewritten"))),
+        );
+
+        rewriter.rewrite("stream:old", "stream:new").await.unwrap();
+
+        let rewritten: Vec<_> = store
+            .stream(&"stream:new", event::VersionSelect::All)
+            .map_ok(|event| event.event.message)
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert_eq!(
+            vec![
+                Raw::Decoded(StringMessage("created")),
+                Raw::Decoded(StringMessage("renamed"))
+            ],
+            rewritten
+        );
+    }
+
+    struct Utf8;
+
+    impl serde::Serializer<String> for Utf8 {
+        fn serialize(&self, value: String) -> anyhow::Result<Vec<u8>> {
+            Ok(value.into_bytes())
+        }
+    }
+
+    impl serde::Deserializer<String> for Utf8 {
+        fn deserialize(&self, data: &[u8]) -> anyhow::Result<String> {
+            Ok(String::from_utf8(data.to_vec())?)
+        }
+    }
+
+    #[test]
+    fn raw_serde_keeps_the_payloads_that_cannot_be_deserialized() {
+        use crate::serde::{Deserializer, Serializer};
+
+        let serde = RawSerde::new(Utf8);
+
+        assert_eq!(
+            Raw::Decoded("valid".to_owned()),
+            serde.deserialize(b"valid").unwrap()
+        );
+
+        let corrupted = serde.deserialize(&[0xff, 0xfe]).unwrap();
+        assert!(matches!(
+            &corrupted,
+            Raw::Corrupted { payload, .. } if payload == &[0xff, 0xfe]
+        ));
+
+        assert_eq!(
+            b"valid".to_vec(),
+            serde.serialize(Raw::Decoded("valid".to_owned())).unwrap()
+        );
+        assert!(serde.serialize(corrupted).is_err());
+    }
+}
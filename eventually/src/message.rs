@@ -2,7 +2,9 @@
 //! can be used to describe some sort of domain value such as a [Domain Event][crate::event::Envelope],
 //! a [Domain Command][crate::command::Envelope], and so on.
 
+use std::cell::RefCell;
 use std::collections::HashMap;
+use std::future::Future;
 
 use serde::{Deserialize, Serialize};
 
@@ -20,6 +22,25 @@ pub trait Message {
 /// to the [Message] carried out.
 pub type Metadata = HashMap<String, String>;
 
+/// [Metadata] key used to store the unique identifier of a [Message].
+pub const MESSAGE_ID_KEY: &str = "Message-Id";
+
+/// [Metadata] key used to store the identifier shared by all the [Message]s
+/// belonging to the same logical flow (e.g. a user request).
+pub const CORRELATION_ID_KEY: &str = "Correlation-Id";
+
+/// [Metadata] key used to store the identifier of the [Message]
+/// that directly caused the current one.
+pub const CAUSATION_ID_KEY: &str = "Causation-Id";
+
+/// [Metadata] key used to store the identifier of the tenant owning a [Message],
+/// in multi-tenant deployments.
+pub const TENANT_ID_KEY: &str = "Tenant-Id";
+
+/// [Metadata] key used to store the key identifying all the deliveries
+/// of the same [Message], used to detect duplicates.
+pub const IDEMPOTENCY_KEY: &str = "Idempotency-Key";
+
 /// Represents a [Message] packaged for persistance and/or processing by other
 /// parts of the system.
 ///
@@ -45,6 +66,141 @@ where
         self.metadata.insert(key, value);
         self
     }
+
+    /// Returns the unique identifier of the [Message], if any.
+    #[must_use]
+    pub fn message_id(&self) -> Option<&str> {
+        self.metadata.get(MESSAGE_ID_KEY).map(String::as_str)
+    }
+
+    /// Sets the unique identifier of the [Message] in the [Envelope]'s [Metadata].
+    #[must_use]
+    pub fn with_message_id(self, id: String) -> Self {
+        self.with_metadata(MESSAGE_ID_KEY.to_owned(), id)
+    }
+
+    /// Returns the correlation id of the [Message], if any.
+    #[must_use]
+    pub fn correlation_id(&self) -> Option<&str> {
+        self.metadata.get(CORRELATION_ID_KEY).map(String::as_str)
+    }
+
+    /// Sets the correlation id of the [Message] in the [Envelope]'s [Metadata].
+    #[must_use]
+    pub fn with_correlation_id(self, id: String) -> Self {
+        self.with_metadata(CORRELATION_ID_KEY.to_owned(), id)
+    }
+
+    /// Returns the causation id of the [Message], if any.
+    #[must_use]
+    pub fn causation_id(&self) -> Option<&str> {
+        self.metadata.get(CAUSATION_ID_KEY).map(String::as_str)
+    }
+
+    /// Sets the causation id of the [Message] in the [Envelope]'s [Metadata].
+    #[must_use]
+    pub fn with_causation_id(self, id: String) -> Self {
+        self.with_metadata(CAUSATION_ID_KEY.to_owned(), id)
+    }
+
+    /// Returns the identifier of the tenant owning the [Message], if any.
+    #[must_use]
+    pub fn tenant_id(&self) -> Option<&str> {
+        self.metadata.get(TENANT_ID_KEY).map(String::as_str)
+    }
+
+    /// Sets the identifier of the tenant owning the [Message] in the [Envelope]'s [Metadata].
+    #[must_use]
+    pub fn with_tenant_id(self, id: String) -> Self {
+        self.with_metadata(TENANT_ID_KEY.to_owned(), id)
+    }
+
+    /// Returns the idempotency key of the [Message], if any.
+    #[must_use]
+    pub fn idempotency_key(&self) -> Option<&str> {
+        self.metadata.get(IDEMPOTENCY_KEY).map(String::as_str)
+    }
+
+    /// Sets the idempotency key of the [Message] in the [Envelope]'s [Metadata].
+    #[must_use]
+    pub fn with_idempotency_key(self, key: String) -> Self {
+        self.with_metadata(IDEMPOTENCY_KEY.to_owned(), key)
+    }
+
+    /// Marks the [Envelope] as caused by the specified parent [Envelope].
+    ///
+    /// The correlation id of the parent is carried over (falling back to the parent's
+    /// message id if no correlation id is present), while the parent's message id
+    /// is used as the causation id.
+    #[must_use]
+    pub fn caused_by<U>(mut self, parent: &Envelope<U>) -> Self
+    where
+        U: Message,
+    {
+        self.metadata.extend(causation_metadata(parent));
+        self
+    }
+}
+
+/// Returns the correlation and causation [Metadata] entries to use for all
+/// the [Message]s caused by the specified parent [Envelope].
+pub(crate) fn causation_metadata<U>(parent: &Envelope<U>) -> Metadata
+where
+    U: Message,
+{
+    let mut metadata = Metadata::default();
+
+    if let Some(correlation_id) = parent.correlation_id().or(parent.message_id()) {
+        metadata.insert(CORRELATION_ID_KEY.to_owned(), correlation_id.to_owned());
+    }
+
+    if let Some(message_id) = parent.message_id() {
+        metadata.insert(CAUSATION_ID_KEY.to_owned(), message_id.to_owned());
+    }
+
+    metadata
+}
+
+thread_local! {
+    static CAUSATION: RefCell<Option<Metadata>> = const { RefCell::new(None) };
+}
+
+/// Restores the previous causation [Metadata] in scope when dropped,
+/// also when the scoped future panics.
+struct CausationScope(Option<Metadata>);
+
+impl CausationScope {
+    fn enter(causation: Metadata) -> Self {
+        Self(CAUSATION.with(|current| current.replace(Some(causation))))
+    }
+}
+
+impl Drop for CausationScope {
+    fn drop(&mut self) {
+        CAUSATION.with(|current| *current.borrow_mut() = self.0.take());
+    }
+}
+
+/// Runs the future with the specified correlation and causation [Metadata]
+/// entries (see [`causation_metadata`]) in scope, which are then returned
+/// by [`current_causation`] whenever the future is polled.
+pub(crate) async fn with_causation<F>(causation: Metadata, future: F) -> F::Output
+where
+    F: Future,
+{
+    let mut future = std::pin::pin!(future);
+
+    futures::future::poll_fn(move |cx| {
+        let _scope = CausationScope::enter(causation.clone());
+        future.as_mut().poll(cx)
+    })
+    .await
+}
+
+/// Returns the correlation and causation [Metadata] entries in scope,
+/// if called while polling a future run by [`with_causation`].
+pub(crate) fn current_causation() -> Option<Metadata> {
+    CAUSATION.with(|current| current.borrow().clone())
 }
 
 impl<T> From<T> for Envelope<T>
@@ -99,4 +255,25 @@ pub(crate) mod tests {
         // Metadata does not affect equality of message.
         assert_eq!(message, new_message);
     }
+
+    #[test]
+    fn caused_by_propagates_correlation_and_causation_ids() {
+        let command = Envelope::from(StringMessage("command"))
+            .with_message_id("command-1".to_owned())
+            .with_correlation_id("request-1".to_owned());
+
+        let event = Envelope::from(StringMessage("event")).caused_by(&command);
+
+        assert_eq!(Some("request-1"), event.correlation_id());
+        assert_eq!(Some("command-1"), event.causation_id());
+        assert_eq!(None, event.message_id());
+
+        // With no correlation id, the message id starts the correlation chain.
+        let command =
+            Envelope::from(StringMessage("command")).with_message_id("command-2".to_owned());
+        let event = Envelope::from(StringMessage("event")).caused_by(&command);
+
+        assert_eq!(Some("command-2"), event.correlation_id());
+        assert_eq!(Some("command-2"), event.causation_id());
+    }
 }
@@ -0,0 +1,843 @@
+//! Module `projection` contains abstractions to build read models (i.e. Projections)
+//! out of the Domain Events in an Event Store, the [Rebuilder] to rebuild them
+//! from scratch after their logic has changed, the [`ParallelProjector`] to speed
+//! up the rebuilds of large Event Stores, and the [`LagReporter`]
+//! to monitor how far behind the Event Store they are.
+
+use std::convert::Infallible;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use futures::channel::mpsc;
+use futures::{SinkExt, StreamExt, TryStreamExt};
+
+use crate::event::store::{GlobalStreamer, HeadSequence};
+use crate::runner::{Runner, Shutdown};
+use crate::{event, message};
+
+/// A read model built by applying, in order, the Domain Events
+/// across all the Event Streams of an Event Store.
+///
+/// Projections usually keep track of the [Sequence][event::Sequence] number
+/// of the last Domain Event projected (i.e. their checkpoint), so that they
+/// can resume from it using a [`CatchUp`][crate::subscription::CatchUp] subscription.
+#[async_trait]
+pub trait Projection<StreamId, Event>: Send + Sync
+where
+    StreamId: Send + Sync,
+    Event: message::Message + Send + Sync,
+{
+    /// The error type returned by the Projection.
+    type Error: Send + Sync;
+
+    /// Applies the Domain Event to the read model.
+    async fn project(&self, event: event::Sequenced<StreamId, Event>) -> Result<(), Self::Error>;
+
+    /// Applies a batch of Domain Events to the read model, in order,
+    /// e.g. using a single bulk upsert to save database round trips.
+    ///
+    /// Defaults to applying the Domain Events one by one through [`Projection::project`].
+    async fn project_batch(
+        &self,
+        events: Vec<event::Sequenced<StreamId, Event>>,
+    ) -> Result<(), Self::Error>
+    where
+        StreamId: 'async_trait,
+        Event: 'async_trait,
+    {
+        for event in events {
+            self.project(event).await?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A [Projection] that can be brought back to its initial state.
+#[async_trait]
+pub trait Resettable<StreamId, Event>: Projection<StreamId, Event>
+where
+    StreamId: Send + Sync,
+    Event: message::Message + Send + Sync,
+{
+    /// Clears both the checkpoint and the state of the [Projection].
+    async fn reset(&self) -> Result<(), Self::Error>;
+}
+
+/// All possible errors returned by [`Rebuilder::rebuild`].
+#[derive(Debug, thiserror::Error)]
+pub enum RebuildError<StreamErr, ProjectionErr> {
+    /// Error returned when the [Projection] could not be reset.
+    #[error("failed to reset projection: {0}")]
+    Reset(#[source] ProjectionErr),
+    /// Error returned while streaming the Domain Events from the Event Store.
+    #[error("failed to stream domain events: {0}")]
+    Stream(#[source] StreamErr),
+    /// Error returned when the [Projection] failed to project a Domain Event.
+    #[error("failed to project domain event #{sequence}: {error}")]
+    Project {
+        /// The [Sequence][event::Sequence] number of the Domain Event,
+        /// or of the first Domain Event of the batch that failed to be projected.
+        sequence: event::Sequence,
+        /// The error returned by the [Projection].
+        #[source]
+        error: ProjectionErr,
+    },
+}
+
+/// The progress of a [`Rebuilder::rebuild`] operation.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Progress {
+    /// Number of Domain Events projected so far.
+    pub projected_events: u64,
+    /// The [Sequence][event::Sequence] number of the last Domain Event projected, if any.
+    pub last_sequence: Option<event::Sequence>,
+}
+
+type ProgressCallback = Box<dyn Fn(Progress) + Send + Sync>;
+
+/// Rebuilds a [Resettable] [Projection] from scratch, by resetting it and
+/// replaying the full history of the Event Store through [`GlobalStreamer::stream_all`].
+pub struct Rebuilder<S> {
+    streamer: S,
+    batch_size: usize,
+    progress_interval: u64,
+    on_progress: Option<ProgressCallback>,
+}
+
+impl<S> From<S> for Rebuilder<S> {
+    fn from(streamer: S) -> Self {
+        Self {
+            streamer,
+            batch_size: 1,
+            progress_interval: 1000,
+            on_progress: None,
+        }
+    }
+}
+
+impl<S> Rebuilder<S> {
+    /// Calls the specified callback with the current [Progress] every
+    /// [`Rebuilder::with_progress_interval`] Domain Events, and once the rebuild is done.
+    #[must_use]
+    pub fn with_progress<F>(mut self, on_progress: F) -> Self
+    where
+        F: Fn(Progress) + Send + Sync + 'static,
+    {
+        self.on_progress = Some(Box::new(on_progress));
+        self
+    }
+
+    /// Sets how many Domain Events to project between two progress reports.
+    /// Defaults to 1000.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the interval is zero.
+    #[must_use]
+    pub fn with_progress_interval(mut self, interval: u64) -> Self {
+        assert!(interval > 0, "progress interval must be greater than zero");
+        self.progress_interval = interval;
+        self
+    }
+
+    /// Sets how many Domain Events to deliver at once to [`Projection::project_batch`].
+    /// Defaults to 1, i.e. every Domain Event is delivered on its own.
+    ///
+    /// The next batch is streamed from the Event Store only once the previous one
+    /// has been projected.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the batch size is zero.
+    #[must_use]
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        assert!(batch_size > 0, "batch size must be greater than zero");
+        self.batch_size = batch_size;
+        self
+    }
+
+    fn report(&self, progress: Progress) {
+        if let Some(on_progress) = &self.on_progress {
+            on_progress(progress);
+        }
+    }
+
+    /// Resets the [Projection] and projects all the Domain Events in the Event Store,
+    /// returning the final [Progress].
+    ///
+    /// # Errors
+    ///
+    /// An error is returned, and the rebuild stopped, if the [Projection] could not
+    /// be reset, if streaming fails, or if a Domain Event could not be projected.
+    pub async fn rebuild<Id, Evt, P>(
+        &self,
+        projection: &P,
+    ) -> Result<Progress, RebuildError<S::Error, P::Error>>
+    where
+        Id: Send + Sync,
+        Evt: message::Message + Send + Sync,
+        S: GlobalStreamer<Id, Evt>,
+        P: Resettable<Id, Evt>,
+    {
+        projection.reset().await.map_err(RebuildError::Reset)?;
+
+        let mut progress = Progress::default();
+        let mut batch = Vec::with_capacity(self.batch_size);
+        let mut events = self.streamer.stream_all(event::SequenceSelect::All);
+
+        while let Some(event) = events.try_next().await.map_err(RebuildError::Stream)? {
+            batch.push(event);
+
+            if batch.len() == self.batch_size {
+                self.project_batch(projection, &mut batch, &mut progress)
+                    .await?;
+            }
+        }
+
+        if !batch.is_empty() {
+            self.project_batch(projection, &mut batch, &mut progress)
+                .await?;
+        }
+
+        self.report(progress);
+
+        Ok(progress)
+    }
+
+    async fn project_batch<Id, Evt, P>(
+        &self,
+        projection: &P,
+        batch: &mut Vec<event::Sequenced<Id, Evt>>,
+        progress: &mut Progress,
+    ) -> Result<(), RebuildError<S::Error, P::Error>>
+    where
+        Id: Send + Sync,
+        Evt: message::Message + Send + Sync,
+        S: GlobalStreamer<Id, Evt>,
+        P: Projection<Id, Evt>,
+    {
+        let events = std::mem::replace(batch, Vec::with_capacity(self.batch_size));
+        let (Some(first), Some(last)) = (events.first(), events.last()) else {
+            return Ok(());
+        };
+
+        let (sequence, last_sequence) = (first.sequence, last.sequence);
+        let (projected_events, count) = (progress.projected_events, events.len() as u64);
+
+        projection
+            .project_batch(events)
+            .await
+            .map_err(|error| RebuildError::Project { sequence, error })?;
+
+        progress.projected_events += count;
+        progress.last_sequence = Some(last_sequence);
+
+        if progress.projected_events / self.progress_interval
+            > projected_events / self.progress_interval
+        {
+            self.report(*progress);
+        }
+
+        Ok(())
+    }
+}
+
+/// Default number of workers used by the [`ParallelProjector`].
+pub const DEFAULT_PARALLELISM: usize = 4;
+
+/// Number of Domain Events buffered for each worker of the [`ParallelProjector`].
+const WORKER_BUFFER_SIZE: usize = 64;
+
+/// Projects the Domain Events across all the Event Streams of an Event Store
+/// concurrently, using a fixed number of workers.
+///
+/// Every Event Stream is assigned to a single worker by hashing its id,
+/// so the Domain Events of the same Event Stream are always projected in order,
+/// while the Domain Events of different Event Streams are not.
+/// Use it only with [Projection]s whose state is partitioned by Event Stream.
+///
+/// The workers run concurrently on the task awaiting the [`ParallelProjector`],
+/// which is best suited for [Projection]s bound by I/O, such as database writes.
+#[derive(Debug, Clone)]
+pub struct ParallelProjector<S> {
+    streamer: S,
+    parallelism: usize,
+    batch_size: usize,
+}
+
+impl<S> From<S> for ParallelProjector<S> {
+    fn from(streamer: S) -> Self {
+        Self {
+            streamer,
+            parallelism: DEFAULT_PARALLELISM,
+            batch_size: 1,
+        }
+    }
+}
+
+impl<S> ParallelProjector<S> {
+    /// Sets the number of workers projecting the Domain Events concurrently.
+    /// Defaults to [`DEFAULT_PARALLELISM`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the parallelism is zero.
+    #[must_use]
+    pub fn with_parallelism(mut self, parallelism: usize) -> Self {
+        assert!(parallelism > 0, "parallelism must be greater than zero");
+        self.parallelism = parallelism;
+        self
+    }
+
+    /// Sets the maximum number of Domain Events delivered at once by each worker
+    /// to [`Projection::project_batch`]. Defaults to 1.
+    ///
+    /// Workers never wait for a batch to fill up: they deliver the Domain Events
+    /// already buffered, up to the batch size.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the batch size is zero.
+    #[must_use]
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        assert!(batch_size > 0, "batch size must be greater than zero");
+        self.batch_size = batch_size;
+        self
+    }
+
+    /// Resets the [Projection] and projects all the Domain Events in the Event Store,
+    /// returning the final [Progress].
+    ///
+    /// # Errors
+    ///
+    /// An error is returned, and the rebuild stopped, if the [Projection] could not
+    /// be reset, if streaming fails, or if a Domain Event could not be projected.
+    pub async fn rebuild<Id, Evt, P>(
+        &self,
+        projection: &P,
+    ) -> Result<Progress, RebuildError<S::Error, P::Error>>
+    where
+        Id: Hash + Send + Sync,
+        Evt: message::Message + Send + Sync,
+        S: GlobalStreamer<Id, Evt>,
+        P: Resettable<Id, Evt>,
+    {
+        projection.reset().await.map_err(RebuildError::Reset)?;
+
+        self.project(projection, event::SequenceSelect::All).await
+    }
+
+    /// Projects the Domain Events in the Event Store selected by the specified
+    /// [`event::SequenceSelect`], e.g. to catch up from a checkpoint,
+    /// returning the final [Progress].
+    ///
+    /// Since Domain Events are projected out of order across Event Streams,
+    /// the [Progress] is only meaningful as a checkpoint once this method returns.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned, and the projection stopped, if streaming fails,
+    /// or if a Domain Event could not be projected.
+    pub async fn project<Id, Evt, P>(
+        &self,
+        projection: &P,
+        select: event::SequenceSelect,
+    ) -> Result<Progress, RebuildError<S::Error, P::Error>>
+    where
+        Id: Hash + Send + Sync,
+        Evt: message::Message + Send + Sync,
+        S: GlobalStreamer<Id, Evt>,
+        P: Projection<Id, Evt>,
+    {
+        let (senders, receivers): (Vec<_>, Vec<_>) = (0..self.parallelism)
+            .map(|_| mpsc::channel::<event::Sequenced<Id, Evt>>(WORKER_BUFFER_SIZE))
+            .unzip();
+
+        let dispatcher = async move {
+            let mut senders = senders;
+            let mut last_sequence = None;
+            let mut events = self.streamer.stream_all(select);
+
+            while let Some(event) = events.try_next().await.map_err(RebuildError::Stream)? {
+                let mut hasher = DefaultHasher::new();
+                event.event.stream_id.hash(&mut hasher);
+
+                #[allow(clippy::cast_possible_truncation)]
+                let worker = (hasher.finish() % senders.len() as u64) as usize;
+
+                last_sequence = Some(event.sequence);
+
+                // NOTE: sending fails only if the worker has stopped because of an error,
+                // which is then returned by the worker itself.
+                if senders[worker].send(event).await.is_err() {
+                    break;
+                }
+            }
+
+            Ok(last_sequence)
+        };
+
+        let workers = receivers.into_iter().map(|receiver| async move {
+            let mut projected_events = 0;
+            let mut batches = receiver.ready_chunks(self.batch_size);
+
+            while let Some(events) = batches.next().await {
+                let sequence = events[0].sequence;
+                let count = events.len() as u64;
+
+                projection
+                    .project_batch(events)
+                    .await
+                    .map_err(|error| RebuildError::Project { sequence, error })?;
+
+                projected_events += count;
+            }
+
+            Ok(projected_events)
+        });
+
+        let (last_sequence, projected_events) =
+            futures::try_join!(dispatcher, futures::future::try_join_all(workers))?;
+
+        Ok(Progress {
+            projected_events: projected_events.into_iter().sum(),
+            last_sequence,
+        })
+    }
+}
+
+/// A component that keeps track of the [Sequence][event::Sequence] number
+/// of the last Domain Event it has processed (i.e. its checkpoint),
+/// usually a [Projection].
+#[async_trait]
+pub trait Checkpointed: Send + Sync {
+    /// The error type returned while reading the checkpoint.
+    type Error: Send + Sync;
+
+    /// Returns the [Sequence][event::Sequence] number of the last Domain Event
+    /// processed, or nothing if no Domain Event has been processed yet.
+    async fn checkpoint(&self) -> Result<Option<event::Sequence>, Self::Error>;
+}
+
+/// How far behind the head of the Event Store a [Checkpointed] projection is,
+/// as measured by the [`LagReporter`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Lag {
+    /// The checkpoint of the projection, if any.
+    pub checkpoint: Option<event::Sequence>,
+    /// The [Sequence][event::Sequence] number of the last Domain Event
+    /// appended to the Event Store, if any.
+    pub head: Option<event::Sequence>,
+    /// Number of Domain Events the projection still has to process.
+    pub events: u64,
+}
+
+/// All possible errors returned by the [`LagReporter`].
+#[derive(Debug, thiserror::Error)]
+pub enum LagError<StoreErr, CheckpointErr> {
+    /// Error returned when the head of the Event Store could not be read.
+    #[error("failed to read the event store head sequence: {0}")]
+    Head(#[source] StoreErr),
+    /// Error returned when the checkpoint of the projection could not be read.
+    #[error("failed to read the projection checkpoint: {0}")]
+    Checkpoint(#[source] CheckpointErr),
+}
+
+type LagCallback = Box<dyn Fn(Lag) + Send + Sync>;
+
+/// Periodically compares the checkpoint of a [Checkpointed] projection against
+/// the head of the Event Store, and reports the [Lag] through a callback,
+/// so that operators can be alerted when a projection is stuck.
+///
+/// With the `telemetry` feature, use `telemetry::Metrics::record_projection_lag`
+/// in the callback to expose the [Lag] as a metric.
+pub struct LagReporter<S, P> {
+    store: S,
+    projection: P,
+    interval: Duration,
+    on_lag: Option<LagCallback>,
+}
+
+impl<S, P> LagReporter<S, P>
+where
+    S: HeadSequence,
+    P: Checkpointed,
+{
+    /// Creates a new [`LagReporter`] for the specified projection,
+    /// reporting every 10 seconds by default.
+    pub fn new(store: S, projection: P) -> Self {
+        Self {
+            store,
+            projection,
+            interval: Duration::from_secs(10),
+            on_lag: None,
+        }
+    }
+
+    /// Sets how often the [Lag] is measured by [`LagReporter::run`].
+    #[must_use]
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Calls the specified callback with every [Lag] measured.
+    #[must_use]
+    pub fn with_callback<F>(mut self, on_lag: F) -> Self
+    where
+        F: Fn(Lag) + Send + Sync + 'static,
+    {
+        self.on_lag = Some(Box::new(on_lag));
+        self
+    }
+
+    /// Measures the current [Lag] of the projection, and reports it to the callback.
+    ///
+    /// The head of the Event Store is read after the checkpoint, so that a projection
+    /// that is keeping up is never reported to be ahead of the Event Store.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if either the checkpoint or the head could not be read.
+    pub async fn check(&self) -> Result<Lag, LagError<S::Error, P::Error>> {
+        let checkpoint = self
+            .projection
+            .checkpoint()
+            .await
+            .map_err(LagError::Checkpoint)?;
+
+        let head = self.store.head_sequence().await.map_err(LagError::Head)?;
+
+        let lag = Lag {
+            checkpoint,
+            head,
+            events: head
+                .unwrap_or_default()
+                .saturating_sub(checkpoint.unwrap_or_default()),
+        };
+
+        if let Some(on_lag) = &self.on_lag {
+            on_lag(lag);
+        }
+
+        Ok(lag)
+    }
+
+    /// Measures and reports the [Lag] of the projection at every interval,
+    /// until an error occurs.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if either the checkpoint or the head could not be read.
+    pub async fn run(&self) -> Result<Infallible, LagError<S::Error, P::Error>> {
+        loop {
+            self.check().await?;
+            futures_timer::Delay::new(self.interval).await;
+        }
+    }
+}
+
+#[async_trait]
+impl<S, P> Runner for LagReporter<S, P>
+where
+    S: HeadSequence,
+    P: Checkpointed,
+{
+    type Error = LagError<S::Error, P::Error>;
+
+    /// Measures and reports the [Lag] of the projection at every interval,
+    /// like [`LagReporter::run`], until the [Shutdown] is requested or an error occurs.
+    async fn run_until(&self, shutdown: Shutdown) -> Result<(), Self::Error> {
+        while !shutdown.is_requested() {
+            self.check().await?;
+            shutdown.sleep(self.interval).await;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+    use crate::event::store::{Appender, InMemory};
+    use crate::message::tests::StringMessage;
+    use crate::version;
+
+    /// Counts the Domain Events of each Event Stream.
+    #[derive(Default)]
+    struct EventsCount(Mutex<HashMap<&'static str, usize>>);
+
+    #[async_trait]
+    impl Projection<&'static str, StringMessage> for EventsCount {
+        type Error = Infallible;
+
+        async fn project(
+            &self,
+            event: event::Sequenced<&'static str, StringMessage>,
+        ) -> Result<(), Self::Error> {
+            *self
+                .0
+                .lock()
+                .unwrap()
+                .entry(event.event.stream_id)
+                .or_default() += 1;
+
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl Resettable<&'static str, StringMessage> for EventsCount {
+        async fn reset(&self) -> Result<(), Self::Error> {
+            self.0.lock().unwrap().clear();
+            Ok(())
+        }
+    }
+
+    /// Checkpoint of a projection that is not projecting anything.
+    struct StuckProjection(Option<event::Sequence>);
+
+    #[async_trait]
+    impl Checkpointed for StuckProjection {
+        type Error = Infallible;
+
+        async fn checkpoint(&self) -> Result<Option<event::Sequence>, Self::Error> {
+            Ok(self.0)
+        }
+    }
+
+    #[tokio::test]
+    async fn lag_reporter_reports_the_events_behind_the_head() {
+        let event_store = InMemory::<&'static str, StringMessage>::default();
+        let reports = Arc::new(Mutex::new(Vec::new()));
+
+        let reporter = LagReporter::new(event_store.clone(), StuckProjection(Some(1)))
+            .with_callback({
+                let reports = reports.clone();
+                move |lag| reports.lock().unwrap().push(lag.events)
+            });
+
+        assert_eq!(
+            Lag {
+                checkpoint: Some(1),
+                head: None,
+                events: 0,
+            },
+            reporter.check().await.unwrap()
+        );
+
+        event_store
+            .append(
+                "stream:a",
+                version::Check::Any,
+                vec![event::Envelope::from(StringMessage("event")); 3],
+            )
+            .await
+            .expect("append should not fail");
+
+        assert_eq!(
+            Lag {
+                checkpoint: Some(1),
+                head: Some(3),
+                events: 2,
+            },
+            reporter.check().await.unwrap()
+        );
+
+        assert_eq!(vec![0, 2], *reports.lock().unwrap());
+    }
+
+    #[tokio::test]
+    async fn rebuilder_resets_and_replays_the_full_history() {
+        let event_store = InMemory::<&'static str, StringMessage>::default();
+
+        for (stream_id, count) in [("stream:a", 3), ("stream:b", 2)] {
+            event_store
+                .append(
+                    stream_id,
+                    version::Check::MustBe(0),
+                    vec![event::Envelope::from(StringMessage("event")); count],
+                )
+                .await
+                .expect("append should not fail");
+        }
+
+        let projection = EventsCount::default();
+        projection.0.lock().unwrap().insert("stream:stale", 42);
+
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        let progress = Rebuilder::from(event_store)
+            .with_progress_interval(2)
+            .with_progress({
+                let reports = reports.clone();
+                move |progress| reports.lock().unwrap().push(progress.projected_events)
+            })
+            .rebuild(&projection)
+            .await
+            .expect("rebuild should not fail");
+
+        assert_eq!(
+            Progress {
+                projected_events: 5,
+                last_sequence: Some(5),
+            },
+            progress
+        );
+        assert_eq!(vec![2, 4, 5], *reports.lock().unwrap());
+        assert_eq!(
+            HashMap::from([("stream:a", 3), ("stream:b", 2)]),
+            *projection.0.lock().unwrap()
+        );
+    }
+
+    /// Records the versions of the projected Domain Events of each Event Stream.
+    #[derive(Default)]
+    struct StreamVersions(Mutex<HashMap<&'static str, Vec<version::Version>>>);
+
+    #[async_trait]
+    impl Projection<&'static str, StringMessage> for StreamVersions {
+        type Error = Infallible;
+
+        async fn project(
+            &self,
+            event: event::Sequenced<&'static str, StringMessage>,
+        ) -> Result<(), Self::Error> {
+            // Let the other workers interleave with this one.
+            tokio::task::yield_now().await;
+
+            self.0
+                .lock()
+                .unwrap()
+                .entry(event.event.stream_id)
+                .or_default()
+                .push(event.event.version);
+
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl Resettable<&'static str, StringMessage> for StreamVersions {
+        async fn reset(&self) -> Result<(), Self::Error> {
+            self.0.lock().unwrap().clear();
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn parallel_projector_preserves_the_order_of_each_event_stream() {
+        const STREAM_IDS: [&str; 5] = ["stream:a", "stream:b", "stream:c", "stream:d", "stream:e"];
+
+        let event_store = InMemory::<&'static str, StringMessage>::default();
+
+        for _ in 0..10 {
+            for stream_id in STREAM_IDS {
+                event_store
+                    .append(
+                        stream_id,
+                        version::Check::Any,
+                        vec![event::Envelope::from(StringMessage("event"))],
+                    )
+                    .await
+                    .expect("append should not fail");
+            }
+        }
+
+        let projection = StreamVersions::default();
+        let progress = ParallelProjector::from(event_store)
+            .with_parallelism(3)
+            .rebuild(&projection)
+            .await
+            .expect("rebuild should not fail");
+
+        assert_eq!(
+            Progress {
+                projected_events: 50,
+                last_sequence: Some(50),
+            },
+            progress
+        );
+
+        let versions = projection.0.lock().unwrap();
+        assert_eq!(STREAM_IDS.len(), versions.len());
+
+        for stream_id in STREAM_IDS {
+            assert_eq!((1..=10).collect::<Vec<_>>(), versions[stream_id]);
+        }
+    }
+
+    /// Records the size of every batch of Domain Events projected.
+    #[derive(Default)]
+    struct BatchSizes(Mutex<Vec<usize>>);
+
+    #[async_trait]
+    impl Projection<&'static str, StringMessage> for BatchSizes {
+        type Error = Infallible;
+
+        async fn project(
+            &self,
+            event: event::Sequenced<&'static str, StringMessage>,
+        ) -> Result<(), Self::Error> {
+            self.project_batch(vec![event]).await
+        }
+
+        async fn project_batch(
+            &self,
+            events: Vec<event::Sequenced<&'static str, StringMessage>>,
+        ) -> Result<(), Self::Error> {
+            self.0.lock().unwrap().push(events.len());
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl Resettable<&'static str, StringMessage> for BatchSizes {
+        async fn reset(&self) -> Result<(), Self::Error> {
+            self.0.lock().unwrap().clear();
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn rebuilder_delivers_the_events_in_batches() {
+        let event_store = InMemory::<&'static str, StringMessage>::default();
+
+        event_store
+            .append(
+                "stream:a",
+                version::Check::MustBe(0),
+                vec![event::Envelope::from(StringMessage("event")); 5],
+            )
+            .await
+            .expect("append should not fail");
+
+        let projection = BatchSizes::default();
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        let progress = Rebuilder::from(event_store)
+            .with_batch_size(2)
+            .with_progress_interval(2)
+            .with_progress({
+                let reports = reports.clone();
+                move |progress| reports.lock().unwrap().push(progress.projected_events)
+            })
+            .rebuild(&projection)
+            .await
+            .expect("rebuild should not fail");
+
+        assert_eq!(
+            Progress {
+                projected_events: 5,
+                last_sequence: Some(5),
+            },
+            progress
+        );
+        assert_eq!(vec![2, 2, 1], *projection.0.lock().unwrap());
+        assert_eq!(vec![2, 4, 5], *reports.lock().unwrap());
+    }
+}
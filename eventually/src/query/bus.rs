@@ -0,0 +1,605 @@
+//! Module containing a Query [Bus], which routes [Query][Envelope] messages
+//! to the [Handler] registered for their type.
+//!
+//! Cross-cutting concerns (e.g. tracing, caching, timeouts) can be plugged in
+//! the [Bus] through [Middleware]s, which are called in registration order
+//! before reaching the [Handler].
+
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+use std::sync::{Arc, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use futures::future::{self, Either};
+
+use crate::message;
+use crate::query::{Envelope, Handler};
+
+/// The type-erased result of a [Query][Envelope] evaluation.
+pub type Output = Arc<dyn Any + Send + Sync>;
+
+/// All possible errors returned by the [Bus] when dispatching a [Query][Envelope].
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// Error returned when no [Handler] has been registered for the Query type.
+    #[error("no handler registered for query: {0}")]
+    HandlerNotFound(&'static str),
+    /// Error returned when the [Handler] output is not of the type requested
+    /// by the caller.
+    #[error("unexpected output type for query: {0}")]
+    UnexpectedOutput(&'static str),
+    /// Error returned by the [Timeout] middleware when the Query evaluation
+    /// takes longer than allowed.
+    #[error("query evaluation timed out after {0:?}")]
+    Timeout(Duration),
+    /// Error returned when the [Handler] has failed to evaluate the Query.
+    #[error("failed to handle query: {0}")]
+    Handler(#[source] anyhow::Error),
+}
+
+/// A type-erased [Query][Envelope], as seen by the [Middleware]s of the [Bus].
+pub struct Query {
+    /// The name of the Query message.
+    pub name: &'static str,
+    /// The metadata of the Query [Envelope].
+    pub metadata: message::Metadata,
+    type_id: TypeId,
+    payload: Box<dyn Any + Send + Sync>,
+}
+
+impl Query {
+    /// Returns a reference to the Query message, if it is of type `T`.
+    #[must_use]
+    pub fn downcast_ref<T>(&self) -> Option<&T>
+    where
+        T: 'static,
+    {
+        self.payload.downcast_ref()
+    }
+}
+
+/// A Middleware wraps the evaluation of all the [Query][Envelope]
+/// dispatched through the [Bus], regardless of their type.
+#[async_trait]
+pub trait Middleware: Send + Sync {
+    /// Handles the [Query], usually by calling [`Next::run`] to continue
+    /// the evaluation down the chain.
+    ///
+    /// # Errors
+    ///
+    /// The Middleware can stop the evaluation early by returning an [Error].
+    async fn handle(&self, query: Query, next: Next<'_>) -> Result<Output, Error>;
+}
+
+/// The remaining [Middleware]s and the [Handler] that will evaluate the [Query].
+pub struct Next<'a> {
+    middlewares: &'a [Arc<dyn Middleware>],
+    handler: &'a dyn ErasedHandler,
+}
+
+impl Next<'_> {
+    /// Continues the evaluation of the [Query] down the chain.
+    ///
+    /// # Errors
+    ///
+    /// The error returned by the next [Middleware] or [Handler].
+    pub async fn run(self, query: Query) -> Result<Output, Error> {
+        match self.middlewares.split_first() {
+            Some((middleware, middlewares)) => {
+                middleware
+                    .handle(
+                        query,
+                        Next {
+                            middlewares,
+                            handler: self.handler,
+                        },
+                    )
+                    .await
+            },
+            None => self.handler.handle(query).await,
+        }
+    }
+}
+
+#[async_trait]
+trait ErasedHandler: Send + Sync {
+    async fn handle(&self, query: Query) -> Result<Output, Error>;
+}
+
+struct TypedHandler<T, H> {
+    handler: H,
+    query: PhantomData<fn() -> T>,
+}
+
+#[async_trait]
+impl<T, H> ErasedHandler for TypedHandler<T, H>
+where
+    T: message::Message + Send + Sync + 'static,
+    H: Handler<T>,
+    H::Output: 'static,
+    H::Error: Into<anyhow::Error>,
+{
+    async fn handle(&self, query: Query) -> Result<Output, Error> {
+        let name = query.name;
+        let message = query
+            .payload
+            .downcast::<T>()
+            .map_err(|_| Error::HandlerNotFound(name))?;
+
+        let output = self
+            .handler
+            .handle(Envelope {
+                message: *message,
+                metadata: query.metadata,
+            })
+            .await
+            .map_err(|err| Error::Handler(err.into()))?;
+
+        Ok(Arc::new(output))
+    }
+}
+
+/// Routes [Queries][Envelope] to the [Handler] registered for their type,
+/// through the registered [Middleware]s.
+#[derive(Default, Clone)]
+pub struct Bus {
+    handlers: HashMap<TypeId, Arc<dyn ErasedHandler>>,
+    middlewares: Vec<Arc<dyn Middleware>>,
+}
+
+impl Bus {
+    /// Registers the [Handler] to use for all the Queries of type `T`,
+    /// replacing any previously registered one.
+    #[must_use]
+    pub fn register<T, H>(mut self, handler: H) -> Self
+    where
+        T: message::Message + Send + Sync + 'static,
+        H: Handler<T> + 'static,
+        H::Output: 'static,
+        H::Error: Into<anyhow::Error>,
+    {
+        self.handlers.insert(
+            TypeId::of::<T>(),
+            Arc::new(TypedHandler {
+                handler,
+                query: PhantomData,
+            }),
+        );
+
+        self
+    }
+
+    /// Adds a new [Middleware] to the [Bus].
+    ///
+    /// Middlewares are called in the same order they have been added.
+    #[must_use]
+    pub fn with_middleware<M>(mut self, middleware: M) -> Self
+    where
+        M: Middleware + 'static,
+    {
+        self.middlewares.push(Arc::new(middleware));
+        self
+    }
+
+    /// Dispatches the [Query][Envelope] to the [Handler] registered for its type,
+    /// and returns its output.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if no [Handler] has been registered for the Query,
+    /// if the output of the [Handler] is not of type `R`, or if the evaluation
+    /// fails in either a [Middleware] or the [Handler].
+    pub async fn dispatch<T, R>(&self, query: Envelope<T>) -> Result<R, Error>
+    where
+        T: message::Message + Send + Sync + 'static,
+        R: Clone + Send + Sync + 'static,
+    {
+        let name = query.message.name();
+        let handler = self
+            .handlers
+            .get(&TypeId::of::<T>())
+            .ok_or(Error::HandlerNotFound(name))?;
+
+        let next = Next {
+            middlewares: &self.middlewares,
+            handler: handler.as_ref(),
+        };
+
+        let output = next
+            .run(Query {
+                name,
+                metadata: query.metadata,
+                type_id: TypeId::of::<T>(),
+                payload: Box::new(query.message),
+            })
+            .await?
+            .downcast::<R>()
+            .map_err(|_| Error::UnexpectedOutput(name))?;
+
+        Ok(Arc::try_unwrap(output).unwrap_or_else(|output| output.as_ref().clone()))
+    }
+}
+
+/// [Middleware] that fails the evaluation of a [Query] with [`Error::Timeout`]
+/// if it takes longer than the specified duration.
+#[derive(Debug, Clone, Copy)]
+pub struct Timeout(pub Duration);
+
+#[async_trait]
+impl Middleware for Timeout {
+    async fn handle(&self, query: Query, next: Next<'_>) -> Result<Output, Error> {
+        let evaluation = next.run(query);
+        let timeout = futures_timer::Delay::new(self.0);
+
+        futures::pin_mut!(evaluation);
+
+        match future::select(evaluation, timeout).await {
+            Either::Left((result, _)) => result,
+            Either::Right(((), _)) => Err(Error::Timeout(self.0)),
+        }
+    }
+}
+
+/// A type-erased Query message, compared and hashed through its concrete type.
+trait CacheKeyValue: Any + Send + Sync {
+    fn as_any(&self) -> &dyn Any;
+    fn eq_value(&self, other: &dyn CacheKeyValue) -> bool;
+    fn hash_value(&self, state: &mut dyn Hasher);
+}
+
+impl<T> CacheKeyValue for T
+where
+    T: Eq + Hash + Send + Sync + 'static,
+{
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn eq_value(&self, other: &dyn CacheKeyValue) -> bool {
+        other.as_any().downcast_ref::<T>() == Some(self)
+    }
+
+    fn hash_value(&self, mut state: &mut dyn Hasher) {
+        self.hash(&mut state);
+    }
+}
+
+/// The key of a cached Query output: the whole Query message, together with
+/// the values of the metadata keys selected through [`Cache::with_metadata_key`].
+struct CacheKey {
+    type_id: TypeId,
+    metadata: Vec<Option<String>>,
+    value: Box<dyn CacheKeyValue>,
+}
+
+impl PartialEq for CacheKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.type_id == other.type_id
+            && self.metadata == other.metadata
+            && self.value.eq_value(other.value.as_ref())
+    }
+}
+
+impl Eq for CacheKey {}
+
+impl Hash for CacheKey {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.type_id.hash(state);
+        self.metadata.hash(state);
+        self.value.hash_value(state);
+    }
+}
+
+type CacheKeyFn = fn(&Query) -> Option<Box<dyn CacheKeyValue>>;
+
+#[derive(Default)]
+struct CacheEntries {
+    outputs: HashMap<CacheKey, (Instant, Output)>,
+    evicted_at: Option<Instant>,
+}
+
+impl CacheEntries {
+    /// Evicts the expired outputs, at most once per time-to-live,
+    /// so that each output is kept for at most twice its time-to-live.
+    fn evict_expired(&mut self, ttl: Duration) {
+        if self
+            .evicted_at
+            .is_some_and(|evicted_at| evicted_at.elapsed() < ttl)
+        {
+            return;
+        }
+
+        self.outputs
+            .retain(|_, (inserted_at, _)| inserted_at.elapsed() < ttl);
+        self.evicted_at = Some(Instant::now());
+    }
+}
+
+/// [Middleware] that caches the outputs of the Query types opted in
+/// through [`Cache::cache`], for the specified time-to-live.
+///
+/// Queries are cached by their whole message value, together with the values
+/// of the metadata keys selected through [`Cache::with_metadata_key`]
+/// (e.g. the tenant of the Query), while the rest of their metadata is ignored.
+#[derive(Clone)]
+pub struct Cache {
+    ttl: Duration,
+    keys: HashMap<TypeId, CacheKeyFn>,
+    metadata_keys: Vec<String>,
+    entries: Arc<RwLock<CacheEntries>>,
+}
+
+impl Cache {
+    /// Returns a new [Cache] middleware, caching outputs for the specified duration.
+    #[must_use]
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            keys: HashMap::default(),
+            metadata_keys: Vec::default(),
+            entries: Arc::default(),
+        }
+    }
+
+    /// Enables caching of the outputs of the Queries of type `T`.
+    #[must_use]
+    pub fn cache<T>(mut self) -> Self
+    where
+        T: message::Message + Clone + Eq + Hash + Send + Sync + 'static,
+    {
+        self.keys.insert(TypeId::of::<T>(), |query| {
+            let message: Box<dyn CacheKeyValue> = Box::new(query.downcast_ref::<T>()?.clone());
+            Some(message)
+        });
+
+        self
+    }
+
+    /// Caches the outputs separately for each value of the specified metadata key,
+    /// e.g. [`message::TENANT_ID_KEY`], so that Queries with the same message
+    /// but a different value do not share their outputs.
+    #[must_use]
+    pub fn with_metadata_key(mut self, key: impl Into<String>) -> Self {
+        self.metadata_keys.push(key.into());
+        self
+    }
+
+    fn key(&self, query: &Query) -> Option<CacheKey> {
+        let value = self.keys.get(&query.type_id)?(query)?;
+
+        Some(CacheKey {
+            type_id: query.type_id,
+            metadata: self
+                .metadata_keys
+                .iter()
+                .map(|key| query.metadata.get(key).cloned())
+                .collect(),
+            value,
+        })
+    }
+
+    // NOTE: a thread panicking while holding the lock might have left the cache
+    // in an inconsistent state: since the Query handlers are the source of truth,
+    // the cache is emptied instead of propagating the panic.
+    fn recover_entries(&self) {
+        *self.entries.write().unwrap_or_else(PoisonError::into_inner) = CacheEntries::default();
+
+        self.entries.clear_poison();
+    }
+
+    fn read_entries(&self) -> RwLockReadGuard<'_, CacheEntries> {
+        if self.entries.is_poisoned() {
+            self.recover_entries();
+        }
+
+        self.entries.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn write_entries(&self) -> RwLockWriteGuard<'_, CacheEntries> {
+        if self.entries.is_poisoned() {
+            self.recover_entries();
+        }
+
+        self.entries.write().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[async_trait]
+impl Middleware for Cache {
+    async fn handle(&self, query: Query, next: Next<'_>) -> Result<Output, Error> {
+        let Some(key) = self.key(&query) else {
+            return next.run(query).await;
+        };
+
+        let cached_output = self
+            .read_entries()
+            .outputs
+            .get(&key)
+            .filter(|(inserted_at, _)| inserted_at.elapsed() < self.ttl)
+            .map(|(_, output)| output.clone());
+
+        if let Some(output) = cached_output {
+            return Ok(output);
+        }
+
+        let output = next.run(query).await?;
+
+        let mut entries = self.write_entries();
+        entries.evict_expired(self.ttl);
+        entries
+            .outputs
+            .insert(key, (Instant::now(), output.clone()));
+
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+    struct GetUserName(u32);
+
+    impl message::Message for GetUserName {
+        fn name(&self) -> &'static str {
+            "GetUserName"
+        }
+    }
+
+    #[derive(Debug, Clone)]
+    struct GetUserAge(u32);
+
+    impl message::Message for GetUserAge {
+        fn name(&self) -> &'static str {
+            "GetUserAge"
+        }
+    }
+
+    #[derive(Default)]
+    struct UserNames(AtomicUsize);
+
+    #[async_trait]
+    impl Handler<GetUserName> for Arc<UserNames> {
+        type Output = String;
+        type Error = anyhow::Error;
+
+        async fn handle(&self, query: Envelope<GetUserName>) -> Result<String, Self::Error> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            Ok(format!("user-{}", query.message.0))
+        }
+    }
+
+    #[tokio::test]
+    async fn bus_routes_queries_to_the_registered_handler() {
+        let bus = Bus::default()
+            .register(Arc::new(UserNames::default()))
+            .register(|query: Envelope<GetUserAge>| async move {
+                Ok::<_, anyhow::Error>(query.message.0 + 20)
+            });
+
+        let name: String = bus
+            .dispatch(Envelope::from(GetUserName(1)))
+            .await
+            .expect("query should be handled");
+
+        let age: u32 = bus
+            .dispatch(Envelope::from(GetUserAge(1)))
+            .await
+            .expect("query should be handled");
+
+        assert_eq!("user-1", name);
+        assert_eq!(21, age);
+
+        let error = bus
+            .dispatch::<_, u64>(Envelope::from(GetUserAge(1)))
+            .await
+            .expect_err("the output type is not u64");
+
+        assert!(matches!(error, Error::UnexpectedOutput("GetUserAge")));
+    }
+
+    #[tokio::test]
+    async fn bus_fails_when_no_handler_is_registered() {
+        let error = Bus::default()
+            .dispatch::<_, String>(Envelope::from(GetUserName(1)))
+            .await
+            .expect_err("no handler has been registered");
+
+        assert!(matches!(error, Error::HandlerNotFound("GetUserName")));
+    }
+
+    #[tokio::test]
+    async fn cache_middleware_avoids_calling_the_handler_again() {
+        let handler = Arc::new(UserNames::default());
+        let bus = Bus::default()
+            .register(handler.clone())
+            .with_middleware(Cache::new(Duration::from_mins(1)).cache::<GetUserName>());
+
+        for _ in 0..3 {
+            let name: String = bus
+                .dispatch(Envelope::from(GetUserName(1)))
+                .await
+                .expect("query should be handled");
+
+            assert_eq!("user-1", name);
+        }
+
+        let _: String = bus
+            .dispatch(Envelope::from(GetUserName(2)))
+            .await
+            .expect("query should be handled");
+
+        assert_eq!(2, handler.0.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn cache_middleware_keeps_the_outputs_of_each_tenant_apart() {
+        let handler = Arc::new(UserNames::default());
+        let bus = Bus::default().register(handler.clone()).with_middleware(
+            Cache::new(Duration::from_mins(1))
+                .cache::<GetUserName>()
+                .with_metadata_key(message::TENANT_ID_KEY),
+        );
+
+        for tenant in ["tenant-a", "tenant-b", "tenant-a"] {
+            let _: String = bus
+                .dispatch(Envelope::from(GetUserName(1)).with_tenant_id(tenant.to_owned()))
+                .await
+                .expect("query should be handled");
+        }
+
+        assert_eq!(2, handler.0.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn cache_middleware_evicts_the_expired_outputs() {
+        let cache = Cache::new(Duration::from_millis(10)).cache::<GetUserName>();
+        let bus = Bus::default()
+            .register(Arc::new(UserNames::default()))
+            .with_middleware(cache.clone());
+
+        let _: String = bus
+            .dispatch(Envelope::from(GetUserName(1)))
+            .await
+            .expect("query should be handled");
+
+        futures_timer::Delay::new(Duration::from_millis(20)).await;
+
+        let _: String = bus
+            .dispatch(Envelope::from(GetUserName(2)))
+            .await
+            .expect("query should be handled");
+
+        let entries = cache.read_entries();
+        let cached: Vec<_> = entries
+            .outputs
+            .keys()
+            .filter_map(|key| key.value.as_any().downcast_ref::<GetUserName>())
+            .collect();
+
+        assert_eq!(vec![&GetUserName(2)], cached);
+    }
+
+    #[tokio::test]
+    async fn timeout_middleware_fails_slow_queries() {
+        let bus = Bus::default()
+            .register(|_: Envelope<GetUserAge>| async move {
+                futures_timer::Delay::new(Duration::from_secs(5)).await;
+                Ok::<u32, anyhow::Error>(42)
+            })
+            .with_middleware(Timeout(Duration::from_millis(10)));
+
+        let error = bus
+            .dispatch::<_, u32>(Envelope::from(GetUserAge(1)))
+            .await
+            .expect_err("the query should time out");
+
+        assert!(matches!(error, Error::Timeout(_)));
+    }
+}
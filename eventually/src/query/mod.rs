@@ -1,7 +1,10 @@
 //! Module `query` contains types and helpful abstractions to model Domain Queries
 //! and implement Domain Query Handlers.
 
+pub mod bus;
+
 use async_trait::async_trait;
+pub use bus::Bus;
 use futures::Future;
 
 use crate::message;
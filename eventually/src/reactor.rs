@@ -0,0 +1,443 @@
+//! Module `reactor` contains support for the "when X happened, do Y" automation
+//! pattern: a [Reactor] consumes a subscription of Domain Events, applies
+//! a [Policy] to each of them, and dispatches the resulting Commands
+//! through the [Command Bus][command::Bus].
+//!
+//! The [Sequence][event::Sequence] of the last Domain Event reacted to is stored
+//! in a [`CheckpointStore`], so that the [Reactor] resumes from where it left off.
+
+use std::time::Duration;
+
+use futures::{StreamExt, TryStreamExt};
+
+use crate::command::bus;
+use crate::runner::Shutdown;
+use crate::subscription::CheckpointStore;
+use crate::{command, event, message};
+
+/// Decides which Commands should be dispatched as a reaction to a Domain Event.
+///
+/// Implemented by all the functions with the same signature as [`Policy::react`].
+pub trait Policy<StreamId, Event>: Send + Sync
+where
+    Event: message::Message,
+{
+    /// Returns the Commands to dispatch as a reaction to the Domain Event,
+    /// if any.
+    ///
+    /// Commands are dispatched in order, and are automatically marked as caused
+    /// by the Domain Event, unless they carry their own causation metadata.
+    fn react(&self, event: &event::Sequenced<StreamId, Event>) -> Vec<bus::Command>;
+}
+
+impl<StreamId, Event, F> Policy<StreamId, Event> for F
+where
+    Event: message::Message,
+    F: Fn(&event::Sequenced<StreamId, Event>) -> Vec<bus::Command> + Send + Sync,
+{
+    fn react(&self, event: &event::Sequenced<StreamId, Event>) -> Vec<bus::Command> {
+        self(event)
+    }
+}
+
+/// All possible errors returned by a [Reactor].
+#[derive(Debug, thiserror::Error)]
+pub enum ReactorError<StreamErr, CheckpointErr> {
+    /// Error returned when the subscription has failed.
+    #[error("failed to receive domain events from the subscription: {0}")]
+    Stream(#[source] StreamErr),
+    /// Error returned when a Command could not be dispatched, even after retrying.
+    #[error("failed to dispatch command in reaction to event {sequence}: {error}")]
+    Dispatch {
+        /// The [Sequence][event::Sequence] of the Domain Event being reacted to.
+        sequence: event::Sequence,
+        /// The error returned by the [Command Bus][command::Bus].
+        #[source]
+        error: bus::Error,
+    },
+    /// Error returned when the checkpoint could not be read or stored
+    /// in the [`CheckpointStore`].
+    #[error("failed to access the reactor checkpoint: {0}")]
+    Checkpoint(#[source] CheckpointErr),
+}
+
+/// Consumes a subscription of Domain Events, dispatching the Commands decided by
+/// the [Policy] through the [Command Bus][command::Bus], and storing the progress
+/// in a [`CheckpointStore`], under the name of the [Reactor], after each Domain Event.
+///
+/// Commands that fail with a [`bus::Error::Handler`] error are retried,
+/// up to a maximum number of attempts; any other dispatch error stops the [Reactor].
+/// Since the checkpoint is stored only after all the Commands have been dispatched,
+/// a Domain Event might be reacted to more than once after a failure:
+/// make sure the Commands are idempotent, e.g. through an
+/// [`Idempotent`][command::handler::Idempotent] handler.
+pub struct Reactor<P, C> {
+    policy: P,
+    bus: command::Bus,
+    checkpoints: C,
+    name: String,
+    max_attempts: usize,
+    backoff: Duration,
+}
+
+impl<P, C> Reactor<P, C>
+where
+    C: CheckpointStore,
+{
+    /// Creates a new [Reactor], storing its checkpoint in the [`CheckpointStore`]
+    /// under the specified name, and attempting to dispatch each Command
+    /// at most 3 times by default.
+    pub fn new(policy: P, bus: command::Bus, checkpoints: C, name: impl Into<String>) -> Self {
+        Self {
+            policy,
+            bus,
+            checkpoints,
+            name: name.into(),
+            max_attempts: 3,
+            backoff: Duration::ZERO,
+        }
+    }
+
+    /// Sets the maximum number of times the dispatch of a Command is attempted,
+    /// including the first one.
+    #[must_use]
+    pub fn with_max_attempts(mut self, max_attempts: usize) -> Self {
+        self.max_attempts = max_attempts;
+        self
+    }
+
+    /// Sets the duration to wait before the first retry, doubled on every
+    /// subsequent retry. Defaults to no wait.
+    #[must_use]
+    pub fn with_backoff(mut self, backoff: Duration) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Dispatches the Commands decided by the [Policy] as a reaction to the Domain Event,
+    /// then stores its [Sequence][event::Sequence] as the checkpoint of the [Reactor].
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if a Command could not be dispatched,
+    /// or if the checkpoint could not be stored.
+    pub async fn react<Id, Evt, E>(
+        &self,
+        event: event::Sequenced<Id, Evt>,
+    ) -> Result<(), ReactorError<E, C::Error>>
+    where
+        Evt: message::Message,
+        P: Policy<Id, Evt>,
+    {
+        let causation = message::causation_metadata(&event.event.event);
+
+        for mut command in self.policy.react(&event) {
+            for (key, value) in &causation {
+                command
+                    .metadata
+                    .entry(key.clone())
+                    .or_insert_with(|| value.clone());
+            }
+
+            self.dispatch(command)
+                .await
+                .map_err(|error| ReactorError::Dispatch {
+                    sequence: event.sequence,
+                    error,
+                })?;
+        }
+
+        self.checkpoints
+            .save(&self.name, event.sequence)
+            .await
+            .map_err(ReactorError::Checkpoint)
+    }
+
+    async fn dispatch(&self, command: bus::Command) -> Result<(), bus::Error> {
+        let mut attempt = 1;
+        let mut backoff = self.backoff;
+
+        loop {
+            match self.bus.dispatch_command(command.clone()).await {
+                Err(bus::Error::Handler(_)) if attempt < self.max_attempts => {
+                    attempt += 1;
+
+                    if !backoff.is_zero() {
+                        futures_timer::Delay::new(backoff).await;
+                        backoff *= 2;
+                    }
+                },
+                result => return result,
+            }
+        }
+    }
+
+    /// Opens the subscription through `subscribe`, starting right after
+    /// the checkpoint of the [Reactor], and reacts to all the Domain Events it delivers,
+    /// until the subscription ends or an error occurs.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the subscription fails, if a Command could not be
+    /// dispatched, or if the checkpoint could not be read or stored.
+    pub async fn run<'a, Id, Evt, E, F>(
+        &self,
+        subscribe: F,
+    ) -> Result<(), ReactorError<E, C::Error>>
+    where
+        Evt: message::Message,
+        P: Policy<Id, Evt>,
+        F: FnOnce(event::SequenceSelect) -> event::SequencedStream<'a, Id, Evt, E>,
+    {
+        let select = match self
+            .checkpoints
+            .load(&self.name)
+            .await
+            .map_err(ReactorError::Checkpoint)?
+        {
+            None => event::SequenceSelect::All,
+            Some(sequence) => event::SequenceSelect::From(sequence + 1),
+        };
+
+        let mut events = subscribe(select);
+
+        while let Some(event) = events.try_next().await.map_err(ReactorError::Stream)? {
+            self.react(event).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Reacts to all the Domain Events delivered by the subscription, like [`Reactor::run`],
+    /// until the [Shutdown] is requested, the subscription ends or an error occurs.
+    ///
+    /// The Domain Event being reacted to when the shutdown is requested
+    /// is always completed, and its checkpoint stored, before returning.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the subscription fails, if a Command could not be
+    /// dispatched, or if the checkpoint could not be read or stored.
+    pub async fn run_until<'a, Id, Evt, E, F>(
+        &self,
+        subscribe: F,
+        shutdown: Shutdown,
+    ) -> Result<(), ReactorError<E, C::Error>>
+    where
+        Evt: message::Message,
+        P: Policy<Id, Evt>,
+        F: FnOnce(event::SequenceSelect) -> event::SequencedStream<'a, Id, Evt, E>,
+    {
+        self.run(|select| subscribe(select).take_until(shutdown).boxed())
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+
+    use anyhow::anyhow;
+    use async_trait::async_trait;
+
+    use super::*;
+    use crate::command::{Envelope, Handler};
+    use futures::channel::oneshot;
+    use futures::FutureExt;
+
+    use crate::event::store::{Appender, GlobalStreamer, InMemory};
+    use crate::message::tests::StringMessage;
+    use crate::subscription::InMemoryCheckpointStore;
+    use crate::version;
+
+    #[derive(Debug, Clone)]
+    struct SendWelcomeEmail(String);
+
+    impl message::Message for SendWelcomeEmail {
+        fn name(&self) -> &'static str {
+            "SendWelcomeEmail"
+        }
+    }
+
+    #[derive(Default)]
+    struct EmailService {
+        failures: usize,
+        attempts: AtomicUsize,
+        sent: Mutex<Vec<(String, Option<String>)>>,
+    }
+
+    #[async_trait]
+    impl Handler<SendWelcomeEmail> for Arc<EmailService> {
+        type Error = anyhow::Error;
+
+        async fn handle(&self, command: Envelope<SendWelcomeEmail>) -> Result<(), Self::Error> {
+            if self.attempts.fetch_add(1, Ordering::SeqCst) < self.failures {
+                return Err(anyhow!("smtp server is unavailable"));
+            }
+
+            let causation_id = command.causation_id().map(ToOwned::to_owned);
+            self.sent
+                .lock()
+                .unwrap()
+                .push((command.message.0, causation_id));
+
+            Ok(())
+        }
+    }
+
+    fn welcome_new_users(
+        event: &event::Sequenced<&'static str, StringMessage>,
+    ) -> Vec<bus::Command> {
+        if event.event.event.message.0 != "created" {
+            return Vec::new();
+        }
+
+        vec![Envelope::from(SendWelcomeEmail(event.event.stream_id.to_owned())).into()]
+    }
+
+    #[tokio::test]
+    async fn reactor_dispatches_commands_and_resumes_from_the_checkpoint() {
+        let event_store = InMemory::<&'static str, StringMessage>::default();
+        let service = Arc::new(EmailService {
+            failures: 1,
+            ..EmailService::default()
+        });
+        let checkpoints = InMemoryCheckpointStore::default();
+
+        for user in ["user:a", "user:b"] {
+            event_store
+                .append(
+                    user,
+                    version::Check::MustBe(0),
+                    vec![
+                        event::Envelope::from(StringMessage("created"))
+                            .with_message_id(format!("{user}:created")),
+                        event::Envelope::from(StringMessage("renamed")),
+                    ],
+                )
+                .await
+                .unwrap();
+        }
+
+        let reactor = Reactor::new(
+            welcome_new_users,
+            command::Bus::default().register(service.clone()),
+            checkpoints.clone(),
+            "welcome-emails",
+        );
+
+        // Stops after the first two Domain Events, as if the process crashed.
+        reactor
+            .run(|select| {
+                event_store
+                    .stream_all(select)
+                    .try_take_while(|event| futures::future::ok(event.sequence <= 2))
+                    .boxed()
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(Some(2), checkpoints.load("welcome-emails").await.unwrap());
+
+        reactor
+            .run(|select| event_store.stream_all(select))
+            .await
+            .unwrap();
+
+        assert_eq!(Some(4), checkpoints.load("welcome-emails").await.unwrap());
+        assert_eq!(
+            vec![
+                ("user:a".to_owned(), Some("user:a:created".to_owned())),
+                ("user:b".to_owned(), Some("user:b:created".to_owned())),
+            ],
+            *service.sent.lock().unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn reactor_stops_when_commands_fail_after_all_attempts() {
+        let event_store = InMemory::<&'static str, StringMessage>::default();
+        let service = Arc::new(EmailService {
+            failures: usize::MAX,
+            ..EmailService::default()
+        });
+        let checkpoints = InMemoryCheckpointStore::default();
+
+        event_store
+            .append(
+                "user:a",
+                version::Check::MustBe(0),
+                vec![event::Envelope::from(StringMessage("created"))],
+            )
+            .await
+            .unwrap();
+
+        let error = Reactor::new(
+            welcome_new_users,
+            command::Bus::default().register(service.clone()),
+            checkpoints.clone(),
+            "welcome-emails",
+        )
+        .with_max_attempts(2)
+        .run(|select| event_store.stream_all(select))
+        .await
+        .expect_err("the reactor should fail");
+
+        assert!(matches!(error, ReactorError::Dispatch { sequence: 1, .. }));
+        assert_eq!(2, service.attempts.load(Ordering::SeqCst));
+        assert_eq!(None, checkpoints.load("welcome-emails").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn reactor_completes_the_event_in_flight_on_shutdown() {
+        let event_store = InMemory::<&'static str, StringMessage>::default();
+        let service = Arc::new(EmailService::default());
+        let checkpoints = InMemoryCheckpointStore::default();
+
+        for user in ["user:a", "user:b", "user:c"] {
+            event_store
+                .append(
+                    user,
+                    version::Check::MustBe(0),
+                    vec![event::Envelope::from(StringMessage("created"))],
+                )
+                .await
+                .unwrap();
+        }
+
+        // Requests the shutdown while reacting to the second Domain Event.
+        let (sender, receiver) = oneshot::channel::<()>();
+        let sender = Mutex::new(Some(sender));
+        let policy = move |event: &event::Sequenced<&'static str, StringMessage>| {
+            if event.sequence == 2 {
+                if let Some(sender) = sender.lock().unwrap().take() {
+                    sender.send(()).unwrap();
+                }
+            }
+
+            welcome_new_users(event)
+        };
+
+        Reactor::new(
+            policy,
+            command::Bus::default().register(service.clone()),
+            checkpoints.clone(),
+            "welcome-emails",
+        )
+        .run_until(
+            |select| {
+                event_store
+                    .stream_all(select)
+                    .chain(futures::stream::pending())
+                    .boxed()
+            },
+            Shutdown::new(receiver.map(|_| ())),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(Some(2), checkpoints.load("welcome-emails").await.unwrap());
+        assert_eq!(2, service.sent.lock().unwrap().len());
+    }
+}
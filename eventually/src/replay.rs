@@ -0,0 +1,604 @@
+//! Module `replay` contains a deterministic [Recorder] of the inputs received by
+//! an application, and a [Replayer] to feed them back against a fresh Event Store,
+//! useful to reproduce the Aggregate states of a production incident locally.
+//!
+//! The inputs recorded are:
+//! 1. the Commands handled by the decorated Command [Handler]s, using [`RecordingHandler`],
+//! 2. the values returned by the application [Clock], using [`RecordingClock`].
+//!
+//! A [Recording] can be saved to a file as JSON lines, and loaded back
+//! in a local environment to be replayed. During the replay, the application
+//! should be wired with the [`ReplayedClock`] of the [Recording], so that
+//! the Commands observe the same time they observed when first handled.
+
+use std::collections::{HashMap, VecDeque};
+use std::fmt::Display;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::marker::PhantomData;
+use std::path::Path;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::clock::Clock;
+use crate::command::{Envelope, Handler};
+use crate::{message, serde as eventually_serde};
+
+/// An input received by the application while the [Recorder] was recording.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Input {
+    /// A Command handled by a [`RecordingHandler`].
+    Command {
+        /// The name of the Command message.
+        name: String,
+        /// The metadata of the Command [Envelope].
+        metadata: message::Metadata,
+        /// The serialized Command message.
+        payload: Vec<u8>,
+        /// The error returned by the Command [Handler], if any.
+        error: Option<String>,
+    },
+    /// A value returned by a [`RecordingClock`].
+    Clock {
+        /// The time returned by the [Clock].
+        now: DateTime<Utc>,
+    },
+}
+
+/// All possible errors returned when saving or loading a [Recording].
+#[derive(Debug, thiserror::Error)]
+pub enum RecordingError {
+    /// Error returned when the [Recording] file could not be read or written.
+    #[error("failed to access recording: {0}")]
+    Io(#[from] io::Error),
+    /// Error returned when an [Input] could not be encoded or decoded.
+    #[error("failed to encode recording input: {0}")]
+    Encoding(#[from] serde_json::Error),
+}
+
+/// The [Input]s recorded by a [Recorder] during a recording window,
+/// in the order they have been received.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Recording {
+    /// The recorded inputs.
+    pub inputs: Vec<Input>,
+}
+
+impl Recording {
+    /// Writes the [Recording] to the specified writer, one JSON-encoded [Input] per line.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if an [Input] could not be encoded or written.
+    pub fn write_to(&self, mut writer: impl Write) -> Result<(), RecordingError> {
+        for input in &self.inputs {
+            serde_json::to_writer(&mut writer, input)?;
+            writer.write_all(b"\n")?;
+        }
+
+        Ok(writer.flush()?)
+    }
+
+    /// Reads a [Recording] previously written with [`Recording::write_to`].
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if an [Input] could not be read or decoded.
+    pub fn read_from(reader: impl BufRead) -> Result<Self, RecordingError> {
+        let mut inputs = Vec::new();
+
+        for line in reader.lines() {
+            let line = line?;
+
+            if !line.trim().is_empty() {
+                inputs.push(serde_json::from_str(&line)?);
+            }
+        }
+
+        Ok(Self { inputs })
+    }
+
+    /// Saves the [Recording] to the file at the specified path,
+    /// replacing it if it already exists.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the file could not be written.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), RecordingError> {
+        self.write_to(BufWriter::new(File::create(path)?))
+    }
+
+    /// Loads a [Recording] from the file at the specified path.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the file could not be read.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, RecordingError> {
+        Self::read_from(BufReader::new(File::open(path)?))
+    }
+
+    /// Returns a [Clock] that returns the recorded clock values, in order.
+    #[must_use]
+    pub fn clock(&self) -> ReplayedClock {
+        ReplayedClock {
+            values: Arc::new(Mutex::new(
+                self.inputs
+                    .iter()
+                    .filter_map(|input| match input {
+                        Input::Clock { now } => Some(*now),
+                        Input::Command { .. } => None,
+                    })
+                    .collect(),
+            )),
+        }
+    }
+}
+
+/// Records the [Input]s received by the application during a recording window,
+/// opened with [`Recorder::start`] and closed with [`Recorder::stop`].
+///
+/// Inputs received outside of a recording window are not recorded.
+#[derive(Debug, Clone, Default)]
+pub struct Recorder {
+    inputs: Arc<Mutex<Option<Vec<Input>>>>,
+}
+
+impl Recorder {
+    /// Opens a new recording window, discarding any input recorded so far.
+    pub fn start(&self) {
+        *self.lock_inputs() = Some(Vec::new());
+    }
+
+    /// Closes the current recording window, returning all the [Input]s
+    /// recorded in it.
+    #[must_use]
+    pub fn stop(&self) -> Recording {
+        let inputs = self.lock_inputs().take().unwrap_or_default();
+
+        Recording { inputs }
+    }
+
+    /// Returns `true` if a recording window is currently open.
+    #[must_use]
+    pub fn is_recording(&self) -> bool {
+        self.lock_inputs().is_some()
+    }
+
+    fn record(&self, input: Input) {
+        if let Some(inputs) = self.lock_inputs().as_mut() {
+            inputs.push(input);
+        }
+    }
+
+    // NOTE: the recorded inputs are only pushed or replaced while holding the lock,
+    // so they are left consistent by a panicking thread: they are used anyway,
+    // instead of failing the Commands handled while recording.
+    fn lock_inputs(&self) -> MutexGuard<'_, Option<Vec<Input>>> {
+        self.inputs.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Decorator for a [Clock] that records all the values it returns in a [Recorder].
+#[derive(Debug, Clone)]
+pub struct RecordingClock<C> {
+    clock: C,
+    recorder: Recorder,
+}
+
+impl<C> RecordingClock<C> {
+    /// Returns a new [`RecordingClock`] recording the values of the specified [Clock].
+    pub fn new(clock: C, recorder: Recorder) -> Self {
+        Self { clock, recorder }
+    }
+}
+
+impl<C> Clock for RecordingClock<C>
+where
+    C: Clock,
+{
+    fn now(&self) -> DateTime<Utc> {
+        let now = self.clock.now();
+        self.recorder.record(Input::Clock { now });
+        now
+    }
+}
+
+/// [Clock] that returns the values recorded in a [Recording], in order.
+///
+/// Once all the recorded values have been returned, the last one is returned
+/// for all subsequent calls.
+#[derive(Debug, Clone)]
+pub struct ReplayedClock {
+    values: Arc<Mutex<VecDeque<DateTime<Utc>>>>,
+}
+
+impl Clock for ReplayedClock {
+    fn now(&self) -> DateTime<Utc> {
+        // NOTE: the values are only popped while holding the lock,
+        // so they are left consistent by a panicking thread.
+        let mut values = self.values.lock().unwrap_or_else(PoisonError::into_inner);
+
+        match values.len() {
+            0 => DateTime::UNIX_EPOCH,
+            1 => values[0],
+            _ => values.pop_front().expect("values should not be empty"),
+        }
+    }
+}
+
+/// Decorator for a Command [Handler] that records all the Commands it handles
+/// in a [Recorder], together with the outcome of their handling.
+///
+/// Commands are recorded in the order their handling completes.
+#[derive(Debug, Clone)]
+pub struct RecordingHandler<H, S> {
+    handler: H,
+    serde: S,
+    recorder: Recorder,
+}
+
+impl<H, S> RecordingHandler<H, S> {
+    /// Returns a new [`RecordingHandler`] that serializes the Commands handled by
+    /// the specified [Handler] using the [Serializer][eventually_serde::Serializer].
+    pub fn new(handler: H, serde: S, recorder: Recorder) -> Self {
+        Self {
+            handler,
+            serde,
+            recorder,
+        }
+    }
+}
+
+#[async_trait]
+impl<T, H, S> Handler<T> for RecordingHandler<H, S>
+where
+    T: message::Message + Clone + Send + Sync + 'static,
+    H: Handler<T>,
+    H::Error: Display,
+    S: eventually_serde::Serializer<T>,
+{
+    type Error = H::Error;
+
+    async fn handle(&self, command: Envelope<T>) -> Result<(), Self::Error> {
+        if !self.recorder.is_recording() {
+            return self.handler.handle(command).await;
+        }
+
+        let name = command.message.name().to_owned();
+        let metadata = command.metadata.clone();
+        let payload = self.serde.serialize(command.message.clone());
+
+        let result = self.handler.handle(command).await;
+
+        match payload {
+            Ok(payload) => self.recorder.record(Input::Command {
+                name,
+                metadata,
+                payload,
+                error: result.as_ref().err().map(ToString::to_string),
+            }),
+            #[cfg(feature = "tracing")]
+            Err(err) => tracing::warn!(
+                command.name = name,
+                error = %err,
+                "failed to serialize command, skipping recording"
+            ),
+            #[cfg(not(feature = "tracing"))]
+            Err(_) => {},
+        }
+
+        result
+    }
+}
+
+/// All possible errors returned by [`Replayer::replay`].
+#[derive(Debug, thiserror::Error)]
+pub enum ReplayError {
+    /// Error returned when no [Handler] has been registered for a recorded Command.
+    #[error("no handler registered for command: {0}")]
+    HandlerNotFound(String),
+    /// Error returned when a recorded Command could not be deserialized.
+    #[error("failed to deserialize command '{name}': {error}")]
+    Deserialize {
+        /// The name of the Command message.
+        name: String,
+        /// The underlying deserialization error.
+        #[source]
+        error: anyhow::Error,
+    },
+    /// Error returned when the outcome of a replayed Command differs
+    /// from the recorded one.
+    #[error(
+        "command #{index} diverged from the recording: expected error {expected:?}, got {actual:?}"
+    )]
+    Diverged {
+        /// The position of the Command in the [Recording] inputs.
+        index: usize,
+        /// The error recorded for the Command, if any.
+        expected: Option<String>,
+        /// The error returned during the replay, if any.
+        actual: Option<String>,
+    },
+}
+
+#[async_trait]
+trait ErasedHandler: Send + Sync {
+    async fn handle(
+        &self,
+        payload: &[u8],
+        metadata: message::Metadata,
+    ) -> Result<Option<String>, anyhow::Error>;
+}
+
+struct TypedHandler<T, H, D> {
+    handler: H,
+    serde: D,
+    command: PhantomData<fn() -> T>,
+}
+
+#[async_trait]
+impl<T, H, D> ErasedHandler for TypedHandler<T, H, D>
+where
+    T: message::Message + Send + Sync + 'static,
+    H: Handler<T>,
+    H::Error: Display,
+    D: eventually_serde::Deserializer<T>,
+{
+    async fn handle(
+        &self,
+        payload: &[u8],
+        metadata: message::Metadata,
+    ) -> Result<Option<String>, anyhow::Error> {
+        let message = self.serde.deserialize(payload)?;
+
+        Ok(self
+            .handler
+            .handle(Envelope { message, metadata })
+            .await
+            .err()
+            .map(|err| err.to_string()))
+    }
+}
+
+/// Replays the Commands of a [Recording] against the registered [Handler]s,
+/// one at a time and in the recorded order.
+///
+/// The [Handler]s should be backed by a fresh Event Store, and use
+/// the [`Recording::clock`] as their [Clock].
+#[derive(Default)]
+pub struct Replayer {
+    handlers: HashMap<String, Box<dyn ErasedHandler>>,
+}
+
+impl Replayer {
+    /// Registers the [Handler] to use for all the recorded Commands with the
+    /// specified name, deserialized using the [Deserializer][eventually_serde::Deserializer].
+    #[must_use]
+    pub fn register<T, H, D>(mut self, name: &str, handler: H, serde: D) -> Self
+    where
+        T: message::Message + Send + Sync + 'static,
+        H: Handler<T> + 'static,
+        H::Error: Display,
+        D: eventually_serde::Deserializer<T> + 'static,
+    {
+        self.handlers.insert(
+            name.to_owned(),
+            Box::new(TypedHandler {
+                handler,
+                serde,
+                command: PhantomData,
+            }),
+        );
+
+        self
+    }
+
+    /// Replays all the Commands in the [Recording].
+    ///
+    /// # Errors
+    ///
+    /// An error is returned, and the replay stopped, if a Command could not be
+    /// replayed, or if its outcome differs from the recorded one.
+    pub async fn replay(&self, recording: &Recording) -> Result<(), ReplayError> {
+        for (index, input) in recording.inputs.iter().enumerate() {
+            let Input::Command {
+                name,
+                metadata,
+                payload,
+                error,
+            } = input
+            else {
+                continue;
+            };
+
+            let handler = self
+                .handlers
+                .get(name)
+                .ok_or_else(|| ReplayError::HandlerNotFound(name.clone()))?;
+
+            let actual = handler
+                .handle(payload, metadata.clone())
+                .await
+                .map_err(|err| ReplayError::Deserialize {
+                    name: name.clone(),
+                    error: err,
+                })?;
+
+            if actual.is_some() != error.is_some() {
+                return Err(ReplayError::Diverged {
+                    index,
+                    expected: error.clone(),
+                    actual,
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::anyhow;
+
+    use super::*;
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    struct Withdraw(u64);
+
+    impl message::Message for Withdraw {
+        fn name(&self) -> &'static str {
+            "Withdraw"
+        }
+    }
+
+    /// A bank account that only allows withdrawals during opening hours.
+    struct AccountService<C> {
+        clock: C,
+        balance: Mutex<u64>,
+    }
+
+    #[async_trait]
+    impl<C> Handler<Withdraw> for Arc<AccountService<C>>
+    where
+        C: Clock,
+    {
+        type Error = anyhow::Error;
+
+        async fn handle(&self, command: Envelope<Withdraw>) -> Result<(), Self::Error> {
+            if self.clock.now().timestamp() % 2 == 1 {
+                return Err(anyhow!("bank is closed"));
+            }
+
+            let mut balance = self.balance.lock().unwrap();
+            *balance = balance
+                .checked_sub(command.message.0)
+                .ok_or_else(|| anyhow!("insufficient funds"))?;
+
+            Ok(())
+        }
+    }
+
+    #[derive(Debug)]
+    struct FixedClock(Mutex<Vec<i64>>);
+
+    impl Clock for FixedClock {
+        fn now(&self) -> DateTime<Utc> {
+            DateTime::from_timestamp(self.0.lock().unwrap().remove(0), 0).unwrap()
+        }
+    }
+
+    #[tokio::test]
+    async fn replay_reproduces_the_recorded_state() {
+        let recorder = Recorder::default();
+        let service = Arc::new(AccountService {
+            clock: RecordingClock::new(FixedClock(Mutex::new(vec![2, 3, 4])), recorder.clone()),
+            balance: Mutex::new(100),
+        });
+
+        let handler = RecordingHandler::new(
+            service.clone(),
+            eventually_serde::Json::<Withdraw>::default(),
+            recorder.clone(),
+        );
+
+        recorder.start();
+
+        for amount in [30, 30, 50] {
+            _ = handler.handle(Envelope::from(Withdraw(amount))).await;
+        }
+
+        let mut file = Vec::new();
+        recorder
+            .stop()
+            .write_to(&mut file)
+            .expect("recording should be written");
+
+        let recording = Recording::read_from(file.as_slice()).expect("recording should be read");
+        assert_eq!(6, recording.inputs.len());
+
+        let replayed_service = Arc::new(AccountService {
+            clock: recording.clock(),
+            balance: Mutex::new(100),
+        });
+
+        Replayer::default()
+            .register(
+                "Withdraw",
+                replayed_service.clone(),
+                eventually_serde::Json::<Withdraw>::default(),
+            )
+            .replay(&recording)
+            .await
+            .expect("replay should not diverge");
+
+        assert_eq!(20, *service.balance.lock().unwrap());
+        assert_eq!(20, *replayed_service.balance.lock().unwrap());
+    }
+
+    #[tokio::test]
+    async fn replay_fails_when_the_outcome_diverges() {
+        let recorder = Recorder::default();
+        let service = Arc::new(AccountService {
+            clock: RecordingClock::new(FixedClock(Mutex::new(vec![3])), recorder.clone()),
+            balance: Mutex::new(100),
+        });
+
+        recorder.start();
+
+        RecordingHandler::new(
+            service,
+            eventually_serde::Json::<Withdraw>::default(),
+            recorder.clone(),
+        )
+        .handle(Envelope::from(Withdraw(10)))
+        .await
+        .expect_err("the bank should be closed");
+
+        let recording = recorder.stop();
+
+        // Replaying with a different clock changes the outcome of the Command.
+        let error = Replayer::default()
+            .register(
+                "Withdraw",
+                Arc::new(AccountService {
+                    clock: FixedClock(Mutex::new(vec![4])),
+                    balance: Mutex::new(100),
+                }),
+                eventually_serde::Json::<Withdraw>::default(),
+            )
+            .replay(&recording)
+            .await
+            .expect_err("replay should diverge");
+
+        assert!(matches!(error, ReplayError::Diverged { index: 1, .. }));
+    }
+
+    #[test]
+    fn recorder_keeps_recording_after_a_poisoned_lock() {
+        let recorder = Recorder::default();
+        let poisoner = recorder.clone();
+
+        recorder.start();
+
+        std::thread::spawn(move || {
+            let _inputs = poisoner.inputs.lock().unwrap();
+            panic!("poisoning the recorder inputs");
+        })
+        .join()
+        .expect_err("the thread should panic");
+
+        let now = RecordingClock::new(
+            crate::clock::TestClock::new(DateTime::from_timestamp(42, 0).unwrap()),
+            recorder.clone(),
+        )
+        .now();
+
+        assert!(recorder.is_recording());
+        assert_eq!(now, recorder.stop().clock().now());
+    }
+}
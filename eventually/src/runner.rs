@@ -0,0 +1,111 @@
+//! Module `runner` contains the [Runner] abstraction for long-running workers,
+//! such as the [`Poller`][crate::command::scheduler::Poller] of scheduled Commands,
+//! and the [Shutdown] signal used to stop them gracefully, instead of
+//! dropping the task they run on in the middle of their work.
+
+use std::fmt::{Debug, Formatter, Result as FmtResult};
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use futures::future::{self, BoxFuture, Shared};
+use futures::FutureExt;
+
+/// Signal that requests a [Runner] to stop, completing once the shutdown
+/// has been requested.
+///
+/// Cloning a [Shutdown] returns a handle to the same signal, so that it can be
+/// shared by multiple [Runner]s.
+///
+/// With the `tokio-util` feature, a `tokio_util::sync::CancellationToken`
+/// can be converted into a [Shutdown] signal.
+#[derive(Clone)]
+pub struct Shutdown(Shared<BoxFuture<'static, ()>>);
+
+impl Debug for Shutdown {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_struct("Shutdown")
+            .field("requested", &self.is_requested())
+            .finish()
+    }
+}
+
+impl Shutdown {
+    /// Creates a new [Shutdown] signal, requested once the specified future completes,
+    /// e.g. when the process receives a termination signal.
+    pub fn new<F>(signal: F) -> Self
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        Self(signal.boxed().shared())
+    }
+
+    /// Returns a [Shutdown] signal that is never requested.
+    #[must_use]
+    pub fn never() -> Self {
+        Self::new(future::pending())
+    }
+
+    /// Returns true if the shutdown has been requested.
+    #[must_use]
+    pub fn is_requested(&self) -> bool {
+        self.0.clone().now_or_never().is_some()
+    }
+
+    /// Waits for the specified duration, or until the shutdown is requested,
+    /// whichever comes first.
+    pub(crate) async fn sleep(&self, duration: Duration) {
+        future::select(futures_timer::Delay::new(duration), self.clone()).await;
+    }
+}
+
+impl Future for Shutdown {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.0.poll_unpin(cx)
+    }
+}
+
+#[cfg(feature = "tokio-util")]
+impl From<tokio_util::sync::CancellationToken> for Shutdown {
+    fn from(token: tokio_util::sync::CancellationToken) -> Self {
+        Self::new(token.cancelled_owned())
+    }
+}
+
+/// A long-running worker, that keeps working until an error occurs
+/// or its [Shutdown] is requested.
+#[async_trait]
+pub trait Runner: Send + Sync {
+    /// The error type returned by the Runner.
+    type Error: Send + Sync;
+
+    /// Runs the worker until the [Shutdown] is requested: the work in flight,
+    /// such as a batch being processed, is completed before returning.
+    async fn run_until(&self, shutdown: Shutdown) -> Result<(), Self::Error>;
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::channel::oneshot;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn shutdown_is_requested_once_the_signal_completes() {
+        let (sender, receiver) = oneshot::channel::<()>();
+        let shutdown = Shutdown::new(receiver.map(|_| ()));
+        let clone = shutdown.clone();
+
+        assert!(!shutdown.is_requested());
+
+        sender.send(()).expect("receiver should be alive");
+        clone.await;
+
+        assert!(shutdown.is_requested());
+        assert!(!Shutdown::never().is_requested());
+    }
+}
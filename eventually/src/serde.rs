@@ -2,6 +2,8 @@
 //! deserialization, allowing you to convert Rust data structures to and from
 //! different formats like JSON, Protobuf, etc.
 
+#[cfg(feature = "serde-prost")]
+use std::collections::HashMap;
 use std::fmt::Display;
 use std::marker::PhantomData;
 
@@ -11,6 +13,11 @@ use prost::bytes::Bytes;
 #[cfg(feature = "serde-json")]
 use serde::{Deserialize, Serialize};
 
+#[cfg(feature = "serde-json")]
+use crate::command::validate;
+#[cfg(any(feature = "serde-json", feature = "serde-prost"))]
+use crate::message;
+
 /// A serializer interface that can be used to serialize a Rust data type
 /// into a specific wire format as a byte array.
 pub trait Serializer<T>: Send + Sync {
@@ -84,7 +91,7 @@ where
         self.serde.serialize(
             value
                 .try_into()
-                .map_err(|err| anyhow!("failed to convert type values: {}", err))?,
+                .map_err(|err| anyhow!("failed to convert type values: {err}"))?,
         )
     }
 }
@@ -100,18 +107,358 @@ where
         let inn = self.serde.deserialize(data)?;
 
         inn.try_into()
-            .map_err(|err| anyhow!("failed to convert type values: {}", err))
+            .map_err(|err| anyhow!("failed to convert type values: {err}"))
+    }
+}
+
+impl<T, S> Serializer<T> for &S
+where
+    S: Serializer<T> + ?Sized,
+{
+    fn serialize(&self, value: T) -> anyhow::Result<Vec<u8>> {
+        (**self).serialize(value)
+    }
+}
+
+impl<T, S> Deserializer<T> for &S
+where
+    S: Deserializer<T> + ?Sized,
+{
+    fn deserialize(&self, data: &[u8]) -> anyhow::Result<T> {
+        (**self).deserialize(data)
+    }
+}
+
+/// [Metadata][message::Metadata] key used by [`EnvelopeSerde`] to expose the schema version
+/// of a deserialized [Envelope][message::Envelope].
+#[cfg(feature = "serde-json")]
+pub const SCHEMA_VERSION_KEY: &str = "Schema-Version";
+
+#[cfg(feature = "serde-json")]
+const ENVELOPE_MAGIC: &[u8; 4] = b"\0EVE";
+
+/// Returns whether the data has been serialized using [`EnvelopeSerde`].
+#[cfg(feature = "serde-json")]
+#[must_use]
+pub fn is_envelope(data: &[u8]) -> bool {
+    data.starts_with(ENVELOPE_MAGIC)
+}
+
+/// Implements the [Serde] trait for a full [Envelope][message::Envelope], by
+/// persisting its payload, using the specified [Serde], together with its
+/// [Metadata][message::Metadata] and a schema version.
+///
+/// The schema version of a deserialized [Envelope][message::Envelope] is available
+/// in its [Metadata][message::Metadata] under the [`SCHEMA_VERSION_KEY`] key,
+/// unless the persisted metadata had one already.
+#[cfg(feature = "serde-json")]
+#[derive(Debug, Clone, Copy)]
+pub struct EnvelopeSerde<S> {
+    serde: S,
+    schema_version: u32,
+}
+
+#[cfg(feature = "serde-json")]
+impl<S> EnvelopeSerde<S> {
+    /// Creates a new [`EnvelopeSerde`] using the specified [Serde] for the
+    /// message payload, with schema version 1.
+    pub fn new(serde: S) -> Self {
+        Self {
+            serde,
+            schema_version: 1,
+        }
+    }
+
+    /// Sets the schema version persisted with the serialized [Envelope][message::Envelope]s.
+    #[must_use]
+    pub fn with_schema_version(mut self, schema_version: u32) -> Self {
+        self.schema_version = schema_version;
+        self
+    }
+}
+
+#[cfg(feature = "serde-json")]
+impl<T, S> Serializer<message::Envelope<T>> for EnvelopeSerde<S>
+where
+    T: message::Message,
+    S: Serializer<T>,
+{
+    fn serialize(&self, value: message::Envelope<T>) -> anyhow::Result<Vec<u8>> {
+        let metadata = serde_json::to_vec(&value.metadata)
+            .map_err(|err| anyhow!("failed to serialize envelope metadata: {err}"))?;
+        let payload = self.serde.serialize(value.message)?;
+
+        let metadata_len = u32::try_from(metadata.len())
+            .map_err(|_| anyhow!("envelope metadata is too large to be serialized"))?;
+
+        let mut data =
+            Vec::with_capacity(ENVELOPE_MAGIC.len() + 8 + metadata.len() + payload.len());
+        data.extend_from_slice(ENVELOPE_MAGIC);
+        data.extend_from_slice(&self.schema_version.to_be_bytes());
+        data.extend_from_slice(&metadata_len.to_be_bytes());
+        data.extend_from_slice(&metadata);
+        data.extend_from_slice(&payload);
+
+        Ok(data)
+    }
+}
+
+#[cfg(feature = "serde-json")]
+impl<T, S> Deserializer<message::Envelope<T>> for EnvelopeSerde<S>
+where
+    T: message::Message,
+    S: Deserializer<T>,
+{
+    fn deserialize(&self, data: &[u8]) -> anyhow::Result<message::Envelope<T>> {
+        fn split_u32(data: &[u8]) -> anyhow::Result<(u32, &[u8])> {
+            let (value, rest) = data
+                .split_first_chunk::<4>()
+                .ok_or_else(|| anyhow!("envelope data is truncated"))?;
+
+            Ok((u32::from_be_bytes(*value), rest))
+        }
+
+        let data = data
+            .strip_prefix(ENVELOPE_MAGIC)
+            .ok_or_else(|| anyhow!("data is not a serialized envelope"))?;
+
+        let (schema_version, data) = split_u32(data)?;
+        let (metadata_len, data) = split_u32(data)?;
+
+        if data.len() < metadata_len as usize {
+            return Err(anyhow!("envelope data is truncated"));
+        }
+
+        let (metadata, payload) = data.split_at(metadata_len as usize);
+
+        let mut metadata: message::Metadata = serde_json::from_slice(metadata)
+            .map_err(|err| anyhow!("failed to deserialize envelope metadata: {err}"))?;
+
+        metadata
+            .entry(SCHEMA_VERSION_KEY.to_owned())
+            .or_insert_with(|| schema_version.to_string());
+
+        Ok(message::Envelope {
+            message: self.serde.deserialize(payload)?,
+            metadata,
+        })
+    }
+}
+
+#[cfg(any(feature = "serde-gzip", feature = "serde-zstd"))]
+const COMPRESSED_MAGIC: &[u8; 4] = b"\0EVZ";
+
+/// Compression algorithms supported by [Compressed].
+#[cfg(any(feature = "serde-gzip", feature = "serde-zstd"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    /// Gzip compression, with the default compression level.
+    #[cfg(feature = "serde-gzip")]
+    Gzip,
+    /// Zstandard compression, with the specified compression level,
+    /// where `0` is the default one.
+    #[cfg(feature = "serde-zstd")]
+    Zstd {
+        /// The compression level.
+        level: i32,
+    },
+}
+
+#[cfg(any(feature = "serde-gzip", feature = "serde-zstd"))]
+impl Compression {
+    #[cfg(feature = "serde-gzip")]
+    const GZIP_ID: u8 = 1;
+    #[cfg(feature = "serde-zstd")]
+    const ZSTD_ID: u8 = 2;
+
+    fn id(self) -> u8 {
+        match self {
+            #[cfg(feature = "serde-gzip")]
+            Compression::Gzip => Self::GZIP_ID,
+            #[cfg(feature = "serde-zstd")]
+            Compression::Zstd { .. } => Self::ZSTD_ID,
+        }
+    }
+
+    fn compress(self, data: &[u8]) -> std::io::Result<Vec<u8>> {
+        match self {
+            #[cfg(feature = "serde-gzip")]
+            Compression::Gzip => {
+                use std::io::Write;
+
+                let mut encoder =
+                    flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(data)?;
+                encoder.finish()
+            },
+            #[cfg(feature = "serde-zstd")]
+            Compression::Zstd { level } => zstd::encode_all(data, level),
+        }
+    }
+
+    fn decompress(id: u8, data: &[u8]) -> anyhow::Result<Vec<u8>> {
+        match id {
+            #[cfg(feature = "serde-gzip")]
+            Self::GZIP_ID => {
+                use std::io::Read;
+
+                let mut decompressed = Vec::new();
+                flate2::read::GzDecoder::new(data).read_to_end(&mut decompressed)?;
+                Ok(decompressed)
+            },
+            #[cfg(feature = "serde-zstd")]
+            Self::ZSTD_ID => Ok(zstd::decode_all(data)?),
+            id => Err(anyhow!(
+                "unsupported compression algorithm '{id}', check the enabled features"
+            )),
+        }
+    }
+}
+
+/// Implements the [Serde] trait by compressing the output of the specified [Serde],
+/// and decompressing its input, using the specified [Compression] algorithm.
+///
+/// Compressed data is prefixed by a marker, so that data serialized without
+/// compression (e.g. Domain Events persisted before adopting [Compressed])
+/// is passed to the inner [Serde] as-is.
+///
+/// Data compressed with an algorithm other than the configured one can still be
+/// deserialized, as long as the corresponding feature is enabled.
+#[cfg(any(feature = "serde-gzip", feature = "serde-zstd"))]
+#[derive(Debug, Clone, Copy)]
+pub struct Compressed<S> {
+    serde: S,
+    compression: Compression,
+    min_size: usize,
+}
+
+#[cfg(any(feature = "serde-gzip", feature = "serde-zstd"))]
+impl<S> Compressed<S> {
+    /// Creates a new [Compressed] serde instance, compressing the output
+    /// of the specified [Serde] using the specified [Compression] algorithm.
+    pub fn new(serde: S, compression: Compression) -> Self {
+        Self {
+            serde,
+            compression,
+            min_size: 0,
+        }
+    }
+
+    /// Only compresses the serialized data that is at least as large as
+    /// the specified size, in bytes, since compressing small payloads
+    /// usually makes them larger.
+    #[must_use]
+    pub fn with_min_size(mut self, min_size: usize) -> Self {
+        self.min_size = min_size;
+        self
+    }
+}
+
+#[cfg(any(feature = "serde-gzip", feature = "serde-zstd"))]
+impl<T, S> Serializer<T> for Compressed<S>
+where
+    S: Serializer<T>,
+{
+    fn serialize(&self, value: T) -> anyhow::Result<Vec<u8>> {
+        let data = self.serde.serialize(value)?;
+
+        if data.len() < self.min_size {
+            return Ok(data);
+        }
+
+        let compressed = self
+            .compression
+            .compress(&data)
+            .map_err(|err| anyhow!("failed to compress serialized data: {err}"))?;
+
+        let mut output = Vec::with_capacity(COMPRESSED_MAGIC.len() + 1 + compressed.len());
+        output.extend_from_slice(COMPRESSED_MAGIC);
+        output.push(self.compression.id());
+        output.extend_from_slice(&compressed);
+
+        Ok(output)
+    }
+}
+
+#[cfg(any(feature = "serde-gzip", feature = "serde-zstd"))]
+impl<T, S> Deserializer<T> for Compressed<S>
+where
+    S: Deserializer<T>,
+{
+    fn deserialize(&self, data: &[u8]) -> anyhow::Result<T> {
+        let Some(data) = data.strip_prefix(COMPRESSED_MAGIC) else {
+            return self.serde.deserialize(data);
+        };
+
+        let (id, compressed) = data
+            .split_first()
+            .ok_or_else(|| anyhow!("compressed data is truncated"))?;
+
+        let decompressed = Compression::decompress(*id, compressed)
+            .map_err(|err| anyhow!("failed to decompress serialized data: {err}"))?;
+
+        self.serde.deserialize(&decompressed)
+    }
+}
+
+/// Naming strategies that [Json] can apply to the top-level fields
+/// of the serialized messages, similarly to `#[serde(rename_all = "...")]`.
+///
+/// Rust field names are expected to be in `snake_case`.
+#[cfg(feature = "serde-json")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenameAll {
+    /// Renames fields to `camelCase`.
+    CamelCase,
+    /// Renames fields to `PascalCase`.
+    PascalCase,
+    /// Renames fields to `snake_case`.
+    SnakeCase,
+    /// Renames fields to `SCREAMING_SNAKE_CASE`.
+    ScreamingSnakeCase,
+    /// Renames fields to `kebab-case`.
+    KebabCase,
+}
+
+#[cfg(feature = "serde-json")]
+impl RenameAll {
+    fn apply(self, name: &str) -> String {
+        use heck::{
+            ToKebabCase, ToLowerCamelCase, ToShoutySnakeCase, ToSnakeCase, ToUpperCamelCase,
+        };
+
+        match self {
+            RenameAll::CamelCase => name.to_lower_camel_case(),
+            RenameAll::PascalCase => name.to_upper_camel_case(),
+            RenameAll::SnakeCase => name.to_snake_case(),
+            RenameAll::ScreamingSnakeCase => name.to_shouty_snake_case(),
+            RenameAll::KebabCase => name.to_kebab_case(),
+        }
     }
 }
 
+#[cfg(feature = "serde-json")]
+type DefaultValueFn = fn() -> serde_json::Result<serde_json::Value>;
+
 /// Implements the [Serializer] and [Deserializer] traits, which use the [serde] crate
 /// to serialize and deserialize a message into JSON.
+///
+/// By default, decoding is as tolerant as the message type `serde` implementation.
+/// Use [`Json::with_deny_unknown_fields`], [`Json::with_default_missing_fields`] and
+/// [`Json::with_rename_all`] to choose a stricter or more tolerant policy,
+/// e.g. when decoding historical events, without changing the message type.
 #[cfg(feature = "serde-json")]
 #[derive(Debug, Clone, Copy)]
-pub struct Json<T>(PhantomData<T>)
+pub struct Json<T>
 where
     T: Serialize + Send + Sync,
-    for<'d> T: Deserialize<'d>;
+    for<'d> T: Deserialize<'d>,
+{
+    deny_unknown_fields: bool,
+    default_value: Option<DefaultValueFn>,
+    rename_all: Option<RenameAll>,
+    marker: PhantomData<T>,
+}
 
 #[cfg(feature = "serde-json")]
 impl<T> Default for Json<T>
@@ -120,7 +467,97 @@ where
     for<'d> T: Deserialize<'d>,
 {
     fn default() -> Self {
-        Self(PhantomData)
+        Self {
+            deny_unknown_fields: false,
+            default_value: None,
+            rename_all: None,
+            marker: PhantomData,
+        }
+    }
+}
+
+#[cfg(feature = "serde-json")]
+impl<T> Json<T>
+where
+    T: Serialize + Send + Sync,
+    for<'d> T: Deserialize<'d>,
+{
+    /// Fails deserialization when the JSON message contains fields,
+    /// at any depth, that are not known to the message type.
+    #[must_use]
+    pub fn with_deny_unknown_fields(mut self) -> Self {
+        self.deny_unknown_fields = true;
+        self
+    }
+
+    /// Fills the top-level fields missing from the JSON message with the ones
+    /// from the [`Default`] value of the message type, before deserializing it.
+    #[must_use]
+    pub fn with_default_missing_fields(mut self) -> Self
+    where
+        T: Default,
+    {
+        self.default_value = Some(|| serde_json::to_value(T::default()));
+        self
+    }
+
+    /// Renames the top-level fields of the JSON message using the specified strategy.
+    ///
+    /// For externally-tagged enums, the top-level field is the variant name.
+    #[must_use]
+    pub fn with_rename_all(mut self, rename_all: RenameAll) -> Self {
+        self.rename_all = Some(rename_all);
+        self
+    }
+
+    fn is_plain(&self) -> bool {
+        !self.deny_unknown_fields && self.default_value.is_none() && self.rename_all.is_none()
+    }
+
+    fn encode_value(&self, value: impl Serialize) -> serde_json::Result<serde_json::Value> {
+        let mut value = serde_json::to_value(value)?;
+
+        if let (Some(rename_all), serde_json::Value::Object(object)) = (self.rename_all, &mut value)
+        {
+            *object = std::mem::take(object)
+                .into_iter()
+                .map(|(key, value)| (rename_all.apply(&key), value))
+                .collect();
+        }
+
+        Ok(value)
+    }
+
+    fn decode_value(&self, mut value: serde_json::Value) -> anyhow::Result<T> {
+        if let (Some(default_value), serde_json::Value::Object(object)) =
+            (self.default_value, &mut value)
+        {
+            if let serde_json::Value::Object(defaults) = self.encode_value(default_value()?)? {
+                for (key, default) in defaults {
+                    object.entry(key).or_insert(default);
+                }
+            }
+        }
+
+        let deserializer = Renamed {
+            value,
+            rename_all: self.rename_all,
+        };
+
+        if !self.deny_unknown_fields {
+            return Ok(T::deserialize(deserializer)?);
+        }
+
+        let mut unknown_fields = Vec::new();
+        let value = serde_ignored::deserialize(deserializer, |path| {
+            unknown_fields.push(path.to_string());
+        })?;
+
+        if !unknown_fields.is_empty() {
+            return Err(anyhow!("unknown fields: {}", unknown_fields.join(", ")));
+        }
+
+        Ok(value)
     }
 }
 
@@ -131,8 +568,14 @@ where
     for<'d> T: Deserialize<'d>,
 {
     fn serialize(&self, value: T) -> anyhow::Result<Vec<u8>> {
-        serde_json::to_vec(&value)
-            .map_err(|err| anyhow!("failed to serialize value to json: {}", err))
+        if self.is_plain() {
+            return serde_json::to_vec(&value)
+                .map_err(|err| anyhow!("failed to serialize value to json: {err}"));
+        }
+
+        self.encode_value(value)
+            .and_then(|value| serde_json::to_vec(&value))
+            .map_err(|err| anyhow!("failed to serialize value to json: {err}"))
     }
 }
 
@@ -143,8 +586,217 @@ where
     for<'d> T: Deserialize<'d>,
 {
     fn deserialize(&self, data: &[u8]) -> anyhow::Result<T> {
+        if self.is_plain() {
+            return serde_json::from_slice(data)
+                .map_err(|err| anyhow!("failed to deserialize value from json: {err}"));
+        }
+
         serde_json::from_slice(data)
-            .map_err(|err| anyhow!("failed to deserialize value from json: {}", err))
+            .map_err(anyhow::Error::from)
+            .and_then(|value| self.decode_value(value))
+            .map_err(|err| anyhow!("failed to deserialize value from json: {err}"))
+    }
+}
+
+#[cfg(feature = "serde-json")]
+type Validator = std::sync::Arc<dyn validate::Validator<serde_json::Value>>;
+
+/// A [Json] serde that validates the JSON payload after serialization and
+/// before deserialization, so that corrupted payloads are caught at the store boundary.
+///
+/// The validator is a [`validate::Validator`] of the JSON payload, e.g. a closure,
+/// which makes it possible to plug in a JSON Schema validator of choice.
+/// Invalid payloads are rejected with a [`validate::ValidationError`], whose
+/// [`Violation`][validate::Violation]s point at the invalid values through a JSON pointer.
+#[cfg(feature = "serde-json")]
+#[derive(Clone)]
+pub struct Validated<T>
+where
+    T: Serialize + Send + Sync,
+    for<'d> T: Deserialize<'d>,
+{
+    json: Json<T>,
+    validator: Validator,
+}
+
+#[cfg(feature = "serde-json")]
+impl<T> Validated<T>
+where
+    T: Serialize + Send + Sync,
+    for<'d> T: Deserialize<'d>,
+{
+    /// Returns a new [Validated] serde, validating the payloads
+    /// of the specified [Json] serde through the specified validator.
+    pub fn new<V>(json: Json<T>, validator: V) -> Self
+    where
+        V: validate::Validator<serde_json::Value> + 'static,
+    {
+        Self {
+            json,
+            validator: std::sync::Arc::new(validator),
+        }
+    }
+
+    fn validate(&self, value: &serde_json::Value) -> anyhow::Result<()> {
+        self.validator
+            .validate(value)
+            .map_err(|err| anyhow::Error::new(err).context("json payload failed validation"))
+    }
+}
+
+#[cfg(feature = "serde-json")]
+impl<T> std::fmt::Debug for Validated<T>
+where
+    T: Serialize + Send + Sync,
+    for<'d> T: Deserialize<'d>,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Validated").finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "serde-json")]
+impl<T> Serializer<T> for Validated<T>
+where
+    T: Serialize + Send + Sync,
+    for<'d> T: Deserialize<'d>,
+{
+    fn serialize(&self, value: T) -> anyhow::Result<Vec<u8>> {
+        let value = self
+            .json
+            .encode_value(value)
+            .map_err(|err| anyhow!("failed to serialize value to json: {err}"))?;
+
+        self.validate(&value)?;
+
+        serde_json::to_vec(&value)
+            .map_err(|err| anyhow!("failed to serialize value to json: {err}"))
+    }
+}
+
+#[cfg(feature = "serde-json")]
+impl<T> Deserializer<T> for Validated<T>
+where
+    T: Serialize + Send + Sync,
+    for<'d> T: Deserialize<'d>,
+{
+    fn deserialize(&self, data: &[u8]) -> anyhow::Result<T> {
+        let value = serde_json::from_slice(data)
+            .map_err(|err| anyhow!("failed to deserialize value from json: {err}"))?;
+
+        self.validate(&value)?;
+
+        self.json
+            .decode_value(value)
+            .map_err(|err| anyhow!("failed to deserialize value from json: {err}"))
+    }
+}
+
+/// [`serde::Deserializer`] over a [`serde_json::Value`] that maps the renamed
+/// top-level fields back to the ones expected by the deserialized type.
+#[cfg(feature = "serde-json")]
+struct Renamed {
+    value: serde_json::Value,
+    rename_all: Option<RenameAll>,
+}
+
+#[cfg(feature = "serde-json")]
+impl Renamed {
+    fn into_value(self, names: &[&str]) -> serde_json::Value {
+        let (rename_all, object) = match (self.rename_all, self.value) {
+            (Some(rename_all), serde_json::Value::Object(object)) => (rename_all, object),
+            (_, value) => return value,
+        };
+
+        object
+            .into_iter()
+            .map(|(key, value)| {
+                let key = names
+                    .iter()
+                    .find(|name| rename_all.apply(name) == key)
+                    .map_or(key, |name| (*name).to_owned());
+
+                (key, value)
+            })
+            .collect::<serde_json::Map<_, _>>()
+            .into()
+    }
+}
+
+#[cfg(feature = "serde-json")]
+macro_rules! forward_to_value {
+    ($($method:ident($($arg:ident: $ty:ty),*)),* $(,)?) => {
+        $(
+            fn $method<V>(self, $($arg: $ty,)* visitor: V) -> Result<V::Value, Self::Error>
+            where
+                V: serde::de::Visitor<'de>,
+            {
+                self.value.$method($($arg,)* visitor)
+            }
+        )*
+    };
+}
+
+#[cfg(feature = "serde-json")]
+impl<'de> serde::Deserializer<'de> for Renamed {
+    type Error = serde_json::Error;
+
+    forward_to_value! {
+        deserialize_any(),
+        deserialize_bool(),
+        deserialize_i8(),
+        deserialize_i16(),
+        deserialize_i32(),
+        deserialize_i64(),
+        deserialize_i128(),
+        deserialize_u8(),
+        deserialize_u16(),
+        deserialize_u32(),
+        deserialize_u64(),
+        deserialize_u128(),
+        deserialize_f32(),
+        deserialize_f64(),
+        deserialize_char(),
+        deserialize_str(),
+        deserialize_string(),
+        deserialize_bytes(),
+        deserialize_byte_buf(),
+        deserialize_option(),
+        deserialize_unit(),
+        deserialize_unit_struct(name: &'static str),
+        deserialize_newtype_struct(name: &'static str),
+        deserialize_seq(),
+        deserialize_tuple(len: usize),
+        deserialize_tuple_struct(name: &'static str, len: usize),
+        deserialize_map(),
+        deserialize_identifier(),
+        deserialize_ignored_any(),
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        self.into_value(fields)
+            .deserialize_struct(name, fields, visitor)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        self.into_value(variants)
+            .deserialize_enum(name, variants, visitor)
     }
 }
 
@@ -175,7 +827,158 @@ where
         let buf = Bytes::copy_from_slice(data);
 
         T::decode(buf)
-            .map_err(|err| anyhow!("failed to deserialize protobuf message into value: {}", err))
+            .map_err(|err| anyhow!("failed to deserialize protobuf message into value: {err}"))
+    }
+}
+
+#[cfg(feature = "serde-prost")]
+#[derive(Debug, Clone)]
+struct AnyEntry<T> {
+    name: &'static str,
+    type_url: String,
+    encode: fn(T) -> anyhow::Result<prost_types::Any>,
+    decode: fn(&prost_types::Any) -> anyhow::Result<T>,
+}
+
+#[cfg(feature = "serde-prost")]
+fn encode_any<T, P>(value: T) -> anyhow::Result<prost_types::Any>
+where
+    P: prost::Name + TryFrom<T>,
+    <P as TryFrom<T>>::Error: Display,
+{
+    let message = P::try_from(value)
+        .map_err(|err| anyhow!("failed to convert value into protobuf message: {err}"))?;
+
+    prost_types::Any::from_msg(&message)
+        .map_err(|err| anyhow!("failed to serialize protobuf message into any: {err}"))
+}
+
+#[cfg(feature = "serde-prost")]
+fn decode_any<T, P>(any: &prost_types::Any) -> anyhow::Result<T>
+where
+    P: prost::Name + Default + Into<T>,
+{
+    any.to_msg::<P>()
+        .map(Into::into)
+        .map_err(|err| anyhow!("failed to deserialize protobuf message from any: {err}"))
+}
+
+/// Implements the [Serde] trait for heterogeneous [Message][message::Message] types,
+/// such as event enums, by mapping each [`message::Message::name`] to a concrete
+/// [`prost::Message`] type and encoding the payload as a `google.protobuf.Any`.
+///
+/// This avoids declaring a single wrapper Protobuf message with a `oneof`
+/// of all the possible messages: each message type is registered using
+/// [`ProtobufAny::with_message`], and the `type_url` of the `Any` is used
+/// to pick the right type during deserialization.
+#[cfg(feature = "serde-prost")]
+#[derive(Debug, Clone)]
+pub struct ProtobufAny<T> {
+    by_name: HashMap<&'static str, AnyEntry<T>>,
+    names_by_type_url: HashMap<String, &'static str>,
+}
+
+#[cfg(feature = "serde-prost")]
+impl<T> Default for ProtobufAny<T> {
+    fn default() -> Self {
+        Self {
+            by_name: HashMap::default(),
+            names_by_type_url: HashMap::default(),
+        }
+    }
+}
+
+#[cfg(feature = "serde-prost")]
+impl<T> ProtobufAny<T>
+where
+    T: message::Message,
+{
+    /// Registers the Protobuf message type `P` for the values whose
+    /// [`message::Message::name`] is equal to the specified name.
+    ///
+    /// Values are converted into `P` through [`TryFrom`] when serializing,
+    /// and back through [`Into`] when deserializing; `P` must implement
+    /// [`prost::Name`] to provide the `type_url` of the encoded `Any`.
+    ///
+    /// Registering a name or a `type_url` twice replaces the previous registration.
+    #[must_use]
+    pub fn with_message<P>(mut self, name: &'static str) -> Self
+    where
+        P: prost::Name + Default + TryFrom<T> + Into<T>,
+        <P as TryFrom<T>>::Error: Display,
+    {
+        let type_url = P::type_url();
+
+        if let Some(previous) = self.by_name.remove(name) {
+            self.names_by_type_url.remove(&previous.type_url);
+        }
+
+        if let Some(previous) = self.names_by_type_url.insert(type_url.clone(), name) {
+            self.by_name.remove(previous);
+        }
+
+        self.by_name.insert(
+            name,
+            AnyEntry {
+                name,
+                type_url,
+                encode: encode_any::<T, P>,
+                decode: decode_any::<T, P>,
+            },
+        );
+
+        self
+    }
+}
+
+#[cfg(feature = "serde-prost")]
+impl<T> Serializer<T> for ProtobufAny<T>
+where
+    T: message::Message,
+{
+    fn serialize(&self, value: T) -> anyhow::Result<Vec<u8>> {
+        let name = value.name();
+        let entry = self
+            .by_name
+            .get(name)
+            .ok_or_else(|| anyhow!("no protobuf message registered for '{name}'"))?;
+
+        Ok(prost::Message::encode_to_vec(&(entry.encode)(value)?))
+    }
+}
+
+#[cfg(feature = "serde-prost")]
+impl<T> Deserializer<T> for ProtobufAny<T>
+where
+    T: message::Message,
+{
+    fn deserialize(&self, data: &[u8]) -> anyhow::Result<T> {
+        let any: prost_types::Any = prost::Message::decode(data)
+            .map_err(|err| anyhow!("failed to deserialize protobuf any: {err}"))?;
+
+        let entry = self
+            .names_by_type_url
+            .get(any.type_url.as_str())
+            .and_then(|name| self.by_name.get(name))
+            .ok_or_else(|| {
+                anyhow!(
+                    "no protobuf message registered for type url '{}'",
+                    any.type_url
+                )
+            })?;
+
+        let value = (entry.decode)(&any)?;
+
+        if value.name() != entry.name {
+            return Err(anyhow!(
+                "protobuf message '{}' was converted into '{}', expected '{}'",
+                any.type_url,
+                value.name(),
+                entry.name
+            ));
+        }
+
+        Ok(value)
     }
 }
 
@@ -212,3 +1015,308 @@ where
         Json::<T>::default().deserialize(data)
     }
 }
+
+#[cfg(all(test, feature = "serde-json"))]
+mod tests {
+    use serde::{Deserialize, Serialize};
+
+    use super::*;
+
+    #[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+    struct WasCreated {
+        user_id: String,
+        display_name: String,
+        #[serde(default)]
+        tags: Vec<String>,
+    }
+
+    impl message::Message for WasCreated {
+        fn name(&self) -> &'static str {
+            "WasCreated"
+        }
+    }
+
+    fn was_created() -> WasCreated {
+        WasCreated {
+            user_id: "user:1".to_owned(),
+            display_name: "John".to_owned(),
+            tags: vec!["admin".to_owned()],
+        }
+    }
+
+    #[test]
+    fn envelope_serde_persists_metadata_and_schema_version() {
+        let serde = EnvelopeSerde::new(Json::<WasCreated>::default()).with_schema_version(2);
+        let envelope = message::Envelope::from(was_created())
+            .with_metadata("Recorded-At".to_owned(), "yesterday".to_owned());
+
+        let data = serde.serialize(envelope.clone()).unwrap();
+        assert!(is_envelope(&data));
+
+        let deserialized: message::Envelope<WasCreated> = serde.deserialize(&data).unwrap();
+
+        assert_eq!(
+            envelope.with_metadata(SCHEMA_VERSION_KEY.to_owned(), "2".to_owned()),
+            deserialized
+        );
+    }
+
+    #[test]
+    fn json_renames_top_level_fields() {
+        let serde = Json::<WasCreated>::default().with_rename_all(RenameAll::CamelCase);
+
+        let data = serde.serialize(was_created()).unwrap();
+        let value: serde_json::Value = serde_json::from_slice(&data).unwrap();
+
+        assert_eq!(
+            serde_json::json!({
+                "userId": "user:1",
+                "displayName": "John",
+                "tags": ["admin"],
+            }),
+            value
+        );
+        assert_eq!(was_created(), serde.deserialize(&data).unwrap());
+    }
+
+    fn validated() -> Validated<WasCreated> {
+        Validated::new(Json::default(), |value: &serde_json::Value| {
+            match value.get("user_id") {
+                Some(serde_json::Value::String(user_id)) if user_id.starts_with("user:") => Ok(()),
+                _ => Err(validate::Violation::field(
+                    "/user_id",
+                    "must be a string starting with 'user:'",
+                )
+                .into()),
+            }
+        })
+    }
+
+    #[test]
+    fn validated_rejects_invalid_payloads_in_both_directions() {
+        let serde = validated();
+
+        let data = serde.serialize(was_created()).unwrap();
+        assert_eq!(was_created(), serde.deserialize(&data).unwrap());
+
+        let expected = validate::ValidationError::from(validate::Violation::field(
+            "/user_id",
+            "must be a string starting with 'user:'",
+        ));
+
+        let invalid = WasCreated {
+            user_id: "1".to_owned(),
+            ..was_created()
+        };
+
+        let err = serde.serialize(invalid).unwrap_err();
+        assert_eq!(
+            Some(&expected),
+            err.downcast_ref::<validate::ValidationError>()
+        );
+
+        let err = serde
+            .deserialize(br#"{"user_id":1,"display_name":"John"}"#)
+            .unwrap_err();
+        assert_eq!(
+            Some(&expected),
+            err.downcast_ref::<validate::ValidationError>()
+        );
+    }
+
+    #[test]
+    fn json_denies_unknown_fields_only_when_configured() {
+        let data = br#"{"user_id":"user:1","display_name":"John","tags":[],"age":42}"#;
+
+        assert!(Json::<WasCreated>::default().deserialize(data).is_ok());
+
+        let err = Json::<WasCreated>::default()
+            .with_deny_unknown_fields()
+            .deserialize(data)
+            .unwrap_err();
+
+        assert!(err.to_string().contains("unknown fields: age"), "{err}");
+    }
+
+    #[test]
+    fn json_fills_missing_fields_with_defaults_only_when_configured() {
+        let data = br#"{"userId":"user:1"}"#;
+        let serde = Json::<WasCreated>::default().with_rename_all(RenameAll::CamelCase);
+
+        assert!(serde.deserialize(data).is_err());
+        assert_eq!(
+            WasCreated {
+                user_id: "user:1".to_owned(),
+                ..WasCreated::default()
+            },
+            serde
+                .with_default_missing_fields()
+                .deserialize(data)
+                .unwrap()
+        );
+    }
+}
+
+#[cfg(all(test, feature = "serde-prost"))]
+mod prost_tests {
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    enum CounterEvent {
+        WasStarted { id: String },
+        WasIncremented { by: u64 },
+    }
+
+    impl message::Message for CounterEvent {
+        fn name(&self) -> &'static str {
+            match self {
+                CounterEvent::WasStarted { .. } => "CounterWasStarted",
+                CounterEvent::WasIncremented { .. } => "CounterWasIncremented",
+            }
+        }
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    struct WasStarted {
+        #[prost(string, tag = "1")]
+        id: String,
+    }
+
+    impl prost::Name for WasStarted {
+        const NAME: &'static str = "WasStarted";
+        const PACKAGE: &'static str = "counter.v1";
+    }
+
+    impl TryFrom<CounterEvent> for WasStarted {
+        type Error = &'static str;
+
+        fn try_from(event: CounterEvent) -> Result<Self, Self::Error> {
+            match event {
+                CounterEvent::WasStarted { id } => Ok(Self { id }),
+                CounterEvent::WasIncremented { .. } => Err("not a WasStarted event"),
+            }
+        }
+    }
+
+    impl From<WasStarted> for CounterEvent {
+        fn from(message: WasStarted) -> Self {
+            CounterEvent::WasStarted { id: message.id }
+        }
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    struct WasIncremented {
+        #[prost(uint64, tag = "1")]
+        by: u64,
+    }
+
+    impl prost::Name for WasIncremented {
+        const NAME: &'static str = "WasIncremented";
+        const PACKAGE: &'static str = "counter.v1";
+    }
+
+    impl TryFrom<CounterEvent> for WasIncremented {
+        type Error = &'static str;
+
+        fn try_from(event: CounterEvent) -> Result<Self, Self::Error> {
+            match event {
+                CounterEvent::WasIncremented { by } => Ok(Self { by }),
+                CounterEvent::WasStarted { .. } => Err("not a WasIncremented event"),
+            }
+        }
+    }
+
+    impl From<WasIncremented> for CounterEvent {
+        fn from(message: WasIncremented) -> Self {
+            CounterEvent::WasIncremented { by: message.by }
+        }
+    }
+
+    #[test]
+    fn protobuf_any_maps_message_names_to_registered_types() {
+        let serde = ProtobufAny::<CounterEvent>::default()
+            .with_message::<WasStarted>("CounterWasStarted")
+            .with_message::<WasIncremented>("CounterWasIncremented");
+
+        for event in [
+            CounterEvent::WasStarted {
+                id: "counter:1".to_owned(),
+            },
+            CounterEvent::WasIncremented { by: 2 },
+        ] {
+            let data = serde.serialize(event.clone()).unwrap();
+            assert_eq!(event, serde.deserialize(&data).unwrap());
+        }
+
+        let data = serde
+            .serialize(CounterEvent::WasIncremented { by: 2 })
+            .unwrap();
+        let any: prost_types::Any = prost::Message::decode(data.as_slice()).unwrap();
+        assert_eq!("/counter.v1.WasIncremented", any.type_url);
+
+        let serde =
+            ProtobufAny::<CounterEvent>::default().with_message::<WasStarted>("CounterWasStarted");
+
+        assert!(serde
+            .serialize(CounterEvent::WasIncremented { by: 2 })
+            .is_err());
+        assert!(serde.deserialize(&data).is_err());
+    }
+}
+
+#[cfg(all(
+    test,
+    feature = "serde-json",
+    feature = "serde-gzip",
+    feature = "serde-zstd"
+))]
+mod compression_tests {
+    use serde::{Deserialize, Serialize};
+
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+    struct WasImported {
+        payload: String,
+    }
+
+    #[test]
+    fn compressed_serde_reads_compressed_and_uncompressed_data() {
+        let value = WasImported {
+            payload: "a rather repetitive payload ".repeat(100),
+        };
+
+        let uncompressed = Json::<WasImported>::default()
+            .serialize(value.clone())
+            .unwrap();
+
+        let gzip = Compressed::new(Json::<WasImported>::default(), Compression::Gzip);
+        let zstd = Compressed::new(
+            Json::<WasImported>::default(),
+            Compression::Zstd { level: 0 },
+        );
+
+        let gzipped = gzip.serialize(value.clone()).unwrap();
+        assert!(gzipped.len() < uncompressed.len());
+
+        assert_eq!(value, gzip.deserialize(&gzipped).unwrap());
+        assert_eq!(value, gzip.deserialize(&uncompressed).unwrap());
+        assert_eq!(value, zstd.deserialize(&gzipped).unwrap());
+        assert_eq!(
+            value,
+            zstd.deserialize(&zstd.serialize(value.clone()).unwrap())
+                .unwrap()
+        );
+
+        let small = WasImported {
+            payload: "small".to_owned(),
+        };
+
+        assert_eq!(
+            Json::<WasImported>::default()
+                .serialize(small.clone())
+                .unwrap(),
+            gzip.with_min_size(1024).serialize(small).unwrap()
+        );
+    }
+}
@@ -0,0 +1,1409 @@
+//! Module `subscription` contains abstractions to consume the Domain Events
+//! appended to an Event Store as they happen, such as the [`CatchUp`] subscription,
+//! the [`Polling`] subscription that works with any Event Store,
+//! the [`CheckpointStore`] to resume them from where they left off,
+//! and the [`ConsumerGroup`] abstraction to share them between competing consumers.
+
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::hash::Hash;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use futures::stream::{self, BoxStream, StreamExt, TryStreamExt};
+
+use crate::clock::{Clock, SystemClock};
+use crate::event::store::{GlobalStreamer, PoisonedError};
+use crate::version::Version;
+use crate::{event, message};
+
+/// Interface used to receive the Domain Events appended to an Event Store
+/// after the subscription has started, across all Event Streams.
+#[async_trait]
+pub trait Subscriber<StreamId, Event>: Send + Sync
+where
+    StreamId: Send + Sync,
+    Event: message::Message + Send + Sync,
+{
+    /// The error type returned by the Subscriber.
+    type Error: Send + Sync;
+
+    /// Starts a live subscription to all the Domain Events in the Event Store.
+    ///
+    /// Once this method returns, all the Domain Events appended to the Event Store
+    /// are delivered through the returned stream.
+    async fn subscribe_all(
+        &self,
+    ) -> Result<event::SequencedStream<'_, StreamId, Event, Self::Error>, Self::Error>;
+
+    /// Returns `true` if the error has been returned by the live subscription
+    /// because it lagged behind and some Domain Events have been skipped,
+    /// in which case a [`CatchUp`] subscription reads them again from the Event Store.
+    fn is_lagged(error: &Self::Error) -> bool {
+        let _ = error;
+        false
+    }
+}
+
+/// Error returned by a live subscription that could not keep up with
+/// the Domain Events appended to the Event Store, some of which have been skipped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("live subscription lagged behind, {skipped} domain events have been skipped")]
+pub struct LaggedError {
+    /// The number of Domain Events skipped.
+    pub skipped: u64,
+}
+
+/// Stores the [Sequence][event::Sequence] number of the last Domain Event processed
+/// by each named subscription (i.e. its checkpoint), e.g. by a projector
+/// or a [`Reactor`][crate::reactor::Reactor], so that it can be resumed
+/// from where it left off.
+#[async_trait]
+pub trait CheckpointStore: Send + Sync {
+    /// The error type returned by the store.
+    type Error: Send + Sync;
+
+    /// Returns the checkpoint of the named subscription,
+    /// or nothing if no Domain Event has been processed yet.
+    async fn load(&self, name: &str) -> Result<Option<event::Sequence>, Self::Error>;
+
+    /// Stores the checkpoint of the named subscription.
+    async fn save(&self, name: &str, sequence: event::Sequence) -> Result<(), Self::Error>;
+}
+
+/// In-memory implementation of a [`CheckpointStore`],
+/// best suited for testing and single-instance deployments.
+///
+/// Cloning an [`InMemoryCheckpointStore`] returns a handle to the same checkpoints.
+#[derive(Debug, Clone, Default)]
+pub struct InMemoryCheckpointStore {
+    checkpoints: Arc<Mutex<HashMap<String, event::Sequence>>>,
+}
+
+#[async_trait]
+impl CheckpointStore for InMemoryCheckpointStore {
+    type Error = PoisonedError;
+
+    async fn load(&self, name: &str) -> Result<Option<event::Sequence>, Self::Error> {
+        Ok(self
+            .checkpoints
+            .lock()
+            .map_err(|_| PoisonedError)?
+            .get(name)
+            .copied())
+    }
+
+    async fn save(&self, name: &str, sequence: event::Sequence) -> Result<(), Self::Error> {
+        self.checkpoints
+            .lock()
+            .map_err(|_| PoisonedError)?
+            .insert(name.to_owned(), sequence);
+
+        Ok(())
+    }
+}
+
+/// All possible errors returned by a [`CatchUp`] subscription.
+#[derive(Debug, thiserror::Error)]
+pub enum CatchUpError<StreamErr, SubscriptionErr> {
+    /// Error returned while streaming the Domain Events already in the Event Store.
+    #[error("failed to stream domain events: {0}")]
+    Stream(#[source] StreamErr),
+    /// Error returned by the live subscription.
+    #[error("failed to subscribe to domain events: {0}")]
+    Subscription(#[source] SubscriptionErr),
+}
+
+enum Phase<T> {
+    Historical(T),
+    Live(T),
+}
+
+type PhaseStream<'a, Id, Evt, StreamErr, SubscriptionErr> = BoxStream<
+    'a,
+    Result<Phase<event::Sequenced<Id, Evt>>, CatchUpError<StreamErr, SubscriptionErr>>,
+>;
+
+struct CatchingUp<'a, Id, Evt, StreamErr, SubscriptionErr>
+where
+    Evt: message::Message,
+{
+    // Sequence number of the last Domain Event delivered by the subscription.
+    high_watermark: Option<event::Sequence>,
+    phases: Option<PhaseStream<'a, Id, Evt, StreamErr, SubscriptionErr>>,
+}
+
+/// A subscription that first streams all the Domain Events already in the
+/// Event Store, starting from a checkpoint, and then switches to the Domain Events
+/// delivered by a live [Subscriber].
+///
+/// The live subscription is started before streaming the Domain Events already
+/// in the Event Store, so no Domain Event is lost during the switch. Domain Events
+/// delivered by both are de-duplicated using their [Sequence][event::Sequence] number,
+/// skipping the live ones not greater than the last one streamed while catching up:
+/// this relies on the [`GlobalStreamer`] assigning them in commit order.
+///
+/// Live Domain Events are buffered by the [Subscriber] while catching up:
+/// if it cannot hold enough of them and reports it through [`Subscriber::is_lagged`],
+/// the subscription is started again right after the last Domain Event delivered.
+#[derive(Debug, Clone)]
+pub struct CatchUp<S, L> {
+    streamer: S,
+    subscriber: L,
+}
+
+impl<S, L> CatchUp<S, L> {
+    /// Creates a new [`CatchUp`] subscription, using the specified [`GlobalStreamer`]
+    /// to catch up with the Domain Events already in the Event Store,
+    /// and the [Subscriber] for the live ones.
+    pub fn new(streamer: S, subscriber: L) -> Self {
+        Self {
+            streamer,
+            subscriber,
+        }
+    }
+
+    /// Opens the subscription, starting from the Domain Events selected
+    /// by the specified [`event::SequenceSelect`].
+    pub fn stream<'a, Id, Evt>(
+        &'a self,
+        select: event::SequenceSelect,
+    ) -> event::SequencedStream<'a, Id, Evt, CatchUpError<S::Error, L::Error>>
+    where
+        Id: Send + Sync + 'a,
+        Evt: message::Message + Send + Sync + 'a,
+        S: GlobalStreamer<Id, Evt>,
+        S::Error: 'a,
+        L: Subscriber<Id, Evt>,
+        L::Error: 'a,
+    {
+        let state = CatchingUp {
+            high_watermark: match select {
+                event::SequenceSelect::All => None,
+                event::SequenceSelect::From(sequence) => sequence.checked_sub(1),
+            },
+            phases: None,
+        };
+
+        stream::unfold(state, move |mut state| async move {
+            loop {
+                if state.phases.is_none() {
+                    let select = state
+                        .high_watermark
+                        .map_or(event::SequenceSelect::All, |sequence| {
+                            event::SequenceSelect::From(sequence + 1)
+                        });
+
+                    match self.open(select).await {
+                        Ok(phases) => state.phases = Some(phases),
+                        Err(err) => {
+                            state.phases = Some(stream::empty().boxed());
+                            return Some((Err(err), state));
+                        },
+                    }
+                }
+
+                let phases = state.phases.as_mut()?;
+
+                let event = match phases.next().await? {
+                    // Skip the live Domain Events already streamed while catching up.
+                    Ok(Phase::Live(event))
+                        if state
+                            .high_watermark
+                            .is_some_and(|sequence| event.sequence <= sequence) =>
+                    {
+                        continue;
+                    },
+                    Ok(Phase::Historical(event) | Phase::Live(event)) => event,
+                    // Read the skipped Domain Events again from the Event Store.
+                    Err(CatchUpError::Subscription(err)) if L::is_lagged(&err) => {
+                        state.phases = None;
+                        continue;
+                    },
+                    Err(err) => return Some((Err(err), state)),
+                };
+
+                state.high_watermark = Some(event.sequence);
+                return Some((Ok(event), state));
+            }
+        })
+        .boxed()
+    }
+
+    async fn open<'a, Id, Evt>(
+        &'a self,
+        select: event::SequenceSelect,
+    ) -> Result<PhaseStream<'a, Id, Evt, S::Error, L::Error>, CatchUpError<S::Error, L::Error>>
+    where
+        Id: Send + Sync + 'a,
+        Evt: message::Message + Send + Sync + 'a,
+        S: GlobalStreamer<Id, Evt>,
+        S::Error: 'a,
+        L: Subscriber<Id, Evt>,
+        L::Error: 'a,
+    {
+        let live = self
+            .subscriber
+            .subscribe_all()
+            .await
+            .map_err(CatchUpError::Subscription)?
+            .map_ok(Phase::Live)
+            .map_err(CatchUpError::Subscription);
+
+        let historical = self
+            .streamer
+            .stream_all(select)
+            .map_ok(Phase::Historical)
+            .map_err(CatchUpError::Stream);
+
+        Ok(historical.chain(live).boxed())
+    }
+
+    /// Opens the subscription right after the checkpoint of the named subscription
+    /// stored in the [`CheckpointStore`], or from the start if there is none.
+    ///
+    /// Saving the checkpoint while processing the Domain Events is up to the caller.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the checkpoint could not be loaded.
+    pub async fn resume<'a, Id, Evt, C>(
+        &'a self,
+        checkpoints: &C,
+        name: &str,
+    ) -> Result<event::SequencedStream<'a, Id, Evt, CatchUpError<S::Error, L::Error>>, C::Error>
+    where
+        Id: Send + Sync + 'a,
+        Evt: message::Message + Send + Sync + 'a,
+        S: GlobalStreamer<Id, Evt>,
+        S::Error: 'a,
+        L: Subscriber<Id, Evt>,
+        L::Error: 'a,
+        C: CheckpointStore,
+    {
+        let select = match checkpoints.load(name).await? {
+            None => event::SequenceSelect::All,
+            Some(sequence) => event::SequenceSelect::From(sequence + 1),
+        };
+
+        Ok(self.stream(select))
+    }
+}
+
+/// Default interval between two polls of the Event Store
+/// by a [`Polling`] subscription.
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Default time a [`Polling`] subscription waits for the Domain Events
+/// with skipped [Sequence][event::Sequence] numbers to be committed.
+pub const DEFAULT_GAP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Maximum number of Domain Events read from the Event Store in a single poll.
+const POLL_PAGE_SIZE: usize = 1000;
+
+/// An item delivered by a [`Polling`] subscription, together with
+/// the [`event::ResumeToken`] to resume the subscription right after it.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Polled<StreamId, Event>
+where
+    Event: message::Message,
+{
+    /// A Domain Event appended to the Event Store.
+    Event {
+        /// The delivered Domain Event.
+        event: event::Sequenced<StreamId, Event>,
+        /// The token to resume the subscription right after this Domain Event.
+        resume_token: event::ResumeToken,
+    },
+    /// Delivered when no Domain Event has been delivered for a heartbeat interval.
+    Heartbeat {
+        /// The token to resume the subscription from.
+        resume_token: event::ResumeToken,
+    },
+}
+
+impl<StreamId, Event> Polled<StreamId, Event>
+where
+    Event: message::Message,
+{
+    /// Returns the [`event::ResumeToken`] to resume the subscription
+    /// right after this item.
+    #[must_use]
+    pub fn resume_token(&self) -> event::ResumeToken {
+        match self {
+            Self::Event { resume_token, .. } | Self::Heartbeat { resume_token } => *resume_token,
+        }
+    }
+}
+
+/// A stream of [Polled] items, returned by a [`Polling`] subscription.
+pub type PolledStream<'a, Id, Evt, Err> = BoxStream<'a, Result<Polled<Id, Evt>, Err>>;
+
+/// A subscription to all the Domain Events in the Event Store, implemented by polling
+/// a [`GlobalStreamer`] for the ones following the last delivered Domain Event,
+/// so that it works with any Event Store implementation.
+///
+/// Event Stores might assign [Sequence][event::Sequence] numbers before committing
+/// the Domain Events, so a transaction can commit after another one holding greater
+/// Sequence numbers. The subscription keeps polling the Sequence numbers skipped so far
+/// for the gap timeout, delivering the Domain Events committed late, and the
+/// [`event::ResumeToken`] of each item points at the first skipped Sequence number:
+/// resuming from it might deliver some Domain Events again.
+///
+/// When a heartbeat interval is set, the subscription delivers a [heartbeat][Polled::Heartbeat]
+/// on the first poll after no Domain Event has been delivered for that long,
+/// so that idle consumers know the subscription is still alive.
+#[derive(Debug, Clone)]
+pub struct Polling<S> {
+    streamer: S,
+    poll_interval: Duration,
+    gap_timeout: Duration,
+    heartbeat_interval: Option<Duration>,
+}
+
+struct PollingState<S, Id, Evt>
+where
+    Evt: message::Message,
+{
+    polling: Polling<S>,
+    names: event::NameSelect,
+    /// The Sequence number right after the last delivered Domain Event, or the selected one.
+    next_sequence: Option<event::Sequence>,
+    /// The ranges of Sequence numbers skipped so far, by their start,
+    /// with their end (excluded) and the time they have been skipped at.
+    gaps: BTreeMap<event::Sequence, (event::Sequence, Instant)>,
+    /// The Sequence number to continue the current poll from, after a full page.
+    cursor: Option<event::Sequence>,
+    page: VecDeque<event::Sequenced<Id, Evt>>,
+    caught_up: bool,
+    idle_since: Instant,
+}
+
+impl<S, Id, Evt> PollingState<S, Id, Evt>
+where
+    Evt: message::Message,
+{
+    /// Returns the first Sequence number the subscription is still waiting for.
+    fn first_pending(&self) -> event::Sequence {
+        self.gaps
+            .keys()
+            .next()
+            .copied()
+            .or(self.next_sequence)
+            .unwrap_or_default()
+    }
+
+    fn resume_token(&self) -> event::ResumeToken {
+        event::ResumeToken::from_sequence(self.first_pending())
+    }
+
+    /// Records the Domain Event as delivered, returning `false`
+    /// if it had already been delivered.
+    fn deliver(&mut self, event: &event::Sequenced<Id, Evt>) -> bool {
+        let sequence = event.sequence;
+
+        match self.next_sequence {
+            Some(next_sequence) if sequence < next_sequence => {
+                let Some((&start, &(end, skipped_at))) = self.gaps.range(..=sequence).next_back()
+                else {
+                    return false;
+                };
+
+                if sequence >= end {
+                    return false;
+                }
+
+                // The Domain Event has been committed late: split the gap around it.
+                self.gaps.remove(&start);
+
+                if start < sequence {
+                    self.gaps.insert(start, (sequence, skipped_at));
+                }
+
+                if sequence + 1 < end {
+                    self.gaps.insert(sequence + 1, (end, skipped_at));
+                }
+            },
+            Some(next_sequence) => {
+                if sequence > next_sequence {
+                    self.gaps.insert(next_sequence, (sequence, Instant::now()));
+                }
+
+                self.next_sequence = Some(sequence.saturating_add(1));
+            },
+            None => self.next_sequence = Some(sequence.saturating_add(1)),
+        }
+
+        true
+    }
+
+    fn expire_gaps(&mut self) {
+        let gap_timeout = self.polling.gap_timeout;
+        self.gaps
+            .retain(|_, (_, skipped_at)| skipped_at.elapsed() < gap_timeout);
+    }
+}
+
+impl<S> Polling<S> {
+    /// Creates a new [`Polling`] subscription on the specified [`GlobalStreamer`],
+    /// which can be shared through an [`Arc`].
+    pub fn new(streamer: S) -> Self {
+        Self {
+            streamer,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            gap_timeout: DEFAULT_GAP_TIMEOUT,
+            heartbeat_interval: None,
+        }
+    }
+
+    /// Sets the interval between two polls of the Event Store
+    /// once all the Domain Events in it have been delivered.
+    ///
+    /// Defaults to [`DEFAULT_POLL_INTERVAL`].
+    #[must_use]
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// Sets how long the subscription waits for the Domain Events with skipped
+    /// [Sequence][event::Sequence] numbers to be committed, before giving up on them.
+    ///
+    /// Defaults to [`DEFAULT_GAP_TIMEOUT`].
+    #[must_use]
+    pub fn with_gap_timeout(mut self, gap_timeout: Duration) -> Self {
+        self.gap_timeout = gap_timeout;
+        self
+    }
+
+    /// Delivers a [heartbeat][Polled::Heartbeat] when no Domain Event
+    /// has been delivered for the specified interval.
+    ///
+    /// Heartbeats are disabled by default.
+    #[must_use]
+    pub fn with_heartbeat_interval(mut self, heartbeat_interval: Duration) -> Self {
+        self.heartbeat_interval = Some(heartbeat_interval);
+        self
+    }
+
+    /// Opens the subscription, starting from the Domain Events selected by the
+    /// specified [`event::SequenceSelect`] and [`event::NameSelect`].
+    ///
+    /// The returned stream never ends, unless the [`GlobalStreamer`] fails.
+    pub fn into_stream<'a, Id, Evt>(
+        self,
+        select: event::SequenceSelect,
+        names: event::NameSelect,
+    ) -> PolledStream<'a, Id, Evt, S::Error>
+    where
+        S: GlobalStreamer<Id, Evt> + 'a,
+        S::Error: 'a,
+        Id: Send + Sync + 'a,
+        Evt: message::Message + Send + Sync + 'a,
+    {
+        let next_sequence = match select {
+            event::SequenceSelect::All => None,
+            event::SequenceSelect::From(sequence) => Some(sequence),
+        };
+
+        let state = PollingState {
+            polling: self,
+            names,
+            next_sequence,
+            gaps: BTreeMap::new(),
+            cursor: None,
+            page: VecDeque::new(),
+            caught_up: false,
+            idle_since: Instant::now(),
+        };
+
+        stream::try_unfold(state, |mut state| async move {
+            loop {
+                while let Some(event) = state.page.pop_front() {
+                    if state.deliver(&event) {
+                        state.idle_since = Instant::now();
+                        let resume_token = state.resume_token();
+
+                        return Ok(Some((
+                            Polled::Event {
+                                event,
+                                resume_token,
+                            },
+                            state,
+                        )));
+                    }
+                }
+
+                if state.caught_up {
+                    state.expire_gaps();
+
+                    let heartbeat_due = state
+                        .polling
+                        .heartbeat_interval
+                        .is_some_and(|interval| state.idle_since.elapsed() >= interval);
+
+                    if heartbeat_due {
+                        state.idle_since = Instant::now();
+                        let resume_token = state.resume_token();
+
+                        return Ok(Some((Polled::Heartbeat { resume_token }, state)));
+                    }
+
+                    futures_timer::Delay::new(state.polling.poll_interval).await;
+                    state.cursor = None;
+                }
+
+                let from = state.cursor.unwrap_or_else(|| state.first_pending());
+
+                state.page = state
+                    .polling
+                    .streamer
+                    .stream_all_filtered(event::SequenceSelect::From(from), state.names.clone())
+                    .take(POLL_PAGE_SIZE)
+                    .try_collect()
+                    .await?;
+
+                // A full page means there might be more Domain Events to read right away.
+                state.caught_up = state.page.len() < POLL_PAGE_SIZE;
+                state.cursor = state
+                    .page
+                    .back()
+                    .map(|event| event.sequence.saturating_add(1));
+            }
+        })
+        .boxed()
+    }
+}
+
+/// Default number of `(stream id, version)` pairs remembered by an
+/// [`InMemoryDeduplicationStore`].
+pub const DEFAULT_DEDUPLICATION_CAPACITY: usize = 10_000;
+
+/// Stores the `(stream id, version)` pairs of the Domain Events delivered
+/// through a [Deduplicated] [Subscriber].
+#[async_trait]
+pub trait DeduplicationStore<StreamId>: Send + Sync
+where
+    StreamId: Send + Sync,
+{
+    /// The error type returned by the store.
+    type Error: Send + Sync;
+
+    /// Records the Domain Event at the specified version of the Event Stream
+    /// as seen, atomically.
+    ///
+    /// Returns `false` if it had already been recorded, i.e. the Domain Event is a duplicate.
+    async fn record(&self, stream_id: &StreamId, version: Version) -> Result<bool, Self::Error>;
+}
+
+#[derive(Debug)]
+struct SeenPairs<StreamId> {
+    set: HashSet<(StreamId, Version)>,
+    log: VecDeque<(StreamId, Version)>,
+}
+
+/// In-memory implementation of a [`DeduplicationStore`], remembering only
+/// the most recently seen Domain Events, up to a fixed capacity.
+///
+/// Cloning an [`InMemoryDeduplicationStore`] returns a handle to the same pairs.
+#[derive(Debug, Clone)]
+pub struct InMemoryDeduplicationStore<StreamId> {
+    capacity: usize,
+    seen: Arc<Mutex<SeenPairs<StreamId>>>,
+}
+
+impl<StreamId> Default for InMemoryDeduplicationStore<StreamId> {
+    fn default() -> Self {
+        Self::with_capacity(DEFAULT_DEDUPLICATION_CAPACITY)
+    }
+}
+
+impl<StreamId> InMemoryDeduplicationStore<StreamId> {
+    /// Creates a new [`InMemoryDeduplicationStore`] remembering at most
+    /// the specified number of `(stream id, version)` pairs,
+    /// evicting the oldest ones first.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the capacity is zero.
+    #[must_use]
+    pub fn with_capacity(capacity: usize) -> Self {
+        assert!(capacity > 0, "capacity must be greater than zero");
+
+        Self {
+            capacity,
+            seen: Arc::new(Mutex::new(SeenPairs {
+                set: HashSet::default(),
+                log: VecDeque::default(),
+            })),
+        }
+    }
+}
+
+#[async_trait]
+impl<StreamId> DeduplicationStore<StreamId> for InMemoryDeduplicationStore<StreamId>
+where
+    StreamId: Clone + Eq + Hash + Send + Sync,
+{
+    type Error = PoisonedError;
+
+    async fn record(&self, stream_id: &StreamId, version: Version) -> Result<bool, Self::Error> {
+        let mut seen = self.seen.lock().map_err(|_| PoisonedError)?;
+        let pair = (stream_id.clone(), version);
+
+        if !seen.set.insert(pair.clone()) {
+            return Ok(false);
+        }
+
+        seen.log.push_back(pair);
+
+        if seen.log.len() > self.capacity {
+            if let Some(oldest) = seen.log.pop_front() {
+                seen.set.remove(&oldest);
+            }
+        }
+
+        Ok(true)
+    }
+}
+
+/// All possible errors returned by a [Deduplicated] [Subscriber].
+#[derive(Debug, thiserror::Error)]
+pub enum DeduplicatedError<SubscriptionErr, StoreErr> {
+    /// Error returned by the decorated [Subscriber].
+    #[error("failed to subscribe to domain events: {0}")]
+    Subscription(#[source] SubscriptionErr),
+    /// Error returned when the [`DeduplicationStore`] has failed.
+    #[error("failed to access the deduplication store: {0}")]
+    Store(#[source] StoreErr),
+}
+
+/// Decorator for a [Subscriber] that filters out the Domain Events already delivered,
+/// as it happens with at-least-once transports, so that the consumers
+/// do not need their own idempotency logic.
+///
+/// Domain Events are identified by their Event Stream id and version,
+/// recorded in the [`DeduplicationStore`] as they are delivered.
+#[derive(Debug, Clone)]
+pub struct Deduplicated<S, D> {
+    subscriber: S,
+    store: D,
+}
+
+impl<S, D> Deduplicated<S, D> {
+    /// Decorates the [Subscriber] with the specified [`DeduplicationStore`].
+    pub fn new(subscriber: S, store: D) -> Self {
+        Self { subscriber, store }
+    }
+
+    /// Returns the underlying [Subscriber].
+    pub fn into_inner(self) -> S {
+        self.subscriber
+    }
+}
+
+#[async_trait]
+impl<S, D, StreamId, Evt> Subscriber<StreamId, Evt> for Deduplicated<S, D>
+where
+    S: Subscriber<StreamId, Evt>,
+    S::Error: 'static,
+    D: DeduplicationStore<StreamId>,
+    D::Error: 'static,
+    StreamId: Send + Sync + 'static,
+    Evt: message::Message + Send + Sync + 'static,
+{
+    type Error = DeduplicatedError<S::Error, D::Error>;
+
+    async fn subscribe_all(
+        &self,
+    ) -> Result<event::SequencedStream<'_, StreamId, Evt, Self::Error>, Self::Error> {
+        let store = &self.store;
+
+        let stream = self
+            .subscriber
+            .subscribe_all()
+            .await
+            .map_err(DeduplicatedError::Subscription)?
+            .map_err(DeduplicatedError::Subscription)
+            .try_filter_map(move |event| async move {
+                let is_new = store
+                    .record(&event.event.stream_id, event.event.version)
+                    .await
+                    .map_err(DeduplicatedError::Store)?;
+
+                Ok(is_new.then_some(event))
+            });
+
+        Ok(stream.boxed())
+    }
+}
+
+/// A Domain Event delivered to a consumer of a [`ConsumerGroup`],
+/// which stays pending until it is acknowledged.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Delivery<StreamId, Event>
+where
+    Event: message::Message,
+{
+    /// Number of times the Domain Event has been delivered to the group,
+    /// including this one.
+    pub attempts: u32,
+    /// The delivered Domain Event.
+    pub event: event::Sequenced<StreamId, Event>,
+}
+
+/// A named group of competing consumers, sharing the Domain Events
+/// of a subscription so that each of them is processed by a single consumer.
+///
+/// Every delivered Domain Event stays pending, assigned to its consumer,
+/// until it is acknowledged: pending Domain Events of consumers that have crashed
+/// can be claimed by the other consumers of the group.
+///
+/// Implemented by the backends supporting competing consumers,
+/// so that application code does not depend on a specific one.
+#[async_trait]
+pub trait ConsumerGroup<StreamId, Event>: Send + Sync
+where
+    StreamId: Send + Sync,
+    Event: message::Message + Send + Sync,
+{
+    /// The error type returned by the Consumer Group.
+    type Error: Send + Sync;
+
+    /// Returns the name of the Consumer Group.
+    fn name(&self) -> &str;
+
+    /// Delivers up to `max` Domain Events not yet delivered to the group,
+    /// or negatively acknowledged, to the named consumer.
+    async fn fetch(
+        &self,
+        consumer: &str,
+        max: usize,
+    ) -> Result<Vec<Delivery<StreamId, Event>>, Self::Error>;
+
+    /// Acknowledges the Domain Event with the specified [Sequence][event::Sequence]
+    /// number as processed, so that it is not delivered again.
+    async fn ack(&self, sequence: event::Sequence) -> Result<(), Self::Error>;
+
+    /// Negatively acknowledges the Domain Event with the specified
+    /// [Sequence][event::Sequence] number, so that it is delivered again
+    /// to the next consumer fetching from the group.
+    async fn nack(&self, sequence: event::Sequence) -> Result<(), Self::Error>;
+
+    /// Assigns to the named consumer up to `max` pending Domain Events
+    /// that have not been acknowledged for at least `min_idle`,
+    /// e.g. because their consumer has crashed, and delivers them again.
+    async fn claim_pending(
+        &self,
+        consumer: &str,
+        min_idle: Duration,
+        max: usize,
+    ) -> Result<Vec<Delivery<StreamId, Event>>, Self::Error>;
+}
+
+#[derive(Debug)]
+struct Pending<StreamId, Evt>
+where
+    Evt: message::Message,
+{
+    delivered_at: chrono::DateTime<chrono::Utc>,
+    delivery: Delivery<StreamId, Evt>,
+}
+
+#[derive(Debug)]
+struct GroupState<StreamId, Evt>
+where
+    Evt: message::Message,
+{
+    next_sequence: event::Sequence,
+    pending: BTreeMap<event::Sequence, Pending<StreamId, Evt>>,
+    released: BTreeMap<event::Sequence, Delivery<StreamId, Evt>>,
+}
+
+/// In-memory implementation of a [`ConsumerGroup`], sharing the Domain Events
+/// of a [`GlobalStreamer`] between its consumers, best suited for testing
+/// and single-instance deployments.
+///
+/// Since all the consumers live in the same process, the pending Domain Events
+/// are not tracked per consumer.
+///
+/// Cloning an [`InMemoryConsumerGroup`] returns a handle to the same group.
+#[derive(Debug)]
+pub struct InMemoryConsumerGroup<S, StreamId, Evt>
+where
+    Evt: message::Message,
+{
+    name: String,
+    streamer: S,
+    clock: Arc<dyn Clock>,
+    state: Arc<Mutex<GroupState<StreamId, Evt>>>,
+}
+
+impl<S, StreamId, Evt> Clone for InMemoryConsumerGroup<S, StreamId, Evt>
+where
+    S: Clone,
+    Evt: message::Message,
+{
+    fn clone(&self) -> Self {
+        Self {
+            name: self.name.clone(),
+            streamer: self.streamer.clone(),
+            clock: self.clock.clone(),
+            state: self.state.clone(),
+        }
+    }
+}
+
+impl<S, StreamId, Evt> InMemoryConsumerGroup<S, StreamId, Evt>
+where
+    Evt: message::Message,
+{
+    /// Creates a new [`InMemoryConsumerGroup`] with the specified name,
+    /// delivering all the Domain Events of the [`GlobalStreamer`] from the start.
+    pub fn new(name: impl Into<String>, streamer: S) -> Self {
+        Self {
+            name: name.into(),
+            streamer,
+            clock: Arc::new(SystemClock),
+            state: Arc::new(Mutex::new(GroupState {
+                next_sequence: 0,
+                pending: BTreeMap::default(),
+                released: BTreeMap::default(),
+            })),
+        }
+    }
+
+    /// Uses the specified [Clock] to measure for how long the Domain Events
+    /// have been pending, instead of the system time.
+    #[must_use]
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    // NOTE: the group state is never left halfway updated by a panicking thread,
+    // as none of the operations performed while holding the lock can panic.
+    fn lock_state(&self) -> MutexGuard<'_, GroupState<StreamId, Evt>> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[async_trait]
+impl<S, StreamId, Evt> ConsumerGroup<StreamId, Evt> for InMemoryConsumerGroup<S, StreamId, Evt>
+where
+    S: GlobalStreamer<StreamId, Evt>,
+    StreamId: Clone + Send + Sync,
+    Evt: message::Message + Clone + Send + Sync,
+{
+    type Error = S::Error;
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn fetch(
+        &self,
+        _consumer: &str,
+        max: usize,
+    ) -> Result<Vec<Delivery<StreamId, Evt>>, Self::Error> {
+        let now = self.clock.now();
+
+        let (mut deliveries, next_sequence) = {
+            let mut state = self.lock_state();
+            let mut deliveries = Vec::new();
+
+            while deliveries.len() < max {
+                let Some((sequence, mut delivery)) = state.released.pop_first() else {
+                    break;
+                };
+
+                delivery.attempts += 1;
+                deliveries.push(delivery.clone());
+                state.pending.insert(
+                    sequence,
+                    Pending {
+                        delivered_at: now,
+                        delivery,
+                    },
+                );
+            }
+
+            (deliveries, state.next_sequence)
+        };
+
+        if deliveries.len() == max {
+            return Ok(deliveries);
+        }
+
+        let events: Vec<_> = self
+            .streamer
+            .stream_all(event::SequenceSelect::From(next_sequence))
+            .take(max - deliveries.len())
+            .try_collect()
+            .await?;
+
+        let mut state = self.lock_state();
+
+        for event in events {
+            // NOTE: skip the Domain Events delivered by a concurrent fetch in the meantime.
+            if event.sequence < state.next_sequence {
+                continue;
+            }
+
+            state.next_sequence = event.sequence + 1;
+
+            let delivery = Delivery { attempts: 1, event };
+            deliveries.push(delivery.clone());
+            state.pending.insert(
+                delivery.event.sequence,
+                Pending {
+                    delivered_at: now,
+                    delivery,
+                },
+            );
+        }
+
+        Ok(deliveries)
+    }
+
+    async fn ack(&self, sequence: event::Sequence) -> Result<(), Self::Error> {
+        self.lock_state().pending.remove(&sequence);
+
+        Ok(())
+    }
+
+    async fn nack(&self, sequence: event::Sequence) -> Result<(), Self::Error> {
+        let mut state = self.lock_state();
+
+        if let Some(pending) = state.pending.remove(&sequence) {
+            state.released.insert(sequence, pending.delivery);
+        }
+
+        Ok(())
+    }
+
+    async fn claim_pending(
+        &self,
+        _consumer: &str,
+        min_idle: Duration,
+        max: usize,
+    ) -> Result<Vec<Delivery<StreamId, Evt>>, Self::Error> {
+        let now = self.clock.now();
+        let min_idle = chrono::Duration::from_std(min_idle).unwrap_or(chrono::Duration::MAX);
+        let mut state = self.lock_state();
+
+        Ok(state
+            .pending
+            .values_mut()
+            .filter(|pending| now.signed_duration_since(pending.delivered_at) >= min_idle)
+            .take(max)
+            .map(|pending| {
+                pending.delivered_at = now;
+                pending.delivery.attempts += 1;
+                pending.delivery.clone()
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::future::ready;
+    use std::sync::LazyLock;
+
+    use super::*;
+    use crate::clock::TestClock;
+    use crate::event::store::{Appender, InMemory};
+    use crate::message::tests::StringMessage;
+    use crate::version;
+
+    static EVENTS: LazyLock<Vec<event::Envelope<StringMessage>>> = LazyLock::new(|| {
+        vec![
+            event::Envelope::from(StringMessage("event-1")),
+            event::Envelope::from(StringMessage("event-2")),
+        ]
+    });
+
+    /// Replays the Domain Events appended to the Event Store so far,
+    /// to simulate the overlap between the catch-up and the live subscription.
+    struct ReplayingSubscriber(InMemory<&'static str, StringMessage>);
+
+    #[async_trait]
+    impl Subscriber<&'static str, StringMessage> for ReplayingSubscriber {
+        type Error = PoisonedError;
+
+        async fn subscribe_all(
+            &self,
+        ) -> Result<
+            event::SequencedStream<'_, &'static str, StringMessage, PoisonedError>,
+            PoisonedError,
+        > {
+            let events: Vec<_> = self
+                .0
+                .stream_all(event::SequenceSelect::All)
+                .try_collect()
+                .await?;
+
+            self.0
+                .append("stream:live", version::Check::Any, EVENTS.clone())
+                .await
+                .expect("append should not fail");
+
+            let live_events: Vec<_> = self
+                .0
+                .stream_all(event::SequenceSelect::From(events.len() as u64 + 1))
+                .try_collect()
+                .await?;
+
+            Ok(stream::iter(events.into_iter().chain(live_events).map(Ok)).boxed())
+        }
+    }
+
+    #[tokio::test]
+    async fn catch_up_streams_historical_and_live_events_without_duplicates() {
+        let event_store = InMemory::<&'static str, StringMessage>::default();
+
+        for stream_id in ["stream:a", "stream:b"] {
+            event_store
+                .append(stream_id, version::Check::MustBe(0), EVENTS.clone())
+                .await
+                .expect("append should not fail");
+        }
+
+        let subscription = CatchUp::new(
+            event_store.clone(),
+            ReplayingSubscriber(event_store.clone()),
+        );
+
+        let sequences: Vec<_> = subscription
+            .stream(event::SequenceSelect::From(2))
+            .map_ok(|event| (event.sequence, event.event.stream_id))
+            .try_collect()
+            .await
+            .expect("subscription should not fail");
+
+        assert_eq!(
+            vec![
+                (2, "stream:a"),
+                (3, "stream:b"),
+                (4, "stream:b"),
+                (5, "stream:live"),
+                (6, "stream:live"),
+            ],
+            sequences
+        );
+    }
+
+    #[tokio::test]
+    async fn catch_up_resumes_right_after_the_stored_checkpoint() {
+        let event_store = InMemory::<&'static str, StringMessage>::default();
+        let checkpoints = InMemoryCheckpointStore::default();
+
+        event_store
+            .append("stream:a", version::Check::MustBe(0), EVENTS.clone())
+            .await
+            .expect("append should not fail");
+
+        checkpoints
+            .save("projector", 1)
+            .await
+            .expect("checkpoint should be saved");
+
+        let subscription = CatchUp::new(
+            event_store.clone(),
+            ReplayingSubscriber(event_store.clone()),
+        );
+
+        let sequences: Vec<_> = subscription
+            .resume(&checkpoints, "projector")
+            .await
+            .expect("checkpoint should be loaded")
+            .map_ok(|event| event.sequence)
+            .try_collect()
+            .await
+            .expect("subscription should not fail");
+
+        assert_eq!(vec![2, 3, 4], sequences);
+        assert_eq!(
+            None,
+            checkpoints
+                .load("reactor")
+                .await
+                .expect("checkpoint should be loaded")
+        );
+    }
+
+    #[tokio::test]
+    async fn polling_delivers_new_events_and_heartbeats_to_resume_from() {
+        let event_store = InMemory::<&'static str, StringMessage>::default();
+
+        event_store
+            .append("stream:a", version::Check::MustBe(0), EVENTS.clone())
+            .await
+            .expect("append should not fail");
+
+        let polling = Polling::new(event_store.clone())
+            .with_poll_interval(Duration::from_millis(5))
+            .with_heartbeat_interval(Duration::from_millis(20));
+
+        let delivered: Vec<_> = polling
+            .clone()
+            .into_stream(event::SequenceSelect::From(2), event::NameSelect::All)
+            .take(2)
+            .try_collect()
+            .await
+            .expect("subscription should not fail");
+
+        let [Polled::Event { event, .. }, Polled::Heartbeat { resume_token }] =
+            delivered.as_slice()
+        else {
+            panic!("subscription should deliver the domain event, then a heartbeat once idle");
+        };
+
+        assert_eq!(2, event.sequence);
+        assert_eq!(event.resume_token(), *resume_token);
+
+        event_store
+            .append("stream:b", version::Check::MustBe(0), EVENTS.clone())
+            .await
+            .expect("append should not fail");
+
+        let select =
+            event::SequenceSelect::try_from(*resume_token).expect("token should select sequences");
+        let sequences: Vec<_> = polling
+            .into_stream(select, event::NameSelect::All)
+            .take(2)
+            .map_ok(|polled| match polled {
+                Polled::Event { event, .. } => event.sequence,
+                Polled::Heartbeat { .. } => {
+                    panic!("subscription should deliver the new domain events")
+                },
+            })
+            .try_collect()
+            .await
+            .expect("subscription should not fail");
+
+        assert_eq!(vec![3, 4], sequences);
+    }
+
+    /// Hides the Domain Event with the specified Sequence number until it is committed,
+    /// to simulate a transaction committing after the ones with greater Sequence numbers.
+    #[derive(Clone)]
+    struct LateCommit {
+        event_store: InMemory<&'static str, StringMessage>,
+        sequence: event::Sequence,
+        committed: Arc<std::sync::atomic::AtomicBool>,
+    }
+
+    impl GlobalStreamer<&'static str, StringMessage> for LateCommit {
+        type Error = PoisonedError;
+
+        fn stream_all(
+            &self,
+            select: event::SequenceSelect,
+        ) -> event::SequencedStream<'_, &'static str, StringMessage, Self::Error> {
+            let committed = self.committed.load(std::sync::atomic::Ordering::SeqCst);
+
+            self.event_store
+                .stream_all(select)
+                .try_filter(move |event| ready(committed || event.sequence != self.sequence))
+                .boxed()
+        }
+    }
+
+    #[tokio::test]
+    async fn polling_delivers_events_committed_after_greater_sequences() {
+        let event_store = InMemory::<&'static str, StringMessage>::default();
+
+        for stream_id in ["stream:a", "stream:b"] {
+            event_store
+                .append(stream_id, version::Check::MustBe(0), EVENTS.clone())
+                .await
+                .expect("append should not fail");
+        }
+
+        let late_commit = LateCommit {
+            event_store,
+            sequence: 2,
+            committed: Arc::default(),
+        };
+
+        let mut subscription = Polling::new(late_commit.clone())
+            .with_poll_interval(Duration::from_millis(5))
+            .into_stream(event::SequenceSelect::All, event::NameSelect::All)
+            .map_ok(|polled| match polled {
+                Polled::Event {
+                    event,
+                    resume_token,
+                } => (event.sequence, resume_token),
+                Polled::Heartbeat { .. } => panic!("heartbeats should be disabled"),
+            });
+
+        let mut delivered = Vec::new();
+        for _ in 0..3 {
+            delivered.push(subscription.try_next().await.unwrap().unwrap());
+        }
+
+        late_commit
+            .committed
+            .store(true, std::sync::atomic::Ordering::SeqCst);
+
+        delivered.push(subscription.try_next().await.unwrap().unwrap());
+
+        let token = event::ResumeToken::from_sequence;
+        assert_eq!(
+            vec![(1, token(2)), (3, token(2)), (4, token(2)), (2, token(5))],
+            delivered
+        );
+    }
+
+    /// Delivers every Domain Event in the Event Store twice,
+    /// to simulate the redeliveries of an at-least-once transport.
+    struct RedeliveringSubscriber(InMemory<&'static str, StringMessage>);
+
+    #[async_trait]
+    impl Subscriber<&'static str, StringMessage> for RedeliveringSubscriber {
+        type Error = PoisonedError;
+
+        async fn subscribe_all(
+            &self,
+        ) -> Result<
+            event::SequencedStream<'_, &'static str, StringMessage, PoisonedError>,
+            PoisonedError,
+        > {
+            let events: Vec<_> = self
+                .0
+                .stream_all(event::SequenceSelect::All)
+                .try_collect()
+                .await?;
+
+            Ok(stream::iter(events.clone().into_iter().chain(events).map(Ok)).boxed())
+        }
+    }
+
+    #[tokio::test]
+    async fn deduplicated_subscriber_filters_out_redelivered_events() {
+        let event_store = InMemory::<&'static str, StringMessage>::default();
+
+        for stream_id in ["stream:a", "stream:b"] {
+            event_store
+                .append(stream_id, version::Check::MustBe(0), EVENTS.clone())
+                .await
+                .expect("append should not fail");
+        }
+
+        let store = InMemoryDeduplicationStore::default();
+        let subscriber = Deduplicated::new(RedeliveringSubscriber(event_store.clone()), store);
+
+        let delivered: Vec<_> = subscriber
+            .subscribe_all()
+            .await
+            .expect("subscription should not fail")
+            .map_ok(|event| (event.event.stream_id, event.event.version))
+            .try_collect()
+            .await
+            .expect("subscription should not fail");
+
+        assert_eq!(
+            vec![
+                ("stream:a", 1),
+                ("stream:a", 2),
+                ("stream:b", 1),
+                ("stream:b", 2),
+            ],
+            delivered
+        );
+    }
+
+    #[tokio::test]
+    async fn in_memory_deduplication_store_forgets_the_oldest_pairs() {
+        let store = InMemoryDeduplicationStore::with_capacity(2);
+
+        assert!(store
+            .record(&"stream:a", 1)
+            .await
+            .expect("record should not fail"));
+        assert!(store
+            .record(&"stream:a", 2)
+            .await
+            .expect("record should not fail"));
+        assert!(!store
+            .record(&"stream:a", 1)
+            .await
+            .expect("record should not fail"));
+        assert!(store
+            .record(&"stream:a", 3)
+            .await
+            .expect("record should not fail"));
+
+        // The first pair has been evicted to make room for the last one.
+        assert!(store
+            .record(&"stream:a", 1)
+            .await
+            .expect("record should not fail"));
+        assert!(!store
+            .record(&"stream:a", 3)
+            .await
+            .expect("record should not fail"));
+    }
+
+    #[tokio::test]
+    async fn consumer_group_shares_the_events_between_its_consumers() {
+        let event_store = InMemory::<&'static str, StringMessage>::default();
+        let clock = TestClock::default();
+
+        event_store
+            .append(
+                "stream:a",
+                version::Check::MustBe(0),
+                vec![event::Envelope::from(StringMessage("event")); 4],
+            )
+            .await
+            .expect("append should not fail");
+
+        let group = InMemoryConsumerGroup::new("group", event_store).with_clock(clock.clone());
+
+        let sequences = |deliveries: Vec<Delivery<&'static str, StringMessage>>| -> Vec<_> {
+            deliveries
+                .into_iter()
+                .map(|delivery| (delivery.event.sequence, delivery.attempts))
+                .collect()
+        };
+
+        let first = group
+            .fetch("consumer-1", 2)
+            .await
+            .expect("fetch should not fail");
+        let second = group
+            .fetch("consumer-2", 3)
+            .await
+            .expect("fetch should not fail");
+
+        assert_eq!(vec![(1, 1), (2, 1)], sequences(first));
+        assert_eq!(vec![(3, 1), (4, 1)], sequences(second));
+
+        group.ack(1).await.expect("ack should not fail");
+        group.nack(3).await.expect("nack should not fail");
+
+        let redelivered = group
+            .fetch("consumer-1", 10)
+            .await
+            .expect("fetch should not fail");
+        assert_eq!(vec![(3, 2)], sequences(redelivered));
+
+        // Nothing has been pending for long enough yet.
+        let claimed = group
+            .claim_pending("consumer-1", Duration::from_secs(30), 10)
+            .await
+            .expect("claim should not fail");
+        assert!(claimed.is_empty());
+
+        clock.advance(chrono::Duration::seconds(30));
+
+        let claimed = group
+            .claim_pending("consumer-1", Duration::from_secs(30), 10)
+            .await
+            .expect("claim should not fail");
+        assert_eq!(vec![(2, 2), (3, 3), (4, 2)], sequences(claimed));
+    }
+}
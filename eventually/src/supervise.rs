@@ -0,0 +1,201 @@
+//! Module `supervise` contains the [Supervisor], to restart long-running
+//! [Runner]s, such as reactors or schedulers, when they fail,
+//! instead of writing bespoke recovery loops around them.
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+
+use crate::runner::{Runner, Shutdown};
+
+type CrashCallback<E> = Box<dyn Fn(usize, &E) + Send + Sync>;
+
+/// [Runner] decorator that restarts the supervised [Runner] every time it fails,
+/// waiting for an exponentially increasing backoff between restarts.
+///
+/// The [Supervisor] stops, returning the last error, once the maximum number
+/// of restarts has been reached, if any.
+pub struct Supervisor<R>
+where
+    R: Runner,
+{
+    runner: R,
+    max_restarts: Option<usize>,
+    backoff: Duration,
+    max_backoff: Duration,
+    on_crash: Option<CrashCallback<R::Error>>,
+}
+
+impl<R> From<R> for Supervisor<R>
+where
+    R: Runner,
+{
+    fn from(runner: R) -> Self {
+        Self {
+            runner,
+            max_restarts: None,
+            backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_mins(1),
+            on_crash: None,
+        }
+    }
+}
+
+impl<R> Supervisor<R>
+where
+    R: Runner,
+{
+    /// Sets the maximum number of times the [Runner] is restarted.
+    /// Defaults to restarting it forever.
+    #[must_use]
+    pub fn with_max_restarts(mut self, max_restarts: usize) -> Self {
+        self.max_restarts = Some(max_restarts);
+        self
+    }
+
+    /// Sets the duration to wait before the first restart, doubled on every
+    /// subsequent restart up to the maximum backoff.
+    /// Defaults to 1 second, up to 1 minute.
+    #[must_use]
+    pub fn with_backoff(mut self, backoff: Duration, max_backoff: Duration) -> Self {
+        self.backoff = backoff;
+        self.max_backoff = max_backoff;
+        self
+    }
+
+    /// Calls the specified callback with the number of failures so far
+    /// and the error, every time the [Runner] fails.
+    #[must_use]
+    pub fn with_crash_callback<F>(mut self, on_crash: F) -> Self
+    where
+        F: Fn(usize, &R::Error) + Send + Sync + 'static,
+    {
+        self.on_crash = Some(Box::new(on_crash));
+        self
+    }
+
+    /// Returns the supervised [Runner].
+    pub fn into_inner(self) -> R {
+        self.runner
+    }
+}
+
+#[async_trait]
+impl<R> Runner for Supervisor<R>
+where
+    R: Runner,
+{
+    type Error = R::Error;
+
+    /// Runs the supervised [Runner] until the [Shutdown] is requested,
+    /// restarting it every time it fails.
+    ///
+    /// If the shutdown is requested while waiting to restart the [Runner],
+    /// the [Supervisor] returns right away, without an error.
+    async fn run_until(&self, shutdown: Shutdown) -> Result<(), Self::Error> {
+        let mut failures = 0;
+        let mut backoff = self.backoff;
+
+        loop {
+            let Err(err) = self.runner.run_until(shutdown.clone()).await else {
+                return Ok(());
+            };
+
+            failures += 1;
+
+            if let Some(on_crash) = &self.on_crash {
+                on_crash(failures, &err);
+            }
+
+            if self
+                .max_restarts
+                .is_some_and(|max_restarts| failures > max_restarts)
+            {
+                return Err(err);
+            }
+
+            shutdown.sleep(backoff).await;
+
+            if shutdown.is_requested() {
+                return Ok(());
+            }
+
+            backoff = (backoff * 2).min(self.max_backoff);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+
+    use anyhow::anyhow;
+
+    use super::*;
+
+    /// Fails the specified number of runs, then stops successfully.
+    #[derive(Default)]
+    struct FlakyRunner {
+        failures: usize,
+        runs: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl Runner for FlakyRunner {
+        type Error = anyhow::Error;
+
+        async fn run_until(&self, _shutdown: Shutdown) -> Result<(), Self::Error> {
+            let run = self.runs.fetch_add(1, Ordering::SeqCst) + 1;
+
+            if run <= self.failures {
+                return Err(anyhow!("run #{run} failed"));
+            }
+
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn supervisor_restarts_the_runner_until_it_succeeds() {
+        let crashes = Arc::new(Mutex::new(Vec::new()));
+        let supervisor = Supervisor::from(FlakyRunner {
+            failures: 2,
+            ..FlakyRunner::default()
+        })
+        .with_backoff(Duration::from_millis(1), Duration::from_millis(2))
+        .with_crash_callback({
+            let crashes = crashes.clone();
+            move |failures, err| crashes.lock().unwrap().push((failures, err.to_string()))
+        });
+
+        supervisor.run_until(Shutdown::never()).await.unwrap();
+
+        assert_eq!(3, supervisor.into_inner().runs.load(Ordering::SeqCst));
+        assert_eq!(
+            vec![
+                (1, "run #1 failed".to_owned()),
+                (2, "run #2 failed".to_owned())
+            ],
+            *crashes.lock().unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn supervisor_gives_up_after_the_maximum_restarts() {
+        let supervisor = Supervisor::from(FlakyRunner {
+            failures: usize::MAX,
+            ..FlakyRunner::default()
+        })
+        .with_backoff(Duration::from_millis(1), Duration::from_millis(2))
+        .with_max_restarts(2);
+
+        let err = supervisor
+            .run_until(Shutdown::never())
+            .await
+            .expect_err("the supervisor should give up");
+
+        assert_eq!("run #3 failed", err.to_string());
+        assert_eq!(3, supervisor.into_inner().runs.load(Ordering::SeqCst));
+    }
+}
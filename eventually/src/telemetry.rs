@@ -0,0 +1,423 @@
+//! Module containing decorators to emit [OpenTelemetry](https://opentelemetry.io) metrics
+//! from the core abstractions, such as the [`event::Store`] and the [`command::Handler`].
+//!
+//! The module is exporter-agnostic: all the instruments are created from
+//! the [`Meter`] passed to [`Metrics::new`], which is usually obtained from
+//! the `MeterProvider` configured by the application.
+
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Instant;
+
+use async_trait::async_trait;
+use futures::stream::{StreamExt, TryStreamExt};
+use opentelemetry::metrics::{Counter, Histogram, Meter, Unit};
+use opentelemetry::KeyValue;
+
+use crate::aggregate::repository::{GetError, Getter, SaveError, Saver};
+use crate::aggregate::Aggregate;
+use crate::version::{self, Version};
+use crate::{aggregate, command, event, message};
+
+/// The set of instruments used to emit metrics from the core abstractions.
+///
+/// The following metrics are emitted:
+/// - `eventually.events.appended`: number of Domain Events appended to an Event Store,
+/// - `eventually.events.streamed`: number of Domain Events streamed from an Event Store,
+/// - `eventually.events.conflicts`: number of append operations failed with a version conflict,
+/// - `eventually.command.duration`: duration of the handling of a Command, in seconds,
+/// - `eventually.aggregate.rehydration.duration`: duration of the loading of an
+///   [Aggregate Root][aggregate::Root] from a [Repository][aggregate::Repository], in seconds,
+/// - `eventually.aggregate.rehydration.events`: number of Domain Events applied
+///   to load an [Aggregate Root][aggregate::Root],
+/// - `eventually.projection.lag`: number of Domain Events a projection
+///   still has to process, as recorded through [`Metrics::record_projection_lag`].
+///
+/// Cloning [Metrics] is cheap, as all the instruments are shared.
+#[derive(Debug, Clone)]
+pub struct Metrics {
+    events_appended: Counter<u64>,
+    events_streamed: Counter<u64>,
+    conflicts: Counter<u64>,
+    command_duration: Histogram<f64>,
+    rehydration_duration: Histogram<f64>,
+    rehydration_events: Histogram<u64>,
+    projection_lags: Arc<Mutex<HashMap<String, u64>>>,
+}
+
+impl Metrics {
+    /// Creates all the instruments using the specified [`Meter`].
+    #[must_use]
+    pub fn new(meter: &Meter) -> Self {
+        let projection_lags: Arc<Mutex<HashMap<String, u64>>> = Arc::default();
+        let observed_lags = projection_lags.clone();
+
+        // The gauge is kept alive by the Meter through its callback.
+        let _projection_lag = meter
+            .u64_observable_gauge("eventually.projection.lag")
+            .with_description("Number of domain events a projection still has to process")
+            .with_unit(Unit::new("{event}"))
+            .with_callback(move |observer| {
+                let lags = observed_lags.lock().unwrap_or_else(PoisonError::into_inner);
+
+                for (projection, lag) in lags.iter() {
+                    observer.observe(*lag, &[KeyValue::new("projection", projection.clone())]);
+                }
+            })
+            .init();
+
+        Self {
+            events_appended: meter
+                .u64_counter("eventually.events.appended")
+                .with_description("Number of domain events appended to the event store")
+                .with_unit(Unit::new("{event}"))
+                .init(),
+            events_streamed: meter
+                .u64_counter("eventually.events.streamed")
+                .with_description("Number of domain events streamed from the event store")
+                .with_unit(Unit::new("{event}"))
+                .init(),
+            conflicts: meter
+                .u64_counter("eventually.events.conflicts")
+                .with_description("Number of append operations failed with a version conflict")
+                .with_unit(Unit::new("{conflict}"))
+                .init(),
+            command_duration: meter
+                .f64_histogram("eventually.command.duration")
+                .with_description("Duration of the handling of a command")
+                .with_unit(Unit::new("s"))
+                .init(),
+            rehydration_duration: meter
+                .f64_histogram("eventually.aggregate.rehydration.duration")
+                .with_description("Duration of the loading of an aggregate root")
+                .with_unit(Unit::new("s"))
+                .init(),
+            rehydration_events: meter
+                .u64_histogram("eventually.aggregate.rehydration.events")
+                .with_description("Number of domain events applied to load an aggregate root")
+                .with_unit(Unit::new("{event}"))
+                .init(),
+            projection_lags,
+        }
+    }
+
+    /// Records the lag of the specified projection, computed as the difference
+    /// between the head [Sequence][event::Sequence] of the Event Store and
+    /// the last [Sequence][event::Sequence] processed by the projection (its checkpoint).
+    pub fn record_projection_lag(
+        &self,
+        projection: &str,
+        checkpoint: event::Sequence,
+        head: event::Sequence,
+    ) {
+        self.projection_lags
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(projection.to_owned(), head.saturating_sub(checkpoint));
+    }
+
+    fn record_appended(&self, names: Vec<&'static str>) {
+        for name in names {
+            self.events_appended
+                .add(1, &[KeyValue::new("event.name", name)]);
+        }
+    }
+
+    fn record_streamed(&self, name: &'static str) {
+        self.events_streamed
+            .add(1, &[KeyValue::new("event.name", name)]);
+    }
+
+    fn record_append_error(&self, err: &event::store::AppendError) {
+        if let event::store::AppendError::Conflict(_) = err {
+            self.conflicts.add(1, &[]);
+        }
+    }
+}
+
+/// [`event::Store`] type wrapper that emits metrics through the specified [Metrics].
+#[derive(Debug, Clone)]
+pub struct MeteredEventStore<T, StreamId, Event> {
+    store: T,
+    metrics: Metrics,
+    stream_id: PhantomData<StreamId>,
+    event: PhantomData<Event>,
+}
+
+impl<T, StreamId, Event> event::store::Streamer<StreamId, Event>
+    for MeteredEventStore<T, StreamId, Event>
+where
+    T: event::Store<StreamId, Event> + Send + Sync,
+    StreamId: Send + Sync,
+    Event: message::Message + Send + Sync,
+{
+    type Error = <T as event::store::Streamer<StreamId, Event>>::Error;
+
+    fn stream(
+        &self,
+        id: &StreamId,
+        select: event::VersionSelect,
+    ) -> event::Stream<'_, StreamId, Event, Self::Error> {
+        self.store
+            .stream(id, select)
+            .inspect_ok(|event| self.metrics.record_streamed(event.event.message.name()))
+            .boxed()
+    }
+
+    fn stream_filtered<'a>(
+        &'a self,
+        id: &StreamId,
+        select: event::VersionSelect,
+        names: event::NameSelect,
+    ) -> event::Stream<'a, StreamId, Event, Self::Error>
+    where
+        StreamId: 'a,
+        Event: 'a,
+        Self::Error: 'a,
+    {
+        self.store
+            .stream_filtered(id, select, names)
+            .inspect_ok(|event| self.metrics.record_streamed(event.event.message.name()))
+            .boxed()
+    }
+}
+
+impl<T, StreamId, Event> event::store::GlobalStreamer<StreamId, Event>
+    for MeteredEventStore<T, StreamId, Event>
+where
+    T: event::Store<StreamId, Event> + event::store::GlobalStreamer<StreamId, Event> + Send + Sync,
+    StreamId: Send + Sync,
+    Event: message::Message + Send + Sync,
+{
+    type Error = <T as event::store::GlobalStreamer<StreamId, Event>>::Error;
+
+    fn stream_all(
+        &self,
+        select: event::SequenceSelect,
+    ) -> event::SequencedStream<'_, StreamId, Event, Self::Error> {
+        self.store
+            .stream_all(select)
+            .inspect_ok(|event| {
+                self.metrics
+                    .record_streamed(event.event.event.message.name());
+            })
+            .boxed()
+    }
+
+    fn stream_all_filtered<'a>(
+        &'a self,
+        select: event::SequenceSelect,
+        names: event::NameSelect,
+    ) -> event::SequencedStream<'a, StreamId, Event, Self::Error>
+    where
+        StreamId: 'a,
+        Event: 'a,
+        Self::Error: 'a,
+    {
+        self.store
+            .stream_all_filtered(select, names)
+            .inspect_ok(|event| {
+                self.metrics
+                    .record_streamed(event.event.event.message.name());
+            })
+            .boxed()
+    }
+}
+
+#[async_trait]
+impl<T, StreamId, Event> event::store::Appender<StreamId, Event>
+    for MeteredEventStore<T, StreamId, Event>
+where
+    T: event::Store<StreamId, Event> + Send + Sync,
+    StreamId: Send + Sync,
+    Event: message::Message + Send + Sync,
+{
+    async fn append(
+        &self,
+        id: StreamId,
+        version_check: version::Check,
+        events: Vec<event::Envelope<Event>>,
+    ) -> Result<Version, event::store::AppendError> {
+        let names: Vec<_> = events.iter().map(|event| event.message.name()).collect();
+
+        let result = self.store.append(id, version_check, events).await;
+
+        match &result {
+            Ok(_) => self.metrics.record_appended(names),
+            Err(err) => self.metrics.record_append_error(err),
+        }
+
+        result
+    }
+
+    async fn append_multi(
+        &self,
+        appends: Vec<event::store::StreamAppend<StreamId, Event>>,
+    ) -> Result<Vec<Version>, event::store::AppendError> {
+        let names: Vec<_> = appends
+            .iter()
+            .flat_map(|append| append.events.iter().map(|event| event.message.name()))
+            .collect();
+
+        let result = self.store.append_multi(appends).await;
+
+        match &result {
+            Ok(_) => self.metrics.record_appended(names),
+            Err(err) => self.metrics.record_append_error(err),
+        }
+
+        result
+    }
+}
+
+/// Extension trait for any [`event::Store`] type to emit metrics
+/// through the specified [Metrics].
+pub trait EventStoreExt<StreamId, Event>: event::Store<StreamId, Event> + Sized
+where
+    StreamId: Send + Sync,
+    Event: message::Message + Send + Sync,
+{
+    /// Returns a metered version of the [`event::Store`] instance.
+    fn with_metrics(self, metrics: Metrics) -> MeteredEventStore<Self, StreamId, Event> {
+        MeteredEventStore {
+            store: self,
+            metrics,
+            stream_id: PhantomData,
+            event: PhantomData,
+        }
+    }
+}
+
+impl<T, StreamId, Event> EventStoreExt<StreamId, Event> for T
+where
+    T: event::Store<StreamId, Event> + Send + Sync,
+    StreamId: Send + Sync,
+    Event: message::Message + Send + Sync,
+{
+}
+
+/// [`command::Handler`] type wrapper that records the duration
+/// of the Command handling through the specified [Metrics].
+#[derive(Debug, Clone)]
+pub struct MeteredCommandHandler<H, T> {
+    handler: H,
+    metrics: Metrics,
+    t: PhantomData<T>,
+}
+
+#[async_trait]
+impl<H, T> command::Handler<T> for MeteredCommandHandler<H, T>
+where
+    H: command::Handler<T>,
+    T: message::Message + Send + Sync,
+{
+    type Error = H::Error;
+
+    async fn handle(&self, command: command::Envelope<T>) -> Result<(), Self::Error> {
+        let name = command.message.name();
+        let started = Instant::now();
+        let result = self.handler.handle(command).await;
+
+        self.metrics.command_duration.record(
+            started.elapsed().as_secs_f64(),
+            &[
+                KeyValue::new("command.name", name),
+                KeyValue::new("outcome", if result.is_ok() { "ok" } else { "error" }),
+            ],
+        );
+
+        result
+    }
+}
+
+/// Extension trait for any [`command::Handler`] type to record the duration
+/// of the Command handling through the specified [Metrics].
+pub trait CommandHandlerExt<T>: command::Handler<T> + Sized
+where
+    T: message::Message,
+{
+    /// Returns a metered version of the [`command::Handler`] instance.
+    fn with_metrics(self, metrics: Metrics) -> MeteredCommandHandler<Self, T> {
+        MeteredCommandHandler {
+            handler: self,
+            metrics,
+            t: PhantomData,
+        }
+    }
+}
+
+impl<H, T> CommandHandlerExt<T> for H
+where
+    H: command::Handler<T>,
+    T: message::Message,
+{
+}
+
+/// [Repository][aggregate::Repository] type wrapper that records the duration
+/// and size of the [Aggregate Root][aggregate::Root] rehydrations through the specified [Metrics].
+#[derive(Debug, Clone)]
+pub struct MeteredRepository<R, T> {
+    repository: R,
+    metrics: Metrics,
+    t: PhantomData<T>,
+}
+
+#[async_trait]
+impl<R, T> Getter<T> for MeteredRepository<R, T>
+where
+    R: Getter<T>,
+    T: Aggregate,
+{
+    async fn get(&self, id: &T::Id) -> Result<aggregate::Root<T>, GetError> {
+        let started = Instant::now();
+        let result = self.repository.get(id).await;
+        let attributes = [KeyValue::new("aggregate.type", T::type_name())];
+
+        self.metrics
+            .rehydration_duration
+            .record(started.elapsed().as_secs_f64(), &attributes);
+
+        if let Ok(root) = &result {
+            self.metrics
+                .rehydration_events
+                .record(root.version(), &attributes);
+        }
+
+        result
+    }
+}
+
+#[async_trait]
+impl<R, T> Saver<T> for MeteredRepository<R, T>
+where
+    R: Saver<T>,
+    T: Aggregate,
+{
+    async fn save(&self, root: &mut aggregate::Root<T>) -> Result<(), SaveError> {
+        self.repository.save(root).await
+    }
+}
+
+/// Extension trait for any [Repository][aggregate::Repository] type to record
+/// the [Aggregate Root][aggregate::Root] rehydrations through the specified [Metrics].
+pub trait RepositoryExt<T>: aggregate::Repository<T> + Sized
+where
+    T: Aggregate,
+{
+    /// Returns a metered version of the [Repository][aggregate::Repository] instance.
+    fn with_metrics(self, metrics: Metrics) -> MeteredRepository<Self, T> {
+        MeteredRepository {
+            repository: self,
+            metrics,
+            t: PhantomData,
+        }
+    }
+}
+
+impl<R, T> RepositoryExt<T> for R
+where
+    R: aggregate::Repository<T>,
+    T: Aggregate,
+{
+}
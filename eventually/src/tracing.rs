@@ -1,15 +1,20 @@
 //! Module containing some extension traits to support code instrumentation
 //! using the `tracing` crate.
 
-use std::fmt::Debug;
+use std::fmt::{Debug, Display};
 use std::marker::PhantomData;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
 
 use async_trait::async_trait;
-use tracing::instrument;
+use futures::stream::{self, BoxStream, StreamExt};
+use tracing::field::Empty;
+use tracing::{instrument, Instrument, Span};
 
 use crate::aggregate::Aggregate;
 use crate::version::{self, Version};
-use crate::{aggregate, event, message};
+use crate::{aggregate, command, event, message, query};
 
 /// [`aggregate::Repository`] type wrapper that provides instrumentation
 /// features through the `tracing` crate.
@@ -84,6 +89,20 @@ where
 {
 }
 
+/// Controls which persisted Domain Events are emitted as `tracing` events
+/// by the [`InstrumentedEventStore`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EventSampling {
+    /// No `tracing` event is emitted.
+    #[default]
+    Disabled,
+    /// One `tracing` event is emitted for every persisted Domain Event.
+    All,
+    /// One `tracing` event is emitted every the specified number
+    /// of persisted Domain Events. `OneEvery(0)` is the same as `Disabled`.
+    OneEvery(u64),
+}
+
 /// [`event::Store`] type wrapper that provides instrumentation
 /// features through the `tracing` crate.
 #[derive(Debug, Clone)]
@@ -94,10 +113,136 @@ where
     Event: message::Message + Debug + Send + Sync,
 {
     store: T,
+    sampling: EventSampling,
+    persisted_events: Arc<AtomicU64>,
     stream_id: PhantomData<StreamId>,
     event: PhantomData<Event>,
 }
 
+impl<T, StreamId, Event> InstrumentedEventStore<T, StreamId, Event>
+where
+    T: event::Store<StreamId, Event> + Send + Sync,
+    StreamId: Debug + Send + Sync,
+    Event: message::Message + Debug + Send + Sync,
+{
+    /// Emits a structured `tracing` event, with the name, Event Stream id and version,
+    /// for the persisted Domain Events selected by the specified [`EventSampling`].
+    ///
+    /// Useful to capture the flow of Domain Events through log-based analytics,
+    /// without the need of a message broker.
+    #[must_use]
+    pub fn with_event_sampling(mut self, sampling: EventSampling) -> Self {
+        self.sampling = sampling;
+        self
+    }
+
+    /// Returns the name of the Domain Events, and whether they have been sampled,
+    /// or nothing if the emission of `tracing` events is disabled.
+    fn sample(&self, events: &[event::Envelope<Event>]) -> Option<Vec<(&'static str, bool)>> {
+        let one_every = match self.sampling {
+            EventSampling::Disabled | EventSampling::OneEvery(0) => return None,
+            EventSampling::All => 1,
+            EventSampling::OneEvery(n) => n,
+        };
+
+        let count = events.len() as u64;
+        let first = self.persisted_events.fetch_add(count, Ordering::Relaxed);
+
+        Some(
+            events
+                .iter()
+                .zip(first..)
+                .map(|(event, i)| (event.message.name(), i % one_every == 0))
+                .collect(),
+        )
+    }
+}
+
+fn emit_persisted_events(stream_id: &str, new_version: Version, events: &[(&'static str, bool)]) {
+    let first_version = new_version - events.len() as Version + 1;
+
+    for ((name, sampled), version) in events.iter().zip(first_version..) {
+        if *sampled {
+            tracing::info!(
+                event.name = name,
+                event.stream_id = stream_id,
+                event.version = version,
+                "domain event persisted"
+            );
+        }
+    }
+}
+
+/// Records the latency of an operation on the specified [Span], in milliseconds,
+/// and emits it as a `histogram.` prefixed field, so that it can be
+/// exported as a latency histogram (e.g. by `tracing-opentelemetry`).
+fn record_latency(span: &Span, operation: &'static str, started: Instant) {
+    let latency_ms = started.elapsed().as_secs_f64() * 1000.0;
+
+    span.record("latency_ms", latency_ms);
+    span.in_scope(|| {
+        tracing::debug!(
+            histogram.eventually.latency_ms = latency_ms,
+            operation,
+            "operation completed"
+        );
+    });
+}
+
+/// Records the outcome of an append operation on the specified [Span].
+///
+/// Version conflicts are emitted as `tracing` events, since they are expected
+/// to happen under concurrent writes and are not necessarily an error.
+fn record_append_outcome(span: &Span, error: Option<&event::store::AppendError>) {
+    match error {
+        None => {
+            span.record("version.check.outcome", "passed");
+        },
+        Some(event::store::AppendError::Conflict(err)) => {
+            span.record("version.check.outcome", "conflict");
+            span.in_scope(|| {
+                tracing::warn!(
+                    version.expected = err.expected,
+                    version.actual = err.actual,
+                    "version conflict detected"
+                );
+            });
+        },
+        Some(_) => {},
+    }
+}
+
+/// Instruments a stream of Domain Events: the specified [Span] is entered
+/// while polling the stream, and records the number of Domain Events streamed
+/// and the latency of the whole stream once it has been consumed.
+fn instrument_stream<'a, T, E>(
+    span: Span,
+    operation: &'static str,
+    stream: BoxStream<'a, Result<T, E>>,
+) -> BoxStream<'a, Result<T, E>>
+where
+    T: Send + 'a,
+    E: Send + 'a,
+{
+    let started = Instant::now();
+
+    stream::unfold((stream, 0_u64), move |(mut stream, count)| {
+        let span = span.clone();
+
+        async move {
+            let Some(item) = stream.next().instrument(span.clone()).await else {
+                span.record("events.count", count);
+                record_latency(&span, operation, started);
+                return None;
+            };
+
+            let count = count + u64::from(item.is_ok());
+            Some((item, (stream, count)))
+        }
+    })
+    .boxed()
+}
+
 impl<T, StreamId, Event> event::store::Streamer<StreamId, Event>
     for InstrumentedEventStore<T, StreamId, Event>
 where
@@ -107,13 +252,100 @@ where
 {
     type Error = <T as event::store::Streamer<StreamId, Event>>::Error;
 
-    #[instrument(name = "event::Store.stream", skip(self))]
     fn stream(
         &self,
         id: &StreamId,
         select: event::VersionSelect,
-    ) -> event::Stream<StreamId, Event, Self::Error> {
-        self.store.stream(id, select)
+    ) -> event::Stream<'_, StreamId, Event, Self::Error> {
+        let span = tracing::info_span!(
+            "event::Store.stream",
+            stream.id = ?id,
+            version.select = ?select,
+            events.count = Empty,
+            latency_ms = Empty,
+        );
+
+        instrument_stream(span, "event::Store.stream", self.store.stream(id, select))
+    }
+
+    fn stream_filtered<'a>(
+        &'a self,
+        id: &StreamId,
+        select: event::VersionSelect,
+        names: event::NameSelect,
+    ) -> event::Stream<'a, StreamId, Event, Self::Error>
+    where
+        StreamId: 'a,
+        Event: 'a,
+        Self::Error: 'a,
+    {
+        let span = tracing::info_span!(
+            "event::Store.stream_filtered",
+            stream.id = ?id,
+            version.select = ?select,
+            event.names = ?names,
+            events.count = Empty,
+            latency_ms = Empty,
+        );
+
+        instrument_stream(
+            span,
+            "event::Store.stream_filtered",
+            self.store.stream_filtered(id, select, names),
+        )
+    }
+}
+
+impl<T, StreamId, Event> event::store::GlobalStreamer<StreamId, Event>
+    for InstrumentedEventStore<T, StreamId, Event>
+where
+    T: event::Store<StreamId, Event> + event::store::GlobalStreamer<StreamId, Event> + Send + Sync,
+    StreamId: Debug + Send + Sync,
+    Event: message::Message + Debug + Send + Sync,
+{
+    type Error = <T as event::store::GlobalStreamer<StreamId, Event>>::Error;
+
+    fn stream_all(
+        &self,
+        select: event::SequenceSelect,
+    ) -> event::SequencedStream<'_, StreamId, Event, Self::Error> {
+        let span = tracing::info_span!(
+            "event::Store.stream_all",
+            sequence.select = ?select,
+            events.count = Empty,
+            latency_ms = Empty,
+        );
+
+        instrument_stream(
+            span,
+            "event::Store.stream_all",
+            self.store.stream_all(select),
+        )
+    }
+
+    fn stream_all_filtered<'a>(
+        &'a self,
+        select: event::SequenceSelect,
+        names: event::NameSelect,
+    ) -> event::SequencedStream<'a, StreamId, Event, Self::Error>
+    where
+        StreamId: 'a,
+        Event: 'a,
+        Self::Error: 'a,
+    {
+        let span = tracing::info_span!(
+            "event::Store.stream_all_filtered",
+            sequence.select = ?select,
+            event.names = ?names,
+            events.count = Empty,
+            latency_ms = Empty,
+        );
+
+        instrument_stream(
+            span,
+            "event::Store.stream_all_filtered",
+            self.store.stream_all_filtered(select, names),
+        )
     }
 }
 
@@ -126,14 +358,131 @@ where
     Event: message::Message + Debug + Send + Sync,
 {
     #[allow(clippy::blocks_in_conditions)] // NOTE(ar3s3ru): seems to be a false positive.
-    #[instrument(name = "event::Store.append", ret, err, skip(self))]
+    #[instrument(
+        name = "event::Store.append",
+        err,
+        skip_all,
+        fields(
+            stream.id = ?id,
+            events.count = events.len(),
+            version.check = ?version_check,
+            version.check.outcome = Empty,
+            version.new = Empty,
+            latency_ms = Empty,
+        )
+    )]
     async fn append(
         &self,
         id: StreamId,
         version_check: version::Check,
         events: Vec<event::Envelope<Event>>,
     ) -> Result<Version, event::store::AppendError> {
-        self.store.append(id, version_check, events).await
+        let started = Instant::now();
+        let sampled = self
+            .sample(&events)
+            .map(|sampled| (format!("{id:?}"), sampled));
+
+        let result = self.store.append(id, version_check, events).await;
+
+        let span = Span::current();
+        record_append_outcome(&span, result.as_ref().err());
+        record_latency(&span, "event::Store.append", started);
+
+        if let Ok(new_version) = &result {
+            span.record("version.new", new_version);
+        }
+
+        if let (Ok(new_version), Some((stream_id, sampled))) = (&result, sampled) {
+            emit_persisted_events(&stream_id, *new_version, &sampled);
+        }
+
+        result
+    }
+
+    #[allow(clippy::blocks_in_conditions)] // NOTE(ar3s3ru): seems to be a false positive.
+    #[instrument(
+        name = "event::Store.append_multi",
+        err,
+        skip_all,
+        fields(
+            streams.count = appends.len(),
+            events.count = appends.iter().map(|append| append.events.len()).sum::<usize>(),
+            version.check.outcome = Empty,
+            latency_ms = Empty,
+        )
+    )]
+    async fn append_multi(
+        &self,
+        appends: Vec<event::store::StreamAppend<StreamId, Event>>,
+    ) -> Result<Vec<Version>, event::store::AppendError> {
+        let started = Instant::now();
+        let sampled: Vec<_> = appends
+            .iter()
+            .map(|append| {
+                self.sample(&append.events)
+                    .map(|sampled| (format!("{:?}", append.id), sampled))
+            })
+            .collect();
+
+        let result = self.store.append_multi(appends).await;
+
+        let span = Span::current();
+        record_append_outcome(&span, result.as_ref().err());
+        record_latency(&span, "event::Store.append_multi", started);
+
+        let new_versions = result?;
+
+        for (sampled, new_version) in sampled.iter().zip(&new_versions) {
+            if let Some((stream_id, sampled)) = sampled {
+                emit_persisted_events(stream_id, *new_version, sampled);
+            }
+        }
+
+        Ok(new_versions)
+    }
+}
+
+#[async_trait]
+impl<T, StreamId, Event> event::store::StreamDeleter<StreamId, Event>
+    for InstrumentedEventStore<T, StreamId, Event>
+where
+    T: event::Store<StreamId, Event> + event::store::StreamDeleter<StreamId, Event> + Send + Sync,
+    StreamId: Debug + Send + Sync,
+    Event: message::Message + Debug + Send + Sync,
+{
+    #[allow(clippy::blocks_in_conditions)] // NOTE(ar3s3ru): seems to be a false positive.
+    #[instrument(name = "event::Store.delete", ret, err, skip(self))]
+    async fn delete(&self, id: &StreamId) -> Result<(), event::store::DeleteError> {
+        self.store.delete(id).await
+    }
+
+    #[allow(clippy::blocks_in_conditions)] // NOTE(ar3s3ru): seems to be a false positive.
+    #[instrument(name = "event::Store.truncate", ret, err, skip(self))]
+    async fn truncate(
+        &self,
+        id: &StreamId,
+        before_version: Version,
+    ) -> Result<(), event::store::DeleteError> {
+        self.store.truncate(id, before_version).await
+    }
+}
+
+#[async_trait]
+impl<T, StreamId, Event> event::store::Tombstoner<StreamId>
+    for InstrumentedEventStore<T, StreamId, Event>
+where
+    T: event::Store<StreamId, Event> + event::store::Tombstoner<StreamId> + Send + Sync,
+    StreamId: Debug + Send + Sync,
+    Event: message::Message + Debug + Send + Sync,
+{
+    #[allow(clippy::blocks_in_conditions)] // NOTE(ar3s3ru): seems to be a false positive.
+    #[instrument(name = "event::Store.tombstone", ret, err, skip(self))]
+    async fn tombstone(
+        &self,
+        id: &StreamId,
+        version_check: version::Check,
+    ) -> Result<(), event::store::TombstoneError> {
+        self.store.tombstone(id, version_check).await
     }
 }
 
@@ -148,6 +497,8 @@ where
     fn with_tracing(self) -> InstrumentedEventStore<Self, StreamId, Event> {
         InstrumentedEventStore {
             store: self,
+            sampling: EventSampling::default(),
+            persisted_events: Arc::default(),
             stream_id: PhantomData,
             event: PhantomData,
         }
@@ -161,3 +512,96 @@ where
     Event: message::Message + Debug + Send + Sync,
 {
 }
+
+/// [`command::Handler`] type wrapper that provides instrumentation
+/// features through the `tracing` crate.
+#[derive(Debug, Clone)]
+pub struct InstrumentedCommandHandler<H, T> {
+    handler: H,
+    t: PhantomData<T>,
+}
+
+#[async_trait]
+impl<H, T> command::Handler<T> for InstrumentedCommandHandler<H, T>
+where
+    H: command::Handler<T>,
+    H::Error: Display,
+    T: message::Message + Send + Sync,
+{
+    type Error = H::Error;
+
+    #[allow(clippy::blocks_in_conditions)] // NOTE(ar3s3ru): seems to be a false positive.
+    #[instrument(
+        name = "command::Handler.handle",
+        err,
+        skip_all,
+        fields(command.name = command.message.name(), latency_ms = Empty)
+    )]
+    async fn handle(&self, command: command::Envelope<T>) -> Result<(), Self::Error> {
+        let started = Instant::now();
+        let result = self.handler.handle(command).await;
+
+        record_latency(&Span::current(), "command::Handler.handle", started);
+
+        result
+    }
+}
+
+/// Extension trait for any [`command::Handler`] type to provide
+/// instrumentation features through the `tracing` crate.
+pub trait CommandHandlerExt<T>: command::Handler<T> + Sized
+where
+    T: message::Message,
+{
+    /// Returns an instrumented version of the [`command::Handler`] instance.
+    fn with_tracing(self) -> InstrumentedCommandHandler<Self, T> {
+        InstrumentedCommandHandler {
+            handler: self,
+            t: PhantomData,
+        }
+    }
+}
+
+impl<H, T> CommandHandlerExt<T> for H
+where
+    H: command::Handler<T>,
+    H::Error: Display,
+    T: message::Message,
+{
+}
+
+/// [`query::bus::Middleware`] that instruments the evaluation of all the Queries
+/// dispatched through a [`query::Bus`] using the `tracing` crate.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct QueryBusMiddleware;
+
+#[async_trait]
+impl query::bus::Middleware for QueryBusMiddleware {
+    #[allow(clippy::blocks_in_conditions)] // NOTE(ar3s3ru): seems to be a false positive.
+    #[instrument(name = "query::Bus.dispatch", err, skip_all, fields(query.name = query.name))]
+    async fn handle(
+        &self,
+        query: query::bus::Query,
+        next: query::bus::Next<'_>,
+    ) -> Result<query::bus::Output, query::bus::Error> {
+        next.run(query).await
+    }
+}
+
+/// [`command::bus::Middleware`] that instruments the handling of all the Commands
+/// dispatched through a [`command::Bus`] using the `tracing` crate.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CommandBusMiddleware;
+
+#[async_trait]
+impl command::bus::Middleware for CommandBusMiddleware {
+    #[allow(clippy::blocks_in_conditions)] // NOTE(ar3s3ru): seems to be a false positive.
+    #[instrument(name = "command::Bus.dispatch", err, skip_all, fields(command.name = command.name))]
+    async fn handle(
+        &self,
+        command: command::bus::Command,
+        next: command::bus::Next<'_>,
+    ) -> Result<(), command::bus::Error> {
+        next.run(command).await
+    }
+}
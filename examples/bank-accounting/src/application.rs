@@ -1,7 +1,7 @@
 use std::sync::Arc;
 
-use async_trait::async_trait;
-use eventually::{aggregate, command, message};
+use eventually::aggregate;
+use eventually_macros::command_handler;
 use rust_decimal::Decimal;
 
 use crate::domain::{
@@ -24,6 +24,7 @@ where
     }
 }
 
+#[command_handler(service = "Service", root = "BankAccountRoot", create = "open")]
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct OpenBankAccount {
     pub bank_account_id: BankAccountId,
@@ -31,64 +32,24 @@ pub struct OpenBankAccount {
     pub opening_balance: Option<Decimal>,
 }
 
-impl message::Message for OpenBankAccount {
-    fn name(&self) -> &'static str {
-        "OpenBankAccount"
-    }
-}
-
-#[async_trait]
-impl command::Handler<OpenBankAccount> for Service {
-    type Error = anyhow::Error;
-
-    async fn handle(&self, command: command::Envelope<OpenBankAccount>) -> Result<(), Self::Error> {
-        let command = command.message;
-
-        let mut bank_account = BankAccountRoot::open(
-            command.bank_account_id,
-            command.bank_account_holder_id,
-            command.opening_balance,
-        )?;
-
-        self.repository.save(&mut bank_account).await?;
-
-        Ok(())
-    }
-}
-
+#[command_handler(
+    service = "Service",
+    root = "BankAccountRoot",
+    method = "deposit",
+    id = "bank_account_id"
+)]
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct DepositInBankAccount {
     pub bank_account_id: BankAccountId,
     pub amount: Decimal,
 }
 
-impl message::Message for DepositInBankAccount {
-    fn name(&self) -> &'static str {
-        "DepositInBankAccount"
-    }
-}
-
-#[async_trait]
-impl command::Handler<DepositInBankAccount> for Service {
-    type Error = anyhow::Error;
-
-    async fn handle(
-        &self,
-        command: command::Envelope<DepositInBankAccount>,
-    ) -> Result<(), Self::Error> {
-        let command = command.message;
-
-        let mut bank_account: BankAccountRoot =
-            self.repository.get(&command.bank_account_id).await?.into();
-
-        bank_account.deposit(command.amount)?;
-
-        self.repository.save(&mut bank_account).await?;
-
-        Ok(())
-    }
-}
-
+#[command_handler(
+    service = "Service",
+    root = "BankAccountRoot",
+    method = "send_transfer",
+    id = "bank_account_id"
+)]
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct SendTransferToBankAccount {
     pub bank_account_id: BankAccountId,
@@ -96,33 +57,6 @@ pub struct SendTransferToBankAccount {
     pub message: Option<String>,
 }
 
-impl message::Message for SendTransferToBankAccount {
-    fn name(&self) -> &'static str {
-        "SendTransferToBankAccount"
-    }
-}
-
-#[async_trait]
-impl command::Handler<SendTransferToBankAccount> for Service {
-    type Error = anyhow::Error;
-
-    async fn handle(
-        &self,
-        command: command::Envelope<SendTransferToBankAccount>,
-    ) -> Result<(), Self::Error> {
-        let command = command.message;
-
-        let mut bank_account: BankAccountRoot =
-            self.repository.get(&command.bank_account_id).await?.into();
-
-        bank_account.send_transfer(command.transaction, command.message)?;
-
-        self.repository.save(&mut bank_account).await?;
-
-        Ok(())
-    }
-}
-
 #[cfg(test)]
 mod test {
     use eventually::{command, event};
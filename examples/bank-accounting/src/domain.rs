@@ -1,8 +1,7 @@
 use std::collections::HashMap;
 
 use eventually::aggregate;
-use eventually::message::Message;
-use eventually_macros::aggregate_root;
+use eventually_macros::{aggregate_root, IntoStatus, Message};
 use rust_decimal::Decimal;
 
 pub type BankAccountRepository<S> = aggregate::EventSourcedRepository<BankAccount, S>;
@@ -19,69 +18,63 @@ pub struct Transaction {
 pub type BankAccountHolderId = String;
 pub type BankAccountId = String;
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Message)]
 pub enum BankAccountEvent {
+    #[message(name = "BankAccountWasOpened")]
     WasOpened {
         id: BankAccountId,
         account_holder_id: BankAccountHolderId,
         initial_balance: Option<Decimal>,
     },
-    DepositWasRecorded {
-        amount: Decimal,
-    },
+    #[message(name = "BankAccountDepositWasRecorded")]
+    DepositWasRecorded { amount: Decimal },
+    #[message(name = "BankAccountTransferWasSent")]
     TransferWasSent {
         transaction: Transaction,
         message: Option<String>,
     },
+    #[message(name = "BankAccountTransferWasReceived")]
     TransferWasReceived {
         transaction: Transaction,
         message: Option<String>,
     },
+    #[message(name = "BankAccountTransferWasDeclined")]
     TransferWasDeclined {
         transaction_id: TransactionId,
         reason: Option<String>,
     },
-    TransferWasConfirmed {
-        transaction_id: TransactionId,
-    },
+    #[message(name = "BankAccountTransferWasConfirmed")]
+    TransferWasConfirmed { transaction_id: TransactionId },
+    #[message(name = "BankAccountWasClosed")]
     WasClosed,
-    WasReopened {
-        reopening_balance: Option<Decimal>,
-    },
+    #[message(name = "BankAccountWasReopened")]
+    WasReopened { reopening_balance: Option<Decimal> },
 }
 
-impl Message for BankAccountEvent {
-    fn name(&self) -> &'static str {
-        match self {
-            BankAccountEvent::WasOpened { .. } => "BankAccountWasOpened",
-            BankAccountEvent::DepositWasRecorded { .. } => "BankAccountDepositWasRecorded",
-            BankAccountEvent::TransferWasSent { .. } => "BankAccountTransferWasSent",
-            BankAccountEvent::TransferWasReceived { .. } => "BankAccountTransferWasReceived",
-            BankAccountEvent::TransferWasDeclined { .. } => "BankAccountTransferWasDeclined",
-            BankAccountEvent::TransferWasConfirmed { .. } => "BankAccountTransferWasConfirmed",
-            BankAccountEvent::WasClosed { .. } => "BankAccountWasClosed",
-            BankAccountEvent::WasReopened { .. } => "BankAccountWasReopened",
-        }
-    }
-}
-
-#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error, IntoStatus)]
+#[status(code = "failed_precondition")]
 pub enum BankAccountError {
     #[error("bank account has not been opened yet")]
+    #[status(code = "not_found")]
     NotOpenedYet,
     #[error("bank account has already been opened")]
+    #[status(code = "already_exists")]
     AlreadyOpened,
     #[error("empty id provided for the new bank account")]
+    #[status(code = "invalid_argument")]
     EmptyAccountId,
     #[error("empty account holder id provided for the new bank account")]
+    #[status(code = "invalid_argument")]
     EmptyAccountHolderId,
     #[error("a deposit was attempted with negative import")]
     NegativeDepositAttempted,
     #[error("no money to deposit has been specified")]
+    #[status(code = "invalid_argument")]
     NoMoneyDeposited,
     #[error("transfer could not be sent due to insufficient funds")]
     InsufficientFunds,
     #[error("transfer transaction was destined to a different recipient: {0}")]
+    #[status(code = "invalid_argument")]
     WrongTransactionRecipient(BankAccountId),
     #[error("the account is closed")]
     Closed,
@@ -125,46 +118,53 @@ impl aggregate::Aggregate for BankAccount {
                 }),
                 _ => Err(BankAccountError::NotOpenedYet),
             },
-            Some(mut account) => match event {
-                BankAccountEvent::DepositWasRecorded { amount } => {
-                    account.current_balance += amount;
-                    Ok(account)
-                },
-                BankAccountEvent::TransferWasReceived { transaction, .. } => {
-                    account.current_balance += transaction.amount;
-                    Ok(account)
-                },
-                BankAccountEvent::TransferWasSent { transaction, .. } => {
-                    account.current_balance -= transaction.amount;
-                    account
-                        .pending_transactions
-                        .insert(transaction.id.clone(), transaction);
-                    Ok(account)
-                },
-                BankAccountEvent::TransferWasConfirmed { transaction_id } => {
-                    account.pending_transactions.remove(&transaction_id);
-                    Ok(account)
-                },
-                BankAccountEvent::TransferWasDeclined { transaction_id, .. } => {
-                    if let Some(transaction) = account.pending_transactions.remove(&transaction_id)
-                    {
-                        account.current_balance += transaction.amount;
-                    }
-
-                    Ok(account)
-                },
-                BankAccountEvent::WasClosed => {
-                    account.is_closed = true;
-                    account.current_balance = Decimal::default();
-                    Ok(account)
-                },
-                BankAccountEvent::WasReopened { reopening_balance } => {
-                    account.is_closed = false;
-                    account.current_balance = reopening_balance.unwrap_or_default();
-                    Ok(account)
-                },
-                BankAccountEvent::WasOpened { .. } => Err(BankAccountError::AlreadyOpened),
+            Some(mut account) => {
+                account.apply_in_place(event)?;
+                Ok(account)
+            },
+        }
+    }
+
+    // NOTE: Root::record_that calls this method when recording new Domain Events,
+    // which avoids cloning the whole account, including its pending transactions.
+    fn apply_in_place(&mut self, event: Self::Event) -> Result<(), Self::Error> {
+        match event {
+            BankAccountEvent::DepositWasRecorded { amount } => {
+                self.current_balance += amount;
+                Ok(())
+            },
+            BankAccountEvent::TransferWasReceived { transaction, .. } => {
+                self.current_balance += transaction.amount;
+                Ok(())
+            },
+            BankAccountEvent::TransferWasSent { transaction, .. } => {
+                self.current_balance -= transaction.amount;
+                self.pending_transactions
+                    .insert(transaction.id.clone(), transaction);
+                Ok(())
+            },
+            BankAccountEvent::TransferWasConfirmed { transaction_id } => {
+                self.pending_transactions.remove(&transaction_id);
+                Ok(())
+            },
+            BankAccountEvent::TransferWasDeclined { transaction_id, .. } => {
+                if let Some(transaction) = self.pending_transactions.remove(&transaction_id) {
+                    self.current_balance += transaction.amount;
+                }
+
+                Ok(())
+            },
+            BankAccountEvent::WasClosed => {
+                self.is_closed = true;
+                self.current_balance = Decimal::default();
+                Ok(())
+            },
+            BankAccountEvent::WasReopened { reopening_balance } => {
+                self.is_closed = false;
+                self.current_balance = reopening_balance.unwrap_or_default();
+                Ok(())
             },
+            BankAccountEvent::WasOpened { .. } => Err(BankAccountError::AlreadyOpened),
         }
     }
 }
@@ -233,7 +233,7 @@ impl BankAccountRoot {
             return Err(BankAccountError::InsufficientFunds);
         }
 
-        let transaction_already_pending = self.pending_transactions.get(&transaction.id).is_some();
+        let transaction_already_pending = self.pending_transactions.contains_key(&transaction.id);
         if transaction_already_pending {
             return Ok(());
         }
@@ -275,7 +275,7 @@ impl BankAccountRoot {
         &mut self,
         transaction_id: TransactionId,
     ) -> Result<(), BankAccountError> {
-        let is_transaction_recorded = self.pending_transactions.get(&transaction_id).is_some();
+        let is_transaction_recorded = self.pending_transactions.contains_key(&transaction_id);
         if !is_transaction_recorded {
             // TODO: return error
         }
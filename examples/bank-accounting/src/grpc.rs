@@ -1,5 +1,6 @@
 use async_trait::async_trait;
 use eventually::command::Handler;
+use eventually::interop::grpc::IntoStatus;
 use eventually::version;
 use rust_decimal::prelude::FromPrimitive;
 use rust_decimal::Decimal;
@@ -42,17 +43,11 @@ impl proto::bank_accounting_server::BankAccounting for BankAccountingApi {
             .await
             .map(|_| tonic::Response::new(proto::OpenBankAccountResponse {}))
             .map_err(|e| {
-                use BankAccountError::*;
-
-                let bank_error = as_error::<BankAccountError>(&e);
-                let conflict_error = as_error::<version::ConflictError>(&e);
-
-                if let Some(EmptyAccountId | EmptyAccountHolderId) = bank_error {
-                    tonic::Status::invalid_argument(e.to_string())
-                } else if conflict_error.is_some() {
-                    tonic::Status::already_exists(AlreadyOpened.to_string())
+                // Opening a bank account that already exists is a version conflict.
+                if as_error::<version::ConflictError>(&e).is_some() {
+                    BankAccountError::AlreadyOpened.into_status()
                 } else {
-                    tonic::Status::internal(e.to_string())
+                    into_status(e)
                 }
             })
     }
@@ -76,22 +71,14 @@ impl proto::bank_accounting_server::BankAccounting for BankAccountingApi {
             )
             .await
             .map(|_| tonic::Response::new(proto::DepositInBankAccountResponse {}))
-            .map_err(|e| {
-                use BankAccountError::*;
-
-                let bank_error = as_error::<BankAccountError>(&e);
-                let conflict_error = as_error::<version::ConflictError>(&e);
+            .map_err(into_status)
+    }
+}
 
-                if let Some(Closed | NegativeDepositAttempted) = bank_error {
-                    tonic::Status::failed_precondition(e.to_string())
-                } else if let Some(NoMoneyDeposited) = bank_error {
-                    tonic::Status::invalid_argument(e.to_string())
-                } else if let Some(e) = conflict_error {
-                    tonic::Status::failed_precondition(e.to_string())
-                } else {
-                    tonic::Status::internal(e.to_string())
-                }
-            })
+fn into_status(e: anyhow::Error) -> tonic::Status {
+    match as_error::<BankAccountError>(&e) {
+        Some(err) => err.clone().into_status(),
+        None => e.into_status(),
     }
 }
 
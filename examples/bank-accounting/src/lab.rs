@@ -0,0 +1,44 @@
+use std::net::SocketAddr;
+
+use anyhow::anyhow;
+use eventually::lab::Lab;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+/// Serves the current [`eventually::lab::Snapshot`] as JSON on every HTTP request
+/// received on the specified address, regardless of the path.
+///
+/// This is a development-only endpoint: no HTTP framework is used,
+/// and the request is only read to be discarded.
+pub async fn serve(lab: Lab, addr: SocketAddr) -> anyhow::Result<()> {
+    let listener = TcpListener::bind(addr)
+        .await
+        .map_err(|e| anyhow!("failed to bind lab address: {}", e))?;
+
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let lab = lab.clone();
+
+        tokio::spawn(async move {
+            if let Err(err) = respond(stream, &lab).await {
+                tracing::warn!(error = %err, "failed to serve lab snapshot");
+            }
+        });
+    }
+}
+
+async fn respond(mut stream: TcpStream, lab: &Lab) -> anyhow::Result<()> {
+    let mut request = [0; 1024];
+    let _ = stream.read(&mut request).await?;
+
+    let body = lab.snapshot().to_json()?;
+    let response = format!(
+        "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+        body.len()
+    );
+
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await?;
+
+    Ok(())
+}
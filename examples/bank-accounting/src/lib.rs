@@ -1,6 +1,7 @@
 pub mod application;
 pub mod domain;
 pub mod grpc;
+pub mod lab;
 pub mod postgres;
 pub mod serde;
 pub mod tracing;
@@ -3,6 +3,7 @@ use std::time::Duration;
 use anyhow::anyhow;
 use bank_accounting::domain::{BankAccountEvent, BankAccountRepository};
 use bank_accounting::{application, grpc, proto};
+use eventually::lab::Lab;
 use eventually::serde;
 use eventually::tracing::{AggregateRepositoryExt, EventStoreExt};
 use eventually_postgres::event;
@@ -17,9 +18,23 @@ async fn main() -> anyhow::Result<()> {
         serde::Protobuf::<proto::Event>::default(),
     );
 
-    let bank_account_event_store = event::Store::new(pool, bank_account_event_serde)
-        .await?
-        .with_tracing();
+    // NOTE: the lab is only served when LAB_ADDRESS is set, e.g. "0.0.0.0:10438".
+    let lab = Lab::default();
+
+    let bank_account_event_store = lab.observe(
+        event::Store::new(pool, bank_account_event_serde)
+            .await?
+            .with_tracing(),
+    );
+
+    if let Ok(lab_addr) = std::env::var("LAB_ADDRESS") {
+        let lab_addr = lab_addr
+            .parse()
+            .map_err(|e| anyhow!("failed to parse lab address: {}", e))?;
+
+        tracing::info!("Serving lab snapshots on {lab_addr}");
+        tokio::spawn(bank_accounting::lab::serve(lab, lab_addr));
+    }
 
     let bank_account_repository =
         BankAccountRepository::from(bank_account_event_store.clone()).with_tracing();